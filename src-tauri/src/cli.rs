@@ -0,0 +1,1367 @@
+//! 命令行子命令 (Headless 管理)
+//!
+//! 让无 GUI 的部署 (Docker / 服务器) 也能完成日常管理，而不必依赖 Web UI 或 Admin API：
+//! `antigravity-tools accounts list`、`config set proxy.port 8046` 等。
+//! 全部复用 `AccountService` 与 `modules::*` 现有逻辑，输出表格或 `--json`。
+
+use serde::Serialize;
+use serde_json::Value;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::models::{Account, AppConfig};
+use crate::modules;
+use crate::modules::account_service::AccountService;
+use crate::modules::backup::BackupSection;
+use crate::modules::integration::SystemManager;
+
+pub const USAGE: &str = "\
+Usage: antigravity-tools <command> [options] [--json]
+
+Commands:
+  accounts list
+  accounts add [--refresh-token-file <path> | --refresh-token-env <VAR>]
+                                         Reads the refresh token from stdin unless a file or
+                                         env var is given (`--insecure-arg <token>` allows the
+                                         token on the command line)
+  accounts import [--db <path>]          Import from the Antigravity IDE database
+  accounts delete <id|email>
+  accounts switch <id|email>
+  accounts set-groups <id|email> [<a,b,...>]   Omit the list to remove the account from all groups
+  accounts export [<id|email>...] [--out <file>] [--passphrase-env <VAR>]
+                                         Export refresh tokens (all accounts by default),
+                                         encrypted with the passphrase in $VAR if given
+  accounts import-json <file> [--passphrase-env <VAR>]
+                                         Import a file produced by `accounts export` / the UI
+  accounts encryption                    Show at-rest encryption status of account files
+  accounts rotate-key (--new-passphrase-env <VAR> | --new-key-file <path> | --disable)
+                                         Enable, re-key or disable account file encryption
+                                         (the current key comes from ABV_ACCOUNT_PASSPHRASE /
+                                         ABV_ACCOUNT_KEY_FILE)
+  quota refresh [<id|email>]             Refresh all accounts, or one
+  backup create --out <file> [--sections <a,b,...>] [--passphrase-env <VAR>]
+                                         Sections: accounts,config,user_tokens,security,
+                                         token_stats,cli_backups (default: all)
+  backup restore <file> [--sections <a,b,...>] [--accounts <id|email,...>] [--dry-run]
+                 [--passphrase-env <VAR>]
+  proxy start                            Run the proxy in the foreground (same as --headless)
+  proxy stop                             Stop the proxy of a running instance via the admin API
+  config get [<dotted.path>]             e.g. config get proxy.port
+  config set <dotted.path> <value>       Value is parsed as JSON, falling back to a string
+  user-token create --username <name> [--expires day|week|month|never] [--description <text>]
+                    [--max-ips <n>] [--rpm <n>] [--daily-tokens <n>] [--monthly-tokens <n>]
+                    [--models <a,b,...>] [--groups <a,b,...>]
+                    [--daily-budget <usd>] [--monthly-budget <usd>] [--budget-action warn|block]
+  user-token list
+  user-token revoke <id>
+  logs tail [-n <lines>] [--follow]
+  help
+
+Options:
+  --json    Print machine-readable JSON instead of tables";
+
+const SUBCOMMANDS: &[&str] = &[
+    "accounts",
+    "quota",
+    "backup",
+    "proxy",
+    "config",
+    "user-token",
+    "logs",
+    "help",
+];
+
+/// 启动参数中与子命令无关的全局开关 (Docker ENTRYPOINT 固定带 --headless)
+const PASSTHROUGH_FLAGS: &[&str] = &["--headless", "--minimized"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateTokenArgs {
+    pub username: String,
+    pub expires_type: String,
+    pub description: Option<String>,
+    pub max_ips: i32,
+    pub limits: modules::user_token_db::TokenLimits,
+}
+
+/// `accounts add` 的 refresh token 来源 (默认 stdin，避免出现在 shell 历史 / 进程列表中)
+#[derive(Debug, Clone, PartialEq)]
+pub enum RefreshTokenSource {
+    Stdin,
+    File(String),
+    Env(String),
+    /// 仅在显式传入 `--insecure-arg` 时接受命令行参数
+    Arg(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    AccountsList,
+    AccountsAdd { source: RefreshTokenSource },
+    AccountsImport { db_path: Option<String> },
+    AccountsDelete { account: String },
+    AccountsSwitch { account: String },
+    AccountsSetGroups { account: String, groups: Vec<String> },
+    AccountsExport { accounts: Vec<String>, out: Option<String>, passphrase_env: Option<String> },
+    AccountsImportJson { path: String, passphrase_env: Option<String> },
+    AccountsEncryption,
+    AccountsRotateKey { new_passphrase_env: Option<String>, new_key_file: Option<String>, disable: bool },
+    QuotaRefresh { account: Option<String> },
+    BackupCreate { out: String, sections: Vec<BackupSection>, passphrase_env: Option<String> },
+    BackupRestore {
+        path: String,
+        sections: Option<Vec<BackupSection>>,
+        accounts: Option<Vec<String>>,
+        dry_run: bool,
+        passphrase_env: Option<String>,
+    },
+    ProxyStart,
+    ProxyStop,
+    ConfigGet { path: Option<String> },
+    ConfigSet { path: String, value: String },
+    UserTokenCreate(CreateTokenArgs),
+    UserTokenList,
+    UserTokenRevoke { id: String },
+    LogsTail { lines: usize, follow: bool },
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliInvocation {
+    pub command: CliCommand,
+    pub json: bool,
+}
+
+impl CliInvocation {
+    /// `proxy start` 直接复用 lib::run 中的 headless 启动流程
+    pub fn is_proxy_start(&self) -> bool {
+        self.command == CliCommand::ProxyStart
+    }
+}
+
+/// 简单的参数游标：支持 `--name value`、`--name=value` 与布尔开关
+struct ArgCursor {
+    items: Vec<String>,
+}
+
+impl ArgCursor {
+    fn take_flag(&mut self, names: &[&str]) -> bool {
+        let before = self.items.len();
+        self.items.retain(|a| !names.contains(&a.as_str()));
+        self.items.len() != before
+    }
+
+    fn take_value(&mut self, names: &[&str]) -> Result<Option<String>, String> {
+        for name in names {
+            let prefix = format!("{}=", name);
+            if let Some(i) = self.items.iter().position(|a| a.starts_with(&prefix)) {
+                let item = self.items.remove(i);
+                return Ok(Some(item[prefix.len()..].to_string()));
+            }
+            if let Some(i) = self.items.iter().position(|a| a == name) {
+                if i + 1 >= self.items.len() {
+                    return Err(format!("Missing value for {}", name));
+                }
+                let value = self.items.remove(i + 1);
+                self.items.remove(i);
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    fn take_number<T: std::str::FromStr>(&mut self, names: &[&str]) -> Result<Option<T>, String> {
+        match self.take_value(names)? {
+            Some(v) => v
+                .parse::<T>()
+                .map(Some)
+                .map_err(|_| format!("Invalid number for {}: {}", names[0], v)),
+            None => Ok(None),
+        }
+    }
+
+    /// 取出剩余的位置参数；若仍有未识别的 `--xxx` 则报错
+    fn finish(self, max_positionals: usize) -> Result<Vec<String>, String> {
+        if let Some(unknown) = self.items.iter().find(|a| a.starts_with("--")) {
+            return Err(format!("Unknown option: {}", unknown));
+        }
+        if self.items.len() > max_positionals {
+            return Err(format!("Unexpected argument: {}", self.items[max_positionals]));
+        }
+        Ok(self.items)
+    }
+}
+
+/// 解析命令行参数 (不含程序名)。
+/// 第一个非全局参数不是已知子命令时返回 `Ok(None)`，交由 GUI / headless 流程处理。
+pub fn parse_args(args: &[String]) -> Result<Option<CliInvocation>, String> {
+    let mut items: Vec<String> = args
+        .iter()
+        .filter(|a| !PASSTHROUGH_FLAGS.contains(&a.as_str()))
+        .cloned()
+        .collect();
+
+    if items.is_empty() || !SUBCOMMANDS.contains(&items[0].as_str()) {
+        return Ok(None);
+    }
+
+    let group = items.remove(0);
+    let mut cursor = ArgCursor { items };
+    let json = cursor.take_flag(&["--json"]);
+    if cursor.take_flag(&["--help", "-h"]) || group == "help" {
+        return Ok(Some(CliInvocation { command: CliCommand::Help, json }));
+    }
+
+    let action = if cursor.items.is_empty() {
+        String::new()
+    } else {
+        cursor.items.remove(0)
+    };
+
+    let command = match (group.as_str(), action.as_str()) {
+        ("accounts", "list") => {
+            cursor.finish(0)?;
+            CliCommand::AccountsList
+        }
+        ("accounts", "add") => {
+            let file = cursor.take_value(&["--refresh-token-file"])?;
+            let env = cursor.take_value(&["--refresh-token-env"])?;
+            let insecure_arg = cursor.take_flag(&["--insecure-arg"]);
+            let positional = cursor.finish(1)?.into_iter().next().filter(|v| v != "-");
+            let source = match (file, env, positional) {
+                (Some(path), None, None) => RefreshTokenSource::File(path),
+                (None, Some(var), None) => RefreshTokenSource::Env(var),
+                (None, None, Some(token)) if insecure_arg => RefreshTokenSource::Arg(token),
+                (None, None, Some(_)) => {
+                    return Err("Refusing to read the refresh token from the command line (it would leak \
+                                into shell history and the process list); pipe it on stdin, use \
+                                --refresh-token-file / --refresh-token-env, or pass --insecure-arg"
+                        .to_string())
+                }
+                (None, None, None) => RefreshTokenSource::Stdin,
+                _ => {
+                    return Err("accounts add accepts only one of stdin, --refresh-token-file, \
+                                --refresh-token-env or --insecure-arg <token>"
+                        .to_string())
+                }
+            };
+            if insecure_arg && !matches!(source, RefreshTokenSource::Arg(_)) {
+                return Err("--insecure-arg requires a refresh token argument".to_string());
+            }
+            CliCommand::AccountsAdd { source }
+        }
+        ("accounts", "import") => {
+            let db_path = cursor.take_value(&["--db"])?;
+            cursor.finish(0)?;
+            CliCommand::AccountsImport { db_path }
+        }
+        ("accounts", "delete") | ("accounts", "switch") => {
+            let account = cursor
+                .finish(1)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| format!("accounts {} requires an account id or email", action))?;
+            if action == "delete" {
+                CliCommand::AccountsDelete { account }
+            } else {
+                CliCommand::AccountsSwitch { account }
+            }
+        }
+        ("accounts", "set-groups") => {
+            let mut rest = cursor.finish(2)?.into_iter();
+            let account = rest
+                .next()
+                .ok_or("accounts set-groups requires an account id or email")?;
+            CliCommand::AccountsSetGroups {
+                account,
+                groups: rest.next().map(|v| split_list(&v)).unwrap_or_default(),
+            }
+        }
+        ("accounts", "export") => {
+            let out = cursor.take_value(&["--out", "-o"])?;
+            let passphrase_env = cursor.take_value(&["--passphrase-env"])?;
+            CliCommand::AccountsExport {
+                accounts: cursor.finish(usize::MAX)?,
+                out,
+                passphrase_env,
+            }
+        }
+        ("accounts", "import-json") => {
+            let passphrase_env = cursor.take_value(&["--passphrase-env"])?;
+            let path = cursor
+                .finish(1)?
+                .into_iter()
+                .next()
+                .ok_or("accounts import-json requires a file path")?;
+            CliCommand::AccountsImportJson { path, passphrase_env }
+        }
+        ("accounts", "encryption") => {
+            cursor.finish(0)?;
+            CliCommand::AccountsEncryption
+        }
+        ("accounts", "rotate-key") => {
+            let new_passphrase_env = cursor.take_value(&["--new-passphrase-env"])?;
+            let new_key_file = cursor.take_value(&["--new-key-file"])?;
+            let disable = cursor.take_flag(&["--disable"]);
+            cursor.finish(0)?;
+            CliCommand::AccountsRotateKey {
+                new_passphrase_env,
+                new_key_file,
+                disable,
+            }
+        }
+        ("quota", "refresh") => CliCommand::QuotaRefresh {
+            account: cursor.finish(1)?.into_iter().next(),
+        },
+        ("backup", "create") => {
+            let out = cursor
+                .take_value(&["--out", "-o"])?
+                .ok_or("backup create requires --out <file>")?;
+            let sections = parse_sections(cursor.take_value(&["--sections"])?)?.unwrap_or_default();
+            let passphrase_env = cursor.take_value(&["--passphrase-env"])?;
+            cursor.finish(0)?;
+            CliCommand::BackupCreate { out, sections, passphrase_env }
+        }
+        ("backup", "restore") => {
+            let sections = parse_sections(cursor.take_value(&["--sections"])?)?;
+            let accounts = cursor.take_value(&["--accounts"])?.map(|v| split_list(&v));
+            let passphrase_env = cursor.take_value(&["--passphrase-env"])?;
+            let dry_run = cursor.take_flag(&["--dry-run"]);
+            let path = cursor
+                .finish(1)?
+                .into_iter()
+                .next()
+                .ok_or("backup restore requires a backup file")?;
+            CliCommand::BackupRestore {
+                path,
+                sections,
+                accounts,
+                dry_run,
+                passphrase_env,
+            }
+        }
+        ("proxy", "start") => {
+            cursor.finish(0)?;
+            CliCommand::ProxyStart
+        }
+        ("proxy", "stop") => {
+            cursor.finish(0)?;
+            CliCommand::ProxyStop
+        }
+        ("config", "get") => CliCommand::ConfigGet {
+            path: cursor.finish(1)?.into_iter().next(),
+        },
+        ("config", "set") => {
+            let rest = cursor.finish(2)?;
+            if rest.len() != 2 {
+                return Err("config set requires <dotted.path> <value>".to_string());
+            }
+            let mut rest = rest.into_iter();
+            CliCommand::ConfigSet {
+                path: rest.next().unwrap_or_default(),
+                value: rest.next().unwrap_or_default(),
+            }
+        }
+        ("user-token", "create") => {
+            let username = cursor
+                .take_value(&["--username", "-u"])?
+                .ok_or("user-token create requires --username")?;
+            let expires_type = cursor
+                .take_value(&["--expires"])?
+                .unwrap_or_else(|| "never".to_string());
+            if !matches!(expires_type.as_str(), "day" | "week" | "month" | "never") {
+                return Err(format!("Invalid --expires value: {}", expires_type));
+            }
+            let description = cursor.take_value(&["--description"])?;
+            let max_ips = cursor.take_number::<i32>(&["--max-ips"])?.unwrap_or(0);
+            let limits = modules::user_token_db::TokenLimits {
+                rpm_limit: cursor.take_number::<i32>(&["--rpm"])?.unwrap_or(0),
+                daily_token_limit: cursor.take_number::<i64>(&["--daily-tokens"])?.unwrap_or(0),
+                monthly_token_limit: cursor
+                    .take_number::<i64>(&["--monthly-tokens"])?
+                    .unwrap_or(0),
+                allowed_models: cursor
+                    .take_value(&["--models"])?
+                    .map(|v| split_list(&v))
+                    .unwrap_or_default(),
+                allowed_groups: cursor
+                    .take_value(&["--groups"])?
+                    .map(|v| split_list(&v))
+                    .unwrap_or_default(),
+                daily_budget_usd: cursor.take_number::<f64>(&["--daily-budget"])?.unwrap_or(0.0),
+                monthly_budget_usd: cursor.take_number::<f64>(&["--monthly-budget"])?.unwrap_or(0.0),
+                budget_action: match cursor.take_value(&["--budget-action"])?.as_deref() {
+                    None | Some("warn") => crate::proxy::config::BudgetAction::Warn,
+                    Some("block") => crate::proxy::config::BudgetAction::Block,
+                    Some(other) => return Err(format!("Invalid --budget-action value: {}", other)),
+                },
+            };
+            cursor.finish(0)?;
+            CliCommand::UserTokenCreate(CreateTokenArgs {
+                username,
+                expires_type,
+                description,
+                max_ips,
+                limits,
+            })
+        }
+        ("user-token", "list") => {
+            cursor.finish(0)?;
+            CliCommand::UserTokenList
+        }
+        ("user-token", "revoke") => {
+            let id = cursor
+                .finish(1)?
+                .into_iter()
+                .next()
+                .ok_or("user-token revoke requires a token id")?;
+            CliCommand::UserTokenRevoke { id }
+        }
+        ("logs", "tail") => {
+            let lines = cursor.take_number::<usize>(&["-n", "--lines"])?.unwrap_or(100);
+            let follow = cursor.take_flag(&["--follow", "-f"]);
+            cursor.finish(0)?;
+            CliCommand::LogsTail { lines, follow }
+        }
+        (group, "") => return Err(format!("Missing subcommand for '{}'", group)),
+        (group, action) => return Err(format!("Unknown command: {} {}", group, action)),
+    };
+
+    Ok(Some(CliInvocation { command, json }))
+}
+
+/// 执行子命令，返回进程退出码
+pub fn run(invocation: CliInvocation) -> i32 {
+    let rt = match tokio::runtime::Runtime::new() {
+        Ok(rt) => rt,
+        Err(e) => {
+            eprintln!("Error: failed to create Tokio runtime: {}", e);
+            return 1;
+        }
+    };
+
+    match rt.block_on(execute(invocation)) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    }
+}
+
+async fn execute(invocation: CliInvocation) -> Result<(), String> {
+    let json = invocation.json;
+    let service = AccountService::new(SystemManager::Headless);
+    if invocation.command != CliCommand::Help {
+        // 解锁加密的账号文件 (ABV_ACCOUNT_PASSPHRASE / ABV_ACCOUNT_KEY_FILE)
+        modules::account_crypto::init()?;
+    }
+
+    match invocation.command {
+        CliCommand::Help => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        CliCommand::AccountsList => {
+            let current = service.get_current_id()?;
+            let accounts: Vec<AccountSummary> = service
+                .list_accounts()?
+                .iter()
+                .map(|a| AccountSummary::from_account(a, current.as_deref()))
+                .collect();
+            if json {
+                return print_json(&accounts);
+            }
+            print_table(
+                &["CURRENT", "ID", "EMAIL", "TIER", "STATUS", "LOWEST QUOTA", "GROUPS"],
+                accounts
+                    .iter()
+                    .map(|a| {
+                        vec![
+                            if a.current { "*".to_string() } else { String::new() },
+                            a.id.clone(),
+                            a.email.clone(),
+                            a.subscription_tier.clone().unwrap_or_else(|| "-".to_string()),
+                            a.status.clone(),
+                            a.lowest_quota
+                                .as_ref()
+                                .map(|(model, pct)| format!("{}% ({})", pct, model))
+                                .unwrap_or_else(|| "-".to_string()),
+                            if a.groups.is_empty() { "-".to_string() } else { a.groups.join(",") },
+                        ]
+                    })
+                    .collect(),
+            );
+            Ok(())
+        }
+        CliCommand::AccountsAdd { source } => {
+            let refresh_token = read_refresh_token(source)?;
+            let account = service.add_account(&refresh_token).await?;
+            print_account_result("Added", &account, json)
+        }
+        CliCommand::AccountsImport { db_path } => {
+            let account = match db_path {
+                Some(path) => modules::migration::import_from_custom_db_path(path).await?,
+                None => modules::migration::import_from_db().await?,
+            };
+            print_account_result("Imported", &account, json)
+        }
+        CliCommand::AccountsDelete { account } => {
+            let account = resolve_account(&service, &account)?;
+            service.delete_account(&account.id)?;
+            print_account_result("Deleted", &account, json)
+        }
+        CliCommand::AccountsSwitch { account } => {
+            let account = resolve_account(&service, &account)?;
+            service.switch_account(&account.id).await?;
+            print_account_result("Switched to", &account, json)
+        }
+        CliCommand::AccountsSetGroups { account, groups } => {
+            let account = resolve_account(&service, &account)?;
+            let account = modules::account::set_account_groups(&account.id, groups)?;
+            if json {
+                let current = modules::get_current_account_id().ok().flatten();
+                return print_json(&AccountSummary::from_account(&account, current.as_deref()));
+            }
+            println!(
+                "Account {} groups: {}",
+                account.email,
+                if account.groups.is_empty() { "-".to_string() } else { account.groups.join(",") }
+            );
+            Ok(())
+        }
+        CliCommand::AccountsExport { accounts, out, passphrase_env } => {
+            let ids = if accounts.is_empty() {
+                service.list_accounts()?.into_iter().map(|a| a.id).collect::<Vec<_>>()
+            } else {
+                accounts
+                    .iter()
+                    .map(|key| resolve_account(&service, key).map(|a| a.id))
+                    .collect::<Result<Vec<_>, _>>()?
+            };
+            let export = modules::account::export_accounts_by_ids(&ids)?;
+            let mut content = serde_json::to_string_pretty(&export.accounts).map_err(|e| e.to_string())?;
+            if let Some(var) = &passphrase_env {
+                content = modules::account_crypto::seal_export(&content, &read_env_secret(var)?)?;
+            }
+            match out {
+                Some(path) => {
+                    std::fs::write(&path, &content).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+                    if json {
+                        return print_json(&serde_json::json!({
+                            "exported": export.accounts.len(),
+                            "path": path,
+                            "encrypted": passphrase_env.is_some(),
+                        }));
+                    }
+                    println!("Exported {} account(s) to {}", export.accounts.len(), path);
+                }
+                None => println!("{}", content),
+            }
+            Ok(())
+        }
+        CliCommand::AccountsImportJson { path, passphrase_env } => {
+            let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            let content = if modules::account_crypto::is_sealed_export(&content) {
+                let var = passphrase_env
+                    .as_deref()
+                    .ok_or("The file is encrypted; pass --passphrase-env <VAR>")?;
+                modules::account_crypto::open_export(&content, &read_env_secret(var)?)?
+            } else {
+                content
+            };
+            let items: Vec<crate::models::AccountExportItem> =
+                serde_json::from_str(&content).map_err(|e| format!("Invalid export file: {}", e))?;
+
+            let (mut imported, mut failed) = (Vec::new(), Vec::new());
+            for item in items {
+                match service.add_account(item.refresh_token.trim()).await {
+                    Ok(account) => imported.push(account.email),
+                    Err(e) => failed.push(serde_json::json!({ "email": item.email, "error": e })),
+                }
+            }
+            if json {
+                return print_json(&serde_json::json!({ "imported": imported, "failed": failed }));
+            }
+            println!("Imported {} account(s), {} failed", imported.len(), failed.len());
+            for f in &failed {
+                println!("  {}: {}", f["email"].as_str().unwrap_or("-"), f["error"].as_str().unwrap_or("-"));
+            }
+            Ok(())
+        }
+        CliCommand::AccountsEncryption => print_encryption_status(&modules::account_crypto::status()?, json),
+        CliCommand::AccountsRotateKey { new_passphrase_env, new_key_file, disable } => {
+            let new_passphrase = new_passphrase_env.as_deref().map(read_env_secret).transpose()?;
+            let target = crate::commands::resolve_rotation_target(new_passphrase, new_key_file, disable)?;
+            let count = modules::account_crypto::rotate_key(target)?;
+            let status = modules::account_crypto::status()?;
+            if !json {
+                println!("Re-wrote {} account file(s)", count);
+            }
+            print_encryption_status(&status, json)
+        }
+        CliCommand::QuotaRefresh { account: None } => {
+            let stats = modules::account::refresh_all_quotas_logic().await?;
+            if json {
+                return print_json(&stats);
+            }
+            println!(
+                "Refreshed {} account(s): {} succeeded, {} failed",
+                stats.total, stats.success, stats.failed
+            );
+            for detail in &stats.details {
+                println!("  {}", detail);
+            }
+            Ok(())
+        }
+        CliCommand::QuotaRefresh { account: Some(account) } => {
+            let mut account = resolve_account(&service, &account)?;
+            let quota = modules::account::fetch_quota_with_retry(&mut account)
+                .await
+                .map_err(|e| e.to_string())?;
+            modules::account::update_account_quota(&account.id, quota.clone())?;
+            if json {
+                return print_json(&quota);
+            }
+            println!("Quota for {}:", account.email);
+            print_table(
+                &["MODEL", "REMAINING", "RESET"],
+                quota
+                    .models
+                    .iter()
+                    .map(|m| vec![m.name.clone(), format!("{}%", m.percentage), m.reset_time.clone()])
+                    .collect(),
+            );
+            Ok(())
+        }
+        // 由 lib::run 以 headless 模式处理，不会走到这里
+        CliCommand::BackupCreate { out, sections, passphrase_env } => {
+            let passphrase = passphrase_env.as_deref().map(read_env_secret).transpose()?;
+            let archive = modules::backup::create_backup(&sections, passphrase.as_deref())?;
+            std::fs::write(&out, &archive.content).map_err(|e| format!("Failed to write {}: {}", out, e))?;
+            if json {
+                return print_json(&archive.summary);
+            }
+            println!(
+                "Backup written to {} ({} entries, {} bytes{})",
+                out,
+                archive.summary.entries,
+                archive.summary.bytes,
+                if archive.summary.encrypted { ", encrypted" } else { "" }
+            );
+            Ok(())
+        }
+        CliCommand::BackupRestore { path, sections, accounts, dry_run, passphrase_env } => {
+            let content = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            let options = modules::backup::RestoreOptions {
+                sections,
+                accounts,
+                dry_run,
+                passphrase: passphrase_env.as_deref().map(read_env_secret).transpose()?,
+            };
+            let report = modules::backup::restore_backup(&content, &options)?;
+            if json {
+                return print_json(&report);
+            }
+            for warning in &report.warnings {
+                println!("Warning: {}", warning);
+            }
+            print_table(
+                &["SECTION", "PATH", "ACTION", "DETAIL"],
+                report
+                    .items
+                    .iter()
+                    .map(|i| {
+                        vec![
+                            i.section.as_str().to_string(),
+                            i.path.clone(),
+                            i.action.as_str().to_string(),
+                            i.detail.clone().unwrap_or_else(|| "-".to_string()),
+                        ]
+                    })
+                    .collect(),
+            );
+            if report.dry_run {
+                println!("Dry run: nothing was written");
+            }
+            Ok(())
+        }
+        CliCommand::ProxyStart => Err("proxy start must be handled by the headless runner".to_string()),
+        CliCommand::ProxyStop => proxy_stop(json).await,
+        CliCommand::ConfigGet { path } => {
+            let config = serde_json::to_value(modules::config::load_app_config()?)
+                .map_err(|e| e.to_string())?;
+            let value = match path.as_deref() {
+                Some(p) => get_path(&config, p).ok_or_else(|| format!("Unknown config key: {}", p))?,
+                None => &config,
+            };
+            match value {
+                Value::String(s) if !json => println!("{}", s),
+                Value::Object(_) | Value::Array(_) => print_json(value)?,
+                other => println!("{}", other),
+            }
+            Ok(())
+        }
+        CliCommand::ConfigSet { path, value } => {
+            let config = modules::config::load_app_config()?;
+            let updated = set_config_value(&config, &path, &value)?;
+            modules::config::save_app_config(&updated)?;
+            let new_value = serde_json::to_value(&updated)
+                .ok()
+                .and_then(|v| get_path(&v, &path).cloned())
+                .unwrap_or(Value::Null);
+            if json {
+                return print_json(&serde_json::json!({ "path": path, "value": new_value }));
+            }
+            println!("{} = {}", path, new_value);
+            println!("Saved. A running headless instance hot-reloads supported sections; others need a restart.");
+            Ok(())
+        }
+        CliCommand::UserTokenCreate(args) => {
+            modules::user_token_db::init_db()?;
+            let token = modules::user_token_db::create_token(
+                args.username,
+                args.expires_type,
+                args.description,
+                args.max_ips,
+                None,
+                None,
+                None,
+                args.limits,
+            )?;
+            if json {
+                return print_json(&token);
+            }
+            println!("Created user token {} for {}", token.id, token.username);
+            println!("Token: {}", token.token);
+            Ok(())
+        }
+        CliCommand::UserTokenList => {
+            modules::user_token_db::init_db()?;
+            let tokens = modules::user_token_db::list_tokens()?;
+            if json {
+                return print_json(&tokens);
+            }
+            print_table(
+                &["ID", "USERNAME", "TOKEN", "ENABLED", "EXPIRES", "REQUESTS", "TOKENS USED"],
+                tokens
+                    .iter()
+                    .map(|t| {
+                        vec![
+                            t.id.clone(),
+                            t.username.clone(),
+                            mask_secret(&t.token),
+                            t.enabled.to_string(),
+                            t.expires_at
+                                .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+                                .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                                .unwrap_or_else(|| "never".to_string()),
+                            t.total_requests.to_string(),
+                            t.total_tokens_used.to_string(),
+                        ]
+                    })
+                    .collect(),
+            );
+            Ok(())
+        }
+        CliCommand::UserTokenRevoke { id } => {
+            modules::user_token_db::init_db()?;
+            let token = modules::user_token_db::get_token_by_id(&id)?
+                .ok_or_else(|| format!("User token not found: {}", id))?;
+            modules::user_token_db::delete_token(&id)?;
+            if json {
+                return print_json(&serde_json::json!({ "revoked": id, "username": token.username }));
+            }
+            println!("Revoked user token {} ({})", id, token.username);
+            Ok(())
+        }
+        CliCommand::LogsTail { lines, follow } => logs_tail(lines, follow).await,
+    }
+}
+
+/// 账号摘要 (不包含 refresh_token 等敏感字段)
+#[derive(Debug, Serialize)]
+struct AccountSummary {
+    id: String,
+    email: String,
+    name: Option<String>,
+    current: bool,
+    status: String,
+    subscription_tier: Option<String>,
+    /// (模型, 剩余百分比)
+    lowest_quota: Option<(String, i32)>,
+    groups: Vec<String>,
+    last_used: i64,
+}
+
+impl AccountSummary {
+    fn from_account(account: &Account, current_id: Option<&str>) -> Self {
+        let status = if account.disabled {
+            "disabled"
+        } else if account.proxy_disabled {
+            "proxy_disabled"
+        } else if account.validation_blocked {
+            "validation_blocked"
+        } else if account.quota.as_ref().map(|q| q.is_forbidden).unwrap_or(false) {
+            "forbidden"
+        } else {
+            "active"
+        };
+        Self {
+            id: account.id.clone(),
+            email: account.email.clone(),
+            name: account.name.clone(),
+            current: current_id == Some(account.id.as_str()),
+            status: status.to_string(),
+            subscription_tier: account.quota.as_ref().and_then(|q| q.subscription_tier.clone()),
+            lowest_quota: account.quota.as_ref().and_then(|q| {
+                q.models
+                    .iter()
+                    .min_by_key(|m| m.percentage)
+                    .map(|m| (m.name.clone(), m.percentage))
+            }),
+            groups: account.groups.clone(),
+            last_used: account.last_used,
+        }
+    }
+}
+
+/// 按 ID 或邮箱查找账号
+fn resolve_account(service: &AccountService, key: &str) -> Result<Account, String> {
+    service
+        .list_accounts()?
+        .into_iter()
+        .find(|a| a.id == key || a.email.eq_ignore_ascii_case(key))
+        .ok_or_else(|| format!("Account not found: {}", key))
+}
+
+fn print_account_result(action: &str, account: &Account, json: bool) -> Result<(), String> {
+    if json {
+        let current = modules::get_current_account_id().ok().flatten();
+        return print_json(&AccountSummary::from_account(account, current.as_deref()));
+    }
+    println!("{} account {} ({})", action, account.email, account.id);
+    Ok(())
+}
+
+fn parse_sections(raw: Option<String>) -> Result<Option<Vec<BackupSection>>, String> {
+    raw.map(|v| {
+        split_list(&v)
+            .iter()
+            .map(|s| BackupSection::parse(s).ok_or_else(|| format!("Unknown backup section: {}", s)))
+            .collect()
+    })
+    .transpose()
+}
+
+/// 从环境变量读取口令 (避免口令出现在命令行 / shell 历史中)
+fn read_env_secret(var: &str) -> Result<String, String> {
+    std::env::var(var)
+        .ok()
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| format!("Environment variable {} is not set", var))
+}
+
+/// 按来源读取 refresh token (stdin 为终端时提示输入一行，否则读取全部输入)
+fn read_refresh_token(source: RefreshTokenSource) -> Result<String, String> {
+    use std::io::IsTerminal;
+
+    let raw = match source {
+        RefreshTokenSource::Stdin => {
+            let stdin = std::io::stdin();
+            let mut buf = String::new();
+            if stdin.is_terminal() {
+                eprint!("Refresh token: ");
+                let _ = std::io::stderr().flush();
+                stdin.read_line(&mut buf).map_err(|e| format!("Failed to read stdin: {}", e))?;
+            } else {
+                stdin
+                    .lock()
+                    .read_to_string(&mut buf)
+                    .map_err(|e| format!("Failed to read stdin: {}", e))?;
+            }
+            buf
+        }
+        RefreshTokenSource::File(path) => {
+            std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?
+        }
+        RefreshTokenSource::Env(var) => read_env_secret(&var)?,
+        RefreshTokenSource::Arg(token) => token,
+    };
+    let token = raw.trim();
+    if token.is_empty() {
+        return Err("accounts add requires a refresh token".to_string());
+    }
+    Ok(token.to_string())
+}
+
+fn print_encryption_status(
+    status: &modules::account_crypto::AccountEncryptionStatus,
+    json: bool,
+) -> Result<(), String> {
+    if json {
+        return print_json(status);
+    }
+    if !status.enabled {
+        println!("Account file encryption: disabled");
+    } else {
+        println!(
+            "Account file encryption: enabled ({}, key {}){}",
+            status.kdf.as_deref().unwrap_or("-"),
+            status.key_id.as_deref().unwrap_or("-"),
+            if status.unlocked { "" } else { " - LOCKED" }
+        );
+        if let Some(key_file) = &status.key_file {
+            println!("Key file: {}", key_file);
+        }
+    }
+    println!(
+        "Files: {} encrypted, {} plaintext",
+        status.encrypted_files, status.plaintext_files
+    );
+    Ok(())
+}
+
+/// 通过 Admin API 停止正在运行实例的反代服务
+async fn proxy_stop(json: bool) -> Result<(), String> {
+    let config = modules::config::load_app_config()?;
+    let secret = config
+        .proxy
+        .admin_password
+        .clone()
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| config.proxy.api_key.clone());
+    let url = format!("http://127.0.0.1:{}/api/proxy/stop", config.proxy.port);
+
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .no_proxy()
+        .build()
+        .map_err(|e| e.to_string())?;
+    let response = client
+        .post(&url)
+        .bearer_auth(secret)
+        .send()
+        .await
+        .map_err(|e| format!("No running instance reachable at {}: {}", url, e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("Stop request failed (HTTP {}): {}", status.as_u16(), body));
+    }
+    if json {
+        return print_json(&serde_json::json!({ "stopped": true, "port": config.proxy.port }));
+    }
+    println!("Proxy service on port {} stopped", config.proxy.port);
+    Ok(())
+}
+
+/// 按点分路径读取 JSON 值 (数组下标使用数字段，如 `proxy.upstream_proxy.url`)
+pub fn get_path<'a>(root: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(root, |node, key| match node {
+        Value::Object(map) => map.get(key),
+        Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    })
+}
+
+/// 按点分路径写入 JSON 值。中间节点必须存在；末级键可以是对象中的新键 (例如 HashMap 条目)
+pub fn set_path(root: &mut Value, path: &str, new_value: Value) -> Result<(), String> {
+    let (parent_path, last) = match path.rsplit_once('.') {
+        Some((parent, last)) => (Some(parent), last),
+        None => (None, path),
+    };
+    let parent = match parent_path {
+        Some(p) => {
+            let mut node = root;
+            for key in p.split('.') {
+                node = match node {
+                    Value::Object(map) => map.get_mut(key),
+                    Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get_mut(i)),
+                    _ => None,
+                }
+                .ok_or_else(|| format!("Unknown config key: {}", path))?;
+            }
+            node
+        }
+        None => root,
+    };
+
+    match parent {
+        Value::Object(map) => {
+            map.insert(last.to_string(), new_value);
+            Ok(())
+        }
+        Value::Array(items) => {
+            let slot = last
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| items.get_mut(i))
+                .ok_or_else(|| format!("Index out of range: {}", path))?;
+            *slot = new_value;
+            Ok(())
+        }
+        _ => Err(format!("Cannot set a field inside a scalar value: {}", path)),
+    }
+}
+
+/// 逗号分隔列表 (`--models a,b` / `--groups a,b`)，忽略空白项
+fn split_list(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// 将字符串解析为 JSON 值；解析失败时按普通字符串处理 (`config set proxy.api_key sk-xxx`)
+fn parse_cli_value(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+/// 在 AppConfig 上应用一次点分路径修改，并通过反序列化校验类型。
+/// 修改后重新序列化比对，用于拒绝拼写错误的字段 (serde 会静默丢弃未知字段)。
+pub fn set_config_value(config: &AppConfig, path: &str, raw: &str) -> Result<AppConfig, String> {
+    let tree = serde_json::to_value(config).map_err(|e| e.to_string())?;
+    let parsed = parse_cli_value(raw);
+    let as_string = Value::String(raw.to_string());
+
+    // 目标字段原本是字符串时，不要把 "8045" / "true" 之类的输入转成数字或布尔；
+    // 否则先按 JSON 尝试，失败再按字符串尝试 (例如原值为 null 的 Option<String>)
+    let candidates = if matches!(get_path(&tree, path), Some(Value::String(_))) || parsed.is_string() {
+        vec![as_string]
+    } else {
+        vec![parsed, as_string]
+    };
+
+    let mut last_error = String::new();
+    for new_value in candidates {
+        let mut candidate = tree.clone();
+        set_path(&mut candidate, path, new_value.clone())?;
+        let updated: AppConfig = match serde_json::from_value(candidate) {
+            Ok(c) => c,
+            Err(e) => {
+                last_error = format!("Invalid value for {}: {}", path, e);
+                continue;
+            }
+        };
+        let roundtrip = serde_json::to_value(&updated).map_err(|e| e.to_string())?;
+        return match get_path(&roundtrip, path) {
+            Some(v) if *v == new_value => Ok(updated),
+            Some(v) => Err(format!(
+                "Value for {} was normalized to {}; refusing to save an unexpected value",
+                path, v
+            )),
+            None => Err(format!("Unknown config key: {}", path)),
+        };
+    }
+    Err(last_error)
+}
+
+fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    println!("{}", text);
+    Ok(())
+}
+
+fn print_table(headers: &[&str], rows: Vec<Vec<String>>) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+    let format_row = |cells: Vec<String>| -> String {
+        cells
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!("{:<width$}", c, width = widths[i]))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    println!("{}", format_row(headers.iter().map(|h| h.to_string()).collect()));
+    if rows.is_empty() {
+        println!("(none)");
+    }
+    for row in rows {
+        println!("{}", format_row(row));
+    }
+}
+
+fn mask_secret(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() <= 10 {
+        return "*".repeat(chars.len());
+    }
+    format!(
+        "{}…{}",
+        chars[..6].iter().collect::<String>(),
+        chars[chars.len() - 4..].iter().collect::<String>()
+    )
+}
+
+/// 当前正在写入的日志文件 (tracing_appender 按天滚动: app.log.YYYY-MM-DD)
+fn latest_log_file(log_dir: &Path) -> Result<Option<PathBuf>, String> {
+    let entries = std::fs::read_dir(log_dir).map_err(|e| e.to_string())?;
+    Ok(entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().starts_with("app.log"))
+        .filter_map(|e| {
+            let modified = e.metadata().ok()?.modified().ok()?;
+            Some((modified, e.path()))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path))
+}
+
+/// 读取文件末尾的 `lines` 行，返回 (行, 文件长度)。从文件尾部按块回读，避免加载整个日志。
+fn read_tail(path: &Path, lines: usize) -> Result<(Vec<String>, u64), String> {
+    let mut file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let len = file.metadata().map_err(|e| e.to_string())?.len();
+    let mut window: u64 = 64 * 1024;
+
+    loop {
+        let start = len.saturating_sub(window);
+        file.seek(SeekFrom::Start(start)).map_err(|e| e.to_string())?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf).map_err(|e| e.to_string())?;
+        let text = String::from_utf8_lossy(&buf);
+        let mut all: Vec<&str> = text.lines().collect();
+        if start > 0 {
+            if all.len() <= lines {
+                window *= 4;
+                continue;
+            }
+            // 首行可能从中间截断
+            all.remove(0);
+        }
+        let skip = all.len().saturating_sub(lines);
+        return Ok((all[skip..].iter().map(|s| s.to_string()).collect(), len));
+    }
+}
+
+async fn logs_tail(lines: usize, follow: bool) -> Result<(), String> {
+    let log_dir = modules::logger::get_log_dir()?;
+    let mut current = latest_log_file(&log_dir)?;
+    let mut offset = 0u64;
+
+    match &current {
+        Some(path) => {
+            let (tail, len) = read_tail(path, lines)?;
+            for line in tail {
+                println!("{}", line);
+            }
+            offset = len;
+        }
+        None if !follow => return Err(format!("No log files in {}", log_dir.display())),
+        None => {}
+    }
+    if !follow {
+        return Ok(());
+    }
+
+    let mut stdout = std::io::stdout();
+    loop {
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+        // 日志按天滚动后切换到新文件
+        let latest = latest_log_file(&log_dir)?;
+        if latest != current {
+            current = latest;
+            offset = 0;
+        }
+        let Some(path) = &current else { continue };
+
+        let len = match std::fs::metadata(path) {
+            Ok(m) => m.len(),
+            Err(_) => continue,
+        };
+        if len < offset {
+            // 文件被清空 (clear_logs)
+            offset = 0;
+        }
+        if len == offset {
+            continue;
+        }
+
+        let mut file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+        file.seek(SeekFrom::Start(offset)).map_err(|e| e.to_string())?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf).map_err(|e| e.to_string())?;
+        offset += buf.len() as u64;
+        stdout.write_all(&buf).map_err(|e| e.to_string())?;
+        stdout.flush().ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_ignores_non_cli_invocations() {
+        assert_eq!(parse_args(&args("")).unwrap(), None);
+        assert_eq!(parse_args(&args("--headless")).unwrap(), None);
+        assert_eq!(parse_args(&args("--minimized")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_subcommands() {
+        // Docker ENTRYPOINT 自带 --headless
+        let inv = parse_args(&args("--headless accounts list --json")).unwrap().unwrap();
+        assert_eq!(inv.command, CliCommand::AccountsList);
+        assert!(inv.json);
+
+        let inv = parse_args(&args("config set proxy.port 8046")).unwrap().unwrap();
+        assert_eq!(
+            inv.command,
+            CliCommand::ConfigSet { path: "proxy.port".into(), value: "8046".into() }
+        );
+
+        let inv = parse_args(&args("logs tail -n 20 --follow")).unwrap().unwrap();
+        assert_eq!(inv.command, CliCommand::LogsTail { lines: 20, follow: true });
+
+        let inv = parse_args(&args("accounts set-groups a@test.com team-a,,team-b")).unwrap().unwrap();
+        assert_eq!(
+            inv.command,
+            CliCommand::AccountsSetGroups {
+                account: "a@test.com".into(),
+                groups: vec!["team-a".into(), "team-b".into()],
+            }
+        );
+
+        let inv = parse_args(&args(
+            "user-token create --username alice --expires week --rpm=30 --models gemini-*,claude-* --groups team-a --daily-budget 5 --budget-action block",
+        ))
+        .unwrap()
+        .unwrap();
+        match inv.command {
+            CliCommand::UserTokenCreate(a) => {
+                assert_eq!(a.username, "alice");
+                assert_eq!(a.expires_type, "week");
+                assert_eq!(a.limits.rpm_limit, 30);
+                assert_eq!(a.limits.allowed_models, vec!["gemini-*", "claude-*"]);
+                assert_eq!(a.limits.allowed_groups, vec!["team-a"]);
+                assert_eq!(a.limits.daily_budget_usd, 5.0);
+                assert_eq!(a.limits.budget_action, crate::proxy::config::BudgetAction::Block);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        let inv = parse_args(&args("accounts export a@test.com b@test.com --out x.json --passphrase-env PASS"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            inv.command,
+            CliCommand::AccountsExport {
+                accounts: vec!["a@test.com".into(), "b@test.com".into()],
+                out: Some("x.json".into()),
+                passphrase_env: Some("PASS".into()),
+            }
+        );
+
+        let inv = parse_args(&args("accounts rotate-key --disable")).unwrap().unwrap();
+        assert_eq!(
+            inv.command,
+            CliCommand::AccountsRotateKey { new_passphrase_env: None, new_key_file: None, disable: true }
+        );
+
+        let inv = parse_args(&args("backup restore b.json --sections accounts,config --dry-run"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            inv.command,
+            CliCommand::BackupRestore {
+                path: "b.json".into(),
+                sections: Some(vec![BackupSection::Accounts, BackupSection::Config]),
+                accounts: None,
+                dry_run: true,
+                passphrase_env: None,
+            }
+        );
+        assert!(parse_args(&args("backup create --out b.json --sections nope")).is_err());
+        assert!(parse_args(&args("backup create")).is_err());
+
+        // refresh token 默认从 stdin 读取，命令行参数需显式 --insecure-arg
+        let inv = parse_args(&args("accounts add")).unwrap().unwrap();
+        assert_eq!(inv.command, CliCommand::AccountsAdd { source: RefreshTokenSource::Stdin });
+        let inv = parse_args(&args("accounts add -")).unwrap().unwrap();
+        assert_eq!(inv.command, CliCommand::AccountsAdd { source: RefreshTokenSource::Stdin });
+        let inv = parse_args(&args("accounts add --refresh-token-file rt.txt")).unwrap().unwrap();
+        assert_eq!(
+            inv.command,
+            CliCommand::AccountsAdd { source: RefreshTokenSource::File("rt.txt".into()) }
+        );
+        let inv = parse_args(&args("accounts add --refresh-token-env RT")).unwrap().unwrap();
+        assert_eq!(inv.command, CliCommand::AccountsAdd { source: RefreshTokenSource::Env("RT".into()) });
+        let inv = parse_args(&args("accounts add 1//abc --insecure-arg")).unwrap().unwrap();
+        assert_eq!(inv.command, CliCommand::AccountsAdd { source: RefreshTokenSource::Arg("1//abc".into()) });
+        assert!(parse_args(&args("accounts add 1//abc")).is_err());
+        assert!(parse_args(&args("accounts add --insecure-arg")).is_err());
+        assert!(parse_args(&args("accounts add --refresh-token-file rt.txt --refresh-token-env RT")).is_err());
+
+        assert!(parse_args(&args("accounts frobnicate")).is_err());
+        assert!(parse_args(&args("accounts delete")).is_err());
+        assert!(parse_args(&args("accounts list --bogus")).is_err());
+        assert!(parse_args(&args("user-token create --expires week")).is_err());
+    }
+
+    #[test]
+    fn test_dotted_path_get_and_set() {
+        let mut v = json!({ "proxy": { "port": 8045, "custom_mapping": {} }, "list": [1, 2] });
+        assert_eq!(get_path(&v, "proxy.port"), Some(&json!(8045)));
+        assert_eq!(get_path(&v, "list.1"), Some(&json!(2)));
+        assert_eq!(get_path(&v, "proxy.missing"), None);
+
+        set_path(&mut v, "proxy.custom_mapping.gpt-4o", json!("gemini-3-flash")).unwrap();
+        assert_eq!(get_path(&v, "proxy.custom_mapping.gpt-4o"), Some(&json!("gemini-3-flash")));
+        assert!(set_path(&mut v, "nope.port", json!(1)).is_err());
+        assert!(set_path(&mut v, "proxy.port.x", json!(1)).is_err());
+    }
+
+    #[test]
+    fn test_set_config_value_validates_against_app_config() {
+        let config = AppConfig::new();
+
+        let updated = set_config_value(&config, "proxy.port", "8046").unwrap();
+        assert_eq!(updated.proxy.port, 8046);
+
+        // 字符串字段保持字符串
+        let updated = set_config_value(&config, "proxy.api_key", "12345").unwrap();
+        assert_eq!(updated.proxy.api_key, "12345");
+
+        let updated =
+            set_config_value(&config, "proxy.custom_mapping.gpt-4o", "gemini-3-flash").unwrap();
+        assert_eq!(
+            updated.proxy.custom_mapping.get("gpt-4o").map(String::as_str),
+            Some("gemini-3-flash")
+        );
+
+        // 类型错误与未知字段都会被拒绝
+        assert!(set_config_value(&config, "proxy.port", "not-a-port").is_err());
+        assert!(set_config_value(&config, "proxy.prot", "8046").is_err());
+    }
+
+    #[test]
+    fn test_read_tail_returns_last_lines() {
+        let path = std::env::temp_dir().join(format!("abv-cli-tail-{}.log", uuid::Uuid::new_v4()));
+        let content: String = (0..5000).map(|i| format!("line {}\n", i)).collect();
+        std::fs::write(&path, &content).unwrap();
+
+        let (lines, len) = read_tail(&path, 3).unwrap();
+        assert_eq!(lines, vec!["line 4997", "line 4998", "line 4999"]);
+        assert_eq!(len, content.len() as u64);
+
+        let (lines, _) = read_tail(&path, 10_000).unwrap();
+        assert_eq!(lines.len(), 5000);
+        assert_eq!(lines[0], "line 0");
+
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,56 @@
+use crate::modules::admin_user_db::{self, AdminSession, AdminUser, AdminUserUpdate, NewAdminUser};
+use crate::modules::audit;
+
+/// 列出管理员
+#[tauri::command]
+pub async fn list_admin_users() -> Result<Vec<AdminUser>, String> {
+    admin_user_db::list_users()
+}
+
+/// 创建管理员
+#[tauri::command]
+pub async fn create_admin_user(request: NewAdminUser) -> Result<AdminUser, String> {
+    let result = admin_user_db::create_user(&request);
+    audit::record_command(
+        "create_admin_user",
+        result.as_ref().ok().map(|u| u.id.as_str()),
+        serde_json::json!({ "username": request.username, "role": request.role }),
+        &result,
+    );
+    result
+}
+
+/// 修改管理员 (密码 / 角色 / 启用状态)
+#[tauri::command]
+pub async fn update_admin_user(id: String, request: AdminUserUpdate) -> Result<AdminUser, String> {
+    let result = admin_user_db::update_user(&id, &request);
+    audit::record_command(
+        "update_admin_user",
+        Some(&id),
+        serde_json::to_value(&request).unwrap_or_default(),
+        &result,
+    );
+    result
+}
+
+/// 删除管理员 (同时注销其全部会话)
+#[tauri::command]
+pub async fn delete_admin_user(id: String) -> Result<(), String> {
+    let result = admin_user_db::delete_user(&id);
+    audit::record_command("delete_admin_user", Some(&id), serde_json::Value::Null, &result);
+    result
+}
+
+/// 列出未过期的登录会话
+#[tauri::command]
+pub async fn list_admin_sessions() -> Result<Vec<AdminSession>, String> {
+    admin_user_db::list_sessions()
+}
+
+/// 吊销登录会话
+#[tauri::command]
+pub async fn revoke_admin_session(id: String) -> Result<bool, String> {
+    let result = admin_user_db::revoke_session(&id);
+    audit::record_command("revoke_admin_session", Some(&id), serde_json::Value::Null, &result);
+    result
+}
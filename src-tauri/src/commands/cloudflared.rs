@@ -72,12 +72,16 @@ pub async fn cloudflared_start(
 ) -> Result<CloudflaredStatus, String> {
     state.ensure_manager().await?;
     
+    let details = serde_json::to_value(&config).unwrap_or_default();
     let lock = state.manager.read().await;
-    if let Some(manager) = lock.as_ref() {
+    let result = if let Some(manager) = lock.as_ref() {
         manager.start(config).await
     } else {
         Err("Manager not initialized".to_string())
-    }
+    };
+    // 隧道会把服务暴露到公网，记录到审计日志
+    crate::modules::audit::record_command("cloudflared_start", None, details, &result);
+    result
 }
 
 /// 停止cloudflared隧道
@@ -88,11 +92,13 @@ pub async fn cloudflared_stop(
     state.ensure_manager().await?;
     
     let lock = state.manager.read().await;
-    if let Some(manager) = lock.as_ref() {
+    let result = if let Some(manager) = lock.as_ref() {
         manager.stop().await
     } else {
         Err("Manager not initialized".to_string())
-    }
+    };
+    crate::modules::audit::record_command("cloudflared_stop", None, serde_json::Value::Null, &result);
+    result
 }
 
 /// 获取cloudflared状态
@@ -15,6 +15,8 @@ pub mod security;
 pub mod proxy_pool;
 // 导出 user_token 命令
 pub mod user_token;
+// 导出 admin_user 命令 (管理员账号与登录会话)
+pub mod admin_user;
 
 /// 列出所有账号
 #[tauri::command]
@@ -33,7 +35,14 @@ pub async fn add_account(
         crate::modules::integration::SystemManager::Desktop(app.clone()),
     );
 
-    let mut account = service.add_account(&refresh_token).await?;
+    let added = service.add_account(&refresh_token).await;
+    modules::audit::record_command(
+        "add_account",
+        added.as_ref().ok().map(|a| a.id.as_str()),
+        serde_json::json!({ "email": added.as_ref().ok().map(|a| a.email.clone()) }),
+        &added,
+    );
+    let mut account = added?;
 
     // 自动刷新配额
     let _ = internal_refresh_account_quota(&app, &mut account).await;
@@ -58,7 +67,9 @@ pub async fn delete_account(
     let service = modules::account_service::AccountService::new(
         crate::modules::integration::SystemManager::Desktop(app.clone()),
     );
-    service.delete_account(&account_id)?;
+    let result = service.delete_account(&account_id);
+    modules::audit::record_command("delete_account", Some(&account_id), serde_json::Value::Null, &result);
+    result?;
 
     // Reload token pool
     let _ = crate::commands::proxy::reload_proxy_accounts(proxy_state).await;
@@ -77,7 +88,14 @@ pub async fn delete_accounts(
         "收到批量删除请求，共 {} 个账号",
         account_ids.len()
     ));
-    modules::account::delete_accounts(&account_ids).map_err(|e| {
+    let result = modules::account::delete_accounts(&account_ids);
+    modules::audit::record_command(
+        "delete_accounts",
+        Some(&account_ids.join(",")),
+        serde_json::Value::Null,
+        &result,
+    );
+    result.map_err(|e| {
         modules::logger::log_error(&format!("批量删除失败: {}", e));
         e
     })?;
@@ -123,7 +141,9 @@ pub async fn switch_account(
         crate::modules::integration::SystemManager::Desktop(app.clone()),
     );
 
-    service.switch_account(&account_id).await?;
+    let result = service.switch_account(&account_id).await;
+    modules::audit::record_command("switch_account", Some(&account_id), serde_json::Value::Null, &result);
+    result?;
 
     // 同步托盘
     crate::modules::tray::update_tray_menus(&app);
@@ -157,7 +177,15 @@ use crate::models::AccountExportResponse;
 
 #[tauri::command]
 pub async fn export_accounts(account_ids: Vec<String>) -> Result<AccountExportResponse, String> {
-    modules::account::export_accounts_by_ids(&account_ids)
+    let result = modules::account::export_accounts_by_ids(&account_ids);
+    // 导出包含 refresh_token，属于敏感操作
+    modules::audit::record_command(
+        "export_accounts",
+        Some(&account_ids.join(",")),
+        serde_json::json!({ "count": account_ids.len() }),
+        &result,
+    );
+    result
 }
 
 /// 内部辅助功能：在添加或导入账号后自动刷新一次额度
@@ -339,11 +367,26 @@ pub async fn save_config(
     proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
     config: AppConfig,
 ) -> Result<(), String> {
-    modules::save_app_config(&config)?;
+    // [NEW] 记录字段级 diff 到审计日志
+    let previous = modules::load_app_config().ok();
+    let result = modules::save_app_config(&config);
+    let diff = previous
+        .map(|prev| modules::audit::config_diff_details(&prev, &config))
+        .unwrap_or(serde_json::Value::Null);
+    modules::audit::record_command("save_config", None, diff, &result);
+    result?;
 
     // 通知托盘配置已更新
     let _ = app.emit("config://updated", ());
 
+    // [NEW] 响应缓存与费用核算为全局配置，与服务是否运行无关
+    crate::proxy::update_response_cache_config(config.proxy.response_cache.clone());
+    crate::proxy::update_cost_config(config.proxy.cost.clone());
+    crate::proxy::update_providers_config(config.proxy.providers.clone());
+    crate::proxy::update_structured_output_config(config.proxy.structured_output.clone());
+    crate::proxy::update_batch_config(config.proxy.batch.clone());
+    crate::proxy::update_files_config(config.proxy.files.clone());
+
     // 热更新正在运行的服务
     let instance_lock = proxy_state.instance.read().await;
     if let Some(instance) = instance_lock.as_ref() {
@@ -466,7 +509,14 @@ pub async fn import_v1_accounts(
     app: tauri::AppHandle,
     proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
 ) -> Result<Vec<Account>, String> {
-    let accounts = modules::migration::import_from_v1().await?;
+    let imported = modules::migration::import_from_v1().await;
+    modules::audit::record_command(
+        "import_v1_accounts",
+        None,
+        serde_json::json!({ "count": imported.as_ref().map(|a| a.len()).unwrap_or(0) }),
+        &imported,
+    );
+    let accounts = imported?;
 
     // 对导入的账号尝试刷新一波
     for mut account in accounts.clone() {
@@ -485,7 +535,14 @@ pub async fn import_from_db(
     proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
 ) -> Result<Account, String> {
     // 同步函数包装为 async
-    let mut account = modules::migration::import_from_db().await?;
+    let imported = modules::migration::import_from_db().await;
+    modules::audit::record_command(
+        "import_from_db",
+        imported.as_ref().ok().map(|a| a.id.as_str()),
+        serde_json::Value::Null,
+        &imported,
+    );
+    let mut account = imported?;
 
     // 既然是从数据库导入（即 IDE 当前账号），自动将其设为 Manager 的当前账号
     let account_id = account.id.clone();
@@ -511,7 +568,15 @@ pub async fn import_custom_db(
     path: String,
 ) -> Result<Account, String> {
     // 调用重构后的自定义导入函数
-    let mut account = modules::migration::import_from_custom_db_path(path).await?;
+    let details = serde_json::json!({ "path": path });
+    let imported = modules::migration::import_from_custom_db_path(path).await;
+    modules::audit::record_command(
+        "import_custom_db",
+        imported.as_ref().ok().map(|a| a.id.as_str()),
+        details,
+        &imported,
+    );
+    let mut account = imported?;
 
     // 自动设为当前账号
     let account_id = account.id.clone();
@@ -611,14 +676,18 @@ pub async fn read_text_file(path: String) -> Result<String, String> {
 /// 清理日志缓存
 #[tauri::command]
 pub async fn clear_log_cache() -> Result<(), String> {
-    modules::logger::clear_logs()
+    let result = modules::logger::clear_logs();
+    modules::audit::record_command("clear_log_cache", None, serde_json::Value::Null, &result);
+    result
 }
 
 /// 清理 Antigravity 应用缓存
 /// 用于解决登录失败、版本验证错误等问题
 #[tauri::command]
 pub async fn clear_antigravity_cache() -> Result<modules::cache::ClearResult, String> {
-    modules::cache::clear_antigravity_cache(None)
+    let result = modules::cache::clear_antigravity_cache(None);
+    modules::audit::record_command("clear_antigravity_cache", None, serde_json::Value::Null, &result);
+    result
 }
 
 /// 获取 Antigravity 缓存路径列表（用于预览）
@@ -798,7 +867,7 @@ pub async fn toggle_proxy_status(
     }
 
     let content =
-        std::fs::read_to_string(&account_path).map_err(|e| format!("读取账号文件失败: {}", e))?;
+        modules::account_crypto::read_account_file(&account_path).map_err(|e| format!("读取账号文件失败: {}", e))?;
 
     let mut account_json: serde_json::Value =
         serde_json::from_str(&content).map_err(|e| format!("解析账号文件失败: {}", e))?;
@@ -821,7 +890,18 @@ pub async fn toggle_proxy_status(
     // 3. 保存到磁盘
     let json_str = serde_json::to_string_pretty(&account_json)
         .map_err(|e| format!("序列化账号数据失败: {}", e))?;
-    std::fs::write(&account_path, json_str).map_err(|e| format!("写入账号文件失败: {}", e))?;
+    let written = modules::account_crypto::write_account_file(&account_path, &json_str)
+        .map_err(|e| format!("写入账号文件失败: {}", e));
+    modules::audit::record_command(
+        "toggle_proxy_status",
+        Some(&account_id),
+        serde_json::json!({
+            "enable": enable,
+            "reason": account_json["proxy_disabled_reason"],
+        }),
+        &written,
+    );
+    written?;
 
     modules::logger::log_info(&format!(
         "账号反代状态已更新: {} ({})",
@@ -899,7 +979,7 @@ pub async fn update_account_label(account_id: String, label: String) -> Result<(
     }
 
     let content =
-        std::fs::read_to_string(&account_path).map_err(|e| format!("读取账号文件失败: {}", e))?;
+        modules::account_crypto::read_account_file(&account_path).map_err(|e| format!("读取账号文件失败: {}", e))?;
 
     let mut account_json: serde_json::Value =
         serde_json::from_str(&content).map_err(|e| format!("解析账号文件失败: {}", e))?;
@@ -914,7 +994,8 @@ pub async fn update_account_label(account_id: String, label: String) -> Result<(
     // 3. 保存到磁盘
     let json_str = serde_json::to_string_pretty(&account_json)
         .map_err(|e| format!("序列化账号数据失败: {}", e))?;
-    std::fs::write(&account_path, json_str).map_err(|e| format!("写入账号文件失败: {}", e))?;
+    modules::account_crypto::write_account_file(&account_path, &json_str)
+        .map_err(|e| format!("写入账号文件失败: {}", e))?;
 
     modules::logger::log_info(&format!(
         "账号标签已更新: {} ({})",
@@ -929,6 +1010,158 @@ pub async fn update_account_label(account_id: String, label: String) -> Result<(
     Ok(())
 }
 
+/// 设置账号分组 (空列表 = 移出所有分组)
+#[tauri::command]
+pub async fn update_account_groups(account_id: String, groups: Vec<String>) -> Result<Account, String> {
+    let details = serde_json::json!({ "groups": groups });
+    let result = modules::account::set_account_groups(&account_id, groups);
+    modules::audit::record_command("update_account_groups", Some(&account_id), details, &result);
+    let account = result?;
+    modules::logger::log_info(&format!(
+        "账号分组已更新: {} -> {:?}",
+        account.email, account.groups
+    ));
+    Ok(account)
+}
+
+// ============================================================================
+// 账号文件静态加密 / 加密导出
+// ============================================================================
+
+/// 获取账号文件加密状态
+#[tauri::command]
+pub async fn get_account_encryption_status() -> Result<modules::account_crypto::AccountEncryptionStatus, String> {
+    modules::account_crypto::status()
+}
+
+/// 用口令解锁已加密的账号文件，并同步到运行中的反代服务
+#[tauri::command]
+pub async fn unlock_account_encryption(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    passphrase: String,
+) -> Result<modules::account_crypto::AccountEncryptionStatus, String> {
+    let result = modules::account_crypto::unlock(&passphrase);
+    modules::audit::record_command("unlock_account_encryption", None, serde_json::Value::Null, &result);
+    let status = result?;
+    let instance_lock = proxy_state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        let _ = instance.token_manager.reload_all_accounts().await;
+    }
+    Ok(status)
+}
+
+/// 解析轮换目标: 口令 / 密钥文件 / 显式关闭加密
+pub fn resolve_rotation_target(
+    new_passphrase: Option<String>,
+    new_key_file: Option<String>,
+    disable: bool,
+) -> Result<Option<modules::account_crypto::KeySource>, String> {
+    use modules::account_crypto::KeySource;
+    match (new_passphrase.filter(|p| !p.is_empty()), new_key_file.filter(|p| !p.trim().is_empty()), disable) {
+        (Some(p), None, false) => Ok(Some(KeySource::Passphrase(p))),
+        (None, Some(path), false) => Ok(Some(KeySource::KeyFile(std::path::PathBuf::from(path.trim())))),
+        (None, None, true) => Ok(None),
+        (None, None, false) => Err("Specify a new passphrase, a new key file, or disable".to_string()),
+        _ => Err("Only one of new passphrase / new key file / disable may be given".to_string()),
+    }
+}
+
+/// 轮换账号文件加密密钥 (也用于首次启用与关闭加密)
+#[tauri::command]
+pub async fn rotate_account_encryption_key(
+    new_passphrase: Option<String>,
+    new_key_file: Option<String>,
+    disable: Option<bool>,
+) -> Result<modules::account_crypto::AccountEncryptionStatus, String> {
+    let target = resolve_rotation_target(new_passphrase, new_key_file, disable.unwrap_or(false))?;
+    let mode = match &target {
+        Some(modules::account_crypto::KeySource::Passphrase(_)) => "passphrase",
+        Some(modules::account_crypto::KeySource::KeyFile(_)) => "key_file",
+        None => "disable",
+    };
+    let result = modules::account_crypto::rotate_key(target);
+    modules::audit::record_command(
+        "rotate_account_encryption_key",
+        None,
+        serde_json::json!({ "mode": mode }),
+        &result,
+    );
+    result?;
+    modules::account_crypto::status()
+}
+
+/// 用口令加密导出内容
+#[tauri::command]
+pub async fn seal_account_export(content: String, passphrase: String) -> Result<String, String> {
+    modules::account_crypto::seal_export(&content, &passphrase)
+}
+
+/// 解开加密的导出文件 (明文内容原样返回)
+#[tauri::command]
+pub async fn open_account_export(content: String, passphrase: String) -> Result<String, String> {
+    modules::account_crypto::open_export(&content, &passphrase)
+}
+
+// ============================================================================
+// 全量备份 / 恢复
+// ============================================================================
+
+/// 生成备份包 (sections 为空表示全部)
+#[tauri::command]
+pub async fn create_backup(
+    sections: Option<Vec<modules::backup::BackupSection>>,
+    passphrase: Option<String>,
+) -> Result<modules::backup::BackupArchive, String> {
+    let details = serde_json::json!({ "sections": sections, "encrypted": passphrase.is_some() });
+    let result = modules::backup::create_backup(&sections.unwrap_or_default(), passphrase.as_deref());
+    modules::audit::record_command("create_backup", None, details, &result);
+    result
+}
+
+/// 从备份包恢复；dry_run 只返回报告
+#[tauri::command]
+pub async fn restore_backup(
+    app: tauri::AppHandle,
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    content: String,
+    sections: Option<Vec<modules::backup::BackupSection>>,
+    accounts: Option<Vec<String>>,
+    dry_run: Option<bool>,
+    passphrase: Option<String>,
+) -> Result<modules::backup::RestoreReport, String> {
+    let details = serde_json::json!({
+        "sections": sections,
+        "accounts": accounts,
+        "dry_run": dry_run.unwrap_or(false),
+    });
+    let options = modules::backup::RestoreOptions {
+        sections,
+        accounts,
+        dry_run: dry_run.unwrap_or(false),
+        passphrase,
+    };
+    let result = modules::backup::restore_backup(&content, &options);
+    modules::audit::record_command("restore_backup", None, details, &result);
+    let report = result?;
+    if report.dry_run {
+        return Ok(report);
+    }
+
+    // 配置被恢复时走 save_config 的热更新流程
+    if report.changed(modules::backup::BackupSection::Config) {
+        let config = modules::load_app_config()?;
+        save_config(app.clone(), proxy_state.clone(), config).await?;
+    }
+    {
+        let instance_lock = proxy_state.instance.read().await;
+        if let Some(instance) = instance_lock.as_ref() {
+            let _ = instance.token_manager.reload_all_accounts().await;
+        }
+    }
+    let _ = app.emit("accounts://refreshed", ());
+    Ok(report)
+}
+
 // ============================================================================
 // HTTP API 设置命令
 // ============================================================================
@@ -944,7 +1177,14 @@ pub async fn get_http_api_settings() -> Result<crate::modules::http_api::HttpApi
 pub async fn save_http_api_settings(
     settings: crate::modules::http_api::HttpApiSettings,
 ) -> Result<(), String> {
-    crate::modules::http_api::save_settings(&settings)
+    let result = crate::modules::http_api::save_settings(&settings);
+    modules::audit::record_command(
+        "save_http_api_settings",
+        None,
+        serde_json::to_value(&settings).unwrap_or_default(),
+        &result,
+    );
+    result
 }
 
 // ============================================================================
@@ -973,6 +1213,119 @@ pub async fn get_token_stats_by_account(hours: i64) -> Result<Vec<AccountTokenSt
     crate::modules::token_stats::get_account_stats(hours)
 }
 
+#[tauri::command]
+pub async fn get_token_stats_by_group(
+    hours: i64,
+) -> Result<Vec<crate::modules::token_stats::GroupTokenStats>, String> {
+    crate::modules::token_stats::get_group_stats(hours)
+}
+
+#[tauri::command]
+pub async fn get_token_stats_by_user_token(
+    hours: i64,
+) -> Result<Vec<crate::modules::token_stats::UserTokenUsageStats>, String> {
+    crate::modules::token_stats::get_user_token_stats(hours)
+}
+
+#[tauri::command]
+pub async fn get_token_stats_cache_hits(
+    hours: i64,
+) -> Result<Vec<crate::modules::token_stats::ModelCacheHitStats>, String> {
+    crate::modules::token_stats::get_cache_hit_stats(hours)
+}
+
+/// 获取 Webhook 投递日志 (按时间倒序)
+#[tauri::command]
+pub async fn get_webhook_deliveries(
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<crate::modules::webhook_db::WebhookDelivery>, String> {
+    crate::modules::webhook_db::list_deliveries(limit.unwrap_or(100), offset.unwrap_or(0))
+}
+
+/// 清空 Webhook 投递日志
+#[tauri::command]
+pub async fn clear_webhook_deliveries() -> Result<usize, String> {
+    let result = crate::modules::webhook_db::clear_deliveries();
+    modules::audit::record_command("clear_webhook_deliveries", None, serde_json::Value::Null, &result);
+    result
+}
+
+/// 向指定端点发送测试通知
+#[tauri::command]
+pub async fn test_webhook(
+    endpoint: crate::modules::webhook::WebhookEndpoint,
+) -> Result<crate::modules::webhook_db::WebhookDelivery, String> {
+    crate::modules::webhook::send_test(endpoint).await
+}
+
+/// 审计日志查询参数 (与 `GET /api/audit` 的 query 参数一致，便于 Web 模式映射)
+#[allow(clippy::too_many_arguments)]
+fn audit_query(
+    since: Option<i64>,
+    until: Option<i64>,
+    actor: Option<String>,
+    action: Option<String>,
+    target: Option<String>,
+    ip: Option<String>,
+    success: Option<bool>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> crate::modules::audit_db::AuditQuery {
+    crate::modules::audit_db::AuditQuery {
+        since,
+        until,
+        actor,
+        action,
+        target,
+        ip,
+        success,
+        limit,
+        offset,
+    }
+}
+
+/// 查询审计日志 (按时间倒序，附总数)
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_audit_logs(
+    since: Option<i64>,
+    until: Option<i64>,
+    actor: Option<String>,
+    action: Option<String>,
+    target: Option<String>,
+    ip: Option<String>,
+    success: Option<bool>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<crate::modules::audit_db::AuditLogPage, String> {
+    let query = audit_query(since, until, actor, action, target, ip, success, limit, offset);
+    crate::modules::audit_db::query_page(&query)
+}
+
+/// 导出审计日志，返回文件内容 (`csv` / `json`)
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn export_audit_logs(
+    format: String,
+    since: Option<i64>,
+    until: Option<i64>,
+    actor: Option<String>,
+    action: Option<String>,
+    target: Option<String>,
+    ip: Option<String>,
+    success: Option<bool>,
+) -> Result<String, String> {
+    let query = audit_query(since, until, actor, action, target, ip, success, None, None);
+    crate::modules::audit_db::export(&query, &format)
+}
+
+/// 校验审计日志哈希链是否完整
+#[tauri::command]
+pub async fn verify_audit_log() -> Result<crate::modules::audit_db::AuditChainStatus, String> {
+    crate::modules::audit_db::verify_chain()
+}
+
 #[tauri::command]
 pub async fn get_token_stats_summary(hours: i64) -> Result<TokenStatsSummary, String> {
     crate::modules::token_stats::get_summary_stats(hours)
@@ -59,13 +59,16 @@ pub async fn start_proxy_service(
     cf_state: State<'_, crate::commands::cloudflared::CloudflaredState>,
     app_handle: tauri::AppHandle,
 ) -> Result<ProxyStatus, String> {
-    internal_start_proxy_service(
+    let details = serde_json::json!({ "port": config.port });
+    let result = internal_start_proxy_service(
         config,
         &state,
         crate::modules::integration::SystemManager::Desktop(app_handle),
         Arc::new(cf_state.inner().clone()),
     )
-    .await
+    .await;
+    crate::modules::audit::record_command("start_proxy_service", None, details, &result);
+    result
 }
 
 struct StartingGuard(Arc<AtomicBool>);
@@ -130,6 +133,13 @@ pub async fn internal_start_proxy_service(
         cloudflared_state.clone(),
     )
     .await?;
+    // 管理服务器可能早已启动，这里同步最新的响应缓存与费用核算配置
+    crate::proxy::update_response_cache_config(config.response_cache.clone());
+    crate::proxy::update_cost_config(config.cost.clone());
+    crate::proxy::update_providers_config(config.providers.clone());
+    crate::proxy::update_structured_output_config(config.structured_output.clone());
+    crate::proxy::update_batch_config(config.batch.clone());
+    crate::proxy::update_files_config(config.files.clone());
 
     // 2. [FIX] 复用管理服务器的 Token 管理器 (单实例，解决热更新同步问题)
     let token_manager = {
@@ -244,7 +254,7 @@ pub async fn ensure_admin_server(
         config.get_bind_address().to_string(),
         config.port,
         token_manager,
-        config.custom_mapping.clone(),
+        crate::proxy::common::routing_rules::ModelRouter::from_config(&config),
         config.request_timeout,
         config.upstream_proxy.clone(),
         config.user_agent_override.clone(),
@@ -274,6 +284,14 @@ pub async fn ensure_admin_server(
     crate::proxy::update_global_system_prompt_config(config.global_system_prompt.clone());
     // [NEW] 初始化全局图像思维模式配置
     crate::proxy::update_image_thinking_mode(config.image_thinking_mode.clone());
+    // [NEW] 初始化全局响应缓存配置
+    crate::proxy::update_response_cache_config(config.response_cache.clone());
+    // [NEW] 初始化全局费用核算配置 (价格表与全局预算)
+    crate::proxy::update_cost_config(config.cost.clone());
+    crate::proxy::update_providers_config(config.providers.clone());
+    crate::proxy::update_structured_output_config(config.structured_output.clone());
+    crate::proxy::update_batch_config(config.batch.clone());
+    crate::proxy::update_files_config(config.files.clone());
 
     Ok(())
 }
@@ -294,6 +312,7 @@ pub async fn stop_proxy_service(state: State<'_, ProxyServiceState>) -> Result<(
         // 已移除 instance.axum_server.stop() 调用，防止杀死 Admin Server
     }
 
+    crate::modules::audit::record_command_success("stop_proxy_service", None, serde_json::Value::Null);
     Ok(())
 }
 
@@ -375,6 +394,11 @@ pub async fn set_proxy_monitor_enabled(
     if let Some(monitor) = monitor_lock.as_ref() {
         monitor.set_enabled(enabled);
     }
+    crate::modules::audit::record_command_success(
+        "set_proxy_monitor_enabled",
+        None,
+        serde_json::json!({ "enabled": enabled }),
+    );
     Ok(())
 }
 
@@ -385,6 +409,7 @@ pub async fn clear_proxy_logs(state: State<'_, ProxyServiceState>) -> Result<(),
     if let Some(monitor) = monitor_lock.as_ref() {
         monitor.clear().await;
     }
+    crate::modules::audit::record_command_success("clear_proxy_logs", None, serde_json::Value::Null);
     Ok(())
 }
 
@@ -460,6 +485,14 @@ pub async fn get_proxy_logs_filtered(
     crate::modules::proxy_db::get_logs_filtered(&filter, errors_only, limit, offset)
 }
 
+/// 按账号分组汇总请求日志
+#[tauri::command]
+pub async fn get_proxy_log_stats_by_group(
+    hours: i64,
+) -> Result<Vec<crate::modules::proxy_db::GroupLogStats>, String> {
+    crate::modules::proxy_db::get_group_log_stats(hours)
+}
+
 /// 生成 API Key
 #[tauri::command]
 pub fn generate_api_key() -> String {
@@ -506,12 +539,141 @@ pub async fn update_model_mapping(
 
     // 2. 无论是否运行，都保存到全局配置持久化
     let mut app_config = crate::modules::config::load_app_config().map_err(|e| e)?;
+    let previous = app_config.clone();
     app_config.proxy.custom_mapping = config.custom_mapping;
-    crate::modules::config::save_app_config(&app_config).map_err(|e| e)?;
+    app_config.proxy.model_routing_rules = config.model_routing_rules;
+    app_config.proxy.model_fallback_chains = config.model_fallback_chains;
+    let result = crate::modules::config::save_app_config(&app_config);
+    crate::modules::audit::record_command(
+        "update_model_mapping",
+        None,
+        crate::modules::audit::config_diff_details(&previous, &app_config),
+        &result,
+    );
+    result?;
 
     Ok(())
 }
 
+/// [NEW] 将配置文件中可热更新的部分应用到运行中的服务 (配置文件监听使用)
+/// 与 `save_config` / `update_model_mapping` / `update_proxy_scheduling_config` 走相同的更新路径
+pub async fn apply_hot_config(
+    state: &ProxyServiceState,
+    config: &crate::models::AppConfig,
+    sections: &[crate::modules::config_watcher::HotSection],
+) {
+    use crate::modules::config_watcher::HotSection;
+
+    // 全局配置与服务是否运行无关
+    for section in sections {
+        match section {
+            HotSection::ThinkingBudget => {
+                crate::proxy::update_thinking_budget_config(config.proxy.thinking_budget.clone())
+            }
+            HotSection::GlobalSystemPrompt => crate::proxy::update_global_system_prompt_config(
+                config.proxy.global_system_prompt.clone(),
+            ),
+            HotSection::ResponseCache => {
+                crate::proxy::update_response_cache_config(config.proxy.response_cache.clone())
+            }
+            HotSection::Cost => crate::proxy::update_cost_config(config.proxy.cost.clone()),
+            HotSection::Providers => {
+                crate::proxy::update_providers_config(config.proxy.providers.clone())
+            }
+            HotSection::StructuredOutput => crate::proxy::update_structured_output_config(
+                config.proxy.structured_output.clone(),
+            ),
+            HotSection::Batch => crate::proxy::update_batch_config(config.proxy.batch.clone()),
+            HotSection::Files => crate::proxy::update_files_config(config.proxy.files.clone()),
+            _ => {}
+        }
+    }
+
+    let mut instance_lock = state.instance.write().await;
+    let axum_server = match instance_lock.as_ref() {
+        Some(instance) => instance.axum_server.clone(),
+        None => match state.admin_server.read().await.as_ref() {
+            // 反代已停止时仍更新管理服务器共享的状态，下次启动即生效
+            Some(admin) => admin.axum_server.clone(),
+            None => return,
+        },
+    };
+
+    // 保持实例中的配置快照与文件一致
+    if let Some(instance) = instance_lock.as_mut() {
+        let running = &mut instance.config;
+        for section in sections {
+            match section {
+                HotSection::ModelMapping => {
+                    running.custom_mapping = config.proxy.custom_mapping.clone();
+                    running.model_routing_rules = config.proxy.model_routing_rules.clone();
+                    running.model_fallback_chains = config.proxy.model_fallback_chains.clone();
+                }
+                HotSection::Scheduling => running.scheduling = config.proxy.scheduling.clone(),
+                HotSection::ProxyPool => running.proxy_pool = config.proxy.proxy_pool.clone(),
+                HotSection::ThinkingBudget => {
+                    running.thinking_budget = config.proxy.thinking_budget.clone()
+                }
+                HotSection::GlobalSystemPrompt => {
+                    running.global_system_prompt = config.proxy.global_system_prompt.clone()
+                }
+                HotSection::ResponseCache => {
+                    running.response_cache = config.proxy.response_cache.clone()
+                }
+                HotSection::Cost => running.cost = config.proxy.cost.clone(),
+                HotSection::Providers => running.providers = config.proxy.providers.clone(),
+                HotSection::StructuredOutput => {
+                    running.structured_output = config.proxy.structured_output.clone()
+                }
+                HotSection::Batch => running.batch = config.proxy.batch.clone(),
+                HotSection::Files => running.files = config.proxy.files.clone(),
+                HotSection::SecurityMonitor => {
+                    running.security_monitor = config.proxy.security_monitor.clone()
+                }
+                HotSection::Zai => running.zai = config.proxy.zai.clone(),
+                HotSection::CircuitBreaker => {}
+            }
+        }
+    }
+
+    for section in sections {
+        match section {
+            HotSection::ModelMapping => axum_server.update_mapping(&config.proxy).await,
+            HotSection::Scheduling => {
+                axum_server
+                    .token_manager
+                    .update_sticky_config(config.proxy.scheduling.clone())
+                    .await
+            }
+            HotSection::CircuitBreaker => {
+                axum_server
+                    .token_manager
+                    .update_circuit_breaker_config(config.circuit_breaker.clone())
+                    .await
+            }
+            HotSection::ProxyPool => {
+                axum_server
+                    .update_proxy_pool(config.proxy.proxy_pool.clone())
+                    .await
+            }
+            HotSection::SecurityMonitor => {
+                axum_server
+                    .update_security_monitor(config.proxy.security_monitor.clone())
+                    .await
+            }
+            HotSection::Zai => axum_server.update_zai(&config.proxy).await,
+            HotSection::ThinkingBudget
+            | HotSection::GlobalSystemPrompt
+            | HotSection::ResponseCache
+            | HotSection::Cost
+            | HotSection::Providers
+            | HotSection::StructuredOutput
+            | HotSection::Batch
+            | HotSection::Files => {}
+        }
+    }
+}
+
 fn join_base_url(base: &str, path: &str) -> String {
     let base = base.trim_end_matches('/');
     let path = if path.starts_with('/') {
@@ -667,6 +829,11 @@ pub async fn clear_proxy_session_bindings(
     let instance_lock = state.instance.read().await;
     if let Some(instance) = instance_lock.as_ref() {
         instance.token_manager.clear_all_sessions();
+        crate::modules::audit::record_command_success(
+            "clear_proxy_session_bindings",
+            None,
+            serde_json::Value::Null,
+        );
         Ok(())
     } else {
         Err("服务未运行".to_string())
@@ -697,8 +864,15 @@ pub async fn set_preferred_account(
         let mut app_config = crate::modules::config::load_app_config()
             .map_err(|e| format!("加载配置失败: {}", e))?;
         app_config.proxy.preferred_account_id = cleaned_id.clone();
-        crate::modules::config::save_app_config(&app_config)
-            .map_err(|e| format!("保存配置失败: {}", e))?;
+        let result = crate::modules::config::save_app_config(&app_config)
+            .map_err(|e| format!("保存配置失败: {}", e));
+        crate::modules::audit::record_command(
+            "set_preferred_account",
+            cleaned_id.as_deref(),
+            serde_json::Value::Null,
+            &result,
+        );
+        result?;
 
         if let Some(ref id) = cleaned_id {
             tracing::info!(
@@ -736,7 +910,13 @@ pub async fn clear_proxy_rate_limit(
 ) -> Result<bool, String> {
     let instance_lock = state.instance.read().await;
     if let Some(instance) = instance_lock.as_ref() {
-        Ok(instance.token_manager.clear_rate_limit(&account_id))
+        let cleared = instance.token_manager.clear_rate_limit(&account_id);
+        crate::modules::audit::record_command_success(
+            "clear_proxy_rate_limit",
+            Some(&account_id),
+            serde_json::json!({ "cleared": cleared }),
+        );
+        Ok(cleared)
     } else {
         Err("服务未运行".to_string())
     }
@@ -750,12 +930,78 @@ pub async fn clear_all_proxy_rate_limits(
     let instance_lock = state.instance.read().await;
     if let Some(instance) = instance_lock.as_ref() {
         instance.token_manager.clear_all_rate_limits();
+        crate::modules::audit::record_command_success(
+            "clear_all_proxy_rate_limits",
+            None,
+            serde_json::Value::Null,
+        );
         Ok(())
     } else {
         Err("服务未运行".to_string())
     }
 }
 
+/// 获取响应缓存占用概览
+#[tauri::command]
+pub async fn get_response_cache_stats(
+) -> Result<crate::modules::response_cache_db::ResponseCacheStats, String> {
+    tokio::task::spawn_blocking(crate::modules::response_cache_db::get_stats)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 清空响应缓存，返回删除的条目数
+#[tauri::command]
+pub async fn clear_response_cache() -> Result<usize, String> {
+    let result = tokio::task::spawn_blocking(crate::modules::response_cache_db::clear)
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+    crate::modules::audit::record_command("clear_response_cache", None, serde_json::Value::Null, &result);
+    result
+}
+
+/// 批处理队列概览 (统计 + 最近 50 个批次)
+#[tauri::command]
+pub async fn get_batch_overview() -> Result<crate::modules::batch_db::BatchOverview, String> {
+    tokio::task::spawn_blocking(|| crate::modules::batch_db::get_overview(50))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 取消批次：排队中的请求立即取消，运行中的请求完成后结束
+#[tauri::command]
+pub async fn cancel_batch(batch_id: String) -> Result<(), String> {
+    let id = batch_id.clone();
+    let result = tokio::task::spawn_blocking(move || crate::modules::batch_db::cancel_batch(&id))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r)
+        .and_then(|b| b.map(|_| ()).ok_or_else(|| format!("Batch '{}' not found", batch_id)));
+    crate::modules::audit::record_command(
+        "cancel_batch",
+        Some(&batch_id),
+        serde_json::Value::Null,
+        &result,
+    );
+    result
+}
+
+/// 获取 Files API 本地存储的用量
+#[tauri::command]
+pub async fn get_file_store_stats() -> Result<crate::modules::file_db::FileStoreStats, String> {
+    tokio::task::spawn_blocking(crate::modules::file_db::get_stats)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 获取自定义上游的运行状态 (请求数、错误数、各 Key 的限流冷却)
+#[tauri::command]
+pub async fn get_provider_statuses(
+) -> Result<Vec<crate::proxy::providers::registry::ProviderStatus>, String> {
+    Ok(crate::proxy::providers::registry::get_provider_statuses())
+}
+
 /// 触发所有代理的健康检查，并返回更新后的配置
 #[tauri::command]
 pub async fn check_proxy_health(
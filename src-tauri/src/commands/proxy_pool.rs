@@ -10,11 +10,15 @@ pub async fn bind_account_proxy(
     proxy_id: String,
 ) -> Result<(), String> {
     let instance_lock = state.instance.read().await;
-    if let Some(instance) = instance_lock.as_ref() {
+    let details = serde_json::json!({ "proxy_id": proxy_id });
+    let target = account_id.clone();
+    let result = if let Some(instance) = instance_lock.as_ref() {
         instance.axum_server.proxy_pool_manager.bind_account_to_proxy(account_id, proxy_id).await
     } else {
         Err("Service not running".to_string())
-    }
+    };
+    crate::modules::audit::record_command("bind_account_proxy", Some(&target), details, &result);
+    result
 }
 
 /// Unbind an account from its proxy
@@ -25,6 +29,11 @@ pub async fn unbind_account_proxy(
 ) -> Result<(), String> {
     let instance_lock = state.instance.read().await;
     if let Some(instance) = instance_lock.as_ref() {
+        crate::modules::audit::record_command_success(
+            "unbind_account_proxy",
+            Some(&account_id),
+            serde_json::Value::Null,
+        );
         instance.axum_server.proxy_pool_manager.unbind_account_proxy(account_id).await;
         Ok(())
     } else {
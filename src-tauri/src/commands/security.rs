@@ -1,6 +1,6 @@
 use tauri::State;
 use serde::{Deserialize, Serialize};
-use crate::modules::security_db;
+use crate::modules::{audit, security_db};
 
 // ==================== 请求/响应结构 ====================
 
@@ -81,7 +81,9 @@ pub async fn get_ip_stats() -> Result<IpStatsResponse, String> {
 /// 清空 IP 访问日志
 #[tauri::command]
 pub async fn clear_ip_access_logs() -> Result<(), String> {
-    security_db::clear_ip_access_logs()
+    let result = security_db::clear_ip_access_logs();
+    audit::record_command("clear_ip_access_logs", None, serde_json::Value::Null, &result);
+    result
 }
 
 // ==================== IP 黑名单命令 ====================
@@ -102,12 +104,19 @@ pub async fn add_ip_to_blacklist(
         return Err("Invalid IP pattern. Use IP address or CIDR notation (e.g., 192.168.1.0/24)".to_string());
     }
     
-    security_db::add_to_blacklist(
+    let result = security_db::add_to_blacklist(
         &request.ip_pattern,
         request.reason.as_deref(),
         request.expires_at,
         "manual",
-    )?;
+    );
+    audit::record_command(
+        "add_ip_to_blacklist",
+        Some(&request.ip_pattern),
+        serde_json::json!({ "reason": request.reason, "expires_at": request.expires_at }),
+        &result,
+    );
+    result?;
     Ok(())
 }
 
@@ -118,11 +127,13 @@ pub async fn remove_ip_from_blacklist(ip_pattern: String) -> Result<(), String>
     let entries = security_db::get_blacklist()?;
     let entry = entries.iter().find(|e| e.ip_pattern == ip_pattern);
     
-    if let Some(entry) = entry {
+    let result = if let Some(entry) = entry {
         security_db::remove_from_blacklist(&entry.id)
     } else {
         Err(format!("IP pattern {} not found in blacklist", ip_pattern))
-    }
+    };
+    audit::record_command("remove_ip_from_blacklist", Some(&ip_pattern), serde_json::Value::Null, &result);
+    result
 }
 
 /// 清空黑名单
@@ -130,9 +141,11 @@ pub async fn remove_ip_from_blacklist(ip_pattern: String) -> Result<(), String>
 pub async fn clear_ip_blacklist() -> Result<(), String> {
     // 获取所有黑名单条目并逐个删除
     let entries = security_db::get_blacklist()?;
+    let count = entries.len();
     for entry in entries {
         security_db::remove_from_blacklist(&entry.ip_pattern)?;
     }
+    audit::record_command_success("clear_ip_blacklist", None, serde_json::json!({ "count": count }));
     Ok(())
 }
 
@@ -160,10 +173,17 @@ pub async fn add_ip_to_whitelist(
         return Err("Invalid IP pattern. Use IP address or CIDR notation (e.g., 192.168.1.0/24)".to_string());
     }
     
-    security_db::add_to_whitelist(
+    let result = security_db::add_to_whitelist(
         &request.ip_pattern,
         request.description.as_deref(),
-    )?;
+    );
+    audit::record_command(
+        "add_ip_to_whitelist",
+        Some(&request.ip_pattern),
+        serde_json::json!({ "description": request.description }),
+        &result,
+    );
+    result?;
     Ok(())
 }
 
@@ -174,11 +194,13 @@ pub async fn remove_ip_from_whitelist(ip_pattern: String) -> Result<(), String>
     let entries = security_db::get_whitelist()?;
     let entry = entries.iter().find(|e| e.ip_pattern == ip_pattern);
     
-    if let Some(entry) = entry {
+    let result = if let Some(entry) = entry {
         security_db::remove_from_whitelist(&entry.id)
     } else {
         Err(format!("IP pattern {} not found in whitelist", ip_pattern))
-    }
+    };
+    audit::record_command("remove_ip_from_whitelist", Some(&ip_pattern), serde_json::Value::Null, &result);
+    result
 }
 
 /// 清空白名单
@@ -186,9 +208,11 @@ pub async fn remove_ip_from_whitelist(ip_pattern: String) -> Result<(), String>
 pub async fn clear_ip_whitelist() -> Result<(), String> {
     // 获取所有白名单条目并逐个删除
     let entries = security_db::get_whitelist()?;
+    let count = entries.len();
     for entry in entries {
         security_db::remove_from_whitelist(&entry.ip_pattern)?;
     }
+    audit::record_command_success("clear_ip_whitelist", None, serde_json::json!({ "count": count }));
     Ok(())
 }
 
@@ -226,9 +250,17 @@ pub async fn update_security_config(
     // 1. 同步保存到配置文件
     let mut app_config = crate::modules::config::load_app_config()
         .map_err(|e| format!("Failed to load config: {}", e))?;
+    let previous = app_config.clone();
     app_config.proxy.security_monitor = config.clone();
-    crate::modules::config::save_app_config(&app_config)
-        .map_err(|e| format!("Failed to save config: {}", e))?;
+    let result = crate::modules::config::save_app_config(&app_config)
+        .map_err(|e| format!("Failed to save config: {}", e));
+    audit::record_command(
+        "update_security_config",
+        None,
+        audit::config_diff_details(&previous, &app_config),
+        &result,
+    );
+    result?;
 
     // 2. 更新内存中的配置 (如果服务正在运行)
     {
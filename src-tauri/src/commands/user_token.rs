@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
-use crate::modules::user_token_db::{self, UserToken, TokenIpBinding};
+use crate::modules::audit;
+use crate::modules::user_token_db::{self, TokenIpBinding, TokenLimits, TokenQuotaUsage, UserToken};
+use crate::proxy::config::BudgetAction;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateTokenRequest {
@@ -10,6 +12,22 @@ pub struct CreateTokenRequest {
     pub curfew_start: Option<String>,
     pub curfew_end: Option<String>,
     pub custom_expires_at: Option<i64>,  // 自定义过期时间戳 (秒)
+    #[serde(default)]
+    pub rpm_limit: i32,                  // 每分钟请求数上限, 0 = unlimited
+    #[serde(default)]
+    pub daily_token_limit: i64,          // 每日 Token 上限, 0 = unlimited
+    #[serde(default)]
+    pub monthly_token_limit: i64,        // 每月 Token 上限, 0 = unlimited
+    #[serde(default)]
+    pub allowed_models: Vec<String>,     // 模型白名单, 空 = 不限制
+    #[serde(default)]
+    pub allowed_groups: Vec<String>,     // 账号分组, 空 = 整个账号池
+    #[serde(default)]
+    pub daily_budget_usd: f64,           // 每日费用预算 (美元), 0 = unlimited
+    #[serde(default)]
+    pub monthly_budget_usd: f64,         // 每月费用预算 (美元), 0 = unlimited
+    #[serde(default)]
+    pub budget_action: BudgetAction,     // 超出预算: warn / block
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,6 +38,17 @@ pub struct UpdateTokenRequest {
     pub max_ips: Option<i32>,
     pub curfew_start: Option<Option<String>>,
     pub curfew_end: Option<Option<String>>,
+    pub rpm_limit: Option<i32>,
+    pub daily_token_limit: Option<i64>,
+    pub monthly_token_limit: Option<i64>,
+    pub allowed_models: Option<Vec<String>>,
+    pub allowed_groups: Option<Vec<String>>,
+    #[serde(default)]
+    pub daily_budget_usd: Option<f64>,
+    #[serde(default)]
+    pub monthly_budget_usd: Option<f64>,
+    #[serde(default)]
+    pub budget_action: Option<BudgetAction>,
 }
 
 // 命令实现
@@ -33,7 +62,8 @@ pub async fn list_user_tokens() -> Result<Vec<UserToken>, String> {
 /// 创建新令牌
 #[tauri::command]
 pub async fn create_user_token(request: CreateTokenRequest) -> Result<UserToken, String> {
-    user_token_db::create_token(
+    let details = serde_json::to_value(&request).unwrap_or_default();
+    let result = user_token_db::create_token(
         request.username,
         request.expires_type,
         request.description,
@@ -41,33 +71,96 @@ pub async fn create_user_token(request: CreateTokenRequest) -> Result<UserToken,
         request.curfew_start,
         request.curfew_end,
         request.custom_expires_at,
-    )
+        TokenLimits {
+            rpm_limit: request.rpm_limit,
+            daily_token_limit: request.daily_token_limit,
+            monthly_token_limit: request.monthly_token_limit,
+            allowed_models: request.allowed_models,
+            allowed_groups: request.allowed_groups,
+            daily_budget_usd: request.daily_budget_usd,
+            monthly_budget_usd: request.monthly_budget_usd,
+            budget_action: request.budget_action,
+        },
+    );
+    audit::record_command(
+        "create_user_token",
+        result.as_ref().ok().map(|t| t.id.as_str()),
+        details,
+        &result,
+    );
+    result
 }
 
 /// 更新令牌
 #[tauri::command]
 pub async fn update_user_token(id: String, request: UpdateTokenRequest) -> Result<(), String> {
+    let details = serde_json::to_value(&request).unwrap_or_default();
+    let result = apply_token_update(&id, request);
+    audit::record_command("update_user_token", Some(&id), details, &result);
+    result
+}
+
+fn apply_token_update(id: &str, request: UpdateTokenRequest) -> Result<(), String> {
     user_token_db::update_token(
-        &id,
+        id,
         request.username,
         request.description,
         request.enabled,
         request.max_ips,
         request.curfew_start,
         request.curfew_end,
-    )
+    )?;
+
+    if request.rpm_limit.is_some()
+        || request.daily_token_limit.is_some()
+        || request.monthly_token_limit.is_some()
+        || request.allowed_models.is_some()
+        || request.allowed_groups.is_some()
+    {
+        user_token_db::update_token_limits(
+            id,
+            request.rpm_limit,
+            request.daily_token_limit,
+            request.monthly_token_limit,
+            request.allowed_models,
+            request.allowed_groups,
+        )?;
+    }
+
+    if request.daily_budget_usd.is_some()
+        || request.monthly_budget_usd.is_some()
+        || request.budget_action.is_some()
+    {
+        user_token_db::update_token_budget(
+            id,
+            request.daily_budget_usd,
+            request.monthly_budget_usd,
+            request.budget_action,
+        )?;
+    }
+
+    Ok(())
 }
 
 /// 删除令牌
 #[tauri::command]
 pub async fn delete_user_token(id: String) -> Result<(), String> {
-    user_token_db::delete_token(&id)
+    let result = user_token_db::delete_token(&id);
+    audit::record_command("delete_user_token", Some(&id), serde_json::Value::Null, &result);
+    result
 }
 
 /// 续期令牌
 #[tauri::command]
 pub async fn renew_user_token(id: String, expires_type: String) -> Result<(), String> {
-    user_token_db::renew_token(&id, &expires_type)
+    let result = user_token_db::renew_token(&id, &expires_type);
+    audit::record_command(
+        "renew_user_token",
+        Some(&id),
+        serde_json::json!({ "expires_type": expires_type }),
+        &result,
+    );
+    result
 }
 
 /// 获取令牌 IP 绑定
@@ -76,6 +169,20 @@ pub async fn get_token_ip_bindings(token_id: String) -> Result<Vec<TokenIpBindin
     user_token_db::get_token_ips(&token_id)
 }
 
+/// 获取令牌当前周期的配额使用情况
+#[tauri::command]
+pub async fn get_user_token_quota_usage(token_id: String) -> Result<TokenQuotaUsage, String> {
+    user_token_db::get_token_quota_usage(&token_id)?.ok_or_else(|| "Token not found".to_string())
+}
+
+/// 重置令牌的配额计数器
+#[tauri::command]
+pub async fn reset_user_token_quota_usage(token_id: String) -> Result<(), String> {
+    let result = user_token_db::reset_token_quota_usage(&token_id);
+    audit::record_command("reset_user_token_quota_usage", Some(&token_id), serde_json::Value::Null, &result);
+    result
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UserTokenStats {
     pub total_tokens: usize,
@@ -3,6 +3,7 @@ mod modules;
 mod commands;
 mod utils;
 mod proxy;  // Proxy service module
+mod cli;    // Headless CLI subcommands
 pub mod error;
 pub mod constants;
 
@@ -109,7 +110,22 @@ fn greet(name: &str) -> String {
 pub fn run() {
     // Check for headless mode
     let args: Vec<String> = std::env::args().collect();
-    let is_headless = args.iter().any(|arg| arg == "--headless");
+
+    // [NEW] CLI 子命令 (accounts / quota / proxy / config / user-token / logs)
+    // 在初始化日志之前处理，避免控制台日志混入 JSON / 表格输出
+    let cli_invocation = match cli::parse_args(args.get(1..).unwrap_or(&[])) {
+        Ok(invocation) => invocation,
+        Err(e) => {
+            eprintln!("Error: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    let is_proxy_start = cli_invocation.as_ref().map(|i| i.is_proxy_start()).unwrap_or(false);
+    if let Some(invocation) = cli_invocation.filter(|i| !i.is_proxy_start()) {
+        std::process::exit(cli::run(invocation));
+    }
+
+    let is_headless = is_proxy_start || args.iter().any(|arg| arg == "--headless");
 
     // Increase file descriptor limit (macOS only)
     #[cfg(target_os = "macos")]
@@ -136,6 +152,51 @@ pub fn run() {
         error!("Failed to initialize user token database: {}", e);
     }
 
+    // Initialize rate limit state database
+    if let Err(e) = modules::rate_limit_db::init_db() {
+        error!("Failed to initialize rate limit database: {}", e);
+    }
+
+    // Initialize session state database (signature cache + sticky sessions)
+    if let Err(e) = modules::session_state_db::init_db() {
+        error!("Failed to initialize session state database: {}", e);
+    }
+
+    // Initialize response cache database
+    if let Err(e) = modules::response_cache_db::init_db() {
+        error!("Failed to initialize response cache database: {}", e);
+    }
+
+    // Initialize webhook delivery log database
+    if let Err(e) = modules::webhook_db::init_db() {
+        error!("Failed to initialize webhook database: {}", e);
+    }
+
+    // Initialize audit log database
+    if let Err(e) = modules::audit_db::init_db() {
+        error!("Failed to initialize audit database: {}", e);
+    }
+
+    // Initialize admin users / login sessions database
+    if let Err(e) = modules::admin_user_db::init_db() {
+        error!("Failed to initialize admin user database: {}", e);
+    }
+
+    // Initialize batch queue database
+    if let Err(e) = modules::batch_db::init_db() {
+        error!("Failed to initialize batch database: {}", e);
+    }
+
+    // Initialize Files API store
+    if let Err(e) = modules::file_db::init_db() {
+        error!("Failed to initialize file store: {}", e);
+    }
+
+    // [NEW] 账号文件静态加密: 解锁密钥并透明迁移明文账号文件
+    if let Err(e) = modules::account_crypto::init() {
+        error!("Failed to initialize account file encryption: {}", e);
+    }
+
     if is_headless {
         info!("Starting in HEADLESS mode...");
 
@@ -273,6 +334,12 @@ pub fn run() {
                     // Start smart scheduler
                     modules::scheduler::start_scheduler(None, proxy_state.clone());
                     info!("Smart scheduler started in headless mode.");
+
+                    // [NEW] 用户令牌过期 Webhook 通知
+                    modules::webhook::start_token_expiry_watcher();
+
+                    // [NEW] 监听 gui_config.json，校验后热更新可在线生效的配置
+                    modules::config_watcher::start_config_watcher(proxy_state.clone());
                 }
                 Err(e) => {
                     error!("Failed to load config for headless mode: {}", e);
@@ -391,6 +458,9 @@ pub fn run() {
             let scheduler_state = app.handle().state::<commands::proxy::ProxyServiceState>();
             modules::scheduler::start_scheduler(Some(app.handle().clone()), scheduler_state.inner().clone());
 
+            // [NEW] 用户令牌过期 Webhook 通知
+            modules::webhook::start_token_expiry_watcher();
+
             // [PHASE 1] 已整合至 Axum 端口 (8045)，不再单独启动 19527 端口
             info!("Management API integrated into main proxy server (port 8045)");
 
@@ -488,6 +558,7 @@ pub fn run() {
             commands::proxy::export_proxy_logs_json,
             commands::proxy::get_proxy_logs_count_filtered,
             commands::proxy::get_proxy_logs_filtered,
+            commands::proxy::get_proxy_log_stats_by_group,
             commands::proxy::set_proxy_monitor_enabled,
             commands::proxy::clear_proxy_logs,
             commands::proxy::generate_api_key,
@@ -503,6 +574,12 @@ pub fn run() {
             commands::proxy::get_preferred_account,
             commands::proxy::clear_proxy_rate_limit,
             commands::proxy::clear_all_proxy_rate_limits,
+            commands::proxy::get_response_cache_stats,
+            commands::proxy::get_provider_statuses,
+            commands::proxy::clear_response_cache,
+            commands::proxy::get_batch_overview,
+            commands::proxy::cancel_batch,
+            commands::proxy::get_file_store_stats,
             commands::proxy::check_proxy_health,
             // Proxy Pool Binding commands
             commands::proxy_pool::bind_account_proxy,
@@ -516,6 +593,14 @@ pub fn run() {
             commands::warm_up_all_accounts,
             commands::warm_up_account,
             commands::update_account_label,
+            commands::update_account_groups,
+            commands::get_account_encryption_status,
+            commands::unlock_account_encryption,
+            commands::rotate_account_encryption_key,
+            commands::seal_account_export,
+            commands::open_account_export,
+            commands::create_backup,
+            commands::restore_backup,
             // HTTP API settings commands
             commands::get_http_api_settings,
             commands::save_http_api_settings,
@@ -524,6 +609,15 @@ pub fn run() {
             commands::get_token_stats_daily,
             commands::get_token_stats_weekly,
             commands::get_token_stats_by_account,
+            commands::get_token_stats_by_group,
+            commands::get_token_stats_by_user_token,
+            commands::get_token_stats_cache_hits,
+            commands::get_webhook_deliveries,
+            commands::clear_webhook_deliveries,
+            commands::test_webhook,
+            commands::get_audit_logs,
+            commands::export_audit_logs,
+            commands::verify_audit_log,
             commands::get_token_stats_summary,
             commands::get_token_stats_by_model,
             commands::get_token_stats_model_trend_hourly,
@@ -580,6 +674,15 @@ pub fn run() {
             commands::user_token::renew_user_token,
             commands::user_token::get_token_ip_bindings,
             commands::user_token::get_user_token_summary,
+            commands::user_token::get_user_token_quota_usage,
+            commands::user_token::reset_user_token_quota_usage,
+            // Admin user commands
+            commands::admin_user::list_admin_users,
+            commands::admin_user::create_admin_user,
+            commands::admin_user::update_admin_user,
+            commands::admin_user::delete_admin_user,
+            commands::admin_user::list_admin_sessions,
+            commands::admin_user::revoke_admin_session,
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
@@ -57,6 +57,9 @@ pub struct Account {
     /// 用户自定义标签
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub custom_label: Option<String>,
+    /// 账号分组 (团队)，绑定了分组的 User Token 只会从对应分组中选号
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
 }
 
 impl Account {
@@ -85,6 +88,7 @@ impl Account {
             proxy_id: None,
             proxy_bound_at: None,
             custom_label: None,
+            groups: Vec::new(),
         }
     }
 
@@ -161,6 +165,7 @@ pub struct DeviceProfileVersion {
 /// 导出账号项（用于备份/迁移）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountExportItem {
+    #[serde(default)]
     pub email: String,
     pub refresh_token: String,
 }
@@ -2,7 +2,7 @@ use serde::Serialize;
 use serde_json;
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 use std::collections::HashSet;
 
@@ -511,7 +511,7 @@ fn rebuild_index_from_accounts_in_dir(data_dir: &PathBuf) -> Result<AccountIndex
 
 /// Load account from a specific path (internal helper)
 fn load_account_at_path(account_path: &PathBuf) -> Result<Account, String> {
-    let content = fs::read_to_string(account_path)
+    let content = crate::modules::account_crypto::read_account_file(account_path)
         .map_err(|e| format!("failed_to_read_account_data: {}", e))?;
     serde_json::from_str(&content).map_err(|e| format!("failed_to_parse_account_data: {}", e))
 }
@@ -657,7 +657,8 @@ pub fn save_account(account: &Account) -> Result<(), String> {
     let content = serde_json::to_string_pretty(account)
         .map_err(|e| format!("failed_to_serialize_account_data: {}", e))?;
 
-    fs::write(&account_path, content).map_err(|e| format!("failed_to_save_account_data: {}", e))
+    crate::modules::account_crypto::write_account_file(&account_path, &content)
+        .map_err(|e| format!("failed_to_save_account_data: {}", e))
 }
 
 /// List all accounts
@@ -1190,6 +1191,7 @@ pub fn update_account_quota(account_id: &str, quota: QuotaData) -> Result<(), St
     account.update_quota(quota);
 
     // --- Quota protection logic start ---
+    let mut newly_protected: Vec<(String, i32, i32)> = Vec::new();
     if let Ok(config) = crate::modules::config::load_app_config() {
         if config.quota_protection.enabled {
             if let Some(ref q) = account.quota {
@@ -1218,6 +1220,7 @@ pub fn update_account_quota(account_id: &str, quota: QuotaData) -> Result<(), St
                                 account.email, std_id, min_pct, threshold
                             ));
                             account.protected_models.insert(std_id.clone());
+                            newly_protected.push((std_id.clone(), min_pct, threshold));
                         }
                     } else {
                         if account.protected_models.contains(std_id) {
@@ -1253,6 +1256,25 @@ pub fn update_account_quota(account_id: &str, quota: QuotaData) -> Result<(), St
     // Save account first
     save_account(&account)?;
 
+    // [NEW] Webhook 告警
+    for (model, percentage, threshold) in newly_protected {
+        crate::modules::webhook::notify(
+            crate::modules::webhook::WebhookEvent::QuotaProtectionTriggered,
+            format!("{}/{}", account.email, model),
+            format!(
+                "Account {} is protected for {} (quota {}% <= {}%)",
+                account.email, model, percentage, threshold
+            ),
+            serde_json::json!({
+                "account_id": account.id,
+                "email": account.email,
+                "model": model,
+                "percentage": percentage,
+                "threshold": threshold,
+            }),
+        );
+    }
+
     // [FIX] 同时更新索引文件中的摘要信息，确保列表页图标即时刷新
     {
         let _lock = ACCOUNT_INDEX_LOCK
@@ -1309,6 +1331,101 @@ pub fn toggle_proxy_status(
     Ok(())
 }
 
+/// 清理分组名列表 (去除空白项与重复项，保持原顺序)
+pub fn normalize_groups(groups: Vec<String>) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for g in groups {
+        let g = g.trim().to_string();
+        if !g.is_empty() && !result.contains(&g) {
+            result.push(g);
+        }
+    }
+    result
+}
+
+/// 设置账号分组 (空列表 = 移出所有分组)
+pub fn set_account_groups(account_id: &str, groups: Vec<String>) -> Result<Account, String> {
+    let _lock = ACCOUNT_INDEX_LOCK
+        .lock()
+        .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
+
+    let mut account = load_account(account_id)?;
+    account.groups = normalize_groups(groups);
+    save_account(&account)?;
+
+    // 同步到运行中的反代号池
+    crate::proxy::server::trigger_account_reload(account_id);
+
+    Ok(account)
+}
+
+/// 邮箱 -> 所属分组 (用于按分组汇总统计与日志)
+pub fn account_groups_by_email() -> Result<std::collections::HashMap<String, Vec<String>>, String> {
+    Ok(list_accounts()?
+        .into_iter()
+        .map(|a| (a.email, a.groups))
+        .collect())
+}
+
+/// 按邮箱 / ID 查找 `data_dir` 中的账号，返回本地 ID (用于备份恢复的冲突判断)
+pub fn find_local_account_id(data_dir: &Path, id: &str, email: &str) -> Result<Option<String>, String> {
+    Ok(load_account_index_in_dir(&data_dir.to_path_buf())?
+        .accounts
+        .into_iter()
+        .find(|s| s.email == email || s.id == id)
+        .map(|s| s.id))
+}
+
+/// 恢复备份中的完整账号快照 (token、设备指纹、分组等全部字段) 到 `data_dir`
+///
+/// 同邮箱账号已存在时覆盖本地账号并沿用本地 ID。返回 (最终 ID, 是否新增)。
+pub fn restore_account_snapshot(data_dir: &Path, mut account: Account) -> Result<(String, bool), String> {
+    let data_dir = data_dir.to_path_buf();
+    let _lock = ACCOUNT_INDEX_LOCK
+        .lock()
+        .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
+    let mut index = load_account_index_in_dir(&data_dir)?;
+
+    let existing = index
+        .accounts
+        .iter()
+        .position(|s| s.email == account.email || s.id == account.id);
+    if let Some(i) = existing {
+        account.id = index.accounts[i].id.clone();
+    }
+    let accounts_dir = data_dir.join(ACCOUNTS_DIR);
+    fs::create_dir_all(&accounts_dir).map_err(|e| format!("failed_to_create_accounts_dir: {}", e))?;
+    let content = serde_json::to_string_pretty(&account)
+        .map_err(|e| format!("failed_to_serialize_account_data: {}", e))?;
+    crate::modules::account_crypto::write_account_file(
+        &accounts_dir.join(format!("{}.json", account.id)),
+        &content,
+    )
+    .map_err(|e| format!("failed_to_save_account_data: {}", e))?;
+
+    let summary = AccountSummary {
+        id: account.id.clone(),
+        email: account.email.clone(),
+        name: account.name.clone(),
+        disabled: account.disabled,
+        proxy_disabled: account.proxy_disabled,
+        protected_models: account.protected_models.clone(),
+        created_at: account.created_at,
+        last_used: account.last_used,
+    };
+    match existing {
+        Some(i) => index.accounts[i] = summary,
+        None => index.accounts.push(summary),
+    }
+    if index.current_account_id.is_none() {
+        index.current_account_id = Some(account.id.clone());
+    }
+    save_account_index_in_dir(&data_dir, &index)?;
+
+    crate::proxy::server::trigger_account_reload(&account.id);
+    Ok((account.id, existing.is_none()))
+}
+
 /// Export accounts by IDs (for backup/migration)
 pub fn export_accounts_by_ids(account_ids: &[String]) -> Result<crate::models::AccountExportResponse, String> {
     use crate::models::{AccountExportItem, AccountExportResponse};
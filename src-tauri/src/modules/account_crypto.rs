@@ -0,0 +1,702 @@
+//! 账号文件静态加密 (at-rest encryption)
+//!
+//! `accounts/<id>.json` 中保存着 refresh_token / access_token，默认是明文。
+//! 配置口令或密钥文件后，账号文件会被整体封装为 AES-256-GCM 信封:
+//!
+//! ```json
+//! {"ag_encrypted": 1, "key_id": "…", "nonce": "…", "ciphertext": "…"}
+//! ```
+//!
+//! 密钥来源 (不再仅依赖 machine-uid):
+//! - `ABV_ACCOUNT_PASSPHRASE`: 口令，经 PBKDF2-HMAC-SHA256 派生
+//! - `ABV_ACCOUNT_KEY_FILE`: 密钥文件路径 (路径会记录在元数据中，之后无需再设置)
+//!
+//! 元数据 (`account_encryption.json`，位于数据目录) 只保存 salt / 迭代次数 / key_id，
+//! 不包含任何可还原密钥的信息。明文账号文件在启用后会被透明迁移；
+//! 所有账号文件的读写都必须经过本模块的 `read_account_file` / `write_account_*`。
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use base64::{engine::general_purpose, Engine as _};
+use once_cell::sync::Lazy;
+use pbkdf2::pbkdf2_hmac_array;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+pub const PASSPHRASE_ENV: &str = "ABV_ACCOUNT_PASSPHRASE";
+pub const KEY_FILE_ENV: &str = "ABV_ACCOUNT_KEY_FILE";
+
+const META_FILE: &str = "account_encryption.json";
+const ACCOUNTS_DIR: &str = "accounts";
+/// 轮换密钥时的中间文件后缀 (写完全部文件后再统一 rename)
+const REKEY_SUFFIX: &str = ".rekey";
+const ENVELOPE_MARKER: &str = "ag_encrypted";
+const EXPORT_MARKER: &str = "ag_encrypted_export";
+const KDF_PASSPHRASE: &str = "pbkdf2-sha256";
+const KDF_KEY_FILE: &str = "keyfile";
+/// OWASP 2023 对 PBKDF2-HMAC-SHA256 的建议值；只在启动 / 解锁时派生一次
+const PASSPHRASE_ITERATIONS: u32 = 600_000;
+
+/// 密钥来源
+#[derive(Debug, Clone)]
+pub enum KeySource {
+    Passphrase(String),
+    KeyFile(PathBuf),
+}
+
+impl KeySource {
+    /// 从环境变量读取 (口令优先)
+    pub fn from_env() -> Option<Self> {
+        if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+            if !passphrase.is_empty() {
+                return Some(KeySource::Passphrase(passphrase));
+            }
+        }
+        std::env::var(KEY_FILE_ENV)
+            .ok()
+            .filter(|p| !p.trim().is_empty())
+            .map(|p| KeySource::KeyFile(PathBuf::from(p.trim())))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EncryptionMeta {
+    version: u32,
+    kdf: String,
+    iterations: u32,
+    salt: String,
+    key_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key_file: Option<String>,
+}
+
+#[derive(Clone)]
+struct ActiveKey {
+    key: [u8; 32],
+    key_id: String,
+}
+
+/// 当前进程持有的密钥
+///
+/// `previous` 保留轮换前的旧密钥，使轮换过程中已读入内存的旧密文仍可解开。
+#[derive(Default)]
+struct Keyring {
+    current: Option<ActiveKey>,
+    previous: Vec<ActiveKey>,
+    /// 已启用加密但尚未提供正确的口令 / 密钥文件
+    locked: bool,
+}
+
+static KEYRING: Lazy<RwLock<Keyring>> = Lazy::new(|| RwLock::new(Keyring::default()));
+
+/// 加密状态 (供 CLI / 前端展示)
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountEncryptionStatus {
+    pub enabled: bool,
+    pub unlocked: bool,
+    pub kdf: Option<String>,
+    pub key_id: Option<String>,
+    pub key_file: Option<String>,
+    pub encrypted_files: usize,
+    pub plaintext_files: usize,
+}
+
+// ===== 基础密码学原语 (PBKDF2 / HMAC 由 RustCrypto `pbkdf2` / `hmac` 提供) =====
+
+fn key_id_of(key: &[u8; 32]) -> String {
+    let digest = Sha256::new()
+        .chain_update(b"ag-account-key-id")
+        .chain_update(key)
+        .finalize();
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub(crate) fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut buf = [0u8; N];
+    rand::thread_rng().fill_bytes(&mut buf);
+    buf
+}
+
+fn b64_decode(field: &str, value: &str) -> Result<Vec<u8>, String> {
+    general_purpose::STANDARD
+        .decode(value)
+        .map_err(|e| format!("invalid {} encoding: {}", field, e))
+}
+
+fn seal(key: &[u8; 32], plaintext: &[u8]) -> Result<(String, String), String> {
+    let cipher = Aes256Gcm::new(&(*key).into());
+    let nonce = random_bytes::<12>();
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|e| format!("Encryption failed: {}", e))?;
+    Ok((
+        general_purpose::STANDARD.encode(nonce),
+        general_purpose::STANDARD.encode(ciphertext),
+    ))
+}
+
+fn open(key: &[u8; 32], nonce_b64: &str, ciphertext_b64: &str) -> Result<Vec<u8>, String> {
+    let nonce = b64_decode("nonce", nonce_b64)?;
+    if nonce.len() != 12 {
+        return Err("invalid nonce length".to_string());
+    }
+    let ciphertext = b64_decode("ciphertext", ciphertext_b64)?;
+    Aes256Gcm::new(&(*key).into())
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+        .map_err(|_| "Decryption failed (wrong key or corrupted file)".to_string())
+}
+
+// ===== 密钥派生 =====
+
+fn derive_key(source: &KeySource, kdf: &str, salt: &[u8], iterations: u32) -> Result<[u8; 32], String> {
+    match (source, kdf) {
+        (KeySource::Passphrase(passphrase), KDF_PASSPHRASE) => {
+            Ok(pbkdf2_hmac_array::<Sha256, 32>(passphrase.as_bytes(), salt, iterations))
+        }
+        (KeySource::KeyFile(path), KDF_KEY_FILE) => {
+            let material = fs::read(path)
+                .map_err(|e| format!("failed to read key file {}: {}", path.display(), e))?;
+            if material.len() < 16 {
+                return Err(format!("key file {} is too short (need at least 16 bytes)", path.display()));
+            }
+            Ok(pbkdf2_hmac_array::<Sha256, 32>(&material, salt, iterations))
+        }
+        (KeySource::Passphrase(_), other) => {
+            Err(format!("account store uses '{}', a passphrase cannot unlock it", other))
+        }
+        (KeySource::KeyFile(_), other) => {
+            Err(format!("account store uses '{}', a key file cannot unlock it", other))
+        }
+    }
+}
+
+/// 为新密钥生成元数据并派生密钥
+fn new_key(source: &KeySource, iterations: u32) -> Result<(EncryptionMeta, ActiveKey), String> {
+    let (kdf, iterations, key_file) = match source {
+        KeySource::Passphrase(p) if p.is_empty() => return Err("passphrase must not be empty".to_string()),
+        KeySource::Passphrase(_) => (KDF_PASSPHRASE, iterations, None),
+        // 密钥文件本身是高熵随机数据，无需拉伸
+        KeySource::KeyFile(path) => (KDF_KEY_FILE, 1, Some(path.to_string_lossy().to_string())),
+    };
+    let salt = random_bytes::<16>();
+    let key = derive_key(source, kdf, &salt, iterations)?;
+    let key_id = key_id_of(&key);
+    let meta = EncryptionMeta {
+        version: 1,
+        kdf: kdf.to_string(),
+        iterations,
+        salt: general_purpose::STANDARD.encode(salt),
+        key_id: key_id.clone(),
+        key_file,
+    };
+    Ok((meta, ActiveKey { key, key_id }))
+}
+
+/// 按元数据解锁；未显式提供来源时使用元数据中记录的密钥文件
+fn unlock_with_meta(meta: &EncryptionMeta, source: Option<&KeySource>) -> Result<ActiveKey, String> {
+    let fallback;
+    let source = match source {
+        Some(s) => s,
+        None => match &meta.key_file {
+            Some(path) => {
+                fallback = KeySource::KeyFile(PathBuf::from(path));
+                &fallback
+            }
+            None => {
+                return Err(format!(
+                    "account files are encrypted; set {} or {} to unlock them",
+                    PASSPHRASE_ENV, KEY_FILE_ENV
+                ))
+            }
+        },
+    };
+    let salt = b64_decode("salt", &meta.salt)?;
+    let key = derive_key(source, &meta.kdf, &salt, meta.iterations)?;
+    let key_id = key_id_of(&key);
+    if key_id != meta.key_id {
+        return Err("wrong passphrase or key file for the account store".to_string());
+    }
+    Ok(ActiveKey { key, key_id })
+}
+
+// ===== 元数据 =====
+
+fn load_meta(data_dir: &Path) -> Result<Option<EncryptionMeta>, String> {
+    let path = data_dir.join(META_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).map_err(|e| format!("failed to read {}: {}", META_FILE, e))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("failed to parse {}: {}", META_FILE, e))
+}
+
+fn save_meta(data_dir: &Path, meta: &EncryptionMeta) -> Result<(), String> {
+    let path = data_dir.join(META_FILE);
+    let tmp = data_dir.join(format!("{}{}", META_FILE, REKEY_SUFFIX));
+    let content = serde_json::to_string_pretty(meta).map_err(|e| e.to_string())?;
+    fs::write(&tmp, content).map_err(|e| format!("failed to write {}: {}", META_FILE, e))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("failed to replace {}: {}", META_FILE, e))
+}
+
+// ===== 信封编解码 =====
+
+/// 文件内容是否为加密信封；返回信封中的 key_id
+fn envelope_key_id(content: &str) -> Option<String> {
+    if !content.contains(ENVELOPE_MARKER) {
+        return None;
+    }
+    let value: Value = serde_json::from_str(content).ok()?;
+    value.get(ENVELOPE_MARKER)?;
+    Some(value.get("key_id").and_then(|v| v.as_str()).unwrap_or_default().to_string())
+}
+
+fn decode_with(keyring: &Keyring, content: &str) -> Result<String, String> {
+    let Some(key_id) = envelope_key_id(content) else {
+        return Ok(content.to_string());
+    };
+    let key = keyring
+        .current
+        .iter()
+        .chain(keyring.previous.iter())
+        .find(|k| k.key_id == key_id)
+        .ok_or_else(|| {
+            format!(
+                "account file is encrypted with key {} which is not unlocked (set {} or {})",
+                key_id, PASSPHRASE_ENV, KEY_FILE_ENV
+            )
+        })?;
+    let value: Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let field = |name: &str| value.get(name).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    let plaintext = open(&key.key, &field("nonce"), &field("ciphertext"))?;
+    String::from_utf8(plaintext).map_err(|e| format!("UTF-8 conversion failed: {}", e))
+}
+
+fn encode_with(key: Option<&ActiveKey>, json: &str) -> Result<String, String> {
+    let Some(key) = key else {
+        return Ok(json.to_string());
+    };
+    let (nonce, ciphertext) = seal(&key.key, json.as_bytes())?;
+    serde_json::to_string_pretty(&serde_json::json!({
+        ENVELOPE_MARKER: 1,
+        "key_id": key.key_id,
+        "nonce": nonce,
+        "ciphertext": ciphertext,
+    }))
+    .map_err(|e| e.to_string())
+}
+
+// ===== 账号文件读写 (所有账号文件访问的唯一入口) =====
+
+/// 解码账号文件内容 (明文原样返回)
+pub fn decode_account_content(content: &str) -> Result<String, String> {
+    let keyring = KEYRING.read().map_err(|_| "account keyring poisoned".to_string())?;
+    decode_with(&keyring, content)
+}
+
+/// 读取账号文件并返回明文 JSON
+pub fn read_account_file(path: &Path) -> Result<String, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    decode_account_content(&content)
+}
+
+/// 写入账号文件；已启用加密时自动封装为信封
+pub fn write_account_file(path: &Path, json: &str) -> Result<(), String> {
+    let keyring = KEYRING.read().map_err(|_| "account keyring poisoned".to_string())?;
+    if keyring.locked {
+        // 锁定状态下写明文会把 token 以明文落盘，直接拒绝
+        return Err("account store is locked; refusing to write account file".to_string());
+    }
+    let content = encode_with(keyring.current.as_ref(), json)?;
+    fs::write(path, content).map_err(|e| e.to_string())
+}
+
+pub fn write_account_value(path: &Path, value: &Value) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| format!("序列化失败: {}", e))?;
+    write_account_file(path, &json)
+}
+
+// ===== 初始化 / 迁移 / 轮换 =====
+
+fn account_files(data_dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(data_dir.join(ACCOUNTS_DIR)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("json"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn rekey_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(REKEY_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// 收尾上次中断的轮换: 能被当前密钥解开的 `.rekey` 文件覆盖原文件，其余丢弃
+fn finish_pending_rekey(data_dir: &Path, keyring: &Keyring) {
+    let Ok(entries) = fs::read_dir(data_dir.join(ACCOUNTS_DIR)) else {
+        return;
+    };
+    for path in entries.flatten().map(|e| e.path()) {
+        let Some(name) = path.to_str().and_then(|s| s.strip_suffix(REKEY_SUFFIX)) else {
+            continue;
+        };
+        let target = PathBuf::from(name);
+        let usable = fs::read_to_string(&path)
+            .ok()
+            .map(|c| {
+                // 明文 .rekey 只在未启用加密 (关闭加密的中途) 时有效
+                let matches_mode = envelope_key_id(&c).is_some() == keyring.current.is_some();
+                matches_mode && decode_with(keyring, &c).is_ok()
+            })
+            .unwrap_or(false);
+        if usable && fs::rename(&path, &target).is_ok() {
+            crate::modules::logger::log_info(&format!(
+                "[AccountCrypto] Completed interrupted re-key for {}",
+                target.display()
+            ));
+        } else {
+            let _ = fs::remove_file(&path);
+        }
+    }
+}
+
+/// 将尚未使用当前密钥加密的账号文件重新写入 (透明迁移)
+fn migrate_files(data_dir: &Path, keyring: &Keyring) -> Result<usize, String> {
+    let Some(current) = keyring.current.as_ref() else {
+        return Ok(0);
+    };
+    let mut migrated = 0;
+    for path in account_files(data_dir) {
+        let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        if envelope_key_id(&content).as_deref() == Some(current.key_id.as_str()) {
+            continue;
+        }
+        let plaintext = match decode_with(keyring, &content) {
+            Ok(p) => p,
+            Err(e) => {
+                crate::modules::logger::log_warn(&format!(
+                    "[AccountCrypto] Skipping {}: {}",
+                    path.display(),
+                    e
+                ));
+                continue;
+            }
+        };
+        fs::write(&path, encode_with(Some(current), &plaintext)?)
+            .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+        migrated += 1;
+    }
+    Ok(migrated)
+}
+
+fn init_keyring(data_dir: &Path, source: Option<&KeySource>, iterations: u32) -> Result<Keyring, String> {
+    let mut keyring = Keyring::default();
+    match load_meta(data_dir)? {
+        Some(meta) => match unlock_with_meta(&meta, source) {
+            Ok(key) => keyring.current = Some(key),
+            Err(e) => {
+                // 保持锁定: 加密文件不可读，也拒绝写入明文
+                crate::modules::logger::log_error(&format!("[AccountCrypto] {}", e));
+                keyring.locked = true;
+                return Ok(keyring);
+            }
+        },
+        None => {
+            if let Some(source) = source {
+                let (meta, key) = new_key(source, iterations)?;
+                fs::create_dir_all(data_dir).map_err(|e| e.to_string())?;
+                save_meta(data_dir, &meta)?;
+                crate::modules::logger::log_info(&format!(
+                    "[AccountCrypto] Enabled account file encryption (key {})",
+                    key.key_id
+                ));
+                keyring.current = Some(key);
+            }
+        }
+    }
+
+    finish_pending_rekey(data_dir, &keyring);
+    let migrated = migrate_files(data_dir, &keyring)?;
+    if migrated > 0 {
+        crate::modules::logger::log_info(&format!(
+            "[AccountCrypto] Encrypted {} plaintext account file(s)",
+            migrated
+        ));
+    }
+    Ok(keyring)
+}
+
+/// 启动时调用: 从环境变量 / 元数据解锁，并迁移明文账号文件
+pub fn init() -> Result<AccountEncryptionStatus, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    let keyring = init_keyring(&data_dir, KeySource::from_env().as_ref(), PASSPHRASE_ITERATIONS)?;
+    *KEYRING.write().map_err(|_| "account keyring poisoned".to_string())? = keyring;
+    status()
+}
+
+/// 运行时用口令解锁 (例如桌面端未设置环境变量时)；调用方需随后重新加载账号
+pub fn unlock(passphrase: &str) -> Result<AccountEncryptionStatus, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    let meta = load_meta(&data_dir)?.ok_or("account file encryption is not enabled")?;
+    let key = unlock_with_meta(&meta, Some(&KeySource::Passphrase(passphrase.to_string())))?;
+    {
+        let mut keyring = KEYRING.write().map_err(|_| "account keyring poisoned".to_string())?;
+        keyring.current = Some(key);
+        keyring.locked = false;
+        migrate_files(&data_dir, &keyring)?;
+    }
+    status()
+}
+
+fn rotate_in_dir(
+    data_dir: &Path,
+    keyring: &mut Keyring,
+    new_source: Option<&KeySource>,
+    iterations: u32,
+) -> Result<usize, String> {
+    if keyring.locked {
+        return Err("account store is locked; unlock it with the current key before rotating".to_string());
+    }
+
+    // 1. 用旧密钥解出全部账号 (任何一个失败都中止，不做部分轮换)
+    let files = account_files(data_dir);
+    let mut plaintexts = Vec::with_capacity(files.len());
+    for path in &files {
+        let content = fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let plaintext = decode_with(keyring, &content).map_err(|e| format!("{}: {}", path.display(), e))?;
+        plaintexts.push(plaintext);
+    }
+
+    // 2. 新密钥写入 .rekey 中间文件
+    let new = match new_source {
+        Some(source) => Some(new_key(source, iterations)?),
+        None => None,
+    };
+    let new_key = new.as_ref().map(|(_, key)| key);
+    for (path, plaintext) in files.iter().zip(&plaintexts) {
+        let tmp = rekey_path(path);
+        if let Err(e) = encode_with(new_key, plaintext).and_then(|c| fs::write(&tmp, c).map_err(|e| e.to_string())) {
+            for path in &files {
+                let _ = fs::remove_file(rekey_path(path));
+            }
+            return Err(format!("failed to write {}: {}", tmp.display(), e));
+        }
+    }
+
+    // 3. 提交元数据，再替换账号文件 (中断后由 finish_pending_rekey 收尾)
+    match &new {
+        Some((meta, _)) => save_meta(data_dir, meta)?,
+        None => {
+            let _ = fs::remove_file(data_dir.join(META_FILE));
+        }
+    }
+    if let Some(old) = keyring.current.take() {
+        keyring.previous.push(old);
+    }
+    keyring.current = new.map(|(_, key)| key);
+    for path in &files {
+        fs::rename(rekey_path(path), path).map_err(|e| format!("failed to replace {}: {}", path.display(), e))?;
+    }
+    Ok(files.len())
+}
+
+/// 轮换密钥: `Some` 启用 / 更换密钥，`None` 关闭加密 (全部还原为明文)
+///
+/// 需要当前密钥已解锁；返回重写的账号文件数。
+pub fn rotate_key(new_source: Option<KeySource>) -> Result<usize, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    let count = {
+        let mut keyring = KEYRING.write().map_err(|_| "account keyring poisoned".to_string())?;
+        rotate_in_dir(&data_dir, &mut keyring, new_source.as_ref(), PASSPHRASE_ITERATIONS)?
+    };
+    crate::modules::logger::log_info(&format!(
+        "[AccountCrypto] Re-keyed {} account file(s) ({})",
+        count,
+        if new_source.is_some() { "encrypted" } else { "encryption disabled" }
+    ));
+    Ok(count)
+}
+
+fn status_in_dir(data_dir: &Path, keyring: &Keyring) -> Result<AccountEncryptionStatus, String> {
+    let meta = load_meta(data_dir)?;
+    let (mut encrypted_files, mut plaintext_files) = (0, 0);
+    for path in account_files(data_dir) {
+        match fs::read_to_string(&path).ok().and_then(|c| envelope_key_id(&c)) {
+            Some(_) => encrypted_files += 1,
+            None => plaintext_files += 1,
+        }
+    }
+    Ok(AccountEncryptionStatus {
+        enabled: meta.is_some(),
+        unlocked: keyring.current.is_some(),
+        kdf: meta.as_ref().map(|m| m.kdf.clone()),
+        key_id: meta.as_ref().map(|m| m.key_id.clone()),
+        key_file: meta.and_then(|m| m.key_file),
+        encrypted_files,
+        plaintext_files,
+    })
+}
+
+pub fn status() -> Result<AccountEncryptionStatus, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    let keyring = KEYRING.read().map_err(|_| "account keyring poisoned".to_string())?;
+    status_in_dir(&data_dir, &keyring)
+}
+
+// ===== 导出 / 导入 =====
+
+fn seal_export_with(plaintext: &str, passphrase: &str, iterations: u32) -> Result<String, String> {
+    if passphrase.is_empty() {
+        return Err("passphrase must not be empty".to_string());
+    }
+    let salt = random_bytes::<16>();
+    let key = pbkdf2_hmac_array::<Sha256, 32>(passphrase.as_bytes(), &salt, iterations);
+    let (nonce, ciphertext) = seal(&key, plaintext.as_bytes())?;
+    serde_json::to_string_pretty(&serde_json::json!({
+        EXPORT_MARKER: 1,
+        "kdf": KDF_PASSPHRASE,
+        "iterations": iterations,
+        "salt": general_purpose::STANDARD.encode(salt),
+        "nonce": nonce,
+        "ciphertext": ciphertext,
+    }))
+    .map_err(|e| e.to_string())
+}
+
+/// 用导出口令加密导出内容 (独立于本机账号密钥，可在其他机器导入)
+pub fn seal_export(plaintext: &str, passphrase: &str) -> Result<String, String> {
+    seal_export_with(plaintext, passphrase, PASSPHRASE_ITERATIONS)
+}
+
+pub fn is_sealed_export(content: &str) -> bool {
+    serde_json::from_str::<Value>(content)
+        .map(|v| v.get(EXPORT_MARKER).is_some())
+        .unwrap_or(false)
+}
+
+/// 解开加密导出文件；非加密内容原样返回
+pub fn open_export(content: &str, passphrase: &str) -> Result<String, String> {
+    let value: Value = match serde_json::from_str(content) {
+        Ok(v) => v,
+        Err(_) => return Ok(content.to_string()),
+    };
+    if value.get(EXPORT_MARKER).is_none() {
+        return Ok(content.to_string());
+    }
+    let field = |name: &str| value.get(name).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    if field("kdf") != KDF_PASSPHRASE {
+        return Err(format!("unsupported export kdf: {}", field("kdf")));
+    }
+    let iterations = value.get("iterations").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+    let salt = b64_decode("salt", &field("salt"))?;
+    let key = pbkdf2_hmac_array::<Sha256, 32>(passphrase.as_bytes(), &salt, iterations);
+    let plaintext = open(&key, &field("nonce"), &field("ciphertext"))
+        .map_err(|_| "wrong export passphrase or corrupted file".to_string())?;
+    String::from_utf8(plaintext).map_err(|e| format!("UTF-8 conversion failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(tag: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ag_account_crypto_{}_{}", tag, uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join(ACCOUNTS_DIR)).unwrap();
+        dir
+    }
+
+    fn write_plain_account(dir: &Path, id: &str) -> PathBuf {
+        let path = dir.join(ACCOUNTS_DIR).join(format!("{}.json", id));
+        let json = serde_json::json!({"id": id, "token": {"refresh_token": format!("1//{}", id)}});
+        fs::write(&path, serde_json::to_string_pretty(&json).unwrap()).unwrap();
+        path
+    }
+
+    // 已有的账号密钥 / 导出文件用 PBKDF2-HMAC-SHA256 派生，库实现必须与 RFC 7914 向量一致
+    #[test]
+    fn test_pbkdf2_rfc7914_vector() {
+        let out = pbkdf2_hmac_array::<Sha256, 32>(b"passwd", b"salt", 1);
+        let hex: String = out.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(hex, "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc");
+    }
+
+    #[test]
+    fn test_migrate_rotate_and_disable() {
+        let dir = temp_dir("rotate");
+        let path = write_plain_account(&dir, "a1");
+        let source = KeySource::Passphrase("old-pass".to_string());
+
+        // 启用: 明文被透明迁移
+        let mut keyring = init_keyring(&dir, Some(&source), 10).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(!content.contains("1//a1"));
+        assert!(decode_with(&keyring, &content).unwrap().contains("1//a1"));
+
+        // 错误口令: 锁定，无法解密
+        let locked = init_keyring(&dir, Some(&KeySource::Passphrase("nope".into())), 10).unwrap();
+        assert!(locked.locked);
+        assert!(decode_with(&locked, &content).is_err());
+
+        // 轮换到新口令
+        let new_source = KeySource::Passphrase("new-pass".to_string());
+        assert_eq!(rotate_in_dir(&dir, &mut keyring, Some(&new_source), 10).unwrap(), 1);
+        let rotated = fs::read_to_string(&path).unwrap();
+        assert_ne!(envelope_key_id(&rotated), envelope_key_id(&content));
+        assert!(init_keyring(&dir, Some(&source), 10).unwrap().locked);
+        let reopened = init_keyring(&dir, Some(&new_source), 10).unwrap();
+        assert!(decode_with(&reopened, &rotated).unwrap().contains("1//a1"));
+        // 轮换前读入的旧密文仍可用旧密钥解开
+        assert!(decode_with(&keyring, &content).is_ok());
+
+        // 关闭加密: 还原为明文并删除元数据
+        rotate_in_dir(&dir, &mut keyring, None, 10).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("1//a1"));
+        assert!(load_meta(&dir).unwrap().is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_key_file_is_remembered() {
+        let dir = temp_dir("keyfile");
+        write_plain_account(&dir, "b1");
+        let key_file = dir.join("account.key");
+        fs::write(&key_file, random_bytes::<32>()).unwrap();
+
+        init_keyring(&dir, Some(&KeySource::KeyFile(key_file.clone())), 10).unwrap();
+        // 元数据记录了密钥文件路径，之后无需再设置环境变量
+        let keyring = init_keyring(&dir, None, 10).unwrap();
+        assert!(keyring.current.is_some());
+        let status = status_in_dir(&dir, &keyring).unwrap();
+        assert_eq!(status.encrypted_files, 1);
+        assert_eq!(status.plaintext_files, 0);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_export_roundtrip() {
+        let plaintext = r#"[{"email":"a@b.c","refresh_token":"1//x"}]"#;
+        let sealed = seal_export_with(plaintext, "export-pass", 10).unwrap();
+        assert!(is_sealed_export(&sealed));
+        assert!(!sealed.contains("1//x"));
+        assert_eq!(open_export(&sealed, "export-pass").unwrap(), plaintext);
+        assert!(open_export(&sealed, "wrong").is_err());
+        // 明文导出文件原样通过
+        assert_eq!(open_export(plaintext, "").unwrap(), plaintext);
+    }
+}
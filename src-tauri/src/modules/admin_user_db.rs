@@ -0,0 +1,728 @@
+//! 管理员账号与登录会话存储
+//!
+//! - 密码以 PHC 字符串 (`$pbkdf2-sha256$i=…,l=32$salt$hash`) 保存，迭代次数随哈希一起存储，后续可调高。
+//! - 用户名不存在时同样执行一次哈希，登录耗时不会暴露用户名是否存在。
+//! - 会话令牌只在登录时返回一次，库中仅保存其 SHA-256，数据库泄露也无法直接冒用会话。
+
+use pbkdf2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use pbkdf2::{Algorithm, Params, Pbkdf2};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use super::account_crypto::random_bytes;
+
+/// 会话令牌前缀，鉴权中间件据此区分会话令牌与 api_key / admin_password
+pub const SESSION_TOKEN_PREFIX: &str = "abv_sess_";
+
+/// 密码哈希的 PBKDF2 迭代次数
+const PASSWORD_ITERATIONS: u32 = 210_000;
+const MIN_PASSWORD_LEN: usize = 8;
+/// last_seen_at 的最小刷新间隔 (秒)，避免每个请求都写库
+const LAST_SEEN_UPDATE_INTERVAL: i64 = 60;
+
+/// 管理员角色，按权限从低到高排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AdminRole {
+    /// 只读：查看账号、统计与日志
+    Viewer,
+    /// 日常运维：切换账号、刷新配额、启停服务等
+    Operator,
+    /// 完全控制：删除账号、导出令牌、改配置、管理管理员
+    Owner,
+}
+
+impl AdminRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminRole::Viewer => "viewer",
+            AdminRole::Operator => "operator",
+            AdminRole::Owner => "owner",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "viewer" => Ok(AdminRole::Viewer),
+            "operator" => Ok(AdminRole::Operator),
+            "owner" => Ok(AdminRole::Owner),
+            other => Err(format!("Unknown admin role: {}", other)),
+        }
+    }
+}
+
+/// 管理员账号 (不含密码哈希)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminUser {
+    pub id: String,
+    pub username: String,
+    pub role: AdminRole,
+    pub enabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub last_login_at: Option<i64>,
+}
+
+/// 登录会话
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AdminSession {
+    pub id: String,
+    pub user_id: String,
+    pub username: String,
+    pub role: AdminRole,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub last_seen_at: i64,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// 创建管理员
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewAdminUser {
+    pub username: String,
+    pub password: String,
+    pub role: AdminRole,
+}
+
+/// 修改管理员，字段为空表示不修改
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdminUserUpdate {
+    pub password: Option<String>,
+    pub role: Option<AdminRole>,
+    pub enabled: Option<bool>,
+}
+
+/// 登录成功后返回给客户端
+#[derive(Debug, Clone, Serialize)]
+pub struct LoginResult {
+    /// 明文会话令牌，仅此一次
+    pub token: String,
+    pub expires_at: i64,
+    pub user: AdminUser,
+}
+
+/// 获取数据库路径
+pub fn get_db_path() -> Result<PathBuf, String> {
+    let mut path = crate::modules::account::get_data_dir()?;
+    path.push("admin_users.db");
+    Ok(path)
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let path = get_db_path()?;
+    let conn = Connection::open(&path).map_err(|e| format!("Failed to open database: {}", e))?;
+    conn.execute_batch("PRAGMA foreign_keys = ON;")
+        .map_err(|e| format!("Failed to enable foreign keys: {}", e))?;
+    Ok(conn)
+}
+
+fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS admin_users (
+            id TEXT PRIMARY KEY,
+            username TEXT UNIQUE NOT NULL COLLATE NOCASE,
+            password_hash TEXT NOT NULL,
+            role TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL,
+            last_login_at INTEGER
+        );
+        CREATE TABLE IF NOT EXISTS admin_sessions (
+            id TEXT PRIMARY KEY,
+            token_hash TEXT UNIQUE NOT NULL,
+            user_id TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL,
+            last_seen_at INTEGER NOT NULL,
+            ip TEXT,
+            user_agent TEXT,
+            FOREIGN KEY(user_id) REFERENCES admin_users(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_admin_sessions_user ON admin_sessions(user_id);",
+    )
+    .map_err(|e| format!("Failed to create admin user tables: {}", e))
+}
+
+/// 初始化数据库并清理过期会话
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+    create_tables(&conn)?;
+    purge_expired_at(&conn, chrono::Utc::now().timestamp())?;
+    Ok(())
+}
+
+// ===== 密码与令牌 =====
+
+fn password_params(iterations: u32) -> Params {
+    Params { rounds: iterations, output_length: 32 }
+}
+
+/// 以 PHC 字符串格式保存: `$pbkdf2-sha256$i=<迭代次数>,l=32$<salt>$<hash>`
+fn hash_password_with(password: &str, iterations: u32) -> Result<String, String> {
+    let salt = SaltString::encode_b64(&random_bytes::<16>()).map_err(|e| e.to_string())?;
+    Pbkdf2
+        .hash_password_customized(
+            password.as_bytes(),
+            Some(Algorithm::Pbkdf2Sha256.ident()),
+            None,
+            password_params(iterations),
+            &salt,
+        )
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("Failed to hash password: {}", e))
+}
+
+fn verify_password(password: &str, stored: &str) -> bool {
+    PasswordHash::new(stored)
+        .map(|hash| Pbkdf2.verify_password(password.as_bytes(), &hash).is_ok())
+        .unwrap_or(false)
+}
+
+/// 用户名不存在 / 已停用时也做一次同等代价的哈希，避免登录耗时暴露哪些用户名存在
+fn dummy_verify(password: &str, iterations: u32) {
+    if let Ok(salt) = SaltString::encode_b64(&[0u8; 16]) {
+        let _ = Pbkdf2.hash_password_customized(
+            password.as_bytes(),
+            Some(Algorithm::Pbkdf2Sha256.ident()),
+            None,
+            password_params(iterations),
+            &salt,
+        );
+    }
+}
+
+fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn generate_token() -> String {
+    let bytes = random_bytes::<32>();
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}{}", SESSION_TOKEN_PREFIX, hex)
+}
+
+fn validate_password(password: &str) -> Result<(), String> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(format!(
+            "Password must be at least {} characters",
+            MIN_PASSWORD_LEN
+        ));
+    }
+    Ok(())
+}
+
+// ===== 内部实现 (传入连接，便于测试) =====
+
+const USER_COLUMNS: &str = "id, username, role, enabled, created_at, updated_at, last_login_at";
+
+fn row_to_user(row: &rusqlite::Row) -> rusqlite::Result<AdminUser> {
+    let role: String = row.get(2)?;
+    Ok(AdminUser {
+        id: row.get(0)?,
+        username: row.get(1)?,
+        role: AdminRole::parse(&role).unwrap_or(AdminRole::Viewer),
+        enabled: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+        last_login_at: row.get(6)?,
+    })
+}
+
+fn get_user_at(conn: &Connection, id: &str) -> Result<Option<AdminUser>, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM admin_users WHERE id = ?1", USER_COLUMNS),
+        params![id],
+        row_to_user,
+    )
+    .optional()
+    .map_err(|e| format!("Failed to load admin user: {}", e))
+}
+
+fn list_users_at(conn: &Connection) -> Result<Vec<AdminUser>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM admin_users ORDER BY created_at ASC",
+            USER_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let users = stmt
+        .query_map([], row_to_user)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(users)
+}
+
+/// 除 `excluding` 外仍启用的 Owner 数量
+fn other_enabled_owners(conn: &Connection, excluding: &str) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM admin_users WHERE role = 'owner' AND enabled = 1 AND id != ?1",
+        params![excluding],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn create_user_at(
+    conn: &Connection,
+    user: &NewAdminUser,
+    iterations: u32,
+    now: i64,
+) -> Result<AdminUser, String> {
+    let username = user.username.trim();
+    if username.is_empty() {
+        return Err("Username is required".to_string());
+    }
+    validate_password(&user.password)?;
+
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO admin_users (id, username, password_hash, role, enabled, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, 1, ?5, ?5)",
+        params![
+            id,
+            username,
+            hash_password_with(&user.password, iterations)?,
+            user.role.as_str(),
+            now
+        ],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(err, _)
+            if err.code == rusqlite::ErrorCode::ConstraintViolation =>
+        {
+            format!("Admin user '{}' already exists", username)
+        }
+        other => format!("Failed to create admin user: {}", other),
+    })?;
+
+    get_user_at(conn, &id)?.ok_or_else(|| "Admin user not found after insert".to_string())
+}
+
+fn update_user_at(
+    conn: &Connection,
+    id: &str,
+    update: &AdminUserUpdate,
+    iterations: u32,
+    now: i64,
+) -> Result<AdminUser, String> {
+    let current = get_user_at(conn, id)?.ok_or_else(|| "Admin user not found".to_string())?;
+
+    let demoted = update.role.map(|r| r != AdminRole::Owner).unwrap_or(false);
+    let disabled = update.enabled == Some(false);
+    if current.role == AdminRole::Owner
+        && current.enabled
+        && (demoted || disabled)
+        && other_enabled_owners(conn, id)? == 0
+    {
+        return Err("At least one enabled owner is required".to_string());
+    }
+
+    if let Some(password) = &update.password {
+        validate_password(password)?;
+        conn.execute(
+            "UPDATE admin_users SET password_hash = ?1 WHERE id = ?2",
+            params![hash_password_with(password, iterations)?, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    if let Some(role) = update.role {
+        conn.execute(
+            "UPDATE admin_users SET role = ?1 WHERE id = ?2",
+            params![role.as_str(), id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    if let Some(enabled) = update.enabled {
+        conn.execute(
+            "UPDATE admin_users SET enabled = ?1 WHERE id = ?2",
+            params![enabled, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    conn.execute(
+        "UPDATE admin_users SET updated_at = ?1 WHERE id = ?2",
+        params![now, id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    // 改密码、停用后已登录的会话全部失效；角色变更在下次请求时即生效，无需注销
+    if update.password.is_some() || disabled {
+        conn.execute("DELETE FROM admin_sessions WHERE user_id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+    }
+
+    get_user_at(conn, id)?.ok_or_else(|| "Admin user not found".to_string())
+}
+
+fn delete_user_at(conn: &Connection, id: &str) -> Result<(), String> {
+    let current = get_user_at(conn, id)?.ok_or_else(|| "Admin user not found".to_string())?;
+    if current.role == AdminRole::Owner && current.enabled && other_enabled_owners(conn, id)? == 0 {
+        return Err("At least one enabled owner is required".to_string());
+    }
+    conn.execute("DELETE FROM admin_sessions WHERE user_id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM admin_users WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn login_at(
+    conn: &Connection,
+    username: &str,
+    password: &str,
+    ttl_secs: i64,
+    ip: Option<&str>,
+    user_agent: Option<&str>,
+    iterations: u32,
+    now: i64,
+) -> Result<Option<LoginResult>, String> {
+    let row: Option<(String, String)> = conn
+        .query_row(
+            "SELECT id, password_hash FROM admin_users WHERE username = ?1 AND enabled = 1",
+            params![username.trim()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    let Some((user_id, password_hash)) = row else {
+        dummy_verify(password, iterations);
+        return Ok(None);
+    };
+    if !verify_password(password, &password_hash) {
+        return Ok(None);
+    }
+
+    let token = generate_token();
+    let expires_at = now + ttl_secs.max(60);
+    conn.execute(
+        "INSERT INTO admin_sessions (id, token_hash, user_id, created_at, expires_at, last_seen_at, ip, user_agent)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?4, ?6, ?7)",
+        params![
+            Uuid::new_v4().to_string(),
+            hash_token(&token),
+            user_id,
+            now,
+            expires_at,
+            ip,
+            user_agent
+        ],
+    )
+    .map_err(|e| format!("Failed to create session: {}", e))?;
+    conn.execute(
+        "UPDATE admin_users SET last_login_at = ?1 WHERE id = ?2",
+        params![now, user_id],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let user = get_user_at(conn, &user_id)?.ok_or_else(|| "Admin user not found".to_string())?;
+    Ok(Some(LoginResult {
+        token,
+        expires_at,
+        user,
+    }))
+}
+
+const SESSION_COLUMNS: &str = "s.id, s.user_id, u.username, u.role, s.created_at, s.expires_at,
+    s.last_seen_at, s.ip, s.user_agent";
+
+fn row_to_session(row: &rusqlite::Row) -> rusqlite::Result<AdminSession> {
+    let role: String = row.get(3)?;
+    Ok(AdminSession {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        username: row.get(2)?,
+        role: AdminRole::parse(&role).unwrap_or(AdminRole::Viewer),
+        created_at: row.get(4)?,
+        expires_at: row.get(5)?,
+        last_seen_at: row.get(6)?,
+        ip: row.get(7)?,
+        user_agent: row.get(8)?,
+    })
+}
+
+fn resolve_session_at(conn: &Connection, token: &str, now: i64) -> Result<Option<AdminSession>, String> {
+    let session = conn
+        .query_row(
+            &format!(
+                "SELECT {} FROM admin_sessions s JOIN admin_users u ON u.id = s.user_id
+                 WHERE s.token_hash = ?1 AND s.expires_at > ?2 AND u.enabled = 1",
+                SESSION_COLUMNS
+            ),
+            params![hash_token(token), now],
+            row_to_session,
+        )
+        .optional()
+        .map_err(|e| format!("Failed to resolve session: {}", e))?;
+
+    if let Some(session) = &session {
+        if now - session.last_seen_at >= LAST_SEEN_UPDATE_INTERVAL {
+            let _ = conn.execute(
+                "UPDATE admin_sessions SET last_seen_at = ?1 WHERE id = ?2",
+                params![now, session.id],
+            );
+        }
+    }
+    Ok(session)
+}
+
+fn list_sessions_at(conn: &Connection, now: i64) -> Result<Vec<AdminSession>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM admin_sessions s JOIN admin_users u ON u.id = s.user_id
+             WHERE s.expires_at > ?1 ORDER BY s.last_seen_at DESC",
+            SESSION_COLUMNS
+        ))
+        .map_err(|e| e.to_string())?;
+    let sessions = stmt
+        .query_map(params![now], row_to_session)
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(sessions)
+}
+
+fn purge_expired_at(conn: &Connection, now: i64) -> Result<usize, String> {
+    conn.execute("DELETE FROM admin_sessions WHERE expires_at <= ?1", params![now])
+        .map_err(|e| format!("Failed to purge expired sessions: {}", e))
+}
+
+// ===== 公开接口 =====
+
+pub fn list_users() -> Result<Vec<AdminUser>, String> {
+    list_users_at(&connect_db()?)
+}
+
+pub fn create_user(user: &NewAdminUser) -> Result<AdminUser, String> {
+    create_user_at(
+        &connect_db()?,
+        user,
+        PASSWORD_ITERATIONS,
+        chrono::Utc::now().timestamp(),
+    )
+}
+
+pub fn update_user(id: &str, update: &AdminUserUpdate) -> Result<AdminUser, String> {
+    update_user_at(
+        &connect_db()?,
+        id,
+        update,
+        PASSWORD_ITERATIONS,
+        chrono::Utc::now().timestamp(),
+    )
+}
+
+pub fn delete_user(id: &str) -> Result<(), String> {
+    delete_user_at(&connect_db()?, id)
+}
+
+/// 校验用户名密码并签发会话；用户名或密码错误、账号停用时返回 `Ok(None)`
+pub fn login(
+    username: &str,
+    password: &str,
+    ttl_secs: i64,
+    ip: Option<&str>,
+    user_agent: Option<&str>,
+) -> Result<Option<LoginResult>, String> {
+    login_at(
+        &connect_db()?,
+        username,
+        password,
+        ttl_secs,
+        ip,
+        user_agent,
+        PASSWORD_ITERATIONS,
+        chrono::Utc::now().timestamp(),
+    )
+}
+
+/// 由明文令牌查找有效会话 (未过期、未注销、账号仍启用)
+pub fn resolve_session(token: &str) -> Result<Option<AdminSession>, String> {
+    resolve_session_at(&connect_db()?, token, chrono::Utc::now().timestamp())
+}
+
+/// 注销当前令牌 (logout)
+pub fn revoke_token(token: &str) -> Result<bool, String> {
+    let conn = connect_db()?;
+    let removed = conn
+        .execute(
+            "DELETE FROM admin_sessions WHERE token_hash = ?1",
+            params![hash_token(token)],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(removed > 0)
+}
+
+/// 按会话 ID 吊销 (Owner 踢下线)
+pub fn revoke_session(id: &str) -> Result<bool, String> {
+    let conn = connect_db()?;
+    let removed = conn
+        .execute("DELETE FROM admin_sessions WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    Ok(removed > 0)
+}
+
+pub fn list_sessions() -> Result<Vec<AdminSession>, String> {
+    list_sessions_at(&connect_db()?, chrono::Utc::now().timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试里使用极低的迭代次数，避免拖慢用例
+    const TEST_ITERATIONS: u32 = 10;
+
+    fn test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("PRAGMA foreign_keys = ON;").unwrap();
+        create_tables(&conn).unwrap();
+        conn
+    }
+
+    fn new_user(conn: &Connection, username: &str, role: AdminRole) -> AdminUser {
+        create_user_at(
+            conn,
+            &NewAdminUser {
+                username: username.to_string(),
+                password: "correct-horse".to_string(),
+                role,
+            },
+            TEST_ITERATIONS,
+            1_000,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_password_hash_roundtrip() {
+        let stored = hash_password_with("s3cret-pass", TEST_ITERATIONS).unwrap();
+        assert!(stored.starts_with("$pbkdf2-sha256$i=10,l=32$"));
+        assert!(verify_password("s3cret-pass", &stored));
+        assert!(!verify_password("s3cret-pasS", &stored));
+        assert!(!verify_password("s3cret-pass", "plain"));
+        // 相同密码每次加盐不同
+        assert_ne!(stored, hash_password_with("s3cret-pass", TEST_ITERATIONS).unwrap());
+    }
+
+    #[test]
+    fn test_login_issues_expiring_session() {
+        let conn = test_db();
+        let user = new_user(&conn, "alice", AdminRole::Viewer);
+
+        assert!(login_at(&conn, "alice", "wrong-password", 3600, None, None, TEST_ITERATIONS, 2_000)
+            .unwrap()
+            .is_none());
+        assert!(login_at(&conn, "nobody", "correct-horse", 3600, None, None, TEST_ITERATIONS, 2_000)
+            .unwrap()
+            .is_none());
+
+        let result = login_at(
+            &conn,
+            "ALICE",
+            "correct-horse",
+            3600,
+            Some("10.0.0.2"),
+            None,
+            TEST_ITERATIONS,
+            2_000,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(result.token.starts_with(SESSION_TOKEN_PREFIX));
+        assert_eq!(result.expires_at, 5_600);
+        assert_eq!(result.user.last_login_at, Some(2_000));
+
+        let session = resolve_session_at(&conn, &result.token, 3_000).unwrap().unwrap();
+        assert_eq!(session.user_id, user.id);
+        assert_eq!(session.role, AdminRole::Viewer);
+        assert_eq!(session.ip.as_deref(), Some("10.0.0.2"));
+
+        // 过期后失效
+        assert!(resolve_session_at(&conn, &result.token, 5_600).unwrap().is_none());
+        // 令牌明文不落库
+        let stored: String = conn
+            .query_row("SELECT token_hash FROM admin_sessions", [], |r| r.get(0))
+            .unwrap();
+        assert_ne!(stored, result.token);
+    }
+
+    #[test]
+    fn test_disable_and_password_change_revoke_sessions() {
+        let conn = test_db();
+        new_user(&conn, "root", AdminRole::Owner);
+        let bob = new_user(&conn, "bob", AdminRole::Operator);
+        let login = login_at(&conn, "bob", "correct-horse", 3600, None, None, TEST_ITERATIONS, 2_000)
+            .unwrap()
+            .unwrap();
+
+        // 仅改角色：会话保留，角色即时生效
+        update_user_at(
+            &conn,
+            &bob.id,
+            &AdminUserUpdate { role: Some(AdminRole::Viewer), ..Default::default() },
+            TEST_ITERATIONS,
+            2_100,
+        )
+        .unwrap();
+        let session = resolve_session_at(&conn, &login.token, 2_200).unwrap().unwrap();
+        assert_eq!(session.role, AdminRole::Viewer);
+
+        update_user_at(
+            &conn,
+            &bob.id,
+            &AdminUserUpdate { password: Some("another-pass".to_string()), ..Default::default() },
+            TEST_ITERATIONS,
+            2_300,
+        )
+        .unwrap();
+        assert!(resolve_session_at(&conn, &login.token, 2_400).unwrap().is_none());
+        assert!(login_at(&conn, "bob", "another-pass", 3600, None, None, TEST_ITERATIONS, 2_500)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_last_owner_is_protected() {
+        let conn = test_db();
+        let owner = new_user(&conn, "root", AdminRole::Owner);
+
+        let demote = AdminUserUpdate { role: Some(AdminRole::Operator), ..Default::default() };
+        assert!(update_user_at(&conn, &owner.id, &demote, TEST_ITERATIONS, 2_000).is_err());
+        assert!(delete_user_at(&conn, &owner.id).is_err());
+
+        let second = new_user(&conn, "root2", AdminRole::Owner);
+        assert!(update_user_at(&conn, &owner.id, &demote, TEST_ITERATIONS, 2_000).is_ok());
+        assert!(delete_user_at(&conn, &second.id).is_err());
+    }
+
+    #[test]
+    fn test_create_user_validation() {
+        let conn = test_db();
+        new_user(&conn, "alice", AdminRole::Viewer);
+        let dup = NewAdminUser {
+            username: "Alice".to_string(),
+            password: "correct-horse".to_string(),
+            role: AdminRole::Viewer,
+        };
+        assert!(create_user_at(&conn, &dup, TEST_ITERATIONS, 1_000)
+            .unwrap_err()
+            .contains("already exists"));
+        let short = NewAdminUser {
+            username: "carol".to_string(),
+            password: "short".to_string(),
+            role: AdminRole::Viewer,
+        };
+        assert!(create_user_at(&conn, &short, TEST_ITERATIONS, 1_000).is_err());
+    }
+}
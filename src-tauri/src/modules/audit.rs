@@ -0,0 +1,172 @@
+//! 审计日志记录入口
+//!
+//! - Admin API：`proxy::middleware::audit` 在请求结束后按 HTTP 方法/路径/状态码记录一条，
+//!   并在 `ADMIN_REQUEST` 作用域内执行 handler。
+//! - Tauri 命令：关键的变更类命令调用 `record_command`。Admin handler 会直接复用部分命令，
+//!   在 `ADMIN_REQUEST` 作用域内的调用由中间件统一记录，这里跳过以免重复。
+
+use super::audit_db::{self, AuditRecord};
+use serde_json::{Map, Value};
+
+tokio::task_local! {
+    /// 当前任务正在处理 Admin API 请求
+    pub static ADMIN_REQUEST: ();
+}
+
+/// 参数中需要打码的字段 (小写，子串匹配)
+const SECRET_FIELD_MARKERS: &[&str] = &[
+    "password",
+    "passphrase",
+    "secret",
+    "token",
+    "api_key",
+    "apikey",
+    "authorization",
+    "credential",
+];
+
+/// 单个字符串字段保留的最大字符数
+const MAX_STRING_CHARS: usize = 200;
+/// 数组保留的最大元素数
+const MAX_ARRAY_ITEMS: usize = 50;
+
+fn is_secret_field(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SECRET_FIELD_MARKERS.iter().any(|m| key.contains(m))
+}
+
+/// 敏感字段下的所有非空字符串替换为 `***` (数字/布尔如 `max_tokens` 保留)
+fn mask_strings(value: &Value) -> Value {
+    match value {
+        Value::String(s) if !s.is_empty() => Value::String("***".to_string()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), mask_strings(v)))
+                .collect::<Map<String, Value>>(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(mask_strings).collect()),
+        other => other.clone(),
+    }
+}
+
+/// 递归脱敏：敏感字段替换为 `***`，过长字符串与数组截断
+pub fn redact(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| {
+                    let masked = if is_secret_field(k) {
+                        mask_strings(v)
+                    } else {
+                        redact(v)
+                    };
+                    (k.clone(), masked)
+                })
+                .collect::<Map<String, Value>>(),
+        ),
+        Value::Array(items) => {
+            let mut out: Vec<Value> = items.iter().take(MAX_ARRAY_ITEMS).map(redact).collect();
+            if items.len() > MAX_ARRAY_ITEMS {
+                out.push(Value::String(format!(
+                    "…(+{} items)",
+                    items.len() - MAX_ARRAY_ITEMS
+                )));
+            }
+            Value::Array(out)
+        }
+        Value::String(s) if s.chars().count() > MAX_STRING_CHARS => Value::String(format!(
+            "{}…({} chars)",
+            s.chars().take(MAX_STRING_CHARS).collect::<String>(),
+            s.chars().count()
+        )),
+        other => other.clone(),
+    }
+}
+
+/// 配置保存的审计明细：字段级 diff，敏感值已打码
+pub fn config_diff_details(
+    current: &crate::models::AppConfig,
+    next: &crate::models::AppConfig,
+) -> Value {
+    let changes: Vec<String> = super::config_watcher::diff_configs(current, next)
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    serde_json::json!({ "changes": changes })
+}
+
+/// 写入审计记录；失败只记日志，不影响业务
+pub fn record(record: AuditRecord) {
+    if let Err(e) = audit_db::append(&record) {
+        tracing::error!("[Audit] Failed to record '{}': {}", record.action, e);
+    }
+}
+
+/// 记录一次 Tauri 命令 (桌面端本地操作)
+pub fn record_command<T>(action: &str, target: Option<&str>, details: Value, result: &Result<T, String>) {
+    if ADMIN_REQUEST.try_with(|_| ()).is_ok() {
+        return;
+    }
+    record(AuditRecord {
+        actor: "desktop".to_string(),
+        source: "tauri".to_string(),
+        ip: None,
+        action: action.to_string(),
+        target: target.map(|t| t.to_string()),
+        details: redact(&details),
+        success: result.is_ok(),
+        status_code: None,
+        error: result.as_ref().err().cloned(),
+    });
+}
+
+/// 记录一次没有失败分支的 Tauri 命令
+pub fn record_command_success(action: &str, target: Option<&str>, details: Value) {
+    record_command(action, target, details, &Ok::<(), String>(()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redact_masks_secrets_and_truncates() {
+        let long = "x".repeat(MAX_STRING_CHARS + 10);
+        let value = json!({
+            "refresh_token": "1//abc",
+            "config": { "proxy": { "api_key": "sk-1", "port": 8045 } },
+            "admin_password": "",
+            "max_tokens": 1024,
+            "note": long,
+            "ids": (0..MAX_ARRAY_ITEMS + 2).collect::<Vec<_>>(),
+        });
+        let out = redact(&value);
+        assert_eq!(out["refresh_token"], "***");
+        assert_eq!(out["config"]["proxy"]["api_key"], "***");
+        assert_eq!(out["config"]["proxy"]["port"], 8045);
+        assert_eq!(out["admin_password"], "");
+        assert_eq!(out["max_tokens"], 1024);
+        assert!(out["note"].as_str().unwrap().ends_with(&format!("({} chars)", MAX_STRING_CHARS + 10)));
+        assert_eq!(out["ids"].as_array().unwrap().len(), MAX_ARRAY_ITEMS + 1);
+    }
+
+    #[test]
+    fn test_config_diff_details_lists_masked_changes() {
+        let current = crate::models::AppConfig::new();
+        let mut next = current.clone();
+        next.proxy.port = 9000;
+        next.proxy.api_key = "sk-rotated".to_string();
+
+        let details = config_diff_details(&current, &next);
+        let changes: Vec<&str> = details["changes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c.as_str().unwrap())
+            .collect();
+        assert!(changes.contains(&"proxy.port: 8045 -> 9000"));
+        assert!(changes.iter().any(|c| c.starts_with("proxy.api_key:") && c.ends_with("-> ***")));
+        assert!(!changes.iter().any(|c| c.contains("sk-rotated")));
+    }
+}
@@ -0,0 +1,508 @@
+//! 审计日志存储
+//!
+//! 只追加：表上的触发器拒绝 UPDATE / DELETE；每行保存上一行的哈希 (`prev_hash`)
+//! 与自身哈希 (`hash`)，绕过触发器直接改库也能被 `verify_chain` 发现。
+
+use rusqlite::{params, params_from_iter, Connection, TransactionBehavior};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// 第一行的 `prev_hash`
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// 单次查询/导出的最大行数
+pub const MAX_QUERY_ROWS: usize = 100_000;
+
+/// 串行化 "读最后一行哈希 + 插入"，防止同进程内并发写出分叉的链
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 待写入的审计事件
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    /// `admin` / `unauthenticated` / `desktop`
+    pub actor: String,
+    /// `admin_api` / `tauri`
+    pub source: String,
+    pub ip: Option<String>,
+    /// 如 `DELETE /api/accounts/:id` 或 `delete_account`
+    pub action: String,
+    /// 受影响的对象 (账号 ID、令牌 ID 等)
+    pub target: Option<String>,
+    /// 已脱敏的参数 / 配置 diff
+    pub details: Value,
+    pub success: bool,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+}
+
+/// 已落库的审计事件
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub timestamp: i64,
+    pub actor: String,
+    pub source: String,
+    pub ip: Option<String>,
+    pub action: String,
+    pub target: Option<String>,
+    pub details: Value,
+    pub success: bool,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+/// 查询条件，全部可选
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AuditQuery {
+    /// 起始时间 (Unix 秒，含)
+    pub since: Option<i64>,
+    /// 结束时间 (Unix 秒，含)
+    pub until: Option<i64>,
+    pub actor: Option<String>,
+    /// 子串匹配 action
+    pub action: Option<String>,
+    pub target: Option<String>,
+    pub ip: Option<String>,
+    pub success: Option<bool>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// 分页查询结果
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditLogPage {
+    pub entries: Vec<AuditEntry>,
+    /// 满足条件的总条数 (不受 limit/offset 影响)
+    pub total: u64,
+}
+
+/// 哈希链校验结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditChainStatus {
+    pub total: u64,
+    pub valid: bool,
+    /// 第一条哈希不匹配的记录
+    pub first_broken_id: Option<i64>,
+}
+
+pub fn get_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("audit.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let conn = Connection::open(get_db_path()?).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            actor TEXT NOT NULL,
+            source TEXT NOT NULL,
+            ip TEXT,
+            action TEXT NOT NULL,
+            target TEXT,
+            details TEXT NOT NULL,
+            success INTEGER NOT NULL,
+            status_code INTEGER,
+            error TEXT,
+            prev_hash TEXT NOT NULL,
+            hash TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_audit_log_timestamp ON audit_log (timestamp DESC);
+        CREATE INDEX IF NOT EXISTS idx_audit_log_action ON audit_log (action);
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, 'audit_log is append-only');
+        END;
+        CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log
+        BEGIN
+            SELECT RAISE(ABORT, 'audit_log is append-only');
+        END;",
+    )
+    .map_err(|e| format!("Failed to create audit_log table: {}", e))
+}
+
+/// 初始化数据库
+pub fn init_db() -> Result<(), String> {
+    create_tables(&connect_db()?)
+}
+
+/// 行哈希：覆盖除 id 以外的全部字段，字段之间用 0x1f 分隔
+fn compute_hash(prev_hash: &str, timestamp: i64, record: &AuditRecord, details: &str) -> String {
+    let fields = [
+        prev_hash.to_string(),
+        timestamp.to_string(),
+        record.actor.clone(),
+        record.source.clone(),
+        record.ip.clone().unwrap_or_default(),
+        record.action.clone(),
+        record.target.clone().unwrap_or_default(),
+        details.to_string(),
+        (record.success as u8).to_string(),
+        record.status_code.map(|c| c.to_string()).unwrap_or_default(),
+        record.error.clone().unwrap_or_default(),
+    ];
+    Sha256::digest(fields.join("\u{1f}").as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn append_at(conn: &mut Connection, record: &AuditRecord, timestamp: i64) -> Result<i64, String> {
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| e.to_string())?;
+
+    let prev_hash: String = tx
+        .query_row("SELECT hash FROM audit_log ORDER BY id DESC LIMIT 1", [], |row| row.get(0))
+        .unwrap_or_else(|_| GENESIS_HASH.to_string());
+    let details = record.details.to_string();
+    let hash = compute_hash(&prev_hash, timestamp, record, &details);
+
+    tx.execute(
+        "INSERT INTO audit_log
+            (timestamp, actor, source, ip, action, target, details, success,
+             status_code, error, prev_hash, hash)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        params![
+            timestamp,
+            record.actor,
+            record.source,
+            record.ip,
+            record.action,
+            record.target,
+            details,
+            record.success,
+            record.status_code,
+            record.error,
+            prev_hash,
+            hash,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = tx.last_insert_rowid();
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+fn build_where(query: &AuditQuery) -> (String, Vec<rusqlite::types::Value>) {
+    use rusqlite::types::Value as Sql;
+
+    let mut clauses = Vec::new();
+    let mut args: Vec<Sql> = Vec::new();
+    if let Some(since) = query.since {
+        clauses.push("timestamp >= ?");
+        args.push(Sql::Integer(since));
+    }
+    if let Some(until) = query.until {
+        clauses.push("timestamp <= ?");
+        args.push(Sql::Integer(until));
+    }
+    if let Some(actor) = query.actor.as_ref().filter(|s| !s.is_empty()) {
+        clauses.push("actor = ?");
+        args.push(Sql::Text(actor.clone()));
+    }
+    if let Some(action) = query.action.as_ref().filter(|s| !s.is_empty()) {
+        clauses.push("action LIKE ?");
+        args.push(Sql::Text(format!("%{}%", action)));
+    }
+    if let Some(target) = query.target.as_ref().filter(|s| !s.is_empty()) {
+        clauses.push("target LIKE ?");
+        args.push(Sql::Text(format!("%{}%", target)));
+    }
+    if let Some(ip) = query.ip.as_ref().filter(|s| !s.is_empty()) {
+        clauses.push("ip = ?");
+        args.push(Sql::Text(ip.clone()));
+    }
+    if let Some(success) = query.success {
+        clauses.push("success = ?");
+        args.push(Sql::Integer(success as i64));
+    }
+
+    let sql = if clauses.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", clauses.join(" AND "))
+    };
+    (sql, args)
+}
+
+fn row_to_entry(row: &rusqlite::Row) -> rusqlite::Result<AuditEntry> {
+    let details: String = row.get(7)?;
+    Ok(AuditEntry {
+        id: row.get(0)?,
+        timestamp: row.get(1)?,
+        actor: row.get(2)?,
+        source: row.get(3)?,
+        ip: row.get(4)?,
+        action: row.get(5)?,
+        target: row.get(6)?,
+        details: serde_json::from_str(&details).unwrap_or(Value::String(details)),
+        success: row.get(8)?,
+        status_code: row.get(9)?,
+        error: row.get(10)?,
+        prev_hash: row.get(11)?,
+        hash: row.get(12)?,
+    })
+}
+
+const SELECT_COLUMNS: &str = "SELECT id, timestamp, actor, source, ip, action, target, details, success,
+        status_code, error, prev_hash, hash FROM audit_log";
+
+fn query_at(conn: &Connection, query: &AuditQuery) -> Result<Vec<AuditEntry>, String> {
+    let (where_sql, mut args) = build_where(query);
+    let limit = query.limit.unwrap_or(100).clamp(1, MAX_QUERY_ROWS);
+    args.push(rusqlite::types::Value::Integer(limit as i64));
+    args.push(rusqlite::types::Value::Integer(query.offset.unwrap_or(0) as i64));
+
+    let sql = format!("{}{} ORDER BY id DESC LIMIT ? OFFSET ?", SELECT_COLUMNS, where_sql);
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params_from_iter(args), row_to_entry)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn count_at(conn: &Connection, query: &AuditQuery) -> Result<u64, String> {
+    let (where_sql, args) = build_where(query);
+    let sql = format!("SELECT COUNT(*) FROM audit_log{}", where_sql);
+    conn.query_row(&sql, params_from_iter(args), |row| row.get::<_, i64>(0))
+        .map(|n| n.max(0) as u64)
+        .map_err(|e| e.to_string())
+}
+
+fn verify_at(conn: &Connection) -> Result<AuditChainStatus, String> {
+    let mut stmt = conn
+        .prepare(&format!("{} ORDER BY id ASC", SELECT_COLUMNS))
+        .map_err(|e| e.to_string())?;
+    let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
+
+    let mut expected_prev = GENESIS_HASH.to_string();
+    let mut total = 0u64;
+    let mut first_broken_id = None;
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        total += 1;
+        if first_broken_id.is_some() {
+            continue;
+        }
+        let raw_details: String = row.get(7).map_err(|e| e.to_string())?;
+        let entry = row_to_entry(row).map_err(|e| e.to_string())?;
+        let record = AuditRecord {
+            actor: entry.actor,
+            source: entry.source,
+            ip: entry.ip,
+            action: entry.action,
+            target: entry.target,
+            details: Value::Null,
+            success: entry.success,
+            status_code: entry.status_code,
+            error: entry.error,
+        };
+        let hash = compute_hash(&entry.prev_hash, entry.timestamp, &record, &raw_details);
+        if entry.prev_hash != expected_prev || hash != entry.hash {
+            first_broken_id = Some(entry.id);
+        }
+        expected_prev = entry.hash;
+    }
+
+    Ok(AuditChainStatus {
+        total,
+        valid: first_broken_id.is_none(),
+        first_broken_id,
+    })
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 按 CSV 导出 (details 为 JSON 字符串列)
+pub fn entries_to_csv(entries: &[AuditEntry]) -> String {
+    let mut out = String::from(
+        "id,time,actor,source,ip,action,target,success,status_code,error,details,hash\n",
+    );
+    for e in entries {
+        let time = chrono::DateTime::from_timestamp(e.timestamp, 0)
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_else(|| e.timestamp.to_string());
+        let fields = [
+            e.id.to_string(),
+            time,
+            e.actor.clone(),
+            e.source.clone(),
+            e.ip.clone().unwrap_or_default(),
+            e.action.clone(),
+            e.target.clone().unwrap_or_default(),
+            e.success.to_string(),
+            e.status_code.map(|c| c.to_string()).unwrap_or_default(),
+            e.error.clone().unwrap_or_default(),
+            e.details.to_string(),
+            e.hash.clone(),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&line.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+/// 追加一条审计记录，返回行 ID
+pub fn append(record: &AuditRecord) -> Result<i64, String> {
+    append_at(&mut connect_db()?, record, chrono::Utc::now().timestamp())
+}
+
+/// 按条件倒序查询
+pub fn query(query: &AuditQuery) -> Result<Vec<AuditEntry>, String> {
+    query_at(&connect_db()?, query)
+}
+
+/// 满足条件的总条数
+pub fn count(query: &AuditQuery) -> Result<u64, String> {
+    count_at(&connect_db()?, query)
+}
+
+/// 分页查询并附带总数
+pub fn query_page(query: &AuditQuery) -> Result<AuditLogPage, String> {
+    let conn = connect_db()?;
+    Ok(AuditLogPage {
+        entries: query_at(&conn, query)?,
+        total: count_at(&conn, query)?,
+    })
+}
+
+/// 按条件导出 (`csv` 或 `json`)，未指定 limit 时导出全部 (上限 `MAX_QUERY_ROWS`)
+pub fn export(query: &AuditQuery, format: &str) -> Result<String, String> {
+    let mut query = query.clone();
+    query.limit = Some(query.limit.unwrap_or(MAX_QUERY_ROWS));
+    let entries = query_at(&connect_db()?, &query)?;
+    match format {
+        "csv" => Ok(entries_to_csv(&entries)),
+        "json" => serde_json::to_string_pretty(&entries).map_err(|e| e.to_string()),
+        other => Err(format!("Unsupported export format: {}", other)),
+    }
+}
+
+/// 从头校验整条哈希链
+pub fn verify_chain() -> Result<AuditChainStatus, String> {
+    verify_at(&connect_db()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(action: &str, success: bool) -> AuditRecord {
+        AuditRecord {
+            actor: "admin".to_string(),
+            source: "admin_api".to_string(),
+            ip: Some("10.0.0.1".to_string()),
+            action: action.to_string(),
+            target: Some("acc-1".to_string()),
+            details: serde_json::json!({ "changes": ["proxy.port: 8045 -> 8046"] }),
+            success,
+            status_code: Some(if success { 200 } else { 500 }),
+            error: None,
+        }
+    }
+
+    fn test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_append_query_and_filter() {
+        let mut conn = test_db();
+        append_at(&mut conn, &record("POST /api/config", true), 100).unwrap();
+        append_at(&mut conn, &record("DELETE /api/accounts/acc-1", false), 200).unwrap();
+        append_at(&mut conn, &record("DELETE /api/accounts/acc-2", true), 300).unwrap();
+
+        let all = query_at(&conn, &AuditQuery::default()).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].timestamp, 300);
+        assert_eq!(all[2].details["changes"][0], "proxy.port: 8045 -> 8046");
+
+        let deletes = AuditQuery {
+            action: Some("accounts".to_string()),
+            success: Some(true),
+            ..Default::default()
+        };
+        let rows = query_at(&conn, &deletes).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].action, "DELETE /api/accounts/acc-2");
+        assert_eq!(count_at(&conn, &deletes).unwrap(), 1);
+
+        let window = AuditQuery {
+            since: Some(150),
+            until: Some(250),
+            ..Default::default()
+        };
+        assert_eq!(count_at(&conn, &window).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_log_is_append_only() {
+        let mut conn = test_db();
+        append_at(&mut conn, &record("POST /api/config", true), 100).unwrap();
+
+        assert!(conn.execute("UPDATE audit_log SET actor = 'x'", []).is_err());
+        assert!(conn.execute("DELETE FROM audit_log", []).is_err());
+        assert_eq!(count_at(&conn, &AuditQuery::default()).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_hash_chain_detects_tampering() {
+        let mut conn = test_db();
+        for i in 0..3 {
+            append_at(&mut conn, &record("POST /api/config", true), 100 + i).unwrap();
+        }
+        let status = verify_at(&conn).unwrap();
+        assert!(status.valid);
+        assert_eq!(status.total, 3);
+
+        // 绕过触发器篡改第二行
+        conn.execute_batch(
+            "DROP TRIGGER audit_log_no_update;
+             UPDATE audit_log SET success = 0 WHERE id = 2;",
+        )
+        .unwrap();
+        let status = verify_at(&conn).unwrap();
+        assert!(!status.valid);
+        assert_eq!(status.first_broken_id, Some(2));
+    }
+
+    #[test]
+    fn test_csv_export_escapes_fields() {
+        let mut conn = test_db();
+        let mut r = record("POST /api/config", false);
+        r.error = Some("bad \"value\", retry".to_string());
+        append_at(&mut conn, &r, 0).unwrap();
+
+        let csv = entries_to_csv(&query_at(&conn, &AuditQuery::default()).unwrap());
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("id,time,actor"));
+        let row = lines.next().unwrap();
+        assert!(row.starts_with("1,1970-01-01T00:00:00+00:00,admin,admin_api,10.0.0.1,"));
+        assert!(row.contains("\"bad \"\"value\"\", retry\""));
+    }
+}
@@ -0,0 +1,799 @@
+//! 全量备份 / 恢复
+//!
+//! 备份包是一个带版本号的 JSON 文档 (可选用口令整体加密，复用 `account_crypto::seal_export`)，
+//! 每个条目记录所属分区、相对路径、SHA-256 与 Base64 内容:
+//!
+//! - `config`: gui_config.json (代理池密码以明文写入包内，恢复时由本机密钥重新加密)、
+//!   http_api_settings.json、update_settings.json
+//! - `accounts`: 账号文件 (含设备指纹 / 历史版本，已解密) 与 device_original.json
+//! - `user_tokens` / `security` / `token_stats`: 对应 SQLite 数据库 (`VACUUM INTO` 一致性快照)
+//! - `cli_backups`: CLI 配置同步时生成的 `*.antigravity.bak`
+//!
+//! 恢复支持按分区 / 按账号选择，以及只生成报告不落盘的 dry-run。
+
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::models::{Account, AppConfig};
+use crate::proxy::cli_sync::CliApp;
+
+pub const BACKUP_FORMAT: &str = "antigravity-manager-backup";
+pub const BACKUP_VERSION: u32 = 1;
+
+const CONFIG_FILES: &[&str] = &["gui_config.json", "http_api_settings.json", "update_settings.json"];
+const DEVICE_BASELINE: &str = "device_original.json";
+const CLI_APPS: &[CliApp] = &[CliApp::Claude, CliApp::Codex, CliApp::Gemini, CliApp::OpenCode];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupSection {
+    Config,
+    Accounts,
+    UserTokens,
+    Security,
+    TokenStats,
+    CliBackups,
+}
+
+impl BackupSection {
+    /// 恢复顺序: 账号先于配置，以便重写代理池绑定中的账号 ID
+    pub const ALL: [BackupSection; 6] = [
+        BackupSection::Accounts,
+        BackupSection::Config,
+        BackupSection::UserTokens,
+        BackupSection::Security,
+        BackupSection::TokenStats,
+        BackupSection::CliBackups,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackupSection::Config => "config",
+            BackupSection::Accounts => "accounts",
+            BackupSection::UserTokens => "user_tokens",
+            BackupSection::Security => "security",
+            BackupSection::TokenStats => "token_stats",
+            BackupSection::CliBackups => "cli_backups",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.as_str() == value.trim())
+    }
+
+    fn db_file(&self) -> Option<&'static str> {
+        match self {
+            BackupSection::UserTokens => Some("user_tokens.db"),
+            BackupSection::Security => Some("security.db"),
+            BackupSection::TokenStats => Some("token_stats.db"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupEntry {
+    pub section: BackupSection,
+    /// 相对路径 (数据目录内的文件名 / `accounts/<id>.json` / `cli/<app>/<name>`)
+    pub path: String,
+    pub sha256: String,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupBundle {
+    pub format: String,
+    pub version: u32,
+    pub app_version: String,
+    pub created_at: i64,
+    pub sections: Vec<BackupSection>,
+    pub entries: Vec<BackupEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupSummary {
+    pub sections: Vec<BackupSection>,
+    pub entries: usize,
+    pub bytes: usize,
+    pub encrypted: bool,
+}
+
+/// 备份包内容 + 摘要 (前端 / Admin API 自行保存为文件)
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupArchive {
+    pub content: String,
+    pub summary: BackupSummary,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RestoreOptions {
+    /// 只恢复这些分区 (缺省为包内全部)
+    #[serde(default)]
+    pub sections: Option<Vec<BackupSection>>,
+    /// 只恢复这些账号 (ID 或邮箱，仅作用于 accounts 分区)
+    #[serde(default)]
+    pub accounts: Option<Vec<String>>,
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub passphrase: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RestoreAction {
+    Create,
+    Overwrite,
+    Unchanged,
+    Skip,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreItem {
+    pub section: BackupSection,
+    pub path: String,
+    pub action: RestoreAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreReport {
+    pub dry_run: bool,
+    pub bundle_version: u32,
+    pub app_version: String,
+    pub created_at: i64,
+    pub items: Vec<RestoreItem>,
+    pub warnings: Vec<String>,
+}
+
+impl RestoreAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RestoreAction::Create => "create",
+            RestoreAction::Overwrite => "overwrite",
+            RestoreAction::Unchanged => "unchanged",
+            RestoreAction::Skip => "skip",
+        }
+    }
+}
+
+impl RestoreItem {
+    fn is_change(&self) -> bool {
+        matches!(self.action, RestoreAction::Create | RestoreAction::Overwrite)
+    }
+}
+
+impl RestoreReport {
+    /// 该分区是否有文件被写入 (调用方据此决定是否热更新配置 / 重载账号)
+    pub fn changed(&self, section: BackupSection) -> bool {
+        self.items.iter().any(|i| i.section == section && i.is_change())
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn entry(section: BackupSection, path: String, bytes: &[u8]) -> BackupEntry {
+    BackupEntry {
+        section,
+        path,
+        sha256: sha256_hex(bytes),
+        data: general_purpose::STANDARD.encode(bytes),
+    }
+}
+
+// ===== 创建备份 =====
+
+/// 配置中的代理池密码用本机 machine-uid 密钥加密，换机后无法解开；包内改存明文
+fn portable_config_json(config: &AppConfig) -> Result<Vec<u8>, String> {
+    let mut value = serde_json::to_value(config).map_err(|e| e.to_string())?;
+    if let Some(proxies) = value
+        .pointer_mut("/proxy/proxy_pool/proxies")
+        .and_then(|v| v.as_array_mut())
+    {
+        for proxy in proxies {
+            if let Some(password) = proxy.pointer_mut("/auth/password") {
+                if let Some(plain) = password
+                    .as_str()
+                    .and_then(|p| crate::utils::crypto::decrypt_string(p).ok())
+                {
+                    *password = Value::String(plain);
+                }
+            }
+        }
+    }
+    serde_json::to_vec_pretty(&value).map_err(|e| e.to_string())
+}
+
+/// SQLite 一致性快照 (WAL 模式下直接复制文件可能丢失未 checkpoint 的数据)
+fn snapshot_db(db_path: &Path) -> Result<Vec<u8>, String> {
+    let tmp = db_path.with_extension(format!("backup-{}.db", uuid::Uuid::new_v4()));
+    let result = (|| {
+        let conn = rusqlite::Connection::open(db_path).map_err(|e| e.to_string())?;
+        conn.execute("VACUUM INTO ?1", [tmp.to_string_lossy().to_string()])
+            .map_err(|e| format!("VACUUM INTO failed: {}", e))?;
+        fs::read(&tmp).map_err(|e| e.to_string())
+    })();
+    let _ = fs::remove_file(&tmp);
+    result.map_err(|e| format!("failed to snapshot {}: {}", db_path.display(), e))
+}
+
+fn collect_section(
+    data_dir: &Path,
+    section: BackupSection,
+    entries: &mut Vec<BackupEntry>,
+) -> Result<(), String> {
+    match section {
+        BackupSection::Config => {
+            for name in CONFIG_FILES {
+                let path = data_dir.join(name);
+                if !path.exists() {
+                    continue;
+                }
+                let bytes = if *name == "gui_config.json" {
+                    portable_config_json(&crate::modules::config::load_app_config_in_dir(data_dir)?)?
+                } else {
+                    fs::read(&path).map_err(|e| e.to_string())?
+                };
+                entries.push(entry(section, name.to_string(), &bytes));
+            }
+        }
+        BackupSection::Accounts => {
+            let accounts_dir = data_dir.join("accounts");
+            if let Ok(read_dir) = fs::read_dir(&accounts_dir) {
+                let mut paths: Vec<PathBuf> = read_dir
+                    .flatten()
+                    .map(|e| e.path())
+                    .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("json"))
+                    .collect();
+                paths.sort();
+                for path in paths {
+                    // 账号文件可能已静态加密；包内存明文 JSON，恢复时按目标机器的密钥重新加密
+                    let json = crate::modules::account_crypto::read_account_file(&path)
+                        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    entries.push(entry(section, format!("accounts/{}", name), json.as_bytes()));
+                }
+            }
+            let baseline = data_dir.join(DEVICE_BASELINE);
+            if baseline.exists() {
+                let bytes = fs::read(&baseline).map_err(|e| e.to_string())?;
+                entries.push(entry(section, DEVICE_BASELINE.to_string(), &bytes));
+            }
+        }
+        BackupSection::UserTokens | BackupSection::Security | BackupSection::TokenStats => {
+            let name = section.db_file().unwrap_or_default();
+            let path = data_dir.join(name);
+            if path.exists() {
+                entries.push(entry(section, name.to_string(), &snapshot_db(&path)?));
+            }
+        }
+        BackupSection::CliBackups => {
+            for app in CLI_APPS {
+                for file in app.config_files() {
+                    let backup = file.backup_path();
+                    if let Ok(bytes) = fs::read(&backup) {
+                        entries.push(entry(section, format!("cli/{}/{}", app.as_str(), file.name), &bytes));
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn create_backup_in_dir(
+    data_dir: &Path,
+    sections: &[BackupSection],
+    passphrase: Option<&str>,
+) -> Result<BackupArchive, String> {
+    let sections: Vec<BackupSection> = BackupSection::ALL
+        .into_iter()
+        .filter(|s| sections.is_empty() || sections.contains(s))
+        .collect();
+    let mut entries = Vec::new();
+    for section in &sections {
+        collect_section(data_dir, *section, &mut entries)?;
+    }
+
+    let bundle = BackupBundle {
+        format: BACKUP_FORMAT.to_string(),
+        version: BACKUP_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: chrono::Utc::now().timestamp(),
+        sections: sections.clone(),
+        entries,
+    };
+    let mut content = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    let encrypted = passphrase.map(|p| !p.is_empty()).unwrap_or(false);
+    if encrypted {
+        content = crate::modules::account_crypto::seal_export(&content, passphrase.unwrap_or_default())?;
+    }
+    let summary = BackupSummary {
+        sections,
+        entries: bundle.entries.len(),
+        bytes: content.len(),
+        encrypted,
+    };
+    Ok(BackupArchive { content, summary })
+}
+
+/// 生成备份包内容；`sections` 为空表示全部分区
+pub fn create_backup(sections: &[BackupSection], passphrase: Option<&str>) -> Result<BackupArchive, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    create_backup_in_dir(&data_dir, sections, passphrase)
+}
+
+// ===== 恢复 =====
+
+pub fn parse_bundle(content: &str, passphrase: Option<&str>) -> Result<BackupBundle, String> {
+    let content = if crate::modules::account_crypto::is_sealed_export(content) {
+        let passphrase = passphrase
+            .filter(|p| !p.is_empty())
+            .ok_or("backup is encrypted; a passphrase is required")?;
+        crate::modules::account_crypto::open_export(content, passphrase)?
+    } else {
+        content.to_string()
+    };
+    let bundle: BackupBundle =
+        serde_json::from_str(&content).map_err(|e| format!("invalid backup file: {}", e))?;
+    if bundle.format != BACKUP_FORMAT {
+        return Err(format!("not a backup file (format: {})", bundle.format));
+    }
+    if bundle.version > BACKUP_VERSION {
+        return Err(format!(
+            "backup version {} is newer than supported version {}; upgrade first",
+            bundle.version, BACKUP_VERSION
+        ));
+    }
+    Ok(bundle)
+}
+
+fn decode_entry(entry: &BackupEntry) -> Result<Vec<u8>, String> {
+    let bytes = general_purpose::STANDARD
+        .decode(&entry.data)
+        .map_err(|e| format!("{}: invalid data: {}", entry.path, e))?;
+    if sha256_hex(&bytes) != entry.sha256 {
+        return Err(format!("{}: checksum mismatch", entry.path));
+    }
+    Ok(bytes)
+}
+
+/// 比较目标文件决定动作
+fn file_action(target: &Path, bytes: &[u8]) -> RestoreAction {
+    match fs::read(target) {
+        Ok(existing) if existing == bytes => RestoreAction::Unchanged,
+        Ok(_) => RestoreAction::Overwrite,
+        Err(_) => RestoreAction::Create,
+    }
+}
+
+/// 替换数据库文件，并清理旧的 WAL / SHM，避免旧日志被回放到新库上
+fn replace_db(target: &Path, bytes: &[u8]) -> Result<(), String> {
+    let tmp = target.with_extension("db.restore");
+    fs::write(&tmp, bytes).map_err(|e| e.to_string())?;
+    for suffix in ["-wal", "-shm"] {
+        let mut side = target.as_os_str().to_owned();
+        side.push(suffix);
+        let _ = fs::remove_file(PathBuf::from(side));
+    }
+    fs::rename(&tmp, target).map_err(|e| e.to_string())
+}
+
+/// 一次恢复过程中的共享状态
+struct RestoreContext<'a> {
+    data_dir: &'a Path,
+    dry_run: bool,
+    account_filter: Option<&'a [String]>,
+    /// 备份中的账号 ID -> 本地账号 ID (同邮箱账号沿用本地 ID)
+    id_remap: HashMap<String, String>,
+}
+
+fn restore_account_entry(
+    ctx: &mut RestoreContext,
+    entry: &BackupEntry,
+    bytes: &[u8],
+) -> Result<RestoreItem, String> {
+    let item = |action, detail: Option<String>| RestoreItem {
+        section: entry.section,
+        path: entry.path.clone(),
+        action,
+        detail,
+    };
+
+    if entry.path == DEVICE_BASELINE {
+        // 全局原始指纹只在本机没有时写入 (与 device::save_global_original 语义一致)
+        let target = ctx.data_dir.join(DEVICE_BASELINE);
+        if target.exists() {
+            return Ok(item(RestoreAction::Skip, Some("local baseline kept".to_string())));
+        }
+        if !ctx.dry_run {
+            fs::write(&target, bytes).map_err(|e| e.to_string())?;
+        }
+        return Ok(item(RestoreAction::Create, None));
+    }
+
+    let account: Account = serde_json::from_slice(bytes)
+        .map_err(|e| format!("{}: invalid account file: {}", entry.path, e))?;
+    if let Some(filter) = ctx.account_filter {
+        if !filter
+            .iter()
+            .any(|k| *k == account.id || k.eq_ignore_ascii_case(&account.email))
+        {
+            return Ok(item(RestoreAction::Skip, Some(format!("{} not selected", account.email))));
+        }
+    }
+
+    let backup_id = account.id.clone();
+    let local_id =
+        crate::modules::account::find_local_account_id(ctx.data_dir, &account.id, &account.email)?;
+    let (action, final_id) = match &local_id {
+        Some(id) => (RestoreAction::Overwrite, id.clone()),
+        None => (RestoreAction::Create, backup_id.clone()),
+    };
+    let final_id = if ctx.dry_run {
+        final_id
+    } else {
+        crate::modules::account::restore_account_snapshot(ctx.data_dir, account.clone())?.0
+    };
+    let detail = if final_id != backup_id {
+        ctx.id_remap.insert(backup_id.clone(), final_id.clone());
+        Some(format!("{} (local id {})", account.email, final_id))
+    } else {
+        Some(account.email.clone())
+    };
+    Ok(item(action, detail))
+}
+
+fn restore_config_entry(
+    ctx: &RestoreContext,
+    entry: &BackupEntry,
+    bytes: &[u8],
+) -> Result<RestoreItem, String> {
+    let target = ctx.data_dir.join(&entry.path);
+    let mut detail = None;
+
+    if entry.path == "gui_config.json" {
+        let mut config: AppConfig = serde_json::from_slice(bytes)
+            .map_err(|e| format!("gui_config.json: invalid config: {}", e))?;
+        // 同邮箱账号沿用了本地 ID，代理池绑定需要跟着改
+        let bindings = &mut config.proxy.proxy_pool.account_bindings;
+        let remapped: Vec<(String, String)> = ctx
+            .id_remap
+            .iter()
+            .filter(|(from, _)| bindings.contains_key(*from))
+            .map(|(f, t)| (f.clone(), t.clone()))
+            .collect();
+        for (from, to) in &remapped {
+            if let Some(proxy_id) = bindings.remove(from) {
+                bindings.insert(to.clone(), proxy_id);
+            }
+        }
+        if !remapped.is_empty() {
+            detail = Some(format!("{} proxy binding(s) remapped to local account ids", remapped.len()));
+        }
+        // 目标不存在时不加载 (加载会写入默认配置，dry-run 也不应落盘)
+        let current = if target.exists() {
+            crate::modules::config::load_app_config_in_dir(ctx.data_dir).ok()
+        } else {
+            None
+        };
+        let unchanged = current
+            .as_ref()
+            .and_then(|c| serde_json::to_value(c).ok())
+            .zip(serde_json::to_value(&config).ok())
+            .map(|(a, b)| a == b)
+            .unwrap_or(false);
+        let action = match (current.is_some() && target.exists(), unchanged) {
+            (_, true) => RestoreAction::Unchanged,
+            (true, false) => RestoreAction::Overwrite,
+            (false, false) => RestoreAction::Create,
+        };
+        if !ctx.dry_run && action != RestoreAction::Unchanged {
+            crate::modules::config::save_app_config_in_dir(ctx.data_dir, &config)?;
+        }
+        return Ok(RestoreItem {
+            section: entry.section,
+            path: entry.path.clone(),
+            action,
+            detail,
+        });
+    }
+
+    if !CONFIG_FILES.contains(&entry.path.as_str()) {
+        return Ok(RestoreItem {
+            section: entry.section,
+            path: entry.path.clone(),
+            action: RestoreAction::Skip,
+            detail: Some("unknown config file".to_string()),
+        });
+    }
+    let action = file_action(&target, bytes);
+    if !ctx.dry_run && action != RestoreAction::Unchanged {
+        fs::write(&target, bytes).map_err(|e| e.to_string())?;
+    }
+    Ok(RestoreItem {
+        section: entry.section,
+        path: entry.path.clone(),
+        action,
+        detail,
+    })
+}
+
+fn restore_db_entry(ctx: &RestoreContext, entry: &BackupEntry, bytes: &[u8]) -> Result<RestoreItem, String> {
+    let expected = entry.section.db_file().unwrap_or_default();
+    if entry.path != expected {
+        return Ok(RestoreItem {
+            section: entry.section,
+            path: entry.path.clone(),
+            action: RestoreAction::Skip,
+            detail: Some("unexpected database file".to_string()),
+        });
+    }
+    let target = ctx.data_dir.join(expected);
+    let action = file_action(&target, bytes);
+    if !ctx.dry_run && action != RestoreAction::Unchanged {
+        replace_db(&target, bytes)?;
+        // 旧版本备份可能缺少新增列，重新执行建表 / 迁移
+        let migrated = match entry.section {
+            BackupSection::UserTokens => crate::modules::user_token_db::init_db(),
+            BackupSection::Security => crate::modules::security_db::init_db(),
+            BackupSection::TokenStats => crate::modules::token_stats::init_db(),
+            _ => Ok(()),
+        };
+        migrated.map_err(|e| format!("{}: migration after restore failed: {}", expected, e))?;
+    }
+    Ok(RestoreItem {
+        section: entry.section,
+        path: entry.path.clone(),
+        action,
+        detail: None,
+    })
+}
+
+fn restore_cli_entry(ctx: &RestoreContext, entry: &BackupEntry, bytes: &[u8]) -> Result<RestoreItem, String> {
+    let mut item = RestoreItem {
+        section: entry.section,
+        path: entry.path.clone(),
+        action: RestoreAction::Skip,
+        detail: None,
+    };
+    let target = entry
+        .path
+        .strip_prefix("cli/")
+        .and_then(|rest| rest.split_once('/'))
+        .and_then(|(app, name)| {
+            CLI_APPS
+                .iter()
+                .find(|a| a.as_str() == app)
+                .and_then(|a| a.config_files().into_iter().find(|f| f.name == name))
+        })
+        .map(|f| f.backup_path());
+    let Some(target) = target else {
+        item.detail = Some("unknown CLI file or no home directory".to_string());
+        return Ok(item);
+    };
+
+    item.action = file_action(&target, bytes);
+    item.detail = Some(target.display().to_string());
+    if !ctx.dry_run && item.action != RestoreAction::Unchanged {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(&target, bytes).map_err(|e| e.to_string())?;
+    }
+    Ok(item)
+}
+
+fn restore_bundle_in_dir(
+    data_dir: &Path,
+    bundle: &BackupBundle,
+    options: &RestoreOptions,
+) -> Result<RestoreReport, String> {
+    let mut ctx = RestoreContext {
+        data_dir,
+        dry_run: options.dry_run,
+        account_filter: options.accounts.as_deref(),
+        id_remap: HashMap::new(),
+    };
+    let mut report = RestoreReport {
+        dry_run: options.dry_run,
+        bundle_version: bundle.version,
+        app_version: bundle.app_version.clone(),
+        created_at: bundle.created_at,
+        items: Vec::new(),
+        warnings: Vec::new(),
+    };
+    if bundle.app_version != env!("CARGO_PKG_VERSION") {
+        report.warnings.push(format!(
+            "backup was created by version {}, running {}",
+            bundle.app_version,
+            env!("CARGO_PKG_VERSION")
+        ));
+    }
+
+    let selected: Vec<BackupSection> = BackupSection::ALL
+        .into_iter()
+        .filter(|s| bundle.sections.contains(s))
+        .filter(|s| options.sections.as_ref().map(|sel| sel.contains(s)).unwrap_or(true))
+        .collect();
+    if let Some(requested) = &options.sections {
+        for s in requested.iter().filter(|s| !bundle.sections.contains(s)) {
+            report.warnings.push(format!("section '{}' is not in this backup", s.as_str()));
+        }
+    }
+
+    // 先校验全部条目，任何损坏都在写入之前中止
+    let mut decoded = Vec::new();
+    for section in &selected {
+        for entry in bundle.entries.iter().filter(|e| e.section == *section) {
+            decoded.push((entry, decode_entry(entry)?));
+        }
+    }
+
+    for (entry, bytes) in &decoded {
+        let item = match entry.section {
+            BackupSection::Accounts => restore_account_entry(&mut ctx, entry, bytes)?,
+            BackupSection::Config => restore_config_entry(&ctx, entry, bytes)?,
+            BackupSection::UserTokens | BackupSection::Security | BackupSection::TokenStats => {
+                restore_db_entry(&ctx, entry, bytes)?
+            }
+            BackupSection::CliBackups => restore_cli_entry(&ctx, entry, bytes)?,
+        };
+        report.items.push(item);
+    }
+
+    if !options.dry_run {
+        crate::modules::logger::log_info(&format!(
+            "[Backup] Restored {} item(s) from backup created at {}",
+            report.items.iter().filter(|i| i.is_change()).count(),
+            bundle.created_at
+        ));
+    }
+    Ok(report)
+}
+
+/// 恢复备份包 (dry_run 时只返回报告)
+pub fn restore_backup(content: &str, options: &RestoreOptions) -> Result<RestoreReport, String> {
+    let bundle = parse_bundle(content, options.passphrase.as_deref())?;
+    let data_dir = crate::modules::account::get_data_dir()?;
+    restore_bundle_in_dir(&data_dir, &bundle, options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ag_backup_test_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_db_snapshot_roundtrip_and_dry_run() {
+        let src = temp_dir();
+        let conn = rusqlite::Connection::open(src.join("security.db")).unwrap();
+        conn.pragma_update(None, "journal_mode", "WAL").unwrap();
+        conn.execute_batch("CREATE TABLE ip_blacklist (ip TEXT); INSERT INTO ip_blacklist VALUES ('1.2.3.4');")
+            .unwrap();
+
+        let archive = create_backup_in_dir(&src, &[BackupSection::Security], Some("pw")).unwrap();
+        drop(conn);
+        assert!(archive.summary.encrypted);
+        assert_eq!(archive.summary.entries, 1);
+        assert!(parse_bundle(&archive.content, None).is_err());
+        let bundle = parse_bundle(&archive.content, Some("pw")).unwrap();
+
+        let dst = temp_dir();
+        let dry = RestoreOptions { dry_run: true, ..Default::default() };
+        let report = restore_bundle_in_dir(&dst, &bundle, &dry).unwrap();
+        assert_eq!(report.items.len(), 1);
+        assert_eq!(report.items[0].action, RestoreAction::Create);
+        assert!(!dst.join("security.db").exists());
+
+        // 只选择包内不存在的分区: 不做任何事并给出警告
+        let other = RestoreOptions {
+            sections: Some(vec![BackupSection::TokenStats]),
+            dry_run: true,
+            ..Default::default()
+        };
+        let report = restore_bundle_in_dir(&dst, &bundle, &other).unwrap();
+        assert!(report.items.is_empty());
+        assert_eq!(report.warnings.iter().filter(|w| w.contains("token_stats")).count(), 1);
+
+        // 损坏的条目在写入前被拒绝
+        let mut corrupted = bundle.clone();
+        corrupted.entries[0].sha256 = "00".to_string();
+        assert!(restore_bundle_in_dir(&dst, &corrupted, &dry).is_err());
+
+        let _ = fs::remove_dir_all(&src);
+        let _ = fs::remove_dir_all(&dst);
+    }
+
+    #[test]
+    fn test_accounts_and_config_roundtrip_remaps_ids() {
+        use crate::models::TokenData;
+
+        let token = || TokenData::new("at".into(), "1//rt".into(), 3600, None, None, None);
+        let src = temp_dir();
+        fs::create_dir_all(src.join("accounts")).unwrap();
+        let mut backed_up = Account::new("acc-backup".into(), "a@example.com".into(), token());
+        backed_up.name = Some("From backup".into());
+        fs::write(
+            src.join("accounts/acc-backup.json"),
+            serde_json::to_string_pretty(&backed_up).unwrap(),
+        )
+        .unwrap();
+        let mut config = AppConfig::new();
+        config
+            .proxy
+            .proxy_pool
+            .account_bindings
+            .insert("acc-backup".into(), "proxy-1".into());
+        crate::modules::config::save_app_config_in_dir(&src, &config).unwrap();
+
+        let archive =
+            create_backup_in_dir(&src, &[BackupSection::Accounts, BackupSection::Config], None).unwrap();
+        let bundle = parse_bundle(&archive.content, None).unwrap();
+
+        // 目标目录已有同邮箱账号 (不同 ID)
+        let dst = temp_dir();
+        let local = Account::new("acc-local".into(), "a@example.com".into(), token());
+        crate::modules::account::restore_account_snapshot(&dst, local).unwrap();
+
+        let report = restore_bundle_in_dir(&dst, &bundle, &RestoreOptions::default()).unwrap();
+        assert!(report.changed(BackupSection::Accounts));
+        assert!(report.changed(BackupSection::Config));
+        let account_item = report.items.iter().find(|i| i.section == BackupSection::Accounts).unwrap();
+        assert_eq!(account_item.action, RestoreAction::Overwrite);
+        assert!(account_item.detail.as_deref().unwrap().contains("acc-local"));
+
+        // 账号内容写入本地 ID，未新建备份 ID 的文件
+        let restored = fs::read_to_string(dst.join("accounts/acc-local.json")).unwrap();
+        assert!(restored.contains("From backup"));
+        assert!(!dst.join("accounts/acc-backup.json").exists());
+
+        // 配置写入目标目录，代理池绑定改为本地 ID
+        let restored_config = crate::modules::config::load_app_config_in_dir(&dst).unwrap();
+        let bindings = &restored_config.proxy.proxy_pool.account_bindings;
+        assert_eq!(bindings.get("acc-local").map(String::as_str), Some("proxy-1"));
+        assert!(!bindings.contains_key("acc-backup"));
+
+        // 再次恢复: 配置不再变化
+        let again = restore_bundle_in_dir(&dst, &bundle, &RestoreOptions::default()).unwrap();
+        assert!(!again.changed(BackupSection::Config));
+
+        let _ = fs::remove_dir_all(&src);
+        let _ = fs::remove_dir_all(&dst);
+    }
+
+    #[test]
+    fn test_parse_bundle_rejects_newer_versions() {
+        let bundle = BackupBundle {
+            format: BACKUP_FORMAT.to_string(),
+            version: BACKUP_VERSION + 1,
+            app_version: "0.0.0".to_string(),
+            created_at: 0,
+            sections: vec![],
+            entries: vec![],
+        };
+        let content = serde_json::to_string(&bundle).unwrap();
+        assert!(parse_bundle(&content, None).unwrap_err().contains("newer"));
+        assert!(parse_bundle("{\"format\":\"x\"}", None).is_err());
+    }
+
+    #[test]
+    fn test_section_parse() {
+        assert_eq!(BackupSection::parse("user_tokens"), Some(BackupSection::UserTokens));
+        assert_eq!(BackupSection::parse(" cli_backups "), Some(BackupSection::CliBackups));
+        assert_eq!(BackupSection::parse("nope"), None);
+    }
+}
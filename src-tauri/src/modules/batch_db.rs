@@ -0,0 +1,877 @@
+//! 批处理任务队列
+//!
+//! Anthropic Message Batches 与 OpenAI Batch 共用的本地 SQLite 队列。
+//! 每个批次拆成逐条请求写入 `batch_items`，由 `proxy::batch_worker` 低优先级消费；
+//! 批次结束后结果保留 `retention_days` 天。
+
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use serde::Serialize;
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// 批次状态 (协议无关，由 handler 映射为各自的状态名)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    InProgress,
+    /// 已请求取消，等待运行中的请求结束
+    Canceling,
+    Ended,
+}
+
+impl BatchStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InProgress => "in_progress",
+            Self::Canceling => "canceling",
+            Self::Ended => "ended",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "canceling" => Self::Canceling,
+            "ended" => Self::Ended,
+            _ => Self::InProgress,
+        }
+    }
+}
+
+/// 单条请求状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Errored,
+    Canceled,
+    Expired,
+}
+
+impl ItemStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Errored => "errored",
+            Self::Canceled => "canceled",
+            Self::Expired => "expired",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "running" => Self::Running,
+            "succeeded" => Self::Succeeded,
+            "errored" => Self::Errored,
+            "canceled" => Self::Canceled,
+            "expired" => Self::Expired,
+            _ => Self::Queued,
+        }
+    }
+}
+
+/// 各状态的请求数
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BatchCounts {
+    pub queued: u64,
+    pub running: u64,
+    pub succeeded: u64,
+    pub errored: u64,
+    pub canceled: u64,
+    pub expired: u64,
+}
+
+impl BatchCounts {
+    pub fn total(&self) -> u64 {
+        self.queued + self.running + self.succeeded + self.errored + self.canceled + self.expired
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchRecord {
+    pub id: String,
+    /// "anthropic" / "openai"
+    pub protocol: String,
+    /// 每条请求转发到的端点，如 "/v1/messages"
+    pub endpoint: String,
+    pub status: BatchStatus,
+    /// 创建者的用户令牌 ID (使用 api_key 或未鉴权时为空)
+    pub owner_token_id: Option<String>,
+    /// 创建时的客户端 IP，worker 转发时沿用 (用户令牌 IP 绑定与日志)
+    pub client_ip: Option<String>,
+    pub metadata: Option<Value>,
+    pub input_file_id: Option<String>,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub started_at: Option<i64>,
+    pub ended_at: Option<i64>,
+    pub cancel_requested_at: Option<i64>,
+    pub counts: BatchCounts,
+}
+
+/// 创建批次时的一条请求
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewBatchItem {
+    pub custom_id: String,
+    /// 转发给端点的请求体
+    pub body: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchItem {
+    pub seq: i64,
+    pub custom_id: String,
+    pub status: ItemStatus,
+    pub attempts: u32,
+    pub response_status: Option<u16>,
+    /// 响应体 (JSON 解析失败时为字符串)
+    pub response: Option<Value>,
+    pub error: Option<String>,
+    pub updated_at: i64,
+}
+
+/// worker 领取的一条待执行请求
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClaimedItem {
+    pub batch_id: String,
+    pub seq: i64,
+    pub custom_id: String,
+    pub endpoint: String,
+    pub body: Value,
+    /// 含本次在内的执行次数
+    pub attempts: u32,
+    pub owner_token_id: Option<String>,
+    pub client_ip: Option<String>,
+}
+
+/// 队列概览
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BatchQueueStats {
+    pub active_batches: u64,
+    pub queued: u64,
+    pub running: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BatchOverview {
+    pub stats: BatchQueueStats,
+    pub batches: Vec<BatchRecord>,
+}
+
+pub fn get_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("batches.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let conn = Connection::open(get_db_path()?).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS batches (
+            id TEXT PRIMARY KEY,
+            protocol TEXT NOT NULL,
+            endpoint TEXT NOT NULL,
+            status TEXT NOT NULL,
+            owner_token_id TEXT,
+            client_ip TEXT,
+            metadata TEXT,
+            input_file_id TEXT,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL,
+            started_at INTEGER,
+            ended_at INTEGER,
+            cancel_requested_at INTEGER
+        )",
+        [],
+    )
+    .map_err(|e| format!("Failed to create batches table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS batch_items (
+            batch_id TEXT NOT NULL,
+            seq INTEGER NOT NULL,
+            custom_id TEXT NOT NULL,
+            body TEXT NOT NULL,
+            status TEXT NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            available_at INTEGER NOT NULL DEFAULT 0,
+            response_status INTEGER,
+            response TEXT,
+            error TEXT,
+            updated_at INTEGER NOT NULL,
+            PRIMARY KEY (batch_id, seq)
+        )",
+        [],
+    )
+    .map_err(|e| format!("Failed to create batch_items table: {}", e))?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_batch_items_status ON batch_items (status, available_at)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_batches_owner ON batches (protocol, owner_token_id)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// 初始化数据库
+///
+/// 仅在进程启动时调用：上次退出时仍在执行的请求放回队列。
+/// 反代服务重启不会走到这里，避免把旧 worker 尚未返回的请求重复执行。
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+    create_tables(&conn)?;
+    let recovered = recover_running_at(&conn, now())?;
+    if recovered > 0 {
+        tracing::info!("[Batch] Requeued {} interrupted batch request(s)", recovered);
+    }
+    Ok(())
+}
+
+fn counts_at(conn: &Connection, batch_id: &str) -> Result<BatchCounts, String> {
+    let mut stmt = conn
+        .prepare("SELECT status, COUNT(*) FROM batch_items WHERE batch_id = ?1 GROUP BY status")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![batch_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, u64>(1)?)))
+        .map_err(|e| e.to_string())?;
+
+    let mut counts = BatchCounts::default();
+    for row in rows {
+        let (status, count) = row.map_err(|e| e.to_string())?;
+        match ItemStatus::parse(&status) {
+            ItemStatus::Queued => counts.queued = count,
+            ItemStatus::Running => counts.running = count,
+            ItemStatus::Succeeded => counts.succeeded = count,
+            ItemStatus::Errored => counts.errored = count,
+            ItemStatus::Canceled => counts.canceled = count,
+            ItemStatus::Expired => counts.expired = count,
+        }
+    }
+    Ok(counts)
+}
+
+const BATCH_COLUMNS: &str = "id, protocol, endpoint, status, owner_token_id, client_ip, metadata,
+    input_file_id, created_at, expires_at, started_at, ended_at, cancel_requested_at";
+
+fn row_to_batch(row: &rusqlite::Row) -> rusqlite::Result<BatchRecord> {
+    let status: String = row.get(3)?;
+    let metadata: Option<String> = row.get(6)?;
+    Ok(BatchRecord {
+        id: row.get(0)?,
+        protocol: row.get(1)?,
+        endpoint: row.get(2)?,
+        status: BatchStatus::parse(&status),
+        owner_token_id: row.get(4)?,
+        client_ip: row.get(5)?,
+        metadata: metadata.and_then(|m| serde_json::from_str(&m).ok()),
+        input_file_id: row.get(7)?,
+        created_at: row.get(8)?,
+        expires_at: row.get(9)?,
+        started_at: row.get(10)?,
+        ended_at: row.get(11)?,
+        cancel_requested_at: row.get(12)?,
+        counts: BatchCounts::default(),
+    })
+}
+
+fn get_batch_at(conn: &Connection, id: &str) -> Result<Option<BatchRecord>, String> {
+    let batch = conn
+        .query_row(
+            &format!("SELECT {} FROM batches WHERE id = ?1", BATCH_COLUMNS),
+            params![id],
+            row_to_batch,
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+    match batch {
+        Some(mut batch) => {
+            batch.counts = counts_at(conn, id)?;
+            Ok(Some(batch))
+        }
+        None => Ok(None),
+    }
+}
+
+/// 没有排队或运行中的请求时结束批次
+fn finalize_if_done(conn: &Connection, batch_id: &str, now: i64) -> Result<bool, String> {
+    let changed = conn
+        .execute(
+            "UPDATE batches SET status = 'ended', ended_at = ?2
+             WHERE id = ?1 AND status != 'ended'
+               AND NOT EXISTS (
+                   SELECT 1 FROM batch_items
+                   WHERE batch_id = ?1 AND status IN ('queued', 'running')
+               )",
+            params![batch_id, now],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(changed > 0)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_batch_at(
+    conn: &mut Connection,
+    id: &str,
+    protocol: &str,
+    endpoint: &str,
+    owner_token_id: Option<&str>,
+    client_ip: Option<&str>,
+    metadata: Option<&Value>,
+    input_file_id: Option<&str>,
+    items: &[NewBatchItem],
+    now: i64,
+    ttl_secs: i64,
+) -> Result<BatchRecord, String> {
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to create transaction: {}", e))?;
+    tx.execute(
+        "INSERT INTO batches
+            (id, protocol, endpoint, status, owner_token_id, client_ip, metadata, input_file_id, created_at, expires_at)
+         VALUES (?1, ?2, ?3, 'in_progress', ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            id,
+            protocol,
+            endpoint,
+            owner_token_id,
+            client_ip,
+            metadata.map(|m| m.to_string()),
+            input_file_id,
+            now,
+            now + ttl_secs,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT INTO batch_items (batch_id, seq, custom_id, body, status, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, 'queued', ?5)",
+            )
+            .map_err(|e| e.to_string())?;
+        for (seq, item) in items.iter().enumerate() {
+            stmt.execute(params![id, seq as i64, item.custom_id, item.body.to_string(), now])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    get_batch_at(conn, id)?.ok_or_else(|| "Batch disappeared after insert".to_string())
+}
+
+fn list_batches_at(
+    conn: &Connection,
+    protocol: Option<&str>,
+    owner_token_id: Option<&str>,
+    after_id: Option<&str>,
+    before_id: Option<&str>,
+    limit: usize,
+) -> Result<(Vec<BatchRecord>, bool), String> {
+    // 最新的在前；after_id 翻向更旧的一页，before_id 翻向更新的一页
+    let newer_page = after_id.is_none() && before_id.is_some();
+    let sql = format!(
+        "SELECT {} FROM batches
+         WHERE (?1 IS NULL OR protocol = ?1)
+           AND (?2 IS NULL OR owner_token_id = ?2)
+           AND (?3 IS NULL OR rowid < (SELECT rowid FROM batches WHERE id = ?3))
+           AND (?4 IS NULL OR rowid > (SELECT rowid FROM batches WHERE id = ?4))
+         ORDER BY rowid {}
+         LIMIT ?5",
+        BATCH_COLUMNS,
+        if newer_page { "ASC" } else { "DESC" }
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            params![protocol, owner_token_id, after_id, before_id, (limit + 1) as i64],
+            row_to_batch,
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut batches = Vec::new();
+    for row in rows {
+        batches.push(row.map_err(|e| e.to_string())?);
+    }
+    let has_more = batches.len() > limit;
+    batches.truncate(limit);
+    if newer_page {
+        batches.reverse();
+    }
+    for batch in &mut batches {
+        batch.counts = counts_at(conn, &batch.id)?;
+    }
+    Ok((batches, has_more))
+}
+
+fn cancel_batch_at(conn: &Connection, id: &str, now: i64) -> Result<Option<BatchRecord>, String> {
+    let changed = conn
+        .execute(
+            "UPDATE batches SET status = 'canceling', cancel_requested_at = ?2
+             WHERE id = ?1 AND status = 'in_progress'",
+            params![id, now],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed > 0 {
+        // 排队中的请求立即取消，运行中的请求结束后批次再转为 ended
+        conn.execute(
+            "UPDATE batch_items SET status = 'canceled', updated_at = ?2
+             WHERE batch_id = ?1 AND status = 'queued'",
+            params![id, now],
+        )
+        .map_err(|e| e.to_string())?;
+        finalize_if_done(conn, id, now)?;
+    }
+    get_batch_at(conn, id)
+}
+
+fn delete_batch_at(conn: &Connection, id: &str) -> Result<bool, String> {
+    let deleted = conn
+        .execute("DELETE FROM batches WHERE id = ?1 AND status = 'ended'", params![id])
+        .map_err(|e| e.to_string())?;
+    if deleted > 0 {
+        conn.execute("DELETE FROM batch_items WHERE batch_id = ?1", params![id])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(deleted > 0)
+}
+
+fn claim_next_at(conn: &mut Connection, now: i64) -> Result<Option<ClaimedItem>, String> {
+    let tx = conn
+        .transaction_with_behavior(TransactionBehavior::Immediate)
+        .map_err(|e| format!("Failed to create transaction: {}", e))?;
+    // 先到先服务: 最早创建的批次优先，批次内按提交顺序
+    let claimed = tx
+        .query_row(
+            "SELECT i.batch_id, i.seq, i.custom_id, b.endpoint, i.body, i.attempts, b.owner_token_id, b.client_ip
+             FROM batch_items i JOIN batches b ON b.id = i.batch_id
+             WHERE i.status = 'queued' AND i.available_at <= ?1
+               AND b.status = 'in_progress' AND b.expires_at > ?1
+             ORDER BY b.rowid, i.seq
+             LIMIT 1",
+            params![now],
+            |row| {
+                let body: String = row.get(4)?;
+                Ok(ClaimedItem {
+                    batch_id: row.get(0)?,
+                    seq: row.get(1)?,
+                    custom_id: row.get(2)?,
+                    endpoint: row.get(3)?,
+                    body: serde_json::from_str(&body).unwrap_or(Value::Null),
+                    attempts: row.get::<_, u32>(5)? + 1,
+                    owner_token_id: row.get(6)?,
+                    client_ip: row.get(7)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    if let Some(item) = &claimed {
+        tx.execute(
+            "UPDATE batch_items SET status = 'running', attempts = ?3, updated_at = ?4
+             WHERE batch_id = ?1 AND seq = ?2",
+            params![item.batch_id, item.seq, item.attempts, now],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "UPDATE batches SET started_at = ?2 WHERE id = ?1 AND started_at IS NULL",
+            params![item.batch_id, now],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    Ok(claimed)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn complete_item_at(
+    conn: &Connection,
+    batch_id: &str,
+    seq: i64,
+    status: ItemStatus,
+    response_status: Option<u16>,
+    response: Option<&Value>,
+    error: Option<&str>,
+    now: i64,
+) -> Result<(), String> {
+    conn.execute(
+        "UPDATE batch_items SET status = ?3, response_status = ?4, response = ?5, error = ?6, updated_at = ?7
+         WHERE batch_id = ?1 AND seq = ?2",
+        params![
+            batch_id,
+            seq,
+            status.as_str(),
+            response_status,
+            response.map(|r| r.to_string()),
+            error,
+            now
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    finalize_if_done(conn, batch_id, now)?;
+    Ok(())
+}
+
+fn requeue_item_at(
+    conn: &Connection,
+    batch_id: &str,
+    seq: i64,
+    available_at: i64,
+    error: &str,
+    count_attempt: bool,
+    now: i64,
+) -> Result<(), String> {
+    // 批次在执行期间被取消时直接标记为 canceled
+    conn.execute(
+        "UPDATE batch_items SET
+            status = CASE WHEN (SELECT status FROM batches WHERE id = ?1) = 'in_progress' THEN 'queued' ELSE 'canceled' END,
+            attempts = CASE WHEN ?6 THEN attempts ELSE MAX(attempts - 1, 0) END,
+            available_at = ?3, error = ?4, updated_at = ?5
+         WHERE batch_id = ?1 AND seq = ?2",
+        params![batch_id, seq, available_at, error, now, count_attempt],
+    )
+    .map_err(|e| e.to_string())?;
+    finalize_if_done(conn, batch_id, now)?;
+    Ok(())
+}
+
+/// 过期批次: 剩余排队请求标记为 expired
+fn expire_overdue_at(conn: &Connection, now: i64) -> Result<usize, String> {
+    let mut stmt = conn
+        .prepare("SELECT id FROM batches WHERE status != 'ended' AND expires_at <= ?1")
+        .map_err(|e| e.to_string())?;
+    let ids = stmt
+        .query_map(params![now], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    drop(stmt);
+
+    let mut expired = 0;
+    for id in &ids {
+        expired += conn
+            .execute(
+                "UPDATE batch_items SET status = 'expired', updated_at = ?2
+                 WHERE batch_id = ?1 AND status = 'queued'",
+                params![id, now],
+            )
+            .map_err(|e| e.to_string())?;
+        finalize_if_done(conn, id, now)?;
+    }
+    Ok(expired)
+}
+
+/// 进程重启后把中断的请求放回队列 (不计入执行次数)
+fn recover_running_at(conn: &Connection, now: i64) -> Result<usize, String> {
+    conn.execute(
+        "UPDATE batch_items SET status = 'queued', attempts = MAX(attempts - 1, 0), updated_at = ?1
+         WHERE status = 'running'",
+        params![now],
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn purge_ended_before_at(conn: &Connection, cutoff: i64) -> Result<usize, String> {
+    conn.execute(
+        "DELETE FROM batch_items WHERE batch_id IN
+            (SELECT id FROM batches WHERE status = 'ended' AND ended_at < ?1)",
+        params![cutoff],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM batches WHERE status = 'ended' AND ended_at < ?1",
+        params![cutoff],
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn list_items_at(conn: &Connection, batch_id: &str) -> Result<Vec<BatchItem>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT seq, custom_id, status, attempts, response_status, response, error, updated_at
+             FROM batch_items WHERE batch_id = ?1 ORDER BY seq",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(params![batch_id], |row| {
+            let status: String = row.get(2)?;
+            let response: Option<String> = row.get(5)?;
+            Ok(BatchItem {
+                seq: row.get(0)?,
+                custom_id: row.get(1)?,
+                status: ItemStatus::parse(&status),
+                attempts: row.get(3)?,
+                response_status: row.get(4)?,
+                response: response.map(|r| serde_json::from_str(&r).unwrap_or(Value::String(r))),
+                error: row.get(6)?,
+                updated_at: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn queue_stats_at(conn: &Connection) -> Result<BatchQueueStats, String> {
+    conn.query_row(
+        "SELECT
+            (SELECT COUNT(*) FROM batches WHERE status != 'ended'),
+            (SELECT COUNT(*) FROM batch_items WHERE status = 'queued'),
+            (SELECT COUNT(*) FROM batch_items WHERE status = 'running')",
+        [],
+        |row| {
+            Ok(BatchQueueStats {
+                active_batches: row.get(0)?,
+                queued: row.get(1)?,
+                running: row.get(2)?,
+            })
+        },
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+/// 创建批次并写入全部请求
+#[allow(clippy::too_many_arguments)]
+pub fn create_batch(
+    id: &str,
+    protocol: &str,
+    endpoint: &str,
+    owner_token_id: Option<&str>,
+    client_ip: Option<&str>,
+    metadata: Option<&Value>,
+    input_file_id: Option<&str>,
+    items: &[NewBatchItem],
+    ttl_secs: i64,
+) -> Result<BatchRecord, String> {
+    create_batch_at(
+        &mut connect_db()?,
+        id,
+        protocol,
+        endpoint,
+        owner_token_id,
+        client_ip,
+        metadata,
+        input_file_id,
+        items,
+        now(),
+        ttl_secs,
+    )
+}
+
+pub fn get_batch(id: &str) -> Result<Option<BatchRecord>, String> {
+    get_batch_at(&connect_db()?, id)
+}
+
+/// 分页列出批次，返回 (批次, 是否还有更多)；protocol / owner_token_id 为 None 时不过滤
+pub fn list_batches(
+    protocol: Option<&str>,
+    owner_token_id: Option<&str>,
+    after_id: Option<&str>,
+    before_id: Option<&str>,
+    limit: usize,
+) -> Result<(Vec<BatchRecord>, bool), String> {
+    list_batches_at(&connect_db()?, protocol, owner_token_id, after_id, before_id, limit)
+}
+
+/// 请求取消批次，返回最新状态
+pub fn cancel_batch(id: &str) -> Result<Option<BatchRecord>, String> {
+    cancel_batch_at(&connect_db()?, id, now())
+}
+
+/// 删除已结束的批次，未结束时返回 false
+pub fn delete_batch(id: &str) -> Result<bool, String> {
+    delete_batch_at(&connect_db()?, id)
+}
+
+/// 领取下一条可执行的请求
+pub fn claim_next() -> Result<Option<ClaimedItem>, String> {
+    claim_next_at(&mut connect_db()?, now())
+}
+
+/// 记录请求的最终结果
+pub fn complete_item(
+    batch_id: &str,
+    seq: i64,
+    status: ItemStatus,
+    response_status: Option<u16>,
+    response: Option<&Value>,
+    error: Option<&str>,
+) -> Result<(), String> {
+    complete_item_at(&connect_db()?, batch_id, seq, status, response_status, response, error, now())
+}
+
+/// 暂时无法执行，在 available_at 之后重新排队
+///
+/// 被限流的执行计入次数；因无可用账号而未发出的请求不计入
+pub fn requeue_item(
+    batch_id: &str,
+    seq: i64,
+    available_at: i64,
+    error: &str,
+    count_attempt: bool,
+) -> Result<(), String> {
+    requeue_item_at(&connect_db()?, batch_id, seq, available_at, error, count_attempt, now())
+}
+
+/// 过期处理与结果清理，返回 (过期请求数, 删除批次数)
+pub fn run_maintenance(retention_days: u32) -> Result<(usize, usize), String> {
+    let conn = connect_db()?;
+    let now = now();
+    let expired = expire_overdue_at(&conn, now)?;
+    let purged = purge_ended_before_at(&conn, now - retention_days as i64 * 86400)?;
+    Ok((expired, purged))
+}
+
+pub fn list_items(batch_id: &str) -> Result<Vec<BatchItem>, String> {
+    list_items_at(&connect_db()?, batch_id)
+}
+
+pub fn get_queue_stats() -> Result<BatchQueueStats, String> {
+    queue_stats_at(&connect_db()?)
+}
+
+/// 管理页概览：队列统计与最近的批次 (不区分协议与创建者)
+pub fn get_overview(limit: usize) -> Result<BatchOverview, String> {
+    let conn = connect_db()?;
+    let stats = queue_stats_at(&conn)?;
+    let (batches, _) = list_batches_at(&conn, None, None, None, None, limit)?;
+    Ok(BatchOverview { stats, batches })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        conn
+    }
+
+    fn items(n: usize) -> Vec<NewBatchItem> {
+        (0..n)
+            .map(|i| NewBatchItem {
+                custom_id: format!("req-{}", i),
+                body: json!({ "model": "claude-sonnet-4-5", "max_tokens": 16 }),
+            })
+            .collect()
+    }
+
+    fn create(conn: &mut Connection, id: &str, owner: Option<&str>, n: usize, now: i64) -> BatchRecord {
+        create_batch_at(conn, id, "anthropic", "/v1/messages", owner, None, None, None, &items(n), now, 86400).unwrap()
+    }
+
+    #[test]
+    fn test_claim_complete_and_finalize() {
+        let mut conn = setup();
+        let now = 1_700_000_000;
+        let batch = create(&mut conn, "b1", None, 2, now);
+        assert_eq!(batch.counts.queued, 2);
+
+        let first = claim_next_at(&mut conn, now).unwrap().unwrap();
+        assert_eq!((first.seq, first.attempts, first.endpoint.as_str()), (0, 1, "/v1/messages"));
+        complete_item_at(&conn, "b1", 0, ItemStatus::Succeeded, Some(200), Some(&json!({"id": "msg"})), None, now).unwrap();
+
+        // 限流后重新排队，available_at 之前不会被领取
+        let second = claim_next_at(&mut conn, now).unwrap().unwrap();
+        requeue_item_at(&conn, "b1", second.seq, now + 30, "429", true, now).unwrap();
+        assert!(claim_next_at(&mut conn, now + 10).unwrap().is_none());
+        let retried = claim_next_at(&mut conn, now + 30).unwrap().unwrap();
+        assert_eq!(retried.attempts, 2);
+        complete_item_at(&conn, "b1", 1, ItemStatus::Errored, Some(400), Some(&json!("bad")), None, now + 31).unwrap();
+
+        let batch = get_batch_at(&conn, "b1").unwrap().unwrap();
+        assert_eq!(batch.status, BatchStatus::Ended);
+        assert_eq!(batch.ended_at, Some(now + 31));
+        assert_eq!((batch.counts.succeeded, batch.counts.errored), (1, 1));
+        let results = list_items_at(&conn, "b1").unwrap();
+        assert_eq!(results[0].response, Some(json!({"id": "msg"})));
+        assert_eq!(results[1].response, Some(json!("bad")));
+    }
+
+    #[test]
+    fn test_cancel_waits_for_running_items() {
+        let mut conn = setup();
+        let now = 1_700_000_000;
+        create(&mut conn, "b1", None, 3, now);
+        let running = claim_next_at(&mut conn, now).unwrap().unwrap();
+
+        let batch = cancel_batch_at(&conn, "b1", now + 1).unwrap().unwrap();
+        assert_eq!(batch.status, BatchStatus::Canceling);
+        assert_eq!((batch.counts.canceled, batch.counts.running), (2, 1));
+        assert!(claim_next_at(&mut conn, now + 2).unwrap().is_none());
+        assert!(!delete_batch_at(&conn, "b1").unwrap());
+
+        complete_item_at(&conn, "b1", running.seq, ItemStatus::Succeeded, Some(200), None, None, now + 3).unwrap();
+        assert_eq!(get_batch_at(&conn, "b1").unwrap().unwrap().status, BatchStatus::Ended);
+        assert!(delete_batch_at(&conn, "b1").unwrap());
+        assert!(list_items_at(&conn, "b1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_expire_recover_and_purge() {
+        let mut conn = setup();
+        let now = 1_700_000_000;
+        create(&mut conn, "b1", None, 2, now);
+        claim_next_at(&mut conn, now).unwrap().unwrap();
+
+        // 重启恢复: running 放回队列且不计入次数
+        assert_eq!(recover_running_at(&conn, now).unwrap(), 1);
+        assert_eq!(claim_next_at(&mut conn, now).unwrap().unwrap().attempts, 1);
+        recover_running_at(&conn, now).unwrap();
+
+        assert_eq!(expire_overdue_at(&conn, now + 86400).unwrap(), 2);
+        let batch = get_batch_at(&conn, "b1").unwrap().unwrap();
+        assert_eq!((batch.status, batch.counts.expired), (BatchStatus::Ended, 2));
+
+        assert_eq!(purge_ended_before_at(&conn, now + 86400).unwrap(), 0);
+        assert_eq!(purge_ended_before_at(&conn, now + 86401).unwrap(), 1);
+        assert!(get_batch_at(&conn, "b1").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_list_pagination_and_owner_filter() {
+        let mut conn = setup();
+        let now = 1_700_000_000;
+        for (i, owner) in ["t1", "t2", "t1", "t1"].iter().enumerate() {
+            create(&mut conn, &format!("b{}", i), Some(owner), 1, now + i as i64);
+        }
+
+        let (page, has_more) = list_batches_at(&conn, Some("anthropic"), Some("t1"), None, None, 2).unwrap();
+        assert_eq!(page.iter().map(|b| b.id.as_str()).collect::<Vec<_>>(), vec!["b3", "b2"]);
+        assert!(has_more);
+
+        let (page, has_more) = list_batches_at(&conn, Some("anthropic"), Some("t1"), Some("b2"), None, 2).unwrap();
+        assert_eq!(page.iter().map(|b| b.id.as_str()).collect::<Vec<_>>(), vec!["b0"]);
+        assert!(!has_more);
+
+        let (page, _) = list_batches_at(&conn, Some("anthropic"), None, None, Some("b1"), 10).unwrap();
+        assert_eq!(page.iter().map(|b| b.id.as_str()).collect::<Vec<_>>(), vec!["b3", "b2"]);
+        assert!(list_batches_at(&conn, Some("openai"), None, None, None, 10).unwrap().0.is_empty());
+    }
+}
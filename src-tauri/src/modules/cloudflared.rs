@@ -345,6 +345,7 @@ impl CloudflaredManager {
                                     let mut s = status_ref.write().await;
                                     s.running = false;
                                     s.error = Some(format!("Tunnel process exited (status: {:?})", exit_status));
+                                    notify_tunnel_down(&s);
                                     break;
                                 }
                                 Ok(None) => {
@@ -358,6 +359,7 @@ impl CloudflaredManager {
                                     let mut s = status_ref.write().await;
                                     s.running = false;
                                     s.error = Some(format!("Error checking tunnel: {}", e));
+                                    notify_tunnel_down(&s);
                                     break;
                                 }
                             }
@@ -368,6 +370,7 @@ impl CloudflaredManager {
                             if s.running {
                                 s.running = false;
                                 s.error = Some("Tunnel process not found".to_string());
+                                notify_tunnel_down(&s);
                             }
                             break;
                         }
@@ -398,6 +401,20 @@ impl CloudflaredManager {
 }
 
 /// 获取下载URL
+/// [NEW] 隧道意外断开时发送 Webhook 告警 (手动停止走 shutdown 分支，不会触发)
+fn notify_tunnel_down(status: &CloudflaredStatus) {
+    let error = status.error.clone().unwrap_or_default();
+    crate::modules::webhook::notify(
+        crate::modules::webhook::WebhookEvent::TunnelDown,
+        status.url.clone().unwrap_or_else(|| "cloudflared".to_string()),
+        format!("Cloudflared tunnel is down: {}", error),
+        serde_json::json!({
+            "url": status.url,
+            "error": error,
+        }),
+    );
+}
+
 fn get_download_url() -> Result<String, String> {
     let os = std::env::consts::OS;
     let arch = std::env::consts::ARCH;
@@ -1,4 +1,5 @@
 use std::fs;
+use std::path::Path;
 use serde_json;
 
 use crate::models::AppConfig;
@@ -7,15 +8,24 @@ use tracing::warn;
 
 const CONFIG_FILE: &str = "gui_config.json";
 
+/// 配置文件路径 (gui_config.json)
+pub fn get_config_path() -> Result<std::path::PathBuf, String> {
+    Ok(get_data_dir()?.join(CONFIG_FILE))
+}
+
 /// Load application configuration
 pub fn load_app_config() -> Result<AppConfig, String> {
-    let data_dir = get_data_dir()?;
+    load_app_config_in_dir(&get_data_dir()?)
+}
+
+/// 从指定数据目录加载配置 (备份恢复 / 测试使用临时目录)
+pub fn load_app_config_in_dir(data_dir: &Path) -> Result<AppConfig, String> {
     let config_path = data_dir.join(CONFIG_FILE);
     
     if !config_path.exists() {
         let config = AppConfig::new();
         // [FIX #1460] Persist initial config to prevent new API Key on every refresh
-        let _ = save_app_config(&config);
+        let _ = save_app_config_in_dir(data_dir, &config);
         return Ok(config);
     }
     
@@ -80,7 +90,7 @@ pub fn load_app_config() -> Result<AppConfig, String> {
     
     // If migration occurred, auto-save once to clean up the file
     if modified {
-        let _ = save_app_config(&config);
+        let _ = save_app_config_in_dir(data_dir, &config);
     }
 
     Ok(config)
@@ -88,7 +98,11 @@ pub fn load_app_config() -> Result<AppConfig, String> {
 
 /// Save application configuration
 pub fn save_app_config(config: &AppConfig) -> Result<(), String> {
-    let data_dir = get_data_dir()?;
+    save_app_config_in_dir(&get_data_dir()?, config)
+}
+
+/// 保存配置到指定数据目录
+pub fn save_app_config_in_dir(data_dir: &Path, config: &AppConfig) -> Result<(), String> {
     let config_path = data_dir.join(CONFIG_FILE);
     
     let content = serde_json::to_string_pretty(config)
@@ -0,0 +1,571 @@
+//! 配置文件热重载
+//!
+//! Docker 部署通常直接挂载并编辑 gui_config.json。这里轮询文件变化，
+//! 校验通过后把可在线生效的部分 (模型路由、调度、熔断、代理池、Thinking Budget、
+//! 全局系统提示词、IP 黑白名单、z.ai、响应缓存、费用核算、自定义上游) 热更新到运行中的服务；校验失败则保留当前配置。
+
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::time::{Duration, SystemTime};
+use tracing::{debug, error, info, warn};
+
+use crate::models::AppConfig;
+
+/// 轮询间隔
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+/// 检测到变化后等待写入完成的时间 (编辑器/`fs::write` 可能分多次写入)
+const SETTLE_DELAY: Duration = Duration::from_millis(300);
+
+/// 可热更新的配置分组
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotSection {
+    ModelMapping,
+    Scheduling,
+    CircuitBreaker,
+    ProxyPool,
+    ThinkingBudget,
+    GlobalSystemPrompt,
+    SecurityMonitor,
+    Zai,
+    ResponseCache,
+    Cost,
+    Providers,
+    StructuredOutput,
+    Batch,
+    Files,
+}
+
+impl HotSection {
+    pub const ALL: [HotSection; 14] = [
+        HotSection::ModelMapping,
+        HotSection::Scheduling,
+        HotSection::CircuitBreaker,
+        HotSection::ProxyPool,
+        HotSection::ThinkingBudget,
+        HotSection::GlobalSystemPrompt,
+        HotSection::SecurityMonitor,
+        HotSection::Zai,
+        HotSection::ResponseCache,
+        HotSection::Cost,
+        HotSection::Providers,
+        HotSection::StructuredOutput,
+        HotSection::Batch,
+        HotSection::Files,
+    ];
+
+    /// 该分组在 AppConfig JSON 中对应的路径 (JSON Pointer)
+    fn pointers(&self) -> &'static [&'static str] {
+        match self {
+            HotSection::ModelMapping => &[
+                "/proxy/custom_mapping",
+                "/proxy/model_routing_rules",
+                "/proxy/model_fallback_chains",
+            ],
+            HotSection::Scheduling => &["/proxy/scheduling"],
+            HotSection::CircuitBreaker => &["/circuit_breaker"],
+            HotSection::ProxyPool => &["/proxy/proxy_pool"],
+            HotSection::ThinkingBudget => &["/proxy/thinking_budget"],
+            HotSection::GlobalSystemPrompt => &["/proxy/global_system_prompt"],
+            HotSection::SecurityMonitor => &["/proxy/security_monitor"],
+            HotSection::Zai => &["/proxy/zai"],
+            HotSection::ResponseCache => &["/proxy/response_cache"],
+            HotSection::Cost => &["/proxy/cost"],
+            HotSection::Providers => &["/proxy/providers"],
+            HotSection::StructuredOutput => &["/proxy/structured_output"],
+            HotSection::Batch => &["/proxy/batch"],
+            HotSection::Files => &["/proxy/files"],
+        }
+    }
+}
+
+/// 需要重启才能生效的字段 (监听地址与鉴权)
+const RESTART_REQUIRED: &[&str] = &[
+    "/proxy/port",
+    "/proxy/allow_lan_access",
+    "/proxy/auth_mode",
+    "/proxy/api_key",
+    "/proxy/admin_password",
+];
+
+/// 日志中需要打码的字段名
+const SECRET_KEYS: &[&str] = &["api_key", "api_keys", "admin_password", "password", "secret", "token"];
+
+/// 单个字段变化
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigChange {
+    /// 点分路径，如 `proxy.scheduling.mode`
+    pub path: String,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+impl std::fmt::Display for ConfigChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let secret = self
+            .path
+            .rsplit('.')
+            .next()
+            .map(|k| SECRET_KEYS.contains(&k))
+            .unwrap_or(false);
+        let show = |v: &Option<Value>| match v {
+            None => "(unset)".to_string(),
+            Some(v) => {
+                let masked = if secret { mask_secret(v) } else { redact_secrets(v) };
+                let text = masked.to_string();
+                if text.chars().count() > 120 {
+                    format!("{}…", text.chars().take(120).collect::<String>())
+                } else {
+                    text
+                }
+            }
+        };
+        write!(f, "{}: {} -> {}", self.path, show(&self.old), show(&self.new))
+    }
+}
+
+/// 一次配置变化的处理计划
+#[derive(Debug, Default)]
+pub struct ConfigReloadPlan {
+    /// 需要热更新的分组
+    pub sections: Vec<HotSection>,
+    pub hot_changes: Vec<ConfigChange>,
+    /// 已写入文件但需要重启才能生效的变化
+    pub restart_changes: Vec<ConfigChange>,
+    /// 其他变化 (GUI 偏好、预热、配额保护等由各自模块按需读取文件)
+    pub other_changes: Vec<ConfigChange>,
+}
+
+impl ConfigReloadPlan {
+    pub fn is_empty(&self) -> bool {
+        self.hot_changes.is_empty() && self.restart_changes.is_empty() && self.other_changes.is_empty()
+    }
+
+    fn log(&self) {
+        for change in &self.hot_changes {
+            info!("[ConfigWatcher]   ~ {}", change);
+        }
+        for change in &self.restart_changes {
+            warn!("[ConfigWatcher]   ! {} (requires restart)", change);
+        }
+        for change in &self.other_changes {
+            debug!("[ConfigWatcher]   · {}", change);
+        }
+    }
+}
+
+/// 对象/数组整体变化时 (如 `webhooks.endpoints`)，对其中的敏感字段逐层打码
+fn redact_secrets(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| {
+                    let masked = if SECRET_KEYS.contains(&k.as_str()) {
+                        mask_secret(v)
+                    } else {
+                        redact_secrets(v)
+                    };
+                    (k.clone(), masked)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(redact_secrets).collect()),
+        other => other.clone(),
+    }
+}
+
+/// 敏感字段的取值打码 (字符串或字符串列表，如 `providers[].api_keys`)
+fn mask_secret(value: &Value) -> Value {
+    match value {
+        Value::String(s) if !s.is_empty() => Value::String("***".to_string()),
+        Value::Array(items) => Value::Array(items.iter().map(mask_secret).collect()),
+        other => redact_secrets(other),
+    }
+}
+
+fn pointer_to_path(pointer: &str) -> String {
+    pointer.trim_start_matches('/').replace('/', ".")
+}
+
+/// 递归比较两个 JSON 值，对象逐键展开，其它类型整体比较
+fn diff_values(path: &str, old: Option<&Value>, new: Option<&Value>, out: &mut Vec<ConfigChange>) {
+    match (old, new) {
+        (Some(Value::Object(a)), Some(Value::Object(b))) => {
+            let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                let child = if path.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                diff_values(&child, a.get(key), b.get(key), out);
+            }
+        }
+        (a, b) if a != b => out.push(ConfigChange {
+            path: path.to_string(),
+            old: a.cloned(),
+            new: b.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+/// [NEW] 列出两份配置之间的全部字段变化 (审计日志使用，不做分类)
+pub fn diff_configs(current: &AppConfig, next: &AppConfig) -> Vec<ConfigChange> {
+    let (Ok(old), Ok(new)) = (serde_json::to_value(current), serde_json::to_value(next)) else {
+        return Vec::new();
+    };
+    let mut all = Vec::new();
+    diff_values("", Some(&old), Some(&new), &mut all);
+    all
+}
+
+/// 对比已生效配置与新配置，划分为热更新 / 需重启 / 其他三类
+pub fn plan_reload(current: &AppConfig, next: &AppConfig) -> ConfigReloadPlan {
+    let (Ok(old), Ok(new)) = (serde_json::to_value(current), serde_json::to_value(next)) else {
+        return ConfigReloadPlan::default();
+    };
+
+    let mut all = Vec::new();
+    diff_values("", Some(&old), Some(&new), &mut all);
+
+    let mut plan = ConfigReloadPlan::default();
+    for section in HotSection::ALL {
+        let prefixes: Vec<String> = section.pointers().iter().map(|p| pointer_to_path(p)).collect();
+        if section
+            .pointers()
+            .iter()
+            .any(|p| old.pointer(p) != new.pointer(p))
+        {
+            plan.sections.push(section);
+        }
+        let (hot, rest): (Vec<_>, Vec<_>) = all.into_iter().partition(|c: &ConfigChange| {
+            prefixes
+                .iter()
+                .any(|p| c.path == *p || c.path.starts_with(&format!("{}.", p)))
+        });
+        plan.hot_changes.extend(hot);
+        all = rest;
+    }
+
+    let restart: Vec<String> = RESTART_REQUIRED.iter().map(|p| pointer_to_path(p)).collect();
+    let (restart_changes, other_changes): (Vec<_>, Vec<_>) =
+        all.into_iter().partition(|c| restart.contains(&c.path));
+    plan.restart_changes = restart_changes;
+    plan.other_changes = other_changes;
+    plan
+}
+
+fn is_valid_url(raw: &str, schemes: &[&str]) -> bool {
+    url::Url::parse(raw)
+        .map(|u| schemes.contains(&u.scheme()) && u.host_str().is_some())
+        .unwrap_or(false)
+}
+
+/// 语义校验 (类型错误已在反序列化阶段拦截)
+pub fn validate_config(config: &AppConfig) -> Result<(), Vec<String>> {
+    use crate::proxy::common::routing_rules::RouteTarget;
+
+    let proxy = &config.proxy;
+    let mut errors = Vec::new();
+
+    if proxy.port == 0 {
+        errors.push("proxy.port must not be 0".to_string());
+    }
+
+    if proxy.admin_session_ttl_hours == 0 {
+        errors.push("proxy.admin_session_ttl_hours must be at least 1".to_string());
+    }
+
+    if proxy.upstream_proxy.enabled
+        && !is_valid_url(&proxy.upstream_proxy.url, &["http", "https", "socks5", "socks5h"])
+    {
+        errors.push(format!("proxy.upstream_proxy.url is invalid: {}", proxy.upstream_proxy.url));
+    }
+
+    let mut proxy_ids = BTreeSet::new();
+    for (i, entry) in proxy.proxy_pool.proxies.iter().enumerate() {
+        if !proxy_ids.insert(entry.id.as_str()) {
+            errors.push(format!("proxy.proxy_pool.proxies.{}: duplicate id '{}'", i, entry.id));
+        }
+        if !is_valid_url(&entry.url, &["http", "https", "socks5", "socks5h"]) {
+            errors.push(format!("proxy.proxy_pool.proxies.{}: invalid url '{}'", i, entry.url));
+        }
+    }
+
+    if proxy.zai.enabled && !is_valid_url(&proxy.zai.base_url, &["http", "https"]) {
+        errors.push(format!("proxy.zai.base_url is invalid: {}", proxy.zai.base_url));
+    }
+
+    if proxy.batch.concurrency == 0 || proxy.batch.max_attempts == 0 || proxy.batch.max_requests_per_batch == 0 {
+        errors.push("proxy.batch: concurrency, max_attempts and max_requests_per_batch must be at least 1".to_string());
+    }
+    if proxy.files.max_file_size_mb == 0 || proxy.files.max_total_size_mb < proxy.files.max_file_size_mb {
+        errors.push("proxy.files: max_file_size_mb must be at least 1 and no larger than max_total_size_mb".to_string());
+    }
+
+    let mut provider_ids = BTreeSet::new();
+    for (i, provider) in proxy.providers.iter().enumerate() {
+        if provider.id.trim().is_empty() {
+            errors.push(format!("proxy.providers.{}: id must not be empty", i));
+        } else if !provider_ids.insert(provider.id.as_str()) {
+            errors.push(format!("proxy.providers.{}: duplicate id '{}'", i, provider.id));
+        }
+        if !is_valid_url(&provider.base_url, &["http", "https"]) {
+            errors.push(format!("proxy.providers.{}: invalid base_url '{}'", i, provider.base_url));
+        }
+        if provider.models.iter().any(|m| m.trim().is_empty()) {
+            errors.push(format!("proxy.providers.{}: model patterns must not be empty", i));
+        }
+    }
+
+    let mut rule_ids = BTreeSet::new();
+    for (i, rule) in proxy.model_routing_rules.iter().enumerate() {
+        if rule.id.trim().is_empty() {
+            errors.push(format!("proxy.model_routing_rules.{}: id must not be empty", i));
+        } else if !rule_ids.insert(rule.id.as_str()) {
+            errors.push(format!("proxy.model_routing_rules.{}: duplicate id '{}'", i, rule.id));
+        }
+        match &rule.target {
+            RouteTarget::Model { model } if model.trim().is_empty() => {
+                errors.push(format!("proxy.model_routing_rules.{}: target model is empty", i))
+            }
+            RouteTarget::Fallback { models } if models.is_empty() => {
+                errors.push(format!("proxy.model_routing_rules.{}: fallback models are empty", i))
+            }
+            RouteTarget::Provider { provider, .. } if !provider_ids.contains(provider.as_str()) => {
+                errors.push(format!(
+                    "proxy.model_routing_rules.{}: unknown provider '{}'",
+                    i, provider
+                ))
+            }
+            _ => {}
+        }
+    }
+
+    if config.circuit_breaker.enabled && config.circuit_breaker.backoff_steps.is_empty() {
+        errors.push("circuit_breaker.backoff_steps must not be empty when enabled".to_string());
+    }
+
+    if let Some(effort) = &proxy.thinking_budget.effort {
+        if !matches!(effort.as_str(), "low" | "medium" | "high") {
+            errors.push(format!(
+                "proxy.thinking_budget.effort must be low, medium or high (got '{}')",
+                effort
+            ));
+        }
+    }
+
+    for (i, price) in proxy.cost.prices.iter().enumerate() {
+        if price.model.trim().is_empty() {
+            errors.push(format!("proxy.cost.prices.{}: model must not be empty", i));
+        }
+        let rates = [Some(price.input), Some(price.output), price.cached_input, price.thinking];
+        if rates.iter().flatten().any(|rate| !rate.is_finite() || *rate < 0.0) {
+            errors.push(format!("proxy.cost.prices.{}: prices must be non-negative numbers", i));
+        }
+    }
+
+    let budget = &proxy.cost.budget;
+    if !budget.daily_usd.is_finite() || !budget.monthly_usd.is_finite()
+        || budget.daily_usd < 0.0 || budget.monthly_usd < 0.0
+    {
+        errors.push("proxy.cost.budget limits must be non-negative numbers".to_string());
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// 解析并校验配置文件内容
+pub fn parse_and_validate(content: &str) -> Result<AppConfig, String> {
+    let config: AppConfig =
+        serde_json::from_str(content).map_err(|e| format!("invalid JSON or field type: {}", e))?;
+    validate_config(&config).map_err(|errors| errors.join("; "))?;
+    Ok(config)
+}
+
+fn file_stamp(path: &std::path::Path) -> Option<(SystemTime, u64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// 启动配置文件监听 (headless 模式)。设置 ABV_DISABLE_CONFIG_WATCH=1 可关闭。
+pub fn start_config_watcher(proxy_state: crate::commands::proxy::ProxyServiceState) {
+    let disabled = std::env::var("ABV_DISABLE_CONFIG_WATCH")
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false);
+    if disabled {
+        info!("[ConfigWatcher] Disabled by ABV_DISABLE_CONFIG_WATCH");
+        return;
+    }
+
+    let path = match super::config::get_config_path() {
+        Ok(p) => p,
+        Err(e) => {
+            error!("[ConfigWatcher] Cannot resolve config path: {}", e);
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        // 启动时的文件内容即当前生效配置
+        let mut applied = match super::config::load_app_config() {
+            Ok(c) => c,
+            Err(e) => {
+                error!("[ConfigWatcher] Failed to load baseline config: {}", e);
+                return;
+            }
+        };
+        let mut last_stamp = file_stamp(&path);
+        let mut last_content = std::fs::read_to_string(&path).ok();
+        info!("[ConfigWatcher] Watching {} for changes", path.display());
+
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let stamp = file_stamp(&path);
+            if stamp.is_none() || stamp == last_stamp {
+                continue;
+            }
+            tokio::time::sleep(SETTLE_DELAY).await;
+            if file_stamp(&path) != stamp {
+                // 仍在写入，下个周期再处理
+                continue;
+            }
+            last_stamp = stamp;
+
+            let content = match std::fs::read_to_string(&path) {
+                Ok(c) => c,
+                Err(e) => {
+                    warn!("[ConfigWatcher] Failed to read {}: {}", path.display(), e);
+                    continue;
+                }
+            };
+            if last_content.as_deref() == Some(content.as_str()) {
+                continue;
+            }
+            last_content = Some(content.clone());
+
+            let next = match parse_and_validate(&content) {
+                Ok(c) => c,
+                Err(e) => {
+                    error!(
+                        "[ConfigWatcher] Rejected invalid {}, keeping the running config: {}",
+                        path.display(),
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            let plan = plan_reload(&applied, &next);
+            if plan.is_empty() {
+                continue;
+            }
+            info!(
+                "[ConfigWatcher] Config file changed: {} hot, {} need restart, {} other",
+                plan.hot_changes.len(),
+                plan.restart_changes.len(),
+                plan.other_changes.len()
+            );
+            plan.log();
+
+            if !plan.sections.is_empty() {
+                crate::commands::proxy::apply_hot_config(&proxy_state, &next, &plan.sections).await;
+                info!("[ConfigWatcher] Hot-applied: {:?}", plan.sections);
+            }
+            applied = next;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_reload_classifies_changes() {
+        let current = AppConfig::new();
+        let mut next = current.clone();
+        next.proxy
+            .custom_mapping
+            .insert("gpt-4o".to_string(), "gemini-3-flash".to_string());
+        next.proxy.scheduling.max_wait_seconds = 5;
+        next.circuit_breaker.enabled = !current.circuit_breaker.enabled;
+        next.proxy.port = 9000;
+        next.proxy.api_key = "sk-new".to_string();
+        next.language = "en".to_string();
+
+        let plan = plan_reload(&current, &next);
+        assert_eq!(
+            plan.sections,
+            vec![
+                HotSection::ModelMapping,
+                HotSection::Scheduling,
+                HotSection::CircuitBreaker
+            ]
+        );
+        let hot: Vec<&str> = plan.hot_changes.iter().map(|c| c.path.as_str()).collect();
+        assert!(hot.contains(&"proxy.custom_mapping.gpt-4o"));
+        assert!(hot.contains(&"proxy.scheduling.max_wait_seconds"));
+        assert!(hot.contains(&"circuit_breaker.enabled"));
+
+        let restart: Vec<&str> = plan.restart_changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(restart, vec!["proxy.api_key", "proxy.port"]);
+        assert_eq!(plan.other_changes.len(), 1);
+        assert_eq!(plan.other_changes[0].path, "language");
+
+        assert!(plan_reload(&current, &current.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_change_display_masks_secrets() {
+        let change = ConfigChange {
+            path: "proxy.zai.api_key".to_string(),
+            old: Some(Value::String(String::new())),
+            new: Some(Value::String("secret".to_string())),
+        };
+        assert_eq!(change.to_string(), "proxy.zai.api_key: \"\" -> ***");
+
+        let nested = ConfigChange {
+            path: "webhooks.endpoints".to_string(),
+            old: None,
+            new: Some(serde_json::json!([{ "url": "https://h", "secret": "s3cr3t" }])),
+        };
+        let text = nested.to_string();
+        assert!(!text.contains("s3cr3t"));
+        assert!(text.contains("\"secret\":\"***\""));
+
+        let keys = ConfigChange {
+            path: "proxy.providers".to_string(),
+            old: None,
+            new: Some(serde_json::json!([{ "id": "vllm", "api_keys": ["sk-a", "sk-b"] }])),
+        };
+        let text = keys.to_string();
+        assert!(!text.contains("sk-a") && !text.contains("sk-b"));
+        assert!(text.contains("\"api_keys\":[\"***\",\"***\"]"));
+    }
+
+    #[test]
+    fn test_parse_and_validate_rejects_bad_configs() {
+        let valid = serde_json::to_string(&AppConfig::new()).unwrap();
+        assert!(parse_and_validate(&valid).is_ok());
+
+        assert!(parse_and_validate("{ not json").is_err());
+
+        let mut v: Value = serde_json::from_str(&valid).unwrap();
+        v["proxy"]["port"] = Value::String("eighty".to_string());
+        assert!(parse_and_validate(&v.to_string()).is_err());
+
+        let mut config = AppConfig::new();
+        config.proxy.upstream_proxy.enabled = true;
+        config.proxy.upstream_proxy.url = "not a url".to_string();
+        config.proxy.thinking_budget.effort = Some("extreme".to_string());
+        let errors = validate_config(&config).unwrap_err();
+        assert_eq!(errors.len(), 2);
+    }
+}
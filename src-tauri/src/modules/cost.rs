@@ -0,0 +1,347 @@
+//! 费用核算与预算
+//!
+//! - 从各协议的 usage 对象中拆分出 输入 / 缓存输入 / 输出 / 思考 Token
+//! - 按 `proxy.cost.prices` 价格表 (美元 / 百万 Token) 计算单次请求费用
+//! - 判断全局与用户令牌的日 / 月预算是否超限，超限时按配置告警或拒绝
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Timelike, Utc};
+use serde_json::{json, Value};
+
+use crate::modules::user_token_db::QuotaViolation;
+use crate::proxy::config::{BudgetConfig, ModelPrice};
+
+/// 从单个 usage 对象解析出的 Token 用量 (仅包含对象中出现的字段)
+///
+/// 统一口径：`cached_tokens` 属于 `input_tokens` 的一部分，`thinking_tokens` 属于
+/// `output_tokens` 的一部分。Anthropic 的缓存读取与 Gemini 的思考 Token 原本单独计数，
+/// 解析时会并入总量。
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct UsageReport {
+    pub input_tokens: Option<u32>,
+    pub output_tokens: Option<u32>,
+    pub cached_tokens: Option<u32>,
+    pub thinking_tokens: Option<u32>,
+}
+
+fn field(v: &Value, key: &str) -> Option<u32> {
+    v.get(key).and_then(|n| n.as_u64()).map(|n| n as u32)
+}
+
+impl UsageReport {
+    /// 解析 OpenAI (Chat / Responses)、Anthropic 与 Gemini 的 usage 对象
+    pub fn parse(usage: &Value) -> Self {
+        // Gemini: usageMetadata (思考 Token 不含在 candidatesTokenCount 中)
+        if usage.get("promptTokenCount").is_some() || usage.get("candidatesTokenCount").is_some() {
+            let thinking = field(usage, "thoughtsTokenCount");
+            return Self {
+                input_tokens: field(usage, "promptTokenCount"),
+                output_tokens: field(usage, "candidatesTokenCount")
+                    .map(|out| out + thinking.unwrap_or(0)),
+                cached_tokens: field(usage, "cachedContentTokenCount"),
+                thinking_tokens: thinking,
+            };
+        }
+
+        // OpenAI Chat Completions: 缓存与推理 Token 已包含在总量中
+        if usage.get("prompt_tokens").is_some() || usage.get("completion_tokens").is_some() {
+            return Self {
+                input_tokens: field(usage, "prompt_tokens"),
+                output_tokens: field(usage, "completion_tokens"),
+                cached_tokens: usage.get("prompt_tokens_details").and_then(|d| field(d, "cached_tokens")),
+                thinking_tokens: usage
+                    .get("completion_tokens_details")
+                    .and_then(|d| field(d, "reasoning_tokens")),
+            };
+        }
+
+        // Anthropic: input_tokens 不含缓存读取 / 写入
+        let cache_read = field(usage, "cache_read_input_tokens");
+        let cache_creation = field(usage, "cache_creation_input_tokens");
+        if cache_read.is_some() || cache_creation.is_some() {
+            return Self {
+                input_tokens: field(usage, "input_tokens")
+                    .map(|input| input + cache_read.unwrap_or(0) + cache_creation.unwrap_or(0)),
+                output_tokens: field(usage, "output_tokens"),
+                cached_tokens: cache_read,
+                thinking_tokens: None,
+            };
+        }
+
+        // OpenAI Responses (以及不带缓存字段的 Anthropic usage)
+        Self {
+            input_tokens: field(usage, "input_tokens"),
+            output_tokens: field(usage, "output_tokens"),
+            cached_tokens: usage.get("input_tokens_details").and_then(|d| field(d, "cached_tokens")),
+            thinking_tokens: usage
+                .get("output_tokens_details")
+                .and_then(|d| field(d, "reasoning_tokens")),
+        }
+    }
+}
+
+/// 一次请求的完整 Token 用量 (口径同 [`UsageReport`])
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenUsage {
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub cached_tokens: u32,
+    pub thinking_tokens: u32,
+}
+
+impl TokenUsage {
+    pub fn from_log(log: &crate::proxy::monitor::ProxyRequestLog) -> Option<Self> {
+        Some(Self {
+            input_tokens: log.input_tokens?,
+            output_tokens: log.output_tokens?,
+            cached_tokens: log.cached_tokens.unwrap_or(0),
+            thinking_tokens: log.thinking_tokens.unwrap_or(0),
+        })
+    }
+}
+
+/// 查找模型对应的价格：精确匹配优先，其次按顺序取第一条通配符匹配
+pub fn find_price<'a>(prices: &'a [ModelPrice], model: &str) -> Option<&'a ModelPrice> {
+    prices.iter().find(|p| p.model == model).or_else(|| {
+        prices
+            .iter()
+            .find(|p| crate::proxy::common::model_mapping::wildcard_match(&p.model, model))
+    })
+}
+
+/// 按价格计算费用 (美元)
+pub fn compute_cost(price: &ModelPrice, usage: &TokenUsage) -> f64 {
+    let cached = usage.cached_tokens.min(usage.input_tokens);
+    let thinking = usage.thinking_tokens.min(usage.output_tokens);
+    let uncached_input = (usage.input_tokens - cached) as f64;
+    let visible_output = (usage.output_tokens - thinking) as f64;
+
+    (uncached_input * price.input
+        + cached as f64 * price.cached_input.unwrap_or(price.input)
+        + visible_output * price.output
+        + thinking as f64 * price.thinking.unwrap_or(price.output))
+        / 1_000_000.0
+}
+
+/// 按当前价格表估算费用：先按实际路由后的模型查价，再回退到客户端请求的模型。
+/// 两者都没有价格时返回 None。
+pub fn estimate_cost(models: &[Option<&str>], usage: &TokenUsage) -> Option<f64> {
+    let config = crate::proxy::config::get_cost_config();
+    models
+        .iter()
+        .flatten()
+        .find_map(|model| find_price(&config.prices, model))
+        .map(|price| compute_cost(price, usage))
+}
+
+/// 计算一条请求日志的费用 (缓存命中不消耗上游额度，费用为 0)
+pub fn cost_for_log(log: &crate::proxy::monitor::ProxyRequestLog) -> Option<f64> {
+    if log.cache_hit {
+        return Some(0.0);
+    }
+    let usage = TokenUsage::from_log(log)?;
+    estimate_cost(&[log.mapped_model.as_deref(), log.model.as_deref()], &usage)
+}
+
+/// 预算周期
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetPeriod {
+    Day,
+    Month,
+}
+
+impl BudgetPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BudgetPeriod::Day => "day",
+            BudgetPeriod::Month => "month",
+        }
+    }
+}
+
+/// 判断已花费金额是否超出预算 (先检查月预算，再检查日预算)
+///
+/// `owner` 为 None 表示全局预算，否则为用户令牌的用户名。
+pub fn budget_violation(
+    budget: &BudgetConfig,
+    spent_today: f64,
+    spent_this_month: f64,
+    now: DateTime<Utc>,
+    owner: Option<&str>,
+) -> Option<QuotaViolation> {
+    let owner = owner.map(|s| s.to_string());
+    if budget.monthly_usd > 0.0 && spent_this_month >= budget.monthly_usd {
+        return Some(QuotaViolation::BudgetExceeded {
+            owner,
+            period: BudgetPeriod::Month,
+            used_usd: spent_this_month,
+            limit_usd: budget.monthly_usd,
+            retry_after_secs: crate::modules::user_token_db::secs_until_next_month(now),
+        });
+    }
+    if budget.daily_usd > 0.0 && spent_today >= budget.daily_usd {
+        return Some(QuotaViolation::BudgetExceeded {
+            owner,
+            period: BudgetPeriod::Day,
+            used_usd: spent_today,
+            limit_usd: budget.daily_usd,
+            retry_after_secs: 86_400 - now.num_seconds_from_midnight() as i64,
+        });
+    }
+    None
+}
+
+/// 检查全局预算 (读取 token_stats 中当日 / 当月的费用合计)
+pub fn check_global_budget() -> Result<Option<QuotaViolation>, String> {
+    let budget = crate::proxy::config::get_cost_config().budget;
+    if !budget.is_enabled() {
+        return Ok(None);
+    }
+    let now = Utc::now();
+    let (spent_today, spent_this_month) = crate::modules::token_stats::get_period_costs(now)?;
+    Ok(budget_violation(&budget, spent_today, spent_this_month, now, None))
+}
+
+/// 已发出告警的预算周期 (subject -> 周期标识)，同一周期只告警一次
+static WARNED_PERIODS: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+
+/// 预算超限但配置为 warn 时调用：记录日志并发送 `budget_exceeded` Webhook (每个周期一次)
+pub fn warn_budget_exceeded(violation: &QuotaViolation) {
+    let QuotaViolation::BudgetExceeded { owner, period, used_usd, limit_usd, .. } = violation else {
+        return;
+    };
+    let subject = match owner {
+        Some(username) => format!("token:{}/{}", username, period.as_str()),
+        None => format!("global/{}", period.as_str()),
+    };
+    let now = Utc::now();
+    let period_key = match period {
+        BudgetPeriod::Day => now.format("%Y-%m-%d").to_string(),
+        BudgetPeriod::Month => now.format("%Y-%m").to_string(),
+    };
+
+    {
+        let mut guard = match WARNED_PERIODS.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let warned = guard.get_or_insert_with(HashMap::new);
+        if warned.get(&subject) == Some(&period_key) {
+            return;
+        }
+        warned.insert(subject.clone(), period_key.clone());
+    }
+
+    let message = violation.message();
+    tracing::warn!("[Cost] Budget exceeded ({}): {}", subject, message);
+    crate::modules::webhook::notify(
+        crate::modules::webhook::WebhookEvent::BudgetExceeded,
+        subject,
+        message,
+        json!({
+            "scope": if owner.is_some() { "user_token" } else { "global" },
+            "username": owner,
+            "period": period.as_str(),
+            "period_key": period_key,
+            "used_usd": used_usd,
+            "limit_usd": limit_usd,
+        }),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn price(model: &str) -> ModelPrice {
+        ModelPrice {
+            model: model.to_string(),
+            input: 2.0,
+            output: 10.0,
+            cached_input: Some(0.5),
+            thinking: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_usage_formats() {
+        let gemini = UsageReport::parse(&json!({
+            "promptTokenCount": 1000, "candidatesTokenCount": 200,
+            "thoughtsTokenCount": 50, "cachedContentTokenCount": 400
+        }));
+        assert_eq!(gemini.input_tokens, Some(1000));
+        assert_eq!(gemini.output_tokens, Some(250));
+        assert_eq!(gemini.cached_tokens, Some(400));
+        assert_eq!(gemini.thinking_tokens, Some(50));
+
+        let openai = UsageReport::parse(&json!({
+            "prompt_tokens": 100, "completion_tokens": 80,
+            "prompt_tokens_details": { "cached_tokens": 60 },
+            "completion_tokens_details": { "reasoning_tokens": 30 }
+        }));
+        assert_eq!(openai.input_tokens, Some(100));
+        assert_eq!(openai.output_tokens, Some(80));
+        assert_eq!(openai.cached_tokens, Some(60));
+        assert_eq!(openai.thinking_tokens, Some(30));
+
+        let anthropic = UsageReport::parse(&json!({
+            "input_tokens": 20, "output_tokens": 5,
+            "cache_read_input_tokens": 300, "cache_creation_input_tokens": 0
+        }));
+        assert_eq!(anthropic.input_tokens, Some(320));
+        assert_eq!(anthropic.cached_tokens, Some(300));
+
+        let responses = UsageReport::parse(&json!({
+            "input_tokens": 10, "output_tokens": 7,
+            "output_tokens_details": { "reasoning_tokens": 4 }
+        }));
+        assert_eq!(responses.input_tokens, Some(10));
+        assert_eq!(responses.thinking_tokens, Some(4));
+
+        // Anthropic 流式 message_delta 只携带输出
+        let delta = UsageReport::parse(&json!({ "output_tokens": 42 }));
+        assert_eq!(delta.input_tokens, None);
+        assert_eq!(delta.output_tokens, Some(42));
+    }
+
+    #[test]
+    fn test_find_price_and_compute_cost() {
+        let prices = vec![price("gemini-*"), ModelPrice { input: 1.0, ..price("gemini-3-flash") }];
+        assert_eq!(find_price(&prices, "gemini-3-flash").unwrap().input, 1.0);
+        assert_eq!(find_price(&prices, "gemini-3-pro").unwrap().input, 2.0);
+        assert!(find_price(&prices, "claude-sonnet-4-5").is_none());
+
+        let usage = TokenUsage {
+            input_tokens: 1_000_000,
+            output_tokens: 500_000,
+            cached_tokens: 400_000,
+            thinking_tokens: 100_000,
+        };
+        // 600k * $2 + 400k * $0.5 + 400k * $10 + 100k * $10 (thinking 默认按 output)
+        let cost = compute_cost(&price("gemini-*"), &usage);
+        assert!((cost - (1.2 + 0.2 + 4.0 + 1.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_budget_violation() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 31, 23, 0, 0).unwrap();
+        let budget = BudgetConfig { daily_usd: 10.0, monthly_usd: 100.0, ..Default::default() };
+
+        assert!(budget_violation(&budget, 9.99, 50.0, now, None).is_none());
+
+        let daily = budget_violation(&budget, 10.0, 50.0, now, Some("alice")).unwrap();
+        assert!(matches!(
+            &daily,
+            QuotaViolation::BudgetExceeded { period: BudgetPeriod::Day, owner: Some(name), .. } if name == "alice"
+        ));
+        assert_eq!(daily.retry_after_secs(), Some(3600));
+
+        let monthly = budget_violation(&budget, 20.0, 120.0, now, None).unwrap();
+        assert!(matches!(monthly, QuotaViolation::BudgetExceeded { period: BudgetPeriod::Month, .. }));
+
+        assert!(budget_violation(&BudgetConfig::default(), 1e9, 1e9, now, None).is_none());
+    }
+}
@@ -0,0 +1,494 @@
+//! Files API 本地存储
+//!
+//! 元数据写入 `files.db`，内容按 SHA-256 存放在 `files/` 目录：相同内容只保存一份，
+//! 最后一个引用被删除或过期后再删除内容。
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StoredFile {
+    pub id: String,
+    pub filename: String,
+    pub mime_type: String,
+    pub size_bytes: u64,
+    pub sha256: String,
+    /// OpenAI 上传的 purpose (Anthropic 上传为空)
+    pub purpose: Option<String>,
+    /// 上传者的用户令牌 ID (使用 api_key 或未鉴权时为空)
+    pub owner_token_id: Option<String>,
+    pub created_at: i64,
+    /// 为空表示不过期
+    pub expires_at: Option<i64>,
+}
+
+/// 上传时的元数据
+#[derive(Debug, Clone)]
+pub struct NewFile<'a> {
+    pub id: &'a str,
+    pub filename: &'a str,
+    pub mime_type: &'a str,
+    pub purpose: Option<&'a str>,
+    pub owner_token_id: Option<&'a str>,
+    pub expires_at: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FileStoreStats {
+    pub files: u64,
+    /// 各文件大小之和
+    pub total_bytes: u64,
+    /// 去重后实际占用的磁盘空间
+    pub stored_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveFileError {
+    /// 写入后会超过存储总量上限
+    StorageFull { limit_bytes: u64 },
+    Other(String),
+}
+
+impl std::fmt::Display for SaveFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::StorageFull { limit_bytes } => write!(
+                f,
+                "File storage is full ({} MB limit); delete files or raise proxy.files.max_total_size_mb",
+                limit_bytes / 1024 / 1024
+            ),
+            Self::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+pub fn get_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("files.db"))
+}
+
+/// 文件内容目录
+pub fn get_blob_dir() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("files"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let conn = Connection::open(get_db_path()?).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS files (
+            id TEXT PRIMARY KEY,
+            filename TEXT NOT NULL,
+            mime_type TEXT NOT NULL,
+            size_bytes INTEGER NOT NULL,
+            sha256 TEXT NOT NULL,
+            purpose TEXT,
+            owner_token_id TEXT,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER
+        )",
+        [],
+    )
+    .map_err(|e| format!("Failed to create files table: {}", e))?;
+
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_files_sha256 ON files (sha256)", [])
+        .map_err(|e| e.to_string())?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_files_expires ON files (expires_at)", [])
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// 初始化数据库与内容目录，并清理已过期的文件
+pub fn init_db() -> Result<(), String> {
+    let conn = connect_db()?;
+    create_tables(&conn)?;
+    let dir = get_blob_dir()?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create file store: {}", e))?;
+    let purged = purge_expired_at(&conn, &dir, now())?;
+    if purged > 0 {
+        tracing::info!("[Files] Removed {} expired file(s)", purged);
+    }
+    Ok(())
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+fn blob_path(dir: &Path, sha256: &str) -> PathBuf {
+    dir.join(sha256)
+}
+
+/// 写入内容 (已存在则跳过)，先写临时文件再重命名，避免读到半截内容
+fn write_blob(dir: &Path, sha256: &str, bytes: &[u8]) -> Result<(), String> {
+    let path = blob_path(dir, sha256);
+    if path.exists() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let tmp = dir.join(format!("{}.tmp-{}", sha256, uuid::Uuid::new_v4().simple()));
+    std::fs::write(&tmp, bytes).map_err(|e| format!("Failed to write file: {}", e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        format!("Failed to store file: {}", e)
+    })
+}
+
+/// 没有记录再引用该内容时删除
+fn remove_blob_if_orphan(conn: &Connection, dir: &Path, sha256: &str) -> Result<(), String> {
+    let refs: i64 = conn
+        .query_row("SELECT COUNT(*) FROM files WHERE sha256 = ?1", params![sha256], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    if refs == 0 {
+        let _ = std::fs::remove_file(blob_path(dir, sha256));
+    }
+    Ok(())
+}
+
+const FILE_COLUMNS: &str =
+    "id, filename, mime_type, size_bytes, sha256, purpose, owner_token_id, created_at, expires_at";
+
+fn row_to_file(row: &rusqlite::Row) -> rusqlite::Result<StoredFile> {
+    Ok(StoredFile {
+        id: row.get(0)?,
+        filename: row.get(1)?,
+        mime_type: row.get(2)?,
+        size_bytes: row.get::<_, i64>(3)? as u64,
+        sha256: row.get(4)?,
+        purpose: row.get(5)?,
+        owner_token_id: row.get(6)?,
+        created_at: row.get(7)?,
+        expires_at: row.get(8)?,
+    })
+}
+
+fn stats_at(conn: &Connection, now: i64) -> Result<FileStoreStats, String> {
+    let (files, total_bytes): (i64, i64) = conn
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(size_bytes), 0) FROM files
+             WHERE expires_at IS NULL OR expires_at > ?1",
+            params![now],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())?;
+    // 过期但尚未清理的文件仍占用空间，按全部记录统计
+    let stored_bytes: i64 = conn
+        .query_row(
+            "SELECT COALESCE(SUM(size_bytes), 0) FROM (SELECT MAX(size_bytes) AS size_bytes FROM files GROUP BY sha256)",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(FileStoreStats {
+        files: files as u64,
+        total_bytes: total_bytes as u64,
+        stored_bytes: stored_bytes as u64,
+    })
+}
+
+fn save_file_at(
+    conn: &Connection,
+    dir: &Path,
+    file: &NewFile,
+    bytes: &[u8],
+    max_total_bytes: u64,
+    now: i64,
+) -> Result<StoredFile, SaveFileError> {
+    purge_expired_at(conn, dir, now).map_err(SaveFileError::Other)?;
+
+    let sha256 = sha256_hex(bytes);
+    let exists: bool = conn
+        .query_row("SELECT 1 FROM files WHERE sha256 = ?1 LIMIT 1", params![sha256], |_| Ok(()))
+        .optional()
+        .map_err(|e| SaveFileError::Other(e.to_string()))?
+        .is_some();
+    // 已有相同内容时不占用新空间
+    if !exists {
+        let stats = stats_at(conn, now).map_err(SaveFileError::Other)?;
+        if stats.stored_bytes + bytes.len() as u64 > max_total_bytes {
+            return Err(SaveFileError::StorageFull { limit_bytes: max_total_bytes });
+        }
+    }
+
+    write_blob(dir, &sha256, bytes).map_err(SaveFileError::Other)?;
+    conn.execute(
+        "INSERT INTO files (id, filename, mime_type, size_bytes, sha256, purpose, owner_token_id, created_at, expires_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            file.id,
+            file.filename,
+            file.mime_type,
+            bytes.len() as i64,
+            sha256,
+            file.purpose,
+            file.owner_token_id,
+            now,
+            file.expires_at
+        ],
+    )
+    .map_err(|e| SaveFileError::Other(e.to_string()))?;
+
+    Ok(StoredFile {
+        id: file.id.to_string(),
+        filename: file.filename.to_string(),
+        mime_type: file.mime_type.to_string(),
+        size_bytes: bytes.len() as u64,
+        sha256,
+        purpose: file.purpose.map(|p| p.to_string()),
+        owner_token_id: file.owner_token_id.map(|o| o.to_string()),
+        created_at: now,
+        expires_at: file.expires_at,
+    })
+}
+
+fn get_file_at(conn: &Connection, id: &str, now: i64) -> Result<Option<StoredFile>, String> {
+    let sql = format!(
+        "SELECT {} FROM files WHERE id = ?1 AND (expires_at IS NULL OR expires_at > ?2)",
+        FILE_COLUMNS
+    );
+    conn.query_row(&sql, params![id, now], row_to_file)
+        .optional()
+        .map_err(|e| e.to_string())
+}
+
+fn list_files_at(
+    conn: &Connection,
+    owner_token_id: Option<&str>,
+    purpose: Option<&str>,
+    after_id: Option<&str>,
+    before_id: Option<&str>,
+    limit: usize,
+    now: i64,
+) -> Result<(Vec<StoredFile>, bool), String> {
+    // 最新的在前；after_id 翻向更旧的一页，before_id 翻向更新的一页
+    let newer_page = after_id.is_none() && before_id.is_some();
+    let sql = format!(
+        "SELECT {} FROM files
+         WHERE (expires_at IS NULL OR expires_at > ?1)
+           AND (?2 IS NULL OR owner_token_id = ?2)
+           AND (?3 IS NULL OR purpose = ?3)
+           AND (?4 IS NULL OR rowid < (SELECT rowid FROM files WHERE id = ?4))
+           AND (?5 IS NULL OR rowid > (SELECT rowid FROM files WHERE id = ?5))
+         ORDER BY rowid {}
+         LIMIT ?6",
+        FILE_COLUMNS,
+        if newer_page { "ASC" } else { "DESC" }
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            params![now, owner_token_id, purpose, after_id, before_id, (limit + 1) as i64],
+            row_to_file,
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut files = Vec::new();
+    for row in rows {
+        files.push(row.map_err(|e| e.to_string())?);
+    }
+    let has_more = files.len() > limit;
+    files.truncate(limit);
+    if newer_page {
+        files.reverse();
+    }
+    Ok((files, has_more))
+}
+
+fn delete_file_at(conn: &Connection, dir: &Path, id: &str) -> Result<bool, String> {
+    let sha256: Option<String> = conn
+        .query_row("SELECT sha256 FROM files WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()
+        .map_err(|e| e.to_string())?;
+    let Some(sha256) = sha256 else {
+        return Ok(false);
+    };
+    conn.execute("DELETE FROM files WHERE id = ?1", params![id])
+        .map_err(|e| e.to_string())?;
+    remove_blob_if_orphan(conn, dir, &sha256)?;
+    Ok(true)
+}
+
+fn purge_expired_at(conn: &Connection, dir: &Path, now: i64) -> Result<usize, String> {
+    let mut stmt = conn
+        .prepare("SELECT DISTINCT sha256 FROM files WHERE expires_at IS NOT NULL AND expires_at <= ?1")
+        .map_err(|e| e.to_string())?;
+    let shas = stmt
+        .query_map(params![now], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    if shas.is_empty() {
+        return Ok(0);
+    }
+
+    let removed = conn
+        .execute(
+            "DELETE FROM files WHERE expires_at IS NOT NULL AND expires_at <= ?1",
+            params![now],
+        )
+        .map_err(|e| e.to_string())?;
+    for sha256 in shas {
+        remove_blob_if_orphan(conn, dir, &sha256)?;
+    }
+    Ok(removed)
+}
+
+/// 保存上传的文件
+pub fn save_file(file: &NewFile, bytes: &[u8], max_total_bytes: u64) -> Result<StoredFile, SaveFileError> {
+    let conn = connect_db().map_err(SaveFileError::Other)?;
+    let dir = get_blob_dir().map_err(SaveFileError::Other)?;
+    save_file_at(&conn, &dir, file, bytes, max_total_bytes, now())
+}
+
+/// 查询未过期的文件元数据
+pub fn get_file(id: &str) -> Result<Option<StoredFile>, String> {
+    get_file_at(&connect_db()?, id, now())
+}
+
+/// 读取文件内容
+pub fn read_content(file: &StoredFile) -> Result<Vec<u8>, String> {
+    let path = blob_path(&get_blob_dir()?, &file.sha256);
+    std::fs::read(&path).map_err(|e| format!("Failed to read file '{}': {}", file.id, e))
+}
+
+/// 查询元数据并读取内容，文件不存在或已过期时返回 None
+pub fn load_file(id: &str) -> Result<Option<(StoredFile, Vec<u8>)>, String> {
+    match get_file(id)? {
+        Some(file) => {
+            let bytes = read_content(&file)?;
+            Ok(Some((file, bytes)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// 分页列出文件，返回 (文件, 是否还有更多)；owner_token_id / purpose 为 None 时不过滤
+pub fn list_files(
+    owner_token_id: Option<&str>,
+    purpose: Option<&str>,
+    after_id: Option<&str>,
+    before_id: Option<&str>,
+    limit: usize,
+) -> Result<(Vec<StoredFile>, bool), String> {
+    list_files_at(&connect_db()?, owner_token_id, purpose, after_id, before_id, limit, now())
+}
+
+/// 删除文件，不存在时返回 false
+pub fn delete_file(id: &str) -> Result<bool, String> {
+    delete_file_at(&connect_db()?, &get_blob_dir()?, id)
+}
+
+pub fn get_stats() -> Result<FileStoreStats, String> {
+    stats_at(&connect_db()?, now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (Connection, PathBuf) {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let dir = std::env::temp_dir().join(format!("ag_files_test_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        (conn, dir)
+    }
+
+    fn new_file<'a>(id: &'a str, expires_at: Option<i64>) -> NewFile<'a> {
+        NewFile {
+            id,
+            filename: "doc.pdf",
+            mime_type: "application/pdf",
+            purpose: None,
+            owner_token_id: Some("t1"),
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn test_identical_content_is_stored_once() {
+        let (conn, dir) = setup();
+        let a = save_file_at(&conn, &dir, &new_file("file_a", None), b"same bytes", 1024, 100).unwrap();
+        let b = save_file_at(&conn, &dir, &new_file("file_b", None), b"same bytes", 1024, 101).unwrap();
+        assert_eq!(a.sha256, b.sha256);
+
+        let stats = stats_at(&conn, 102).unwrap();
+        assert_eq!(stats.files, 2);
+        assert_eq!(stats.total_bytes, 20);
+        assert_eq!(stats.stored_bytes, 10);
+
+        // 删除其中一个引用时内容保留，删除最后一个后移除
+        assert!(delete_file_at(&conn, &dir, "file_a").unwrap());
+        assert!(blob_path(&dir, &a.sha256).exists());
+        assert!(delete_file_at(&conn, &dir, "file_b").unwrap());
+        assert!(!blob_path(&dir, &a.sha256).exists());
+        assert!(!delete_file_at(&conn, &dir, "file_b").unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_storage_limit_counts_unique_content() {
+        let (conn, dir) = setup();
+        save_file_at(&conn, &dir, &new_file("file_a", None), b"0123456789", 15, 100).unwrap();
+        // 重复内容不占用新空间
+        assert!(save_file_at(&conn, &dir, &new_file("file_b", None), b"0123456789", 15, 100).is_ok());
+        assert_eq!(
+            save_file_at(&conn, &dir, &new_file("file_c", None), b"abcdefghij", 15, 100).unwrap_err(),
+            SaveFileError::StorageFull { limit_bytes: 15 }
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_expired_files_are_hidden_and_purged() {
+        let (conn, dir) = setup();
+        let file = save_file_at(&conn, &dir, &new_file("file_old", Some(150)), b"old", 1024, 100).unwrap();
+        assert!(get_file_at(&conn, "file_old", 120).unwrap().is_some());
+        assert!(get_file_at(&conn, "file_old", 150).unwrap().is_none());
+
+        assert_eq!(purge_expired_at(&conn, &dir, 200).unwrap(), 1);
+        assert!(!blob_path(&dir, &file.sha256).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_list_files_filters_and_pages() {
+        let (conn, dir) = setup();
+        for (i, id) in ["f1", "f2", "f3"].iter().enumerate() {
+            let mut file = new_file(id, None);
+            file.purpose = Some(if i == 0 { "batch" } else { "user_data" });
+            save_file_at(&conn, &dir, &file, id.as_bytes(), 1024, 100 + i as i64).unwrap();
+        }
+
+        let (page, has_more) = list_files_at(&conn, Some("t1"), None, None, None, 2, 200).unwrap();
+        assert_eq!(page.iter().map(|f| f.id.as_str()).collect::<Vec<_>>(), vec!["f3", "f2"]);
+        assert!(has_more);
+
+        let (page, _) = list_files_at(&conn, Some("t1"), None, Some("f2"), None, 2, 200).unwrap();
+        assert_eq!(page[0].id, "f1");
+
+        let (page, _) = list_files_at(&conn, None, Some("batch"), None, None, 10, 200).unwrap();
+        assert_eq!(page.len(), 1);
+        assert!(list_files_at(&conn, Some("t2"), None, None, None, 10, 200).unwrap().0.is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
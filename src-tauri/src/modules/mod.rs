@@ -1,6 +1,12 @@
 pub mod account;
+pub mod admin_user_db;
+pub mod account_crypto;
+pub mod audit;
+pub mod audit_db;
+pub mod backup;
 pub mod quota;
 pub mod config;
+pub mod config_watcher;
 pub mod logger;
 pub mod db;
 pub mod process;
@@ -14,6 +20,14 @@ pub mod device;
 pub mod update_checker;
 pub mod scheduler;
 pub mod token_stats;
+pub mod cost;
+pub mod rate_limit_db;
+pub mod session_state_db;
+pub mod response_cache_db;
+pub mod webhook;
+pub mod webhook_db;
+pub mod batch_db;
+pub mod file_db;
 pub mod cloudflared;
 pub mod integration;
 pub mod account_service;
@@ -51,6 +51,10 @@ pub fn init_db() -> Result<(), String> {
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN protocol TEXT", []);
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN client_ip TEXT", []);
     let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN username TEXT", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN cache_hit INTEGER", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN cached_tokens INTEGER", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN thinking_tokens INTEGER", []);
+    let _ = conn.execute("ALTER TABLE request_logs ADD COLUMN cost_usd REAL", []);
 
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_timestamp ON request_logs (timestamp DESC)",
@@ -70,8 +74,8 @@ pub fn save_log(log: &ProxyRequestLog) -> Result<(), String> {
     let conn = connect_db()?;
 
     conn.execute(
-        "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, cache_hit, cached_tokens, thinking_tokens, cost_usd)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
         params![
             log.id,
             log.timestamp,
@@ -90,6 +94,10 @@ pub fn save_log(log: &ProxyRequestLog) -> Result<(), String> {
             log.protocol,
             log.client_ip,
             log.username,
+            log.cache_hit,
+            log.cached_tokens,
+            log.thinking_tokens,
+            log.cost_usd,
         ],
     ).map_err(|e| e.to_string())?;
 
@@ -103,7 +111,8 @@ pub fn get_logs_summary(limit: usize, offset: usize) -> Result<Vec<ProxyRequestL
     let mut stmt = conn.prepare(
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip
+                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, cache_hit,
+                cached_tokens, thinking_tokens, cost_usd
          FROM request_logs 
          ORDER BY timestamp DESC 
          LIMIT ?1 OFFSET ?2"
@@ -128,6 +137,10 @@ pub fn get_logs_summary(limit: usize, offset: usize) -> Result<Vec<ProxyRequestL
             protocol: row.get(14).unwrap_or(None),
             client_ip: row.get(15).unwrap_or(None),
             username: row.get(16).unwrap_or(None),
+            cache_hit: row.get::<_, Option<bool>>(17).unwrap_or(None).unwrap_or(false),
+            cached_tokens: row.get(18).unwrap_or(None),
+            thinking_tokens: row.get(19).unwrap_or(None),
+            cost_usd: row.get(20).unwrap_or(None),
         })
 
     }).map_err(|e| e.to_string())?;
@@ -173,7 +186,8 @@ pub fn get_log_detail(log_id: &str) -> Result<ProxyRequestLog, String> {
     let mut stmt = conn.prepare(
         "SELECT id, timestamp, method, url, status, duration, model, error,
                 request_body, response_body, input_tokens, output_tokens,
-                account_email, mapped_model, protocol, client_ip, username
+                account_email, mapped_model, protocol, client_ip, username, cache_hit,
+                cached_tokens, thinking_tokens, cost_usd
          FROM request_logs
          WHERE id = ?1"
     ).map_err(|e| e.to_string())?;
@@ -197,6 +211,10 @@ pub fn get_log_detail(log_id: &str) -> Result<ProxyRequestLog, String> {
             protocol: row.get(14).unwrap_or(None),
             client_ip: row.get(15).unwrap_or(None),
             username: row.get(16).unwrap_or(None),
+            cache_hit: row.get::<_, Option<bool>>(17).unwrap_or(None).unwrap_or(false),
+            cached_tokens: row.get(18).unwrap_or(None),
+            thinking_tokens: row.get(19).unwrap_or(None),
+            cost_usd: row.get(20).unwrap_or(None),
         })
     }).map_err(|e| e.to_string())
 }
@@ -293,7 +311,8 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
     let sql = if errors_only {
         "SELECT id, timestamp, method, url, status, duration, model, error,
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username
+                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, cache_hit,
+                cached_tokens, thinking_tokens, cost_usd
          FROM request_logs
          WHERE (status < 200 OR status >= 400)
          ORDER BY timestamp DESC
@@ -301,14 +320,16 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
     } else if filter.is_empty() {
         "SELECT id, timestamp, method, url, status, duration, model, error,
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username
+                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, cache_hit,
+                cached_tokens, thinking_tokens, cost_usd
          FROM request_logs
          ORDER BY timestamp DESC
          LIMIT ?1 OFFSET ?2"
     } else {
         "SELECT id, timestamp, method, url, status, duration, model, error,
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username
+                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, cache_hit,
+                cached_tokens, thinking_tokens, cost_usd
          FROM request_logs
          WHERE (url LIKE ?3 OR method LIKE ?3 OR model LIKE ?3 OR CAST(status AS TEXT) LIKE ?3 OR account_email LIKE ?3 OR client_ip LIKE ?3)
          ORDER BY timestamp DESC
@@ -336,6 +357,10 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
                 protocol: row.get(14).unwrap_or(None),
                 client_ip: row.get(15).unwrap_or(None),
                 username: row.get(16).unwrap_or(None),
+                cache_hit: row.get::<_, Option<bool>>(17).unwrap_or(None).unwrap_or(false),
+                cached_tokens: row.get(18).unwrap_or(None),
+                thinking_tokens: row.get(19).unwrap_or(None),
+                cost_usd: row.get(20).unwrap_or(None),
             })
 
         }).map_err(|e| e.to_string())?;
@@ -361,6 +386,10 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
                 protocol: row.get(14).unwrap_or(None),
                 client_ip: row.get(15).unwrap_or(None),
                 username: row.get(16).unwrap_or(None),
+                cache_hit: row.get::<_, Option<bool>>(17).unwrap_or(None).unwrap_or(false),
+                cached_tokens: row.get(18).unwrap_or(None),
+                thinking_tokens: row.get(19).unwrap_or(None),
+                cost_usd: row.get(20).unwrap_or(None),
             })
 
         }).map_err(|e| e.to_string())?;
@@ -386,6 +415,10 @@ pub fn get_logs_filtered(filter: &str, errors_only: bool, limit: usize, offset:
                 protocol: row.get(14).unwrap_or(None),
                 client_ip: row.get(15).unwrap_or(None),
                 username: row.get(16).unwrap_or(None),
+                cache_hit: row.get::<_, Option<bool>>(17).unwrap_or(None).unwrap_or(false),
+                cached_tokens: row.get(18).unwrap_or(None),
+                thinking_tokens: row.get(19).unwrap_or(None),
+                cost_usd: row.get(20).unwrap_or(None),
             })
 
         }).map_err(|e| e.to_string())?;
@@ -402,7 +435,8 @@ pub fn get_all_logs_for_export() -> Result<Vec<ProxyRequestLog>, String> {
     let mut stmt = conn.prepare(
         "SELECT id, timestamp, method, url, status, duration, model, error,
                 request_body, response_body, input_tokens, output_tokens,
-                account_email, mapped_model, protocol, client_ip, username
+                account_email, mapped_model, protocol, client_ip, username, cache_hit,
+                cached_tokens, thinking_tokens, cost_usd
          FROM request_logs
          ORDER BY timestamp DESC"
     ).map_err(|e| e.to_string())?;
@@ -426,6 +460,10 @@ pub fn get_all_logs_for_export() -> Result<Vec<ProxyRequestLog>, String> {
             protocol: row.get(14).unwrap_or(None),
             client_ip: row.get(15).unwrap_or(None),
             username: row.get(16).unwrap_or(None),
+            cache_hit: row.get::<_, Option<bool>>(17).unwrap_or(None).unwrap_or(false),
+            cached_tokens: row.get(18).unwrap_or(None),
+            thinking_tokens: row.get(19).unwrap_or(None),
+            cost_usd: row.get(20).unwrap_or(None),
         })
 
     }).map_err(|e| e.to_string())?;
@@ -504,3 +542,71 @@ pub fn get_token_usage_by_ip(limit: usize, hours: i64) -> Result<Vec<IpTokenStat
     Ok(stats)
 }
 
+
+/// 按账号分组汇总的请求日志统计 (账号可属于多个分组，会分别计入每个分组)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct GroupLogStats {
+    /// None = 未分组的账号
+    pub group: Option<String>,
+    pub request_count: i64,
+    pub error_count: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+}
+
+/// Get request log stats grouped by account group (分组按账号当前的 groups 归属计算)
+pub fn get_group_log_stats(hours: i64) -> Result<Vec<GroupLogStats>, String> {
+    let conn = connect_db()?;
+    let since = chrono::Utc::now().timestamp_millis() - (hours * 3600 * 1000);
+
+    let mut stmt = conn.prepare(
+        "SELECT
+            account_email,
+            COUNT(*) as cnt,
+            COALESCE(SUM(CASE WHEN status < 200 OR status >= 400 THEN 1 ELSE 0 END), 0) as errors,
+            COALESCE(SUM(input_tokens), 0) as input,
+            COALESCE(SUM(output_tokens), 0) as output
+         FROM request_logs
+         WHERE timestamp >= ?1 AND account_email IS NOT NULL AND account_email != ''
+         GROUP BY account_email"
+    ).map_err(|e| e.to_string())?;
+
+    let rows = stmt.query_map(params![since], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, i64>(3)?,
+            row.get::<_, i64>(4)?,
+        ))
+    }).map_err(|e| e.to_string())?;
+
+    let groups_by_email = crate::modules::account::account_groups_by_email()?;
+    let mut by_group: std::collections::BTreeMap<Option<String>, GroupLogStats> =
+        std::collections::BTreeMap::new();
+
+    for row in rows {
+        let (email, request_count, error_count, input_tokens, output_tokens) = row.map_err(|e| e.to_string())?;
+        let groups: Vec<Option<String>> = match groups_by_email.get(&email) {
+            Some(groups) if !groups.is_empty() => groups.iter().cloned().map(Some).collect(),
+            _ => vec![None],
+        };
+        for group in groups {
+            let entry = by_group.entry(group.clone()).or_insert_with(|| GroupLogStats {
+                group,
+                request_count: 0,
+                error_count: 0,
+                input_tokens: 0,
+                output_tokens: 0,
+            });
+            entry.request_count += request_count;
+            entry.error_count += error_count;
+            entry.input_tokens += input_tokens;
+            entry.output_tokens += output_tokens;
+        }
+    }
+
+    let mut stats: Vec<GroupLogStats> = by_group.into_values().collect();
+    stats.sort_by(|a, b| b.request_count.cmp(&a.request_count));
+    Ok(stats)
+}
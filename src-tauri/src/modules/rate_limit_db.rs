@@ -0,0 +1,296 @@
+//! 限流状态持久化
+//!
+//! 将 RateLimitTracker 的锁定记录与连续失败计数写入 SQLite，
+//! 重启后由 TokenManager::load_accounts 恢复，避免重新冲击已被锁定的账号。
+//!
+//! 数据库位于 TokenManager 的数据目录下 (而非全局数据目录)，
+//! 以便测试用的临时目录不会读写真实的限流状态。
+
+use rusqlite::{params, Connection};
+use std::path::{Path, PathBuf};
+
+/// 持久化的限流记录
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersistedRateLimit {
+    /// 限流 Key ("account_id" 或 "account_id:model")
+    pub key: String,
+    pub account_id: String,
+    pub model: Option<String>,
+    /// RateLimitReason 的字符串形式
+    pub reason: String,
+    /// 重置时间 (Unix 秒)
+    pub reset_at: i64,
+    pub retry_after_sec: i64,
+    pub detected_at: i64,
+}
+
+/// 持久化的连续失败计数
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersistedFailureCount {
+    pub account_id: String,
+    pub count: u32,
+    /// 最近一次失败时间 (Unix 秒)
+    pub updated_at: i64,
+}
+
+pub fn get_db_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("rate_limits.db")
+}
+
+fn connect_db(data_dir: &Path) -> Result<Connection, String> {
+    let conn = Connection::open(get_db_path(data_dir)).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS rate_limits (
+            key TEXT PRIMARY KEY,
+            account_id TEXT NOT NULL,
+            model TEXT,
+            reason TEXT NOT NULL,
+            reset_at INTEGER NOT NULL,
+            retry_after_sec INTEGER NOT NULL DEFAULT 0,
+            detected_at INTEGER NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| format!("Failed to create rate_limits table: {}", e))?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_rate_limits_account ON rate_limits (account_id)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS failure_counts (
+            account_id TEXT PRIMARY KEY,
+            count INTEGER NOT NULL,
+            updated_at INTEGER NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| format!("Failed to create failure_counts table: {}", e))?;
+
+    Ok(())
+}
+
+/// 初始化数据库
+pub fn init_db() -> Result<(), String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    create_tables(&connect_db(&data_dir)?)
+}
+
+fn upsert_limit(conn: &Connection, limit: &PersistedRateLimit) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO rate_limits (key, account_id, model, reason, reset_at, retry_after_sec, detected_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(key) DO UPDATE SET
+            account_id = ?2, model = ?3, reason = ?4, reset_at = ?5,
+            retry_after_sec = ?6, detected_at = ?7",
+        params![
+            limit.key,
+            limit.account_id,
+            limit.model,
+            limit.reason,
+            limit.reset_at,
+            limit.retry_after_sec,
+            limit.detected_at
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 加载未过期的限流记录，同时删除已过期的记录
+fn load_active_limits(conn: &Connection, now: i64) -> Result<Vec<PersistedRateLimit>, String> {
+    let expired = conn
+        .execute("DELETE FROM rate_limits WHERE reset_at <= ?1", params![now])
+        .map_err(|e| e.to_string())?;
+    if expired > 0 {
+        tracing::debug!("[RateLimitDB] Dropped {} expired rate limit record(s)", expired);
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT key, account_id, model, reason, reset_at, retry_after_sec, detected_at
+             FROM rate_limits ORDER BY reset_at",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(PersistedRateLimit {
+                key: row.get(0)?,
+                account_id: row.get(1)?,
+                model: row.get(2)?,
+                reason: row.get(3)?,
+                reset_at: row.get(4)?,
+                retry_after_sec: row.get(5)?,
+                detected_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// 加载未过期的失败计数 (超过 `expiry_secs` 未更新的记录视为过期并删除)
+fn load_active_failure_counts(
+    conn: &Connection,
+    now: i64,
+    expiry_secs: i64,
+) -> Result<Vec<PersistedFailureCount>, String> {
+    conn.execute(
+        "DELETE FROM failure_counts WHERE updated_at <= ?1",
+        params![now - expiry_secs],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare("SELECT account_id, count, updated_at FROM failure_counts")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(PersistedFailureCount {
+                account_id: row.get(0)?,
+                count: row.get(1)?,
+                updated_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// 保存 (或覆盖) 一条限流记录
+pub fn save_limit(data_dir: &Path, limit: &PersistedRateLimit) -> Result<(), String> {
+    upsert_limit(&connect_db(data_dir)?, limit)
+}
+
+/// 删除指定 Key 的限流记录
+pub fn delete_limit(data_dir: &Path, key: &str) -> Result<(), String> {
+    connect_db(data_dir)?
+        .execute("DELETE FROM rate_limits WHERE key = ?1", params![key])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 删除所有已过期的限流记录
+pub fn delete_expired_limits(data_dir: &Path, now: i64) -> Result<usize, String> {
+    connect_db(data_dir)?
+        .execute("DELETE FROM rate_limits WHERE reset_at <= ?1", params![now])
+        .map_err(|e| e.to_string())
+}
+
+/// 清空所有限流记录
+pub fn clear_limits(data_dir: &Path) -> Result<(), String> {
+    connect_db(data_dir)?
+        .execute("DELETE FROM rate_limits", [])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 保存账号的连续失败计数
+pub fn save_failure_count(
+    data_dir: &Path,
+    account_id: &str,
+    count: u32,
+    updated_at: i64,
+) -> Result<(), String> {
+    connect_db(data_dir)?
+        .execute(
+            "INSERT INTO failure_counts (account_id, count, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(account_id) DO UPDATE SET count = ?2, updated_at = ?3",
+            params![account_id, count, updated_at],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 删除账号的连续失败计数
+pub fn delete_failure_count(data_dir: &Path, account_id: &str) -> Result<(), String> {
+    connect_db(data_dir)?
+        .execute("DELETE FROM failure_counts WHERE account_id = ?1", params![account_id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 加载全部有效状态 (限流记录 + 失败计数)，并清理过期数据
+pub fn load_active_state(
+    data_dir: &Path,
+    failure_expiry_secs: i64,
+) -> Result<(Vec<PersistedRateLimit>, Vec<PersistedFailureCount>), String> {
+    let conn = connect_db(data_dir)?;
+    create_tables(&conn)?;
+    let now = chrono::Utc::now().timestamp();
+    Ok((
+        load_active_limits(&conn, now)?,
+        load_active_failure_counts(&conn, now, failure_expiry_secs)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(key: &str, reset_at: i64) -> PersistedRateLimit {
+        PersistedRateLimit {
+            key: key.to_string(),
+            account_id: key.split(':').next().unwrap().to_string(),
+            model: key.split_once(':').map(|(_, m)| m.to_string()),
+            reason: "quota_exhausted".to_string(),
+            reset_at,
+            retry_after_sec: 3600,
+            detected_at: reset_at - 3600,
+        }
+    }
+
+    #[test]
+    fn test_load_drops_expired_entries() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let now = 1_700_000_000;
+
+        upsert_limit(&conn, &limit("acc1", now + 600)).unwrap();
+        upsert_limit(&conn, &limit("acc1:gemini-3-pro-high", now + 7200)).unwrap();
+        upsert_limit(&conn, &limit("acc2", now - 10)).unwrap();
+
+        let active = load_active_limits(&conn, now).unwrap();
+        assert_eq!(active.len(), 2);
+        assert_eq!(active[1].model.as_deref(), Some("gemini-3-pro-high"));
+
+        // 过期记录已从表中删除
+        let remaining: i64 = conn
+            .query_row("SELECT COUNT(*) FROM rate_limits", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(remaining, 2);
+    }
+
+    #[test]
+    fn test_upsert_overwrites_and_failure_counts_expire() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let now = 1_700_000_000;
+
+        upsert_limit(&conn, &limit("acc1", now + 60)).unwrap();
+        upsert_limit(&conn, &limit("acc1", now + 300)).unwrap();
+        let active = load_active_limits(&conn, now).unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].reset_at, now + 300);
+
+        conn.execute(
+            "INSERT INTO failure_counts (account_id, count, updated_at) VALUES ('fresh', 3, ?1), ('stale', 2, ?2)",
+            params![now - 60, now - 7200],
+        )
+        .unwrap();
+        let counts = load_active_failure_counts(&conn, now, 3600).unwrap();
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts[0].account_id, "fresh");
+        assert_eq!(counts[0].count, 3);
+    }
+}
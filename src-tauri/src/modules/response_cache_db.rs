@@ -0,0 +1,292 @@
+//! 响应缓存存储
+//!
+//! 确定性请求 (temperature = 0) 的完整响应体按请求指纹写入 SQLite，
+//! 由 `proxy::middleware::response_cache` 读写。条目带 TTL，
+//! 并按条目数与总大小上限淘汰最久未命中的记录。
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// 缓存的一次完整响应
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedResponse {
+    /// 请求指纹 (sha256 hex)
+    pub key: String,
+    /// "openai" / "anthropic" / "gemini"
+    pub protocol: String,
+    /// 路由后的模型名
+    pub mapped_model: String,
+    pub status: u16,
+    pub content_type: String,
+    /// 原始响应体 (SSE 响应为完整事件流)
+    pub body: Vec<u8>,
+    /// 写入时间 (Unix 秒)
+    pub created_at: i64,
+}
+
+/// 缓存占用概览
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ResponseCacheStats {
+    pub entries: u64,
+    pub total_bytes: u64,
+    /// 现存条目的累计命中次数
+    pub total_hits: u64,
+    pub oldest_created_at: Option<i64>,
+}
+
+pub fn get_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("response_cache.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let conn = Connection::open(get_db_path()?).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "synchronous", "NORMAL")
+        .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS response_cache (
+            key TEXT PRIMARY KEY,
+            protocol TEXT NOT NULL,
+            mapped_model TEXT NOT NULL,
+            status INTEGER NOT NULL,
+            content_type TEXT NOT NULL,
+            body BLOB NOT NULL,
+            size INTEGER NOT NULL,
+            created_at INTEGER NOT NULL,
+            expires_at INTEGER NOT NULL,
+            last_hit_at INTEGER NOT NULL,
+            hits INTEGER NOT NULL DEFAULT 0
+        )",
+        [],
+    )
+    .map_err(|e| format!("Failed to create response_cache table: {}", e))?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_response_cache_expires ON response_cache (expires_at)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_response_cache_last_hit ON response_cache (last_hit_at DESC)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// 初始化数据库
+pub fn init_db() -> Result<(), String> {
+    create_tables(&connect_db()?)
+}
+
+fn lookup_at(conn: &Connection, key: &str, now: i64) -> Result<Option<CachedResponse>, String> {
+    let entry = conn
+        .query_row(
+            "SELECT key, protocol, mapped_model, status, content_type, body, created_at
+             FROM response_cache WHERE key = ?1 AND expires_at > ?2",
+            params![key, now],
+            |row| {
+                Ok(CachedResponse {
+                    key: row.get(0)?,
+                    protocol: row.get(1)?,
+                    mapped_model: row.get(2)?,
+                    status: row.get(3)?,
+                    content_type: row.get(4)?,
+                    body: row.get(5)?,
+                    created_at: row.get(6)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())?;
+
+    if entry.is_some() {
+        conn.execute(
+            "UPDATE response_cache SET hits = hits + 1, last_hit_at = ?2 WHERE key = ?1",
+            params![key, now],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(entry)
+}
+
+fn store_at(
+    conn: &Connection,
+    entry: &CachedResponse,
+    ttl_secs: i64,
+    max_entries: u64,
+    max_bytes: u64,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO response_cache
+            (key, protocol, mapped_model, status, content_type, body, size, created_at, expires_at, last_hit_at, hits)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?8, 0)
+         ON CONFLICT(key) DO UPDATE SET
+            protocol = ?2, mapped_model = ?3, status = ?4, content_type = ?5, body = ?6,
+            size = ?7, created_at = ?8, expires_at = ?9, last_hit_at = ?8",
+        params![
+            entry.key,
+            entry.protocol,
+            entry.mapped_model,
+            entry.status,
+            entry.content_type,
+            entry.body,
+            entry.body.len() as i64,
+            entry.created_at,
+            entry.created_at + ttl_secs,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    evict_at(conn, entry.created_at, max_entries, max_bytes)?;
+    Ok(())
+}
+
+/// 删除过期条目，再按最近命中时间保留不超过条目数与总大小上限的记录
+fn evict_at(conn: &Connection, now: i64, max_entries: u64, max_bytes: u64) -> Result<usize, String> {
+    let mut removed = conn
+        .execute("DELETE FROM response_cache WHERE expires_at <= ?1", params![now])
+        .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare("SELECT key, size FROM response_cache ORDER BY last_hit_at DESC, created_at DESC")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))
+        .map_err(|e| e.to_string())?;
+
+    let mut kept_entries = 0u64;
+    let mut kept_bytes = 0u64;
+    let mut evicted = Vec::new();
+    for row in rows {
+        let (key, size) = row.map_err(|e| e.to_string())?;
+        let size = size.max(0) as u64;
+        if kept_entries < max_entries && kept_bytes + size <= max_bytes {
+            kept_entries += 1;
+            kept_bytes += size;
+        } else {
+            evicted.push(key);
+        }
+    }
+    drop(stmt);
+
+    for key in &evicted {
+        removed += conn
+            .execute("DELETE FROM response_cache WHERE key = ?1", params![key])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(removed)
+}
+
+fn stats_at(conn: &Connection) -> Result<ResponseCacheStats, String> {
+    conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(size), 0), COALESCE(SUM(hits), 0), MIN(created_at)
+         FROM response_cache",
+        [],
+        |row| {
+            Ok(ResponseCacheStats {
+                entries: row.get(0)?,
+                total_bytes: row.get(1)?,
+                total_hits: row.get(2)?,
+                oldest_created_at: row.get(3)?,
+            })
+        },
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// 查找未过期的缓存响应，命中时累加命中次数
+pub fn lookup(key: &str) -> Result<Option<CachedResponse>, String> {
+    lookup_at(&connect_db()?, key, chrono::Utc::now().timestamp())
+}
+
+/// 写入 (或覆盖) 一条缓存响应，并执行淘汰
+pub fn store(entry: &CachedResponse, ttl_secs: i64, max_entries: u64, max_bytes: u64) -> Result<(), String> {
+    store_at(&connect_db()?, entry, ttl_secs, max_entries, max_bytes)
+}
+
+/// 缓存占用概览 (先清理过期条目)
+pub fn get_stats() -> Result<ResponseCacheStats, String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "DELETE FROM response_cache WHERE expires_at <= ?1",
+        params![chrono::Utc::now().timestamp()],
+    )
+    .map_err(|e| e.to_string())?;
+    stats_at(&conn)
+}
+
+/// 清空缓存，返回删除的条目数
+pub fn clear() -> Result<usize, String> {
+    connect_db()?
+        .execute("DELETE FROM response_cache", [])
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, size: usize, created_at: i64) -> CachedResponse {
+        CachedResponse {
+            key: key.to_string(),
+            protocol: "openai".to_string(),
+            mapped_model: "gemini-2.5-flash".to_string(),
+            status: 200,
+            content_type: "application/json".to_string(),
+            body: vec![b'x'; size],
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_lookup_respects_ttl_and_counts_hits() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let now = 1_700_000_000;
+
+        store_at(&conn, &entry("k1", 10, now), 60, 100, 1024).unwrap();
+        assert_eq!(lookup_at(&conn, "k1", now + 30).unwrap(), Some(entry("k1", 10, now)));
+        assert_eq!(lookup_at(&conn, "k1", now + 31).unwrap().map(|e| e.key), Some("k1".to_string()));
+        assert_eq!(stats_at(&conn).unwrap().total_hits, 2);
+
+        // 过期后不再命中
+        assert_eq!(lookup_at(&conn, "k1", now + 60).unwrap(), None);
+        assert_eq!(lookup_at(&conn, "missing", now).unwrap(), None);
+    }
+
+    #[test]
+    fn test_eviction_keeps_most_recently_hit_within_caps() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let now = 1_700_000_000;
+
+        store_at(&conn, &entry("a", 40, now), 3600, 2, 1024).unwrap();
+        store_at(&conn, &entry("b", 40, now + 1), 3600, 2, 1024).unwrap();
+        // a 被命中后比 b 更 "新"
+        lookup_at(&conn, "a", now + 2).unwrap();
+        store_at(&conn, &entry("c", 40, now + 3), 3600, 2, 1024).unwrap();
+
+        assert!(lookup_at(&conn, "a", now + 4).unwrap().is_some());
+        assert!(lookup_at(&conn, "b", now + 4).unwrap().is_none());
+        assert!(lookup_at(&conn, "c", now + 4).unwrap().is_some());
+
+        // 总大小上限: 100 字节只能保留两条 40 字节的记录
+        store_at(&conn, &entry("d", 40, now + 5), 3600, 10, 100).unwrap();
+        let stats = stats_at(&conn).unwrap();
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.total_bytes, 80);
+        assert!(lookup_at(&conn, "d", now + 6).unwrap().is_some());
+    }
+}
@@ -0,0 +1,477 @@
+//! 会话状态持久化
+//!
+//! 将 SignatureCache 的三层签名缓存 (tool / family / session) 与
+//! TokenManager 的会话-账号绑定写入 SQLite，重启后恢复，
+//! 避免进行中的 Claude Code 会话丢失签名与粘性账号。
+//!
+//! 数据库位于 TokenManager 的数据目录下 (而非全局数据目录)，
+//! 以便不同数据目录 (如测试用的临时目录) 之间的会话状态互不干扰。
+//!
+//! 写入发生在请求路径上 (SSE 映射器缓存签名、`get_token` 绑定会话)，因此不直接操作 SQLite，
+//! 而是提交到每个数据目录一个的后台写入线程，由其持有一条共享连接按提交顺序执行。
+
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex, OnceLock};
+
+/// 签名缓存层
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureLayer {
+    /// tool_use_id -> signature
+    Tool,
+    /// signature -> model family
+    Family,
+    /// session_id -> signature (+ message_count)
+    Session,
+}
+
+impl SignatureLayer {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureLayer::Tool => "tool",
+            SignatureLayer::Family => "family",
+            SignatureLayer::Session => "session",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "tool" => Some(SignatureLayer::Tool),
+            "family" => Some(SignatureLayer::Family),
+            "session" => Some(SignatureLayer::Session),
+            _ => None,
+        }
+    }
+}
+
+/// 持久化的签名缓存条目
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersistedSignature {
+    pub layer: SignatureLayer,
+    /// tool_use_id / signature / session_id (取决于 layer)
+    pub key: String,
+    /// signature / model family / signature (取决于 layer)
+    pub value: String,
+    /// 仅 Session 层使用
+    pub message_count: i64,
+    /// 写入时间 (Unix 秒)，TTL 从此刻计算
+    pub created_at: i64,
+}
+
+/// 持久化的会话-账号绑定
+#[derive(Debug, Clone, PartialEq)]
+pub struct PersistedSessionBinding {
+    pub session_id: String,
+    pub account_id: String,
+    /// 最近一次绑定/复用时间 (Unix 秒)
+    pub updated_at: i64,
+}
+
+pub fn get_db_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("session_state.db")
+}
+
+fn connect_db(data_dir: &Path) -> Result<Connection, String> {
+    let conn = Connection::open(get_db_path(data_dir)).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS signatures (
+            layer TEXT NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT NOT NULL,
+            message_count INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL,
+            PRIMARY KEY (layer, key)
+        )",
+        [],
+    )
+    .map_err(|e| format!("Failed to create signatures table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS session_accounts (
+            session_id TEXT PRIMARY KEY,
+            account_id TEXT NOT NULL,
+            updated_at INTEGER NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| format!("Failed to create session_accounts table: {}", e))?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_session_accounts_account ON session_accounts (account_id)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// 初始化数据库 (全局数据目录)
+pub fn init_db() -> Result<(), String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    create_tables(&connect_db(&data_dir)?)
+}
+
+fn upsert_signature(conn: &Connection, entry: &PersistedSignature) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO signatures (layer, key, value, message_count, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(layer, key) DO UPDATE SET
+            value = ?3, message_count = ?4, created_at = ?5",
+        params![
+            entry.layer.as_str(),
+            entry.key,
+            entry.value,
+            entry.message_count,
+            entry.created_at
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn delete_signature_at(conn: &Connection, layer: SignatureLayer, key: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM signatures WHERE layer = ?1 AND key = ?2",
+        params![layer.as_str(), key],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn upsert_session_binding(conn: &Connection, binding: &PersistedSessionBinding) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO session_accounts (session_id, account_id, updated_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(session_id) DO UPDATE SET account_id = ?2, updated_at = ?3",
+        params![binding.session_id, binding.account_id, binding.updated_at],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 加载未过期的签名 (写入时间早于 `now - ttl_secs` 的记录视为过期并删除)
+fn load_active_signatures(
+    conn: &Connection,
+    now: i64,
+    ttl_secs: i64,
+) -> Result<Vec<PersistedSignature>, String> {
+    let expired = conn
+        .execute(
+            "DELETE FROM signatures WHERE created_at <= ?1",
+            params![now - ttl_secs],
+        )
+        .map_err(|e| e.to_string())?;
+    if expired > 0 {
+        tracing::debug!("[SessionStateDB] Dropped {} expired signature(s)", expired);
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT layer, key, value, message_count, created_at
+             FROM signatures ORDER BY created_at",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, i64>(4)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        let (layer, key, value, message_count, created_at) = row.map_err(|e| e.to_string())?;
+        match SignatureLayer::parse(&layer) {
+            Some(layer) => entries.push(PersistedSignature {
+                layer,
+                key,
+                value,
+                message_count,
+                created_at,
+            }),
+            None => tracing::debug!("[SessionStateDB] Skipping unknown signature layer: {}", layer),
+        }
+    }
+    Ok(entries)
+}
+
+/// 加载未过期的会话绑定 (超过 `ttl_secs` 未复用的绑定视为过期并删除)
+fn load_active_session_bindings(
+    conn: &Connection,
+    now: i64,
+    ttl_secs: i64,
+) -> Result<Vec<PersistedSessionBinding>, String> {
+    conn.execute(
+        "DELETE FROM session_accounts WHERE updated_at <= ?1",
+        params![now - ttl_secs],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut stmt = conn
+        .prepare("SELECT session_id, account_id, updated_at FROM session_accounts")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(PersistedSessionBinding {
+                session_id: row.get(0)?,
+                account_id: row.get(1)?,
+                updated_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// 待写入 session_state.db 的变更
+#[derive(Debug, Clone, PartialEq)]
+pub enum PersistOp {
+    SaveSignature(PersistedSignature),
+    DeleteSignature(SignatureLayer, String),
+    /// 删除写入时间早于 `now - ttl_secs` 的签名
+    DeleteExpiredSignatures { now: i64, ttl_secs: i64 },
+    ClearSignatures,
+    SaveSessionBinding(PersistedSessionBinding),
+    DeleteSessionBinding(String),
+    /// 删除绑定到该账号的所有会话
+    DeleteAccountSessionBindings(String),
+    ClearSessionBindings,
+}
+
+impl PersistOp {
+    fn apply(self, conn: &Connection) -> Result<(), String> {
+        match self {
+            PersistOp::SaveSignature(entry) => upsert_signature(conn, &entry),
+            PersistOp::DeleteSignature(layer, key) => delete_signature_at(conn, layer, &key),
+            PersistOp::DeleteExpiredSignatures { now, ttl_secs } => conn
+                .execute(
+                    "DELETE FROM signatures WHERE created_at <= ?1",
+                    params![now - ttl_secs],
+                )
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            PersistOp::ClearSignatures => conn
+                .execute("DELETE FROM signatures", [])
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            PersistOp::SaveSessionBinding(binding) => upsert_session_binding(conn, &binding),
+            PersistOp::DeleteSessionBinding(session_id) => conn
+                .execute(
+                    "DELETE FROM session_accounts WHERE session_id = ?1",
+                    params![session_id],
+                )
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            PersistOp::DeleteAccountSessionBindings(account_id) => conn
+                .execute(
+                    "DELETE FROM session_accounts WHERE account_id = ?1",
+                    params![account_id],
+                )
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            PersistOp::ClearSessionBindings => conn
+                .execute("DELETE FROM session_accounts", [])
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+enum WriterMessage {
+    Op(PersistOp),
+    /// 前面的变更全部执行后回复，用于加载前保证读到已提交的写入
+    Flush(mpsc::Sender<()>),
+}
+
+/// session_state.db 的后台写入队列 (同一数据目录共享一个写入线程与一条连接)
+#[derive(Clone)]
+pub struct SessionStateWriter {
+    data_dir: PathBuf,
+    tx: mpsc::Sender<WriterMessage>,
+}
+
+impl SessionStateWriter {
+    /// 获取 `data_dir` 对应的写入队列，首次调用时启动写入线程
+    pub fn for_dir(data_dir: &Path) -> Self {
+        static WRITERS: OnceLock<Mutex<HashMap<PathBuf, SessionStateWriter>>> = OnceLock::new();
+        let mut writers = WRITERS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        writers
+            .entry(data_dir.to_path_buf())
+            .or_insert_with(|| Self::spawn(data_dir.to_path_buf()))
+            .clone()
+    }
+
+    fn spawn(data_dir: PathBuf) -> Self {
+        let (tx, rx) = mpsc::channel::<WriterMessage>();
+        let worker_dir = data_dir.clone();
+        // 独立线程而非 spawn_blocking：写入线程与进程同生命周期，不应阻止 Tokio 运行时关闭
+        let spawned = std::thread::Builder::new()
+            .name("session-state-db".to_string())
+            .spawn(move || run_writer(&worker_dir, rx));
+        if let Err(e) = spawned {
+            tracing::error!("[SessionStateDB] Failed to start writer thread: {}", e);
+        }
+        Self { data_dir, tx }
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// 提交变更 (不阻塞)
+    pub fn send(&self, op: PersistOp) {
+        if self.tx.send(WriterMessage::Op(op)).is_err() {
+            tracing::warn!("[SessionStateDB] Writer thread is gone, dropping update");
+        }
+    }
+
+    /// 等待已提交的变更全部落盘
+    pub fn flush(&self) {
+        let (done_tx, done_rx) = mpsc::channel();
+        if self.tx.send(WriterMessage::Flush(done_tx)).is_ok() {
+            let _ = done_rx.recv();
+        }
+    }
+}
+
+fn run_writer(data_dir: &Path, rx: mpsc::Receiver<WriterMessage>) {
+    let mut conn: Option<Connection> = None;
+    while let Ok(message) = rx.recv() {
+        let op = match message {
+            WriterMessage::Op(op) => op,
+            WriterMessage::Flush(done) => {
+                let _ = done.send(());
+                continue;
+            }
+        };
+        if conn.is_none() {
+            match connect_db(data_dir).and_then(|c| create_tables(&c).map(|_| c)) {
+                Ok(c) => conn = Some(c),
+                Err(e) => {
+                    tracing::warn!("[SessionStateDB] Failed to open {}: {}", get_db_path(data_dir).display(), e);
+                    continue;
+                }
+            }
+        }
+        if let Some(c) = conn.as_ref() {
+            if let Err(e) = op.apply(c) {
+                tracing::warn!("[SessionStateDB] Failed to persist session state: {}", e);
+            }
+        }
+    }
+}
+
+/// 加载全部有效签名，并清理过期数据
+pub fn load_signatures(data_dir: &Path, ttl_secs: i64) -> Result<Vec<PersistedSignature>, String> {
+    let conn = connect_db(data_dir)?;
+    create_tables(&conn)?;
+    load_active_signatures(&conn, chrono::Utc::now().timestamp(), ttl_secs)
+}
+
+/// 加载全部有效会话绑定，并清理过期数据
+pub fn load_session_bindings(data_dir: &Path, ttl_secs: i64) -> Result<Vec<PersistedSessionBinding>, String> {
+    let conn = connect_db(data_dir)?;
+    create_tables(&conn)?;
+    load_active_session_bindings(&conn, chrono::Utc::now().timestamp(), ttl_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signature(layer: SignatureLayer, key: &str, created_at: i64) -> PersistedSignature {
+        PersistedSignature {
+            layer,
+            key: key.to_string(),
+            value: format!("value-{}", key),
+            message_count: 3,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_signatures_expire_by_ttl_and_layers_are_separate() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let now = 1_700_000_000;
+
+        upsert_signature(&conn, &signature(SignatureLayer::Tool, "toolu_1", now - 60)).unwrap();
+        // 同一个 key 在不同层互不覆盖
+        upsert_signature(&conn, &signature(SignatureLayer::Session, "toolu_1", now - 30)).unwrap();
+        upsert_signature(&conn, &signature(SignatureLayer::Family, "old", now - 7300)).unwrap();
+
+        let active = load_active_signatures(&conn, now, 7200).unwrap();
+        assert_eq!(active.len(), 2);
+        assert_eq!(active[0].layer, SignatureLayer::Tool);
+        assert_eq!(active[1].layer, SignatureLayer::Session);
+        assert_eq!(active[1].message_count, 3);
+
+        let remaining: i64 = conn
+            .query_row("SELECT COUNT(*) FROM signatures", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(remaining, 2);
+    }
+
+    #[test]
+    fn test_session_bindings_upsert_and_expire() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let now = 1_700_000_000;
+
+        let binding = |sid: &str, acc: &str, updated_at: i64| PersistedSessionBinding {
+            session_id: sid.to_string(),
+            account_id: acc.to_string(),
+            updated_at,
+        };
+
+        upsert_session_binding(&conn, &binding("sid-1", "acc1", now - 100)).unwrap();
+        upsert_session_binding(&conn, &binding("sid-1", "acc2", now - 10)).unwrap();
+        upsert_session_binding(&conn, &binding("sid-stale", "acc1", now - 9000)).unwrap();
+
+        let active = load_active_session_bindings(&conn, now, 7200).unwrap();
+        assert_eq!(active, vec![binding("sid-1", "acc2", now - 10)]);
+    }
+
+    #[test]
+    fn test_writer_applies_ops_in_order_and_flushes() {
+        let dir = std::env::temp_dir().join(format!("session_state_writer_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let now = chrono::Utc::now().timestamp();
+
+        let writer = SessionStateWriter::for_dir(&dir);
+        writer.send(PersistOp::SaveSignature(signature(SignatureLayer::Tool, "toolu_1", now)));
+        writer.send(PersistOp::SaveSignature(signature(SignatureLayer::Tool, "toolu_2", now)));
+        writer.send(PersistOp::DeleteSignature(SignatureLayer::Tool, "toolu_1".to_string()));
+        writer.send(PersistOp::SaveSessionBinding(PersistedSessionBinding {
+            session_id: "sid-1".to_string(),
+            account_id: "acc1".to_string(),
+            updated_at: now,
+        }));
+        writer.send(PersistOp::DeleteAccountSessionBindings("acc1".to_string()));
+        // 同一目录复用同一队列
+        SessionStateWriter::for_dir(&dir).flush();
+
+        let signatures = load_signatures(&dir, 7200).unwrap();
+        assert_eq!(signatures.len(), 1);
+        assert_eq!(signatures[0].key, "toolu_2");
+        assert!(load_session_bindings(&dir, 7200).unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
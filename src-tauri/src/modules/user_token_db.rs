@@ -10,6 +10,9 @@ use std::path::PathBuf;
 use uuid::Uuid;
 use chrono::{Utc, Local, Timelike, FixedOffset};
 
+use crate::modules::cost::BudgetPeriod;
+use crate::proxy::config::{BudgetAction, BudgetConfig};
+
 /// 用户令牌结构体
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserToken {
@@ -28,6 +31,131 @@ pub struct UserToken {
     pub last_used_at: Option<i64>,
     pub total_requests: i64,
     pub total_tokens_used: i64,
+    #[serde(default)]
+    pub rpm_limit: i32,               // 每分钟请求数上限, 0 = unlimited
+    #[serde(default)]
+    pub daily_token_limit: i64,       // 每日 Token 上限 (UTC 自然日), 0 = unlimited
+    #[serde(default)]
+    pub monthly_token_limit: i64,     // 每月 Token 上限 (UTC 自然月), 0 = unlimited
+    #[serde(default)]
+    pub allowed_models: Vec<String>,  // 允许的模型列表 (支持 * 通配符), 空 = 不限制
+    #[serde(default)]
+    pub allowed_groups: Vec<String>,  // 可使用的账号分组, 空 = 整个账号池
+    #[serde(default)]
+    pub daily_budget_usd: f64,        // 每日费用预算 (美元, UTC 自然日), 0 = unlimited
+    #[serde(default)]
+    pub monthly_budget_usd: f64,      // 每月费用预算 (美元, UTC 自然月), 0 = unlimited
+    #[serde(default)]
+    pub budget_action: BudgetAction,  // 超出预算后 warn (仅告警) / block (拒绝请求)
+}
+
+/// 令牌配额限制 (创建令牌时使用)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenLimits {
+    #[serde(default)]
+    pub rpm_limit: i32,
+    #[serde(default)]
+    pub daily_token_limit: i64,
+    #[serde(default)]
+    pub monthly_token_limit: i64,
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    #[serde(default)]
+    pub allowed_groups: Vec<String>,
+    #[serde(default)]
+    pub daily_budget_usd: f64,
+    #[serde(default)]
+    pub monthly_budget_usd: f64,
+    #[serde(default)]
+    pub budget_action: BudgetAction,
+}
+
+/// 令牌配额使用情况 (当前周期计数器)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenQuotaUsage {
+    pub token_id: String,
+    pub requests_this_minute: i64,
+    pub tokens_today: i64,
+    pub tokens_this_month: i64,
+    pub rpm_limit: i32,
+    pub daily_token_limit: i64,
+    pub monthly_token_limit: i64,
+    #[serde(default)]
+    pub cost_today: f64,
+    #[serde(default)]
+    pub cost_this_month: f64,
+    #[serde(default)]
+    pub daily_budget_usd: f64,
+    #[serde(default)]
+    pub monthly_budget_usd: f64,
+}
+
+/// 配额校验失败原因
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuotaViolation {
+    /// 超出每分钟请求数
+    RateLimited { limit: i32, retry_after_secs: i64 },
+    /// 超出每日 Token 上限
+    DailyTokensExceeded { used: i64, limit: i64, retry_after_secs: i64 },
+    /// 超出每月 Token 上限
+    MonthlyTokensExceeded { used: i64, limit: i64, retry_after_secs: i64 },
+    /// 模型不在允许列表中
+    ModelNotAllowed { model: String },
+    /// 超出费用预算 (owner 为 None 表示全局预算)
+    BudgetExceeded {
+        owner: Option<String>,
+        period: BudgetPeriod,
+        used_usd: f64,
+        limit_usd: f64,
+        retry_after_secs: i64,
+    },
+}
+
+impl QuotaViolation {
+    pub fn message(&self) -> String {
+        match self {
+            QuotaViolation::RateLimited { limit, .. } => format!(
+                "Rate limit exceeded: this token allows {} requests per minute.",
+                limit
+            ),
+            QuotaViolation::DailyTokensExceeded { used, limit, .. } => format!(
+                "Daily token quota exceeded ({}/{}). The quota resets at 00:00 UTC.",
+                used, limit
+            ),
+            QuotaViolation::MonthlyTokensExceeded { used, limit, .. } => format!(
+                "Monthly token quota exceeded ({}/{}). The quota resets on the 1st of next month (UTC).",
+                used, limit
+            ),
+            QuotaViolation::ModelNotAllowed { model } => format!(
+                "Model '{}' is not allowed for this token. Please contact the administrator.",
+                model
+            ),
+            QuotaViolation::BudgetExceeded { owner, period, used_usd, limit_usd, .. } => {
+                let (label, reset) = match period {
+                    BudgetPeriod::Day => ("daily", "at 00:00 UTC"),
+                    BudgetPeriod::Month => ("monthly", "on the 1st of next month (UTC)"),
+                };
+                let scope = if owner.is_some() { "This token's" } else { "The global" };
+                format!(
+                    "{} {} budget is exceeded (${:.2}/${:.2}). The budget resets {}.",
+                    scope, label, used_usd, limit_usd, reset
+                )
+            }
+        }
+    }
+
+    /// 距离配额恢复的秒数 (用于 Retry-After)
+    pub fn retry_after_secs(&self) -> Option<i64> {
+        match self {
+            QuotaViolation::RateLimited { retry_after_secs, .. }
+            | QuotaViolation::DailyTokensExceeded { retry_after_secs, .. }
+            | QuotaViolation::MonthlyTokensExceeded { retry_after_secs, .. }
+            | QuotaViolation::BudgetExceeded { retry_after_secs, .. } => {
+                Some(*retry_after_secs)
+            }
+            QuotaViolation::ModelNotAllowed { .. } => None,
+        }
+    }
 }
 
 /// 令牌 IP 绑定结构体
@@ -105,6 +233,14 @@ pub fn init_db() -> Result<(), String> {
     let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN last_used_at INTEGER", []);
     let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN curfew_start TEXT", []);
     let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN curfew_end TEXT", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN rpm_limit INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN daily_token_limit INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN monthly_token_limit INTEGER DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN allowed_models TEXT", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN allowed_groups TEXT", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN daily_budget_usd REAL DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN monthly_budget_usd REAL DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN budget_action TEXT DEFAULT 'warn'", []);
 
     // 创建 token_ip_bindings 表
     conn.execute(
@@ -138,6 +274,25 @@ pub fn init_db() -> Result<(), String> {
         [],
     ).map_err(|e| format!("Failed to create token_usage_logs table: {}", e))?;
     
+    // 创建 token_quota_usage 表 (配额计数器，按周期分桶)
+    // period: "minute" | "day" | "month"，period_key 为 UTC 周期标识
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS token_quota_usage (
+            token_id TEXT NOT NULL,
+            period TEXT NOT NULL,
+            period_key TEXT NOT NULL,
+            requests INTEGER NOT NULL DEFAULT 0,
+            tokens INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY(token_id, period, period_key),
+            FOREIGN KEY(token_id) REFERENCES user_tokens(id) ON DELETE CASCADE
+        )",
+        [],
+    ).map_err(|e| format!("Failed to create token_quota_usage table: {}", e))?;
+
+    // [NEW] 费用核算：按周期累计的费用与每条使用日志的费用 (美元)
+    let _ = conn.execute("ALTER TABLE token_quota_usage ADD COLUMN cost REAL NOT NULL DEFAULT 0", []);
+    let _ = conn.execute("ALTER TABLE token_usage_logs ADD COLUMN cost REAL", []);
+
     // 创建索引
     let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_token_usage_logs_token_id ON token_usage_logs(token_id)", []);
     let _ = conn.execute("CREATE INDEX IF NOT EXISTS idx_token_usage_logs_request_time ON token_usage_logs(request_time)", []);
@@ -149,10 +304,52 @@ pub fn init_db() -> Result<(), String> {
     let _ = conn.execute("UPDATE user_tokens SET total_requests = 0 WHERE total_requests IS NULL", []);
     let _ = conn.execute("UPDATE user_tokens SET total_tokens_used = 0 WHERE total_tokens_used IS NULL", []);
     let _ = conn.execute("UPDATE user_tokens SET enabled = 1 WHERE enabled IS NULL", []);
+    let _ = conn.execute("UPDATE user_tokens SET rpm_limit = 0 WHERE rpm_limit IS NULL", []);
+    let _ = conn.execute("UPDATE user_tokens SET daily_token_limit = 0 WHERE daily_token_limit IS NULL", []);
+    let _ = conn.execute("UPDATE user_tokens SET monthly_token_limit = 0 WHERE monthly_token_limit IS NULL", []);
 
     Ok(())
 }
 
+/// 将数据库行映射为 UserToken (新增列使用防御性默认值，兼容旧数据库)
+fn row_to_user_token(row: &rusqlite::Row) -> rusqlite::Result<UserToken> {
+    let allowed_models: Option<String> = row.get("allowed_models").unwrap_or(None);
+    let allowed_groups: Option<String> = row.get("allowed_groups").unwrap_or(None);
+    Ok(UserToken {
+        id: row.get("id")?,
+        token: row.get("token")?,
+        username: row.get("username")?,
+        description: row.get("description")?,
+        enabled: row.get("enabled").unwrap_or(true), // 防御性默认值
+        expires_type: row.get("expires_type").unwrap_or("never".to_string()), // 防御性默认值
+        expires_at: row.get("expires_at").unwrap_or(None),
+        max_ips: row.get("max_ips").unwrap_or(0),
+        curfew_start: row.get("curfew_start").unwrap_or(None),
+        curfew_end: row.get("curfew_end").unwrap_or(None),
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+        last_used_at: row.get("last_used_at").unwrap_or(None),
+        total_requests: row.get("total_requests").unwrap_or(0),
+        total_tokens_used: row.get("total_tokens_used").unwrap_or(0),
+        rpm_limit: row.get("rpm_limit").unwrap_or(0),
+        daily_token_limit: row.get("daily_token_limit").unwrap_or(0),
+        monthly_token_limit: row.get("monthly_token_limit").unwrap_or(0),
+        allowed_models: allowed_models
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        allowed_groups: allowed_groups
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        daily_budget_usd: row.get::<_, Option<f64>>("daily_budget_usd").unwrap_or(None).unwrap_or(0.0),
+        monthly_budget_usd: row.get::<_, Option<f64>>("monthly_budget_usd").unwrap_or(None).unwrap_or(0.0),
+        budget_action: row
+            .get::<_, Option<String>>("budget_action")
+            .unwrap_or(None)
+            .map(|s| BudgetAction::parse(&s))
+            .unwrap_or_default(),
+    })
+}
+
 /// 创建新令牌
 pub fn create_token(
     username: String,
@@ -161,7 +358,8 @@ pub fn create_token(
     max_ips: i32,
     curfew_start: Option<String>,
     curfew_end: Option<String>,
-    custom_expires_at: Option<i64>  // 自定义过期时间戳 (秒)
+    custom_expires_at: Option<i64>, // 自定义过期时间戳 (秒)
+    limits: TokenLimits,
 ) -> Result<UserToken, String> {
     let conn = connect_db()?;
     let id = Uuid::new_v4().to_string();
@@ -192,14 +390,29 @@ pub fn create_token(
         last_used_at: None,
         total_requests: 0,
         total_tokens_used: 0,
+        rpm_limit: limits.rpm_limit.max(0),
+        daily_token_limit: limits.daily_token_limit.max(0),
+        monthly_token_limit: limits.monthly_token_limit.max(0),
+        allowed_models: normalize_list(limits.allowed_models),
+        allowed_groups: normalize_list(limits.allowed_groups),
+        daily_budget_usd: limits.daily_budget_usd.max(0.0),
+        monthly_budget_usd: limits.monthly_budget_usd.max(0.0),
+        budget_action: limits.budget_action,
     };
 
+    let allowed_models_json = serde_json::to_string(&user_token.allowed_models)
+        .map_err(|e| format!("Failed to serialize allowed_models: {}", e))?;
+    let allowed_groups_json = serde_json::to_string(&user_token.allowed_groups)
+        .map_err(|e| format!("Failed to serialize allowed_groups: {}", e))?;
+
     conn.execute(
         "INSERT INTO user_tokens (
             id, token, username, description, enabled, expires_type, expires_at, max_ips,
             curfew_start, curfew_end,
-            created_at, updated_at, total_requests, total_tokens_used
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            created_at, updated_at, total_requests, total_tokens_used,
+            rpm_limit, daily_token_limit, monthly_token_limit, allowed_models, allowed_groups,
+            daily_budget_usd, monthly_budget_usd, budget_action
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)",
         params![
             user_token.id,
             user_token.token,
@@ -215,6 +428,14 @@ pub fn create_token(
             user_token.updated_at,
             user_token.total_requests,
             user_token.total_tokens_used,
+            user_token.rpm_limit,
+            user_token.daily_token_limit,
+            user_token.monthly_token_limit,
+            allowed_models_json,
+            allowed_groups_json,
+            user_token.daily_budget_usd,
+            user_token.monthly_budget_usd,
+            user_token.budget_action.as_str(),
         ],
     ).map_err(|e| format!("Failed to insert user token: {}", e))?;
 
@@ -227,25 +448,7 @@ pub fn list_tokens() -> Result<Vec<UserToken>, String> {
     let mut stmt = conn.prepare("SELECT * FROM user_tokens ORDER BY created_at DESC")
         .map_err(|e| format!("Failed to prepare query: {}", e))?;
     
-    let token_iter = stmt.query_map([], |row| {
-        Ok(UserToken {
-            id: row.get("id")?,
-            token: row.get("token")?,
-            username: row.get("username")?,
-            description: row.get("description")?,
-            enabled: row.get("enabled").unwrap_or(true), // 防御性默认值
-            expires_type: row.get("expires_type").unwrap_or("never".to_string()), // 防御性默认值
-            expires_at: row.get("expires_at").unwrap_or(None),
-            max_ips: row.get("max_ips").unwrap_or(0),
-            curfew_start: row.get("curfew_start").unwrap_or(None),
-            curfew_end: row.get("curfew_end").unwrap_or(None),
-            created_at: row.get("created_at")?,
-            updated_at: row.get("updated_at")?,
-            last_used_at: row.get("last_used_at").unwrap_or(None),
-            total_requests: row.get("total_requests").unwrap_or(0),
-            total_tokens_used: row.get("total_tokens_used").unwrap_or(0),
-        })
-    }).map_err(|e| format!("Failed to query tokens: {}", e))?;
+    let token_iter = stmt.query_map([], row_to_user_token).map_err(|e| format!("Failed to query tokens: {}", e))?;
 
     let mut tokens = Vec::new();
     for token in token_iter {
@@ -261,25 +464,7 @@ pub fn get_token_by_id(id: &str) -> Result<Option<UserToken>, String> {
     let mut stmt = conn.prepare("SELECT * FROM user_tokens WHERE id = ?1")
         .map_err(|e| format!("Failed to prepare query: {}", e))?;
     
-    let token = stmt.query_row(params![id], |row| {
-        Ok(UserToken {
-            id: row.get("id")?,
-            token: row.get("token")?,
-            username: row.get("username")?,
-            description: row.get("description")?,
-            enabled: row.get("enabled")?,
-            expires_type: row.get("expires_type")?,
-            expires_at: row.get("expires_at")?,
-            max_ips: row.get("max_ips")?,
-            curfew_start: row.get("curfew_start").unwrap_or(None),
-            curfew_end: row.get("curfew_end").unwrap_or(None),
-            created_at: row.get("created_at")?,
-            updated_at: row.get("updated_at")?,
-            last_used_at: row.get("last_used_at")?,
-            total_requests: row.get("total_requests")?,
-            total_tokens_used: row.get("total_tokens_used")?,
-        })
-    }).optional().map_err(|e| format!("Failed to query token: {}", e))?;
+    let token = stmt.query_row(params![id], row_to_user_token).optional().map_err(|e| format!("Failed to query token: {}", e))?;
     
     Ok(token)
 }
@@ -290,25 +475,7 @@ pub fn get_token_by_value(token: &str) -> Result<Option<UserToken>, String> {
     let mut stmt = conn.prepare("SELECT * FROM user_tokens WHERE token = ?1")
         .map_err(|e| format!("Failed to prepare query: {}", e))?;
     
-    let token = stmt.query_row(params![token], |row| {
-        Ok(UserToken {
-            id: row.get("id")?,
-            token: row.get("token")?,
-            username: row.get("username")?,
-            description: row.get("description")?,
-            enabled: row.get("enabled")?,
-            expires_type: row.get("expires_type")?,
-            expires_at: row.get("expires_at")?,
-            max_ips: row.get("max_ips")?,
-            curfew_start: row.get("curfew_start").unwrap_or(None),
-            curfew_end: row.get("curfew_end").unwrap_or(None),
-            created_at: row.get("created_at")?,
-            updated_at: row.get("updated_at")?,
-            last_used_at: row.get("last_used_at")?,
-            total_requests: row.get("total_requests")?,
-            total_tokens_used: row.get("total_tokens_used")?,
-        })
-    }).optional().map_err(|e| format!("Failed to query token: {}", e))?;
+    let token = stmt.query_row(params![token], row_to_user_token).optional().map_err(|e| format!("Failed to query token: {}", e))?;
     
     Ok(token)
 }
@@ -378,6 +545,122 @@ pub fn update_token(
     Ok(())
 }
 
+/// 更新令牌配额限制 (仅更新传入的字段)
+pub fn update_token_limits(
+    id: &str,
+    rpm_limit: Option<i32>,
+    daily_token_limit: Option<i64>,
+    monthly_token_limit: Option<i64>,
+    allowed_models: Option<Vec<String>>,
+    allowed_groups: Option<Vec<String>>,
+) -> Result<(), String> {
+    let conn = connect_db()?;
+    let now = Utc::now().timestamp();
+
+    let mut query = "UPDATE user_tokens SET updated_at = ?1".to_string();
+    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(now)];
+    let mut param_idx = 2;
+
+    if let Some(rpm) = rpm_limit {
+        query.push_str(&format!(", rpm_limit = ?{}", param_idx));
+        params_vec.push(Box::new(rpm.max(0)));
+        param_idx += 1;
+    }
+
+    if let Some(daily) = daily_token_limit {
+        query.push_str(&format!(", daily_token_limit = ?{}", param_idx));
+        params_vec.push(Box::new(daily.max(0)));
+        param_idx += 1;
+    }
+
+    if let Some(monthly) = monthly_token_limit {
+        query.push_str(&format!(", monthly_token_limit = ?{}", param_idx));
+        params_vec.push(Box::new(monthly.max(0)));
+        param_idx += 1;
+    }
+
+    if let Some(models) = allowed_models {
+        let json = serde_json::to_string(&normalize_list(models))
+            .map_err(|e| format!("Failed to serialize allowed_models: {}", e))?;
+        query.push_str(&format!(", allowed_models = ?{}", param_idx));
+        params_vec.push(Box::new(json));
+        param_idx += 1;
+    }
+
+    if let Some(groups) = allowed_groups {
+        let json = serde_json::to_string(&normalize_list(groups))
+            .map_err(|e| format!("Failed to serialize allowed_groups: {}", e))?;
+        query.push_str(&format!(", allowed_groups = ?{}", param_idx));
+        params_vec.push(Box::new(json));
+        param_idx += 1;
+    }
+
+    query.push_str(&format!(" WHERE id = ?{}", param_idx));
+    params_vec.push(Box::new(id.to_string()));
+
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+
+    conn.execute(&query, params_refs.as_slice())
+        .map_err(|e| format!("Failed to update user token limits: {}", e))?;
+
+    Ok(())
+}
+
+/// 更新令牌费用预算 (仅更新传入的字段)
+pub fn update_token_budget(
+    id: &str,
+    daily_budget_usd: Option<f64>,
+    monthly_budget_usd: Option<f64>,
+    budget_action: Option<BudgetAction>,
+) -> Result<(), String> {
+    let conn = connect_db()?;
+    let now = Utc::now().timestamp();
+
+    let mut query = "UPDATE user_tokens SET updated_at = ?1".to_string();
+    let mut params_vec: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(now)];
+    let mut param_idx = 2;
+
+    if let Some(daily) = daily_budget_usd {
+        query.push_str(&format!(", daily_budget_usd = ?{}", param_idx));
+        params_vec.push(Box::new(daily.max(0.0)));
+        param_idx += 1;
+    }
+
+    if let Some(monthly) = monthly_budget_usd {
+        query.push_str(&format!(", monthly_budget_usd = ?{}", param_idx));
+        params_vec.push(Box::new(monthly.max(0.0)));
+        param_idx += 1;
+    }
+
+    if let Some(action) = budget_action {
+        query.push_str(&format!(", budget_action = ?{}", param_idx));
+        params_vec.push(Box::new(action.as_str()));
+        param_idx += 1;
+    }
+
+    query.push_str(&format!(" WHERE id = ?{}", param_idx));
+    params_vec.push(Box::new(id.to_string()));
+
+    let params_refs: Vec<&dyn rusqlite::ToSql> = params_vec.iter().map(|p| p.as_ref()).collect();
+
+    conn.execute(&query, params_refs.as_slice())
+        .map_err(|e| format!("Failed to update user token budget: {}", e))?;
+
+    Ok(())
+}
+
+/// 清理模型 / 分组允许列表 (去除空白项与重复项)
+fn normalize_list(items: Vec<String>) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    for m in items {
+        let m = m.trim().to_string();
+        if !m.is_empty() && !result.contains(&m) {
+            result.push(m);
+        }
+    }
+    result
+}
+
 /// 续期令牌
 pub fn renew_token(id: &str, expires_type: &str) -> Result<(), String> {
     let conn = connect_db()?;
@@ -403,6 +686,7 @@ pub fn delete_token(id: &str) -> Result<(), String> {
     let conn = connect_db()?;
     conn.execute("DELETE FROM user_tokens WHERE id = ?1", params![id])
         .map_err(|e| format!("Failed to delete token: {}", e))?;
+    let _ = conn.execute("DELETE FROM token_quota_usage WHERE token_id = ?1", params![id]);
     Ok(())
 }
 
@@ -439,6 +723,7 @@ pub fn record_token_usage_and_ip(
     model: &str,
     input_tokens: i32, 
     output_tokens: i32,
+    cost_usd: Option<f64>,
     status: u16,
     user_agent: Option<String>
 ) -> Result<(), String> {
@@ -456,6 +741,22 @@ pub fn record_token_usage_and_ip(
         params![now, input_tokens + output_tokens, token_id],
     ).map_err(|e| format!("Failed to update user_tokens stats: {}", e))?;
 
+    // 1.1 累加当日/当月配额计数器
+    let (_, day_key, month_key) = quota_period_keys(Utc::now());
+    let total_tokens = (input_tokens.max(0) + output_tokens.max(0)) as i64;
+    let cost = cost_usd.unwrap_or(0.0).max(0.0);
+    for (period, key) in [("day", &day_key), ("month", &month_key)] {
+        tx.execute(
+            "INSERT INTO token_quota_usage (token_id, period, period_key, requests, tokens, cost)
+             VALUES (?1, ?2, ?3, 1, ?4, ?5)
+             ON CONFLICT(token_id, period, period_key) DO UPDATE SET
+                requests = requests + 1,
+                tokens = tokens + excluded.tokens,
+                cost = cost + excluded.cost",
+            params![token_id, period, key, total_tokens, cost],
+        ).map_err(|e| format!("Failed to update quota usage: {}", e))?;
+    }
+
     // 2. 更新或插入 token_ip_bindings 表
     let binding_exists: bool = tx.query_row(
         "SELECT EXISTS(SELECT 1 FROM token_ip_bindings WHERE token_id = ?1 AND ip_address = ?2)",
@@ -486,10 +787,10 @@ pub fn record_token_usage_and_ip(
     let log_id = Uuid::new_v4().to_string();
     tx.execute(
         "INSERT INTO token_usage_logs (
-            id, token_id, ip_address, model, input_tokens, output_tokens, request_time, status
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            id, token_id, ip_address, model, input_tokens, output_tokens, request_time, status, cost
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
         params![
-            log_id, token_id, ip, model, input_tokens, output_tokens, now, status
+            log_id, token_id, ip, model, input_tokens, output_tokens, now, status, cost_usd
         ],
     ).map_err(|e| format!("Failed to insert usage log: {}", e))?;
 
@@ -498,6 +799,215 @@ pub fn record_token_usage_and_ip(
     Ok(())
 }
 
+/// 计算配额周期标识 (UTC): (分钟, 日, 月)
+fn quota_period_keys(now: chrono::DateTime<Utc>) -> (String, String, String) {
+    (
+        now.format("%Y-%m-%dT%H:%M").to_string(),
+        now.format("%Y-%m-%d").to_string(),
+        now.format("%Y-%m").to_string(),
+    )
+}
+
+/// 读取某个周期桶的计数 (requests, tokens)
+fn read_quota_bucket(
+    conn: &Connection,
+    token_id: &str,
+    period: &str,
+    period_key: &str,
+) -> Result<(i64, i64), String> {
+    conn.query_row(
+        "SELECT requests, tokens FROM token_quota_usage
+         WHERE token_id = ?1 AND period = ?2 AND period_key = ?3",
+        params![token_id, period, period_key],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+    .map(|r| r.unwrap_or((0, 0)))
+    .map_err(|e| format!("Failed to query quota usage: {}", e))
+}
+
+/// 读取某个周期桶累计的费用 (美元)
+fn read_quota_cost(
+    conn: &Connection,
+    token_id: &str,
+    period: &str,
+    period_key: &str,
+) -> Result<f64, String> {
+    conn.query_row(
+        "SELECT cost FROM token_quota_usage
+         WHERE token_id = ?1 AND period = ?2 AND period_key = ?3",
+        params![token_id, period, period_key],
+        |row| row.get(0),
+    )
+    .optional()
+    .map(|r| r.unwrap_or(0.0))
+    .map_err(|e| format!("Failed to query quota cost: {}", e))
+}
+
+/// 判断模型是否在令牌的允许列表中 (空列表表示不限制)
+pub fn is_model_allowed(token: &UserToken, model: &str) -> bool {
+    token.allowed_models.is_empty()
+        || token
+            .allowed_models
+            .iter()
+            .any(|pattern| crate::proxy::common::model_mapping::wildcard_match(pattern, model))
+}
+
+/// 校验令牌配额并消耗一次请求额度
+///
+/// 检查顺序：模型允许列表 -> 月 Token -> 日 Token -> 费用预算 -> 每分钟请求数。
+/// 预算配置为 warn 时超限只告警，不拒绝请求。
+/// 仅在全部通过时才会累加分钟请求计数，被拒绝的请求不占用额度。
+pub fn check_and_consume_quota(
+    token: &UserToken,
+    model: Option<&str>,
+) -> Result<Option<QuotaViolation>, String> {
+    if let Some(model) = model {
+        if !is_model_allowed(token, model) {
+            return Ok(Some(QuotaViolation::ModelNotAllowed {
+                model: model.to_string(),
+            }));
+        }
+    }
+
+    let budget = BudgetConfig {
+        daily_usd: token.daily_budget_usd,
+        monthly_usd: token.monthly_budget_usd,
+        action: token.budget_action,
+    };
+    if token.rpm_limit <= 0
+        && token.daily_token_limit <= 0
+        && token.monthly_token_limit <= 0
+        && !budget.is_enabled()
+    {
+        return Ok(None);
+    }
+
+    let mut conn = connect_db()?;
+    let tx = conn.transaction().map_err(|e| format!("Failed to create transaction: {}", e))?;
+    let now = Utc::now();
+    let (minute_key, day_key, month_key) = quota_period_keys(now);
+
+    if token.monthly_token_limit > 0 {
+        let (_, used) = read_quota_bucket(&tx, &token.id, "month", &month_key)?;
+        if used >= token.monthly_token_limit {
+            return Ok(Some(QuotaViolation::MonthlyTokensExceeded {
+                used,
+                limit: token.monthly_token_limit,
+                retry_after_secs: secs_until_next_month(now),
+            }));
+        }
+    }
+
+    if token.daily_token_limit > 0 {
+        let (_, used) = read_quota_bucket(&tx, &token.id, "day", &day_key)?;
+        if used >= token.daily_token_limit {
+            return Ok(Some(QuotaViolation::DailyTokensExceeded {
+                used,
+                limit: token.daily_token_limit,
+                retry_after_secs: 86_400 - now.num_seconds_from_midnight() as i64,
+            }));
+        }
+    }
+
+    if budget.is_enabled() {
+        let spent_today = read_quota_cost(&tx, &token.id, "day", &day_key)?;
+        let spent_this_month = read_quota_cost(&tx, &token.id, "month", &month_key)?;
+        if let Some(violation) = crate::modules::cost::budget_violation(
+            &budget,
+            spent_today,
+            spent_this_month,
+            now,
+            Some(&token.username),
+        ) {
+            if budget.action == BudgetAction::Block {
+                return Ok(Some(violation));
+            }
+            crate::modules::cost::warn_budget_exceeded(&violation);
+        }
+    }
+
+    if token.rpm_limit > 0 {
+        // 清理该令牌过期的分钟桶，避免表无限增长
+        let _ = tx.execute(
+            "DELETE FROM token_quota_usage WHERE token_id = ?1 AND period = 'minute' AND period_key <> ?2",
+            params![token.id, minute_key],
+        );
+
+        let (requests, _) = read_quota_bucket(&tx, &token.id, "minute", &minute_key)?;
+        if requests >= token.rpm_limit as i64 {
+            return Ok(Some(QuotaViolation::RateLimited {
+                limit: token.rpm_limit,
+                retry_after_secs: (60 - now.second() as i64).max(1),
+            }));
+        }
+
+        tx.execute(
+            "INSERT INTO token_quota_usage (token_id, period, period_key, requests, tokens)
+             VALUES (?1, 'minute', ?2, 1, 0)
+             ON CONFLICT(token_id, period, period_key) DO UPDATE SET requests = requests + 1",
+            params![token.id, minute_key],
+        ).map_err(|e| format!("Failed to update quota usage: {}", e))?;
+    }
+
+    tx.commit().map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(None)
+}
+
+/// 距离下个 UTC 自然月的秒数
+pub(crate) fn secs_until_next_month(now: chrono::DateTime<Utc>) -> i64 {
+    use chrono::{Datelike, TimeZone};
+    let (year, month) = if now.month() == 12 {
+        (now.year() + 1, 1)
+    } else {
+        (now.year(), now.month() + 1)
+    };
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0)
+        .single()
+        .map(|next| (next - now).num_seconds().max(1))
+        .unwrap_or(86_400)
+}
+
+/// 获取令牌当前周期的配额使用情况；令牌不存在时返回 `Ok(None)`
+pub fn get_token_quota_usage(token_id: &str) -> Result<Option<TokenQuotaUsage>, String> {
+    let Some(token) = get_token_by_id(token_id)? else {
+        return Ok(None);
+    };
+    let conn = connect_db()?;
+    let (minute_key, day_key, month_key) = quota_period_keys(Utc::now());
+
+    let (requests_this_minute, _) = read_quota_bucket(&conn, token_id, "minute", &minute_key)?;
+    let (_, tokens_today) = read_quota_bucket(&conn, token_id, "day", &day_key)?;
+    let (_, tokens_this_month) = read_quota_bucket(&conn, token_id, "month", &month_key)?;
+    let cost_today = read_quota_cost(&conn, token_id, "day", &day_key)?;
+    let cost_this_month = read_quota_cost(&conn, token_id, "month", &month_key)?;
+
+    Ok(Some(TokenQuotaUsage {
+        token_id: token_id.to_string(),
+        requests_this_minute,
+        tokens_today,
+        tokens_this_month,
+        rpm_limit: token.rpm_limit,
+        daily_token_limit: token.daily_token_limit,
+        monthly_token_limit: token.monthly_token_limit,
+        cost_today,
+        cost_this_month,
+        daily_budget_usd: token.daily_budget_usd,
+        monthly_budget_usd: token.monthly_budget_usd,
+    }))
+}
+
+/// 重置令牌的配额计数器
+pub fn reset_token_quota_usage(token_id: &str) -> Result<(), String> {
+    let conn = connect_db()?;
+    conn.execute(
+        "DELETE FROM token_quota_usage WHERE token_id = ?1",
+        params![token_id],
+    ).map_err(|e| format!("Failed to reset quota usage: {}", e))?;
+    Ok(())
+}
+
 /// 检查 Token 是否有效 (包含过期时间检查和 IP 限制检查)
 /// 返回: (是否有效, 拒绝原因)
 pub fn validate_token(token_str: &str, ip: &str) -> Result<(bool, Option<String>), String> {
@@ -595,7 +1105,7 @@ mod tests {
         
         // Use a random username to avoid collisions in existing DB runs during dev
         let username = format!("TestUser_{}", Uuid::new_v4());
-        let token_res = create_token(username.clone(), "day".to_string(), Some("Test token".to_string()), 0, None, None, None);
+        let token_res = create_token(username.clone(), "day".to_string(), Some("Test token".to_string()), 0, None, None, None, TokenLimits::default());
         assert!(token_res.is_ok());
 
         let token = token_res.unwrap();
@@ -606,4 +1116,107 @@ mod tests {
         assert!(fetched.is_ok());
         assert_eq!(fetched.unwrap().unwrap().username, username);
     }
+
+    #[test]
+    fn test_quota_rpm_and_model_allow_list() {
+        let _ = init_db();
+
+        let username = format!("QuotaUser_{}", Uuid::new_v4());
+        let limits = TokenLimits {
+            rpm_limit: 2,
+            daily_token_limit: 0,
+            monthly_token_limit: 0,
+            allowed_models: vec!["claude-*".to_string()],
+            ..Default::default()
+        };
+        let token = create_token(username, "never".to_string(), None, 0, None, None, None, limits).unwrap();
+
+        let denied = check_and_consume_quota(&token, Some("gpt-4o")).unwrap();
+        assert!(matches!(denied, Some(QuotaViolation::ModelNotAllowed { .. })));
+
+        assert!(check_and_consume_quota(&token, Some("claude-sonnet-4-5")).unwrap().is_none());
+        assert!(check_and_consume_quota(&token, Some("claude-sonnet-4-5")).unwrap().is_none());
+        let limited = check_and_consume_quota(&token, Some("claude-sonnet-4-5")).unwrap();
+        assert!(matches!(limited, Some(QuotaViolation::RateLimited { limit: 2, .. })));
+
+        let _ = delete_token(&token.id);
+    }
+
+    #[test]
+    fn test_allowed_groups_roundtrip() {
+        let _ = init_db();
+
+        let username = format!("GroupUser_{}", Uuid::new_v4());
+        let limits = TokenLimits {
+            allowed_groups: vec![" team-a ".to_string(), "team-a".to_string(), "".to_string()],
+            ..Default::default()
+        };
+        let token = create_token(username, "never".to_string(), None, 0, None, None, None, limits).unwrap();
+        assert_eq!(token.allowed_groups, vec!["team-a"]);
+
+        update_token_limits(&token.id, None, None, None, None, Some(vec!["team-b".to_string(), "team-c".to_string()])).unwrap();
+        let fetched = get_token_by_id(&token.id).unwrap().unwrap();
+        assert_eq!(fetched.allowed_groups, vec!["team-b", "team-c"]);
+        assert!(fetched.allowed_models.is_empty());
+
+        let _ = delete_token(&token.id);
+    }
+
+    #[test]
+    fn test_quota_daily_tokens() {
+        let _ = init_db();
+
+        let username = format!("QuotaUser_{}", Uuid::new_v4());
+        let limits = TokenLimits {
+            daily_token_limit: 100,
+            ..Default::default()
+        };
+        let token = create_token(username, "never".to_string(), None, 0, None, None, None, limits).unwrap();
+
+        assert!(check_and_consume_quota(&token, None).unwrap().is_none());
+        record_token_usage_and_ip(&token.id, "127.0.0.1", "gemini-3-flash", 80, 40, None, 200, None).unwrap();
+
+        let usage = get_token_quota_usage(&token.id).unwrap().unwrap();
+        assert_eq!(usage.tokens_today, 120);
+        assert!(get_token_quota_usage("missing-token").unwrap().is_none());
+
+        let exceeded = check_and_consume_quota(&token, None).unwrap();
+        assert!(matches!(exceeded, Some(QuotaViolation::DailyTokensExceeded { used: 120, limit: 100, .. })));
+
+        reset_token_quota_usage(&token.id).unwrap();
+        assert!(check_and_consume_quota(&token, None).unwrap().is_none());
+
+        let _ = delete_token(&token.id);
+    }
+
+    #[test]
+    fn test_quota_daily_budget() {
+        let _ = init_db();
+
+        let username = format!("BudgetUser_{}", Uuid::new_v4());
+        let limits = TokenLimits {
+            daily_budget_usd: 1.0,
+            budget_action: BudgetAction::Block,
+            ..Default::default()
+        };
+        let token = create_token(username, "never".to_string(), None, 0, None, None, None, limits).unwrap();
+
+        record_token_usage_and_ip(&token.id, "127.0.0.1", "gemini-3-pro", 1000, 500, Some(0.6), 200, None).unwrap();
+        assert!(check_and_consume_quota(&token, None).unwrap().is_none());
+        record_token_usage_and_ip(&token.id, "127.0.0.1", "gemini-3-pro", 1000, 500, Some(0.6), 200, None).unwrap();
+
+        let usage = get_token_quota_usage(&token.id).unwrap().unwrap();
+        assert!((usage.cost_today - 1.2).abs() < 1e-9);
+
+        let exceeded = check_and_consume_quota(&token, None).unwrap();
+        assert!(matches!(exceeded, Some(QuotaViolation::BudgetExceeded { period: BudgetPeriod::Day, .. })));
+
+        // warn 模式只告警不拒绝
+        update_token_budget(&token.id, None, None, Some(BudgetAction::Warn)).unwrap();
+        let token = get_token_by_id(&token.id).unwrap().unwrap();
+        assert_eq!(token.budget_action, BudgetAction::Warn);
+        assert!(check_and_consume_quota(&token, None).unwrap().is_none());
+
+        let _ = delete_token(&token.id);
+    }
 }
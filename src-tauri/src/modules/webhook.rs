@@ -0,0 +1,685 @@
+//! Webhook 通知
+//!
+//! 把运维事件 (账号 403、配额保护触发、模型全池限流、代理池节点失效、用户令牌过期、
+//! Cloudflared 隧道断开) 推送到外部 HTTP 端点。Headless 部署没有托盘通知，这是唯一的告警通道。
+//!
+//! - 每个端点单独订阅事件，载荷由 JSON 模板渲染 (内置 generic / slack / discord，或自定义模板)
+//! - 同一事件 + 对象在 `cooldown_secs` 内只通知一次，避免限流风暴刷屏
+//! - 失败 (网络错误 / 5xx / 408 / 429) 按指数退避重试，结果写入 `webhook_db` 投递日志
+
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::modules::webhook_db::{self, WebhookDelivery};
+
+/// 签名请求头: `sha256=<hex(HMAC-SHA256(secret, body))>`
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+/// 事件名请求头
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+
+/// 单次请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// 单次退避上限
+const MAX_BACKOFF_SECS: u64 = 300;
+/// 用户令牌过期扫描间隔
+const TOKEN_EXPIRY_SWEEP_SECS: i64 = 60;
+
+/// 可订阅的事件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// 账号被上游 403 标记为 forbidden
+    AccountForbidden,
+    /// 账号某模型组因配额低于阈值被加入保护
+    QuotaProtectionTriggered,
+    /// 某模型在所有账号上均被限流
+    ModelRateLimited,
+    /// 代理池节点健康检查由成功变为失败
+    ProxyUnhealthy,
+    /// 用户令牌到期
+    UserTokenExpired,
+    /// Cloudflared 隧道进程意外退出
+    TunnelDown,
+    /// 全局或用户令牌的费用预算超限
+    BudgetExceeded,
+    /// 手动测试投递
+    Test,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::AccountForbidden => "account_forbidden",
+            WebhookEvent::QuotaProtectionTriggered => "quota_protection_triggered",
+            WebhookEvent::ModelRateLimited => "model_rate_limited",
+            WebhookEvent::ProxyUnhealthy => "proxy_unhealthy",
+            WebhookEvent::UserTokenExpired => "user_token_expired",
+            WebhookEvent::TunnelDown => "tunnel_down",
+            WebhookEvent::BudgetExceeded => "budget_exceeded",
+            WebhookEvent::Test => "test",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            WebhookEvent::AccountForbidden => "Account forbidden",
+            WebhookEvent::QuotaProtectionTriggered => "Quota protection triggered",
+            WebhookEvent::ModelRateLimited => "All accounts rate limited",
+            WebhookEvent::ProxyUnhealthy => "Proxy unhealthy",
+            WebhookEvent::UserTokenExpired => "User token expired",
+            WebhookEvent::TunnelDown => "Tunnel down",
+            WebhookEvent::BudgetExceeded => "Budget exceeded",
+            WebhookEvent::Test => "Test notification",
+        }
+    }
+
+    fn severity(&self) -> &'static str {
+        match self {
+            WebhookEvent::AccountForbidden
+            | WebhookEvent::ModelRateLimited
+            | WebhookEvent::TunnelDown => "critical",
+            WebhookEvent::QuotaProtectionTriggered
+            | WebhookEvent::ProxyUnhealthy
+            | WebhookEvent::UserTokenExpired
+            | WebhookEvent::BudgetExceeded => "warning",
+            WebhookEvent::Test => "info",
+        }
+    }
+
+    /// Discord embed 颜色
+    fn color(&self) -> u32 {
+        match self.severity() {
+            "critical" => 0xE74C3C,
+            "warning" => 0xF39C12,
+            _ => 0x3498DB,
+        }
+    }
+}
+
+/// 载荷格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadFormat {
+    #[default]
+    Generic,
+    Slack,
+    Discord,
+    /// 使用端点的 `template`
+    Custom,
+}
+
+/// 单个 Webhook 端点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEndpoint {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    pub url: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 订阅的事件，为空表示订阅全部
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    #[serde(default)]
+    pub format: PayloadFormat,
+    /// `format = custom` 时的 JSON 模板，字符串值中的 `{{placeholder}}` 会被替换
+    #[serde(default)]
+    pub template: Option<String>,
+    /// 附加请求头 (如 Authorization)
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// 设置后使用 HMAC-SHA256 对请求体签名
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+impl WebhookEndpoint {
+    fn subscribes(&self, event: WebhookEvent) -> bool {
+        self.enabled
+            && !self.url.trim().is_empty()
+            && (event == WebhookEvent::Test || self.events.is_empty() || self.events.contains(&event))
+    }
+
+    fn display_name(&self) -> &str {
+        if self.name.is_empty() {
+            &self.id
+        } else {
+            &self.name
+        }
+    }
+}
+
+/// Webhook 配置 (`AppConfig.webhooks`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub endpoints: Vec<WebhookEndpoint>,
+    /// 首次发送失败后的最大重试次数
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// 首次重试前的等待秒数，之后每次翻倍 (上限 300s)
+    #[serde(default = "default_retry_base_secs")]
+    pub retry_base_secs: u64,
+    /// 同一事件 + 对象的最短通知间隔
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_retry_base_secs() -> u64 {
+    5
+}
+
+fn default_cooldown_secs() -> u64 {
+    300
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoints: Vec::new(),
+            max_retries: default_max_retries(),
+            retry_base_secs: default_retry_base_secs(),
+            cooldown_secs: default_cooldown_secs(),
+        }
+    }
+}
+
+/// 一条待发送的通知
+#[derive(Debug, Clone)]
+pub struct WebhookNotification {
+    pub event: WebhookEvent,
+    /// 事件对象 (账号邮箱 / 模型 / 代理名 / 用户名 ...)，同时用于冷却去重
+    pub subject: String,
+    pub message: String,
+    pub details: Value,
+    pub timestamp: i64,
+}
+
+impl WebhookNotification {
+    pub fn new(event: WebhookEvent, subject: impl Into<String>, message: impl Into<String>, details: Value) -> Self {
+        Self {
+            event,
+            subject: subject.into(),
+            message: message.into(),
+            details,
+            timestamp: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+// ===== 模板渲染 =====
+
+fn builtin_template(format: PayloadFormat) -> Value {
+    match format {
+        PayloadFormat::Generic | PayloadFormat::Custom => json!({
+            "event": "{{event}}",
+            "severity": "{{severity}}",
+            "title": "{{title}}",
+            "message": "{{message}}",
+            "subject": "{{subject}}",
+            "timestamp": "{{timestamp}}",
+            "details": "{{details}}"
+        }),
+        PayloadFormat::Slack => json!({
+            "text": "[{{severity}}] *{{title}}*: {{message}}"
+        }),
+        PayloadFormat::Discord => json!({
+            "embeds": [{
+                "title": "{{title}}",
+                "description": "{{message}}",
+                "color": "{{color}}",
+                "timestamp": "{{time}}",
+                "footer": { "text": "{{event}} · {{subject}}" }
+            }]
+        }),
+    }
+}
+
+/// 占位符: 字符串值恰好为 `{{name}}` 时替换为带类型的值，否则按文本替换
+fn placeholder_values(n: &WebhookNotification) -> Vec<(&'static str, Value)> {
+    let time = chrono::DateTime::from_timestamp(n.timestamp, 0)
+        .map(|t| t.to_rfc3339())
+        .unwrap_or_default();
+    vec![
+        ("event", json!(n.event.as_str())),
+        ("severity", json!(n.event.severity())),
+        ("title", json!(n.event.title())),
+        ("message", json!(n.message)),
+        ("subject", json!(n.subject)),
+        ("timestamp", json!(n.timestamp)),
+        ("time", json!(time)),
+        ("color", json!(n.event.color())),
+        ("details", n.details.clone()),
+    ]
+}
+
+fn render_value(template: &Value, values: &[(&'static str, Value)]) -> Value {
+    match template {
+        Value::String(s) => {
+            let trimmed = s.trim();
+            if let Some((_, v)) = values
+                .iter()
+                .find(|(name, _)| trimmed == format!("{{{{{}}}}}", name))
+            {
+                return v.clone();
+            }
+            let mut out = s.clone();
+            for (name, v) in values {
+                let needle = format!("{{{{{}}}}}", name);
+                if out.contains(&needle) {
+                    let text = match v {
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    out = out.replace(&needle, &text);
+                }
+            }
+            Value::String(out)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|v| render_value(v, values)).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), render_value(v, values)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// 按端点格式渲染请求体
+pub fn render_payload(endpoint: &WebhookEndpoint, n: &WebhookNotification) -> Result<Value, String> {
+    let template = match (endpoint.format, endpoint.template.as_deref()) {
+        (PayloadFormat::Custom, Some(t)) if !t.trim().is_empty() => serde_json::from_str::<Value>(t)
+            .map_err(|e| format!("Invalid webhook template for {}: {}", endpoint.display_name(), e))?,
+        (format, _) => builtin_template(format),
+    };
+    Ok(render_value(&template, &placeholder_values(n)))
+}
+
+fn sign_payload(secret: &str, body: &[u8]) -> String {
+    // HMAC 接受任意长度的密钥，new_from_slice 不会失败
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    format!(
+        "sha256={}",
+        mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect::<String>()
+    )
+}
+
+/// 网络错误、5xx、408、429 值得重试；其余 4xx 重试也不会成功
+fn is_retryable_status(status: u16) -> bool {
+    status >= 500 || status == 408 || status == 429
+}
+
+fn backoff_secs(base: u64, retry: u32) -> u64 {
+    base.saturating_mul(1u64 << retry.min(16)).min(MAX_BACKOFF_SECS)
+}
+
+// ===== 冷却去重 =====
+
+/// key = "event:subject"，value = 冷却截止时间
+static COOLDOWNS: Lazy<Mutex<HashMap<String, i64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn cooldown_key(event: WebhookEvent, subject: &str) -> String {
+    format!("{}:{}", event.as_str(), subject)
+}
+
+fn in_cooldown(key: &str, now: i64) -> bool {
+    COOLDOWNS
+        .lock()
+        .map(|map| map.get(key).map_or(false, |until| now < *until))
+        .unwrap_or(false)
+}
+
+fn start_cooldown(key: String, now: i64, cooldown_secs: u64) {
+    if let Ok(mut map) = COOLDOWNS.lock() {
+        map.retain(|_, until| *until > now);
+        map.insert(key, now + cooldown_secs as i64);
+    }
+}
+
+// ===== 投递 =====
+
+fn build_client() -> Result<reqwest::Client, String> {
+    let mut builder = reqwest::Client::builder()
+        .user_agent("Antigravity-Manager")
+        .timeout(REQUEST_TIMEOUT);
+
+    if let Ok(config) = crate::modules::config::load_app_config() {
+        if config.proxy.upstream_proxy.enabled && !config.proxy.upstream_proxy.url.is_empty() {
+            match reqwest::Proxy::all(&config.proxy.upstream_proxy.url) {
+                Ok(proxy) => builder = builder.proxy(proxy),
+                Err(e) => tracing::warn!("[Webhook] Invalid upstream proxy URL: {}", e),
+            }
+        }
+    }
+
+    builder
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// 发送请求并按策略重试，过程中更新 `delivery` 的次数 / 状态码 / 错误
+async fn send_with_retries(
+    endpoint: &WebhookEndpoint,
+    notification: &WebhookNotification,
+    max_retries: u32,
+    retry_base_secs: u64,
+    delivery: &mut WebhookDelivery,
+) -> Result<(), String> {
+    let body = serde_json::to_vec(&render_payload(endpoint, notification)?)
+        .map_err(|e| e.to_string())?;
+    delivery.payload = String::from_utf8_lossy(&body).to_string();
+    let client = build_client()?;
+
+    loop {
+        delivery.attempts += 1;
+        let mut request = client
+            .post(&endpoint.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, notification.event.as_str());
+        for (name, value) in &endpoint.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        if let Some(secret) = endpoint.secret.as_deref().filter(|s| !s.is_empty()) {
+            request = request.header(SIGNATURE_HEADER, sign_payload(secret, &body));
+        }
+
+        let retryable = match request.body(body.clone()).send().await {
+            Ok(resp) => {
+                let status = resp.status().as_u16();
+                delivery.status_code = Some(status);
+                if resp.status().is_success() {
+                    return Ok(());
+                }
+                let text = resp.text().await.unwrap_or_default();
+                delivery.error = Some(format!(
+                    "HTTP {}: {}",
+                    status,
+                    text.chars().take(200).collect::<String>()
+                ));
+                is_retryable_status(status)
+            }
+            Err(e) => {
+                delivery.status_code = None;
+                delivery.error = Some(e.to_string());
+                true
+            }
+        };
+
+        let retry = delivery.attempts - 1;
+        if !retryable || retry >= max_retries {
+            return Err(delivery.error.clone().unwrap_or_default());
+        }
+        tokio::time::sleep(Duration::from_secs(backoff_secs(retry_base_secs, retry))).await;
+    }
+}
+
+/// 发送到单个端点 (含重试)，并写入投递日志
+async fn deliver(
+    endpoint: WebhookEndpoint,
+    notification: WebhookNotification,
+    max_retries: u32,
+    retry_base_secs: u64,
+) -> WebhookDelivery {
+    let started = Instant::now();
+    let mut delivery = WebhookDelivery {
+        id: uuid::Uuid::new_v4().to_string(),
+        endpoint_id: endpoint.id.clone(),
+        endpoint_name: endpoint.display_name().to_string(),
+        event: notification.event.as_str().to_string(),
+        subject: notification.subject.clone(),
+        url: endpoint.url.clone(),
+        success: false,
+        attempts: 0,
+        status_code: None,
+        error: None,
+        payload: String::new(),
+        created_at: notification.timestamp,
+        duration_ms: 0,
+    };
+
+    match send_with_retries(&endpoint, &notification, max_retries, retry_base_secs, &mut delivery).await {
+        Ok(()) => {
+            delivery.success = true;
+            delivery.error = None;
+        }
+        Err(e) => {
+            tracing::warn!(
+                "[Webhook] Delivery of {} to {} failed after {} attempt(s): {}",
+                delivery.event,
+                delivery.endpoint_name,
+                delivery.attempts,
+                e
+            );
+            delivery.error = Some(e);
+        }
+    }
+    delivery.duration_ms = started.elapsed().as_millis() as u64;
+
+    let record = delivery.clone();
+    match tokio::task::spawn_blocking(move || webhook_db::record_delivery(&record)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::warn!("[Webhook] Failed to record delivery: {}", e),
+        Err(e) => tracing::warn!("[Webhook] Record task failed: {}", e),
+    }
+    delivery
+}
+
+/// 触发一次事件通知 (不阻塞调用方)
+///
+/// 未启用、无订阅端点或仍在冷却期内时直接返回。
+pub fn notify(event: WebhookEvent, subject: impl Into<String>, message: impl Into<String>, details: Value) {
+    let notification = WebhookNotification::new(event, subject, message, details);
+    let key = cooldown_key(event, &notification.subject);
+    if in_cooldown(&key, notification.timestamp) {
+        return;
+    }
+
+    let config = match crate::modules::config::load_app_config() {
+        Ok(cfg) => cfg.webhooks,
+        Err(_) => return,
+    };
+    if !config.enabled {
+        return;
+    }
+    let endpoints: Vec<WebhookEndpoint> = config
+        .endpoints
+        .into_iter()
+        .filter(|ep| ep.subscribes(event))
+        .collect();
+    if endpoints.is_empty() {
+        return;
+    }
+    start_cooldown(key, notification.timestamp, config.cooldown_secs);
+
+    tracing::info!(
+        "[Webhook] {} ({}) -> {} endpoint(s)",
+        event.as_str(),
+        notification.subject,
+        endpoints.len()
+    );
+    for endpoint in endpoints {
+        let notification = notification.clone();
+        let (max_retries, base) = (config.max_retries, config.retry_base_secs);
+        tauri::async_runtime::spawn(async move {
+            deliver(endpoint, notification, max_retries, base).await;
+        });
+    }
+}
+
+/// 向指定端点发送测试通知 (不重试、不受冷却与总开关限制)
+pub async fn send_test(endpoint: WebhookEndpoint) -> Result<WebhookDelivery, String> {
+    if endpoint.url.trim().is_empty() {
+        return Err("Webhook URL is empty".to_string());
+    }
+    let notification = WebhookNotification::new(
+        WebhookEvent::Test,
+        endpoint.display_name().to_string(),
+        "This is a test notification from Antigravity Manager.",
+        json!({ "endpoint_id": endpoint.id }),
+    );
+    Ok(deliver(endpoint, notification, 0, 0).await)
+}
+
+/// 周期扫描用户令牌，对刚过期的令牌发出 `user_token_expired`
+pub fn start_token_expiry_watcher() {
+    tauri::async_runtime::spawn(async move {
+        let mut last_sweep = chrono::Utc::now().timestamp() - TOKEN_EXPIRY_SWEEP_SECS;
+        let mut interval = tokio::time::interval(Duration::from_secs(TOKEN_EXPIRY_SWEEP_SECS as u64));
+        loop {
+            interval.tick().await;
+            let now = chrono::Utc::now().timestamp();
+            let tokens = match tokio::task::spawn_blocking(crate::modules::user_token_db::list_tokens).await {
+                Ok(Ok(tokens)) => tokens,
+                Ok(Err(e)) => {
+                    tracing::debug!("[Webhook] Failed to list user tokens: {}", e);
+                    continue;
+                }
+                Err(_) => continue,
+            };
+
+            for token in tokens.iter().filter(|t| t.enabled) {
+                if let Some(expires_at) = token.expires_at {
+                    if expires_at > last_sweep && expires_at <= now {
+                        notify(
+                            WebhookEvent::UserTokenExpired,
+                            token.username.clone(),
+                            format!("User token for {} expired", token.username),
+                            json!({
+                                "token_id": token.id,
+                                "username": token.username,
+                                "expires_at": expires_at,
+                            }),
+                        );
+                    }
+                }
+            }
+            last_sweep = now;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(format: PayloadFormat, template: Option<&str>) -> WebhookEndpoint {
+        WebhookEndpoint {
+            id: "ep1".to_string(),
+            name: "ops".to_string(),
+            url: "https://hooks.example.com/x".to_string(),
+            enabled: true,
+            events: vec![],
+            format,
+            template: template.map(|s| s.to_string()),
+            headers: HashMap::new(),
+            secret: None,
+        }
+    }
+
+    fn notification() -> WebhookNotification {
+        WebhookNotification {
+            event: WebhookEvent::AccountForbidden,
+            subject: "a@example.com".to_string(),
+            message: "Account \"a@example.com\" was marked forbidden".to_string(),
+            details: json!({ "reason": "403" }),
+            timestamp: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn test_builtin_payload_shapes() {
+        let generic = render_payload(&endpoint(PayloadFormat::Generic, None), &notification()).unwrap();
+        assert_eq!(generic["event"], "account_forbidden");
+        assert_eq!(generic["severity"], "critical");
+        assert_eq!(generic["timestamp"], 1_700_000_000);
+        assert_eq!(generic["details"], json!({ "reason": "403" }));
+
+        let slack = render_payload(&endpoint(PayloadFormat::Slack, None), &notification()).unwrap();
+        assert_eq!(
+            slack["text"],
+            "[critical] *Account forbidden*: Account \"a@example.com\" was marked forbidden"
+        );
+
+        let discord = render_payload(&endpoint(PayloadFormat::Discord, None), &notification()).unwrap();
+        assert_eq!(discord["embeds"][0]["color"], 0xE74C3C);
+        assert_eq!(discord["embeds"][0]["timestamp"], "2023-11-14T22:13:20+00:00");
+    }
+
+    #[test]
+    fn test_custom_template_rendering() {
+        let tpl = r#"{"msg": "{{title}} - {{subject}}", "data": "{{details}}", "inline": "d={{details}}", "n": 1, "unknown": "{{nope}}"}"#;
+        let payload = render_payload(&endpoint(PayloadFormat::Custom, Some(tpl)), &notification()).unwrap();
+        assert_eq!(payload["msg"], "Account forbidden - a@example.com");
+        assert_eq!(payload["data"], json!({ "reason": "403" }));
+        assert_eq!(payload["inline"], r#"d={"reason":"403"}"#);
+        assert_eq!(payload["n"], 1);
+        assert_eq!(payload["unknown"], "{{nope}}");
+
+        // 模板本身必须是合法 JSON
+        assert!(render_payload(&endpoint(PayloadFormat::Custom, Some("{bad")), &notification()).is_err());
+        // custom 但模板为空时退回 generic
+        let fallback = render_payload(&endpoint(PayloadFormat::Custom, Some(" ")), &notification()).unwrap();
+        assert_eq!(fallback["event"], "account_forbidden");
+    }
+
+    #[test]
+    fn test_subscription_filter() {
+        let mut ep = endpoint(PayloadFormat::Generic, None);
+        assert!(ep.subscribes(WebhookEvent::TunnelDown));
+
+        ep.events = vec![WebhookEvent::AccountForbidden];
+        assert!(ep.subscribes(WebhookEvent::AccountForbidden));
+        assert!(!ep.subscribes(WebhookEvent::TunnelDown));
+        assert!(ep.subscribes(WebhookEvent::Test));
+
+        ep.enabled = false;
+        assert!(!ep.subscribes(WebhookEvent::AccountForbidden));
+    }
+
+    #[test]
+    fn test_retry_policy_and_backoff() {
+        assert!(is_retryable_status(503));
+        assert!(is_retryable_status(429));
+        assert!(is_retryable_status(408));
+        assert!(!is_retryable_status(404));
+        assert!(!is_retryable_status(401));
+
+        assert_eq!(backoff_secs(5, 0), 5);
+        assert_eq!(backoff_secs(5, 2), 20);
+        assert_eq!(backoff_secs(5, 10), MAX_BACKOFF_SECS);
+    }
+
+    #[test]
+    fn test_signature_and_cooldown() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign_payload("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+
+        let key = cooldown_key(WebhookEvent::ModelRateLimited, "test-cooldown-model");
+        assert!(!in_cooldown(&key, 1000));
+        start_cooldown(key.clone(), 1000, 60);
+        assert!(in_cooldown(&key, 1059));
+        assert!(!in_cooldown(&key, 1060));
+    }
+}
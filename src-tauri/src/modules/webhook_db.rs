@@ -0,0 +1,216 @@
+//! Webhook 投递日志
+//!
+//! 每次投递 (含全部重试) 记录一行，只保留最近 `MAX_LOG_ROWS` 条。
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// 投递日志保留条数
+const MAX_LOG_ROWS: i64 = 1000;
+
+/// 一次 Webhook 投递的结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub endpoint_id: String,
+    pub endpoint_name: String,
+    pub event: String,
+    pub subject: String,
+    pub url: String,
+    pub success: bool,
+    /// 实际发送次数 (首次 + 重试)
+    pub attempts: u32,
+    /// 最后一次响应的 HTTP 状态码
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    /// 渲染后的请求体
+    pub payload: String,
+    pub created_at: i64,
+    /// 从首次发送到结束 (含退避等待) 的耗时
+    pub duration_ms: u64,
+}
+
+pub fn get_db_path() -> Result<PathBuf, String> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("webhooks.db"))
+}
+
+fn connect_db() -> Result<Connection, String> {
+    let conn = Connection::open(get_db_path()?).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| e.to_string())?;
+    Ok(conn)
+}
+
+fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id TEXT PRIMARY KEY,
+            endpoint_id TEXT NOT NULL,
+            endpoint_name TEXT NOT NULL,
+            event TEXT NOT NULL,
+            subject TEXT NOT NULL,
+            url TEXT NOT NULL,
+            success INTEGER NOT NULL,
+            attempts INTEGER NOT NULL,
+            status_code INTEGER,
+            error TEXT,
+            payload TEXT NOT NULL,
+            created_at INTEGER NOT NULL,
+            duration_ms INTEGER NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| format!("Failed to create webhook_deliveries table: {}", e))?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_created ON webhook_deliveries (created_at DESC)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// 初始化数据库
+pub fn init_db() -> Result<(), String> {
+    create_tables(&connect_db()?)
+}
+
+fn record_at(conn: &Connection, delivery: &WebhookDelivery) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO webhook_deliveries
+            (id, endpoint_id, endpoint_name, event, subject, url, success, attempts,
+             status_code, error, payload, created_at, duration_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        params![
+            delivery.id,
+            delivery.endpoint_id,
+            delivery.endpoint_name,
+            delivery.event,
+            delivery.subject,
+            delivery.url,
+            delivery.success,
+            delivery.attempts,
+            delivery.status_code,
+            delivery.error,
+            delivery.payload,
+            delivery.created_at,
+            delivery.duration_ms as i64,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+
+    conn.execute(
+        "DELETE FROM webhook_deliveries WHERE id NOT IN (
+            SELECT id FROM webhook_deliveries ORDER BY created_at DESC, rowid DESC LIMIT ?1
+        )",
+        params![MAX_LOG_ROWS],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn list_at(conn: &Connection, limit: usize, offset: usize) -> Result<Vec<WebhookDelivery>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, endpoint_id, endpoint_name, event, subject, url, success, attempts,
+                    status_code, error, payload, created_at, duration_ms
+             FROM webhook_deliveries
+             ORDER BY created_at DESC, rowid DESC
+             LIMIT ?1 OFFSET ?2",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let rows = stmt
+        .query_map(params![limit as i64, offset as i64], |row| {
+            Ok(WebhookDelivery {
+                id: row.get(0)?,
+                endpoint_id: row.get(1)?,
+                endpoint_name: row.get(2)?,
+                event: row.get(3)?,
+                subject: row.get(4)?,
+                url: row.get(5)?,
+                success: row.get(6)?,
+                attempts: row.get(7)?,
+                status_code: row.get(8)?,
+                error: row.get(9)?,
+                payload: row.get(10)?,
+                created_at: row.get(11)?,
+                duration_ms: row.get::<_, i64>(12)?.max(0) as u64,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// 写入一条投递记录
+pub fn record_delivery(delivery: &WebhookDelivery) -> Result<(), String> {
+    record_at(&connect_db()?, delivery)
+}
+
+/// 按时间倒序分页读取投递记录
+pub fn list_deliveries(limit: usize, offset: usize) -> Result<Vec<WebhookDelivery>, String> {
+    list_at(&connect_db()?, limit, offset)
+}
+
+/// 清空投递日志，返回删除的条数
+pub fn clear_deliveries() -> Result<usize, String> {
+    connect_db()?
+        .execute("DELETE FROM webhook_deliveries", [])
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delivery(id: &str, created_at: i64, success: bool) -> WebhookDelivery {
+        WebhookDelivery {
+            id: id.to_string(),
+            endpoint_id: "ep1".to_string(),
+            endpoint_name: "ops".to_string(),
+            event: "account_forbidden".to_string(),
+            subject: "a@example.com".to_string(),
+            url: "https://hooks.example.com/x".to_string(),
+            success,
+            attempts: if success { 1 } else { 4 },
+            status_code: if success { Some(200) } else { Some(503) },
+            error: if success { None } else { Some("HTTP 503".to_string()) },
+            payload: "{}".to_string(),
+            created_at,
+            duration_ms: 12,
+        }
+    }
+
+    #[test]
+    fn test_record_and_list_newest_first() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+
+        record_at(&conn, &delivery("d1", 100, true)).unwrap();
+        record_at(&conn, &delivery("d2", 200, false)).unwrap();
+
+        let rows = list_at(&conn, 10, 0).unwrap();
+        assert_eq!(rows, vec![delivery("d2", 200, false), delivery("d1", 100, true)]);
+        assert_eq!(list_at(&conn, 1, 1).unwrap()[0].id, "d1");
+    }
+
+    #[test]
+    fn test_log_is_pruned_to_max_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+
+        for i in 0..(MAX_LOG_ROWS + 5) {
+            record_at(&conn, &delivery(&format!("d{}", i), i, true)).unwrap();
+        }
+
+        let rows = list_at(&conn, 10_000, 0).unwrap();
+        assert_eq!(rows.len() as i64, MAX_LOG_ROWS);
+        assert_eq!(rows.last().unwrap().id, "d5");
+    }
+}
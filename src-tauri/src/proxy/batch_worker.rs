@@ -0,0 +1,339 @@
+//! 批处理 worker
+//!
+//! 从 `batch_db` 逐条领取请求，经内部 Router (auth -> monitor -> response_cache -> handler)
+//! 转发，与交互请求走同一条链路：账号轮换、配额、计费和请求日志完全一致。
+//! 只消耗空闲容量：交互请求在途数超过 `max_interactive_in_flight` 时暂停领取。
+
+use crate::modules::batch_db::{self, ClaimedItem, ItemStatus};
+use crate::proxy::common::routing_rules::RouteContext;
+use crate::proxy::config::get_batch_config;
+use crate::proxy::middleware::auth::UserTokenIdentity;
+use crate::proxy::server::AppState;
+use axum::body::Body;
+use axum::http::{header, HeaderMap, Method, Request, StatusCode};
+use axum::Router;
+use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tower::ServiceExt;
+
+/// 有待执行请求时的轮询间隔
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// 队列为空时的轮询间隔
+const IDLE_INTERVAL: Duration = Duration::from_secs(5);
+/// 过期处理与结果清理的间隔
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(60);
+/// 没有可用账号时的推迟时间 (秒)
+const NO_ACCOUNT_DELAY_SECS: i64 = 30;
+/// 被限流后的基础退避 (秒)，按执行次数翻倍
+const RETRY_BASE_SECS: i64 = 30;
+const RETRY_MAX_SECS: i64 = 600;
+
+/// 当前在途的交互请求数 (不含 worker 自己发出的请求)
+static INTERACTIVE_IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+/// 注入到 worker 发出请求的 extensions 的标记，用于区分交互流量
+#[derive(Debug, Clone, Copy)]
+pub struct BatchDispatch;
+
+/// 交互请求计数守卫，Drop 时减一
+pub struct InteractiveGuard;
+
+impl InteractiveGuard {
+    /// 只统计非批处理的 POST 请求 (GET 类的列表/查询不占用上游)
+    pub fn enter(request: &axum::extract::Request) -> Option<Self> {
+        if request.method() != Method::POST || request.extensions().get::<BatchDispatch>().is_some() {
+            return None;
+        }
+        INTERACTIVE_IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+        Some(Self)
+    }
+}
+
+impl Drop for InteractiveGuard {
+    fn drop(&mut self) {
+        INTERACTIVE_IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 批处理管理端点 (创建/查询/结果下载)，不写入请求日志
+pub fn is_batch_management_path(uri: &str) -> bool {
+    uri.starts_with("/v1/messages/batches") || uri.starts_with("/v1/batches")
+}
+
+/// worker 正在执行的请求数，任务结束 (包括 panic) 时减一
+struct RunningSlot(Arc<AtomicUsize>);
+
+impl RunningSlot {
+    fn acquire(counter: &Arc<AtomicUsize>) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Self(counter.clone())
+    }
+}
+
+impl Drop for RunningSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 启动 worker，返回的句柄在反代服务停止时 abort
+pub fn spawn(state: AppState, dispatch: Router) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let running = Arc::new(AtomicUsize::new(0));
+        let mut last_maintenance: Option<Instant> = None;
+
+        loop {
+            let config = get_batch_config();
+
+            if last_maintenance.map_or(true, |t| t.elapsed() >= MAINTENANCE_INTERVAL) {
+                last_maintenance = Some(Instant::now());
+                match batch_db::run_maintenance(config.retention_days) {
+                    Ok((expired, purged)) if expired > 0 || purged > 0 => tracing::info!(
+                        "[Batch] Expired {} request(s), purged {} batch(es)",
+                        expired,
+                        purged
+                    ),
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("[Batch] Maintenance failed: {}", e),
+                }
+            }
+
+            let paused = !config.enabled
+                || !*state.is_running.read().await
+                || running.load(Ordering::SeqCst) >= config.concurrency as usize
+                || INTERACTIVE_IN_FLIGHT.load(Ordering::SeqCst) > config.max_interactive_in_flight as usize;
+            if paused {
+                tokio::time::sleep(POLL_INTERVAL).await;
+                continue;
+            }
+
+            let item = match batch_db::claim_next() {
+                Ok(Some(item)) => item,
+                Ok(None) => {
+                    tokio::time::sleep(IDLE_INTERVAL).await;
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!("[Batch] Failed to claim request: {}", e);
+                    tokio::time::sleep(IDLE_INTERVAL).await;
+                    continue;
+                }
+            };
+
+            let slot = RunningSlot::acquire(&running);
+            let state = state.clone();
+            let dispatch = dispatch.clone();
+            let max_attempts = config.max_attempts;
+            tokio::spawn(async move {
+                let _slot = slot;
+                process_item(&state, dispatch, item, max_attempts).await;
+            });
+        }
+    })
+}
+
+/// 单条请求的执行结果
+enum Outcome {
+    /// 没有可用账号，未发出请求
+    Deferred(String),
+    Response {
+        status: StatusCode,
+        retry_after: Option<i64>,
+        body: Value,
+    },
+    Failed(String),
+}
+
+async fn process_item(state: &AppState, dispatch: Router, item: ClaimedItem, max_attempts: u32) {
+    let now = chrono::Utc::now().timestamp();
+    let result = match dispatch_item(state, dispatch, &item).await {
+        Outcome::Deferred(reason) => {
+            tracing::debug!("[Batch] {}/{} deferred: {}", item.batch_id, item.custom_id, reason);
+            batch_db::requeue_item(&item.batch_id, item.seq, now + NO_ACCOUNT_DELAY_SECS, &reason, false)
+        }
+        Outcome::Response { status, body, .. } if status.is_success() => batch_db::complete_item(
+            &item.batch_id,
+            item.seq,
+            ItemStatus::Succeeded,
+            Some(status.as_u16()),
+            Some(&body),
+            None,
+        ),
+        Outcome::Response { status, retry_after, body } => {
+            let reason = error_message(status, &body);
+            if is_retryable(status) && item.attempts < max_attempts {
+                let delay = retry_after.unwrap_or_else(|| backoff_secs(item.attempts)).min(RETRY_MAX_SECS);
+                tracing::info!(
+                    "[Batch] {}/{} got {} (attempt {}/{}), retrying in {}s",
+                    item.batch_id,
+                    item.custom_id,
+                    status.as_u16(),
+                    item.attempts,
+                    max_attempts,
+                    delay
+                );
+                batch_db::requeue_item(&item.batch_id, item.seq, now + delay, &reason, true)
+            } else {
+                batch_db::complete_item(
+                    &item.batch_id,
+                    item.seq,
+                    ItemStatus::Errored,
+                    Some(status.as_u16()),
+                    Some(&body),
+                    Some(&reason),
+                )
+            }
+        }
+        Outcome::Failed(reason) => batch_db::complete_item(
+            &item.batch_id,
+            item.seq,
+            ItemStatus::Errored,
+            None,
+            None,
+            Some(&reason),
+        ),
+    };
+
+    if let Err(e) = result {
+        tracing::error!("[Batch] Failed to store result of {}/{}: {}", item.batch_id, item.custom_id, e);
+    }
+}
+
+async fn dispatch_item(state: &AppState, dispatch: Router, item: &ClaimedItem) -> Outcome {
+    // 沿用创建者的令牌：配额、计费和分组限制与交互请求一致
+    let (credential, identity) = match &item.owner_token_id {
+        Some(token_id) => match crate::modules::user_token_db::get_token_by_id(token_id) {
+            Ok(Some(token)) => (
+                Some(token.token.clone()),
+                Some(UserTokenIdentity {
+                    token_id: token.id,
+                    token: token.token,
+                    username: token.username,
+                    account_groups: token.allowed_groups,
+                }),
+            ),
+            Ok(None) => return Outcome::Failed("The token that created this batch no longer exists".to_string()),
+            Err(e) => return Outcome::Deferred(format!("Failed to load batch owner token: {}", e)),
+        },
+        None => {
+            let api_key = state.security.read().await.api_key.clone();
+            (Some(api_key).filter(|k| !k.is_empty()), None)
+        }
+    };
+
+    let protocol = if item.endpoint == "/v1/messages" { "anthropic" } else { "openai" };
+    let model = item.body.get("model").and_then(|m| m.as_str()).unwrap_or_default();
+    let route = state.model_router.read().await.resolve(&RouteContext::from_request(
+        model,
+        protocol,
+        &item.body,
+        &HeaderMap::new(),
+        identity.as_ref(),
+    ));
+    let account_groups = identity.map(|i| i.account_groups).unwrap_or_default();
+    if route.provider.is_none()
+        && !route.use_zai
+        && !state
+            .token_manager
+            .has_available_account(protocol, &route.mapped_model, &account_groups)
+            .await
+    {
+        return Outcome::Deferred(format!("No available account for {}", route.mapped_model));
+    }
+
+    let mut body = item.body.clone();
+    if let Some(obj) = body.as_object_mut() {
+        // 批处理结果按整条响应存储
+        if obj.contains_key("stream") {
+            obj.insert("stream".to_string(), Value::Bool(false));
+        }
+    }
+
+    let mut builder = Request::builder()
+        .method(Method::POST)
+        .uri(&item.endpoint)
+        .header(header::CONTENT_TYPE, "application/json");
+    if let Some(credential) = credential {
+        builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", credential));
+    }
+    if let Some(ip) = &item.client_ip {
+        builder = builder.header("x-forwarded-for", ip);
+    }
+    let mut request = match builder.body(Body::from(body.to_string())) {
+        Ok(r) => r,
+        Err(e) => return Outcome::Failed(format!("Failed to build request: {}", e)),
+    };
+    request.extensions_mut().insert(BatchDispatch);
+
+    let response = match dispatch.oneshot(request).await {
+        Ok(r) => r,
+        Err(e) => match e {},
+    };
+    let status = response.status();
+    let retry_after = response
+        .headers()
+        .get(header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|s| *s >= 0);
+    let bytes = match axum::body::to_bytes(response.into_body(), usize::MAX).await {
+        Ok(b) => b,
+        Err(e) => return Outcome::Failed(format!("Failed to read response: {}", e)),
+    };
+    let body = serde_json::from_slice::<Value>(&bytes)
+        .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned()));
+
+    Outcome::Response { status, retry_after, body }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 429 | 503 | 529)
+}
+
+/// 第 n 次执行失败后的退避：30s, 60s, 120s ... 最多 10 分钟
+fn backoff_secs(attempts: u32) -> i64 {
+    let exp = attempts.saturating_sub(1).min(10);
+    (RETRY_BASE_SECS << exp).min(RETRY_MAX_SECS)
+}
+
+/// 提取错误描述 (兼容 Anthropic / OpenAI 错误结构)
+fn error_message(status: StatusCode, body: &Value) -> String {
+    body.get("error")
+        .and_then(|e| e.get("message").and_then(|m| m.as_str()).or_else(|| e.as_str()))
+        .or_else(|| body.as_str().filter(|s| !s.is_empty()))
+        .map(|s| s.to_string())
+        .unwrap_or_else(|| format!("HTTP {}", status.as_u16()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        assert_eq!(backoff_secs(1), 30);
+        assert_eq!(backoff_secs(2), 60);
+        assert_eq!(backoff_secs(3), 120);
+        assert_eq!(backoff_secs(6), 600);
+        assert_eq!(backoff_secs(40), 600);
+    }
+
+    #[test]
+    fn test_error_message_formats() {
+        let anthropic = json!({"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}});
+        assert_eq!(error_message(StatusCode::from_u16(529).unwrap(), &anthropic), "Overloaded");
+        let plain = json!("upstream timeout");
+        assert_eq!(error_message(StatusCode::BAD_GATEWAY, &plain), "upstream timeout");
+        assert_eq!(error_message(StatusCode::UNAUTHORIZED, &json!("")), "HTTP 401");
+    }
+
+    #[test]
+    fn test_batch_management_paths() {
+        assert!(is_batch_management_path("/v1/messages/batches"));
+        assert!(is_batch_management_path("/v1/batches/batch_1/output"));
+        assert!(!is_batch_management_path("/v1/messages"));
+    }
+}
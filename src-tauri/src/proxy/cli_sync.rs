@@ -24,6 +24,13 @@ pub struct CliConfigFile {
     pub path: PathBuf,
 }
 
+impl CliConfigFile {
+    /// 同步前自动创建的备份文件: original_name + .antigravity.bak
+    pub fn backup_path(&self) -> PathBuf {
+        self.path.with_file_name(format!("{}.antigravity.bak", self.name))
+    }
+}
+
 impl CliApp {
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -209,7 +216,7 @@ pub fn get_sync_status(app: &CliApp, proxy_url: &str) -> (bool, bool, Option<Str
 
     for file in &files {
         // 使用更简单的命名规则: original_name + .antigravity.bak
-        let backup_path = file.path.with_file_name(format!("{}.antigravity.bak", file.name));
+        let backup_path = file.backup_path();
         
         if backup_path.exists() {
             has_backup = true;
@@ -326,7 +333,7 @@ pub fn sync_config(app: &CliApp, proxy_url: &str, api_key: &str, model: Option<&
         // [New Feature] 自动备份：如果文件存在且没有备份，创建 .antigravity.bak 备份
         // 这样可以保留用户最初的配置，后续多次同步不会覆盖这个备份
         if file.path.exists() {
-            let backup_path = file.path.with_file_name(format!("{}.antigravity.bak", file.name));
+            let backup_path = file.backup_path();
             if !backup_path.exists() {
                 if let Err(e) = fs::copy(&file.path, &backup_path) {
                     tracing::warn!("Failed to create backup for {}: {}", file.name, e);
@@ -516,7 +523,7 @@ pub async fn execute_cli_restore(app_type: CliApp) -> Result<(), String> {
 
     // 尝试从备份恢复
     for file in &files {
-        let backup_path = file.path.with_file_name(format!("{}.antigravity.bak", file.name));
+        let backup_path = file.backup_path();
         if backup_path.exists() {
             // 还原：覆盖原文件
             if let Err(e) = fs::rename(&backup_path, &file.path) {
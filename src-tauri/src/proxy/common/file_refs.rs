@@ -0,0 +1,269 @@
+//! Files API 引用展开
+//!
+//! 上游 (Gemini / 自定义提供商) 无法识别本地 file_id，handler 入口处把引用替换为内联数据：
+//! - Claude: image / document 块的 `source: { type: "file", file_id }` → `{ type: "base64", media_type, data }`
+//! - OpenAI Chat: `{ type: "file", file: { file_id } }` → `file.file_data` (data URL)
+//! - OpenAI Responses: `input_file.file_id` → `file_data`，`input_image.file_id` → `image_url`
+//!
+//! 找不到的 file_id 返回 400，避免请求带着无效引用进入转换流程后变成 500。
+
+use axum::http::StatusCode;
+use base64::Engine as _;
+use serde_json::{Map, Value};
+
+use crate::modules::file_db::{self, StoredFile};
+
+/// 已加载的文件 (元数据 + 内容)
+pub type LoadedFile = (StoredFile, Vec<u8>);
+
+pub type ResolveError = (StatusCode, String);
+
+/// 从本地存储加载文件；用户令牌只能引用自己上传的文件
+fn load_from_store(file_id: &str, owner: Option<&str>) -> Result<LoadedFile, ResolveError> {
+    file_db::load_file(file_id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .filter(|(file, _)| owner.is_none() || file.owner_token_id.as_deref() == owner)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("File '{}' not found", file_id)))
+}
+
+/// 深度优先访问所有 JSON 对象
+fn visit_objects<F>(value: &mut Value, f: &mut F) -> Result<(), ResolveError>
+where
+    F: FnMut(&mut Map<String, Value>) -> Result<(), ResolveError>,
+{
+    match value {
+        Value::Object(map) => {
+            f(map)?;
+            for child in map.values_mut() {
+                visit_objects(child, f)?;
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                visit_objects(item, f)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn to_data_url(mime_type: &str, bytes: &[u8]) -> String {
+    format!(
+        "data:{};base64,{}",
+        mime_type,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    )
+}
+
+/// 拆分 OpenAI `file_data` (data URL 或裸 base64)，返回 (mime_type, base64)
+///
+/// 裸 base64 按文件名推断类型，无法推断时按 PDF 处理 (OpenAI 文件输入的主要场景)
+pub fn split_file_data(file_data: &str, filename: Option<&str>) -> Option<(String, String)> {
+    if let Some(rest) = file_data.strip_prefix("data:") {
+        let (meta, data) = rest.split_once(',')?;
+        let mime_type = meta.split(';').next().filter(|m| !m.is_empty()).unwrap_or("application/pdf");
+        return Some((mime_type.to_string(), data.to_string()));
+    }
+    if file_data.is_empty() {
+        return None;
+    }
+    let mime_type = match filename.map(crate::proxy::handlers::files::guess_mime_type) {
+        Some(m) if m != "application/octet-stream" => m,
+        _ => "application/pdf",
+    };
+    Some((mime_type.to_string(), file_data.to_string()))
+}
+
+fn resolve_claude_with<L>(body: &mut Value, load: &mut L) -> Result<usize, ResolveError>
+where
+    L: FnMut(&str) -> Result<LoadedFile, ResolveError>,
+{
+    let Some(messages) = body.get_mut("messages") else {
+        return Ok(0);
+    };
+    let mut resolved = 0;
+    visit_objects(messages, &mut |block| {
+        let Some(source) = block.get_mut("source").and_then(|s| s.as_object_mut()) else {
+            return Ok(());
+        };
+        if source.get("type").and_then(|t| t.as_str()) != Some("file") {
+            return Ok(());
+        }
+        let file_id = source
+            .get("file_id")
+            .and_then(|f| f.as_str())
+            .ok_or_else(|| (StatusCode::BAD_REQUEST, "source.file_id: field required".to_string()))?
+            .to_string();
+        let (file, bytes) = load(&file_id)?;
+        source.clear();
+        source.insert("type".to_string(), Value::from("base64"));
+        source.insert("media_type".to_string(), Value::from(file.mime_type));
+        source.insert(
+            "data".to_string(),
+            Value::from(base64::engine::general_purpose::STANDARD.encode(&bytes)),
+        );
+        resolved += 1;
+        Ok(())
+    })?;
+    Ok(resolved)
+}
+
+fn resolve_openai_with<L>(body: &mut Value, load: &mut L) -> Result<usize, ResolveError>
+where
+    L: FnMut(&str) -> Result<LoadedFile, ResolveError>,
+{
+    let mut resolved = 0;
+    let mut resolve_part = |part: &mut Map<String, Value>| -> Result<(), ResolveError> {
+        let part_type = part.get("type").and_then(|t| t.as_str()).unwrap_or_default().to_string();
+        match part_type.as_str() {
+            // Chat: { type: "file", file: { file_id } }
+            "file" => {
+                let Some(file_obj) = part.get_mut("file").and_then(|f| f.as_object_mut()) else {
+                    return Ok(());
+                };
+                let Some(file_id) = file_obj.get("file_id").and_then(|f| f.as_str()).map(|s| s.to_string()) else {
+                    return Ok(());
+                };
+                let (file, bytes) = load(&file_id)?;
+                file_obj.remove("file_id");
+                file_obj.insert("file_data".to_string(), Value::from(to_data_url(&file.mime_type, &bytes)));
+                file_obj.entry("filename").or_insert_with(|| Value::from(file.filename));
+            }
+            // Responses: { type: "input_file", file_id }
+            "input_file" => {
+                let Some(file_id) = part.get("file_id").and_then(|f| f.as_str()).map(|s| s.to_string()) else {
+                    return Ok(());
+                };
+                let (file, bytes) = load(&file_id)?;
+                part.remove("file_id");
+                part.insert("file_data".to_string(), Value::from(to_data_url(&file.mime_type, &bytes)));
+                part.entry("filename").or_insert_with(|| Value::from(file.filename));
+            }
+            // Responses: { type: "input_image", file_id }
+            "input_image" => {
+                let Some(file_id) = part.get("file_id").and_then(|f| f.as_str()).map(|s| s.to_string()) else {
+                    return Ok(());
+                };
+                let (file, bytes) = load(&file_id)?;
+                part.remove("file_id");
+                part.insert("image_url".to_string(), Value::from(to_data_url(&file.mime_type, &bytes)));
+            }
+            _ => return Ok(()),
+        }
+        resolved += 1;
+        Ok(())
+    };
+
+    for key in ["messages", "input"] {
+        if let Some(value) = body.get_mut(key) {
+            visit_objects(value, &mut resolve_part)?;
+        }
+    }
+    Ok(resolved)
+}
+
+/// 展开 Claude Messages 请求中的 file_id 引用
+pub fn resolve_claude_file_refs(body: &mut Value, owner: Option<&str>) -> Result<(), ResolveError> {
+    let resolved = resolve_claude_with(body, &mut |id: &str| load_from_store(id, owner))?;
+    if resolved > 0 {
+        tracing::debug!("[Files] Inlined {} file reference(s) in Claude request", resolved);
+    }
+    Ok(())
+}
+
+/// 展开 OpenAI Chat / Completions / Responses 请求中的 file_id 引用
+pub fn resolve_openai_file_refs(body: &mut Value, owner: Option<&str>) -> Result<(), ResolveError> {
+    let resolved = resolve_openai_with(body, &mut |id: &str| load_from_store(id, owner))?;
+    if resolved > 0 {
+        tracing::debug!("[Files] Inlined {} file reference(s) in OpenAI request", resolved);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fake_loader(id: &str) -> Result<LoadedFile, ResolveError> {
+        if id != "file_1" {
+            return Err((StatusCode::BAD_REQUEST, format!("File '{}' not found", id)));
+        }
+        let file = StoredFile {
+            id: id.to_string(),
+            filename: "a.pdf".to_string(),
+            mime_type: "application/pdf".to_string(),
+            size_bytes: 3,
+            sha256: String::new(),
+            purpose: None,
+            owner_token_id: None,
+            created_at: 0,
+            expires_at: None,
+        };
+        Ok((file, b"pdf".to_vec()))
+    }
+
+    #[test]
+    fn test_claude_file_sources_become_base64() {
+        let mut body = json!({
+            "messages": [{
+                "role": "user",
+                "content": [
+                    {"type": "document", "source": {"type": "file", "file_id": "file_1"}},
+                    {"type": "tool_result", "tool_use_id": "t", "content": [
+                        {"type": "image", "source": {"type": "file", "file_id": "file_1"}}
+                    ]},
+                    {"type": "image", "source": {"type": "url", "url": "https://example.com/a.png"}}
+                ]
+            }]
+        });
+        assert_eq!(resolve_claude_with(&mut body, &mut fake_loader).unwrap(), 2);
+        let source = &body["messages"][0]["content"][0]["source"];
+        assert_eq!(source, &json!({"type": "base64", "media_type": "application/pdf", "data": "cGRm"}));
+        assert_eq!(body["messages"][0]["content"][1]["content"][0]["source"]["type"], "base64");
+        assert_eq!(body["messages"][0]["content"][2]["source"]["type"], "url");
+
+        let mut missing = json!({"messages": [{"role": "user", "content": [
+            {"type": "document", "source": {"type": "file", "file_id": "file_x"}}
+        ]}]});
+        assert_eq!(resolve_claude_with(&mut missing, &mut fake_loader).unwrap_err().0, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_openai_chat_and_responses_parts() {
+        let mut chat = json!({"messages": [{"role": "user", "content": [
+            {"type": "file", "file": {"file_id": "file_1"}},
+            {"type": "text", "text": "summarize"}
+        ]}]});
+        assert_eq!(resolve_openai_with(&mut chat, &mut fake_loader).unwrap(), 1);
+        let file = &chat["messages"][0]["content"][0]["file"];
+        assert_eq!(file["file_data"], "data:application/pdf;base64,cGRm");
+        assert_eq!(file["filename"], "a.pdf");
+        assert!(file.get("file_id").is_none());
+
+        let mut responses = json!({"input": [{"role": "user", "content": [
+            {"type": "input_file", "file_id": "file_1", "filename": "renamed.pdf"},
+            {"type": "input_image", "file_id": "file_1"}
+        ]}]});
+        assert_eq!(resolve_openai_with(&mut responses, &mut fake_loader).unwrap(), 2);
+        let content = &responses["input"][0]["content"];
+        assert_eq!(content[0]["filename"], "renamed.pdf");
+        assert_eq!(content[0]["file_data"], "data:application/pdf;base64,cGRm");
+        assert_eq!(content[1]["image_url"], "data:application/pdf;base64,cGRm");
+    }
+
+    #[test]
+    fn test_split_file_data() {
+        assert_eq!(
+            split_file_data("data:text/plain;base64,aGk=", None),
+            Some(("text/plain".to_string(), "aGk=".to_string()))
+        );
+        assert_eq!(
+            split_file_data("aGk=", Some("notes.txt")),
+            Some(("text/plain".to_string(), "aGk=".to_string()))
+        );
+        assert_eq!(split_file_data("aGk=", None).unwrap().0, "application/pdf");
+        assert_eq!(split_file_data("", None), None);
+    }
+}
@@ -3,6 +3,7 @@
 // pub mod error;
 // pub mod rate_limiter;
 pub mod model_mapping;
+pub mod routing_rules;
 pub mod utils;
 pub mod json_schema;
 pub mod tool_adapter;
@@ -10,3 +11,5 @@ pub mod tool_adapters;
 pub mod schema_cache;
 pub mod client_adapter;
 pub mod client_adapters;
+pub mod structured_output;
+pub mod file_refs;
@@ -117,7 +117,7 @@ pub fn get_supported_models() -> Vec<String> {
 
 /// 动态获取所有可用模型列表 (包含内置与用户自定义)
 pub async fn get_all_dynamic_models(
-    custom_mapping: &tokio::sync::RwLock<std::collections::HashMap<String, String>>,
+    router: &tokio::sync::RwLock<super::routing_rules::ModelRouter>,
 ) -> Vec<String> {
     use std::collections::HashSet;
     let mut model_ids = HashSet::new();
@@ -127,14 +127,19 @@ pub async fn get_all_dynamic_models(
         model_ids.insert(m);
     }
 
-    // 2. 获取所有自定义映射模型 (Custom)
+    // 2. 获取所有自定义路由规则中的具体模型名 (Custom)
     {
-        let mapping = custom_mapping.read().await;
-        for key in mapping.keys() {
-            model_ids.insert(key.clone());
+        let router = router.read().await;
+        for id in router.model_ids() {
+            model_ids.insert(id);
         }
     }
 
+    // 3. [NEW] 自定义上游声明的具体模型名
+    for id in crate::proxy::providers::registry::listed_model_ids() {
+        model_ids.insert(id);
+    }
+
     // 5. 确保包含常用的 Gemini/画画模型 ID
     model_ids.insert("gemini-3-pro-low".to_string());
     
@@ -174,7 +179,7 @@ pub async fn get_all_dynamic_models(
 /// - `claude-*-sonnet-*` matches `claude-3-5-sonnet-20241022` ✓
 /// - `*-thinking` matches `claude-opus-4-5-thinking` ✓
 /// - `a*b*c` matches `a123b456c` ✓
-fn wildcard_match(pattern: &str, text: &str) -> bool {
+pub(crate) fn wildcard_match(pattern: &str, text: &str) -> bool {
     let parts: Vec<&str> = pattern.split('*').collect();
 
     // No wildcard - exact match
@@ -212,53 +217,19 @@ fn wildcard_match(pattern: &str, text: &str) -> bool {
 }
 
 /// 核心模型路由解析引擎
-/// 优先级：精确匹配 > 通配符匹配 > 系统默认映射
-/// 
+/// 优先级：显式路由规则 (按顺序) > 旧版精确映射 > 旧版通配符映射 > 系统默认映射
+///
 /// # 参数
-/// - `original_model`: 原始模型名称
-/// - `custom_mapping`: 用户自定义映射表
-/// 
+/// - `ctx`: 请求的路由上下文 (转发请求使用 `RouteContext::from_request`，以匹配协议/身份条件)
+/// - `router`: 编译后的路由规则表
+///
 /// # 返回
 /// 映射后的目标模型名称
 pub fn resolve_model_route(
-    original_model: &str,
-    custom_mapping: &std::collections::HashMap<String, String>,
+    ctx: &super::routing_rules::RouteContext,
+    router: &super::routing_rules::ModelRouter,
 ) -> String {
-    // 1. 精确匹配 (最高优先级)
-    if let Some(target) = custom_mapping.get(original_model) {
-        crate::modules::logger::log_info(&format!("[Router] 精确映射: {} -> {}", original_model, target));
-        return target.clone();
-    }
-    
-    // 2. Wildcard match - most specific (highest non-wildcard chars) wins
-    // Note: When multiple patterns have the SAME specificity, HashMap iteration order
-    // determines the result (non-deterministic). Users can avoid this by making patterns
-    // more specific. Future improvement: use IndexMap + frontend sorting for full control.
-    let mut best_match: Option<(&str, &str, usize)> = None;
-
-    for (pattern, target) in custom_mapping.iter() {
-        if pattern.contains('*') && wildcard_match(pattern, original_model) {
-            let specificity = pattern.chars().count() - pattern.matches('*').count();
-            if best_match.is_none() || specificity > best_match.unwrap().2 {
-                best_match = Some((pattern.as_str(), target.as_str(), specificity));
-            }
-        }
-    }
-
-    if let Some((pattern, target, _)) = best_match {
-        crate::modules::logger::log_info(&format!(
-            "[Router] Wildcard match: {} -> {} (rule: {})",
-            original_model, target, pattern
-        ));
-        return target.to_string();
-    }
-    
-    // 3. 系统默认映射
-    let result = map_claude_model_to_gemini(original_model);
-    if result != original_model {
-        crate::modules::logger::log_info(&format!("[Router] 系统默认映射: {} -> {}", original_model, result));
-    }
-    result
+    router.resolve(ctx).mapped_model
 }
 
 /// Normalize any physical model name to one of the 3 standard protection IDs.
@@ -299,6 +270,7 @@ pub fn normalize_to_standard_id(model_name: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::proxy::common::routing_rules::RouteContext;
 
     #[test]
     fn test_model_mapping() {
@@ -352,6 +324,10 @@ mod tests {
         );
     }
 
+    fn router(custom: &HashMap<String, String>) -> crate::proxy::common::routing_rules::ModelRouter {
+        crate::proxy::common::routing_rules::ModelRouter::new(Vec::new(), custom)
+    }
+
     #[test]
     fn test_wildcard_priority() {
         let mut custom = HashMap::new();
@@ -361,11 +337,11 @@ mod tests {
         custom.insert("claude-opus*thinking".to_string(), "opus-thinking".to_string());
 
         // More specific pattern wins
-        assert_eq!(resolve_model_route("gpt-4-turbo", &custom), "specific");
-        assert_eq!(resolve_model_route("gpt-3.5", &custom), "fallback");
+        assert_eq!(resolve_model_route(&RouteContext::for_model("gpt-4-turbo"), &router(&custom)), "specific");
+        assert_eq!(resolve_model_route(&RouteContext::for_model("gpt-3.5"), &router(&custom)), "fallback");
         // Suffix constraint is more specific than prefix-only
-        assert_eq!(resolve_model_route("claude-opus-4-5-thinking", &custom), "opus-thinking");
-        assert_eq!(resolve_model_route("claude-opus-4", &custom), "opus-default");
+        assert_eq!(resolve_model_route(&RouteContext::for_model("claude-opus-4-5-thinking"), &router(&custom)), "opus-thinking");
+        assert_eq!(resolve_model_route(&RouteContext::for_model("claude-opus-4"), &router(&custom)), "opus-default");
     }
 
     #[test]
@@ -377,21 +353,21 @@ mod tests {
 
         // Multi-wildcard patterns should work
         assert_eq!(
-            resolve_model_route("claude-3-5-sonnet-20241022", &custom),
+            resolve_model_route(&RouteContext::for_model("claude-3-5-sonnet-20241022"), &router(&custom)),
             "sonnet-versioned"
         );
         assert_eq!(
-            resolve_model_route("gpt-4-turbo-preview", &custom),
+            resolve_model_route(&RouteContext::for_model("gpt-4-turbo-preview"), &router(&custom)),
             "gpt-multi"
         );
         assert_eq!(
-            resolve_model_route("claude-thinking-extended", &custom),
+            resolve_model_route(&RouteContext::for_model("claude-thinking-extended"), &router(&custom)),
             "has-thinking"
         );
 
         // Negative case: *thinking* should NOT match models without "thinking"
         assert_eq!(
-            resolve_model_route("random-model-name", &custom),
+            resolve_model_route(&RouteContext::for_model("random-model-name"), &router(&custom)),
             "random-model-name"  // Falls back to system default (pass-through)
         );
     }
@@ -404,10 +380,10 @@ mod tests {
         custom.insert("a*b*c".to_string(), "multi-wild".to_string());
 
         // Specificity: "prefix*" (6) > "*" (0)
-        assert_eq!(resolve_model_route("prefix-anything", &custom), "prefix-match");
+        assert_eq!(resolve_model_route(&RouteContext::for_model("prefix-anything"), &router(&custom)), "prefix-match");
         // Catch-all has lowest specificity
-        assert_eq!(resolve_model_route("random-model", &custom), "catch-all");
+        assert_eq!(resolve_model_route(&RouteContext::for_model("random-model"), &router(&custom)), "catch-all");
         // Multi-wildcard: "a*b*c" (3)
-        assert_eq!(resolve_model_route("a-test-b-foo-c", &custom), "multi-wild");
+        assert_eq!(resolve_model_route(&RouteContext::for_model("a-test-b-foo-c"), &router(&custom)), "multi-wild");
     }
 }
@@ -0,0 +1,827 @@
+// 模型路由规则引擎
+// 有序规则列表 (首个命中的规则生效)，兼容旧版 custom_mapping 映射表
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::model_mapping::{map_claude_model_to_gemini, wildcard_match};
+
+/// 一条模型路由规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelRoutingRule {
+    /// 规则 ID (用于日志与 explain 输出)
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 匹配条件 (全部满足才命中，未设置的条件视为通配)
+    #[serde(rename = "match", default)]
+    pub conditions: RuleConditions,
+    /// 命中后的路由目标
+    pub target: RouteTarget,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 规则匹配条件
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct RuleConditions {
+    /// 模型名通配符 (区分大小写，如 `claude-opus-*`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// 协议列表: anthropic / openai / gemini
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub protocols: Vec<String>,
+    /// 用户令牌 ID 列表
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub user_tokens: Vec<String>,
+    /// 用户令牌所属用户名列表
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub usernames: Vec<String>,
+    /// 客户端 User-Agent 通配符 (不区分大小写，如 `*claude-cli*`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_tools: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_images: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_thinking: Option<bool>,
+}
+
+/// 路由目标
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RouteTarget {
+    /// 映射到单个模型
+    Model { model: String },
+    /// 按顺序尝试的模型链 (首个为主模型)
+    Fallback { models: Vec<String> },
+    /// 转发到 z.ai (仅 Anthropic 协议生效)，可选覆盖模型名
+    Zai {
+        #[serde(default)]
+        model: Option<String>,
+    },
+    /// 转发到自定义上游 (仅与上游协议相同的请求生效)，可选覆盖模型名
+    Provider {
+        provider: String,
+        #[serde(default)]
+        model: Option<String>,
+    },
+}
+
+/// 用于规则匹配的请求上下文
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RouteContext {
+    pub model: String,
+    #[serde(default)]
+    pub protocol: Option<String>,
+    #[serde(default)]
+    pub user_token_id: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    #[serde(default)]
+    pub has_tools: bool,
+    #[serde(default)]
+    pub has_images: bool,
+    #[serde(default)]
+    pub has_thinking: bool,
+    /// z.ai 是否启用；未启用时 z.ai 目标的规则不参与匹配，避免绕过默认模型映射
+    #[serde(default)]
+    pub zai_enabled: bool,
+}
+
+impl RouteContext {
+    /// 仅按模型名匹配 (无协议/身份信息，如模型能力探测)
+    ///
+    /// 没有协议时自定义上游与 z.ai 目标的规则不参与匹配；转发请求的 handler 应使用 `from_request`。
+    pub fn for_model(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// 从原始请求体构建上下文 (兼容 Claude / OpenAI / Responses / Gemini 三种结构)
+    pub fn from_request(
+        model: &str,
+        protocol: &str,
+        body: &Value,
+        headers: &axum::http::HeaderMap,
+        identity: Option<&crate::proxy::middleware::auth::UserTokenIdentity>,
+    ) -> Self {
+        Self {
+            model: model.to_string(),
+            protocol: Some(protocol.to_string()),
+            user_token_id: identity.map(|i| i.token_id.clone()),
+            username: identity.map(|i| i.username.clone()),
+            user_agent: headers
+                .get(axum::http::header::USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .map(|s| s.to_string()),
+            ..Default::default()
+        }
+        .with_body_features(body)
+    }
+
+    /// 标记 z.ai 是否可用 (来自当前 z.ai 配置)
+    pub fn with_zai_enabled(mut self, enabled: bool) -> Self {
+        self.zai_enabled = enabled;
+        self
+    }
+
+    /// 从请求体检测 tools / 图片 / thinking 特征
+    pub fn with_body_features(mut self, body: &Value) -> Self {
+        self.has_tools = body_has_tools(body);
+        self.has_images = body_has_images(body);
+        self.has_thinking = body_has_thinking(body);
+        self
+    }
+}
+
+fn body_has_tools(body: &Value) -> bool {
+    body.get("tools")
+        .and_then(|t| t.as_array())
+        .map_or(false, |t| !t.is_empty())
+}
+
+fn body_has_thinking(body: &Value) -> bool {
+    // Claude: thinking.type = enabled / adaptive
+    if let Some(thinking) = body.get("thinking") {
+        let kind = thinking.get("type").and_then(|t| t.as_str()).unwrap_or("enabled");
+        return kind != "disabled";
+    }
+    // OpenAI Chat: reasoning_effort; Responses: reasoning.effort
+    if body.get("reasoning_effort").map_or(false, |v| !v.is_null())
+        || body.get("reasoning").map_or(false, |v| !v.is_null())
+    {
+        return true;
+    }
+    // Gemini: generationConfig.thinkingConfig
+    body.get("generationConfig")
+        .and_then(|c| c.get("thinkingConfig"))
+        .map_or(false, |tc| {
+            tc.get("includeThoughts").and_then(|v| v.as_bool()).unwrap_or(false)
+                || tc.get("thinkingBudget").and_then(|v| v.as_i64()).map_or(false, |b| b != 0)
+        })
+}
+
+fn body_has_images(body: &Value) -> bool {
+    ["messages", "input", "contents"]
+        .iter()
+        .filter_map(|key| body.get(*key))
+        .any(value_contains_image)
+}
+
+fn value_contains_image(value: &Value) -> bool {
+    match value {
+        Value::Array(items) => items.iter().any(value_contains_image),
+        Value::Object(obj) => {
+            if let Some(kind) = obj.get("type").and_then(|t| t.as_str()) {
+                if matches!(kind, "image" | "image_url" | "input_image") {
+                    return true;
+                }
+            }
+            for key in ["inlineData", "inline_data", "fileData", "file_data"] {
+                if let Some(data) = obj.get(key) {
+                    let mime = data
+                        .get("mimeType")
+                        .or_else(|| data.get("mime_type"))
+                        .and_then(|m| m.as_str())
+                        .unwrap_or("");
+                    if mime.starts_with("image/") {
+                        return true;
+                    }
+                }
+            }
+            obj.get("content")
+                .or_else(|| obj.get("parts"))
+                .map_or(false, value_contains_image)
+        }
+        _ => false,
+    }
+}
+
+/// 路由决策结果
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct RouteDecision {
+    /// 命中的规则 ID (None 表示走系统默认映射)
+    pub rule_id: Option<String>,
+    pub mapped_model: String,
+    /// 主模型不可用时依次尝试的后备模型
+    pub fallback_models: Vec<String>,
+    /// 是否转发到 z.ai
+    pub use_zai: bool,
+    /// 转发到的自定义上游 ID
+    pub provider: Option<String>,
+}
+
+/// explain 输出中单条规则的评估结果
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleEvaluation {
+    pub rule_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub enabled: bool,
+    pub matched: bool,
+    /// 未命中的原因 (首个不满足的条件)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// explain 输出
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteExplanation {
+    pub context: RouteContext,
+    pub decision: RouteDecision,
+    /// 按评估顺序列出的规则 (到首个命中规则为止)
+    pub evaluated: Vec<RuleEvaluation>,
+}
+
+/// 编译后的有序路由表
+#[derive(Debug, Clone, Default)]
+pub struct ModelRouter {
+    rules: Vec<ModelRoutingRule>,
+    /// 按目标模型配置的降级链 (精确优先，其次按通配符特异度)
+    fallback_chains: Vec<(String, Vec<String>)>,
+}
+
+impl ModelRouter {
+    /// 合并显式规则与旧版映射表
+    ///
+    /// 显式规则按配置顺序优先；旧版映射表追加在其后：
+    /// 精确匹配 (按 key 排序) > 通配符 (按非通配字符数降序，相同则按字典序)，
+    /// 保证同等特异度的通配符结果稳定。
+    pub fn new(rules: Vec<ModelRoutingRule>, legacy_mapping: &HashMap<String, String>) -> Self {
+        let mut exact: Vec<(&String, &String)> = legacy_mapping
+            .iter()
+            .filter(|(k, _)| !k.contains('*'))
+            .collect();
+        exact.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut wildcards: Vec<(&String, &String)> = legacy_mapping
+            .iter()
+            .filter(|(k, _)| k.contains('*'))
+            .collect();
+        wildcards.sort_by(|a, b| {
+            pattern_specificity(b.0)
+                .cmp(&pattern_specificity(a.0))
+                .then_with(|| a.0.cmp(b.0))
+        });
+
+        let mut all = rules;
+        all.extend(exact.into_iter().chain(wildcards).map(|(pattern, target)| {
+            ModelRoutingRule {
+                id: format!("legacy:{}", pattern),
+                name: None,
+                enabled: true,
+                conditions: RuleConditions {
+                    model: Some(pattern.clone()),
+                    ..Default::default()
+                },
+                target: RouteTarget::Model {
+                    model: target.clone(),
+                },
+            }
+        }));
+
+        Self {
+            rules: all,
+            fallback_chains: Vec::new(),
+        }
+    }
+
+    /// 设置按模型的降级链 (key 为映射后的模型名，支持通配符)
+    pub fn with_fallback_chains(mut self, chains: &HashMap<String, Vec<String>>) -> Self {
+        let mut sorted: Vec<(String, Vec<String>)> = chains
+            .iter()
+            .filter(|(_, models)| !models.is_empty())
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        sorted.sort_by(|a, b| {
+            a.0.contains('*')
+                .cmp(&b.0.contains('*'))
+                .then_with(|| pattern_specificity(&b.0).cmp(&pattern_specificity(&a.0)))
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        self.fallback_chains = sorted;
+        self
+    }
+
+    pub fn from_config(config: &crate::proxy::config::ProxyConfig) -> Self {
+        Self::new(config.model_routing_rules.clone(), &config.custom_mapping)
+            .with_fallback_chains(&config.model_fallback_chains)
+    }
+
+    /// 查找目标模型的降级链 (不含目标模型自身)
+    pub fn fallback_chain_for(&self, mapped_model: &str) -> Vec<String> {
+        self.fallback_chains
+            .iter()
+            .find(|(pattern, _)| wildcard_match(pattern, mapped_model))
+            .map(|(_, models)| {
+                models
+                    .iter()
+                    .filter(|m| m.as_str() != mapped_model)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// 规则未显式给出降级链时，补充按模型配置的降级链
+    fn with_chain(&self, mut decision: RouteDecision) -> RouteDecision {
+        if !decision.use_zai && decision.provider.is_none() && decision.fallback_models.is_empty() {
+            decision.fallback_models = self.fallback_chain_for(&decision.mapped_model);
+        }
+        decision
+    }
+
+    pub fn rules(&self) -> &[ModelRoutingRule] {
+        &self.rules
+    }
+
+    /// 解析路由：返回首个命中规则的目标，未命中时使用系统默认映射
+    pub fn resolve(&self, ctx: &RouteContext) -> RouteDecision {
+        for rule in self.rules.iter().filter(|r| r.enabled) {
+            if check_rule(rule, ctx).is_ok() {
+                let decision = self.with_chain(build_decision(rule, ctx));
+                crate::modules::logger::log_info(&format!(
+                    "[Router] {} -> {} (rule: {})",
+                    ctx.model, decision.mapped_model, rule.id
+                ));
+                return decision;
+            }
+        }
+
+        let decision = self.with_chain(default_decision(ctx));
+        if decision.mapped_model != ctx.model {
+            crate::modules::logger::log_info(&format!(
+                "[Router] 系统默认映射: {} -> {}",
+                ctx.model, decision.mapped_model
+            ));
+        }
+        decision
+    }
+
+    /// 解释路由过程 (供管理接口调试规则)
+    pub fn explain(&self, ctx: &RouteContext) -> RouteExplanation {
+        let mut evaluated = Vec::new();
+        let mut decision = None;
+
+        for rule in &self.rules {
+            let result = if rule.enabled {
+                check_rule(rule, ctx)
+            } else {
+                Err("rule disabled".to_string())
+            };
+            let matched = result.is_ok();
+            evaluated.push(RuleEvaluation {
+                rule_id: rule.id.clone(),
+                name: rule.name.clone(),
+                enabled: rule.enabled,
+                matched,
+                reason: result.err(),
+            });
+            if matched {
+                decision = Some(build_decision(rule, ctx));
+                break;
+            }
+        }
+
+        RouteExplanation {
+            context: ctx.clone(),
+            decision: self.with_chain(decision.unwrap_or_else(|| default_decision(ctx))),
+            evaluated,
+        }
+    }
+
+    /// 规则中出现的具体模型名 (非通配符)，用于 /v1/models 列表
+    pub fn model_ids(&self) -> Vec<String> {
+        self.rules
+            .iter()
+            .filter(|r| r.enabled)
+            .filter_map(|r| r.conditions.model.as_ref())
+            .filter(|m| !m.contains('*'))
+            .cloned()
+            .collect()
+    }
+}
+
+fn pattern_specificity(pattern: &str) -> usize {
+    pattern.chars().count() - pattern.matches('*').count()
+}
+
+fn build_decision(rule: &ModelRoutingRule, ctx: &RouteContext) -> RouteDecision {
+    let (mapped_model, fallback_models, use_zai, provider) = match &rule.target {
+        RouteTarget::Model { model } => (model.clone(), Vec::new(), false, None),
+        RouteTarget::Fallback { models } => match models.split_first() {
+            Some((first, rest)) => (first.clone(), rest.to_vec(), false, None),
+            None => (map_claude_model_to_gemini(&ctx.model), Vec::new(), false, None),
+        },
+        // 未指定模型时原样转发，由 z.ai 自身的模型映射处理
+        RouteTarget::Zai { model } => (
+            model.clone().unwrap_or_else(|| ctx.model.clone()),
+            Vec::new(),
+            true,
+            None,
+        ),
+        // 未指定模型时原样转发，由上游的 model_mapping 处理
+        RouteTarget::Provider { provider, model } => (
+            model.clone().unwrap_or_else(|| ctx.model.clone()),
+            Vec::new(),
+            false,
+            Some(provider.clone()),
+        ),
+    };
+    RouteDecision {
+        rule_id: Some(rule.id.clone()),
+        mapped_model,
+        fallback_models,
+        use_zai,
+        provider,
+    }
+}
+
+fn default_decision(ctx: &RouteContext) -> RouteDecision {
+    RouteDecision {
+        rule_id: None,
+        mapped_model: map_claude_model_to_gemini(&ctx.model),
+        fallback_models: Vec::new(),
+        use_zai: false,
+        provider: None,
+    }
+}
+
+fn check_rule(rule: &ModelRoutingRule, ctx: &RouteContext) -> Result<(), String> {
+    // z.ai 是 Anthropic 兼容上游，其他协议的请求跳过该类规则
+    if let RouteTarget::Zai { .. } = rule.target {
+        if !ctx.zai_enabled {
+            return Err("z.ai is disabled".to_string());
+        }
+        if ctx.protocol.as_deref() != Some("anthropic") {
+            return Err("z.ai target only applies to anthropic protocol".to_string());
+        }
+    }
+    // 自定义上游需存在且已启用，且只接收与其协议相同的请求
+    if let RouteTarget::Provider { provider, .. } = &rule.target {
+        let providers = crate::proxy::config::get_providers_config();
+        let Some(config) = providers.iter().find(|p| &p.id == provider && p.enabled) else {
+            return Err(format!("provider '{}' is not configured or disabled", provider));
+        };
+        if ctx.protocol.as_deref() != Some(config.protocol.as_str()) {
+            return Err(format!(
+                "provider '{}' only serves {} protocol",
+                provider,
+                config.protocol.as_str()
+            ));
+        }
+    }
+    check_conditions(&rule.conditions, ctx)
+}
+
+/// 检查规则条件，返回首个不满足的条件描述
+fn check_conditions(cond: &RuleConditions, ctx: &RouteContext) -> Result<(), String> {
+    if let Some(pattern) = &cond.model {
+        if !wildcard_match(pattern, &ctx.model) {
+            return Err(format!("model '{}' does not match '{}'", ctx.model, pattern));
+        }
+    }
+
+    if !cond.protocols.is_empty() {
+        let protocol = ctx.protocol.as_deref().unwrap_or("");
+        if !cond.protocols.iter().any(|p| p.eq_ignore_ascii_case(protocol)) {
+            return Err(format!("protocol '{}' not in {:?}", protocol, cond.protocols));
+        }
+    }
+
+    if !cond.user_tokens.is_empty() {
+        let token = ctx.user_token_id.as_deref().unwrap_or("");
+        if !cond.user_tokens.iter().any(|t| t == token) {
+            return Err("user token not in rule".to_string());
+        }
+    }
+
+    if !cond.usernames.is_empty() {
+        let username = ctx.username.as_deref().unwrap_or("");
+        if !cond.usernames.iter().any(|u| u == username) {
+            return Err(format!("username '{}' not in {:?}", username, cond.usernames));
+        }
+    }
+
+    if let Some(pattern) = &cond.user_agent {
+        let ua = ctx.user_agent.as_deref().unwrap_or("").to_lowercase();
+        if !wildcard_match(&pattern.to_lowercase(), &ua) {
+            return Err(format!("user agent does not match '{}'", pattern));
+        }
+    }
+
+    for (name, expected, actual) in [
+        ("has_tools", cond.has_tools, ctx.has_tools),
+        ("has_images", cond.has_images, ctx.has_images),
+        ("has_thinking", cond.has_thinking, ctx.has_thinking),
+    ] {
+        if let Some(expected) = expected {
+            if expected != actual {
+                return Err(format!("{} is {}, rule requires {}", name, actual, expected));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn rule(id: &str, conditions: RuleConditions, target: RouteTarget) -> ModelRoutingRule {
+        ModelRoutingRule {
+            id: id.to_string(),
+            name: None,
+            enabled: true,
+            conditions,
+            target,
+        }
+    }
+
+    fn to_model(model: &str) -> RouteTarget {
+        RouteTarget::Model {
+            model: model.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rules_take_precedence_over_legacy_mapping() {
+        let mut legacy = HashMap::new();
+        legacy.insert("claude-opus-4".to_string(), "legacy-target".to_string());
+
+        let router = ModelRouter::new(
+            vec![rule(
+                "opus-tools",
+                RuleConditions {
+                    model: Some("claude-opus-*".to_string()),
+                    has_tools: Some(true),
+                    ..Default::default()
+                },
+                to_model("gemini-3-pro-high"),
+            )],
+            &legacy,
+        );
+
+        let mut ctx = RouteContext::for_model("claude-opus-4");
+        assert_eq!(router.resolve(&ctx).mapped_model, "legacy-target");
+
+        ctx.has_tools = true;
+        let decision = router.resolve(&ctx);
+        assert_eq!(decision.mapped_model, "gemini-3-pro-high");
+        assert_eq!(decision.rule_id.as_deref(), Some("opus-tools"));
+    }
+
+    #[test]
+    fn test_equal_specificity_wildcards_are_deterministic() {
+        let mut legacy = HashMap::new();
+        legacy.insert("gpt-*".to_string(), "first".to_string());
+        legacy.insert("*-4o".to_string(), "second".to_string());
+
+        // 两者特异度均为 4，按字典序 "*-4o" < "gpt-*"
+        let router = ModelRouter::new(Vec::new(), &legacy);
+        for _ in 0..10 {
+            assert_eq!(router.resolve(&RouteContext::for_model("gpt-4o")).mapped_model, "second");
+        }
+    }
+
+    #[test]
+    fn test_protocol_identity_and_user_agent_conditions() {
+        let router = ModelRouter::new(
+            vec![
+                rule(
+                    "cli-zai",
+                    RuleConditions {
+                        protocols: vec!["anthropic".to_string()],
+                        user_agent: Some("*Claude-CLI*".to_string()),
+                        usernames: vec!["alice".to_string()],
+                        ..Default::default()
+                    },
+                    RouteTarget::Zai { model: None },
+                ),
+                rule(
+                    "chain",
+                    RuleConditions {
+                        model: Some("gpt-*".to_string()),
+                        ..Default::default()
+                    },
+                    RouteTarget::Fallback {
+                        models: vec!["gemini-3-pro-high".to_string(), "gemini-3-flash".to_string()],
+                    },
+                ),
+            ],
+            &HashMap::new(),
+        );
+
+        let ctx = RouteContext {
+            model: "claude-sonnet-4-5".to_string(),
+            protocol: Some("anthropic".to_string()),
+            username: Some("alice".to_string()),
+            user_agent: Some("claude-cli/2.0.1 (external)".to_string()),
+            zai_enabled: true,
+            ..Default::default()
+        };
+        let decision = router.resolve(&ctx);
+        assert!(decision.use_zai);
+        assert_eq!(decision.mapped_model, "claude-sonnet-4-5");
+
+        // z.ai 未启用: 规则被跳过，走默认映射而不是原样透传客户端模型名
+        let zai_off = RouteContext {
+            zai_enabled: false,
+            ..ctx.clone()
+        };
+        let decision = router.resolve(&zai_off);
+        assert!(!decision.use_zai);
+        let no_protocol = RouteContext {
+            protocol: None,
+            ..ctx.clone()
+        };
+        assert!(!router.resolve(&no_protocol).use_zai);
+        assert_eq!(decision.rule_id, None);
+        assert_eq!(decision.mapped_model, map_claude_model_to_gemini("claude-sonnet-4-5"));
+        let explanation = router.explain(&zai_off);
+        assert!(explanation
+            .evaluated
+            .iter()
+            .any(|e| e.reason.as_deref() == Some("z.ai is disabled")));
+
+        let openai = RouteContext {
+            protocol: Some("openai".to_string()),
+            ..ctx.clone()
+        };
+        assert!(!router.resolve(&openai).use_zai);
+
+        let other_user = RouteContext {
+            username: Some("bob".to_string()),
+            ..ctx.clone()
+        };
+        assert!(!router.resolve(&other_user).use_zai);
+
+        let decision = router.resolve(&RouteContext::for_model("gpt-4o"));
+        assert_eq!(decision.mapped_model, "gemini-3-pro-high");
+        assert_eq!(decision.fallback_models, vec!["gemini-3-flash".to_string()]);
+    }
+
+    #[test]
+    fn test_fallback_chains_fill_decision() {
+        let mut chains = HashMap::new();
+        chains.insert(
+            "gemini-3-pro-*".to_string(),
+            vec!["gemini-3-pro-high".to_string(), "gemini-3-flash".to_string()],
+        );
+        chains.insert("gemini-3-pro-high".to_string(), vec!["gemini-2.5-flash".to_string()]);
+        let router = ModelRouter::new(
+            vec![rule(
+                "explicit",
+                RuleConditions {
+                    model: Some("gpt-*".to_string()),
+                    ..Default::default()
+                },
+                RouteTarget::Fallback {
+                    models: vec!["gemini-3-pro-low".to_string(), "gemini-3-flash".to_string()],
+                },
+            )],
+            &HashMap::new(),
+        )
+        .with_fallback_chains(&chains);
+
+        // 精确 key 优先于通配符
+        let decision = router.resolve(&RouteContext::for_model("gemini-3-pro-high"));
+        assert_eq!(decision.fallback_models, vec!["gemini-2.5-flash".to_string()]);
+
+        // 通配符命中时排除目标模型自身
+        let decision = router.resolve(&RouteContext::for_model("gemini-3-pro-low"));
+        assert_eq!(
+            decision.fallback_models,
+            vec!["gemini-3-pro-high".to_string(), "gemini-3-flash".to_string()]
+        );
+
+        // 规则自带的降级链不被覆盖
+        let decision = router.resolve(&RouteContext::for_model("gpt-4o"));
+        assert_eq!(decision.fallback_models, vec!["gemini-3-flash".to_string()]);
+    }
+
+    #[test]
+    fn test_explain_reports_failed_conditions() {
+        let mut disabled = rule("off", RuleConditions::default(), to_model("x"));
+        disabled.enabled = false;
+        let router = ModelRouter::new(
+            vec![
+                disabled,
+                rule(
+                    "images",
+                    RuleConditions {
+                        has_images: Some(true),
+                        ..Default::default()
+                    },
+                    to_model("gemini-3-pro-image"),
+                ),
+            ],
+            &HashMap::new(),
+        );
+
+        let explanation = router.explain(&RouteContext::for_model("claude-sonnet-4-5"));
+        assert_eq!(explanation.evaluated.len(), 2);
+        assert_eq!(explanation.evaluated[0].reason.as_deref(), Some("rule disabled"));
+        assert!(!explanation.evaluated[1].matched);
+        assert_eq!(explanation.decision.rule_id, None);
+    }
+
+    #[test]
+    fn test_provider_target_requires_matching_protocol() {
+        let mut providers: Vec<_> = crate::proxy::config::get_providers_config()
+            .into_iter()
+            .filter(|p| p.id != "rt-gateway")
+            .collect();
+        providers.push(
+            serde_json::from_value(json!({
+                "id": "rt-gateway",
+                "protocol": "openai",
+                "base_url": "http://127.0.0.1:8000"
+            }))
+            .unwrap(),
+        );
+        crate::proxy::config::update_providers_config(providers);
+
+        let router = ModelRouter::new(
+            vec![
+                rule(
+                    "to-gateway",
+                    RuleConditions {
+                        model: Some("qwen-*".to_string()),
+                        ..Default::default()
+                    },
+                    RouteTarget::Provider {
+                        provider: "rt-gateway".to_string(),
+                        model: Some("Qwen/Qwen2.5-72B".to_string()),
+                    },
+                ),
+                rule(
+                    "missing",
+                    RuleConditions::default(),
+                    RouteTarget::Provider {
+                        provider: "rt-missing".to_string(),
+                        model: None,
+                    },
+                ),
+            ],
+            &HashMap::new(),
+        );
+
+        let mut ctx = RouteContext::for_model("qwen-max");
+        ctx.protocol = Some("openai".to_string());
+        let decision = router.resolve(&ctx);
+        assert_eq!(decision.provider.as_deref(), Some("rt-gateway"));
+        assert_eq!(decision.mapped_model, "Qwen/Qwen2.5-72B");
+        assert!(decision.fallback_models.is_empty());
+
+        // 协议不符或上游不存在时跳过规则
+        ctx.protocol = Some("anthropic".to_string());
+        let explanation = router.explain(&ctx);
+        assert_eq!(explanation.decision.provider, None);
+        assert!(explanation.evaluated.iter().all(|e| !e.matched));
+
+        // 未知协议同样不匹配
+        let explanation = router.explain(&RouteContext::for_model("qwen-max"));
+        assert_eq!(explanation.decision.provider, None);
+        assert_eq!(explanation.decision.mapped_model, map_claude_model_to_gemini("qwen-max"));
+        assert!(explanation.evaluated.iter().all(|e| !e.matched));
+    }
+
+    #[test]
+    fn test_context_feature_detection() {
+        let headers = axum::http::HeaderMap::new();
+        let claude = json!({
+            "model": "claude-sonnet-4-5",
+            "thinking": { "type": "enabled", "budget_tokens": 1024 },
+            "messages": [{ "role": "user", "content": [
+                { "type": "image", "source": { "type": "base64", "media_type": "image/png", "data": "" } }
+            ]}]
+        });
+        let ctx = RouteContext::from_request("claude-sonnet-4-5", "anthropic", &claude, &headers, None);
+        assert!(ctx.has_thinking && ctx.has_images && !ctx.has_tools);
+
+        let gemini = json!({
+            "contents": [{ "role": "user", "parts": [{ "inlineData": { "mimeType": "image/jpeg", "data": "" } }] }],
+            "tools": [{ "functionDeclarations": [] }]
+        });
+        let ctx = RouteContext::from_request("gemini-3-flash", "gemini", &gemini, &headers, None);
+        assert!(ctx.has_images && ctx.has_tools && !ctx.has_thinking);
+
+        let openai = json!({ "messages": [{ "role": "user", "content": "hi" }], "reasoning_effort": "high" });
+        let ctx = RouteContext::from_request("gpt-5", "openai", &openai, &headers, None);
+        assert!(ctx.has_thinking && !ctx.has_images);
+    }
+}
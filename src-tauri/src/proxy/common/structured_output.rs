@@ -0,0 +1,530 @@
+//! 结构化输出 (JSON Schema)
+//!
+//! - 从 Claude `output_format` / `output_config.format`、OpenAI `response_format`
+//!   (Chat 与 Responses 的 `text.format`) 中提取 JSON Schema
+//! - 映射为 Gemini `responseMimeType` + `responseSchema` (经 `clean_json_schema` 清洗)；
+//!   请求带工具时 Gemini 不接受 JSON 模式，改为在 systemInstruction 中注入 Schema 约束
+//! - 严格模式: 缓冲上游 Gemini SSE，按原始 Schema 校验最终文本，通过后再交给协议转换；
+//!   不符合时重试一次或返回协议对应的错误
+
+use std::pin::Pin;
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use serde_json::{json, Value};
+
+use crate::proxy::config::{StrictSchemaMode, StructuredOutputConfig};
+
+/// `$ref` 展开与嵌套校验的最大深度
+const MAX_VALIDATION_DEPTH: usize = 32;
+
+/// 请求声明的输出 Schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputSchema {
+    pub name: Option<String>,
+    /// 客户端提供的原始 Schema (校验使用，未经清洗)
+    pub schema: Value,
+    /// 请求是否要求严格遵守 Schema
+    pub strict: bool,
+}
+
+impl OutputSchema {
+    /// Claude `output_format` / `output_config.format`: `{ "type": "json_schema", "schema": {...} }`
+    ///
+    /// Anthropic 的结构化输出保证符合 Schema，因此始终视为严格
+    pub fn from_claude(format: Option<&Value>) -> Option<Self> {
+        let format = format?;
+        if format.get("type").and_then(|t| t.as_str()) != Some("json_schema") {
+            return None;
+        }
+        Some(Self {
+            name: format.get("name").and_then(|n| n.as_str()).map(String::from),
+            schema: format.get("schema")?.clone(),
+            strict: true,
+        })
+    }
+
+    /// OpenAI `response_format`: `{ "type": "json_schema", "json_schema": { "name", "schema", "strict" } }`
+    pub fn from_openai(format_type: &str, json_schema: Option<&Value>) -> Option<Self> {
+        if format_type != "json_schema" {
+            return None;
+        }
+        let spec = json_schema?;
+        Some(Self {
+            name: spec.get("name").and_then(|n| n.as_str()).map(String::from),
+            schema: spec.get("schema")?.clone(),
+            strict: spec.get("strict").and_then(|s| s.as_bool()).unwrap_or(false),
+        })
+    }
+
+    /// 按全局配置判断是否需要校验输出
+    pub fn should_validate(&self, config: &StructuredOutputConfig) -> bool {
+        match config.strict {
+            StrictSchemaMode::Off => false,
+            StrictSchemaMode::Request => self.strict,
+            StrictSchemaMode::Always => true,
+        }
+    }
+}
+
+/// 将输出 Schema 写入 Gemini 请求 (`inner_request` 为 v1internal 的 `request` 部分)
+pub fn apply_to_gemini_request(inner_request: &mut Value, schema: &OutputSchema) {
+    let has_tools = inner_request
+        .get("tools")
+        .and_then(|t| t.as_array())
+        .map_or(false, |t| !t.is_empty());
+
+    if !has_tools {
+        let mut cleaned = schema.schema.clone();
+        super::json_schema::clean_json_schema(&mut cleaned);
+        inner_request["generationConfig"]["responseMimeType"] = json!("application/json");
+        inner_request["generationConfig"]["responseSchema"] = cleaned;
+        return;
+    }
+
+    // Gemini 不支持函数调用与 JSON 模式同时使用，退化为提示词约束 (严格模式下仍会校验)
+    let instruction = format!(
+        "When you give your final answer (not a tool call), reply with only a JSON value that conforms to this JSON schema, without markdown code fences or any other text:\n{}",
+        schema.schema
+    );
+    if !inner_request["systemInstruction"]["parts"].is_array() {
+        inner_request["systemInstruction"] = json!({ "role": "user", "parts": [] });
+    }
+    if let Some(parts) = inner_request["systemInstruction"]["parts"].as_array_mut() {
+        parts.push(json!({ "text": instruction }));
+    }
+}
+
+/// 上游 Gemini 流中提取的最终输出
+#[derive(Debug, Default)]
+pub struct GeminiOutput {
+    /// 非思考部分的文本拼接
+    pub text: String,
+    pub has_function_call: bool,
+    pub finish_reason: Option<String>,
+}
+
+/// 从缓冲的 Gemini SSE 数据块中提取输出 (兼容 v1internal 的 `response` 包装)
+pub fn extract_gemini_output(chunks: &[Bytes]) -> GeminiOutput {
+    let raw: Vec<u8> = chunks.iter().flat_map(|c| c.iter().copied()).collect();
+    let mut output = GeminiOutput::default();
+
+    for line in String::from_utf8_lossy(&raw).lines() {
+        let Some(data) = line.trim().strip_prefix("data:") else {
+            continue;
+        };
+        let Ok(event) = serde_json::from_str::<Value>(data.trim()) else {
+            continue;
+        };
+        let response = event.get("response").unwrap_or(&event);
+        let Some(candidate) = response.pointer("/candidates/0") else {
+            continue;
+        };
+        for part in candidate
+            .pointer("/content/parts")
+            .and_then(|p| p.as_array())
+            .into_iter()
+            .flatten()
+        {
+            if part.get("functionCall").is_some() {
+                output.has_function_call = true;
+            } else if part.get("thought").and_then(|t| t.as_bool()) != Some(true) {
+                if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                    output.text.push_str(text);
+                }
+            }
+        }
+        if let Some(reason) = candidate.get("finishReason").and_then(|r| r.as_str()) {
+            output.finish_reason = Some(reason.to_string());
+        }
+    }
+
+    output
+}
+
+/// 严格模式失败原因
+#[derive(Debug)]
+pub enum SchemaCheckError {
+    /// 上游流读取失败 (按普通上游错误换号重试)
+    Stream(String),
+    /// 输出不符合 Schema
+    Mismatch(String),
+}
+
+/// 缓冲完整的上游流并校验输出，成功时返回数据块用于重放
+pub async fn buffer_and_validate<S, E>(
+    mut stream: S,
+    schema: &OutputSchema,
+) -> Result<Vec<Bytes>, SchemaCheckError>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+{
+    let mut chunks = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(bytes) => chunks.push(bytes),
+            Err(e) => return Err(SchemaCheckError::Stream(format!("Stream error: {}", e))),
+        }
+    }
+
+    let output = extract_gemini_output(&chunks);
+    // 工具调用回合不是最终答案，不做校验
+    if output.has_function_call {
+        return Ok(chunks);
+    }
+    validate_text(&output.text, &schema.schema).map_err(|reason| {
+        let reason = match output.finish_reason.as_deref() {
+            Some("MAX_TOKENS") => format!("{} (output truncated by max tokens)", reason),
+            _ => reason,
+        };
+        SchemaCheckError::Mismatch(reason)
+    })?;
+    Ok(chunks)
+}
+
+/// 将缓冲的数据块重新包装为流
+pub fn replay<E: Send + 'static>(
+    chunks: Vec<Bytes>,
+) -> Pin<Box<dyn Stream<Item = Result<Bytes, E>> + Send>> {
+    Box::pin(futures::stream::iter(chunks.into_iter().map(Ok)))
+}
+
+/// 校验文本是否为符合 Schema 的 JSON
+pub fn validate_text(text: &str, schema: &Value) -> Result<(), String> {
+    let value: Value = serde_json::from_str(text.trim())
+        .map_err(|e| format!("output is not valid JSON: {}", e))?;
+    validate_instance(&value, schema)
+}
+
+/// 按 JSON Schema 校验实例 (支持常用关键字与本地 `$ref`)
+pub fn validate_instance(instance: &Value, schema: &Value) -> Result<(), String> {
+    check(instance, schema, schema, "$", 0)
+}
+
+fn resolve_ref<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    let pointer = reference.strip_prefix('#')?;
+    if pointer.is_empty() {
+        return Some(root);
+    }
+    root.pointer(pointer)
+}
+
+fn type_matches(instance: &Value, ty: &str) -> bool {
+    match ty {
+        "object" => instance.is_object(),
+        "array" => instance.is_array(),
+        "string" => instance.is_string(),
+        "boolean" => instance.is_boolean(),
+        "null" => instance.is_null(),
+        "number" => instance.is_number(),
+        "integer" => {
+            instance.is_i64()
+                || instance.is_u64()
+                || instance.as_f64().map_or(false, |f| f.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+fn check(instance: &Value, schema: &Value, root: &Value, path: &str, depth: usize) -> Result<(), String> {
+    if depth > MAX_VALIDATION_DEPTH {
+        return Ok(());
+    }
+    let schema = match schema {
+        Value::Bool(true) => return Ok(()),
+        Value::Bool(false) => return Err(format!("{}: no value is allowed here", path)),
+        Value::Object(map) => map,
+        _ => return Ok(()),
+    };
+
+    if let Some(reference) = schema.get("$ref").and_then(|r| r.as_str()) {
+        let target = resolve_ref(root, reference)
+            .ok_or_else(|| format!("{}: unresolvable $ref '{}'", path, reference))?;
+        check(instance, target, root, path, depth + 1)?;
+    }
+
+    if let Some(ty) = schema.get("type") {
+        let allowed: Vec<&str> = match ty {
+            Value::String(s) => vec![s.as_str()],
+            Value::Array(items) => items.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| type_matches(instance, t)) {
+            return Err(format!("{}: expected {}, got {}", path, allowed.join(" | "), json_type(instance)));
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !options.contains(instance) {
+            return Err(format!("{}: {} is not one of {}", path, instance, Value::Array(options.clone())));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != instance {
+            return Err(format!("{}: expected constant {}", path, expected));
+        }
+    }
+
+    for branch in schema.get("allOf").and_then(|v| v.as_array()).into_iter().flatten() {
+        check(instance, branch, root, path, depth + 1)?;
+    }
+    if let Some(branches) = schema.get("anyOf").and_then(|v| v.as_array()) {
+        if !branches.iter().any(|b| check(instance, b, root, path, depth + 1).is_ok()) {
+            return Err(format!("{}: does not match any allowed schema (anyOf)", path));
+        }
+    }
+    if let Some(branches) = schema.get("oneOf").and_then(|v| v.as_array()) {
+        let matched = branches
+            .iter()
+            .filter(|b| check(instance, b, root, path, depth + 1).is_ok())
+            .count();
+        if matched != 1 {
+            return Err(format!("{}: must match exactly one schema (oneOf), matched {}", path, matched));
+        }
+    }
+    if let Some(not) = schema.get("not") {
+        if check(instance, not, root, path, depth + 1).is_ok() {
+            return Err(format!("{}: must not match the 'not' schema", path));
+        }
+    }
+
+    match instance {
+        Value::Object(obj) => {
+            for key in schema.get("required").and_then(|r| r.as_array()).into_iter().flatten() {
+                if let Some(key) = key.as_str() {
+                    if !obj.contains_key(key) {
+                        return Err(format!("{}: missing required property '{}'", path, key));
+                    }
+                }
+            }
+            let properties = schema.get("properties").and_then(|p| p.as_object());
+            for (key, value) in obj {
+                let child = format!("{}.{}", path, key);
+                match properties.and_then(|p| p.get(key)) {
+                    Some(prop_schema) => check(value, prop_schema, root, &child, depth + 1)?,
+                    None => match schema.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            return Err(format!("{}: unexpected property '{}'", path, key));
+                        }
+                        Some(extra) => check(value, extra, root, &child, depth + 1)?,
+                        None => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = schema.get("minItems").and_then(|v| v.as_u64()) {
+                if (items.len() as u64) < min {
+                    return Err(format!("{}: expected at least {} items, got {}", path, min, items.len()));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(|v| v.as_u64()) {
+                if items.len() as u64 > max {
+                    return Err(format!("{}: expected at most {} items, got {}", path, max, items.len()));
+                }
+            }
+            let prefix = schema.get("prefixItems").and_then(|p| p.as_array());
+            let prefix_len = prefix.map_or(0, |p| p.len());
+            for (i, item) in items.iter().enumerate() {
+                let child = format!("{}[{}]", path, i);
+                if let Some(item_schema) = prefix.and_then(|p| p.get(i)) {
+                    check(item, item_schema, root, &child, depth + 1)?;
+                } else if i >= prefix_len {
+                    if let Some(item_schema) = schema.get("items") {
+                        check(item, item_schema, root, &child, depth + 1)?;
+                    }
+                }
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(|v| v.as_u64()) {
+                if len < min {
+                    return Err(format!("{}: string shorter than {}", path, min));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(|v| v.as_u64()) {
+                if len > max {
+                    return Err(format!("{}: string longer than {}", path, max));
+                }
+            }
+            if let Some(pattern) = schema.get("pattern").and_then(|v| v.as_str()) {
+                // 无法编译的正则不作为失败条件
+                if let Ok(re) = regex::Regex::new(pattern) {
+                    if !re.is_match(s) {
+                        return Err(format!("{}: does not match pattern '{}'", path, pattern));
+                    }
+                }
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or(0.0);
+            let bound = |key: &str| schema.get(key).and_then(|v| v.as_f64());
+            if bound("minimum").map_or(false, |min| n < min)
+                || bound("exclusiveMinimum").map_or(false, |min| n <= min)
+            {
+                return Err(format!("{}: {} is below the minimum", path, n));
+            }
+            if bound("maximum").map_or(false, |max| n > max)
+                || bound("exclusiveMaximum").map_or(false, |max| n >= max)
+            {
+                return Err(format!("{}: {} is above the maximum", path, n));
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// 输出不符合 Schema 时返回的协议错误 (502: 上游模型未给出有效结果)
+///
+/// 带上 X-Account-Email / X-Mapped-Model，监控与统计仍能归属到实际账号
+pub fn mismatch_response(protocol: &str, reason: &str, email: &str, mapped_model: &str) -> Response {
+    let message = format!("Model output does not match the requested JSON schema: {}", reason);
+    let body = match protocol {
+        "anthropic" => json!({
+            "type": "error",
+            "error": { "type": "api_error", "message": message }
+        }),
+        _ => json!({
+            "error": {
+                "message": message,
+                "type": "server_error",
+                "param": null,
+                "code": "json_schema_mismatch"
+            }
+        }),
+    };
+    (
+        StatusCode::BAD_GATEWAY,
+        [("X-Account-Email", email), ("X-Mapped-Model", mapped_model)],
+        Json(body),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn person_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "minLength": 1 },
+                "age": { "type": "integer", "minimum": 0 },
+                "tags": { "type": "array", "items": { "$ref": "#/$defs/tag" } }
+            },
+            "required": ["name", "age"],
+            "additionalProperties": false,
+            "$defs": { "tag": { "type": "string", "enum": ["a", "b"] } }
+        })
+    }
+
+    #[test]
+    fn test_validate_text() {
+        let schema = person_schema();
+        assert!(validate_text(r#"{"name":"Ann","age":3,"tags":["a"]}"#, &schema).is_ok());
+
+        let err = validate_text(r#"{"name":"Ann"}"#, &schema).unwrap_err();
+        assert!(err.contains("missing required property 'age'"), "{}", err);
+
+        let err = validate_text(r#"{"name":"Ann","age":3,"tags":["c"]}"#, &schema).unwrap_err();
+        assert!(err.starts_with("$.tags[0]"), "{}", err);
+
+        let err = validate_text(r#"{"name":"Ann","age":1.5}"#, &schema).unwrap_err();
+        assert!(err.contains("expected integer"), "{}", err);
+
+        assert!(validate_text(r#"{"name":"Ann","age":3,"x":1}"#, &schema).is_err());
+        assert!(validate_text("```json\n{}\n```", &schema).unwrap_err().contains("not valid JSON"));
+    }
+
+    #[test]
+    fn test_schema_extraction_and_strictness() {
+        let claude = json!({ "type": "json_schema", "schema": person_schema() });
+        let schema = OutputSchema::from_claude(Some(&claude)).unwrap();
+        assert!(schema.strict);
+        assert!(OutputSchema::from_claude(Some(&json!({ "type": "text" }))).is_none());
+
+        let openai = json!({ "name": "person", "schema": person_schema() });
+        let schema = OutputSchema::from_openai("json_schema", Some(&openai)).unwrap();
+        assert_eq!(schema.name.as_deref(), Some("person"));
+        assert!(!schema.strict);
+
+        let mut config = StructuredOutputConfig::default();
+        assert!(!schema.should_validate(&config));
+        config.strict = StrictSchemaMode::Always;
+        assert!(schema.should_validate(&config));
+        config.strict = StrictSchemaMode::Off;
+        assert!(!OutputSchema::from_claude(Some(&claude)).unwrap().should_validate(&config));
+    }
+
+    #[test]
+    fn test_apply_to_gemini_request() {
+        let schema = OutputSchema::from_claude(Some(&json!({ "type": "json_schema", "schema": person_schema() }))).unwrap();
+
+        let mut plain = json!({ "contents": [] });
+        apply_to_gemini_request(&mut plain, &schema);
+        assert_eq!(plain["generationConfig"]["responseMimeType"], "application/json");
+        assert!(plain["generationConfig"]["responseSchema"].get("$defs").is_none());
+
+        // 带工具时改为提示词约束
+        let mut with_tools = json!({
+            "contents": [],
+            "tools": [{ "functionDeclarations": [{ "name": "lookup" }] }],
+            "systemInstruction": { "role": "user", "parts": [{ "text": "sys" }] }
+        });
+        apply_to_gemini_request(&mut with_tools, &schema);
+        assert!(with_tools.get("generationConfig").is_none());
+        assert_eq!(with_tools["systemInstruction"]["parts"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_extract_gemini_output_skips_thoughts() {
+        let chunks = vec![
+            Bytes::from("data: {\"response\":{\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"plan\",\"thought\":true}]}}]}}\n\n"),
+            Bytes::from("data: {\"response\":{\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"{\\\"a\\\":\"}]}}]}}\n\ndata: {\"response\":{\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"1}\"}]},\"finishReason\":\"STOP\"}]}}\n\n"),
+        ];
+        let output = extract_gemini_output(&chunks);
+        assert_eq!(output.text, "{\"a\":1}");
+        assert!(!output.has_function_call);
+        assert_eq!(output.finish_reason.as_deref(), Some("STOP"));
+    }
+
+    #[tokio::test]
+    async fn test_buffer_and_validate() {
+        let schema = OutputSchema::from_claude(Some(&json!({
+            "type": "json_schema",
+            "schema": { "type": "object", "required": ["a"] }
+        })))
+        .unwrap();
+        let ok = vec![Bytes::from("data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"{\\\"a\\\":1}\"}]}}]}\n\n")];
+        let stream = futures::stream::iter(ok.clone().into_iter().map(Ok::<Bytes, String>));
+        assert_eq!(buffer_and_validate(stream, &schema).await.unwrap(), ok);
+
+        let bad = vec![Bytes::from("data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"{}\"}]}}]}\n\n")];
+        let stream = futures::stream::iter(bad.into_iter().map(Ok::<Bytes, String>));
+        assert!(matches!(
+            buffer_and_validate(stream, &schema).await,
+            Err(SchemaCheckError::Mismatch(_))
+        ));
+    }
+}
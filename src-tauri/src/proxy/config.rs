@@ -122,6 +122,539 @@ pub fn update_image_thinking_mode(mode: Option<String>) {
     }
 }
 
+// ============================================================================
+// 全局响应缓存配置存储
+// 由 response_cache 中间件读取，保存配置后立即生效
+// ============================================================================
+static GLOBAL_RESPONSE_CACHE_CONFIG: OnceLock<RwLock<ResponseCacheConfig>> = OnceLock::new();
+
+/// 获取当前响应缓存配置
+pub fn get_response_cache_config() -> ResponseCacheConfig {
+    GLOBAL_RESPONSE_CACHE_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+/// 更新全局响应缓存配置
+pub fn update_response_cache_config(config: ResponseCacheConfig) {
+    if let Some(lock) = GLOBAL_RESPONSE_CACHE_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = config.clone();
+            tracing::info!(
+                "[Response-Cache] Global config updated: enabled={}, ttl={}s",
+                config.enabled,
+                config.ttl_secs
+            );
+        }
+    } else {
+        let _ = GLOBAL_RESPONSE_CACHE_CONFIG.set(RwLock::new(config.clone()));
+        tracing::info!(
+            "[Response-Cache] Global config initialized: enabled={}, ttl={}s",
+            config.enabled,
+            config.ttl_secs
+        );
+    }
+}
+
+/// 响应缓存配置 (确定性请求去重，默认关闭)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseCacheConfig {
+    /// 是否启用
+    #[serde(default)]
+    pub enabled: bool,
+    /// 条目有效期 (秒)
+    #[serde(default = "default_response_cache_ttl")]
+    pub ttl_secs: u64,
+    /// 最大条目数，超出后按最近命中时间淘汰
+    #[serde(default = "default_response_cache_max_entries")]
+    pub max_entries: u64,
+    /// 缓存总大小上限 (MB)
+    #[serde(default = "default_response_cache_max_size_mb")]
+    pub max_size_mb: u64,
+    /// 仅缓存显式 temperature = 0 的请求 (关闭后所有请求都可命中)
+    #[serde(default = "default_true")]
+    pub require_zero_temperature: bool,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: default_response_cache_ttl(),
+            max_entries: default_response_cache_max_entries(),
+            max_size_mb: default_response_cache_max_size_mb(),
+            require_zero_temperature: true,
+        }
+    }
+}
+
+fn default_response_cache_ttl() -> u64 {
+    24 * 3600
+}
+
+fn default_response_cache_max_entries() -> u64 {
+    10_000
+}
+
+fn default_response_cache_max_size_mb() -> u64 {
+    512
+}
+
+// ============================================================================
+// 全局费用核算配置存储
+// 由 monitor 中间件 (计价) 与 auth 中间件 (预算) 读取，保存配置后立即生效
+// ============================================================================
+static GLOBAL_COST_CONFIG: OnceLock<RwLock<CostConfig>> = OnceLock::new();
+
+/// 获取当前费用核算配置
+pub fn get_cost_config() -> CostConfig {
+    GLOBAL_COST_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+/// 更新全局费用核算配置
+pub fn update_cost_config(config: CostConfig) {
+    if let Some(lock) = GLOBAL_COST_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = config.clone();
+            tracing::info!(
+                "[Cost] Global config updated: {} price rules, budget daily=${} monthly=${} ({:?})",
+                config.prices.len(),
+                config.budget.daily_usd,
+                config.budget.monthly_usd,
+                config.budget.action
+            );
+        }
+    } else {
+        let _ = GLOBAL_COST_CONFIG.set(RwLock::new(config.clone()));
+        tracing::info!(
+            "[Cost] Global config initialized: {} price rules",
+            config.prices.len()
+        );
+    }
+}
+
+/// 单个模型的价格 (美元 / 百万 Token)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    /// 模型名，支持 * 通配符 (如 "claude-sonnet-*")
+    pub model: String,
+    /// 未命中缓存的输入
+    #[serde(default)]
+    pub input: f64,
+    /// 输出 (不含思考)
+    #[serde(default)]
+    pub output: f64,
+    /// 命中上下文缓存的输入，未设置时按 input 计价
+    #[serde(default)]
+    pub cached_input: Option<f64>,
+    /// 思考 / 推理 Token，未设置时按 output 计价
+    #[serde(default)]
+    pub thinking: Option<f64>,
+}
+
+/// 超出预算后的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetAction {
+    /// 仅告警 (日志 + Webhook)，继续放行
+    #[default]
+    Warn,
+    /// 拒绝后续请求，直到周期重置
+    Block,
+}
+
+impl BudgetAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BudgetAction::Warn => "warn",
+            BudgetAction::Block => "block",
+        }
+    }
+
+    /// 解析数据库中保存的取值，未知值按 warn 处理
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "block" => BudgetAction::Block,
+            _ => BudgetAction::Warn,
+        }
+    }
+}
+
+/// 预算上限 (美元，按 UTC 自然日 / 自然月统计，0 = 不限制)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BudgetConfig {
+    #[serde(default)]
+    pub daily_usd: f64,
+    #[serde(default)]
+    pub monthly_usd: f64,
+    #[serde(default)]
+    pub action: BudgetAction,
+}
+
+impl BudgetConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.daily_usd > 0.0 || self.monthly_usd > 0.0
+    }
+}
+
+/// 费用核算配置：模型价格表 + 全局预算
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostConfig {
+    /// 价格表，按顺序匹配第一条 (精确匹配优先于通配符)
+    #[serde(default)]
+    pub prices: Vec<ModelPrice>,
+    /// 全局预算 (所有请求合计)
+    #[serde(default)]
+    pub budget: BudgetConfig,
+}
+
+// ============================================================================
+// 全局自定义上游 (Provider Registry) 配置存储
+// 由各协议 handler 在调度前读取，保存配置后立即生效
+// ============================================================================
+static GLOBAL_PROVIDERS_CONFIG: OnceLock<RwLock<Vec<UpstreamProviderConfig>>> = OnceLock::new();
+
+/// 获取当前自定义上游列表
+pub fn get_providers_config() -> Vec<UpstreamProviderConfig> {
+    GLOBAL_PROVIDERS_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+/// 更新全局自定义上游列表
+pub fn update_providers_config(providers: Vec<UpstreamProviderConfig>) {
+    let enabled = providers.iter().filter(|p| p.enabled).count();
+    if let Some(lock) = GLOBAL_PROVIDERS_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = providers.clone();
+            tracing::info!(
+                "[Providers] Global config updated: {} providers ({} enabled)",
+                providers.len(),
+                enabled
+            );
+        }
+    } else {
+        let _ = GLOBAL_PROVIDERS_CONFIG.set(RwLock::new(providers.clone()));
+        tracing::info!(
+            "[Providers] Global config initialized: {} providers ({} enabled)",
+            providers.len(),
+            enabled
+        );
+    }
+}
+
+/// 自定义上游使用的 API 协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderProtocol {
+    /// OpenAI 兼容 (`/v1/chat/completions`)，如公司网关、vLLM、Ollama
+    Openai,
+    /// Anthropic 兼容 (`/v1/messages`)
+    Anthropic,
+}
+
+impl ProviderProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProviderProtocol::Openai => "openai",
+            ProviderProtocol::Anthropic => "anthropic",
+        }
+    }
+}
+
+/// 自定义上游的调度方式 (仅作用于 `models` 列表命中的请求，路由规则指定的请求总是转发)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderDispatchMode {
+    /// 命中的模型总是转发到该上游
+    #[default]
+    Exclusive,
+    /// 仅当 Google 账号池对该模型无可用账号时转发
+    Fallback,
+}
+
+/// 一个自定义上游 (OpenAI / Anthropic 兼容)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UpstreamProviderConfig {
+    /// 唯一 ID (用于路由规则、日志与统计，统计中显示为 `provider:<id>`)
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub protocol: ProviderProtocol,
+    /// 基础地址，如 `https://llm.example.com` 或 `http://127.0.0.1:8000` (不含 `/v1`)
+    pub base_url: String,
+    /// API Key 列表，按轮询使用，被限流的 Key 暂时跳过；为空时不发送认证头
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+    /// 由该上游服务的模型 (支持 * 通配符)，为空时只能通过路由规则选中
+    #[serde(default)]
+    pub models: Vec<String>,
+    /// 转发前的模型名改写 (客户端模型 -> 上游模型，支持 * 通配符)
+    #[serde(default)]
+    pub model_mapping: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    pub dispatch_mode: ProviderDispatchMode,
+    /// Key 被限流 (429) 且上游未返回 Retry-After 时的冷却时间 (秒)
+    #[serde(default = "default_provider_cooldown_secs")]
+    pub rate_limit_cooldown_secs: u64,
+}
+
+impl UpstreamProviderConfig {
+    /// 展示名 (未设置 name 时使用 id)
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().filter(|n| !n.is_empty()).unwrap_or(&self.id)
+    }
+}
+
+fn default_provider_cooldown_secs() -> u64 {
+    60
+}
+
+// ============================================================================
+// 全局结构化输出配置
+// 由各协议 handler 读取，决定是否校验模型输出是否符合请求的 JSON Schema
+// ============================================================================
+static GLOBAL_STRUCTURED_OUTPUT_CONFIG: OnceLock<RwLock<StructuredOutputConfig>> =
+    OnceLock::new();
+
+/// 获取当前结构化输出配置
+pub fn get_structured_output_config() -> StructuredOutputConfig {
+    GLOBAL_STRUCTURED_OUTPUT_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+/// 更新全局结构化输出配置
+pub fn update_structured_output_config(config: StructuredOutputConfig) {
+    if let Some(lock) = GLOBAL_STRUCTURED_OUTPUT_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = config.clone();
+            tracing::info!(
+                "[StructuredOutput] Global config updated: strict={:?}, on_mismatch={:?}",
+                config.strict,
+                config.on_mismatch
+            );
+        }
+    } else {
+        let _ = GLOBAL_STRUCTURED_OUTPUT_CONFIG.set(RwLock::new(config.clone()));
+        tracing::info!(
+            "[StructuredOutput] Global config initialized: strict={:?}",
+            config.strict
+        );
+    }
+}
+
+/// 何时校验模型输出是否符合 JSON Schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StrictSchemaMode {
+    /// 从不校验 (仅映射为 responseSchema)
+    Off,
+    /// 请求声明严格时校验: OpenAI `strict: true`，Anthropic 结构化输出始终视为严格
+    #[default]
+    Request,
+    /// 所有携带 JSON Schema 的请求都校验
+    Always,
+}
+
+/// 输出不符合 Schema 时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaMismatchAction {
+    /// 直接返回协议对应的错误
+    Error,
+    /// 重新请求一次，仍不符合时返回错误
+    #[default]
+    Retry,
+}
+
+/// 结构化输出 (JSON Schema) 配置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StructuredOutputConfig {
+    #[serde(default)]
+    pub strict: StrictSchemaMode,
+    #[serde(default)]
+    pub on_mismatch: SchemaMismatchAction,
+}
+
+// ============================================================================
+// 全局批处理配置存储
+// 由批处理 worker 每轮读取，保存配置后立即生效
+// ============================================================================
+static GLOBAL_BATCH_CONFIG: OnceLock<RwLock<BatchConfig>> = OnceLock::new();
+
+/// 获取当前批处理配置
+pub fn get_batch_config() -> BatchConfig {
+    GLOBAL_BATCH_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+/// 更新全局批处理配置
+pub fn update_batch_config(config: BatchConfig) {
+    if let Some(lock) = GLOBAL_BATCH_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = config.clone();
+            tracing::info!(
+                "[Batch] Global config updated: enabled={}, concurrency={}, max_interactive_in_flight={}",
+                config.enabled,
+                config.concurrency,
+                config.max_interactive_in_flight
+            );
+        }
+    } else {
+        let _ = GLOBAL_BATCH_CONFIG.set(RwLock::new(config.clone()));
+        tracing::info!(
+            "[Batch] Global config initialized: enabled={}, concurrency={}",
+            config.enabled,
+            config.concurrency
+        );
+    }
+}
+
+/// 批处理 (Message Batches / OpenAI Batch) 配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchConfig {
+    /// 是否消费队列 (关闭后仍可提交批次，恢复后继续执行)
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 同时执行的批处理请求数
+    #[serde(default = "default_batch_concurrency")]
+    pub concurrency: u32,
+    /// 进行中的交互请求超过该值时暂停领取新任务 (0 = 有交互请求就让路)
+    #[serde(default = "default_batch_max_interactive")]
+    pub max_interactive_in_flight: u32,
+    /// 单条请求遇到限流 / 无可用账号时的最大执行次数
+    #[serde(default = "default_batch_max_attempts")]
+    pub max_attempts: u32,
+    /// 单个批次的最大请求数
+    #[serde(default = "default_batch_max_requests")]
+    pub max_requests_per_batch: u32,
+    /// 结束后结果保留天数
+    #[serde(default = "default_batch_retention_days")]
+    pub retention_days: u32,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            concurrency: default_batch_concurrency(),
+            max_interactive_in_flight: default_batch_max_interactive(),
+            max_attempts: default_batch_max_attempts(),
+            max_requests_per_batch: default_batch_max_requests(),
+            retention_days: default_batch_retention_days(),
+        }
+    }
+}
+
+fn default_batch_concurrency() -> u32 {
+    2
+}
+
+fn default_batch_max_interactive() -> u32 {
+    0
+}
+
+fn default_batch_max_attempts() -> u32 {
+    5
+}
+
+fn default_batch_max_requests() -> u32 {
+    100_000
+}
+
+fn default_batch_retention_days() -> u32 {
+    29
+}
+
+// ============================================================================
+// 全局文件存储配置
+// 由 Files API 上传与清理时读取，保存配置后立即生效
+// ============================================================================
+static GLOBAL_FILES_CONFIG: OnceLock<RwLock<FilesConfig>> = OnceLock::new();
+
+/// 获取当前文件存储配置
+pub fn get_files_config() -> FilesConfig {
+    GLOBAL_FILES_CONFIG
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|cfg| cfg.clone())
+        .unwrap_or_default()
+}
+
+/// 更新全局文件存储配置
+pub fn update_files_config(config: FilesConfig) {
+    if let Some(lock) = GLOBAL_FILES_CONFIG.get() {
+        if let Ok(mut cfg) = lock.write() {
+            *cfg = config.clone();
+            tracing::info!(
+                "[Files] Global config updated: max_file_size_mb={}, max_total_size_mb={}, ttl_days={}",
+                config.max_file_size_mb,
+                config.max_total_size_mb,
+                config.ttl_days
+            );
+        }
+    } else {
+        let _ = GLOBAL_FILES_CONFIG.set(RwLock::new(config.clone()));
+        tracing::info!(
+            "[Files] Global config initialized: max_file_size_mb={}, ttl_days={}",
+            config.max_file_size_mb,
+            config.ttl_days
+        );
+    }
+}
+
+/// Files API 本地存储配置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilesConfig {
+    /// 单个文件上限
+    #[serde(default = "default_files_max_file_size_mb")]
+    pub max_file_size_mb: u64,
+    /// 存储总量上限 (相同内容只计一次)
+    #[serde(default = "default_files_max_total_size_mb")]
+    pub max_total_size_mb: u64,
+    /// 上传后保留天数 (0 = 不过期)
+    #[serde(default = "default_files_ttl_days")]
+    pub ttl_days: u32,
+}
+
+impl Default for FilesConfig {
+    fn default() -> Self {
+        Self {
+            max_file_size_mb: default_files_max_file_size_mb(),
+            max_total_size_mb: default_files_max_total_size_mb(),
+            ttl_days: default_files_ttl_days(),
+        }
+    }
+}
+
+fn default_files_max_file_size_mb() -> u64 {
+    100
+}
+
+fn default_files_max_total_size_mb() -> u64 {
+    10 * 1024
+}
+
+fn default_files_ttl_days() -> u32 {
+    30
+}
+
 /// 全局系统提示词配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlobalSystemPromptConfig {
@@ -484,6 +1017,10 @@ pub struct ProxyConfig {
     /// Web UI 管理后台密码 (可选，如未设置则使用 api_key)
     pub admin_password: Option<String>,
 
+    /// [NEW] 管理员登录会话有效期 (小时)
+    #[serde(default = "default_admin_session_ttl_hours")]
+    pub admin_session_ttl_hours: u64,
+
     /// 是否自动启动
     pub auto_start: bool,
 
@@ -491,6 +1028,15 @@ pub struct ProxyConfig {
     #[serde(default)]
     pub custom_mapping: std::collections::HashMap<String, String>,
 
+    /// 有序模型路由规则 (优先于 custom_mapping，首个命中的规则生效)
+    #[serde(default)]
+    pub model_routing_rules: Vec<crate::proxy::common::routing_rules::ModelRoutingRule>,
+
+    /// 模型降级链 (key: 映射后的模型名，支持通配符; value: 依次尝试的后备模型)
+    /// 目标模型在整个账号池均被限流或配额保护时生效
+    #[serde(default)]
+    pub model_fallback_chains: std::collections::HashMap<String, Vec<String>>,
+
     /// API 请求超时时间(秒)
     #[serde(default = "default_request_timeout")]
     pub request_timeout: u64,
@@ -556,6 +1102,30 @@ pub struct ProxyConfig {
     /// 代理池配置
     #[serde(default)]
     pub proxy_pool: ProxyPoolConfig,
+
+    /// 响应缓存配置
+    #[serde(default)]
+    pub response_cache: ResponseCacheConfig,
+
+    /// 费用核算配置 (模型价格表与全局预算)
+    #[serde(default)]
+    pub cost: CostConfig,
+
+    /// 自定义上游 (OpenAI / Anthropic 兼容)，与 Google 账号池并列调度
+    #[serde(default)]
+    pub providers: Vec<UpstreamProviderConfig>,
+
+    /// 结构化输出 (JSON Schema) 的严格校验
+    #[serde(default)]
+    pub structured_output: StructuredOutputConfig,
+
+    /// 本地批处理队列 (Message Batches / OpenAI Batch)
+    #[serde(default)]
+    pub batch: BatchConfig,
+
+    /// Files API 本地存储
+    #[serde(default)]
+    pub files: FilesConfig,
 }
 
 /// 上游代理配置
@@ -576,8 +1146,11 @@ impl Default for ProxyConfig {
             port: 8045,
             api_key: format!("sk-{}", uuid::Uuid::new_v4().simple()),
             admin_password: None,
+            admin_session_ttl_hours: default_admin_session_ttl_hours(),
             auto_start: false,
             custom_mapping: std::collections::HashMap::new(),
+            model_routing_rules: Vec::new(),
+            model_fallback_chains: std::collections::HashMap::new(),
             request_timeout: default_request_timeout(),
             enable_logging: true, // 默认开启，支持 token 统计功能
             debug_logging: DebugLoggingConfig::default(),
@@ -593,6 +1166,12 @@ impl Default for ProxyConfig {
             global_system_prompt: GlobalSystemPromptConfig::default(),
             proxy_pool: ProxyPoolConfig::default(),
             image_thinking_mode: None,
+            response_cache: ResponseCacheConfig::default(),
+            cost: CostConfig::default(),
+            providers: Vec::new(),
+            structured_output: StructuredOutputConfig::default(),
+            batch: BatchConfig::default(),
+            files: FilesConfig::default(),
         }
     }
 }
@@ -601,6 +1180,10 @@ fn default_request_timeout() -> u64 {
     120 // 默认 120 秒,原来 60 秒太短
 }
 
+fn default_admin_session_ttl_hours() -> u64 {
+    12
+}
+
 fn default_zai_base_url() -> String {
     "https://api.z.ai/api/anthropic".to_string()
 }
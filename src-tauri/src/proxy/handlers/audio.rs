@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Multipart, State},
+    extract::{Extension, Multipart, State},
     http::StatusCode,
     response::IntoResponse,
     Json,
@@ -9,10 +9,13 @@ use tracing::{debug, info};
 use uuid::Uuid;
 
 use crate::proxy::{audio::AudioProcessor, server::AppState};
+use crate::proxy::handlers::common::identity_account_groups;
+use crate::proxy::middleware::auth::UserTokenIdentity;
 
 /// 处理音频转录请求 (OpenAI Whisper API 兼容)
 pub async fn handle_audio_transcription(
     State(state): State<AppState>,
+    identity: Option<Extension<UserTokenIdentity>>,
     mut multipart: Multipart,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
     let mut audio_data: Option<Vec<u8>> = None;
@@ -98,7 +101,7 @@ pub async fn handle_audio_transcription(
     // 6. 获取 Token 和上游客户端
     let token_manager = state.token_manager;
     let (access_token, project_id, email, account_id, _wait_ms) = token_manager
-        .get_token("text", false, None, &model)
+        .get_token("text", false, None, &model, &identity_account_groups(&identity))
         .await
         .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e))?;
 
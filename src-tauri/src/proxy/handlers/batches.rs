@@ -0,0 +1,838 @@
+// Batch Handler
+// Anthropic Message Batches (/v1/messages/batches) 与 OpenAI Batch (/v1/batches)
+// 批次写入本地队列 (modules::batch_db)，由 proxy::batch_worker 在空闲时逐条执行
+// OpenAI 的 input_file_id 与 output_file_id / error_file_id 通过本地 Files API (handlers::files) 读写
+
+use axum::{
+    extract::{Extension, Path, Query, Request},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+use crate::modules::batch_db::{self, BatchItem, BatchRecord, BatchStatus, ItemStatus, NewBatchItem};
+use crate::modules::file_db;
+use crate::proxy::config::get_batch_config;
+use crate::proxy::mappers::claude::models::ClaudeRequest;
+use crate::proxy::middleware::auth::UserTokenIdentity;
+use crate::proxy::middleware::ip_filter::extract_client_ip;
+
+/// 批次的有效期 (与上游一致为 24 小时)，到期仍未执行的请求标记为 expired
+const BATCH_TTL_SECS: i64 = 24 * 3600;
+const MAX_BATCH_BODY_SIZE: usize = 256 * 1024 * 1024;
+
+/// OpenAI Batch 支持的端点
+const OPENAI_ENDPOINTS: [&str; 4] = [
+    "/v1/chat/completions",
+    "/v1/completions",
+    "/v1/responses",
+    "/v1/embeddings",
+];
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListQuery {
+    limit: Option<usize>,
+    before_id: Option<String>,
+    after_id: Option<String>,
+    /// OpenAI 分页参数
+    after: Option<String>,
+}
+
+pub(super) type HandlerError = (StatusCode, String);
+
+pub(super) fn owner_of(identity: &Option<Extension<UserTokenIdentity>>) -> Option<String> {
+    identity.as_ref().map(|Extension(i)| i.token_id.clone())
+}
+
+/// 用户令牌只能看到自己创建的批次；api_key / 未鉴权请求可见全部
+fn load_batch(id: &str, protocol: &str, owner: Option<&str>) -> Result<BatchRecord, HandlerError> {
+    let not_found = || (StatusCode::NOT_FOUND, format!("Batch '{}' not found", id));
+    let batch = batch_db::get_batch(id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .ok_or_else(not_found)?;
+    if batch.protocol != protocol {
+        return Err(not_found());
+    }
+    if let Some(owner) = owner {
+        if batch.owner_token_id.as_deref() != Some(owner) {
+            return Err(not_found());
+        }
+    }
+    Ok(batch)
+}
+
+async fn read_json_body(request: Request) -> Result<Value, HandlerError> {
+    let bytes = axum::body::to_bytes(request.into_body(), MAX_BATCH_BODY_SIZE)
+        .await
+        .map_err(|e| (StatusCode::PAYLOAD_TOO_LARGE, format!("Failed to read request body: {}", e)))?;
+    serde_json::from_slice(&bytes).map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid JSON body: {}", e)))
+}
+
+fn check_request_count(count: usize) -> Result<(), HandlerError> {
+    let max = get_batch_config().max_requests_per_batch as usize;
+    if count == 0 {
+        return Err((StatusCode::BAD_REQUEST, "A batch must contain at least one request".to_string()));
+    }
+    if count > max {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("A batch can contain at most {} requests, got {}", max, count),
+        ));
+    }
+    Ok(())
+}
+
+pub(super) fn timestamp_rfc3339(ts: Option<i64>) -> Value {
+    ts.and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+        .map(|t| Value::String(t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)))
+        .unwrap_or(Value::Null)
+}
+
+/// 根据 Host / X-Forwarded-Proto 拼出对外地址
+fn base_url(headers: &HeaderMap) -> String {
+    let host = headers
+        .get(header::HOST)
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("localhost");
+    let proto = headers
+        .get("x-forwarded-proto")
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("http");
+    format!("{}://{}", proto, host)
+}
+
+fn render_jsonl(lines: &[Value]) -> String {
+    let mut body = String::new();
+    for line in lines {
+        body.push_str(&line.to_string());
+        body.push('\n');
+    }
+    body
+}
+
+fn jsonl_response(lines: Vec<Value>) -> Response {
+    ([(header::CONTENT_TYPE, "application/x-jsonl")], render_jsonl(&lines)).into_response()
+}
+
+fn load_items(batch_id: &str) -> Result<Vec<BatchItem>, HandlerError> {
+    batch_db::list_items(batch_id).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+// ===== Anthropic Message Batches =====
+
+pub(super) fn anthropic_error((status, message): HandlerError) -> Response {
+    let error_type = match status {
+        StatusCode::NOT_FOUND => "not_found_error",
+        StatusCode::PAYLOAD_TOO_LARGE => "request_too_large",
+        s if s.is_client_error() => "invalid_request_error",
+        _ => "api_error",
+    };
+    (
+        status,
+        Json(json!({
+            "type": "error",
+            "error": { "type": error_type, "message": message }
+        })),
+    )
+        .into_response()
+}
+
+fn anthropic_batch_json(batch: &BatchRecord, headers: &HeaderMap) -> Value {
+    let counts = &batch.counts;
+    let ended = batch.status == BatchStatus::Ended;
+    json!({
+        "id": batch.id,
+        "type": "message_batch",
+        "processing_status": batch.status.as_str(),
+        "request_counts": {
+            "processing": counts.queued + counts.running,
+            "succeeded": counts.succeeded,
+            "errored": counts.errored,
+            "canceled": counts.canceled,
+            "expired": counts.expired
+        },
+        "ended_at": timestamp_rfc3339(batch.ended_at),
+        "created_at": timestamp_rfc3339(Some(batch.created_at)),
+        "expires_at": timestamp_rfc3339(Some(batch.expires_at)),
+        "archived_at": null,
+        "cancel_initiated_at": timestamp_rfc3339(batch.cancel_requested_at),
+        "results_url": ended.then(|| format!(
+            "{}/v1/messages/batches/{}/results",
+            base_url(headers),
+            batch.id
+        ))
+    })
+}
+
+/// 校验 Anthropic custom_id: 1-64 位字母、数字、`_`、`-`
+fn is_valid_custom_id(id: &str) -> bool {
+    (1..=64).contains(&id.len())
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// 解析并校验请求；params 中的 file_id 引用在创建时展开，之后删除文件不影响批次执行
+fn parse_anthropic_requests(body: &Value, owner: Option<&str>) -> Result<Vec<NewBatchItem>, HandlerError> {
+    let bad = |msg: String| (StatusCode::BAD_REQUEST, msg);
+    let requests = body
+        .get("requests")
+        .and_then(|r| r.as_array())
+        .ok_or_else(|| bad("requests: field required".to_string()))?;
+    check_request_count(requests.len())?;
+
+    let mut seen = HashSet::new();
+    let mut items = Vec::with_capacity(requests.len());
+    for (i, request) in requests.iter().enumerate() {
+        let custom_id = request
+            .get("custom_id")
+            .and_then(|c| c.as_str())
+            .ok_or_else(|| bad(format!("requests.{}.custom_id: field required", i)))?;
+        if !is_valid_custom_id(custom_id) {
+            return Err(bad(format!(
+                "requests.{}.custom_id: must be 1-64 characters of letters, digits, '_' or '-'",
+                i
+            )));
+        }
+        if !seen.insert(custom_id) {
+            return Err(bad(format!("requests.{}.custom_id: duplicate custom_id '{}'", i, custom_id)));
+        }
+        let mut params = request
+            .get("params")
+            .cloned()
+            .ok_or_else(|| bad(format!("requests.{}.params: field required", i)))?;
+        crate::proxy::common::file_refs::resolve_claude_file_refs(&mut params, owner)
+            .map_err(|(status, e)| (status, format!("requests.{}.params: {}", i, e)))?;
+        serde_json::from_value::<ClaudeRequest>(params.clone())
+            .map_err(|e| bad(format!("requests.{}.params: {}", i, e)))?;
+        items.push(NewBatchItem { custom_id: custom_id.to_string(), body: params });
+    }
+    Ok(items)
+}
+
+/// 单条结果 (results 端点的一行)
+fn anthropic_result_line(item: &BatchItem) -> Value {
+    let result = match item.status {
+        ItemStatus::Succeeded => json!({
+            "type": "succeeded",
+            "message": item.response.clone().unwrap_or(Value::Null)
+        }),
+        ItemStatus::Errored => {
+            // 上游已是 Anthropic 错误结构时原样返回
+            let error = match &item.response {
+                Some(r) if r.get("type").and_then(|t| t.as_str()) == Some("error") => r.clone(),
+                _ => json!({
+                    "type": "error",
+                    "error": {
+                        "type": "api_error",
+                        "message": item.error.clone().unwrap_or_else(|| "Request failed".to_string())
+                    }
+                }),
+            };
+            json!({ "type": "errored", "error": error })
+        }
+        ItemStatus::Expired => json!({ "type": "expired" }),
+        _ => json!({ "type": "canceled" }),
+    };
+    json!({ "custom_id": item.custom_id, "result": result })
+}
+
+/// POST /v1/messages/batches
+pub async fn handle_create_message_batch(
+    identity: Option<Extension<UserTokenIdentity>>,
+    request: Request,
+) -> Response {
+    let headers = request.headers().clone();
+    let client_ip = extract_client_ip(&request);
+    let body = match read_json_body(request).await {
+        Ok(b) => b,
+        Err(e) => return anthropic_error(e),
+    };
+    let owner = owner_of(&identity);
+    let items = match parse_anthropic_requests(&body, owner.as_deref()) {
+        Ok(items) => items,
+        Err(e) => return anthropic_error(e),
+    };
+
+    let id = format!("msgbatch_{}", uuid::Uuid::new_v4().simple());
+    match batch_db::create_batch(
+        &id,
+        "anthropic",
+        "/v1/messages",
+        owner.as_deref(),
+        client_ip.as_deref(),
+        None,
+        None,
+        &items,
+        BATCH_TTL_SECS,
+    ) {
+        Ok(batch) => {
+            tracing::info!("[Batch] Created {} with {} request(s)", batch.id, items.len());
+            Json(anthropic_batch_json(&batch, &headers)).into_response()
+        }
+        Err(e) => anthropic_error((StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+/// GET /v1/messages/batches
+pub async fn handle_list_message_batches(
+    identity: Option<Extension<UserTokenIdentity>>,
+    headers: HeaderMap,
+    Query(query): Query<ListQuery>,
+) -> Response {
+    let limit = query.limit.unwrap_or(20).clamp(1, 1000);
+    let owner = owner_of(&identity);
+    match batch_db::list_batches(
+        Some("anthropic"),
+        owner.as_deref(),
+        query.after_id.as_deref(),
+        query.before_id.as_deref(),
+        limit,
+    ) {
+        Ok((batches, has_more)) => Json(json!({
+            "data": batches.iter().map(|b| anthropic_batch_json(b, &headers)).collect::<Vec<_>>(),
+            "has_more": has_more,
+            "first_id": batches.first().map(|b| b.id.clone()),
+            "last_id": batches.last().map(|b| b.id.clone())
+        }))
+        .into_response(),
+        Err(e) => anthropic_error((StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+/// GET /v1/messages/batches/:batch_id
+pub async fn handle_get_message_batch(
+    identity: Option<Extension<UserTokenIdentity>>,
+    headers: HeaderMap,
+    Path(batch_id): Path<String>,
+) -> Response {
+    match load_batch(&batch_id, "anthropic", owner_of(&identity).as_deref()) {
+        Ok(batch) => Json(anthropic_batch_json(&batch, &headers)).into_response(),
+        Err(e) => anthropic_error(e),
+    }
+}
+
+/// POST /v1/messages/batches/:batch_id/cancel
+pub async fn handle_cancel_message_batch(
+    identity: Option<Extension<UserTokenIdentity>>,
+    headers: HeaderMap,
+    Path(batch_id): Path<String>,
+) -> Response {
+    if let Err(e) = load_batch(&batch_id, "anthropic", owner_of(&identity).as_deref()) {
+        return anthropic_error(e);
+    }
+    match batch_db::cancel_batch(&batch_id) {
+        Ok(Some(batch)) => Json(anthropic_batch_json(&batch, &headers)).into_response(),
+        Ok(None) => anthropic_error((StatusCode::NOT_FOUND, format!("Batch '{}' not found", batch_id))),
+        Err(e) => anthropic_error((StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+/// DELETE /v1/messages/batches/:batch_id (仅限已结束的批次)
+pub async fn handle_delete_message_batch(
+    identity: Option<Extension<UserTokenIdentity>>,
+    Path(batch_id): Path<String>,
+) -> Response {
+    if let Err(e) = load_batch(&batch_id, "anthropic", owner_of(&identity).as_deref()) {
+        return anthropic_error(e);
+    }
+    match batch_db::delete_batch(&batch_id) {
+        Ok(true) => Json(json!({ "id": batch_id, "type": "message_batch_deleted" })).into_response(),
+        Ok(false) => anthropic_error((
+            StatusCode::BAD_REQUEST,
+            format!("Batch '{}' has not ended yet; cancel it before deleting", batch_id),
+        )),
+        Err(e) => anthropic_error((StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+/// GET /v1/messages/batches/:batch_id/results (JSONL)
+pub async fn handle_message_batch_results(
+    identity: Option<Extension<UserTokenIdentity>>,
+    Path(batch_id): Path<String>,
+) -> Response {
+    let batch = match load_batch(&batch_id, "anthropic", owner_of(&identity).as_deref()) {
+        Ok(b) => b,
+        Err(e) => return anthropic_error(e),
+    };
+    if batch.status != BatchStatus::Ended {
+        return anthropic_error((
+            StatusCode::BAD_REQUEST,
+            format!("Batch '{}' is still processing; results are available once it has ended", batch_id),
+        ));
+    }
+    match load_items(&batch_id) {
+        Ok(items) => jsonl_response(items.iter().map(anthropic_result_line).collect()),
+        Err(e) => anthropic_error(e),
+    }
+}
+
+// ===== OpenAI Batch =====
+
+pub(super) fn openai_error((status, message): HandlerError) -> Response {
+    super::common::openai_error(status, &message)
+}
+
+/// OpenAI 状态名: 结束后按是否取消 / 过期细分
+fn openai_status(batch: &BatchRecord) -> &'static str {
+    match batch.status {
+        BatchStatus::InProgress => "in_progress",
+        BatchStatus::Canceling => "cancelling",
+        BatchStatus::Ended if batch.cancel_requested_at.is_some() => "cancelled",
+        BatchStatus::Ended if batch.counts.expired > 0 => "expired",
+        BatchStatus::Ended => "completed",
+    }
+}
+
+/// 批次输出 / 错误文件的 file_id，内容在读取时由队列中的结果生成
+pub(super) fn batch_output_file_id(batch_id: &str, errors: bool) -> String {
+    format!("file-{}-{}", batch_id, if errors { "errors" } else { "output" })
+}
+
+/// 解析 [`batch_output_file_id`] 生成的 file_id，返回 (batch_id, 是否为错误文件)
+pub(super) fn parse_batch_output_file_id(file_id: &str) -> Option<(&str, bool)> {
+    let rest = file_id.strip_prefix("file-").filter(|r| r.starts_with("batch_"))?;
+    match rest.strip_suffix("-output") {
+        Some(batch_id) => Some((batch_id, false)),
+        None => rest.strip_suffix("-errors").map(|batch_id| (batch_id, true)),
+    }
+}
+
+fn openai_batch_json(batch: &BatchRecord) -> Value {
+    let status = openai_status(batch);
+    let ended_at = |s: &str| if status == s { batch.ended_at } else { None };
+    let ended = batch.status == BatchStatus::Ended;
+    let counts = &batch.counts;
+    let output_file_id = (ended && counts.succeeded > 0).then(|| batch_output_file_id(&batch.id, false));
+    let error_file_id = (ended && counts.errored + counts.expired + counts.canceled > 0)
+        .then(|| batch_output_file_id(&batch.id, true));
+    json!({
+        "id": batch.id,
+        "object": "batch",
+        "endpoint": batch.endpoint,
+        "errors": null,
+        "input_file_id": batch.input_file_id,
+        "completion_window": "24h",
+        "status": status,
+        "output_file_id": output_file_id,
+        "error_file_id": error_file_id,
+        "created_at": batch.created_at,
+        "in_progress_at": batch.started_at,
+        "expires_at": batch.expires_at,
+        "finalizing_at": null,
+        "completed_at": ended_at("completed"),
+        "failed_at": null,
+        "expired_at": ended_at("expired"),
+        "cancelling_at": batch.cancel_requested_at,
+        "cancelled_at": ended_at("cancelled"),
+        "request_counts": {
+            "total": batch.counts.total(),
+            "completed": batch.counts.succeeded,
+            "failed": batch.counts.errored + batch.counts.expired
+        },
+        "metadata": batch.metadata
+    })
+}
+
+/// 解析 JSONL 中的请求行 `{custom_id, method, url, body}`
+fn parse_openai_requests(lines: &[Value], endpoint: &str) -> Result<Vec<NewBatchItem>, HandlerError> {
+    let bad = |msg: String| (StatusCode::BAD_REQUEST, msg);
+    check_request_count(lines.len())?;
+
+    let mut seen = HashSet::new();
+    let mut items = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        let custom_id = line
+            .get("custom_id")
+            .and_then(|c| c.as_str())
+            .filter(|c| !c.is_empty())
+            .ok_or_else(|| bad(format!("Line {}: custom_id is required", i + 1)))?;
+        if !seen.insert(custom_id) {
+            return Err(bad(format!("Line {}: duplicate custom_id '{}'", i + 1, custom_id)));
+        }
+        let method = line.get("method").and_then(|m| m.as_str()).unwrap_or("POST");
+        if !method.eq_ignore_ascii_case("POST") {
+            return Err(bad(format!("Line {}: method must be POST", i + 1)));
+        }
+        let url = line.get("url").and_then(|u| u.as_str()).unwrap_or(endpoint);
+        if url != endpoint {
+            return Err(bad(format!(
+                "Line {}: url '{}' does not match the batch endpoint '{}'",
+                i + 1,
+                url,
+                endpoint
+            )));
+        }
+        let body = line
+            .get("body")
+            .filter(|b| b.get("model").and_then(|m| m.as_str()).is_some())
+            .cloned()
+            .ok_or_else(|| bad(format!("Line {}: body must be an object with a model", i + 1)))?;
+        items.push(NewBatchItem { custom_id: custom_id.to_string(), body });
+    }
+    Ok(items)
+}
+
+fn openai_item_id(batch: &BatchRecord, item: &BatchItem) -> String {
+    format!("batch_req_{}_{}", batch.id.trim_start_matches("batch_"), item.seq)
+}
+
+/// 输出文件一行 (成功的请求)
+fn openai_output_line(batch: &BatchRecord, item: &BatchItem) -> Value {
+    let id = openai_item_id(batch, item);
+    json!({
+        "id": id,
+        "custom_id": item.custom_id,
+        "response": {
+            "status_code": item.response_status.unwrap_or(200),
+            "request_id": id,
+            "body": item.response
+        },
+        "error": null
+    })
+}
+
+/// 错误文件一行 (失败 / 过期 / 取消的请求)
+fn openai_error_line(batch: &BatchRecord, item: &BatchItem) -> Value {
+    let id = openai_item_id(batch, item);
+    let response = item.response_status.map(|status_code| {
+        json!({ "status_code": status_code, "request_id": id, "body": item.response })
+    });
+    let error = match item.status {
+        ItemStatus::Expired => json!({
+            "code": "batch_expired",
+            "message": "This request could not be executed before the completion window expired."
+        }),
+        ItemStatus::Canceled => json!({
+            "code": "batch_cancelled",
+            "message": "This request was cancelled before it was executed."
+        }),
+        _ if response.is_some() => Value::Null,
+        _ => json!({
+            "code": "request_failed",
+            "message": item.error.clone().unwrap_or_else(|| "Request failed".to_string())
+        }),
+    };
+    json!({ "id": id, "custom_id": item.custom_id, "response": response, "error": error })
+}
+
+/// 读取 purpose=batch 上传的 JSONL 输入文件
+fn load_input_file(file_id: &str, owner: Option<&str>) -> Result<Vec<Value>, HandlerError> {
+    let bad = |msg: String| (StatusCode::BAD_REQUEST, msg);
+    let (file, bytes) = file_db::load_file(file_id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .filter(|(file, _)| owner.is_none() || file.owner_token_id.as_deref() == owner)
+        .ok_or_else(|| bad(format!("input_file_id '{}' not found", file_id)))?;
+    let text = String::from_utf8(bytes)
+        .map_err(|_| bad(format!("input file '{}' is not valid UTF-8 JSONL", file.id)))?;
+    parse_jsonl_lines(&text)
+}
+
+fn parse_jsonl_lines(text: &str) -> Result<Vec<Value>, HandlerError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("Line {}: invalid JSON: {}", i + 1, e)))
+        })
+        .collect()
+}
+
+/// POST /v1/batches
+///
+/// 请求行来自 `input_file_id` 指向的 JSONL 文件，或通过 `requests` 数组内联提交 (每项结构与文件中的一行相同)
+pub async fn handle_create_batch(
+    identity: Option<Extension<UserTokenIdentity>>,
+    request: Request,
+) -> Response {
+    let client_ip = extract_client_ip(&request);
+    let body = match read_json_body(request).await {
+        Ok(b) => b,
+        Err(e) => return openai_error(e),
+    };
+
+    let endpoint = body.get("endpoint").and_then(|e| e.as_str()).unwrap_or_default();
+    if !OPENAI_ENDPOINTS.contains(&endpoint) {
+        return openai_error((
+            StatusCode::BAD_REQUEST,
+            format!("endpoint must be one of: {}", OPENAI_ENDPOINTS.join(", ")),
+        ));
+    }
+    let window = body.get("completion_window").and_then(|w| w.as_str()).unwrap_or("24h");
+    if window != "24h" {
+        return openai_error((StatusCode::BAD_REQUEST, "completion_window must be '24h'".to_string()));
+    }
+    let owner = owner_of(&identity);
+    let input_file_id = body.get("input_file_id").and_then(|f| f.as_str());
+    let lines = match (input_file_id, body.get("requests").and_then(|r| r.as_array())) {
+        (Some(file_id), _) => match load_input_file(file_id, owner.as_deref()) {
+            Ok(lines) => lines,
+            Err(e) => return openai_error(e),
+        },
+        (None, Some(lines)) => lines.clone(),
+        (None, None) => {
+            return openai_error((
+                StatusCode::BAD_REQUEST,
+                "input_file_id is required (or pass the request lines inline as `requests`)".to_string(),
+            ))
+        }
+    };
+    let items = match parse_openai_requests(&lines, endpoint) {
+        Ok(items) => items,
+        Err(e) => return openai_error(e),
+    };
+
+    let id = format!("batch_{}", uuid::Uuid::new_v4().simple());
+    let metadata = body.get("metadata").filter(|m| m.is_object());
+    match batch_db::create_batch(
+        &id,
+        "openai",
+        endpoint,
+        owner.as_deref(),
+        client_ip.as_deref(),
+        metadata,
+        input_file_id,
+        &items,
+        BATCH_TTL_SECS,
+    ) {
+        Ok(batch) => {
+            tracing::info!("[Batch] Created {} ({}) with {} request(s)", batch.id, endpoint, items.len());
+            Json(openai_batch_json(&batch)).into_response()
+        }
+        Err(e) => openai_error((StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+/// GET /v1/batches
+pub async fn handle_list_batches(
+    identity: Option<Extension<UserTokenIdentity>>,
+    Query(query): Query<ListQuery>,
+) -> Response {
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    let owner = owner_of(&identity);
+    match batch_db::list_batches(Some("openai"), owner.as_deref(), query.after.as_deref(), None, limit) {
+        Ok((batches, has_more)) => Json(json!({
+            "object": "list",
+            "data": batches.iter().map(openai_batch_json).collect::<Vec<_>>(),
+            "first_id": batches.first().map(|b| b.id.clone()),
+            "last_id": batches.last().map(|b| b.id.clone()),
+            "has_more": has_more
+        }))
+        .into_response(),
+        Err(e) => openai_error((StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+/// GET /v1/batches/:batch_id
+pub async fn handle_get_batch(
+    identity: Option<Extension<UserTokenIdentity>>,
+    Path(batch_id): Path<String>,
+) -> Response {
+    match load_batch(&batch_id, "openai", owner_of(&identity).as_deref()) {
+        Ok(batch) => Json(openai_batch_json(&batch)).into_response(),
+        Err(e) => openai_error(e),
+    }
+}
+
+/// POST /v1/batches/:batch_id/cancel
+pub async fn handle_cancel_batch(
+    identity: Option<Extension<UserTokenIdentity>>,
+    Path(batch_id): Path<String>,
+) -> Response {
+    if let Err(e) = load_batch(&batch_id, "openai", owner_of(&identity).as_deref()) {
+        return openai_error(e);
+    }
+    match batch_db::cancel_batch(&batch_id) {
+        Ok(Some(batch)) => Json(openai_batch_json(&batch)).into_response(),
+        Ok(None) => openai_error((StatusCode::NOT_FOUND, format!("Batch '{}' not found", batch_id))),
+        Err(e) => openai_error((StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+/// GET /v1/batches/:batch_id/output (JSONL，已完成的成功请求)
+pub async fn handle_batch_output(
+    identity: Option<Extension<UserTokenIdentity>>,
+    Path(batch_id): Path<String>,
+) -> Response {
+    batch_file_response(identity, &batch_id, false)
+}
+
+/// GET /v1/batches/:batch_id/errors (JSONL，失败 / 过期 / 取消的请求)
+pub async fn handle_batch_errors(
+    identity: Option<Extension<UserTokenIdentity>>,
+    Path(batch_id): Path<String>,
+) -> Response {
+    batch_file_response(identity, &batch_id, true)
+}
+
+fn batch_file_response(identity: Option<Extension<UserTokenIdentity>>, batch_id: &str, errors: bool) -> Response {
+    match batch_output_lines(batch_id, owner_of(&identity).as_deref(), errors) {
+        Ok((_, lines)) => jsonl_response(lines),
+        Err(e) => openai_error(e),
+    }
+}
+
+/// 批次输出 / 错误文件的内容 (JSONL)，返回 (批次, 内容)
+pub(super) fn batch_output_content(
+    batch_id: &str,
+    owner: Option<&str>,
+    errors: bool,
+) -> Result<(BatchRecord, String), HandlerError> {
+    batch_output_lines(batch_id, owner, errors).map(|(batch, lines)| (batch, render_jsonl(&lines)))
+}
+
+fn batch_output_lines(
+    batch_id: &str,
+    owner: Option<&str>,
+    errors: bool,
+) -> Result<(BatchRecord, Vec<Value>), HandlerError> {
+    let batch = load_batch(batch_id, "openai", owner)?;
+    let items = load_items(batch_id)?;
+    let lines = items
+        .iter()
+        .filter(|item| match item.status {
+            ItemStatus::Succeeded => !errors,
+            ItemStatus::Errored | ItemStatus::Expired | ItemStatus::Canceled => errors,
+            ItemStatus::Queued | ItemStatus::Running => false,
+        })
+        .map(|item| {
+            if errors {
+                openai_error_line(&batch, item)
+            } else {
+                openai_output_line(&batch, item)
+            }
+        })
+        .collect();
+    Ok((batch, lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modules::batch_db::BatchCounts;
+
+    fn record(status: BatchStatus, counts: BatchCounts) -> BatchRecord {
+        BatchRecord {
+            id: "batch_abc".to_string(),
+            protocol: "openai".to_string(),
+            endpoint: "/v1/chat/completions".to_string(),
+            status,
+            owner_token_id: None,
+            client_ip: None,
+            metadata: None,
+            input_file_id: None,
+            created_at: 1_700_000_000,
+            expires_at: 1_700_086_400,
+            started_at: Some(1_700_000_010),
+            ended_at: (status == BatchStatus::Ended).then_some(1_700_000_100),
+            cancel_requested_at: None,
+            counts,
+        }
+    }
+
+    fn item(status: ItemStatus, response_status: Option<u16>, response: Option<Value>) -> BatchItem {
+        BatchItem {
+            seq: 3,
+            custom_id: "req-1".to_string(),
+            status,
+            attempts: 1,
+            response_status,
+            response,
+            error: Some("boom".to_string()),
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_parse_anthropic_requests_validates_custom_ids() {
+        let params = json!({"model": "claude-sonnet-4-5", "max_tokens": 16, "messages": [{"role": "user", "content": "hi"}]});
+        let ok = json!({"requests": [
+            {"custom_id": "a-1", "params": params},
+            {"custom_id": "a_2", "params": params}
+        ]});
+        assert_eq!(parse_anthropic_requests(&ok, None).unwrap().len(), 2);
+
+        let dup = json!({"requests": [
+            {"custom_id": "a", "params": params},
+            {"custom_id": "a", "params": params}
+        ]});
+        assert!(parse_anthropic_requests(&dup, None).unwrap_err().1.contains("duplicate"));
+
+        let bad_id = json!({"requests": [{"custom_id": "has space", "params": params}]});
+        assert!(parse_anthropic_requests(&bad_id, None).is_err());
+
+        let bad_params = json!({"requests": [{"custom_id": "a", "params": {"model": "x"}}]});
+        assert!(parse_anthropic_requests(&bad_params, None).unwrap_err().1.starts_with("requests.0.params"));
+    }
+
+    #[test]
+    fn test_parse_openai_requests_checks_url() {
+        let lines = vec![json!({
+            "custom_id": "r1",
+            "method": "POST",
+            "url": "/v1/embeddings",
+            "body": {"model": "text-embedding-3-small", "input": "hi"}
+        })];
+        assert!(parse_openai_requests(&lines, "/v1/embeddings").is_ok());
+        assert!(parse_openai_requests(&lines, "/v1/chat/completions").is_err());
+    }
+
+    #[test]
+    fn test_openai_status_mapping() {
+        let done = BatchCounts { succeeded: 2, ..Default::default() };
+        assert_eq!(openai_status(&record(BatchStatus::InProgress, done.clone())), "in_progress");
+        assert_eq!(openai_status(&record(BatchStatus::Ended, done.clone())), "completed");
+
+        let mut cancelled = record(BatchStatus::Ended, done);
+        cancelled.cancel_requested_at = Some(1_700_000_050);
+        assert_eq!(openai_status(&cancelled), "cancelled");
+
+        let expired = BatchCounts { succeeded: 1, expired: 1, ..Default::default() };
+        let batch = record(BatchStatus::Ended, expired);
+        assert_eq!(openai_status(&batch), "expired");
+        assert_eq!(openai_batch_json(&batch)["request_counts"]["failed"], 1);
+        assert_eq!(openai_batch_json(&batch)["expired_at"], 1_700_000_100);
+        assert_eq!(openai_batch_json(&batch)["output_file_id"], "file-batch_abc-output");
+        assert_eq!(openai_batch_json(&batch)["error_file_id"], "file-batch_abc-errors");
+        let running = record(BatchStatus::InProgress, BatchCounts { succeeded: 1, ..Default::default() });
+        assert!(openai_batch_json(&running)["output_file_id"].is_null());
+    }
+
+    #[test]
+    fn test_batch_output_file_ids_round_trip() {
+        assert_eq!(parse_batch_output_file_id("file-batch_abc-output"), Some(("batch_abc", false)));
+        assert_eq!(parse_batch_output_file_id(&batch_output_file_id("batch_abc", true)), Some(("batch_abc", true)));
+        assert_eq!(parse_batch_output_file_id("file-abc123"), None);
+        assert_eq!(parse_batch_output_file_id("file-batch_abc"), None);
+    }
+
+    #[test]
+    fn test_parse_jsonl_lines_skips_blank_lines() {
+        let lines = parse_jsonl_lines("{\"custom_id\":\"a\"}\n\n{\"custom_id\":\"b\"}\n").unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(parse_jsonl_lines("{\"a\":1}\nnot json").unwrap_err().1.starts_with("Line 2"));
+    }
+
+    #[test]
+    fn test_result_lines() {
+        let message = json!({"id": "msg_1", "type": "message"});
+        let line = anthropic_result_line(&item(ItemStatus::Succeeded, Some(200), Some(message.clone())));
+        assert_eq!(line["result"]["type"], "succeeded");
+        assert_eq!(line["result"]["message"], message);
+
+        let line = anthropic_result_line(&item(ItemStatus::Errored, None, None));
+        assert_eq!(line["result"]["error"]["error"]["message"], "boom");
+
+        let batch = record(BatchStatus::Ended, BatchCounts::default());
+        let line = openai_error_line(&batch, &item(ItemStatus::Expired, None, None));
+        assert_eq!(line["id"], "batch_req_abc_3");
+        assert_eq!(line["error"]["code"], "batch_expired");
+        assert!(line["response"].is_null());
+
+        let line = openai_error_line(&batch, &item(ItemStatus::Errored, Some(400), Some(json!({"error": {}}))));
+        assert_eq!(line["response"]["status_code"], 400);
+        assert!(line["error"].is_null());
+    }
+}
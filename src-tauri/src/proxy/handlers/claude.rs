@@ -2,7 +2,7 @@
 
 use axum::{
     body::Body,
-    extract::{Json, State},
+    extract::{Extension, Json, State},
     http::{header, StatusCode},
     response::{IntoResponse, Response},
 };
@@ -24,6 +24,9 @@ use crate::proxy::mappers::estimation_calibrator::get_calibrator;
 use crate::proxy::debug_logger;
 use crate::proxy::upstream::client::mask_email;
 use crate::proxy::common::client_adapter::CLIENT_ADAPTERS; // [NEW] Import Adapter Registry
+use crate::proxy::common::routing_rules::RouteContext;
+use crate::proxy::common::structured_output::{self, OutputSchema, SchemaCheckError};
+use crate::proxy::common::file_refs;
 use axum::http::HeaderMap;
 use std::sync::{atomic::Ordering, Arc};
 
@@ -125,10 +128,9 @@ fn apply_thinking_hints(
     // If level is provided
     if let Some(ref level) = hint.level {
         // Map to output_config.effort if not already set
-        if request.output_config.is_none() {
-            request.output_config = Some(crate::proxy::mappers::claude::models::OutputConfig {
-                effort: Some(level_to_effort(level)),
-            });
+        if request.output_config.as_ref().and_then(|c| c.effort.as_ref()).is_none() {
+            request.output_config.get_or_insert_with(Default::default).effort =
+                Some(level_to_effort(level));
             tracing::debug!("[{}] Applied thinking hint: effort={}", trace_id, level);
             applied = true;
         }
@@ -232,7 +234,7 @@ The structure MUST be as follows:
 
 // ===== 统一退避策略模块 =====
 // 移除本地重复定义，使用 common 中的统一实现
-use super::common::{determine_retry_strategy, apply_retry_strategy, should_rotate_account, RetryStrategy};
+use super::common::{determine_retry_strategy, apply_retry_strategy, should_rotate_account, select_fallback_model, with_fallback_header, identity_account_groups, RetryStrategy};
 
 // ===== 退避策略模块结束 =====
 
@@ -242,8 +244,14 @@ use super::common::{determine_retry_strategy, apply_retry_strategy, should_rotat
 pub async fn handle_messages(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(body): Json<Value>,
+    identity: Option<Extension<crate::proxy::middleware::auth::UserTokenIdentity>>,
+    Json(mut body): Json<Value>,
 ) -> Response {
+    // [NEW] Files API: 先把 file_id 引用展开为 base64，后续转换与上游转发都只看到内联数据
+    if let Err(e) = file_refs::resolve_claude_file_refs(&mut body, identity.as_ref().map(|Extension(i)| i.token_id.as_str())) {
+        return super::batches::anthropic_error(e);
+    }
+
     // [FIX] 保存原始请求体的完整副本，用于日志记录
     // 这确保了即使结构体定义遗漏字段，日志也能完整记录所有参数
     let original_body = body.clone();
@@ -290,6 +298,22 @@ pub async fn handle_messages(
     let thinking_hint = extract_thinking_hint(&original_body);
     apply_thinking_hints(&mut request, &thinking_hint, &trace_id);
 
+    // [NEW] 结构化输出: 兼容 beta 版顶层 output_format，统一到 output_config.format
+    if let Some(format) = original_body.get("output_format").filter(|f| !f.is_null()) {
+        let output_config = request.output_config.get_or_insert_with(Default::default);
+        if output_config.format.is_none() {
+            output_config.format = Some(format.clone());
+        }
+    }
+    let output_schema = OutputSchema::from_claude(
+        request.output_config.as_ref().and_then(|c| c.format.as_ref()),
+    );
+    let structured_cfg = crate::proxy::config::get_structured_output_config();
+    let validate_output = output_schema
+        .as_ref()
+        .map_or(false, |s| s.should_validate(&structured_cfg));
+    let mut schema_retried = false;
+
     if debug_logger::is_enabled(&debug_cfg) {
         // [FIX] 使用原始 body 副本记录日志，确保不丢失任何字段
         let original_payload = json!({
@@ -306,7 +330,43 @@ pub async fn handle_messages(
     let normalized_model = crate::proxy::common::model_mapping::normalize_to_standard_id(&request.model)
         .unwrap_or_else(|| request.model.clone());
 
-    let use_zai = if !zai_enabled {
+    // [NEW] 路由规则解析 (协议/身份/请求特征)，命中 z.ai 目标时优先于调度模式
+    let route_ctx = RouteContext::from_request(
+        &request.model,
+        "anthropic",
+        &original_body,
+        &headers,
+        identity.as_ref().map(|Extension(i)| i),
+    )
+    .with_zai_enabled(zai.enabled);
+    let route = state.model_router.read().await.resolve(&route_ctx);
+    let account_groups = identity_account_groups(&identity);
+
+    // [NEW] 自定义上游 (路由规则指定或 models 列表命中) 优先于 z.ai 与 Google 账号池
+    let provider_selection = crate::proxy::providers::registry::select_provider(
+        &state.token_manager,
+        crate::proxy::config::ProviderProtocol::Anthropic,
+        &request.model,
+        &route,
+        &account_groups,
+        &trace_id,
+    )
+    .await;
+
+    if route.use_zai && !zai.enabled {
+        tracing::warn!(
+            "[{}] Routing rule {:?} targets z.ai but z.ai is disabled, using Google flow",
+            trace_id,
+            route.rule_id
+        );
+    }
+
+    let use_zai = if provider_selection.is_some() {
+        false
+    } else if route.use_zai && zai.enabled {
+        tracing::info!("[{}] Routing rule {:?} dispatches to z.ai", trace_id, route.rule_id);
+        true
+    } else if !zai_enabled {
         false
     } else {
         match zai.dispatch_mode {
@@ -319,7 +379,7 @@ pub async fn handle_messages(
                     true
                 } else {
                     // [Issue #703 Fix] 智能判断:检查是否有可用的 Google 账号
-                    let has_available = state.token_manager.has_available_account("claude", &normalized_model).await;
+                    let has_available = state.token_manager.has_available_account("claude", &normalized_model, &account_groups).await;
                     if !has_available {
                         tracing::info!(
                             "[{}] All Google accounts unavailable (rate-limited or quota-protected for {}), using fallback provider",
@@ -349,7 +409,7 @@ pub async fn handle_messages(
     merge_consecutive_messages(&mut request.messages);
 
     // Get model family for signature validation
-    let target_family = if use_zai {
+    let target_family = if use_zai || provider_selection.is_some() {
         Some("claude")
     } else {
         let mapped_model = crate::proxy::common::model_mapping::map_claude_model_to_gemini(&request.model);
@@ -380,7 +440,24 @@ pub async fn handle_messages(
         return create_warmup_response(&request, request.stream);
     }
 
+    // 自定义上游使用客户端原始请求体 (Anthropic 原生支持 cache_control 等字段)
+    if let Some(selection) = provider_selection {
+        return crate::proxy::providers::compatible::forward(
+            &selection,
+            "/v1/messages",
+            &headers,
+            original_body,
+            state.upstream_proxy.read().await.clone(),
+            state.request_timeout,
+            &trace_id,
+        )
+        .await;
+    }
+
     if use_zai {
+        if route.use_zai {
+            request.model = route.mapped_model.clone();
+        }
         // 重新序列化修复后的请求体
         let new_body = match serde_json::to_value(&request) {
             Ok(v) => v,
@@ -525,11 +602,29 @@ pub async fn handle_messages(
     let mut last_status = StatusCode::SERVICE_UNAVAILABLE; // Default to 503 if no response reached
     
     for attempt in 0..max_attempts {
-        // 2. 模型路由解析
-        let mut mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
-            &request_for_body.model,
-            &*state.custom_mapping.read().await,
-        );
+        // 2. 模型路由解析 (重试时模型名可能被清理，按当前模型重新匹配)
+        let attempt_route = {
+            let attempt_ctx = RouteContext {
+                model: request_for_body.model.clone(),
+                ..route_ctx.clone()
+            };
+            state.model_router.read().await.resolve(&attempt_ctx)
+        };
+        let mut mapped_model = attempt_route.mapped_model.clone();
+
+        // [NEW] 模型降级链: 目标模型在整个账号池不可用时切换到后备模型
+        let mut fallback_from: Option<String> = None;
+        if let Some(fallback) = select_fallback_model(
+            &token_manager,
+            "claude",
+            &attempt_route.mapped_model,
+            &attempt_route.fallback_models,
+            &account_groups,
+            &trace_id,
+        ).await {
+            fallback_from = Some(attempt_route.mapped_model.clone());
+            mapped_model = fallback;
+        }
         last_mapped_model = Some(mapped_model.clone());
         
         // 将 Claude 工具转为 Value 数组以便探测联网
@@ -553,7 +648,7 @@ pub async fn handle_messages(
         let session_id = Some(session_id_str.as_str());
 
         let force_rotate_token = attempt > 0;
-        let (access_token, project_id, email, account_id, _wait_ms) = match token_manager.get_token(&config.request_type, force_rotate_token, session_id, &config.final_model).await {
+        let (access_token, project_id, email, account_id, _wait_ms) = match token_manager.get_token(&config.request_type, force_rotate_token, session_id, &config.final_model, &account_groups).await {
             Ok(t) => t,
             Err(e) => {
                 let safe_message = if e.contains("invalid_grant") {
@@ -596,8 +691,11 @@ pub async fn handle_messages(
             // [FIX] 必须根据虚拟 ID Re-resolve 路由，以支持用户自定义映射 (如 internal-task -> gemini-3)
             // 否则会直接使用 generic ID 导致下游无法识别或只能使用静态默认值
             let resolved_model = crate::proxy::common::model_mapping::resolve_model_route(
-                virtual_model_id, 
-                &*state.custom_mapping.read().await
+                &RouteContext {
+                    model: virtual_model_id.to_string(),
+                    ..route_ctx.clone()
+                },
+                &*state.model_router.read().await,
             );
 
             info!(
@@ -609,8 +707,9 @@ pub async fn handle_messages(
                 resolved_model
             );
             
-            // 覆盖用户自定义映射 (同时更新变量和 Request 对象)
+            // 覆盖用户自定义映射 (同时更新变量和 Request 对象)，后台任务不视为降级
             mapped_model = resolved_model.clone();
+            fallback_from = None;
             request_with_mapped.model = resolved_model;
             
             // 后台任务净化：
@@ -732,7 +831,7 @@ pub async fn handle_messages(
                 // Clone token_manager Arc to avoid borrow issues
                 let token_manager_clone = token_manager.clone();
                 
-                match try_compress_with_summary(&request_with_mapped, &trace_id, &token_manager_clone).await {
+                match try_compress_with_summary(&request_with_mapped, &trace_id, &token_manager_clone, &account_groups).await {
                     Ok(forked_request) => {
                         info!(
                             "[{}] [Layer-3] Fork successful: {} → {} messages",
@@ -779,8 +878,9 @@ pub async fn handle_messages(
 
         // [FIX] Estimate AFTER purification to get accurate token count for calibrator learning
         // Only estimate for calibrator when content was not purified, to avoid skewed learning
+        // [FIX] 只统计 prompt (不含预留的 thinking budget)，与上游 promptTokenCount 可比
         let raw_estimated = if !is_purified {
-            ContextManager::estimate_token_usage(&request_with_mapped)
+            ContextManager::estimate_prompt_tokens(&request_with_mapped)
         } else {
             0 // Don't record calibration data when content was purified
         };
@@ -930,6 +1030,36 @@ pub async fn handle_messages(
                     meta,
                 );
 
+                // [NEW] 严格结构化输出: 缓冲完整上游输出并按 Schema 校验，通过后重放给转换器
+                let gemini_stream = match output_schema.as_ref().filter(|_| validate_output) {
+                    Some(schema) => match structured_output::buffer_and_validate(gemini_stream, schema).await {
+                        Ok(chunks) => structured_output::replay(chunks),
+                        Err(SchemaCheckError::Stream(e)) => {
+                            tracing::warn!("[{}] {} while buffering structured output, retrying...", trace_id, e);
+                            last_error = e;
+                            continue;
+                        }
+                        Err(SchemaCheckError::Mismatch(reason)) => {
+                            tracing::warn!("[{}] Structured output does not match schema: {}", trace_id, reason);
+                            if structured_cfg.on_mismatch == crate::proxy::config::SchemaMismatchAction::Retry
+                                && !schema_retried
+                                && attempt + 1 < max_attempts
+                            {
+                                schema_retried = true;
+                                last_error = format!("Schema mismatch: {}", reason);
+                                continue;
+                            }
+                            return structured_output::mismatch_response(
+                                "anthropic",
+                                &reason,
+                                &email,
+                                &request_with_mapped.model,
+                            );
+                        }
+                    },
+                    None => gemini_stream,
+                };
+
                 let current_message_count = request_with_mapped.messages.len();
 
                 // [FIX #MCP] Extract registered tool names for MCP fuzzy matching
@@ -1016,17 +1146,20 @@ pub async fn handle_messages(
                         // 判断客户端期望的格式
                         if client_wants_stream {
                             // 客户端本就要 Stream，直接返回 SSE
-                            return Response::builder()
-                                .status(StatusCode::OK)
-                                .header(header::CONTENT_TYPE, "text/event-stream")
-                                .header(header::CACHE_CONTROL, "no-cache")
-                                .header(header::CONNECTION, "keep-alive")
-                                .header("X-Accel-Buffering", "no")
-                                .header("X-Account-Email", &email)
-                                .header("X-Mapped-Model", &request_with_mapped.model)
-                                .header("X-Context-Purified", if is_purified { "true" } else { "false" })
-                                .body(Body::from_stream(combined_stream))
-                                .unwrap();
+                            return with_fallback_header(
+                                Response::builder()
+                                    .status(StatusCode::OK)
+                                    .header(header::CONTENT_TYPE, "text/event-stream")
+                                    .header(header::CACHE_CONTROL, "no-cache")
+                                    .header(header::CONNECTION, "keep-alive")
+                                    .header("X-Accel-Buffering", "no")
+                                    .header("X-Account-Email", &email)
+                                    .header("X-Mapped-Model", &request_with_mapped.model)
+                                    .header("X-Context-Purified", if is_purified { "true" } else { "false" })
+                                    .body(Body::from_stream(combined_stream))
+                                    .unwrap(),
+                                fallback_from.as_deref(),
+                            );
                         } else {
                             // 客户端要非 Stream，需要收集完整响应并转换为 JSON
                             use crate::proxy::mappers::claude::collect_stream_to_json;
@@ -1034,14 +1167,17 @@ pub async fn handle_messages(
                             match collect_stream_to_json(combined_stream).await {
                                 Ok(full_response) => {
                                     info!("[{}] ✓ Stream collected and converted to JSON", trace_id);
-                                    return Response::builder()
-                                        .status(StatusCode::OK)
-                                        .header(header::CONTENT_TYPE, "application/json")
-                                        .header("X-Account-Email", &email)
-                                        .header("X-Mapped-Model", &request_with_mapped.model)
-                                        .header("X-Context-Purified", if is_purified { "true" } else { "false" })
-                                        .body(Body::from(serde_json::to_string(&full_response).unwrap()))
-                                        .unwrap();
+                                    return with_fallback_header(
+                                        Response::builder()
+                                            .status(StatusCode::OK)
+                                            .header(header::CONTENT_TYPE, "application/json")
+                                            .header("X-Account-Email", &email)
+                                            .header("X-Mapped-Model", &request_with_mapped.model)
+                                            .header("X-Context-Purified", if is_purified { "true" } else { "false" })
+                                            .body(Body::from(serde_json::to_string(&full_response).unwrap()))
+                                            .unwrap(),
+                                        fallback_from.as_deref(),
+                                    );
                                 }
                                 Err(e) => {
                                     return (StatusCode::INTERNAL_SERVER_ERROR, format!("Stream collection error: {}", e)).into_response();
@@ -1117,7 +1253,10 @@ pub async fn handle_messages(
                     cache_info
                 );
 
-                return (StatusCode::OK, [("X-Account-Email", email.as_str()), ("X-Mapped-Model", request_with_mapped.model.as_str())], Json(claude_response)).into_response();
+                return with_fallback_header(
+                    (StatusCode::OK, [("X-Account-Email", email.as_str()), ("X-Mapped-Model", request_with_mapped.model.as_str())], Json(claude_response)).into_response(),
+                    fallback_from.as_deref(),
+                );
             }
         }
         
@@ -1404,7 +1543,7 @@ pub async fn handle_list_models(State(state): State<AppState>) -> impl IntoRespo
     use crate::proxy::common::model_mapping::get_all_dynamic_models;
 
     let model_ids = get_all_dynamic_models(
-        &state.custom_mapping,
+        &state.model_router,
     ).await;
 
     let data: Vec<_> = model_ids.into_iter().map(|id| {
@@ -1426,10 +1565,58 @@ pub async fn handle_list_models(State(state): State<AppState>) -> impl IntoRespo
 pub async fn handle_count_tokens(
     State(state): State<AppState>,
     headers: HeaderMap,
-    Json(body): Json<Value>,
+    identity: Option<Extension<crate::proxy::middleware::auth::UserTokenIdentity>>,
+    Json(mut body): Json<Value>,
 ) -> Response {
+    if let Err(e) = file_refs::resolve_claude_file_refs(&mut body, identity.as_ref().map(|Extension(i)| i.token_id.as_str())) {
+        return super::batches::anthropic_error(e);
+    }
+
+    let body_model = body
+        .get("model")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
     let zai = state.zai.read().await.clone();
-    let zai_enabled = zai.enabled && !matches!(zai.dispatch_mode, crate::proxy::ZaiDispatchMode::Off);
+    let route = state.model_router.read().await.resolve(
+        &RouteContext::from_request(
+            &body_model,
+            "anthropic",
+            &body,
+            &headers,
+            identity.as_ref().map(|Extension(i)| i),
+        )
+        .with_zai_enabled(zai.enabled),
+    );
+
+    // [NEW] 与 /v1/messages 调度到同一 Anthropic 兼容上游时，由上游计数
+    if !body_model.is_empty() {
+        let selection = crate::proxy::providers::registry::select_provider(
+            &state.token_manager,
+            crate::proxy::config::ProviderProtocol::Anthropic,
+            &body_model,
+            &route,
+            &identity_account_groups(&identity),
+            "count_tokens",
+        )
+        .await;
+        if let Some(selection) = selection {
+            return crate::proxy::providers::compatible::forward(
+                &selection,
+                "/v1/messages/count_tokens",
+                &headers,
+                body,
+                state.upstream_proxy.read().await.clone(),
+                state.request_timeout,
+                "count_tokens",
+            )
+            .await;
+        }
+    }
+
+    // 与 /v1/messages 一致：路由规则指定 z.ai 时即使调度模式为 Off 也由 z.ai 计数
+    let zai_enabled = zai.enabled
+        && (route.use_zai || !matches!(zai.dispatch_mode, crate::proxy::ZaiDispatchMode::Off));
 
     if zai_enabled {
         return crate::proxy::providers::zai_anthropic::forward_anthropic_json(
@@ -1443,11 +1630,59 @@ pub async fn handle_count_tokens(
         .await;
     }
 
-    Json(json!({
-        "input_tokens": 0,
-        "output_tokens": 0
-    }))
-    .into_response()
+    let mut request: ClaudeRequest = match serde_json::from_value(body) {
+        Ok(r) => r,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "type": "error",
+                    "error": {
+                        "type": "invalid_request_error",
+                        "message": format!("Invalid request body: {}", e)
+                    }
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    let mapped_model = route.mapped_model.clone();
+    // thinking budget 属于输出预算，不计入输入 token
+    request.thinking = None;
+    request.model = mapped_model.clone();
+
+    // 1. 优先使用上游 countTokens 获取精确值
+    let upstream_count = match transform_claude_request_in(&request, "", false) {
+        Ok(gemini_body) => {
+            let contents =
+                crate::proxy::handlers::common::build_count_tokens_contents(&gemini_body["request"]);
+            crate::proxy::handlers::common::count_tokens_upstream(
+                &state,
+                &mapped_model,
+                contents,
+                &identity_account_groups(&identity),
+            )
+            .await
+        }
+        Err(e) => Err(format!("Transform failed: {}", e)),
+    };
+
+    // 2. 上游不可用时回退到本地估算 + 校准系数
+    let input_tokens = match upstream_count {
+        Ok(count) => count,
+        Err(e) => {
+            let raw = ContextManager::estimate_prompt_tokens(&request);
+            let calibrated = get_calibrator().calibrate(raw);
+            debug!(
+                "[CountTokens] Upstream count unavailable ({}), using estimate: raw={}, calibrated={}",
+                e, raw, calibrated
+            );
+            calibrated
+        }
+    };
+
+    Json(json!({ "input_tokens": input_tokens })).into_response()
 }
 
 // 移除已失效的简单单元测试，后续将补全完整的集成测试
@@ -1747,10 +1982,11 @@ async fn call_gemini_sync(
     request: &ClaudeRequest,
     token_manager: &Arc<crate::proxy::TokenManager>,
     trace_id: &str,
+    account_groups: &[String],
 ) -> Result<String, String> {
     // Get token and transform request
     let (access_token, project_id, _, _, _wait_ms) = token_manager
-        .get_token("gemini", false, None, model)
+        .get_token("gemini", false, None, model, account_groups)
         .await
         .map_err(|e| format!("Failed to get account: {}", e))?;
     
@@ -1816,6 +2052,7 @@ async fn try_compress_with_summary(
     original_request: &ClaudeRequest,
     trace_id: &str,
     token_manager: &Arc<crate::proxy::TokenManager>,
+    account_groups: &[String],
 ) -> Result<ClaudeRequest, String> {
     info!("[{}] [Layer-3] Starting context compression with XML summary", trace_id);
     
@@ -1871,6 +2108,7 @@ async fn try_compress_with_summary(
         &summary_request,
         token_manager,
         trace_id,
+        account_groups,
     ).await?;
     
     info!("[{}] [Layer-3] Generated XML summary (len: {} chars)", trace_id, xml_summary.len());
@@ -145,6 +145,92 @@ pub fn should_rotate_account(status_code: u16) -> bool {
     }
 }
 
+// ===== 模型降级链 =====
+
+/// 降级时附加的响应头，值为原目标模型
+pub const MODEL_FALLBACK_HEADER: &str = "X-Model-Fallback-From";
+
+/// 目标模型在整个账号池均不可用 (限流 / 配额保护) 时，返回降级链中首个仍有可用账号的模型
+///
+/// 目标模型可用、未配置降级链或整条链都不可用时返回 None (保持原模型，由 get_token 报错)
+pub async fn select_fallback_model(
+    token_manager: &crate::proxy::token_manager::TokenManager,
+    quota_group: &str,
+    mapped_model: &str,
+    fallback_models: &[String],
+    account_groups: &[String],
+    trace_id: &str,
+) -> Option<String> {
+    if fallback_models.is_empty()
+        || token_manager.len() == 0
+        || token_manager
+            .has_available_account(quota_group, mapped_model, account_groups)
+            .await
+    {
+        return None;
+    }
+
+    for candidate in fallback_models {
+        if token_manager
+            .has_available_account(quota_group, candidate, account_groups)
+            .await
+        {
+            info!(
+                "[{}] Model {} exhausted across the pool, falling back to {}",
+                trace_id, mapped_model, candidate
+            );
+            return Some(candidate.clone());
+        }
+    }
+
+    debug!(
+        "[{}] Fallback chain for {} exhausted as well: {:?}",
+        trace_id, mapped_model, fallback_models
+    );
+    None
+}
+
+/// User Token 绑定的账号分组 (无身份或未绑定分组时为空，即整个账号池)
+pub fn identity_account_groups(
+    identity: &Option<axum::Extension<crate::proxy::middleware::auth::UserTokenIdentity>>,
+) -> Vec<String> {
+    identity
+        .as_ref()
+        .map(|axum::Extension(i)| i.account_groups.clone())
+        .unwrap_or_default()
+}
+
+/// 发生降级时在响应中标注原目标模型
+pub fn with_fallback_header(mut response: Response, fallback_from: Option<&str>) -> Response {
+    if let Some(from) = fallback_from {
+        if let Ok(v) = axum::http::HeaderValue::from_str(from) {
+            response.headers_mut().insert(MODEL_FALLBACK_HEADER, v);
+        }
+    }
+    response
+}
+
+/// OpenAI 风格错误响应 (4xx 为 invalid_request_error，其余为 server_error)
+pub fn openai_error(status: StatusCode, message: &str) -> Response {
+    let error_type = if status.is_client_error() {
+        "invalid_request_error"
+    } else {
+        "server_error"
+    };
+    (
+        status,
+        Json(json!({
+            "error": {
+                "message": message,
+                "type": error_type,
+                "param": null,
+                "code": null
+            }
+        })),
+    )
+        .into_response()
+}
+
 /// Detects model capabilities and configuration
 /// POST /v1/models/detect
 pub async fn handle_detect_model(
@@ -158,9 +244,10 @@ pub async fn handle_detect_model(
     }
 
     // 1. Resolve mapping
+    // 静态能力探测不对应具体协议，自定义上游与 z.ai 规则不参与匹配
     let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
-        model_name,
-        &*state.custom_mapping.read().await,
+        &crate::proxy::common::routing_rules::RouteContext::for_model(model_name),
+        &*state.model_router.read().await,
     );
 
     // 2. Resolve capabilities
@@ -193,3 +280,130 @@ pub async fn handle_detect_model(
 
     Json(response).into_response()
 }
+
+// ===== Token 计数 (countTokens) =====
+
+/// 将 v1internal 内层请求 (`request` 字段) 整理为 countTokens 可接受的 contents
+///
+/// v1internal:countTokens 只接受 `contents`，因此将 systemInstruction 和 tools
+/// 折叠为额外的 user 文本片段，使计数结果覆盖完整的提示词开销。
+pub fn build_count_tokens_contents(inner_request: &Value) -> Vec<Value> {
+    let mut contents = Vec::new();
+
+    if let Some(parts) = inner_request
+        .get("systemInstruction")
+        .and_then(|s| s.get("parts"))
+        .and_then(|p| p.as_array())
+    {
+        if !parts.is_empty() {
+            contents.push(json!({ "role": "user", "parts": parts }));
+        }
+    }
+
+    if let Some(tools) = inner_request.get("tools").and_then(|t| t.as_array()) {
+        if !tools.is_empty() {
+            let tools_text = serde_json::to_string(tools).unwrap_or_default();
+            contents.push(json!({ "role": "user", "parts": [{ "text": tools_text }] }));
+        }
+    }
+
+    if let Some(msgs) = inner_request.get("contents").and_then(|c| c.as_array()) {
+        contents.extend(msgs.iter().cloned());
+    }
+
+    contents
+}
+
+/// 调用上游 v1internal:countTokens 获取精确的输入 token 数
+pub async fn count_tokens_upstream(
+    state: &AppState,
+    mapped_model: &str,
+    contents: Vec<Value>,
+    account_groups: &[String],
+) -> Result<u32, String> {
+    let (access_token, _project_id, email, account_id, _wait_ms) = state
+        .token_manager
+        .get_token("agent", false, None, mapped_model, account_groups)
+        .await?;
+
+    let body = json!({
+        "request": {
+            "model": format!("models/{}", mapped_model),
+            "contents": contents,
+        }
+    });
+
+    let call_result = state
+        .upstream
+        .call_v1_internal("countTokens", &access_token, body, None, Some(account_id.as_str()))
+        .await?;
+
+    let response = call_result.response;
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "countTokens returned {} (account: {}): {}",
+            status,
+            crate::proxy::upstream::client::mask_email(&email),
+            error_text
+        ));
+    }
+
+    let json: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Parse countTokens response failed: {}", e))?;
+
+    json.get("totalTokens")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as u32)
+        .ok_or_else(|| format!("countTokens response missing totalTokens: {}", json))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_count_tokens_contents_folds_system_and_tools() {
+        let inner = json!({
+            "systemInstruction": { "parts": [{ "text": "You are helpful" }] },
+            "tools": [{ "functionDeclarations": [{ "name": "read_file" }] }],
+            "contents": [
+                { "role": "user", "parts": [{ "text": "hi" }] },
+                { "role": "model", "parts": [{ "text": "hello" }] }
+            ]
+        });
+
+        let contents = build_count_tokens_contents(&inner);
+        assert_eq!(contents.len(), 4);
+        assert_eq!(contents[0]["parts"][0]["text"], "You are helpful");
+        assert!(contents[1]["parts"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("read_file"));
+        assert_eq!(contents[3]["role"], "model");
+    }
+
+    #[test]
+    fn test_build_count_tokens_contents_plain() {
+        let inner = json!({ "contents": [{ "role": "user", "parts": [{ "text": "hi" }] }] });
+        assert_eq!(build_count_tokens_contents(&inner).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_openai_error_shape() {
+        let resp = openai_error(StatusCode::BAD_REQUEST, "bad input");
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["message"], "bad input");
+        assert_eq!(body["error"]["type"], "invalid_request_error");
+
+        let resp = openai_error(StatusCode::BAD_GATEWAY, "upstream down");
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["type"], "server_error");
+    }
+}
@@ -0,0 +1,272 @@
+// Embeddings Handler
+// OpenAI /v1/embeddings 与 Gemini :embedContent / :batchEmbedContents 共用账号池
+
+use axum::{
+    extract::{Extension, Json, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde_json::{json, Value};
+use tracing::{debug, info};
+
+use super::common::{apply_retry_strategy, determine_retry_strategy, identity_account_groups, openai_error};
+use crate::proxy::common::routing_rules::RouteContext;
+use crate::proxy::middleware::auth::UserTokenIdentity;
+use crate::proxy::mappers::embeddings::{
+    build_embed_requests, build_gemini_embedding_response, build_openai_embedding_response,
+    estimate_request_tokens, extract_embeddings, gemini_body_to_requests,
+    normalize_embedding_model, openai_input_to_texts, OpenAIEmbeddingRequest,
+};
+use crate::proxy::server::AppState;
+use crate::proxy::upstream::client::mask_email;
+
+const MAX_RETRY_ATTEMPTS: usize = 3;
+
+/// Embedding 请求使用独立配额组，不读写对话请求的 60s 锁定 (见 TokenManager::get_token)
+const EMBEDDING_QUOTA_GROUP: &str = "embedding";
+
+/// 上游 Embedding 调用结果
+struct EmbedResult {
+    embeddings: Vec<Vec<f32>>,
+    email: String,
+}
+
+/// 调用 v1internal batchEmbedContents (含账号轮换重试)
+async fn embed_upstream(
+    state: &AppState,
+    mapped_model: &str,
+    requests: Vec<Value>,
+    account_groups: &[String],
+) -> Result<EmbedResult, (StatusCode, String)> {
+    let token_manager = &state.token_manager;
+    let max_attempts = MAX_RETRY_ATTEMPTS.min(token_manager.len()).max(1);
+    let trace_id = format!("emb_{}", chrono::Utc::now().timestamp_subsec_millis());
+    let mut last_error = String::new();
+
+    for attempt in 0..max_attempts {
+        let (access_token, project_id, email, account_id, _wait_ms) = match token_manager
+            .get_token(EMBEDDING_QUOTA_GROUP, attempt > 0, None, mapped_model, account_groups)
+            .await
+        {
+            Ok(t) => t,
+            Err(e) => return Err((StatusCode::SERVICE_UNAVAILABLE, format!("Token error: {}", e))),
+        };
+
+        let body = json!({
+            "project": project_id,
+            "model": mapped_model,
+            "request": { "requests": requests }
+        });
+
+        let response = match state
+            .upstream
+            .call_v1_internal(
+                "batchEmbedContents",
+                &access_token,
+                body,
+                None,
+                Some(account_id.as_str()),
+            )
+            .await
+        {
+            Ok(r) => r.response,
+            Err(e) => {
+                debug!(
+                    "[Embeddings] Request failed on attempt {}/{}: {}",
+                    attempt + 1,
+                    max_attempts,
+                    e
+                );
+                last_error = e;
+                continue;
+            }
+        };
+
+        let status = response.status();
+        if status.is_success() {
+            token_manager.mark_account_success(&email);
+            let json: Value = response
+                .json()
+                .await
+                .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Parse error: {}", e)))?;
+            let embeddings =
+                extract_embeddings(&json).map_err(|e| (StatusCode::BAD_GATEWAY, e))?;
+            info!(
+                "[Embeddings] ✓ {} vectors via {} (model: {})",
+                embeddings.len(),
+                mask_email(&email),
+                mapped_model
+            );
+            return Ok(EmbedResult { embeddings, email });
+        }
+
+        let status_code = status.as_u16();
+        let retry_after = response
+            .headers()
+            .get("Retry-After")
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.to_string());
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| format!("HTTP {}", status_code));
+        last_error = format!("HTTP {}: {}", status_code, error_text);
+
+        if status_code == 429 || status_code == 529 || status_code == 503 || status_code == 500 {
+            token_manager
+                .mark_rate_limited_async(
+                    &email,
+                    status_code,
+                    retry_after.as_deref(),
+                    &error_text,
+                    Some(mapped_model),
+                )
+                .await;
+        }
+
+        let strategy = determine_retry_strategy(status_code, &error_text, false);
+        if !apply_retry_strategy(strategy, attempt, max_attempts, status_code, &trace_id).await {
+            return Err((status, error_text));
+        }
+    }
+
+    Err((
+        StatusCode::TOO_MANY_REQUESTS,
+        format!("All accounts exhausted. Last error: {}", last_error),
+    ))
+}
+
+/// 记录 Embedding 用量到 token_stats (仅输入 token，按价格表计价)
+fn record_embedding_usage(email: &str, model: &str, prompt_tokens: u32, username: Option<String>) {
+    let email = email.to_string();
+    let model = model.to_string();
+    tokio::task::spawn_blocking(move || {
+        let usage = crate::modules::cost::TokenUsage {
+            input_tokens: prompt_tokens,
+            ..Default::default()
+        };
+        let cost = crate::modules::cost::estimate_cost(&[Some(model.as_str())], &usage).unwrap_or(0.0);
+        if let Err(e) = crate::modules::token_stats::record_usage(
+            &email,
+            &model,
+            username.as_deref(),
+            &usage,
+            cost,
+        ) {
+            debug!("Failed to record embedding token stats: {}", e);
+        }
+    });
+}
+
+/// OpenAI Embeddings API: POST /v1/embeddings
+pub async fn handle_openai_embeddings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    identity: Option<Extension<UserTokenIdentity>>,
+    Json(body): Json<Value>,
+) -> Response {
+    let req: OpenAIEmbeddingRequest = match serde_json::from_value(body.clone()) {
+        Ok(r) => r,
+        Err(e) => return openai_error(StatusCode::BAD_REQUEST, &format!("Invalid request: {}", e)),
+    };
+    let texts = match openai_input_to_texts(&req.input) {
+        Ok(t) => t,
+        Err(e) => return openai_error(StatusCode::BAD_REQUEST, &e),
+    };
+
+    let route_ctx = RouteContext::from_request(
+        &req.model,
+        "openai",
+        &body,
+        &headers,
+        identity.as_ref().map(|Extension(i)| i),
+    );
+    let mapped_model = normalize_embedding_model(
+        &crate::proxy::common::model_mapping::resolve_model_route(
+            &route_ctx,
+            &*state.model_router.read().await,
+        ),
+    );
+    let requests = build_embed_requests(&texts, &mapped_model, req.dimensions);
+    let prompt_tokens = estimate_request_tokens(&requests);
+
+    let account_groups = identity_account_groups(&identity);
+    match embed_upstream(&state, &mapped_model, requests, &account_groups).await {
+        Ok(result) => {
+            let username = identity.as_ref().map(|id| id.username.clone());
+            record_embedding_usage(&result.email, &mapped_model, prompt_tokens, username);
+            let resp = build_openai_embedding_response(
+                &result.embeddings,
+                &req.model,
+                prompt_tokens,
+                req.encoding_format.as_deref(),
+            );
+            (
+                StatusCode::OK,
+                [
+                    ("X-Account-Email", result.email.as_str()),
+                    ("X-Mapped-Model", mapped_model.as_str()),
+                ],
+                Json(resp),
+            )
+                .into_response()
+        }
+        Err((status, message)) => openai_error(status, &message),
+    }
+}
+
+/// Gemini 原生 Embedding: POST /v1beta/models/{model}:embedContent | :batchEmbedContents
+/// 由 gemini::handle_generate 按 method 分发
+pub async fn handle_gemini_embed(
+    state: &AppState,
+    model_name: &str,
+    method: &str,
+    body: &Value,
+    headers: &HeaderMap,
+    identity: Option<&UserTokenIdentity>,
+    account_groups: &[String],
+) -> Response {
+    let route_ctx = RouteContext::from_request(model_name, "gemini", body, headers, identity);
+    let mapped_model = normalize_embedding_model(
+        &crate::proxy::common::model_mapping::resolve_model_route(
+            &route_ctx,
+            &*state.model_router.read().await,
+        ),
+    );
+    let requests = match gemini_body_to_requests(method, body, &mapped_model) {
+        Ok(r) => r,
+        Err(e) => return gemini_error(StatusCode::BAD_REQUEST, &e),
+    };
+    let prompt_tokens = estimate_request_tokens(&requests);
+
+    match embed_upstream(state, &mapped_model, requests, account_groups).await {
+        Ok(result) => {
+            let username = identity.map(|id| id.username.clone());
+            record_embedding_usage(&result.email, &mapped_model, prompt_tokens, username);
+            (
+                StatusCode::OK,
+                [
+                    ("X-Account-Email", result.email.as_str()),
+                    ("X-Mapped-Model", mapped_model.as_str()),
+                ],
+                Json(build_gemini_embedding_response(method, &result.embeddings)),
+            )
+                .into_response()
+        }
+        Err((status, message)) => gemini_error(status, &message),
+    }
+}
+
+fn gemini_error(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(json!({
+            "error": {
+                "code": status.as_u16(),
+                "message": message,
+                "status": if status.is_client_error() { "INVALID_ARGUMENT" } else { "UPSTREAM_ERROR" }
+            }
+        })),
+    )
+        .into_response()
+}
@@ -0,0 +1,465 @@
+// Files Handler
+// Anthropic Files API 与 OpenAI Files API (/v1/files)
+// 文件保存在本地 (modules::file_db)，请求中引用的 file_id 由 proxy::common::file_refs 在转发前展开为内联数据
+
+use axum::{
+    extract::{Extension, Multipart, Path, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::batches::{self, HandlerError};
+use crate::modules::file_db::{self, NewFile, SaveFileError, StoredFile};
+use crate::proxy::config::get_files_config;
+use crate::proxy::middleware::auth::UserTokenIdentity;
+
+/// OpenAI 允许的 purpose
+const OPENAI_PURPOSES: [&str; 6] = ["assistants", "batch", "fine-tune", "vision", "user_data", "evals"];
+
+/// OpenAI expires_after.seconds 的取值范围 (1 小时 ~ 30 天)
+const EXPIRES_AFTER_SECS: std::ops::RangeInclusive<i64> = 3600..=30 * 24 * 3600;
+
+/// 按请求头区分返回格式：Anthropic SDK 总会携带 anthropic-version / anthropic-beta
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FileProtocol {
+    Anthropic,
+    OpenAI,
+}
+
+impl FileProtocol {
+    fn detect(headers: &HeaderMap) -> Self {
+        if headers.contains_key("anthropic-version") || headers.contains_key("anthropic-beta") {
+            Self::Anthropic
+        } else {
+            Self::OpenAI
+        }
+    }
+
+    fn error(self, e: HandlerError) -> Response {
+        match self {
+            Self::Anthropic => batches::anthropic_error(e),
+            Self::OpenAI => batches::openai_error(e),
+        }
+    }
+
+    fn new_id(self) -> String {
+        let id = uuid::Uuid::new_v4().simple();
+        match self {
+            Self::Anthropic => format!("file_{}", id),
+            Self::OpenAI => format!("file-{}", id),
+        }
+    }
+
+    fn file_json(self, file: &StoredFile) -> Value {
+        match self {
+            Self::Anthropic => json!({
+                "id": file.id,
+                "type": "file",
+                "filename": file.filename,
+                "mime_type": file.mime_type,
+                "size_bytes": file.size_bytes,
+                "created_at": batches::timestamp_rfc3339(Some(file.created_at)),
+                "downloadable": true
+            }),
+            Self::OpenAI => json!({
+                "id": file.id,
+                "object": "file",
+                "bytes": file.size_bytes,
+                "created_at": file.created_at,
+                "expires_at": file.expires_at,
+                "filename": file.filename,
+                "purpose": file.purpose.as_deref().unwrap_or("user_data"),
+                "status": "processed",
+                "status_details": null
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListQuery {
+    limit: Option<usize>,
+    before_id: Option<String>,
+    after_id: Option<String>,
+    /// OpenAI 分页参数
+    after: Option<String>,
+    purpose: Option<String>,
+}
+
+/// 文件管理端点，不计入配额也不写入请求日志
+pub fn is_files_path(uri: &str) -> bool {
+    uri.starts_with("/v1/files")
+}
+
+/// 按扩展名推断 MIME 类型 (上传未携带 Content-Type 时使用)
+pub fn guess_mime_type(filename: &str) -> &'static str {
+    let ext = filename.rsplit('.').next().unwrap_or_default().to_ascii_lowercase();
+    match ext.as_str() {
+        "pdf" => "application/pdf",
+        "txt" => "text/plain",
+        "md" | "markdown" => "text/markdown",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "json" => "application/json",
+        "jsonl" => "application/jsonl",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        _ => "application/octet-stream",
+    }
+}
+
+/// 用户令牌只能访问自己上传的文件；api_key / 未鉴权请求可见全部
+fn load_visible(id: &str, owner: Option<&str>) -> Result<StoredFile, HandlerError> {
+    file_db::get_file(id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        .filter(|file| owner.is_none() || file.owner_token_id.as_deref() == owner)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("File '{}' not found", id)))
+}
+
+#[derive(Debug, Default)]
+struct Upload {
+    filename: Option<String>,
+    content_type: Option<String>,
+    bytes: Option<Vec<u8>>,
+    purpose: Option<String>,
+    expires_after_anchor: Option<String>,
+    expires_after_seconds: Option<String>,
+}
+
+/// 解析 multipart 表单，文件内容边读边检查大小
+async fn read_upload(multipart: &mut Multipart, max_bytes: u64) -> Result<Upload, HandlerError> {
+    let bad = |e: axum::extract::multipart::MultipartError| {
+        (StatusCode::BAD_REQUEST, format!("Invalid multipart body: {}", e))
+    };
+    let mut upload = Upload::default();
+    while let Some(mut field) = multipart.next_field().await.map_err(bad)? {
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "file" => {
+                upload.filename = field.file_name().map(|s| s.to_string());
+                upload.content_type = field.content_type().map(|s| s.to_string());
+                let mut bytes = Vec::new();
+                while let Some(chunk) = field.chunk().await.map_err(bad)? {
+                    if (bytes.len() + chunk.len()) as u64 > max_bytes {
+                        return Err((
+                            StatusCode::PAYLOAD_TOO_LARGE,
+                            format!("File exceeds the maximum size of {} MB", max_bytes / 1024 / 1024),
+                        ));
+                    }
+                    bytes.extend_from_slice(&chunk);
+                }
+                upload.bytes = Some(bytes);
+            }
+            "purpose" => upload.purpose = Some(field.text().await.map_err(bad)?),
+            "expires_after[anchor]" => upload.expires_after_anchor = Some(field.text().await.map_err(bad)?),
+            "expires_after[seconds]" => upload.expires_after_seconds = Some(field.text().await.map_err(bad)?),
+            _ => {}
+        }
+    }
+    Ok(upload)
+}
+
+/// 计算过期时间：OpenAI expires_after 优先，否则使用 proxy.files.ttl_days (0 = 不过期)
+fn resolve_expires_at(upload: &Upload, now: i64, ttl_days: u32) -> Result<Option<i64>, HandlerError> {
+    let bad = |msg: &str| (StatusCode::BAD_REQUEST, msg.to_string());
+    if let Some(seconds) = &upload.expires_after_seconds {
+        if upload.expires_after_anchor.as_deref().unwrap_or("created_at") != "created_at" {
+            return Err(bad("expires_after[anchor] must be 'created_at'"));
+        }
+        let seconds = seconds
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .filter(|s| EXPIRES_AFTER_SECS.contains(s))
+            .ok_or_else(|| bad("expires_after[seconds] must be between 3600 and 2592000"))?;
+        return Ok(Some(now + seconds));
+    }
+    Ok((ttl_days > 0).then(|| now + ttl_days as i64 * 24 * 3600))
+}
+
+/// POST /v1/files (multipart: file [+ purpose, expires_after])
+pub async fn handle_upload_file(
+    identity: Option<Extension<UserTokenIdentity>>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Response {
+    let protocol = FileProtocol::detect(&headers);
+    let config = get_files_config();
+    let upload = match read_upload(&mut multipart, config.max_file_size_mb * 1024 * 1024).await {
+        Ok(u) => u,
+        Err(e) => return protocol.error(e),
+    };
+
+    if upload.bytes.is_none() {
+        return protocol.error((StatusCode::BAD_REQUEST, "file: field required".to_string()));
+    }
+    let purpose = match (protocol, upload.purpose.as_deref()) {
+        (FileProtocol::OpenAI, Some(p)) if OPENAI_PURPOSES.contains(&p) => Some(p.to_string()),
+        (FileProtocol::OpenAI, _) => {
+            return protocol.error((
+                StatusCode::BAD_REQUEST,
+                format!("purpose must be one of: {}", OPENAI_PURPOSES.join(", ")),
+            ))
+        }
+        (FileProtocol::Anthropic, _) => None,
+    };
+    let expires_at = match resolve_expires_at(&upload, chrono::Utc::now().timestamp(), config.ttl_days) {
+        Ok(e) => e,
+        Err(e) => return protocol.error(e),
+    };
+
+    let filename = upload.filename.clone().unwrap_or_else(|| "upload".to_string());
+    // 浏览器与部分 SDK 对未知类型统一发送 application/octet-stream，此时按扩展名推断
+    let mime_type = upload
+        .content_type
+        .clone()
+        .filter(|c| !c.is_empty() && c != "application/octet-stream")
+        .unwrap_or_else(|| guess_mime_type(&filename).to_string());
+    let id = protocol.new_id();
+    let owner = identity.map(|Extension(i)| i.token_id);
+    let max_total_bytes = config.max_total_size_mb * 1024 * 1024;
+
+    let bytes = upload.bytes.unwrap_or_default();
+    let saved = tokio::task::spawn_blocking(move || {
+        let file = NewFile {
+            id: &id,
+            filename: &filename,
+            mime_type: &mime_type,
+            purpose: purpose.as_deref(),
+            owner_token_id: owner.as_deref(),
+            expires_at,
+        };
+        file_db::save_file(&file, &bytes, max_total_bytes)
+    })
+    .await
+    .unwrap_or_else(|e| Err(SaveFileError::Other(e.to_string())));
+
+    match saved {
+        Ok(file) => {
+            tracing::info!("[Files] Stored {} ({}, {} bytes)", file.id, file.mime_type, file.size_bytes);
+            Json(protocol.file_json(&file)).into_response()
+        }
+        Err(e @ SaveFileError::StorageFull { .. }) => {
+            protocol.error((StatusCode::PAYLOAD_TOO_LARGE, e.to_string()))
+        }
+        Err(e) => protocol.error((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+/// GET /v1/files
+pub async fn handle_list_files(
+    identity: Option<Extension<UserTokenIdentity>>,
+    headers: HeaderMap,
+    Query(query): Query<ListQuery>,
+) -> Response {
+    let protocol = FileProtocol::detect(&headers);
+    let owner = batches::owner_of(&identity);
+    let limit = query.limit.unwrap_or(20).clamp(1, 1000);
+    let after = query.after_id.as_deref().or(query.after.as_deref());
+    match file_db::list_files(
+        owner.as_deref(),
+        query.purpose.as_deref(),
+        after,
+        query.before_id.as_deref(),
+        limit,
+    ) {
+        Ok((files, has_more)) => {
+            let data: Vec<Value> = files.iter().map(|f| protocol.file_json(f)).collect();
+            let first_id = files.first().map(|f| f.id.clone());
+            let last_id = files.last().map(|f| f.id.clone());
+            let body = match protocol {
+                FileProtocol::Anthropic => json!({
+                    "data": data, "has_more": has_more, "first_id": first_id, "last_id": last_id
+                }),
+                FileProtocol::OpenAI => json!({
+                    "object": "list", "data": data, "has_more": has_more, "first_id": first_id, "last_id": last_id
+                }),
+            };
+            Json(body).into_response()
+        }
+        Err(e) => protocol.error((StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+/// 批次输出 / 错误文件的元数据 (内容按需生成，不落盘)
+fn batch_output_json(
+    file_id: &str,
+    batch_id: &str,
+    owner: Option<&str>,
+    errors: bool,
+) -> Result<Value, HandlerError> {
+    let (batch, content) = batches::batch_output_content(batch_id, owner, errors)?;
+    Ok(json!({
+        "id": file_id,
+        "object": "file",
+        "bytes": content.len(),
+        "created_at": batch.ended_at.unwrap_or(batch.created_at),
+        "expires_at": null,
+        "filename": format!("{}_{}.jsonl", batch_id, if errors { "error" } else { "output" }),
+        "purpose": "batch_output",
+        "status": "processed",
+        "status_details": null
+    }))
+}
+
+/// GET /v1/files/:file_id
+pub async fn handle_get_file(
+    identity: Option<Extension<UserTokenIdentity>>,
+    headers: HeaderMap,
+    Path(file_id): Path<String>,
+) -> Response {
+    let protocol = FileProtocol::detect(&headers);
+    let owner = batches::owner_of(&identity);
+    if let Some((batch_id, errors)) = batches::parse_batch_output_file_id(&file_id) {
+        return match batch_output_json(&file_id, batch_id, owner.as_deref(), errors) {
+            Ok(body) => Json(body).into_response(),
+            Err(e) => protocol.error(e),
+        };
+    }
+    match load_visible(&file_id, owner.as_deref()) {
+        Ok(file) => Json(protocol.file_json(&file)).into_response(),
+        Err(e) => protocol.error(e),
+    }
+}
+
+/// GET /v1/files/:file_id/content
+pub async fn handle_file_content(
+    identity: Option<Extension<UserTokenIdentity>>,
+    headers: HeaderMap,
+    Path(file_id): Path<String>,
+) -> Response {
+    let protocol = FileProtocol::detect(&headers);
+    let owner = batches::owner_of(&identity);
+    if let Some((batch_id, errors)) = batches::parse_batch_output_file_id(&file_id) {
+        return match batches::batch_output_content(batch_id, owner.as_deref(), errors) {
+            Ok((_, content)) => ([(header::CONTENT_TYPE, "application/x-jsonl")], content).into_response(),
+            Err(e) => protocol.error(e),
+        };
+    }
+
+    let file = match load_visible(&file_id, owner.as_deref()) {
+        Ok(f) => f,
+        Err(e) => return protocol.error(e),
+    };
+    let content = {
+        let file = file.clone();
+        tokio::task::spawn_blocking(move || file_db::read_content(&file))
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()))
+    };
+    match content {
+        Ok(bytes) => {
+            // 文件名中的引号会破坏 Content-Disposition，统一替换
+            let disposition = format!("attachment; filename=\"{}\"", file.filename.replace('"', "_"));
+            (
+                [
+                    (header::CONTENT_TYPE, file.mime_type.clone()),
+                    (header::CONTENT_DISPOSITION, disposition),
+                ],
+                bytes,
+            )
+                .into_response()
+        }
+        Err(e) => protocol.error((StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+/// DELETE /v1/files/:file_id
+pub async fn handle_delete_file(
+    identity: Option<Extension<UserTokenIdentity>>,
+    headers: HeaderMap,
+    Path(file_id): Path<String>,
+) -> Response {
+    let protocol = FileProtocol::detect(&headers);
+    if batches::parse_batch_output_file_id(&file_id).is_some() {
+        return protocol.error((
+            StatusCode::BAD_REQUEST,
+            "Batch output files are removed together with their batch".to_string(),
+        ));
+    }
+    if let Err(e) = load_visible(&file_id, batches::owner_of(&identity).as_deref()) {
+        return protocol.error(e);
+    }
+    match file_db::delete_file(&file_id) {
+        Ok(_) => {
+            let body = match protocol {
+                FileProtocol::Anthropic => json!({ "id": file_id, "type": "file_deleted" }),
+                FileProtocol::OpenAI => json!({ "id": file_id, "object": "file", "deleted": true }),
+            };
+            Json(body).into_response()
+        }
+        Err(e) => protocol.error((StatusCode::INTERNAL_SERVER_ERROR, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored() -> StoredFile {
+        StoredFile {
+            id: "file-abc".to_string(),
+            filename: "report.pdf".to_string(),
+            mime_type: "application/pdf".to_string(),
+            size_bytes: 42,
+            sha256: "00".to_string(),
+            purpose: Some("user_data".to_string()),
+            owner_token_id: None,
+            created_at: 1_700_000_000,
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn test_protocol_detection_and_shapes() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(FileProtocol::detect(&headers), FileProtocol::OpenAI);
+        headers.insert("anthropic-beta", "files-api-2025-04-14".parse().unwrap());
+        assert_eq!(FileProtocol::detect(&headers), FileProtocol::Anthropic);
+
+        let anthropic = FileProtocol::Anthropic.file_json(&stored());
+        assert_eq!(anthropic["type"], "file");
+        assert_eq!(anthropic["size_bytes"], 42);
+        assert_eq!(anthropic["created_at"], "2023-11-14T22:13:20Z");
+
+        let openai = FileProtocol::OpenAI.file_json(&stored());
+        assert_eq!(openai["object"], "file");
+        assert_eq!(openai["bytes"], 42);
+        assert_eq!(openai["purpose"], "user_data");
+
+        assert!(FileProtocol::Anthropic.new_id().starts_with("file_"));
+        assert!(FileProtocol::OpenAI.new_id().starts_with("file-"));
+    }
+
+    #[test]
+    fn test_resolve_expires_at() {
+        let mut upload = Upload::default();
+        assert_eq!(resolve_expires_at(&upload, 1000, 0).unwrap(), None);
+        assert_eq!(resolve_expires_at(&upload, 1000, 1).unwrap(), Some(1000 + 86400));
+
+        upload.expires_after_seconds = Some("7200".to_string());
+        assert_eq!(resolve_expires_at(&upload, 1000, 1).unwrap(), Some(8200));
+
+        upload.expires_after_seconds = Some("60".to_string());
+        assert!(resolve_expires_at(&upload, 1000, 1).is_err());
+
+        upload.expires_after_seconds = Some("7200".to_string());
+        upload.expires_after_anchor = Some("last_active_at".to_string());
+        assert!(resolve_expires_at(&upload, 1000, 1).is_err());
+    }
+
+    #[test]
+    fn test_guess_mime_type() {
+        assert_eq!(guess_mime_type("a.PDF"), "application/pdf");
+        assert_eq!(guess_mime_type("notes.md"), "text/markdown");
+        assert_eq!(guess_mime_type("noext"), "application/octet-stream");
+    }
+}
@@ -1,7 +1,7 @@
 // Gemini Handler
 use axum::{
     extract::State,
-    extract::{Json, Path},
+    extract::{Extension, Json, Path},
     http::StatusCode,
     response::IntoResponse,
 };
@@ -9,10 +9,14 @@ use serde_json::{json, Value};
 use tracing::{debug, error, info};
 
 use crate::proxy::common::client_adapter::CLIENT_ADAPTERS;
+use crate::proxy::common::routing_rules::RouteContext;
 use crate::proxy::debug_logger;
 use crate::proxy::handlers::common::{
-    apply_retry_strategy, determine_retry_strategy, should_rotate_account,
+    apply_retry_strategy, determine_retry_strategy, identity_account_groups,
+    select_fallback_model, should_rotate_account, with_fallback_header,
 };
+use crate::proxy::mappers::context_manager::ContextManager;
+use crate::proxy::mappers::estimation_calibrator::get_calibrator;
 use crate::proxy::mappers::gemini::{unwrap_response, wrap_request};
 use crate::proxy::server::AppState;
 use crate::proxy::session_manager::SessionManager;
@@ -27,6 +31,7 @@ pub async fn handle_generate(
     State(state): State<AppState>,
     Path(model_action): Path<String>,
     headers: HeaderMap,          // [NEW] Extract headers for adapter detection
+    identity: Option<Extension<crate::proxy::middleware::auth::UserTokenIdentity>>,
     Json(mut body): Json<Value>, // 改为 mut 以支持修复提示词注入
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
     // 解析 model:method
@@ -52,6 +57,22 @@ pub async fn handle_generate(
         debug!("[{}] Client Adapter detected", trace_id);
     }
 
+    // [NEW] Embedding 方法交由独立处理器 (共享账号池，独立配额组)
+    if method == "embedContent" || method == "batchEmbedContents" {
+        return Ok(
+            crate::proxy::handlers::embeddings::handle_gemini_embed(
+                &state,
+                &model_name,
+                &method,
+                &body,
+                &headers,
+                identity.as_ref().map(|Extension(i)| i),
+                &identity_account_groups(&identity),
+            )
+            .await,
+        );
+    }
+
     // 1. 验证方法
     if method != "generateContent" && method != "streamGenerateContent" {
         return Err((
@@ -94,12 +115,32 @@ pub async fn handle_generate(
     let mut last_error = String::new();
     let mut last_email: Option<String> = None;
 
+    // 3. 模型路由解析 (按协议/身份/请求特征匹配路由规则)
+    let route_ctx = RouteContext::from_request(
+        &model_name,
+        "gemini",
+        &body,
+        &headers,
+        identity.as_ref().map(|Extension(i)| i),
+    );
+    let route = state.model_router.read().await.resolve(&route_ctx);
+    let account_groups = identity_account_groups(&identity);
+
     for attempt in 0..max_attempts {
-        // 3. 模型路由解析
-        let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
-            &model_name,
-            &*state.custom_mapping.read().await,
-        );
+        // [NEW] 模型降级链: 目标模型在整个账号池不可用时切换到后备模型
+        let (mapped_model, fallback_from) = match select_fallback_model(
+            &token_manager,
+            "gemini",
+            &route.mapped_model,
+            &route.fallback_models,
+            &account_groups,
+            &trace_id,
+        )
+        .await
+        {
+            Some(fallback) => (fallback, Some(route.mapped_model.clone())),
+            None => (route.mapped_model.clone(), None),
+        };
         // 提取 tools 列表以进行联网探测 (Gemini 风格可能是嵌套的)
         let tools_val: Option<Vec<Value>> =
             body.get("tools").and_then(|t| t.as_array()).map(|arr| {
@@ -138,6 +179,7 @@ pub async fn handle_generate(
                 attempt > 0,
                 Some(&session_id),
                 &config.final_model,
+                &account_groups,
             )
             .await
         {
@@ -414,16 +456,19 @@ pub async fn handle_generate(
 
                 if client_wants_stream {
                     let body = Body::from_stream(stream);
-                    return Ok(Response::builder()
-                        .header("Content-Type", "text/event-stream")
-                        .header("Cache-Control", "no-cache")
-                        .header("Connection", "keep-alive")
-                        .header("X-Accel-Buffering", "no")
-                        .header("X-Account-Email", &email)
-                        .header("X-Mapped-Model", &mapped_model)
-                        .body(body)
-                        .unwrap()
-                        .into_response());
+                    return Ok(with_fallback_header(
+                        Response::builder()
+                            .header("Content-Type", "text/event-stream")
+                            .header("Cache-Control", "no-cache")
+                            .header("Connection", "keep-alive")
+                            .header("X-Accel-Buffering", "no")
+                            .header("X-Account-Email", &email)
+                            .header("X-Mapped-Model", &mapped_model)
+                            .body(body)
+                            .unwrap()
+                            .into_response(),
+                        fallback_from.as_deref(),
+                    ));
                 } else {
                     // Collect to JSON
                     use crate::proxy::mappers::gemini::collector::collect_stream_to_json;
@@ -434,15 +479,18 @@ pub async fn handle_generate(
                                 session_id
                             );
                             let unwrapped = unwrap_response(&gemini_resp);
-                            return Ok((
-                                StatusCode::OK,
-                                [
-                                    ("X-Account-Email", email.as_str()),
-                                    ("X-Mapped-Model", mapped_model.as_str()),
-                                ],
-                                Json(unwrapped),
-                            )
-                                .into_response());
+                            return Ok(with_fallback_header(
+                                (
+                                    StatusCode::OK,
+                                    [
+                                        ("X-Account-Email", email.as_str()),
+                                        ("X-Mapped-Model", mapped_model.as_str()),
+                                    ],
+                                    Json(unwrapped),
+                                )
+                                    .into_response(),
+                                fallback_from.as_deref(),
+                            ));
                         }
                         Err(e) => {
                             error!("Stream collection error: {}", e);
@@ -500,15 +548,18 @@ pub async fn handle_generate(
             }
 
             let unwrapped = unwrap_response(&gemini_resp);
-            return Ok((
-                StatusCode::OK,
-                [
-                    ("X-Account-Email", email.as_str()),
-                    ("X-Mapped-Model", mapped_model.as_str()),
-                ],
-                Json(unwrapped),
-            )
-                .into_response());
+            return Ok(with_fallback_header(
+                (
+                    StatusCode::OK,
+                    [
+                        ("X-Account-Email", email.as_str()),
+                        ("X-Mapped-Model", mapped_model.as_str()),
+                    ],
+                    Json(unwrapped),
+                )
+                    .into_response(),
+                fallback_from.as_deref(),
+            ));
         }
 
         // 处理错误并重试
@@ -642,7 +693,7 @@ pub async fn handle_list_models(
     use crate::proxy::common::model_mapping::get_all_dynamic_models;
 
     // 获取所有动态模型列表（与 /v1/models 一致）
-    let model_ids = get_all_dynamic_models(&state.custom_mapping).await;
+    let model_ids = get_all_dynamic_models(&state.model_router).await;
 
     // 转换为 Gemini API 格式
     let models: Vec<_> = model_ids
@@ -675,20 +726,46 @@ pub async fn handle_get_model(Path(model_name): Path<String>) -> impl IntoRespon
 
 pub async fn handle_count_tokens(
     State(state): State<AppState>,
-    Path(_model_name): Path<String>,
-    Json(_body): Json<Value>,
+    Path(model_name): Path<String>,
+    headers: HeaderMap,
+    identity: Option<Extension<crate::proxy::middleware::auth::UserTokenIdentity>>,
+    Json(body): Json<Value>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let model_group = "gemini";
-    let (_access_token, _project_id, _, _, _wait_ms) = state
-        .token_manager
-        .get_token(model_group, false, None, "gemini")
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::SERVICE_UNAVAILABLE,
-                format!("Token error: {}", e),
-            )
-        })?;
+    let route_ctx = RouteContext::from_request(
+        &model_name,
+        "gemini",
+        &body,
+        &headers,
+        identity.as_ref().map(|Extension(i)| i),
+    );
+    let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
+        &route_ctx,
+        &*state.model_router.read().await,
+    );
+
+    // countTokens 请求体既可以是 { contents } 也可以是 { generateContentRequest: {...} }
+    let request = body.get("generateContentRequest").unwrap_or(&body);
+    let contents = crate::proxy::handlers::common::build_count_tokens_contents(request);
+
+    let total_tokens = match crate::proxy::handlers::common::count_tokens_upstream(
+        &state,
+        &mapped_model,
+        contents,
+        &identity_account_groups(&identity),
+    )
+    .await
+    {
+        Ok(count) => count,
+        Err(e) => {
+            let raw = ContextManager::estimate_gemini_token_usage(&body);
+            let calibrated = get_calibrator().calibrate(raw);
+            debug!(
+                "[CountTokens] Upstream count unavailable ({}), using estimate: raw={}, calibrated={}",
+                e, raw, calibrated
+            );
+            calibrated
+        }
+    };
 
-    Ok(Json(json!({"totalTokens": 0})))
+    Ok(Json(json!({ "totalTokens": total_tokens })))
 }
@@ -7,5 +7,8 @@ pub mod gemini;
 pub mod mcp;
 pub mod common;
 pub mod audio;  // 音频转录处理器
+pub mod embeddings; // Embedding 处理器 (OpenAI + Gemini)
+pub mod batches; // 批处理 API (Anthropic Message Batches + OpenAI Batch)
+pub mod files; // Files API (Anthropic + OpenAI)
 pub mod warmup; // 预热处理器
 
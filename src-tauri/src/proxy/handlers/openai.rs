@@ -1,6 +1,7 @@
 // OpenAI Handler
 use axum::{
-    extract::Json, extract::State, http::StatusCode, response::IntoResponse, response::Response,
+    extract::Extension, extract::Json, extract::Path, extract::State, http::StatusCode,
+    response::IntoResponse, response::Response,
 };
 use base64::Engine as _;
 use bytes::Bytes;
@@ -16,10 +17,16 @@ use crate::proxy::server::AppState;
 use crate::proxy::upstream::client::mask_email;
 
 const MAX_RETRY_ATTEMPTS: usize = 3;
+use super::batches::owner_of;
 use super::common::{
-    apply_retry_strategy, determine_retry_strategy, should_rotate_account, RetryStrategy,
+    apply_retry_strategy, determine_retry_strategy, identity_account_groups, openai_error,
+    select_fallback_model, should_rotate_account, with_fallback_header, RetryStrategy,
 };
 use crate::proxy::common::client_adapter::CLIENT_ADAPTERS; // [NEW] Adapter Registry
+use crate::proxy::common::routing_rules::RouteContext;
+use crate::proxy::common::structured_output::{self, OutputSchema, SchemaCheckError};
+use crate::proxy::common::file_refs;
+use crate::proxy::middleware::auth::UserTokenIdentity;
 use crate::proxy::session_manager::SessionManager;
 use axum::http::HeaderMap;
 use tokio::time::Duration;
@@ -28,8 +35,12 @@ use crate::modules::account;
 pub async fn handle_chat_completions(
     State(state): State<AppState>,
     headers: HeaderMap, // [CHANGED] Extract headers
+    identity: Option<Extension<UserTokenIdentity>>,
     Json(mut body): Json<Value>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
+    // [NEW] Files API: 展开 file_id 引用 (file / input_file / input_image)
+    file_refs::resolve_openai_file_refs(&mut body, identity.as_ref().map(|Extension(i)| i.token_id.as_str()))?;
+
     // [FIX] 保存原始请求体的完整副本，用于日志记录
     // 这确保了即使结构体定义遗漏字段，日志也能完整记录所有参数
     let original_body = body.clone();
@@ -103,6 +114,17 @@ pub async fn handle_chat_completions(
             });
     }
 
+    // [NEW] 结构化输出严格校验
+    let output_schema = openai_req
+        .response_format
+        .as_ref()
+        .and_then(|fmt| OutputSchema::from_openai(&fmt.r#type, fmt.json_schema.as_ref()));
+    let structured_cfg = crate::proxy::config::get_structured_output_config();
+    let validate_output = output_schema
+        .as_ref()
+        .map_or(false, |s| s.should_validate(&structured_cfg));
+    let mut schema_retried = false;
+
     let trace_id = format!("req_{}", chrono::Utc::now().timestamp_subsec_millis());
     info!(
         "[{}] OpenAI Chat Request: {} | {} messages | stream: {}",
@@ -150,12 +172,64 @@ pub async fn handle_chat_completions(
     let mut last_email: Option<String> = None;
 
     // 2. 模型路由解析 (移到循环外以支持在所有路径返回 X-Mapped-Model)
-    let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
+    let route_ctx = RouteContext::from_request(
         &openai_req.model,
-        &*state.custom_mapping.read().await,
+        "openai",
+        &original_body,
+        &headers,
+        identity.as_ref().map(|Extension(i)| i),
     );
+    let route = state.model_router.read().await.resolve(&route_ctx);
+    let account_groups = identity_account_groups(&identity);
+
+    // [NEW] 自定义上游 (路由规则指定或 models 列表命中) 直接透传，不占用 Google 账号池
+    if let Some(selection) = crate::proxy::providers::registry::select_provider(
+        &token_manager,
+        crate::proxy::config::ProviderProtocol::Openai,
+        &openai_req.model,
+        &route,
+        &account_groups,
+        &trace_id,
+    )
+    .await
+    {
+        // Responses 格式已转换为 messages，此时按转换后的结构转发
+        let forward_body = if is_responses_format {
+            serde_json::to_value(&openai_req).unwrap_or(original_body)
+        } else {
+            original_body
+        };
+        return Ok(crate::proxy::providers::compatible::forward(
+            &selection,
+            "/v1/chat/completions",
+            &headers,
+            forward_body,
+            state.upstream_proxy.read().await.clone(),
+            state.request_timeout,
+            &trace_id,
+        )
+        .await);
+    }
+
+    let mut mapped_model = route.mapped_model.clone();
+    let mut fallback_from: Option<String> = None;
 
     for attempt in 0..max_attempts {
+        // [NEW] 模型降级链: 目标模型在整个账号池不可用时切换到后备模型
+        if let Some(fallback) = select_fallback_model(
+            &token_manager,
+            "openai",
+            &route.mapped_model,
+            &route.fallback_models,
+            &account_groups,
+            &trace_id,
+        )
+        .await
+        {
+            fallback_from = Some(route.mapped_model.clone());
+            mapped_model = fallback;
+        }
+
         // 将 OpenAI 工具转为 Value 数组以便探测联网
         let tools_val: Option<Vec<Value>> = openai_req
             .tools
@@ -182,6 +256,7 @@ pub async fn handle_chat_completions(
                 attempt > 0,
                 Some(&session_id),
                 &mapped_model,
+                &account_groups,
             )
             .await
         {
@@ -347,6 +422,31 @@ pub async fn handle_chat_completions(
                     meta,
                 );
 
+                // [NEW] 严格结构化输出: 缓冲完整上游输出并按 Schema 校验，通过后重放给转换器
+                let gemini_stream = match output_schema.as_ref().filter(|_| validate_output) {
+                    Some(schema) => match structured_output::buffer_and_validate(gemini_stream, schema).await {
+                        Ok(chunks) => structured_output::replay(chunks),
+                        Err(SchemaCheckError::Stream(e)) => {
+                            tracing::warn!("[{}] {} while buffering structured output, retrying...", trace_id, e);
+                            last_error = e;
+                            continue;
+                        }
+                        Err(SchemaCheckError::Mismatch(reason)) => {
+                            tracing::warn!("[{}] Structured output does not match schema: {}", trace_id, reason);
+                            if structured_cfg.on_mismatch == crate::proxy::config::SchemaMismatchAction::Retry
+                                && !schema_retried
+                                && attempt + 1 < max_attempts
+                            {
+                                schema_retried = true;
+                                last_error = format!("Schema mismatch: {}", reason);
+                                continue;
+                            }
+                            return Ok(structured_output::mismatch_response("openai", &reason, &email, &mapped_model));
+                        }
+                    },
+                    None => gemini_stream,
+                };
+
                 // [P1 FIX] Enhanced Peek logic to handle heartbeats and slow start
                 // Pre-read until we find meaningful content, skip heartbeats
                 use crate::proxy::mappers::openai::streaming::create_openai_sse_stream;
@@ -431,16 +531,19 @@ pub async fn handle_chat_completions(
                 if client_wants_stream {
                     // 客户端请求流式，返回 SSE
                     let body = Body::from_stream(combined_stream);
-                    return Ok(Response::builder()
-                        .header("Content-Type", "text/event-stream")
-                        .header("Cache-Control", "no-cache")
-                        .header("Connection", "keep-alive")
-                        .header("X-Accel-Buffering", "no")
-                        .header("X-Account-Email", &email)
-                        .header("X-Mapped-Model", &mapped_model)
-                        .body(body)
-                        .unwrap()
-                        .into_response());
+                    return Ok(with_fallback_header(
+                        Response::builder()
+                            .header("Content-Type", "text/event-stream")
+                            .header("Cache-Control", "no-cache")
+                            .header("Connection", "keep-alive")
+                            .header("X-Accel-Buffering", "no")
+                            .header("X-Account-Email", &email)
+                            .header("X-Mapped-Model", &mapped_model)
+                            .body(body)
+                            .unwrap()
+                            .into_response(),
+                        fallback_from.as_deref(),
+                    ));
                 } else {
                     // 客户端请求非流式，但内部强制转为流式
                     // 收集流数据并聚合为 JSON
@@ -449,15 +552,18 @@ pub async fn handle_chat_completions(
                     match collect_stream_to_json(Box::pin(combined_stream)).await {
                         Ok(full_response) => {
                             info!("[{}] ✓ Stream collected and converted to JSON", trace_id);
-                            return Ok((
-                                StatusCode::OK,
-                                [
-                                    ("X-Account-Email", email.as_str()),
-                                    ("X-Mapped-Model", mapped_model.as_str()),
-                                ],
-                                Json(full_response),
-                            )
-                                .into_response());
+                            return Ok(with_fallback_header(
+                                (
+                                    StatusCode::OK,
+                                    [
+                                        ("X-Account-Email", email.as_str()),
+                                        ("X-Mapped-Model", mapped_model.as_str()),
+                                    ],
+                                    Json(full_response),
+                                )
+                                    .into_response(),
+                                fallback_from.as_deref(),
+                            ));
                         }
                         Err(e) => {
                             error!("[{}] Stream collection error: {}", trace_id, e);
@@ -478,15 +584,18 @@ pub async fn handle_chat_completions(
 
             let openai_response =
                 transform_openai_response(&gemini_resp, Some(&session_id), message_count);
-            return Ok((
-                StatusCode::OK,
-                [
-                    ("X-Account-Email", email.as_str()),
-                    ("X-Mapped-Model", mapped_model.as_str()),
-                ],
-                Json(openai_response),
-            )
-                .into_response());
+            return Ok(with_fallback_header(
+                (
+                    StatusCode::OK,
+                    [
+                        ("X-Account-Email", email.as_str()),
+                        ("X-Mapped-Model", mapped_model.as_str()),
+                    ],
+                    Json(openai_response),
+                )
+                    .into_response(),
+                fallback_from.as_deref(),
+            ));
         }
 
         // 处理特定错误并重试
@@ -743,12 +852,19 @@ pub async fn handle_chat_completions(
 /// 将 Prompt 转换为 Chat Message 格式，复用 handle_chat_completions
 pub async fn handle_completions(
     State(state): State<AppState>,
+    headers: HeaderMap,
+    identity: Option<Extension<UserTokenIdentity>>,
     Json(mut body): Json<Value>,
 ) -> Response {
     debug!(
         "Received /v1/completions or /v1/responses payload: {:?}",
         body
     );
+    if let Err(e) = file_refs::resolve_openai_file_refs(&mut body, identity.as_ref().map(|Extension(i)| i.token_id.as_str())) {
+        return e.into_response();
+    }
+    // 自定义上游按客户端原始格式转发
+    let original_body = body.clone();
 
     let is_codex_style = body.get("input").is_some() || body.get("instructions").is_some();
 
@@ -841,6 +957,17 @@ pub async fn handle_completions(
                                         }));
                                     }
                                 }
+                                // [NEW] 文件块 (input_file，file_id 已在入口展开为 file_data)
+                                else if part.get("type").and_then(|v| v.as_str())
+                                    == Some("input_file")
+                                {
+                                    if let Some(file_data) = part.get("file_data") {
+                                        image_parts.push(json!({
+                                            "type": "file",
+                                            "file": { "file_data": file_data, "filename": part.get("filename") }
+                                        }));
+                                    }
+                                }
                             }
                         }
 
@@ -1127,13 +1254,58 @@ pub async fn handle_completions(
     let mut last_email: Option<String> = None;
 
     // 2. 模型路由解析 (移到循环外以支持在所有路径返回 X-Mapped-Model)
-    let mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
+    let route_ctx = RouteContext::from_request(
         &openai_req.model,
-        &*state.custom_mapping.read().await,
+        "openai",
+        &original_body,
+        &headers,
+        identity.as_ref().map(|Extension(i)| i),
     );
+    let route = state.model_router.read().await.resolve(&route_ctx);
+    let account_groups = identity_account_groups(&identity);
     let trace_id = format!("req_{}", chrono::Utc::now().timestamp_subsec_millis());
 
+    // [NEW] 自定义上游直接透传，不占用 Google 账号池
+    if let Some(selection) = crate::proxy::providers::registry::select_provider(
+        &token_manager,
+        crate::proxy::config::ProviderProtocol::Openai,
+        &openai_req.model,
+        &route,
+        &account_groups,
+        &trace_id,
+    )
+    .await
+    {
+        return crate::proxy::providers::compatible::forward(
+            &selection,
+            "/v1/completions",
+            &headers,
+            original_body,
+            state.upstream_proxy.read().await.clone(),
+            state.request_timeout,
+            &trace_id,
+        )
+        .await;
+    }
+
+    let mut mapped_model = route.mapped_model.clone();
+    let mut fallback_from: Option<String> = None;
+
     for attempt in 0..max_attempts {
+        if let Some(fallback) = select_fallback_model(
+            &token_manager,
+            "openai",
+            &route.mapped_model,
+            &route.fallback_models,
+            &account_groups,
+            &trace_id,
+        )
+        .await
+        {
+            fallback_from = Some(route.mapped_model.clone());
+            mapped_model = fallback;
+        }
+
         // 3. 模型配置解析
         // 将 OpenAI 工具转为 Value 数组以便探测联网
         let tools_val: Option<Vec<Value>> = openai_req
@@ -1164,6 +1336,7 @@ pub async fn handle_completions(
                 force_rotate,
                 session_id,
                 &mapped_model,
+                &account_groups,
             )
             .await
         {
@@ -1322,15 +1495,18 @@ pub async fn handle_completions(
                     })
                     .chain(openai_stream);
 
-                    return Response::builder()
-                        .header("Content-Type", "text/event-stream")
-                        .header("Cache-Control", "no-cache")
-                        .header("Connection", "keep-alive")
-                        .header("X-Account-Email", &email)
-                        .header("X-Mapped-Model", &mapped_model)
-                        .body(Body::from_stream(combined_stream))
-                        .unwrap()
-                        .into_response();
+                    return with_fallback_header(
+                        Response::builder()
+                            .header("Content-Type", "text/event-stream")
+                            .header("Cache-Control", "no-cache")
+                            .header("Connection", "keep-alive")
+                            .header("X-Account-Email", &email)
+                            .header("X-Mapped-Model", &mapped_model)
+                            .body(Body::from_stream(combined_stream))
+                            .unwrap()
+                            .into_response(),
+                        fallback_from.as_deref(),
+                    );
                 } else {
                     // Forced Stream Internal -> Convert to Legacy JSON
                     // Use CHAT SSE Stream (so Collector can parse it)
@@ -1424,15 +1600,18 @@ pub async fn handle_completions(
                                 "usage": chat_resp.usage
                             });
 
-                            return (
-                                StatusCode::OK,
-                                [
-                                    ("X-Account-Email", email.as_str()),
-                                    ("X-Mapped-Model", mapped_model.as_str()),
-                                ],
-                                Json(legacy_resp),
-                            )
-                                .into_response();
+                            return with_fallback_header(
+                                (
+                                    StatusCode::OK,
+                                    [
+                                        ("X-Account-Email", email.as_str()),
+                                        ("X-Mapped-Model", mapped_model.as_str()),
+                                    ],
+                                    Json(legacy_resp),
+                                )
+                                    .into_response(),
+                                fallback_from.as_deref(),
+                            );
                         }
                         Err(e) => {
                             return (
@@ -1481,15 +1660,18 @@ pub async fn handle_completions(
                 "usage": chat_resp.usage
             });
 
-            return (
-                StatusCode::OK,
-                [
-                    ("X-Account-Email", email.as_str()),
-                    ("X-Mapped-Model", mapped_model.as_str()),
-                ],
-                Json(legacy_resp),
-            )
-                .into_response();
+            return with_fallback_header(
+                (
+                    StatusCode::OK,
+                    [
+                        ("X-Account-Email", email.as_str()),
+                        ("X-Mapped-Model", mapped_model.as_str()),
+                    ],
+                    Json(legacy_resp),
+                )
+                    .into_response(),
+                fallback_from.as_deref(),
+            );
         }
 
         // Handle errors and retry
@@ -1562,10 +1744,414 @@ pub async fn handle_completions(
     }
 }
 
+/// OpenAI Responses API: POST /v1/responses
+/// 原生 Responses 事件流 + previous_response_id 续接 (本地 ResponseStore)
+pub async fn handle_responses(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    identity: Option<Extension<UserTokenIdentity>>,
+    Json(mut body): Json<Value>,
+) -> Response {
+    use crate::proxy::mappers::responses::collector::collect_responses_stream;
+    use crate::proxy::mappers::responses::store::ResponseStore;
+    use crate::proxy::mappers::responses::{
+        create_responses_sse_stream, transform_responses_request, ResponsesRequest,
+        ResponsesStreamState,
+    };
+    use axum::body::Body;
+    use futures::StreamExt;
+
+    debug!("Received /v1/responses payload: {:?}", body);
+    if let Err((status, message)) =
+        file_refs::resolve_openai_file_refs(&mut body, identity.as_ref().map(|Extension(i)| i.token_id.as_str()))
+    {
+        return openai_error(status, &message);
+    }
+
+    let responses_req: ResponsesRequest = match serde_json::from_value(body.clone()) {
+        Ok(req) => req,
+        Err(e) => {
+            return openai_error(StatusCode::BAD_REQUEST, &format!("Invalid request: {}", e));
+        }
+    };
+
+    let route_ctx = RouteContext::from_request(
+        &responses_req.model,
+        "openai",
+        &body,
+        &headers,
+        identity.as_ref().map(|Extension(i)| i),
+    );
+    let route = state.model_router.read().await.resolve(&route_ctx);
+    let account_groups = identity_account_groups(&identity);
+    let trace_id = format!("req_{}", chrono::Utc::now().timestamp_subsec_millis());
+
+    // [NEW] 自定义上游：原样转发 Responses 请求，previous_response_id 与存储由上游自行维护
+    if let Some(selection) = crate::proxy::providers::registry::select_provider(
+        &state.token_manager,
+        crate::proxy::config::ProviderProtocol::Openai,
+        &responses_req.model,
+        &route,
+        &account_groups,
+        &trace_id,
+    )
+    .await
+    {
+        return crate::proxy::providers::compatible::forward(
+            &selection,
+            "/v1/responses",
+            &headers,
+            body,
+            state.upstream_proxy.read().await.clone(),
+            state.request_timeout,
+            &trace_id,
+        )
+        .await;
+    }
+
+    // 1. 加载 previous_response_id 对应的历史对话
+    let history = match responses_req.previous_response_id.as_deref() {
+        Some(prev_id) => match ResponseStore::global().get(prev_id, owner_of(&identity).as_deref()) {
+            Some(stored) => stored.messages,
+            None => {
+                return openai_error(
+                    StatusCode::NOT_FOUND,
+                    &format!("Previous response with id '{}' not found.", prev_id),
+                );
+            }
+        },
+        None => Vec::new(),
+    };
+
+    let (openai_req, conversation) = match transform_responses_request(&responses_req, &history) {
+        Ok(r) => r,
+        Err(e) => return openai_error(StatusCode::BAD_REQUEST, &e),
+    };
+    let store_conversation = responses_req.should_store().then_some(conversation);
+
+    // [NEW] 结构化输出严格校验 (text.format 已映射为 response_format)
+    let output_schema = openai_req
+        .response_format
+        .as_ref()
+        .and_then(|fmt| OutputSchema::from_openai(&fmt.r#type, fmt.json_schema.as_ref()));
+    let structured_cfg = crate::proxy::config::get_structured_output_config();
+    let validate_output = output_schema
+        .as_ref()
+        .map_or(false, |s| s.should_validate(&structured_cfg));
+    let mut schema_retried = false;
+
+    let upstream = state.upstream.clone();
+    let token_manager = state.token_manager;
+    let pool_size = token_manager.len();
+    let max_attempts = MAX_RETRY_ATTEMPTS.min(pool_size.saturating_add(1)).max(2);
+
+    let mut last_error = String::new();
+    let mut last_email: Option<String> = None;
+
+    let mut mapped_model = route.mapped_model.clone();
+    let mut fallback_from: Option<String> = None;
+
+    for attempt in 0..max_attempts {
+        if let Some(fallback) = select_fallback_model(
+            &token_manager,
+            "openai",
+            &route.mapped_model,
+            &route.fallback_models,
+            &account_groups,
+            &trace_id,
+        )
+        .await
+        {
+            fallback_from = Some(route.mapped_model.clone());
+            mapped_model = fallback;
+        }
+
+        let config = crate::proxy::mappers::common_utils::resolve_request_config(
+            &openai_req.model,
+            &mapped_model,
+            &openai_req.tools,
+            None, // size
+            None, // quality
+            None, // image_size
+            None, // body
+        );
+
+        let session_id_str = SessionManager::extract_openai_session_id(&openai_req);
+        let force_rotate = attempt > 0;
+
+        let (access_token, project_id, email, account_id, _wait_ms) = match token_manager
+            .get_token(
+                &config.request_type,
+                force_rotate,
+                Some(session_id_str.as_str()),
+                &mapped_model,
+                &account_groups,
+            )
+            .await
+        {
+            Ok(t) => t,
+            Err(e) => {
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    [("X-Mapped-Model", mapped_model)],
+                    format!("Token error: {}", e),
+                )
+                    .into_response()
+            }
+        };
+
+        last_email = Some(email.clone());
+        info!("✓ Using account: {} (type: {})", email, config.request_type);
+
+        let (gemini_body, session_id, message_count) =
+            transform_openai_request(&openai_req, &project_id, &mapped_model);
+
+        // 内部始终使用流式请求，非流式由 collector 聚合
+        let call_result = match upstream
+            .call_v1_internal(
+                "streamGenerateContent",
+                &access_token,
+                gemini_body,
+                Some("alt=sse"),
+                Some(account_id.as_str()),
+            )
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                last_error = e.clone();
+                debug!(
+                    "[Responses] Request failed on attempt {}/{}: {}",
+                    attempt + 1,
+                    max_attempts,
+                    e
+                );
+                continue;
+            }
+        };
+
+        let response = call_result.response;
+        let status = response.status();
+        if status.is_success() {
+            token_manager.mark_account_success(&email);
+
+            // Peek 首个数据块，空流 / 错误 / 超时则换号重试
+            let mut gemini_stream = Box::pin(response.bytes_stream());
+            let first_chunk = loop {
+                match tokio::time::timeout(Duration::from_secs(60), gemini_stream.next()).await {
+                    Ok(Some(Ok(bytes))) => {
+                        if bytes.is_empty() {
+                            continue;
+                        }
+                        let text = String::from_utf8_lossy(&bytes);
+                        if text.trim().starts_with(':') {
+                            continue;
+                        }
+                        if text.contains("\"error\"") {
+                            last_error = "Error event during peek".to_string();
+                            break None;
+                        }
+                        break Some(bytes);
+                    }
+                    Ok(Some(Err(e))) => {
+                        last_error = format!("Stream error during peek: {}", e);
+                        break None;
+                    }
+                    Ok(None) => {
+                        last_error = "Empty response stream".to_string();
+                        break None;
+                    }
+                    Err(_) => {
+                        last_error = "Timeout waiting for first data".to_string();
+                        break None;
+                    }
+                }
+            };
+            let Some(first_chunk) = first_chunk else {
+                continue;
+            };
+
+            let combined_stream = futures::stream::once(async move {
+                Ok::<Bytes, rquest::Error>(first_chunk)
+            })
+            .chain(gemini_stream);
+
+            // [NEW] 严格结构化输出: 缓冲完整上游输出并按 Schema 校验，通过后重放给转换器
+            let combined_stream: std::pin::Pin<
+                Box<dyn futures::Stream<Item = Result<Bytes, rquest::Error>> + Send>,
+            > = match output_schema.as_ref().filter(|_| validate_output) {
+                Some(schema) => match structured_output::buffer_and_validate(Box::pin(combined_stream), schema).await {
+                    Ok(chunks) => structured_output::replay(chunks),
+                    Err(SchemaCheckError::Stream(e)) => {
+                        last_error = e;
+                        continue;
+                    }
+                    Err(SchemaCheckError::Mismatch(reason)) => {
+                        tracing::warn!("[{}] Structured output does not match schema: {}", trace_id, reason);
+                        if structured_cfg.on_mismatch == crate::proxy::config::SchemaMismatchAction::Retry
+                            && !schema_retried
+                            && attempt + 1 < max_attempts
+                        {
+                            schema_retried = true;
+                            last_error = format!("Schema mismatch: {}", reason);
+                            continue;
+                        }
+                        return structured_output::mismatch_response("openai", &reason, &email, &mapped_model);
+                    }
+                },
+                None => Box::pin(combined_stream),
+            };
+
+            let stream_state =
+                ResponsesStreamState::new(openai_req.model.clone(), session_id, message_count)
+                    .with_owner(owner_of(&identity));
+
+            if openai_req.stream {
+                let sse_stream = create_responses_sse_stream(
+                    Box::pin(combined_stream),
+                    stream_state,
+                    store_conversation,
+                );
+                return with_fallback_header(
+                    Response::builder()
+                        .header("Content-Type", "text/event-stream")
+                        .header("Cache-Control", "no-cache")
+                        .header("Connection", "keep-alive")
+                        .header("X-Account-Email", &email)
+                        .header("X-Mapped-Model", &mapped_model)
+                        .body(Body::from_stream(sse_stream))
+                        .unwrap()
+                        .into_response(),
+                    fallback_from.as_deref(),
+                );
+            }
+
+            return match collect_responses_stream(
+                Box::pin(combined_stream),
+                stream_state,
+                store_conversation,
+            )
+            .await
+            {
+                Ok(resp) => with_fallback_header(
+                    (
+                        StatusCode::OK,
+                        [
+                            ("X-Account-Email", email.as_str()),
+                            ("X-Mapped-Model", mapped_model.as_str()),
+                        ],
+                        Json(resp),
+                    )
+                        .into_response(),
+                    fallback_from.as_deref(),
+                ),
+                Err(e) => openai_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    &format!("Stream collection error: {}", e),
+                ),
+            };
+        }
+
+        // Handle errors and retry
+        let status_code = status.as_u16();
+        let retry_after = response
+            .headers()
+            .get("Retry-After")
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.to_string());
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| format!("HTTP {}", status_code));
+        last_error = format!("HTTP {}: {}", status_code, error_text);
+
+        tracing::error!(
+            "[Responses-Upstream] Error Response {}: {}",
+            status_code,
+            error_text
+        );
+
+        if status_code == 429 || status_code == 529 || status_code == 503 || status_code == 500 {
+            token_manager
+                .mark_rate_limited_async(
+                    &email,
+                    status_code,
+                    retry_after.as_deref(),
+                    &error_text,
+                    Some(&mapped_model),
+                )
+                .await;
+        }
+
+        let strategy = determine_retry_strategy(status_code, &error_text, false);
+        if apply_retry_strategy(strategy, attempt, max_attempts, status_code, &trace_id).await {
+            continue;
+        }
+        return (
+            status,
+            [
+                ("X-Account-Email", email.as_str()),
+                ("X-Mapped-Model", mapped_model.as_str()),
+            ],
+            error_text,
+        )
+            .into_response();
+    }
+
+    let mut resp = openai_error(
+        StatusCode::TOO_MANY_REQUESTS,
+        &format!("All accounts exhausted. Last error: {}", last_error),
+    );
+    if let Ok(v) = mapped_model.parse() {
+        resp.headers_mut().insert("X-Mapped-Model", v);
+    }
+    if let Some(v) = last_email.and_then(|e| e.parse().ok()) {
+        resp.headers_mut().insert("X-Account-Email", v);
+    }
+    resp
+}
+
+/// OpenAI Responses API: GET /v1/responses/:id
+pub async fn handle_get_response(
+    Path(response_id): Path<String>,
+    identity: Option<Extension<UserTokenIdentity>>,
+) -> Response {
+    use crate::proxy::mappers::responses::store::ResponseStore;
+
+    match ResponseStore::global().get(&response_id, owner_of(&identity).as_deref()) {
+        Some(stored) => Json(stored.response).into_response(),
+        None => openai_error(
+            StatusCode::NOT_FOUND,
+            &format!("Response with id '{}' not found.", response_id),
+        ),
+    }
+}
+
+/// OpenAI Responses API: DELETE /v1/responses/:id
+pub async fn handle_delete_response(
+    Path(response_id): Path<String>,
+    identity: Option<Extension<UserTokenIdentity>>,
+) -> Response {
+    use crate::proxy::mappers::responses::store::ResponseStore;
+
+    if !ResponseStore::global().remove(&response_id, owner_of(&identity).as_deref()) {
+        return openai_error(
+            StatusCode::NOT_FOUND,
+            &format!("Response with id '{}' not found.", response_id),
+        );
+    }
+    Json(json!({
+        "id": response_id,
+        "object": "response.deleted",
+        "deleted": true
+    }))
+    .into_response()
+}
+
 pub async fn handle_list_models(State(state): State<AppState>) -> impl IntoResponse {
     use crate::proxy::common::model_mapping::get_all_dynamic_models;
 
-    let model_ids = get_all_dynamic_models(&state.custom_mapping).await;
+    let model_ids = get_all_dynamic_models(&state.model_router).await;
 
     let data: Vec<_> = model_ids
         .into_iter()
@@ -1589,6 +2175,7 @@ pub async fn handle_list_models(State(state): State<AppState>) -> impl IntoRespo
 /// 处理图像生成请求，转换为 Gemini API 格式
 pub async fn handle_images_generations(
     State(state): State<AppState>,
+    identity: Option<Extension<UserTokenIdentity>>,
     Json(body): Json<Value>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
     // 1. 解析请求参数
@@ -1662,6 +2249,7 @@ pub async fn handle_images_generations(
     // 注意：不再在外部获取 Token，而是移入 Task 内部并在重试时获取
     let upstream = state.upstream.clone();
     let token_manager = state.token_manager.clone();
+    let account_groups = identity_account_groups(&identity);
     let max_pool_size = token_manager.len();
     let max_attempts = MAX_RETRY_ATTEMPTS
         .min(max_pool_size.saturating_add(1))
@@ -1672,6 +2260,7 @@ pub async fn handle_images_generations(
     for _ in 0..n {
         let upstream = upstream.clone();
         let token_manager = token_manager.clone();
+        let account_groups = account_groups.clone();
         let final_prompt = final_prompt.clone();
         let image_config = image_config.clone(); // 使用解析后的完整配置
         let _response_format = response_format.to_string();
@@ -1684,7 +2273,7 @@ pub async fn handle_images_generations(
             for attempt in 0..max_attempts {
                 // 4.1 获取 Token
                 let (access_token, project_id, email, account_id, _wait_ms) = match token_manager
-                    .get_token("image_gen", attempt > 0, None, "gemini-3-pro-image")
+                    .get_token("image_gen", attempt > 0, None, "gemini-3-pro-image", &account_groups)
                     .await
                 {
                     Ok(t) => t,
@@ -1898,6 +2487,7 @@ pub async fn handle_images_generations(
 
 pub async fn handle_images_edits(
     State(state): State<AppState>,
+    identity: Option<Extension<UserTokenIdentity>>,
     mut multipart: axum::extract::Multipart,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
     tracing::info!("[Images] Received edit request");
@@ -2067,6 +2657,7 @@ pub async fn handle_images_edits(
     // 注意：不再在外部获取 Token，而是移入 Task 内部
     let upstream = state.upstream.clone();
     let token_manager = state.token_manager.clone();
+    let account_groups = identity_account_groups(&identity);
     let max_pool_size = token_manager.len();
     let max_attempts = MAX_RETRY_ATTEMPTS
         .min(max_pool_size.saturating_add(1))
@@ -2076,6 +2667,7 @@ pub async fn handle_images_edits(
     for _ in 0..n {
         let upstream = upstream.clone();
         let token_manager = token_manager.clone();
+        let account_groups = account_groups.clone();
         let contents_parts = contents_parts.clone();
         let image_config = image_config.clone();
         let response_format = response_format.clone();
@@ -2087,7 +2679,7 @@ pub async fn handle_images_edits(
             for attempt in 0..max_attempts {
                 // 4.1 获取 Token
                 let (access_token, project_id, email, account_id, _wait_ms) = match token_manager
-                    .get_token("image_gen", attempt > 0, None, "gemini-3-pro-image")
+                    .get_token("image_gen", attempt > 0, None, "gemini-3-pro-image", &account_groups)
                     .await
                 {
                     Ok(t) => t,
@@ -255,6 +255,10 @@ pub async fn handle_warmup(
                 output_tokens: Some(0),
                 protocol: Some("warmup".to_string()),
                 username: None,
+                cache_hit: false,
+                cached_tokens: None,
+                thinking_tokens: None,
+                cost_usd: None,
             };
             state.monitor.log_request(log).await;
 
@@ -335,6 +339,10 @@ pub async fn handle_warmup(
                 output_tokens: None,
                 protocol: Some("warmup".to_string()),
                 username: None,
+                cache_hit: false,
+                cached_tokens: None,
+                thinking_tokens: None,
+                cost_usd: None,
             };
             state.monitor.log_request(log).await;
 
@@ -218,11 +218,14 @@ pub struct Metadata {
 
 /// Output Configuration (Claude API v2.0.67+)
 /// Controls effort level for model reasoning
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct OutputConfig {
     /// Effort level: "high", "medium", "low"
     #[serde(skip_serializing_if = "Option::is_none")]
     pub effort: Option<String>,
+    /// [NEW] Structured output: `{ "type": "json_schema", "schema": {...} }`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<serde_json::Value>,
 }
 
 /// Claude API 响应
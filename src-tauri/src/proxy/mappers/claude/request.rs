@@ -593,6 +593,13 @@ pub fn transform_claude_request_in(
         crate::proxy::mappers::common_utils::inject_google_search_tool(&mut inner_request);
     }
 
+    // [NEW] 结构化输出: output_config.format -> responseSchema
+    if let Some(schema) = crate::proxy::common::structured_output::OutputSchema::from_claude(
+        claude_req.output_config.as_ref().and_then(|c| c.format.as_ref()),
+    ) {
+        crate::proxy::common::structured_output::apply_to_gemini_request(&mut inner_request, &schema);
+    }
+
     // Inject imageConfig if present (for image generation models)
     if let Some(image_config) = config.image_config {
         if let Some(obj) = inner_request.as_object_mut() {
@@ -621,6 +628,7 @@ pub fn transform_claude_request_in(
                 }
 
                 gen_obj.remove("responseMimeType");
+                gen_obj.remove("responseSchema");
                 gen_obj.remove("responseModalities");
                 gen_obj.insert("imageConfig".to_string(), image_config);
             }
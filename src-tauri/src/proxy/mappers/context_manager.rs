@@ -4,36 +4,40 @@
 //! to prevent "Prompt is too long" errors and avoid invalid signatures.
 
 use super::claude::models::{ClaudeRequest, ContentBlock, Message, MessageContent, SystemPrompt};
+use super::tokenizer;
 use tracing::{debug, info};
 
-/// Helper to estimate tokens from text with multi-language awareness
+/// Count tokens of a text fragment with the offline tokenizer
 ///
-/// Improved estimation algorithm:
-/// - ASCII/English: ~4 characters per token
-/// - Unicode/CJK: ~1.5 characters per token (Chinese, Japanese, Korean are tokenized differently)
-/// - Adds 15% safety margin to prevent underestimation
-fn estimate_tokens_from_str(s: &str) -> u32 {
-    if s.is_empty() {
-        return 0;
-    }
-
-    let mut ascii_chars = 0u32;
-    let mut unicode_chars = 0u32;
+/// See [`tokenizer::get_tokenizer`]: a vocabulary from the data directory when present,
+/// otherwise the built-in SentencePiece-style segmenter.
+pub(crate) fn estimate_tokens_from_str(s: &str) -> u32 {
+    tokenizer::count_text(s)
+}
 
-    for c in s.chars() {
-        if c.is_ascii() {
-            ascii_chars += 1;
-        } else {
-            unicode_chars += 1;
-        }
+/// Count a tool_result content value (string, content block array or arbitrary JSON)
+fn estimate_tool_result_content(content: &serde_json::Value) -> u32 {
+    if let Some(s) = content.as_str() {
+        return estimate_tokens_from_str(s);
     }
-
-    // ASCII: ~4 chars/token, Unicode/CJK: ~1.5 chars/token
-    let ascii_tokens = (ascii_chars as f32 / 4.0).ceil() as u32;
-    let unicode_tokens = (unicode_chars as f32 / 1.5).ceil() as u32;
-
-    // Add 15% safety margin to account for tokenizer variations
-    ((ascii_tokens + unicode_tokens) as f32 * 1.15).ceil() as u32
+    let Some(items) = content.as_array() else {
+        return tokenizer::count_json(content);
+    };
+    items
+        .iter()
+        .map(|item| match item.get("type").and_then(|t| t.as_str()) {
+            Some("text") => estimate_tokens_from_str(item["text"].as_str().unwrap_or("")),
+            Some("image") => item["source"]["data"]
+                .as_str()
+                .map(tokenizer::count_image)
+                .unwrap_or(tokenizer::MEDIA_TOKENS_PER_TILE),
+            Some("document") => tokenizer::count_document(
+                item["source"]["media_type"].as_str().unwrap_or(""),
+                item["source"]["data"].as_str().unwrap_or(""),
+            ),
+            _ => tokenizer::count_json(item),
+        })
+        .sum()
 }
 
 /// Strategy for context purification
@@ -104,9 +108,28 @@ impl ContextManager {
 impl ContextManager {
     /// Estimate token usage for a Claude Request
     ///
-    /// This is a lightweight estimation, not a precise count.
-    /// It iterates through all messages and blocks to sum up estimated tokens.
+    /// Prompt tokens ([`Self::estimate_prompt_tokens`]) plus the reserved thinking budget.
+    /// Used for context pressure (compression thresholds).
     pub fn estimate_token_usage(request: &ClaudeRequest) -> u32 {
+        let mut total = Self::estimate_prompt_tokens(request);
+
+        // Thinking budget overhead if enabled
+        if let Some(thinking) = &request.thinking {
+            if let Some(budget) = thinking.budget_tokens {
+                // Reserve budget in estimation
+                total += budget;
+            }
+        }
+
+        total
+    }
+
+    /// Count the prompt (input) tokens of a Claude Request with the offline tokenizer
+    ///
+    /// Text goes through the tokenizer, images / documents use Gemini's media pricing
+    /// and tool schemas / inputs are counted by content rather than serialized JSON.
+    /// Used by count_tokens and calibrator learning (comparable to upstream `promptTokenCount`).
+    pub fn estimate_prompt_tokens(request: &ClaudeRequest) -> u32 {
         let mut total = 0;
 
         // System prompt
@@ -145,31 +168,19 @@ impl ContextManager {
                                 total += estimate_tokens_from_str(data);
                             }
                             ContentBlock::ToolUse { name, input, .. } => {
-                                total += 20; // Function call overhead
+                                total += 8; // Function call overhead
                                 total += estimate_tokens_from_str(name);
-                                if let Ok(json_str) = serde_json::to_string(input) {
-                                    total += estimate_tokens_from_str(&json_str);
-                                }
+                                total += tokenizer::count_json(input);
                             }
                             ContentBlock::ToolResult { content, .. } => {
-                                total += 10; // Result overhead
-                                             // content is serde_json::Value
-                                if let Some(s) = content.as_str() {
-                                    total += estimate_tokens_from_str(s);
-                                } else if let Some(arr) = content.as_array() {
-                                    for item in arr {
-                                        if let Some(text) =
-                                            item.get("text").and_then(|t| t.as_str())
-                                        {
-                                            total += estimate_tokens_from_str(text);
-                                        }
-                                    }
-                                } else {
-                                    // Fallback for objects or other types
-                                    if let Ok(s) = serde_json::to_string(content) {
-                                        total += estimate_tokens_from_str(&s);
-                                    }
-                                }
+                                total += 8; // Result overhead
+                                total += estimate_tool_result_content(content);
+                            }
+                            ContentBlock::Image { source, .. } => {
+                                total += tokenizer::count_image(&source.data);
+                            }
+                            ContentBlock::Document { source, .. } => {
+                                total += tokenizer::count_document(&source.media_type, &source.data);
                             }
                             _ => {}
                         }
@@ -178,20 +189,86 @@ impl ContextManager {
             }
         }
 
-        // Tools definition overhead (rough estimate)
+        // Tool declarations (name + description + input schema)
         if let Some(tools) = &request.tools {
             for tool in tools {
-                if let Ok(json_str) = serde_json::to_string(tool) {
-                    total += estimate_tokens_from_str(&json_str);
+                total += tokenizer::count_tool_declaration(
+                    tool.name.as_deref().unwrap_or(""),
+                    tool.description.as_deref(),
+                    tool.input_schema.as_ref(),
+                );
+            }
+        }
+
+        total
+    }
+
+    /// Estimate token usage for a native Gemini request body
+    ///
+    /// Walks `systemInstruction`, `contents` and `tools` with the same heuristics
+    /// as [`Self::estimate_token_usage`]. Accepts both bare `generateContent`
+    /// bodies and `countTokens` bodies wrapping a `generateContentRequest`.
+    pub fn estimate_gemini_token_usage(body: &serde_json::Value) -> u32 {
+        let request = body.get("generateContentRequest").unwrap_or(body);
+        let mut total = 0;
+
+        let estimate_parts = |parts: &serde_json::Value| -> u32 {
+            let mut sum = 0;
+            for part in parts.as_array().into_iter().flatten() {
+                if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                    sum += estimate_tokens_from_str(text);
+                } else if let Some(call) = part.get("functionCall") {
+                    sum += 8; // Function call overhead
+                    sum += tokenizer::count_json(call);
+                } else if let Some(resp) = part.get("functionResponse") {
+                    sum += 8; // Result overhead
+                    sum += tokenizer::count_json(resp);
+                } else if let Some(inline) = part.get("inlineData") {
+                    sum += tokenizer::count_media(
+                        inline["mimeType"].as_str().unwrap_or(""),
+                        inline["data"].as_str().unwrap_or(""),
+                    );
+                } else if part.get("fileData").is_some() {
+                    // 远程文件无法读取内容，按单个媒体块估算
+                    sum += tokenizer::MEDIA_TOKENS_PER_TILE;
                 }
             }
+            sum
+        };
+
+        if let Some(sys) = request.get("systemInstruction") {
+            total += estimate_parts(&sys["parts"]);
         }
 
-        // Thinking budget overhead if enabled
-        if let Some(thinking) = &request.thinking {
-            if let Some(budget) = thinking.budget_tokens {
-                // Reserve budget in estimation
-                total += budget;
+        for content in request
+            .get("contents")
+            .and_then(|c| c.as_array())
+            .into_iter()
+            .flatten()
+        {
+            // Message overhead
+            total += 4;
+            total += estimate_parts(&content["parts"]);
+        }
+
+        for tool in request
+            .get("tools")
+            .and_then(|t| t.as_array())
+            .into_iter()
+            .flatten()
+        {
+            match tool.get("functionDeclarations").and_then(|d| d.as_array()) {
+                Some(decls) => {
+                    for decl in decls {
+                        total += tokenizer::count_tool_declaration(
+                            decl["name"].as_str().unwrap_or(""),
+                            decl["description"].as_str(),
+                            decl.get("parameters"),
+                        );
+                    }
+                }
+                // googleSearch / codeExecution 等内置工具
+                None => total += tokenizer::count_json(tool),
             }
         }
 
@@ -475,6 +552,21 @@ mod tests {
         assert!(tokens < 50);
     }
 
+    #[test]
+    fn test_estimate_gemini_tokens() {
+        let body = serde_json::json!({
+            "systemInstruction": { "parts": [{ "text": "You are a helpful assistant" }] },
+            "contents": [{ "role": "user", "parts": [{ "text": "Hello World" }] }]
+        });
+        let tokens = ContextManager::estimate_gemini_token_usage(&body);
+        assert!(tokens > 4);
+        assert!(tokens < 50);
+
+        // countTokens wraps the same shape in generateContentRequest
+        let wrapped = serde_json::json!({ "generateContentRequest": body });
+        assert_eq!(ContextManager::estimate_gemini_token_usage(&wrapped), tokens);
+    }
+
     #[test]
     fn test_purify_history_soft() {
         // Construct history of 6 messages (indices 0-5)
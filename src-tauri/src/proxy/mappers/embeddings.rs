@@ -0,0 +1,281 @@
+// Embeddings 协议转换
+// OpenAI /v1/embeddings 与 Gemini embedContent / batchEmbedContents → v1internal batchEmbedContents
+
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// OpenAI 模型名未显式映射时使用的默认 Embedding 模型
+pub const DEFAULT_EMBEDDING_MODEL: &str = "gemini-embedding-001";
+
+/// 单次 batchEmbedContents 最多携带的输入条数
+pub const MAX_EMBEDDING_BATCH: usize = 100;
+
+/// POST /v1/embeddings 请求体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAIEmbeddingRequest {
+    pub model: String,
+    /// 字符串或字符串数组 (token 数组暂不支持)
+    pub input: Value,
+    #[serde(default)]
+    pub dimensions: Option<u32>,
+    /// "float" (默认) 或 "base64"
+    #[serde(default)]
+    pub encoding_format: Option<String>,
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+/// 将路由后的模型名规范为 Gemini Embedding 模型
+///
+/// OpenAI 专有模型 (text-embedding-3-*, text-embedding-ada-*) 未配置自定义映射时
+/// 统一落到默认模型，其余 (gemini-embedding-001, text-embedding-004 等) 原样透传。
+pub fn normalize_embedding_model(mapped_model: &str) -> String {
+    let lower = mapped_model.to_lowercase();
+    if lower.starts_with("text-embedding-3") || lower.starts_with("text-embedding-ada") {
+        return DEFAULT_EMBEDDING_MODEL.to_string();
+    }
+    if !lower.contains("embedding") {
+        tracing::warn!(
+            "[Embeddings] Model '{}' is not an embedding model, falling back to {}",
+            mapped_model,
+            DEFAULT_EMBEDDING_MODEL
+        );
+        return DEFAULT_EMBEDDING_MODEL.to_string();
+    }
+    mapped_model.trim_start_matches("models/").to_string()
+}
+
+/// 解析 OpenAI `input` 字段为文本列表
+pub fn openai_input_to_texts(input: &Value) -> Result<Vec<String>, String> {
+    let texts = match input {
+        Value::String(s) => vec![s.clone()],
+        Value::Array(items) => items
+            .iter()
+            .map(|item| {
+                item.as_str().map(|s| s.to_string()).ok_or_else(|| {
+                    "Token array inputs are not supported; pass strings instead".to_string()
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => return Err("`input` must be a string or an array of strings".to_string()),
+    };
+
+    if texts.is_empty() {
+        return Err("`input` must not be empty".to_string());
+    }
+    if texts.len() > MAX_EMBEDDING_BATCH {
+        return Err(format!(
+            "Too many inputs: {} (max {})",
+            texts.len(),
+            MAX_EMBEDDING_BATCH
+        ));
+    }
+    if texts.iter().any(|t| t.is_empty()) {
+        return Err("`input` must not contain empty strings".to_string());
+    }
+    Ok(texts)
+}
+
+/// 构造 Gemini EmbedContentRequest 列表
+pub fn build_embed_requests(texts: &[String], model: &str, dimensions: Option<u32>) -> Vec<Value> {
+    texts
+        .iter()
+        .map(|text| {
+            let mut req = json!({
+                "model": format!("models/{}", model),
+                "content": { "parts": [{ "text": text }] }
+            });
+            if let Some(dim) = dimensions {
+                req["outputDimensionality"] = json!(dim);
+            }
+            req
+        })
+        .collect()
+}
+
+/// 将 Gemini 原生 embedContent / batchEmbedContents 请求体整理为请求列表，并统一模型名
+pub fn gemini_body_to_requests(method: &str, body: &Value, model: &str) -> Result<Vec<Value>, String> {
+    let model_ref = format!("models/{}", model);
+    let mut requests = match method {
+        "embedContent" => vec![body.clone()],
+        "batchEmbedContents" => body
+            .get("requests")
+            .and_then(|r| r.as_array())
+            .cloned()
+            .ok_or_else(|| "`requests` must be an array".to_string())?,
+        other => return Err(format!("Unsupported method: {}", other)),
+    };
+
+    if requests.is_empty() {
+        return Err("`requests` must not be empty".to_string());
+    }
+    if requests.len() > MAX_EMBEDDING_BATCH {
+        return Err(format!(
+            "Too many requests: {} (max {})",
+            requests.len(),
+            MAX_EMBEDDING_BATCH
+        ));
+    }
+
+    for req in requests.iter_mut() {
+        if req.get("content").is_none() {
+            return Err("Each request must contain `content`".to_string());
+        }
+        req["model"] = json!(model_ref);
+    }
+    Ok(requests)
+}
+
+/// 从上游响应中提取向量 (兼容 v1internal 的 `response` 包装)
+pub fn extract_embeddings(resp: &Value) -> Result<Vec<Vec<f32>>, String> {
+    let inner = resp.get("response").unwrap_or(resp);
+
+    let items: Vec<&Value> = if let Some(arr) = inner.get("embeddings").and_then(|e| e.as_array()) {
+        arr.iter().collect()
+    } else if let Some(single) = inner.get("embedding") {
+        vec![single]
+    } else {
+        return Err("Upstream response contains no embeddings".to_string());
+    };
+
+    items
+        .into_iter()
+        .map(|item| {
+            item.get("values")
+                .and_then(|v| v.as_array())
+                .map(|vals| {
+                    vals.iter()
+                        .map(|v| v.as_f64().unwrap_or(0.0) as f32)
+                        .collect()
+                })
+                .ok_or_else(|| "Embedding entry is missing `values`".to_string())
+        })
+        .collect()
+}
+
+/// 构造 OpenAI Embeddings 响应
+pub fn build_openai_embedding_response(
+    embeddings: &[Vec<f32>],
+    model: &str,
+    prompt_tokens: u32,
+    encoding_format: Option<&str>,
+) -> Value {
+    let data: Vec<Value> = embeddings
+        .iter()
+        .enumerate()
+        .map(|(index, values)| {
+            let embedding = if encoding_format == Some("base64") {
+                // base64: little-endian f32 序列
+                let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+                json!(base64::engine::general_purpose::STANDARD.encode(bytes))
+            } else {
+                json!(values)
+            };
+            json!({
+                "object": "embedding",
+                "index": index,
+                "embedding": embedding
+            })
+        })
+        .collect();
+
+    json!({
+        "object": "list",
+        "data": data,
+        "model": model,
+        "usage": {
+            "prompt_tokens": prompt_tokens,
+            "total_tokens": prompt_tokens
+        }
+    })
+}
+
+/// 构造 Gemini 原生响应 (embedContent 返回单个 embedding)
+pub fn build_gemini_embedding_response(method: &str, embeddings: &[Vec<f32>]) -> Value {
+    if method == "embedContent" {
+        let values = embeddings.first().cloned().unwrap_or_default();
+        return json!({ "embedding": { "values": values } });
+    }
+    let items: Vec<Value> = embeddings
+        .iter()
+        .map(|values| json!({ "values": values }))
+        .collect();
+    json!({ "embeddings": items })
+}
+
+/// 估算请求列表中的文本 token 数 (Embedding 上游不返回 usage)
+pub fn estimate_request_tokens(requests: &[Value]) -> u32 {
+    requests
+        .iter()
+        .flat_map(|req| {
+            req.get("content")
+                .and_then(|c| c.get("parts"))
+                .and_then(|p| p.as_array())
+                .cloned()
+                .unwrap_or_default()
+        })
+        .filter_map(|part| part.get("text").and_then(|t| t.as_str()).map(|s| s.to_string()))
+        .map(|text| crate::proxy::mappers::context_manager::estimate_tokens_from_str(&text))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_input_and_dimensions() {
+        let texts = openai_input_to_texts(&json!(["hello", "world"])).unwrap();
+        let requests = build_embed_requests(&texts, "gemini-embedding-001", Some(256));
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0]["model"], "models/gemini-embedding-001");
+        assert_eq!(requests[1]["content"]["parts"][0]["text"], "world");
+        assert_eq!(requests[0]["outputDimensionality"], 256);
+
+        assert!(openai_input_to_texts(&json!([[1, 2, 3]])).is_err());
+        assert!(openai_input_to_texts(&json!([])).is_err());
+    }
+
+    #[test]
+    fn test_normalize_embedding_model() {
+        assert_eq!(normalize_embedding_model("text-embedding-3-small"), DEFAULT_EMBEDDING_MODEL);
+        assert_eq!(normalize_embedding_model("text-embedding-004"), "text-embedding-004");
+        assert_eq!(normalize_embedding_model("models/gemini-embedding-001"), "gemini-embedding-001");
+        assert_eq!(normalize_embedding_model("gemini-3-flash"), DEFAULT_EMBEDDING_MODEL);
+    }
+
+    #[test]
+    fn test_gemini_batch_body_overrides_model() {
+        let body = json!({
+            "requests": [
+                { "model": "models/whatever", "content": { "parts": [{ "text": "a" }] }, "taskType": "RETRIEVAL_QUERY" }
+            ]
+        });
+        let requests = gemini_body_to_requests("batchEmbedContents", &body, "gemini-embedding-001").unwrap();
+        assert_eq!(requests[0]["model"], "models/gemini-embedding-001");
+        assert_eq!(requests[0]["taskType"], "RETRIEVAL_QUERY");
+    }
+
+    #[test]
+    fn test_extract_and_build_responses() {
+        let upstream = json!({ "response": { "embeddings": [{ "values": [0.5, -1.0] }, { "values": [0.25, 0.0] }] } });
+        let embeddings = extract_embeddings(&upstream).unwrap();
+        assert_eq!(embeddings.len(), 2);
+
+        let openai = build_openai_embedding_response(&embeddings, "text-embedding-3-small", 4, None);
+        assert_eq!(openai["data"][1]["index"], 1);
+        assert_eq!(openai["data"][0]["embedding"][1], -1.0);
+        assert_eq!(openai["usage"]["prompt_tokens"], 4);
+
+        let b64 = build_openai_embedding_response(&embeddings, "m", 4, Some("base64"));
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(b64["data"][0]["embedding"].as_str().unwrap())
+            .unwrap();
+        assert_eq!(decoded.len(), 8);
+        assert_eq!(f32::from_le_bytes(decoded[0..4].try_into().unwrap()), 0.5);
+
+        let single = build_gemini_embedding_response("embedContent", &embeddings);
+        assert_eq!(single["embedding"]["values"][0], 0.5);
+    }
+}
@@ -2,11 +2,19 @@
 //!
 //! Learns from historical request/response pairs to improve token estimation accuracy.
 //! Uses actual token counts from Google API responses to calibrate future estimates.
+//! Estimates come from the offline tokenizer (see `tokenizer.rs`), so the factor only
+//! corrects the remaining tokenizer drift instead of a coarse chars-per-token guess.
 
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::RwLock;
 use tracing::info;
 
+/// Initial factor: the offline tokenizer is expected to be close to the real count
+const INITIAL_FACTOR: f32 = 1.0;
+/// Allowed factor range; outside it the estimate is considered broken rather than drifting
+const MIN_FACTOR: f32 = 0.5;
+const MAX_FACTOR: f32 = 2.0;
+
 /// Estimation Calibrator - learns estimation error from historical requests
 ///
 /// This module tracks the ratio between estimated tokens (before request) and
@@ -29,9 +37,9 @@ impl EstimationCalibrator {
             total_estimated: AtomicU64::new(0),
             total_actual: AtomicU64::new(0),
             sample_count: AtomicU64::new(0),
-            // Initial assumption: estimates are 2.0x lower than actual
-            // This is conservative and will be adjusted based on real data
-            calibration_factor: RwLock::new(2.0),
+            // Initial assumption: tokenizer counts match actual usage
+            // Adjusted based on real data
+            calibration_factor: RwLock::new(INITIAL_FACTOR),
         }
     }
 
@@ -62,10 +70,9 @@ impl EstimationCalibrator {
 
         if estimated > 0.0 {
             let new_factor = (actual / estimated) as f32;
-            // Clamp to reasonable range [0.8, 4.0]
-            // - Below 0.8 means we're overestimating (rare)
-            // - Above 4.0 means severe underestimation
-            let clamped = new_factor.clamp(0.8, 4.0);
+            // Clamp to reasonable range [0.5, 2.0]
+            // - Below 0.5 / above 2.0 means the tokenizer is badly off (e.g. wrong vocabulary)
+            let clamped = new_factor.clamp(MIN_FACTOR, MAX_FACTOR);
 
             if let Ok(mut factor) = self.calibration_factor.write() {
                 // Exponential moving average: 60% old + 40% new
@@ -74,11 +81,12 @@ impl EstimationCalibrator {
                 *factor = old * 0.6 + clamped * 0.4;
 
                 info!(
-                    "[Calibrator] Updated factor: {:.2} -> {:.2} (raw: {:.2}, samples: {})",
+                    "[Calibrator] Updated factor: {:.2} -> {:.2} (raw: {:.2}, samples: {}, tokenizer: {})",
                     old,
                     *factor,
                     new_factor,
-                    self.sample_count.load(Ordering::Relaxed)
+                    self.sample_count.load(Ordering::Relaxed),
+                    super::tokenizer::get_tokenizer().name()
                 );
             }
         }
@@ -88,14 +96,17 @@ impl EstimationCalibrator {
     ///
     /// Multiplies the raw estimate by the current calibration factor.
     pub fn calibrate(&self, estimated: u32) -> u32 {
-        let factor = self.calibration_factor.read().map(|f| *f).unwrap_or(2.0);
+        let factor = self.get_factor();
 
         (estimated as f32 * factor).ceil() as u32
     }
 
     /// Get the current calibration factor
     pub fn get_factor(&self) -> f32 {
-        self.calibration_factor.read().map(|f| *f).unwrap_or(2.0)
+        self.calibration_factor
+            .read()
+            .map(|f| *f)
+            .unwrap_or(INITIAL_FACTOR)
     }
 }
 
@@ -123,27 +134,33 @@ mod tests {
     fn test_calibrator_basic() {
         let calibrator = EstimationCalibrator::new();
 
-        // Initial factor should be 2.0
-        assert!((calibrator.get_factor() - 2.0).abs() < 0.01);
+        // Initial factor should be 1.0
+        assert!((calibrator.get_factor() - 1.0).abs() < 0.01);
 
-        // Record some samples where actual is 3x estimated
+        // Record some samples where actual is 1.5x estimated
         for _ in 0..10 {
-            calibrator.record(100, 300);
+            calibrator.record(100, 150);
         }
 
-        // Factor should have moved towards 3.0
+        // Factor should have moved towards 1.5
         let factor = calibrator.get_factor();
-        assert!(factor > 2.0);
-        assert!(factor < 3.5);
+        assert!(factor > 1.0);
+        assert!(factor < 1.5);
+
+        // Severe drift is clamped to MAX_FACTOR
+        for _ in 0..50 {
+            calibrator.record(100, 1000);
+        }
+        assert!(calibrator.get_factor() <= MAX_FACTOR + 0.01);
     }
 
     #[test]
     fn test_calibrate() {
         let calibrator = EstimationCalibrator::new();
 
-        // With default factor of 2.0, 100 should become 200
+        // With default factor of 1.0, estimates pass through unchanged
         let calibrated = calibrator.calibrate(100);
-        assert_eq!(calibrated, 200);
+        assert_eq!(calibrated, 100);
     }
 
     #[test]
@@ -4,9 +4,12 @@
 pub mod claude;
 pub mod common_utils;
 pub mod context_manager;
+pub mod embeddings;
 pub mod error_classifier;
 pub mod estimation_calibrator;
 pub mod gemini;
 pub mod openai;
+pub mod responses;
 pub mod signature_store;
+pub mod tokenizer;
 pub mod tool_result_compressor;
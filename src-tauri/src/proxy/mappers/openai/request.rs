@@ -294,6 +294,21 @@ pub fn transform_openai_request(
                                     // 这会与 v3.3.16 的 thinkingConfig 逻辑冲突，留待后续版本实现
                                     tracing::debug!("[OpenAI-Request] Skipping audio_url (not yet implemented in v3.3.16)");
                                 }
+                                OpenAIContentBlock::File { file } => {
+                                    // [NEW] 文件输入 → inlineData (Gemini 原生支持 PDF / 纯文本等)
+                                    let inline = file.file_data.as_deref().and_then(|data| {
+                                        crate::proxy::common::file_refs::split_file_data(data, file.filename.as_deref())
+                                    });
+                                    match inline {
+                                        Some((mime_type, data)) => parts.push(json!({
+                                            "inlineData": { "mimeType": mime_type, "data": data }
+                                        })),
+                                        None => tracing::warn!(
+                                            "[OpenAI-Request] Skipping file part without file_data (file_id: {:?})",
+                                            file.file_id
+                                        ),
+                                    }
+                                }
                             }
                         }
                     }
@@ -680,6 +695,13 @@ pub fn transform_openai_request(
         crate::proxy::mappers::common_utils::inject_google_search_tool(&mut inner_request);
     }
 
+    // [NEW] 结构化输出: response_format.json_schema -> responseSchema
+    if let Some(schema) = request.response_format.as_ref().and_then(|fmt| {
+        crate::proxy::common::structured_output::OutputSchema::from_openai(&fmt.r#type, fmt.json_schema.as_ref())
+    }) {
+        crate::proxy::common::structured_output::apply_to_gemini_request(&mut inner_request, &schema);
+    }
+
     if let Some(image_config) = config.image_config {
         if let Some(obj) = inner_request.as_object_mut() {
             obj.remove("tools");
@@ -689,6 +711,7 @@ pub fn transform_openai_request(
                 // [REMOVED] thinkingConfig 拦截已删除，允许图像生成时输出思维链
                 // gen_obj.remove("thinkingConfig");
                 gen_obj.remove("responseMimeType");
+                gen_obj.remove("responseSchema");
                 gen_obj.remove("responseModalities");
                 gen_obj.insert("imageConfig".to_string(), image_config);
             }
@@ -816,6 +839,40 @@ mod tests {
         update_thinking_budget_config(ThinkingBudgetConfig::default());
     }
 
+    #[test]
+    fn test_json_schema_response_format_maps_to_response_schema() {
+        let req = OpenAIRequest {
+            model: "gemini-2.5-flash".to_string(),
+            messages: vec![OpenAIMessage {
+                role: "user".to_string(),
+                content: Some(OpenAIContent::String("list colors".into())),
+                reasoning_content: None,
+                tool_calls: None,
+                tool_call_id: None,
+                name: None,
+            }],
+            response_format: Some(ResponseFormat {
+                r#type: "json_schema".to_string(),
+                json_schema: Some(json!({
+                    "name": "colors",
+                    "strict": true,
+                    "schema": {
+                        "type": "object",
+                        "properties": { "colors": { "type": "array", "items": { "type": "string" } } },
+                        "required": ["colors"],
+                        "additionalProperties": false
+                    }
+                })),
+            }),
+            ..Default::default()
+        };
+
+        let (result, _, _) = transform_openai_request(&req, "test-v", "gemini-2.5-flash");
+        let gen_config = &result["request"]["generationConfig"];
+        assert_eq!(gen_config["responseMimeType"], "application/json");
+        assert_eq!(gen_config["responseSchema"]["properties"]["colors"]["type"], "array");
+    }
+
     #[test]
     fn test_transform_openai_request_multimodal() {
         let req = OpenAIRequest {
@@ -856,6 +913,25 @@ mod tests {
             "image/png"
         );
     }
+
+    #[test]
+    fn test_transform_openai_request_file_part() {
+        let req: OpenAIRequest = serde_json::from_value(json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": [
+                {"type": "file", "file": {"file_data": "data:application/pdf;base64,JVBERi0=", "filename": "a.pdf"}},
+                {"type": "file", "file": {"file_data": "aGk=", "filename": "notes.txt"}},
+                {"type": "text", "text": "summarize"}
+            ]}]
+        }))
+        .unwrap();
+
+        let (result, _, _) = transform_openai_request(&req, "test-v", "gemini-2.5-flash");
+        let parts = &result["request"]["contents"][0]["parts"];
+        assert_eq!(parts[0]["inlineData"], json!({"mimeType": "application/pdf", "data": "JVBERi0="}));
+        assert_eq!(parts[1]["inlineData"]["mimeType"], "text/plain");
+        assert_eq!(parts[2]["text"], "summarize");
+    }
     
     #[test]
     fn test_gemini_pro_thinking_injection() {
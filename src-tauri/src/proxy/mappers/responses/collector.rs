@@ -0,0 +1,101 @@
+// Responses Stream Collector
+// 非流式请求: 将 Gemini SSE 流聚合为完整的 Response 对象
+
+use super::store::ResponseStore;
+use super::streaming::{parse_gemini_sse_line, upstream_error_message, ResponsesStreamState};
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+use serde_json::Value;
+
+/// 聚合 Gemini SSE 流，返回最终 Response 对象
+///
+/// `conversation` 为 Some 时将结果写入 ResponseStore。
+pub async fn collect_responses_stream<S, E>(
+    mut stream: S,
+    mut state: ResponsesStreamState,
+    conversation: Option<Vec<Value>>,
+) -> Result<Value, String>
+where
+    S: futures::Stream<Item = Result<Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+{
+    let mut buffer = BytesMut::new();
+    state.start();
+
+    while let Some(chunk_result) = stream.next().await {
+        let chunk = chunk_result.map_err(|e| format!("Stream error: {}", e))?;
+        buffer.extend_from_slice(&chunk);
+
+        while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+            let line_raw = buffer.split_to(pos + 1);
+            if let Some(json) = std::str::from_utf8(&line_raw)
+                .ok()
+                .and_then(parse_gemini_sse_line)
+            {
+                if let Some(message) = upstream_error_message(&json) {
+                    return Err(format!("Upstream error: {}", message));
+                }
+                state.process_chunk(&json);
+            }
+        }
+    }
+
+    // 末尾可能没有换行
+    if let Some(json) = std::str::from_utf8(&buffer).ok().and_then(parse_gemini_sse_line) {
+        if let Some(message) = upstream_error_message(&json) {
+            return Err(format!("Upstream error: {}", message));
+        }
+        state.process_chunk(&json);
+    }
+
+    state.finish();
+    let response = state.response_object();
+
+    if let Some(mut messages) = conversation {
+        messages.extend(state.output_messages());
+        ResponseStore::global().insert(
+            state.response_id().to_string(),
+            response.clone(),
+            messages,
+            state.owner_token_id(),
+        );
+    }
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_collect_text_and_function_call() {
+        let chunk1 = json!({ "candidates": [{ "content": { "parts": [{ "text": "Checking" }] } }] });
+        let chunk2 = json!({
+            "candidates": [{
+                "content": { "parts": [{ "functionCall": { "name": "get_weather", "args": { "city": "Paris" } } }] },
+                "finishReason": "STOP"
+            }],
+            "usageMetadata": { "promptTokenCount": 8, "candidatesTokenCount": 5, "totalTokenCount": 13 }
+        });
+        let items: Vec<Result<Bytes, String>> = vec![
+            Ok(Bytes::from(format!("data: {}\n\n", chunk1))),
+            Ok(Bytes::from(format!("data: {}", chunk2))),
+        ];
+
+        let state = ResponsesStreamState::new("gpt-5".into(), "sid".into(), 1);
+        let response = collect_responses_stream(stream::iter(items), state, None)
+            .await
+            .unwrap();
+
+        assert_eq!(response["status"], "completed");
+        let output = response["output"].as_array().unwrap();
+        assert_eq!(output.len(), 2);
+        assert_eq!(output[0]["type"], "message");
+        assert_eq!(output[1]["type"], "function_call");
+        assert_eq!(output[1]["arguments"], "{\"city\":\"Paris\"}");
+        assert_eq!(response["usage"]["total_tokens"], 13);
+    }
+}
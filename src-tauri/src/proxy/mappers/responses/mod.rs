@@ -0,0 +1,13 @@
+// OpenAI Responses API mapper 模块
+// 负责 Responses (/v1/responses) ↔ Gemini 协议转换
+// 请求侧复用 mappers::openai 的 Chat → Gemini 转换，响应侧使用独立的事件状态机
+
+pub mod collector;
+pub mod models;
+pub mod request;
+pub mod store;
+pub mod streaming;
+
+pub use models::*;
+pub use request::*;
+pub use streaming::*;
@@ -0,0 +1,68 @@
+// OpenAI Responses API 数据模型
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// POST /v1/responses 请求体
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ResponsesRequest {
+    pub model: String,
+    /// 字符串或输入项数组 (message / function_call / function_call_output / reasoning ...)
+    #[serde(default)]
+    pub input: Option<Value>,
+    #[serde(default)]
+    pub instructions: Option<String>,
+    #[serde(default)]
+    pub previous_response_id: Option<String>,
+    #[serde(default)]
+    pub tools: Option<Vec<Value>>,
+    #[serde(default)]
+    pub tool_choice: Option<Value>,
+    #[serde(default)]
+    pub parallel_tool_calls: Option<bool>,
+    #[serde(default)]
+    pub stream: bool,
+    /// 是否保存到本地存储以便 previous_response_id 续接 (默认 true)
+    #[serde(default)]
+    pub store: Option<bool>,
+    #[serde(default)]
+    pub max_output_tokens: Option<u32>,
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub top_p: Option<f64>,
+    #[serde(default)]
+    pub reasoning: Option<ReasoningConfig>,
+    #[serde(default)]
+    pub metadata: Option<Value>,
+    #[serde(default)]
+    pub text: Option<Value>,
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+impl ResponsesRequest {
+    pub fn should_store(&self) -> bool {
+        self.store.unwrap_or(true)
+    }
+}
+
+/// reasoning 配置 (effort: minimal / low / medium / high)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ReasoningConfig {
+    #[serde(default)]
+    pub effort: Option<String>,
+    #[serde(default)]
+    pub summary: Option<String>,
+}
+
+/// 将 reasoning.effort 映射为 thinking budget
+pub fn effort_to_budget(effort: &str) -> Option<u32> {
+    match effort {
+        "minimal" => Some(1024),
+        "low" => Some(4096),
+        "medium" => Some(16384),
+        "high" => Some(24576),
+        _ => None,
+    }
+}
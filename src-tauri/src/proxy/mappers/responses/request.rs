@@ -0,0 +1,434 @@
+// Responses → Chat 请求转换
+// 将 Responses 输入项整理为 Chat 格式消息，随后交给 mappers::openai 转换为 Gemini 请求
+
+use super::models::*;
+use crate::proxy::mappers::openai::{OpenAIRequest, ResponseFormat, ThinkingConfig};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// 将 Responses 请求转换为 OpenAIRequest
+///
+/// `history` 为 previous_response_id 对应的历史消息 (Chat 格式，不含 system)。
+/// 返回 (OpenAIRequest, 本轮完整对话)，后者用于保存到 ResponseStore。
+pub fn transform_responses_request(
+    request: &ResponsesRequest,
+    history: &[Value],
+) -> Result<(OpenAIRequest, Vec<Value>), String> {
+    let mut conversation: Vec<Value> = history.to_vec();
+    conversation.extend(input_to_chat_messages(request.input.as_ref(), history));
+
+    if conversation.is_empty() {
+        return Err("Request must contain `input` or `previous_response_id`".to_string());
+    }
+
+    // instructions 不随 previous_response_id 继承，每轮单独注入
+    let mut messages = Vec::with_capacity(conversation.len() + 1);
+    if let Some(instructions) = request.instructions.as_deref() {
+        if !instructions.is_empty() {
+            messages.push(json!({ "role": "system", "content": instructions }));
+        }
+    }
+    messages.extend(conversation.iter().cloned());
+
+    let thinking = request
+        .reasoning
+        .as_ref()
+        .and_then(|r| r.effort.as_deref())
+        .and_then(effort_to_budget)
+        .map(|budget| ThinkingConfig {
+            thinking_type: Some("enabled".to_string()),
+            budget_tokens: Some(budget),
+            effort: None,
+        });
+
+    let chat_body = json!({
+        "model": request.model,
+        "messages": messages,
+        "stream": request.stream,
+    });
+    let mut openai_req: OpenAIRequest = serde_json::from_value(chat_body)
+        .map_err(|e| format!("Invalid input items: {}", e))?;
+
+    openai_req.max_tokens = request.max_output_tokens;
+    openai_req.temperature = request.temperature;
+    openai_req.top_p = request.top_p;
+    openai_req.tools = request.tools.clone();
+    openai_req.tool_choice = request.tool_choice.clone();
+    openai_req.parallel_tool_calls = request.parallel_tool_calls;
+    openai_req.thinking = thinking;
+    openai_req.response_format = text_format_to_response_format(request.text.as_ref());
+
+    Ok((openai_req, conversation))
+}
+
+/// `text.format` (Responses) -> `response_format` (Chat)
+///
+/// Responses 把 json_schema 的 name / schema / strict 平铺在 format 上，Chat 则嵌套在 `json_schema` 中
+fn text_format_to_response_format(text: Option<&Value>) -> Option<ResponseFormat> {
+    let format = text?.get("format")?;
+    match format.get("type")?.as_str()? {
+        "json_object" => Some(ResponseFormat {
+            r#type: "json_object".to_string(),
+            json_schema: None,
+        }),
+        "json_schema" => Some(ResponseFormat {
+            r#type: "json_schema".to_string(),
+            json_schema: Some(json!({
+                "name": format.get("name").cloned().unwrap_or(Value::Null),
+                "schema": format.get("schema").cloned().unwrap_or_else(|| json!({})),
+                "strict": format.get("strict").cloned().unwrap_or(Value::Null),
+            })),
+        }),
+        _ => None,
+    }
+}
+
+/// 将 Responses 输入转换为 Chat 格式消息
+///
+/// 支持字符串输入以及 message / function_call / function_call_output /
+/// local_shell_call / web_search_call 输入项。连续的工具调用合并到同一条
+/// assistant 消息中，保证并行调用在 Gemini 侧处于同一个 model 回合。
+pub fn input_to_chat_messages(input: Option<&Value>, history: &[Value]) -> Vec<Value> {
+    let mut messages: Vec<Value> = Vec::new();
+
+    let items = match input {
+        None | Some(Value::Null) => return messages,
+        Some(Value::String(s)) => {
+            messages.push(json!({ "role": "user", "content": s }));
+            return messages;
+        }
+        Some(Value::Array(items)) => items,
+        Some(other) => {
+            messages.push(json!({ "role": "user", "content": other.to_string() }));
+            return messages;
+        }
+    };
+
+    // call_id -> 工具名称 (历史 + 本轮)，用于 function_call_output 回填 name
+    let mut call_id_to_name: HashMap<String, String> = HashMap::new();
+    for msg in history {
+        for tc in msg
+            .get("tool_calls")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+        {
+            if let (Some(id), Some(name)) = (
+                tc.get("id").and_then(|v| v.as_str()),
+                tc["function"].get("name").and_then(|v| v.as_str()),
+            ) {
+                call_id_to_name.insert(id.to_string(), name.to_string());
+            }
+        }
+    }
+
+    for item in items {
+        // 省略 type 的 {role, content} 视为 message
+        let item_type = item
+            .get("type")
+            .and_then(|v| v.as_str())
+            .unwrap_or(if item.get("role").is_some() { "message" } else { "" });
+
+        match item_type {
+            "message" => {
+                let role = match item.get("role").and_then(|v| v.as_str()).unwrap_or("user") {
+                    "developer" => "system",
+                    other => other,
+                };
+                messages.push(json!({
+                    "role": role,
+                    "content": convert_message_content(item.get("content"))
+                }));
+            }
+            "function_call" | "local_shell_call" | "web_search_call" => {
+                let (call_id, name, arguments) = convert_tool_call_item(item_type, item);
+                call_id_to_name.insert(call_id.clone(), name.clone());
+
+                let tool_call = json!({
+                    "id": call_id,
+                    "type": "function",
+                    "function": { "name": name, "arguments": arguments }
+                });
+
+                // 合并到上一条 assistant 消息 (文本 + 工具调用 或 并行工具调用)
+                if let Some(last) = messages.last_mut() {
+                    if last["role"] == "assistant" {
+                        match last.get_mut("tool_calls").and_then(|v| v.as_array_mut()) {
+                            Some(calls) => calls.push(tool_call),
+                            None => last["tool_calls"] = json!([tool_call]),
+                        }
+                        continue;
+                    }
+                }
+                messages.push(json!({ "role": "assistant", "tool_calls": [tool_call] }));
+            }
+            "function_call_output" | "custom_tool_call_output" | "local_shell_call_output" => {
+                let call_id = item
+                    .get("call_id")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("unknown")
+                    .to_string();
+                let output = match item.get("output") {
+                    Some(Value::String(s)) => s.clone(),
+                    Some(o) => o
+                        .get("content")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(|| o.to_string()),
+                    None => String::new(),
+                };
+                let name = call_id_to_name.get(&call_id).cloned().unwrap_or_else(|| {
+                    tracing::warn!("[Responses] Unknown tool name for call_id {}, defaulting to 'shell'", call_id);
+                    "shell".to_string()
+                });
+
+                messages.push(json!({
+                    "role": "tool",
+                    "tool_call_id": call_id,
+                    "name": name,
+                    "content": output
+                }));
+            }
+            // reasoning 项仅用于客户端回放，思维签名由 Session 缓存负责续接
+            "reasoning" => {}
+            other => {
+                tracing::debug!("[Responses] Ignoring unsupported input item type: {}", other);
+            }
+        }
+    }
+
+    messages
+}
+
+/// 转换 message 项的 content (字符串或 input_text / output_text / input_image 数组)
+fn convert_message_content(content: Option<&Value>) -> Value {
+    let parts = match content {
+        Some(Value::String(s)) => return json!(s),
+        Some(Value::Array(parts)) => parts,
+        _ => return json!(""),
+    };
+
+    let mut text_parts: Vec<String> = Vec::new();
+    let mut image_parts: Vec<Value> = Vec::new();
+
+    for part in parts {
+        match part.get("type").and_then(|v| v.as_str()).unwrap_or("") {
+            "input_image" => {
+                let url = part
+                    .get("image_url")
+                    .and_then(|v| v.as_str().or_else(|| v.get("url").and_then(|u| u.as_str())));
+                if let Some(url) = url {
+                    image_parts.push(json!({ "type": "image_url", "image_url": { "url": url } }));
+                }
+            }
+            "image_url" => {
+                if let Some(url_obj) = part.get("image_url") {
+                    image_parts.push(json!({ "type": "image_url", "image_url": url_obj }));
+                }
+            }
+            // file_id 已在 handler 入口展开为 file_data
+            "input_file" => {
+                if let Some(file_data) = part.get("file_data").and_then(|v| v.as_str()) {
+                    image_parts.push(json!({
+                        "type": "file",
+                        "file": { "file_data": file_data, "filename": part.get("filename") }
+                    }));
+                }
+            }
+            "refusal" => {
+                if let Some(text) = part.get("refusal").and_then(|v| v.as_str()) {
+                    text_parts.push(text.to_string());
+                }
+            }
+            _ => {
+                if let Some(text) = part.get("text").and_then(|v| v.as_str()) {
+                    text_parts.push(text.to_string());
+                }
+            }
+        }
+    }
+
+    if image_parts.is_empty() {
+        return json!(text_parts.join("\n"));
+    }
+
+    let mut blocks: Vec<Value> = Vec::new();
+    if !text_parts.is_empty() {
+        blocks.push(json!({ "type": "text", "text": text_parts.join("\n") }));
+    }
+    blocks.extend(image_parts);
+    json!(blocks)
+}
+
+/// 转换工具调用项，返回 (call_id, name, arguments)
+fn convert_tool_call_item(item_type: &str, item: &Value) -> (String, String, String) {
+    let call_id = item
+        .get("call_id")
+        .and_then(|v| v.as_str())
+        .or_else(|| item.get("id").and_then(|v| v.as_str()))
+        .unwrap_or("unknown")
+        .to_string();
+
+    match item_type {
+        "local_shell_call" => {
+            let mut args = serde_json::Map::new();
+            if let Some(exec) = item.get("action").and_then(|a| a.get("exec")) {
+                if let Some(cmd) = exec.get("command") {
+                    // shell 工具的 command 参数为字符串数组
+                    let cmd_val = if cmd.is_string() { json!([cmd]) } else { cmd.clone() };
+                    args.insert("command".to_string(), cmd_val);
+                }
+                if let Some(wd) = exec.get("working_directory").or(exec.get("workdir")) {
+                    args.insert("workdir".to_string(), wd.clone());
+                }
+            }
+            (call_id, "shell".to_string(), Value::Object(args).to_string())
+        }
+        "web_search_call" => {
+            let mut args = serde_json::Map::new();
+            if let Some(q) = item.get("action").and_then(|a| a.get("query")) {
+                args.insert("query".to_string(), q.clone());
+            }
+            (call_id, "google_search".to_string(), Value::Object(args).to_string())
+        }
+        _ => {
+            let name = item
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("unknown")
+                .to_string();
+            let arguments = match item.get("arguments") {
+                Some(Value::String(s)) => s.clone(),
+                Some(other) => other.to_string(),
+                None => "{}".to_string(),
+            };
+            (call_id, name, arguments)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_input_and_instructions() {
+        let req = ResponsesRequest {
+            model: "gpt-5".into(),
+            input: Some(json!("Hello")),
+            instructions: Some("Be brief".into()),
+            max_output_tokens: Some(256),
+            ..Default::default()
+        };
+
+        let (openai_req, conversation) = transform_responses_request(&req, &[]).unwrap();
+        assert_eq!(openai_req.messages.len(), 2);
+        assert_eq!(openai_req.messages[0].role, "system");
+        assert_eq!(openai_req.messages[1].role, "user");
+        assert_eq!(openai_req.max_tokens, Some(256));
+        // instructions 不进入可续接的对话历史
+        assert_eq!(conversation.len(), 1);
+    }
+
+    #[test]
+    fn test_parallel_function_calls_are_merged() {
+        let input = json!([
+            { "type": "message", "role": "user", "content": [{ "type": "input_text", "text": "ls both" }] },
+            { "type": "function_call", "call_id": "call_a", "name": "shell", "arguments": "{\"command\":[\"ls\"]}" },
+            { "type": "function_call", "call_id": "call_b", "name": "read_file", "arguments": "{}" },
+            { "type": "function_call_output", "call_id": "call_a", "output": "a.txt" },
+            { "type": "function_call_output", "call_id": "call_b", "output": { "content": "data" } }
+        ]);
+
+        let messages = input_to_chat_messages(Some(&input), &[]);
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[1]["tool_calls"].as_array().unwrap().len(), 2);
+        assert_eq!(messages[2]["name"], "shell");
+        assert_eq!(messages[3]["name"], "read_file");
+        assert_eq!(messages[3]["content"], "data");
+    }
+
+    #[test]
+    fn test_previous_response_history_resolves_tool_names() {
+        let history = vec![
+            json!({ "role": "user", "content": "run it" }),
+            json!({ "role": "assistant", "tool_calls": [{
+                "id": "call_x", "type": "function",
+                "function": { "name": "apply_patch", "arguments": "{}" }
+            }]}),
+        ];
+        let input = json!([{ "type": "function_call_output", "call_id": "call_x", "output": "ok" }]);
+
+        let req = ResponsesRequest {
+            model: "gpt-5".into(),
+            input: Some(input),
+            previous_response_id: Some("resp_prev".into()),
+            ..Default::default()
+        };
+        let (openai_req, conversation) = transform_responses_request(&req, &history).unwrap();
+        assert_eq!(conversation.len(), 3);
+        assert_eq!(openai_req.messages[2].name.as_deref(), Some("apply_patch"));
+    }
+
+    #[test]
+    fn test_reasoning_effort_maps_to_thinking() {
+        let req = ResponsesRequest {
+            model: "gemini-3-pro".into(),
+            input: Some(json!("think")),
+            reasoning: Some(ReasoningConfig {
+                effort: Some("high".into()),
+                summary: None,
+            }),
+            ..Default::default()
+        };
+        let (openai_req, _) = transform_responses_request(&req, &[]).unwrap();
+        let thinking = openai_req.thinking.unwrap();
+        assert_eq!(thinking.thinking_type.as_deref(), Some("enabled"));
+        assert_eq!(thinking.budget_tokens, Some(24576));
+    }
+
+    #[test]
+    fn test_text_format_maps_to_response_format() {
+        let req = ResponsesRequest {
+            model: "gemini-3-pro".into(),
+            input: Some(json!("list colors")),
+            text: Some(json!({
+                "format": {
+                    "type": "json_schema",
+                    "name": "colors",
+                    "strict": true,
+                    "schema": { "type": "array", "items": { "type": "string" } }
+                }
+            })),
+            ..Default::default()
+        };
+        let (openai_req, _) = transform_responses_request(&req, &[]).unwrap();
+        let format = openai_req.response_format.unwrap();
+        assert_eq!(format.r#type, "json_schema");
+        let spec = format.json_schema.unwrap();
+        assert_eq!(spec["name"], "colors");
+        assert_eq!(spec["strict"], true);
+        assert_eq!(spec["schema"]["type"], "array");
+    }
+
+    #[test]
+    fn test_empty_request_rejected() {
+        let req = ResponsesRequest {
+            model: "gpt-5".into(),
+            ..Default::default()
+        };
+        assert!(transform_responses_request(&req, &[]).is_err());
+    }
+
+    #[test]
+    fn test_input_file_becomes_chat_file_part() {
+        let content = json!([
+            { "type": "input_file", "file_data": "data:application/pdf;base64,JVBERi0=", "filename": "a.pdf" },
+            { "type": "input_text", "text": "summarize" }
+        ]);
+        let converted = convert_message_content(Some(&content));
+        assert_eq!(converted[0]["text"], "summarize");
+        assert_eq!(converted[1]["type"], "file");
+        assert_eq!(converted[1]["file"]["filename"], "a.pdf");
+    }
+}
@@ -0,0 +1,177 @@
+//! Responses Store
+//!
+//! 本地保存已完成的 Response，用于 `previous_response_id` 续接对话以及
+//! `GET /v1/responses/{id}` 查询。采用容量上限 + TTL 的内存存储。
+//!
+//! 与 Files / Batches 相同，用户令牌只能读取、删除、续接自己创建的 Response；
+//! api_key / 未鉴权请求 (`owner` 为 None) 可见全部。
+
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+/// 最多保留的 Response 数量
+const MAX_STORED_RESPONSES: usize = 1000;
+/// Response 保留时长 (秒)
+const RESPONSE_TTL_SECS: i64 = 24 * 3600;
+
+/// 已保存的 Response
+#[derive(Debug, Clone)]
+pub struct StoredResponse {
+    /// 完整的 Response 对象 (与 API 返回一致)
+    pub response: Value,
+    /// 截至本轮 (含本轮输出) 的 Chat 格式对话历史，不含 system 指令
+    pub messages: Vec<Value>,
+    pub created_at: i64,
+    /// 创建者的用户令牌 ID (api_key / 未鉴权请求为空)
+    pub owner_token_id: Option<String>,
+}
+
+impl StoredResponse {
+    fn visible_to(&self, owner: Option<&str>) -> bool {
+        owner.is_none() || self.owner_token_id.as_deref() == owner
+    }
+}
+
+pub struct ResponseStore {
+    inner: Mutex<StoreInner>,
+    capacity: usize,
+}
+
+#[derive(Default)]
+struct StoreInner {
+    entries: HashMap<String, StoredResponse>,
+    order: VecDeque<String>,
+}
+
+impl ResponseStore {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(StoreInner::default()),
+            capacity,
+        }
+    }
+
+    /// 全局单例
+    pub fn global() -> &'static ResponseStore {
+        static STORE: OnceLock<ResponseStore> = OnceLock::new();
+        STORE.get_or_init(|| ResponseStore::new(MAX_STORED_RESPONSES))
+    }
+
+    pub fn insert(
+        &self,
+        id: String,
+        response: Value,
+        messages: Vec<Value>,
+        owner_token_id: Option<String>,
+    ) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+
+        if inner.entries.contains_key(&id) {
+            inner.order.retain(|existing| existing != &id);
+        }
+        inner.order.push_back(id.clone());
+        inner.entries.insert(
+            id,
+            StoredResponse {
+                response,
+                messages,
+                created_at: chrono::Utc::now().timestamp(),
+                owner_token_id,
+            },
+        );
+
+        while inner.order.len() > self.capacity {
+            if let Some(oldest) = inner.order.pop_front() {
+                inner.entries.remove(&oldest);
+            }
+        }
+    }
+
+    /// 读取 Response；属于其他用户令牌的条目视为不存在
+    pub fn get(&self, id: &str, owner: Option<&str>) -> Option<StoredResponse> {
+        let mut inner = self.inner.lock().ok()?;
+        let expired = inner
+            .entries
+            .get(id)
+            .map(|e| chrono::Utc::now().timestamp() - e.created_at > RESPONSE_TTL_SECS)?;
+
+        if expired {
+            inner.entries.remove(id);
+            inner.order.retain(|existing| existing != id);
+            return None;
+        }
+        inner.entries.get(id).filter(|e| e.visible_to(owner)).cloned()
+    }
+
+    /// 删除 Response；属于其他用户令牌的条目不删除并返回 false
+    pub fn remove(&self, id: &str, owner: Option<&str>) -> bool {
+        let Ok(mut inner) = self.inner.lock() else {
+            return false;
+        };
+        if !inner.entries.get(id).map_or(false, |e| e.visible_to(owner)) {
+            return false;
+        }
+        inner.order.retain(|existing| existing != id);
+        inner.entries.remove(id).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.lock().map(|i| i.entries.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_store_insert_get_remove() {
+        let store = ResponseStore::new(10);
+        store.insert("resp_1".into(), json!({"id": "resp_1"}), vec![json!({"role": "user"})], None);
+
+        let stored = store.get("resp_1", None).unwrap();
+        assert_eq!(stored.response["id"], "resp_1");
+        assert_eq!(stored.messages.len(), 1);
+
+        assert!(store.remove("resp_1", None));
+        assert!(store.get("resp_1", None).is_none());
+    }
+
+    #[test]
+    fn test_store_scopes_entries_to_owner() {
+        let store = ResponseStore::new(10);
+        store.insert("resp_a".into(), json!({"id": "resp_a"}), vec![], Some("tok_a".into()));
+        store.insert("resp_admin".into(), json!({"id": "resp_admin"}), vec![], None);
+
+        assert!(store.get("resp_a", Some("tok_a")).is_some());
+        assert!(store.get("resp_a", Some("tok_b")).is_none());
+        assert!(store.get("resp_admin", Some("tok_a")).is_none());
+        // api_key 可见全部
+        assert!(store.get("resp_a", None).is_some());
+
+        assert!(!store.remove("resp_a", Some("tok_b")));
+        assert!(store.get("resp_a", Some("tok_a")).is_some());
+        assert!(store.remove("resp_a", Some("tok_a")));
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn test_store_evicts_oldest() {
+        let store = ResponseStore::new(2);
+        store.insert("a".into(), json!({}), vec![], None);
+        store.insert("b".into(), json!({}), vec![], None);
+        store.insert("c".into(), json!({}), vec![], None);
+
+        assert_eq!(store.len(), 2);
+        assert!(store.get("a", None).is_none());
+        assert!(store.get("c", None).is_some());
+    }
+}
@@ -0,0 +1,788 @@
+// Responses 流式转换
+// Gemini SSE → Responses 事件流 (response.created / output_item / output_text.delta / ... / response.completed)
+
+use super::store::ResponseStore;
+use crate::proxy::mappers::openai::streaming::store_thought_signature;
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::pin::Pin;
+use uuid::Uuid;
+
+/// 当前正在输出的条目 (reasoning 与 message 需要显式关闭)
+#[derive(Debug)]
+enum OpenItem {
+    None,
+    Reasoning { id: String, text: String },
+    Message { id: String, text: String },
+}
+
+/// Responses 事件流状态机
+///
+/// 每个 Gemini chunk 通过 `process_chunk` 转换为零个或多个 Responses 事件，
+/// `start` / `finish` / `fail` 负责首尾事件。事件均带递增的 `sequence_number`。
+pub struct ResponsesStreamState {
+    response_id: String,
+    model: String,
+    created_at: i64,
+    sequence_number: u64,
+    /// 已完成的输出项
+    output: Vec<Value>,
+    open: OpenItem,
+    emitted_calls: HashSet<String>,
+    usage: Option<Value>,
+    finish_reason: Option<String>,
+    status: &'static str,
+    session_id: String,
+    message_count: usize,
+    /// 写入 ResponseStore 时记录的创建者 (用户令牌 ID)
+    owner_token_id: Option<String>,
+}
+
+impl ResponsesStreamState {
+    pub fn new(model: String, session_id: String, message_count: usize) -> Self {
+        Self {
+            response_id: format!("resp_{}", Uuid::new_v4().simple()),
+            model,
+            created_at: chrono::Utc::now().timestamp(),
+            sequence_number: 0,
+            output: Vec::new(),
+            open: OpenItem::None,
+            emitted_calls: HashSet::new(),
+            usage: None,
+            finish_reason: None,
+            status: "in_progress",
+            session_id,
+            message_count,
+            owner_token_id: None,
+        }
+    }
+
+    /// 设置创建者，存储的 Response 仅对该用户令牌可见
+    pub fn with_owner(mut self, owner_token_id: Option<String>) -> Self {
+        self.owner_token_id = owner_token_id;
+        self
+    }
+
+    pub fn response_id(&self) -> &str {
+        &self.response_id
+    }
+
+    pub fn owner_token_id(&self) -> Option<String> {
+        self.owner_token_id.clone()
+    }
+
+    fn event(&mut self, event_type: &str, mut payload: Value) -> Value {
+        payload["type"] = json!(event_type);
+        payload["sequence_number"] = json!(self.sequence_number);
+        self.sequence_number += 1;
+        payload
+    }
+
+    /// 当前状态下的 Response 对象
+    pub fn response_object(&self) -> Value {
+        let incomplete_details = match (self.status, self.finish_reason.as_deref()) {
+            ("incomplete", Some("SAFETY")) | ("incomplete", Some("RECITATION")) => {
+                json!({ "reason": "content_filter" })
+            }
+            ("incomplete", _) => json!({ "reason": "max_output_tokens" }),
+            _ => Value::Null,
+        };
+
+        json!({
+            "id": self.response_id,
+            "object": "response",
+            "created_at": self.created_at,
+            "status": self.status,
+            "model": self.model,
+            "output": self.output,
+            "incomplete_details": incomplete_details,
+            "usage": self.usage,
+        })
+    }
+
+    /// 起始事件: response.created + response.in_progress
+    pub fn start(&mut self) -> Vec<Value> {
+        let response = self.response_object();
+        vec![
+            self.event("response.created", json!({ "response": response })),
+            self.event("response.in_progress", json!({ "response": response })),
+        ]
+    }
+
+    /// 处理一个 Gemini 响应 chunk (已解包 `response` 字段)
+    pub fn process_chunk(&mut self, chunk: &Value) -> Vec<Value> {
+        let mut events = Vec::new();
+
+        if let Some(u) = chunk.get("usageMetadata") {
+            self.usage = Some(convert_usage(u));
+        }
+
+        let Some(candidate) = chunk
+            .get("candidates")
+            .and_then(|c| c.as_array())
+            .and_then(|c| c.first())
+        else {
+            return events;
+        };
+
+        if let Some(parts) = candidate
+            .get("content")
+            .and_then(|c| c.get("parts"))
+            .and_then(|p| p.as_array())
+        {
+            for part in parts {
+                if let Some(sig) = part
+                    .get("thoughtSignature")
+                    .or(part.get("thought_signature"))
+                    .and_then(|s| s.as_str())
+                {
+                    store_thought_signature(sig, &self.session_id, self.message_count);
+                }
+
+                let is_thought = part.get("thought").and_then(|v| v.as_bool()).unwrap_or(false);
+                if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                    if text.is_empty() {
+                        continue;
+                    }
+                    if is_thought {
+                        events.extend(self.push_reasoning_delta(text));
+                    } else {
+                        events.extend(self.push_text_delta(text));
+                    }
+                }
+
+                if let Some(func_call) = part.get("functionCall") {
+                    events.extend(self.push_function_call(func_call));
+                }
+            }
+        }
+
+        if let Some(reason) = candidate.get("finishReason").and_then(|f| f.as_str()) {
+            self.finish_reason = Some(reason.to_string());
+        }
+
+        events
+    }
+
+    fn push_reasoning_delta(&mut self, text: &str) -> Vec<Value> {
+        let mut events = Vec::new();
+        if !matches!(self.open, OpenItem::Reasoning { .. }) {
+            events.extend(self.close_open_item());
+            let id = format!("rs_{}", Uuid::new_v4().simple());
+            let output_index = self.output.len();
+            events.push(self.event(
+                "response.output_item.added",
+                json!({
+                    "output_index": output_index,
+                    "item": { "id": id, "type": "reasoning", "summary": [] }
+                }),
+            ));
+            events.push(self.event(
+                "response.reasoning_summary_part.added",
+                json!({
+                    "item_id": id,
+                    "output_index": output_index,
+                    "summary_index": 0,
+                    "part": { "type": "summary_text", "text": "" }
+                }),
+            ));
+            self.open = OpenItem::Reasoning { id, text: String::new() };
+        }
+
+        let output_index = self.output.len();
+        let item_id = match &mut self.open {
+            OpenItem::Reasoning { id, text: acc } => {
+                acc.push_str(text);
+                id.clone()
+            }
+            _ => unreachable!(),
+        };
+        events.push(self.event(
+            "response.reasoning_summary_text.delta",
+            json!({
+                "item_id": item_id,
+                "output_index": output_index,
+                "summary_index": 0,
+                "delta": text
+            }),
+        ));
+        events
+    }
+
+    fn push_text_delta(&mut self, text: &str) -> Vec<Value> {
+        let mut events = Vec::new();
+        if !matches!(self.open, OpenItem::Message { .. }) {
+            events.extend(self.close_open_item());
+            let id = format!("msg_{}", Uuid::new_v4().simple());
+            let output_index = self.output.len();
+            events.push(self.event(
+                "response.output_item.added",
+                json!({
+                    "output_index": output_index,
+                    "item": {
+                        "id": id,
+                        "type": "message",
+                        "role": "assistant",
+                        "status": "in_progress",
+                        "content": []
+                    }
+                }),
+            ));
+            events.push(self.event(
+                "response.content_part.added",
+                json!({
+                    "item_id": id,
+                    "output_index": output_index,
+                    "content_index": 0,
+                    "part": { "type": "output_text", "text": "", "annotations": [] }
+                }),
+            ));
+            self.open = OpenItem::Message { id, text: String::new() };
+        }
+
+        let output_index = self.output.len();
+        let item_id = match &mut self.open {
+            OpenItem::Message { id, text: acc } => {
+                acc.push_str(text);
+                id.clone()
+            }
+            _ => unreachable!(),
+        };
+        events.push(self.event(
+            "response.output_text.delta",
+            json!({
+                "item_id": item_id,
+                "output_index": output_index,
+                "content_index": 0,
+                "delta": text
+            }),
+        ));
+        events
+    }
+
+    fn push_function_call(&mut self, func_call: &Value) -> Vec<Value> {
+        let call_key = serde_json::to_string(func_call).unwrap_or_default();
+        if !self.emitted_calls.insert(call_key.clone()) {
+            return Vec::new();
+        }
+
+        let mut events = self.close_open_item();
+
+        let name = func_call
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown")
+            .to_string();
+        let arguments = func_call
+            .get("args")
+            .map(|a| a.to_string())
+            .unwrap_or_else(|| "{}".to_string());
+        let call_id = func_call
+            .get("id")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| {
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                call_key.hash(&mut hasher);
+                format!("call_{:x}", hasher.finish())
+            });
+        let item_id = format!("fc_{}", Uuid::new_v4().simple());
+        let output_index = self.output.len();
+
+        events.push(self.event(
+            "response.output_item.added",
+            json!({
+                "output_index": output_index,
+                "item": {
+                    "id": item_id,
+                    "type": "function_call",
+                    "status": "in_progress",
+                    "call_id": call_id,
+                    "name": name,
+                    "arguments": ""
+                }
+            }),
+        ));
+        // Gemini 一次性返回完整参数，作为单个 delta 下发
+        events.push(self.event(
+            "response.function_call_arguments.delta",
+            json!({
+                "item_id": item_id,
+                "output_index": output_index,
+                "delta": arguments
+            }),
+        ));
+        events.push(self.event(
+            "response.function_call_arguments.done",
+            json!({
+                "item_id": item_id,
+                "output_index": output_index,
+                "arguments": arguments
+            }),
+        ));
+
+        let item = json!({
+            "id": item_id,
+            "type": "function_call",
+            "status": "completed",
+            "call_id": call_id,
+            "name": name,
+            "arguments": arguments
+        });
+        events.push(self.event(
+            "response.output_item.done",
+            json!({ "output_index": output_index, "item": item }),
+        ));
+        self.output.push(item);
+        events
+    }
+
+    /// 关闭当前打开的 reasoning / message 条目
+    fn close_open_item(&mut self) -> Vec<Value> {
+        let output_index = self.output.len();
+        let mut events = Vec::new();
+
+        match std::mem::replace(&mut self.open, OpenItem::None) {
+            OpenItem::None => {}
+            OpenItem::Reasoning { id, text } => {
+                let part = json!({ "type": "summary_text", "text": text });
+                events.push(self.event(
+                    "response.reasoning_summary_text.done",
+                    json!({
+                        "item_id": id,
+                        "output_index": output_index,
+                        "summary_index": 0,
+                        "text": text
+                    }),
+                ));
+                events.push(self.event(
+                    "response.reasoning_summary_part.done",
+                    json!({
+                        "item_id": id,
+                        "output_index": output_index,
+                        "summary_index": 0,
+                        "part": part
+                    }),
+                ));
+                let item = json!({ "id": id, "type": "reasoning", "summary": [part] });
+                events.push(self.event(
+                    "response.output_item.done",
+                    json!({ "output_index": output_index, "item": item }),
+                ));
+                self.output.push(item);
+            }
+            OpenItem::Message { id, text } => {
+                let part = json!({ "type": "output_text", "text": text, "annotations": [] });
+                events.push(self.event(
+                    "response.output_text.done",
+                    json!({
+                        "item_id": id,
+                        "output_index": output_index,
+                        "content_index": 0,
+                        "text": text
+                    }),
+                ));
+                events.push(self.event(
+                    "response.content_part.done",
+                    json!({
+                        "item_id": id,
+                        "output_index": output_index,
+                        "content_index": 0,
+                        "part": part
+                    }),
+                ));
+                let item = json!({
+                    "id": id,
+                    "type": "message",
+                    "role": "assistant",
+                    "status": "completed",
+                    "content": [part]
+                });
+                events.push(self.event(
+                    "response.output_item.done",
+                    json!({ "output_index": output_index, "item": item }),
+                ));
+                self.output.push(item);
+            }
+        }
+        events
+    }
+
+    /// 结束事件: response.completed 或 response.incomplete (MAX_TOKENS / SAFETY)
+    pub fn finish(&mut self) -> Vec<Value> {
+        let mut events = self.close_open_item();
+
+        self.status = match self.finish_reason.as_deref() {
+            Some("MAX_TOKENS") | Some("SAFETY") | Some("RECITATION") => "incomplete",
+            _ => "completed",
+        };
+        let event_type = if self.status == "completed" {
+            "response.completed"
+        } else {
+            "response.incomplete"
+        };
+        let response = self.response_object();
+        events.push(self.event(event_type, json!({ "response": response })));
+        events
+    }
+
+    /// 失败事件: response.failed
+    pub fn fail(&mut self, message: &str) -> Vec<Value> {
+        let mut events = self.close_open_item();
+        self.status = "failed";
+        let mut response = self.response_object();
+        response["error"] = json!({ "code": "server_error", "message": message });
+        events.push(self.event("response.failed", json!({ "response": response })));
+        events
+    }
+
+    /// 本轮输出对应的 Chat 格式 assistant 消息 (用于 previous_response_id 续接)
+    pub fn output_messages(&self) -> Vec<Value> {
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+
+        for item in &self.output {
+            match item["type"].as_str() {
+                Some("message") => {
+                    for part in item["content"].as_array().into_iter().flatten() {
+                        if let Some(t) = part["text"].as_str() {
+                            text.push_str(t);
+                        }
+                    }
+                }
+                Some("function_call") => tool_calls.push(json!({
+                    "id": item["call_id"],
+                    "type": "function",
+                    "function": { "name": item["name"], "arguments": item["arguments"] }
+                })),
+                _ => {}
+            }
+        }
+
+        if text.is_empty() && tool_calls.is_empty() {
+            return Vec::new();
+        }
+        let mut msg = json!({ "role": "assistant", "content": text });
+        if !tool_calls.is_empty() {
+            msg["tool_calls"] = json!(tool_calls);
+        }
+        vec![msg]
+    }
+}
+
+/// Gemini usageMetadata → Responses usage
+fn convert_usage(u: &Value) -> Value {
+    let input_tokens = u.get("promptTokenCount").and_then(|v| v.as_u64()).unwrap_or(0);
+    let output_tokens = u.get("candidatesTokenCount").and_then(|v| v.as_u64()).unwrap_or(0)
+        + u.get("thoughtsTokenCount").and_then(|v| v.as_u64()).unwrap_or(0);
+    let total_tokens = u
+        .get("totalTokenCount")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(input_tokens + output_tokens);
+
+    json!({
+        "input_tokens": input_tokens,
+        "input_tokens_details": {
+            "cached_tokens": u.get("cachedContentTokenCount").and_then(|v| v.as_u64()).unwrap_or(0)
+        },
+        "output_tokens": output_tokens,
+        "output_tokens_details": {
+            "reasoning_tokens": u.get("thoughtsTokenCount").and_then(|v| v.as_u64()).unwrap_or(0)
+        },
+        "total_tokens": total_tokens
+    })
+}
+
+/// 解析一行 Gemini SSE，返回解包后的响应 JSON
+pub(crate) fn parse_gemini_sse_line(line: &str) -> Option<Value> {
+    let line = line.trim();
+    let json_part = line.strip_prefix("data:")?.trim();
+    if json_part.is_empty() || json_part == "[DONE]" {
+        return None;
+    }
+    let mut json = serde_json::from_str::<Value>(json_part).ok()?;
+    match json.get_mut("response").map(|v| v.take()) {
+        Some(inner) => Some(inner),
+        None => Some(json),
+    }
+}
+
+/// 上游在 SSE 中途下发的错误 (`{"error": {...}}`)
+pub(crate) fn upstream_error_message(chunk: &Value) -> Option<String> {
+    let error = chunk.get("error")?;
+    Some(
+        error
+            .get("message")
+            .and_then(|m| m.as_str())
+            .map(|m| m.to_string())
+            .unwrap_or_else(|| error.to_string()),
+    )
+}
+
+/// 格式化为 Responses SSE 事件
+pub fn format_sse_event(event: &Value) -> Bytes {
+    let event_type = event["type"].as_str().unwrap_or("message");
+    Bytes::from(format!("event: {}\ndata: {}\n\n", event_type, event))
+}
+
+/// 将 Gemini SSE 流转换为 Responses 事件流
+///
+/// `conversation` 为 Some 时，响应结束后将结果与对话历史写入 ResponseStore。
+/// 上游中途出错时以 `response.failed` 结束 (Responses 流没有 `[DONE]` 结束标记)。
+pub fn create_responses_sse_stream<S, E>(
+    mut gemini_stream: Pin<Box<S>>,
+    mut state: ResponsesStreamState,
+    conversation: Option<Vec<Value>>,
+) -> Pin<Box<dyn Stream<Item = Result<Bytes, String>> + Send>>
+where
+    S: Stream<Item = Result<Bytes, E>> + Send + ?Sized + 'static,
+    E: std::fmt::Display + Send + 'static,
+{
+    let mut buffer = BytesMut::new();
+
+    let stream = async_stream::stream! {
+        for ev in state.start() {
+            yield Ok::<Bytes, String>(format_sse_event(&ev));
+        }
+
+        let mut heartbeat_interval = tokio::time::interval(std::time::Duration::from_secs(15));
+        heartbeat_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut stream_error: Option<String> = None;
+
+        'outer: loop {
+            tokio::select! {
+                item = gemini_stream.next() => {
+                    match item {
+                        Some(Ok(bytes)) => {
+                            buffer.extend_from_slice(&bytes);
+                            while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
+                                let line_raw = buffer.split_to(pos + 1);
+                                let Ok(line_str) = std::str::from_utf8(&line_raw) else { continue };
+                                if let Some(chunk) = parse_gemini_sse_line(line_str) {
+                                    if let Some(message) = upstream_error_message(&chunk) {
+                                        stream_error = Some(format!("Upstream error: {}", message));
+                                        break 'outer;
+                                    }
+                                    for ev in state.process_chunk(&chunk) {
+                                        yield Ok::<Bytes, String>(format_sse_event(&ev));
+                                    }
+                                }
+                            }
+                        }
+                        Some(Err(e)) => {
+                            stream_error = Some(format!("Upstream stream error: {}", e));
+                            break;
+                        }
+                        None => break,
+                    }
+                }
+                _ = heartbeat_interval.tick() => { yield Ok::<Bytes, String>(Bytes::from(": ping\n\n")); }
+            }
+        }
+
+        let final_events = match &stream_error {
+            Some(msg) => {
+                tracing::warn!("[Responses] {}", msg);
+                state.fail(msg)
+            }
+            None => state.finish(),
+        };
+        for ev in &final_events {
+            yield Ok::<Bytes, String>(format_sse_event(ev));
+        }
+
+        if stream_error.is_none() {
+            if let Some(mut messages) = conversation {
+                messages.extend(state.output_messages());
+                ResponseStore::global().insert(
+                    state.response_id().to_string(),
+                    state.response_object(),
+                    messages,
+                    state.owner_token_id(),
+                );
+            }
+        }
+    };
+
+    Box::pin(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+
+    fn event_types(events: &[Value]) -> Vec<String> {
+        events
+            .iter()
+            .map(|e| e["type"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_reasoning_then_text_then_completed() {
+        let mut state = ResponsesStreamState::new("gemini-3-pro".into(), "sid".into(), 1);
+        let mut events = state.start();
+        events.extend(state.process_chunk(&json!({
+            "candidates": [{ "content": { "parts": [{ "text": "Let me think", "thought": true }] } }]
+        })));
+        events.extend(state.process_chunk(&json!({
+            "candidates": [{ "content": { "parts": [{ "text": "Hello" }, { "text": " world" }] }, "finishReason": "STOP" }],
+            "usageMetadata": { "promptTokenCount": 10, "candidatesTokenCount": 3, "thoughtsTokenCount": 4, "totalTokenCount": 17 }
+        })));
+        events.extend(state.finish());
+
+        assert_eq!(
+            event_types(&events),
+            vec![
+                "response.created",
+                "response.in_progress",
+                "response.output_item.added",
+                "response.reasoning_summary_part.added",
+                "response.reasoning_summary_text.delta",
+                "response.reasoning_summary_text.done",
+                "response.reasoning_summary_part.done",
+                "response.output_item.done",
+                "response.output_item.added",
+                "response.content_part.added",
+                "response.output_text.delta",
+                "response.output_text.delta",
+                "response.output_text.done",
+                "response.content_part.done",
+                "response.output_item.done",
+                "response.completed",
+            ]
+        );
+
+        // sequence_number 严格递增
+        for (i, ev) in events.iter().enumerate() {
+            assert_eq!(ev["sequence_number"], i as u64);
+        }
+
+        let completed = &events.last().unwrap()["response"];
+        assert_eq!(completed["status"], "completed");
+        assert_eq!(completed["output"].as_array().unwrap().len(), 2);
+        assert_eq!(completed["output"][1]["content"][0]["text"], "Hello world");
+        assert_eq!(completed["output"][1]["id"], events[8]["item"]["id"]);
+        assert_eq!(completed["usage"]["output_tokens"], 7);
+        assert_eq!(completed["usage"]["output_tokens_details"]["reasoning_tokens"], 4);
+    }
+
+    #[test]
+    fn test_function_call_events_and_dedup() {
+        let mut state = ResponsesStreamState::new("gpt-5".into(), "sid".into(), 1);
+        state.start();
+        let chunk = json!({
+            "candidates": [{ "content": { "parts": [
+                { "text": "Running" },
+                { "functionCall": { "name": "shell", "args": { "command": ["ls"] } } }
+            ] } }]
+        });
+        let events = state.process_chunk(&chunk);
+        // 重复的 functionCall 不应重复下发
+        assert!(state.process_chunk(&json!({
+            "candidates": [{ "content": { "parts": [
+                { "functionCall": { "name": "shell", "args": { "command": ["ls"] } } }
+            ] } }]
+        })).is_empty());
+
+        let types = event_types(&events);
+        assert!(types.contains(&"response.function_call_arguments.delta".to_string()));
+        let delta = events
+            .iter()
+            .find(|e| e["type"] == "response.function_call_arguments.delta")
+            .unwrap();
+        assert_eq!(delta["output_index"], 1);
+        assert_eq!(delta["delta"], "{\"command\":[\"ls\"]}");
+
+        state.finish();
+        let messages = state.output_messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["content"], "Running");
+        assert_eq!(messages[0]["tool_calls"][0]["function"]["name"], "shell");
+    }
+
+    #[test]
+    fn test_max_tokens_is_incomplete() {
+        let mut state = ResponsesStreamState::new("gpt-5".into(), "sid".into(), 1);
+        state.start();
+        state.process_chunk(&json!({
+            "candidates": [{ "content": { "parts": [{ "text": "partial" }] }, "finishReason": "MAX_TOKENS" }]
+        }));
+        let events = state.finish();
+        let last = events.last().unwrap();
+        assert_eq!(last["type"], "response.incomplete");
+        assert_eq!(last["response"]["incomplete_details"]["reason"], "max_output_tokens");
+    }
+
+    #[tokio::test]
+    async fn test_sse_stream_stores_response() {
+        let chunk = json!({
+            "response": {
+                "candidates": [{ "content": { "parts": [{ "text": "Hi" }] }, "finishReason": "STOP" }]
+            }
+        });
+        let items: Vec<Result<Bytes, String>> =
+            vec![Ok(Bytes::from(format!("data: {}\n\n", chunk)))];
+
+        let state = ResponsesStreamState::new("gpt-5".into(), "sid".into(), 1)
+            .with_owner(Some("tok_stream".into()));
+        let response_id = state.response_id().to_string();
+        let mut sse = create_responses_sse_stream(
+            Box::pin(stream::iter(items)),
+            state,
+            Some(vec![json!({ "role": "user", "content": "Hello" })]),
+        );
+
+        let mut body = String::new();
+        while let Some(Ok(bytes)) = sse.next().await {
+            body.push_str(&String::from_utf8_lossy(&bytes));
+        }
+        assert!(body.contains("event: response.output_text.delta"));
+        assert!(body.contains("event: response.completed"));
+        assert!(!body.contains("[DONE]"));
+
+        let stored = ResponseStore::global().get(&response_id, Some("tok_stream")).unwrap();
+        assert_eq!(stored.messages.len(), 2);
+        assert_eq!(stored.messages[1]["content"], "Hi");
+        assert!(ResponseStore::global().get(&response_id, Some("tok_other")).is_none());
+        ResponseStore::global().remove(&response_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_sse_stream_fails_on_upstream_error_event() {
+        let text = json!({
+            "response": { "candidates": [{ "content": { "parts": [{ "text": "Par" }] } }] }
+        });
+        let error = json!({
+            "error": { "code": 503, "message": "The model is overloaded.", "status": "UNAVAILABLE" }
+        });
+        let items: Vec<Result<Bytes, String>> = vec![
+            Ok(Bytes::from(format!("data: {}\n\n", text))),
+            Ok(Bytes::from(format!("data: {}\n\n", error))),
+        ];
+
+        let state = ResponsesStreamState::new("gpt-5".into(), "sid".into(), 1);
+        let response_id = state.response_id().to_string();
+        let mut sse = create_responses_sse_stream(
+            Box::pin(stream::iter(items)),
+            state,
+            Some(vec![json!({ "role": "user", "content": "Hello" })]),
+        );
+
+        let mut body = String::new();
+        while let Some(Ok(bytes)) = sse.next().await {
+            body.push_str(&String::from_utf8_lossy(&bytes));
+        }
+        assert!(body.contains("event: response.output_text.delta"));
+        assert!(body.contains("event: response.failed"));
+        assert!(body.contains("The model is overloaded."));
+        assert!(!body.contains("event: response.completed"));
+        assert!(!body.contains("[DONE]"));
+        // 失败的 Response 不写入存储
+        assert!(ResponseStore::global().get(&response_id, None).is_none());
+    }
+}
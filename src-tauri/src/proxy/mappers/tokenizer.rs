@@ -0,0 +1,630 @@
+//! Offline Tokenizer Module
+//!
+//! Counts tokens locally without calling `countTokens` upstream. Used by the context
+//! manager (compression thresholds), count_tokens fallbacks and the estimation calibrator.
+//!
+//! Two implementations sit behind the [`Tokenizer`] trait:
+//! - [`VocabTokenizer`]: loads a SentencePiece Unigram vocabulary (`tokenizer.json` exported
+//!   by HuggingFace or a `tokenizer.vocab` TSV from `spm_export_vocab`) from the data
+//!   directory and counts with Viterbi segmentation over the piece scores + byte fallback.
+//!   A Gemma vocabulary matches Gemini's tokenization closely. BPE vocabularies are rejected.
+//! - [`SegmentTokenizer`]: the default (no vocabulary is bundled); mimics how a large (256k) SentencePiece
+//!   vocabulary splits text: whole common words, single digits, merged punctuation runs,
+//!   ~1.5 CJK characters per token.
+//!
+//! Media is not tokenized as bytes: images follow Gemini's tile rule (258 tokens per
+//! 768px tile, small images 258), PDFs cost 258 tokens per page.
+
+use base64::Engine;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+/// Gemini 对每个图像分块 / PDF 页计费的 Token 数
+pub const MEDIA_TOKENS_PER_TILE: u32 = 258;
+
+/// 图像两边均不超过该尺寸时按单块计费
+const SMALL_IMAGE_MAX_SIDE: u32 = 384;
+
+/// 解析图像尺寸时最多解码的 base64 前缀长度 (JPEG 的 SOF 段可能位于较大的 EXIF 之后)
+const IMAGE_HEADER_B64_PREFIX: usize = 128 * 1024;
+
+/// SentencePiece 的空格替代符
+const SPACE_MARKER: char = '\u{2581}';
+
+/// 数据目录中按顺序查找的词表文件
+const VOCAB_FILES: &[&str] = &["tokenizer.json", "tokenizer.vocab"];
+
+/// Offline token counter
+pub trait Tokenizer: Send + Sync {
+    /// Short identifier for logs
+    fn name(&self) -> &str;
+
+    /// Count the tokens of a plain text fragment
+    fn count(&self, text: &str) -> u32;
+}
+
+// ===== Built-in segmenter =====
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Space,
+    Newline,
+    Letter,
+    Digit,
+    Cjk,
+    OtherLetter,
+    Punct,
+    Symbol,
+}
+
+fn classify(c: char) -> CharClass {
+    match c {
+        '\n' | '\r' => CharClass::Newline,
+        c if c.is_whitespace() => CharClass::Space,
+        c if c.is_ascii_alphabetic() => CharClass::Letter,
+        c if c.is_ascii_digit() => CharClass::Digit,
+        c if c.is_ascii() => CharClass::Punct,
+        c if is_cjk(c) => CharClass::Cjk,
+        c if c.is_alphabetic() => CharClass::OtherLetter,
+        _ => CharClass::Symbol,
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF     // Hiragana / Katakana
+        | 0x3400..=0x4DBF   // CJK Extension A
+        | 0x4E00..=0x9FFF   // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF   // Hangul Syllables
+        | 0xF900..=0xFAFF   // CJK Compatibility Ideographs
+        | 0x20000..=0x2FA1F // CJK Extension B+
+    )
+}
+
+/// Rule-based approximation of a large SentencePiece vocabulary
+///
+/// - ASCII words: one token up to 8 letters, one more per 8 letters after that;
+///   camelCase / snake_case identifiers are split into their parts
+/// - Digits: one token each (Gemini splits numbers digit by digit)
+/// - ASCII punctuation: runs merge in pairs (`":`, `},`, `//`)
+/// - CJK: ~1.5 characters per token; other scripts ~4 letters per token
+/// - A single space is absorbed by the following word; longer runs and each line
+///   break cost one token
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SegmentTokenizer;
+
+impl SegmentTokenizer {
+    fn word_tokens(len: u32) -> u32 {
+        1 + len.saturating_sub(1) / 8
+    }
+}
+
+impl Tokenizer for SegmentTokenizer {
+    fn name(&self) -> &str {
+        "builtin-segmenter"
+    }
+
+    fn count(&self, text: &str) -> u32 {
+        let chars: Vec<char> = text.chars().collect();
+        let mut total = 0u32;
+        let mut i = 0;
+
+        while i < chars.len() {
+            let class = classify(chars[i]);
+            let start = i;
+            i += 1;
+
+            match class {
+                CharClass::Letter => {
+                    // 驼峰处断开: 小写后接大写视为新词
+                    while i < chars.len()
+                        && classify(chars[i]) == CharClass::Letter
+                        && !(chars[i].is_ascii_uppercase() && chars[i - 1].is_ascii_lowercase())
+                    {
+                        i += 1;
+                    }
+                    total += Self::word_tokens((i - start) as u32);
+                }
+                CharClass::OtherLetter => {
+                    while i < chars.len() && classify(chars[i]) == CharClass::OtherLetter {
+                        i += 1;
+                    }
+                    total += ((i - start) as u32).div_ceil(4);
+                }
+                CharClass::Cjk => {
+                    while i < chars.len() && classify(chars[i]) == CharClass::Cjk {
+                        i += 1;
+                    }
+                    total += ((i - start) as u32 * 2).div_ceil(3);
+                }
+                CharClass::Punct => {
+                    while i < chars.len() && classify(chars[i]) == CharClass::Punct {
+                        i += 1;
+                    }
+                    total += ((i - start) as u32).div_ceil(2);
+                }
+                CharClass::Newline => {
+                    while i < chars.len() && classify(chars[i]) == CharClass::Newline {
+                        i += 1;
+                    }
+                    total += 1;
+                }
+                CharClass::Space => {
+                    while i < chars.len() && classify(chars[i]) == CharClass::Space {
+                        i += 1;
+                    }
+                    // 单个空格并入后续单词 (▁word)；缩进等连续空白单独成词
+                    let absorbed = i < chars.len()
+                        && matches!(
+                            classify(chars[i]),
+                            CharClass::Letter | CharClass::OtherLetter | CharClass::Digit
+                        );
+                    if i - start > 1 || !absorbed {
+                        total += 1;
+                    }
+                }
+                CharClass::Digit | CharClass::Symbol => {
+                    total += 1;
+                }
+            }
+        }
+
+        total
+    }
+}
+
+// ===== Vocabulary tokenizer =====
+
+/// 未登录字符按字节回退时每个字节的惩罚 (与 SentencePiece 的 kUnkPenalty 一致)
+const UNK_PENALTY: f32 = 10.0;
+
+/// 无分数的词表 (TSV 缺少分数列) 中每个 piece 的默认分数
+const DEFAULT_PIECE_SCORE: f32 = -1.0;
+
+/// Unigram (SentencePiece) tokenizer over a scored vocabulary
+///
+/// Segments with Viterbi over the piece log-probabilities, the same search SentencePiece
+/// runs for Unigram models, so the count matches the real encoding rather than a greedy
+/// approximation. Characters outside the vocabulary fall back to one token per UTF-8 byte.
+///
+/// Byte-level BPE vocabularies (`Ġ` space marker, merge rules) cannot be segmented this
+/// way and are rejected.
+pub struct VocabTokenizer {
+    name: String,
+    pieces: HashMap<String, f32>,
+    max_piece_chars: usize,
+    /// 字节回退的单字节分数: 最低 piece 分数再减去惩罚
+    unk_score: f32,
+}
+
+impl VocabTokenizer {
+    pub fn from_scored_pieces<I: IntoIterator<Item = (String, f32)>>(
+        name: &str,
+        pieces: I,
+    ) -> Result<Self, String> {
+        let pieces: HashMap<String, f32> = pieces
+            .into_iter()
+            // 控制符 (<pad>, <unk>, <0x0A> 等) 不参与切分
+            .filter(|(p, _)| !p.is_empty() && !(p.starts_with('<') && p.ends_with('>')))
+            .collect();
+        if pieces.is_empty() {
+            return Err("vocabulary is empty".to_string());
+        }
+        // 限制单次匹配长度，避免超长 piece 拖慢切分
+        let max_piece_chars = pieces
+            .keys()
+            .map(|p| p.chars().count())
+            .max()
+            .unwrap_or(1)
+            .min(32);
+        let min_score = pieces.values().copied().fold(f32::INFINITY, f32::min);
+        Ok(Self {
+            name: name.to_string(),
+            pieces,
+            max_piece_chars,
+            unk_score: min_score - UNK_PENALTY,
+        })
+    }
+
+    /// HuggingFace `tokenizer.json` with a Unigram model (`vocab: [[piece, score], ...]`)
+    pub fn from_hf_json(name: &str, content: &str) -> Result<Self, String> {
+        let json: Value =
+            serde_json::from_str(content).map_err(|e| format!("invalid tokenizer.json: {}", e))?;
+        let model_type = json.pointer("/model/type").and_then(|t| t.as_str());
+        let vocab = json
+            .pointer("/model/vocab")
+            .ok_or("tokenizer.json has no model.vocab")?;
+        match (model_type, vocab) {
+            (None | Some("Unigram"), Value::Array(items)) => Self::from_scored_pieces(
+                name,
+                items.iter().filter_map(|item| {
+                    let piece = item.get(0)?.as_str()?;
+                    let score = item.get(1).and_then(|s| s.as_f64()).unwrap_or(DEFAULT_PIECE_SCORE as f64);
+                    Some((piece.to_string(), score as f32))
+                }),
+            ),
+            // BPE 的计数取决于合并规则与字节级预分词 (Ġ)，最长匹配 / Viterbi 都会算错
+            (Some("BPE"), _) | (_, Value::Object(_)) => Err(
+                "BPE vocabularies are not supported; use a SentencePiece Unigram tokenizer.json (e.g. Gemma)"
+                    .to_string(),
+            ),
+            _ => Err("unsupported model.vocab format".to_string()),
+        }
+    }
+
+    /// SentencePiece `.vocab` export (`piece<TAB>score` per line)
+    pub fn from_vocab_tsv(name: &str, content: &str) -> Result<Self, String> {
+        Self::from_scored_pieces(
+            name,
+            content.lines().filter_map(|line| {
+                let mut cols = line.split('\t');
+                let piece = cols.next()?;
+                let score = cols
+                    .next()
+                    .and_then(|s| s.trim().parse::<f32>().ok())
+                    .unwrap_or(DEFAULT_PIECE_SCORE);
+                Some((piece.to_string(), score))
+            }),
+        )
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "vocab".to_string());
+        if path.extension().and_then(|e| e.to_str()) == Some("json") {
+            Self::from_hf_json(&name, &content)
+        } else {
+            Self::from_vocab_tsv(&name, &content)
+        }
+    }
+}
+
+impl Tokenizer for VocabTokenizer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count(&self, text: &str) -> u32 {
+        let normalized: Vec<char> = text
+            .chars()
+            .map(|c| if c == ' ' { SPACE_MARKER } else { c })
+            .collect();
+        let n = normalized.len();
+        // best[i] = (到位置 i 的最高分, 对应的 Token 数)
+        let mut best: Vec<(f32, u32)> = vec![(f32::NEG_INFINITY, 0); n + 1];
+        best[0] = (0.0, 0);
+        let mut buf = String::new();
+
+        let relax = |best: &mut [(f32, u32)], to: usize, score: f32, tokens: u32| {
+            let (current, current_tokens) = best[to];
+            // 同分时取 Token 更少的切分
+            if score > current || (score == current && tokens < current_tokens) {
+                best[to] = (score, tokens);
+            }
+        };
+
+        for i in 0..n {
+            let (score, tokens) = best[i];
+            if score == f32::NEG_INFINITY {
+                continue;
+            }
+            buf.clear();
+            for len in 1..=self.max_piece_chars.min(n - i) {
+                buf.push(normalized[i + len - 1]);
+                if let Some(piece_score) = self.pieces.get(buf.as_str()) {
+                    relax(&mut best, i + len, score + piece_score, tokens + 1);
+                }
+            }
+            // byte fallback
+            let bytes = normalized[i].len_utf8() as u32;
+            relax(&mut best, i + 1, score + self.unk_score * bytes as f32, tokens + bytes);
+        }
+
+        best[n].1
+    }
+}
+
+// ===== Global instance =====
+
+static TOKENIZER: OnceLock<Box<dyn Tokenizer>> = OnceLock::new();
+
+fn load_tokenizer() -> Box<dyn Tokenizer> {
+    if let Ok(data_dir) = crate::modules::account::get_data_dir() {
+        for file in VOCAB_FILES {
+            let path = data_dir.join(file);
+            if !path.exists() {
+                continue;
+            }
+            match VocabTokenizer::load(&path) {
+                Ok(tokenizer) => {
+                    info!(
+                        "[Tokenizer] Loaded vocabulary {} ({} pieces)",
+                        path.display(),
+                        tokenizer.pieces.len()
+                    );
+                    return Box::new(tokenizer);
+                }
+                Err(e) => warn!("[Tokenizer] Ignoring {}: {}", path.display(), e),
+            }
+        }
+    }
+    info!("[Tokenizer] No vocabulary found in data dir, using built-in segmenter");
+    Box::new(SegmentTokenizer)
+}
+
+/// Global tokenizer: the data-dir vocabulary if present, otherwise the built-in segmenter
+pub fn get_tokenizer() -> &'static dyn Tokenizer {
+    TOKENIZER.get_or_init(load_tokenizer).as_ref()
+}
+
+/// Count tokens of a text fragment with the global tokenizer
+pub fn count_text(text: &str) -> u32 {
+    if text.is_empty() {
+        return 0;
+    }
+    get_tokenizer().count(text)
+}
+
+/// Count a JSON value the way the model sees it
+///
+/// Keys and string values are tokenized as text; each key / scalar adds one token for
+/// the surrounding syntax, instead of tokenizing every quote and brace.
+pub fn count_json(value: &Value) -> u32 {
+    match value {
+        Value::Null | Value::Bool(_) => 1,
+        Value::Number(n) => count_text(&n.to_string()),
+        Value::String(s) => count_text(s) + 1,
+        Value::Array(items) => 1 + items.iter().map(count_json).sum::<u32>(),
+        Value::Object(map) => {
+            1 + map
+                .iter()
+                .map(|(k, v)| count_text(k) + 1 + count_json(v))
+                .sum::<u32>()
+        }
+    }
+}
+
+/// Count a function / tool declaration (name + description + parameter schema)
+pub fn count_tool_declaration(
+    name: &str,
+    description: Option<&str>,
+    schema: Option<&Value>,
+) -> u32 {
+    // 每个声明约 8 Token 的固定结构开销
+    8 + count_text(name)
+        + description.map(count_text).unwrap_or(0)
+        + schema.map(count_json).unwrap_or(0)
+}
+
+// ===== Media =====
+
+/// Gemini image cost: 258 tokens when both sides <= 384px, otherwise 258 per tile
+///
+/// The tile size is `min(width, height) / 1.5`, clamped to 256..=768.
+pub fn image_tokens_for_dimensions(width: u32, height: u32) -> u32 {
+    if width == 0 || height == 0 {
+        return MEDIA_TOKENS_PER_TILE;
+    }
+    if width <= SMALL_IMAGE_MAX_SIDE && height <= SMALL_IMAGE_MAX_SIDE {
+        return MEDIA_TOKENS_PER_TILE;
+    }
+    let unit = ((width.min(height) as f32 / 1.5) as u32).clamp(256, 768);
+    width.div_ceil(unit) * height.div_ceil(unit) * MEDIA_TOKENS_PER_TILE
+}
+
+fn decode_b64_prefix(data: &str, max_chars: usize) -> Option<Vec<u8>> {
+    let data = data.trim();
+    let take = data.len().min(max_chars) / 4 * 4;
+    let prefix = data.get(..take)?;
+    base64::engine::general_purpose::STANDARD
+        .decode(prefix)
+        .or_else(|_| {
+            base64::engine::general_purpose::STANDARD_NO_PAD.decode(prefix.trim_end_matches('='))
+        })
+        .ok()
+}
+
+/// Read width / height from a PNG, GIF, JPEG or WebP header
+pub fn image_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let be32 = |b: &[u8]| u32::from_be_bytes([b[0], b[1], b[2], b[3]]);
+
+    // PNG: IHDR 紧跟在签名之后
+    if bytes.len() >= 24 && bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some((be32(&bytes[16..20]), be32(&bytes[20..24])));
+    }
+    // GIF: 逻辑屏幕尺寸 (小端)
+    if bytes.len() >= 10 && (bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a")) {
+        let w = u16::from_le_bytes([bytes[6], bytes[7]]) as u32;
+        let h = u16::from_le_bytes([bytes[8], bytes[9]]) as u32;
+        return Some((w, h));
+    }
+    // JPEG: 扫描到首个 SOF 段
+    if bytes.len() >= 4 && bytes[0] == 0xFF && bytes[1] == 0xD8 {
+        let mut i = 2;
+        while i + 9 < bytes.len() {
+            if bytes[i] != 0xFF {
+                i += 1;
+                continue;
+            }
+            let marker = bytes[i + 1];
+            let is_sof = matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC);
+            if is_sof {
+                let h = u16::from_be_bytes([bytes[i + 5], bytes[i + 6]]) as u32;
+                let w = u16::from_be_bytes([bytes[i + 7], bytes[i + 8]]) as u32;
+                return Some((w, h));
+            }
+            let len = u16::from_be_bytes([bytes[i + 2], bytes[i + 3]]) as usize;
+            i += 2 + len;
+        }
+        return None;
+    }
+    // WebP 等其他格式交给 image crate
+    image::ImageReader::new(std::io::Cursor::new(bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// Tokens for a base64 image; falls back to a single tile when the header is unreadable
+pub fn count_image(data_b64: &str) -> u32 {
+    decode_b64_prefix(data_b64, IMAGE_HEADER_B64_PREFIX)
+        .and_then(|bytes| image_dimensions(&bytes))
+        .map(|(w, h)| image_tokens_for_dimensions(w, h))
+        .unwrap_or(MEDIA_TOKENS_PER_TILE)
+}
+
+/// Tokens for a base64 document: PDFs by page count, text by content, others one tile
+pub fn count_document(media_type: &str, data_b64: &str) -> u32 {
+    if media_type.starts_with("text/") {
+        return decode_b64_prefix(data_b64, usize::MAX)
+            .map(|bytes| count_text(&String::from_utf8_lossy(&bytes)))
+            .unwrap_or(MEDIA_TOKENS_PER_TILE);
+    }
+    if media_type == "application/pdf" {
+        if let Some(bytes) = decode_b64_prefix(data_b64, usize::MAX) {
+            return pdf_page_count(&bytes).max(1) * MEDIA_TOKENS_PER_TILE;
+        }
+    }
+    MEDIA_TOKENS_PER_TILE
+}
+
+/// Tokens for an inline media part of any type (Gemini `inlineData`)
+pub fn count_media(mime_type: &str, data_b64: &str) -> u32 {
+    if mime_type.starts_with("image/") {
+        count_image(data_b64)
+    } else {
+        count_document(mime_type, data_b64)
+    }
+}
+
+/// 统计 PDF 中的 `/Type /Page` 对象 (不含 `/Pages` 目录节点)
+fn pdf_page_count(bytes: &[u8]) -> u32 {
+    let mut count = 0;
+    let mut i = 0;
+    while i + 5 <= bytes.len() {
+        if &bytes[i..i + 5] == b"/Type" {
+            let mut j = i + 5;
+            while j < bytes.len() && bytes[j].is_ascii_whitespace() {
+                j += 1;
+            }
+            if bytes[j..].starts_with(b"/Page") && bytes.get(j + 5) != Some(&b's') {
+                count += 1;
+            }
+            i = j;
+        } else {
+            i += 1;
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_segmenter_counts() {
+        let t = SegmentTokenizer;
+        assert_eq!(t.count(""), 0);
+        // ▁Hello ▁World
+        assert_eq!(t.count("Hello World"), 2);
+        // 数字逐位切分
+        assert_eq!(t.count("2024"), 4);
+        // get Token Count
+        assert_eq!(t.count("getTokenCount"), 3);
+        // 你好 / 世界 -> 4 个汉字约 3 Token
+        assert_eq!(t.count("你好世界"), 3);
+        // 缩进单独成词: \n + ▁▁▁ + return
+        assert_eq!(t.count("\n    return"), 3);
+    }
+
+    fn unscored(pieces: &[&str]) -> VocabTokenizer {
+        VocabTokenizer::from_scored_pieces("test", pieces.iter().map(|p| (p.to_string(), -1.0))).unwrap()
+    }
+
+    #[test]
+    fn test_vocab_segmentation_and_byte_fallback() {
+        let t = unscored(&["▁Hello", "▁Wor", "ld", "▁", "H", "e", "l", "o", "<unk>"]);
+        // "Hello" -> H e l l o; " World" -> ▁Wor ld
+        assert_eq!(t.count("Hello World"), 7);
+        assert_eq!(t.count(" Hello World"), 3);
+        // "é" 不在词表中 -> 2 字节
+        assert_eq!(t.count("é"), 2);
+    }
+
+    #[test]
+    fn test_vocab_viterbi_beats_greedy_longest_match() {
+        // 贪心最长匹配: ab + c + d (3)；Viterbi: a + bcd (2)
+        let t = unscored(&["ab", "a", "bcd", "c", "d"]);
+        assert_eq!(t.count("abcd"), 2);
+
+        // 分数决定切分: "▁th" + "e" 的总分高于整词 "▁the"
+        let scored = VocabTokenizer::from_scored_pieces(
+            "scored",
+            [("▁the", -12.0), ("▁th", -2.0), ("e", -1.0)].map(|(p, s)| (p.to_string(), s)),
+        )
+        .unwrap();
+        assert_eq!(scored.count(" the"), 2);
+    }
+
+    #[test]
+    fn test_vocab_from_hf_json() {
+        let unigram = json!({ "model": { "type": "Unigram", "vocab": [["<pad>", 0.0], ["▁hi", -1.0], ["!", -2.0]] } });
+        let t = VocabTokenizer::from_hf_json("u", &unigram.to_string()).unwrap();
+        assert_eq!(t.count(" hi!"), 2);
+
+        // 字节级 BPE (Ġ 空格符 + 合并规则) 无法正确切分，直接拒绝
+        let bpe = json!({ "model": { "type": "BPE", "vocab": { "Ġhi": 5, "!": 6 }, "merges": [] } });
+        assert!(VocabTokenizer::from_hf_json("b", &bpe.to_string()).unwrap_err().contains("BPE"));
+
+        assert!(VocabTokenizer::from_hf_json("x", "{}").is_err());
+
+        let tsv = VocabTokenizer::from_vocab_tsv("t", "<unk>\t0\n▁hi\t-3.5\n!\t-4\n").unwrap();
+        assert_eq!(tsv.count(" hi!"), 2);
+    }
+
+    #[test]
+    fn test_image_tiles() {
+        assert_eq!(image_tokens_for_dimensions(300, 200), 258);
+        // 1024x1024: unit = 682 -> 2x2 tiles
+        assert_eq!(image_tokens_for_dimensions(1024, 1024), 4 * 258);
+        // 1920x1080: unit = 720 -> 3x2 tiles
+        assert_eq!(image_tokens_for_dimensions(1920, 1080), 6 * 258);
+    }
+
+    #[test]
+    fn test_image_dimensions_from_png_header() {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        png.extend_from_slice(&1024u32.to_be_bytes());
+        png.extend_from_slice(&768u32.to_be_bytes());
+        png.extend_from_slice(&[8, 6, 0, 0, 0]);
+        assert_eq!(image_dimensions(&png), Some((1024, 768)));
+
+        let b64 = base64::engine::general_purpose::STANDARD.encode(&png);
+        assert_eq!(count_image(&b64), image_tokens_for_dimensions(1024, 768));
+        assert_eq!(count_image("not-base64"), MEDIA_TOKENS_PER_TILE);
+    }
+
+    #[test]
+    fn test_pdf_pages() {
+        let pdf = b"%PDF-1.4 1 0 obj << /Type /Pages /Count 2 >> 2 0 obj << /Type /Page >> 3 0 obj <</Type/Page>>";
+        let b64 = base64::engine::general_purpose::STANDARD.encode(pdf);
+        assert_eq!(count_document("application/pdf", &b64), 2 * 258);
+    }
+
+    #[test]
+    fn test_count_json_ignores_punctuation() {
+        let schema = json!({ "type": "object", "properties": { "path": { "type": "string" } } });
+        assert!(count_json(&schema) < count_text(&schema.to_string()) + 4);
+        assert!(count_json(&schema) > 5);
+    }
+}
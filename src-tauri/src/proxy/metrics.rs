@@ -0,0 +1,412 @@
+// Prometheus 指标导出
+// GET /metrics 以 Prometheus 文本格式 (0.0.4) 输出请求、Token、限流、代理池与配额指标
+
+use crate::proxy::monitor::ProxyRequestLog;
+use crate::proxy::rate_limit::RateLimitReason;
+use crate::proxy::server::AppState;
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::sync::{Mutex, OnceLock};
+
+/// 请求耗时直方图桶 (秒)
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct RequestLabels {
+    protocol: String,
+    model: String,
+    mapped_model: String,
+    account: String,
+    status: u16,
+}
+
+#[derive(Debug, Clone, Default)]
+struct RequestSeries {
+    count: u64,
+    sum_secs: f64,
+    /// 每个桶的非累计计数，渲染时再累加
+    buckets: [u64; LATENCY_BUCKETS.len()],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct TokenLabels {
+    protocol: String,
+    model: String,
+    account: String,
+    direction: &'static str,
+}
+
+#[derive(Default)]
+struct MetricsInner {
+    requests: HashMap<RequestLabels, RequestSeries>,
+    tokens: HashMap<TokenLabels, u64>,
+    /// (protocol, model) -> 响应缓存命中次数
+    cache_hits: HashMap<(String, String), u64>,
+}
+
+/// 进程内指标注册表
+#[derive(Default)]
+pub struct ProxyMetrics {
+    inner: Mutex<MetricsInner>,
+}
+
+impl ProxyMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 全局单例
+    pub fn global() -> &'static ProxyMetrics {
+        static METRICS: OnceLock<ProxyMetrics> = OnceLock::new();
+        METRICS.get_or_init(ProxyMetrics::new)
+    }
+
+    /// 记录一次已完成的代理请求 (由 ProxyMonitor::log_request 调用)
+    pub fn observe_request(&self, log: &ProxyRequestLog) {
+        let protocol = log.protocol.clone().unwrap_or_else(|| "unknown".to_string());
+        let model = log.model.clone().unwrap_or_else(|| "unknown".to_string());
+        let account = log.account_email.clone().unwrap_or_default();
+
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+
+        let labels = RequestLabels {
+            protocol: protocol.clone(),
+            model: model.clone(),
+            mapped_model: log.mapped_model.clone().unwrap_or_default(),
+            account: account.clone(),
+            status: log.status,
+        };
+        let secs = log.duration as f64 / 1000.0;
+        let series = inner.requests.entry(labels).or_default();
+        series.count += 1;
+        series.sum_secs += secs;
+        if let Some(idx) = LATENCY_BUCKETS.iter().position(|b| secs <= *b) {
+            series.buckets[idx] += 1;
+        }
+
+        // 缓存命中不消耗上游 Token，只计命中次数
+        if log.cache_hit {
+            *inner.cache_hits.entry((protocol, model)).or_default() += 1;
+            return;
+        }
+
+        for (direction, value) in [("input", log.input_tokens), ("output", log.output_tokens)] {
+            if let Some(v) = value.filter(|v| *v > 0) {
+                *inner
+                    .tokens
+                    .entry(TokenLabels {
+                        protocol: protocol.clone(),
+                        model: model.clone(),
+                        account: account.clone(),
+                        direction,
+                    })
+                    .or_default() += v as u64;
+            }
+        }
+    }
+
+    /// 输出请求计数、耗时直方图与 Token 计数
+    fn render_request_metrics(&self, out: &mut String) {
+        let Ok(inner) = self.inner.lock() else {
+            return;
+        };
+        // 排序保证输出稳定
+        let requests: BTreeMap<_, _> = inner.requests.iter().collect();
+        let tokens: BTreeMap<_, _> = inner.tokens.iter().collect();
+        let cache_hits: BTreeMap<_, _> = inner.cache_hits.iter().collect();
+
+        write_header(out, "antigravity_requests_total", "counter", "Proxied requests by protocol, model, account and status");
+        for (l, s) in &requests {
+            let _ = writeln!(out, "antigravity_requests_total{} {}", request_labels(l, None), s.count);
+        }
+
+        write_header(
+            out,
+            "antigravity_request_duration_seconds",
+            "histogram",
+            "Seconds until the response headers were returned; excludes stream body time, streams are observed when they end",
+        );
+        for (l, s) in &requests {
+            let mut cumulative = 0;
+            for (i, bound) in LATENCY_BUCKETS.iter().enumerate() {
+                cumulative += s.buckets[i];
+                let _ = writeln!(
+                    out,
+                    "antigravity_request_duration_seconds_bucket{} {}",
+                    request_labels(l, Some(&bound.to_string())),
+                    cumulative
+                );
+            }
+            let _ = writeln!(out, "antigravity_request_duration_seconds_bucket{} {}", request_labels(l, Some("+Inf")), s.count);
+            let _ = writeln!(out, "antigravity_request_duration_seconds_sum{} {}", request_labels(l, None), s.sum_secs);
+            let _ = writeln!(out, "antigravity_request_duration_seconds_count{} {}", request_labels(l, None), s.count);
+        }
+
+        write_header(out, "antigravity_tokens_total", "counter", "Tokens consumed by protocol, model, account and direction");
+        for (l, v) in &tokens {
+            let _ = writeln!(
+                out,
+                "antigravity_tokens_total{} {}",
+                format_labels(&[
+                    ("protocol", &l.protocol),
+                    ("model", &l.model),
+                    ("account", &l.account),
+                    ("direction", l.direction),
+                ]),
+                v
+            );
+        }
+
+        write_header(out, "antigravity_response_cache_hits_total", "counter", "Requests answered from the response cache by protocol and model");
+        for ((protocol, model), v) in &cache_hits {
+            let _ = writeln!(
+                out,
+                "antigravity_response_cache_hits_total{} {}",
+                format_labels(&[("protocol", protocol), ("model", model)]),
+                v
+            );
+        }
+    }
+}
+
+fn write_header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// 转义标签值 (反斜杠、双引号、换行)
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_labels(labels: &[(&str, &str)]) -> String {
+    let inner: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, escape_label_value(v)))
+        .collect();
+    format!("{{{}}}", inner.join(","))
+}
+
+fn request_labels(l: &RequestLabels, le: Option<&str>) -> String {
+    let status = l.status.to_string();
+    let mut labels = vec![
+        ("protocol", l.protocol.as_str()),
+        ("model", l.model.as_str()),
+        ("mapped_model", l.mapped_model.as_str()),
+        ("account", l.account.as_str()),
+        ("status", status.as_str()),
+    ];
+    if let Some(le) = le {
+        labels.push(("le", le));
+    }
+    format_labels(&labels)
+}
+
+/// 输出账号池与限流指标
+fn render_account_metrics(out: &mut String, pool_size: usize, limited: &HashMap<RateLimitReason, usize>) {
+    write_header(out, "antigravity_accounts_available", "gauge", "Accounts currently loaded in the proxy pool");
+    let _ = writeln!(out, "antigravity_accounts_available {}", pool_size);
+
+    write_header(out, "antigravity_rate_limited_accounts", "gauge", "Accounts with an active rate-limit lockout by reason");
+    for reason in [
+        RateLimitReason::QuotaExhausted,
+        RateLimitReason::RateLimitExceeded,
+        RateLimitReason::ModelCapacityExhausted,
+        RateLimitReason::ServerError,
+        RateLimitReason::Unknown,
+    ] {
+        let _ = writeln!(
+            out,
+            "antigravity_rate_limited_accounts{} {}",
+            format_labels(&[("reason", reason.as_str())]),
+            limited.get(&reason).copied().unwrap_or(0)
+        );
+    }
+}
+
+/// 输出代理池健康度与延迟
+fn render_proxy_pool_metrics(out: &mut String, pool: &crate::proxy::config::ProxyPoolConfig) {
+    write_header(out, "antigravity_proxy_pool_up", "gauge", "Health check result of each enabled pool proxy (1 = healthy)");
+    for p in pool.proxies.iter().filter(|p| p.enabled) {
+        let _ = writeln!(
+            out,
+            "antigravity_proxy_pool_up{} {}",
+            format_labels(&[("proxy_id", &p.id), ("name", &p.name)]),
+            if p.is_healthy { 1 } else { 0 }
+        );
+    }
+
+    write_header(out, "antigravity_proxy_pool_latency_seconds", "gauge", "Last measured health check latency of each pool proxy");
+    for p in pool.proxies.iter().filter(|p| p.enabled) {
+        if let Some(latency) = p.latency {
+            let _ = writeln!(
+                out,
+                "antigravity_proxy_pool_latency_seconds{} {}",
+                format_labels(&[("proxy_id", &p.id), ("name", &p.name)]),
+                latency as f64 / 1000.0
+            );
+        }
+    }
+}
+
+/// 输出每个账号各模型的剩余配额百分比
+fn render_quota_metrics(out: &mut String, accounts: &[crate::models::Account]) {
+    write_header(out, "antigravity_account_quota_percent", "gauge", "Remaining quota percentage per account and model");
+    for account in accounts.iter().filter(|a| !a.disabled) {
+        let Some(quota) = &account.quota else {
+            continue;
+        };
+        for m in &quota.models {
+            let _ = writeln!(
+                out,
+                "antigravity_account_quota_percent{} {}",
+                format_labels(&[("account", &account.email), ("model", &m.name)]),
+                m.percentage
+            );
+        }
+    }
+
+    write_header(out, "antigravity_account_forbidden", "gauge", "Whether the account was rejected with 403 on the last quota refresh");
+    for account in accounts.iter().filter(|a| !a.disabled) {
+        if let Some(quota) = &account.quota {
+            let _ = writeln!(
+                out,
+                "antigravity_account_forbidden{} {}",
+                format_labels(&[("account", &account.email)]),
+                if quota.is_forbidden { 1 } else { 0 }
+            );
+        }
+    }
+}
+
+/// GET /metrics
+pub async fn handle_metrics(State(state): State<AppState>) -> Response {
+    let mut out = String::with_capacity(16 * 1024);
+
+    ProxyMetrics::global().render_request_metrics(&mut out);
+
+    render_account_metrics(
+        &mut out,
+        state.token_manager.len(),
+        &state.token_manager.rate_limited_accounts_by_reason(),
+    );
+
+    {
+        let pool = state.proxy_pool_state.read().await;
+        render_proxy_pool_metrics(&mut out, &pool);
+    }
+
+    match tokio::task::spawn_blocking(crate::modules::account::list_accounts).await {
+        Ok(Ok(accounts)) => render_quota_metrics(&mut out, &accounts),
+        Ok(Err(e)) => tracing::debug!("[Metrics] Failed to load accounts for quota metrics: {}", e),
+        Err(e) => tracing::debug!("[Metrics] Quota metrics task failed: {}", e),
+    }
+
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        out,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(status: u16, duration: u64, input: Option<u32>, output: Option<u32>) -> ProxyRequestLog {
+        ProxyRequestLog {
+            id: "1".into(),
+            timestamp: 0,
+            method: "POST".into(),
+            url: "/v1/messages".into(),
+            status,
+            duration,
+            model: Some("claude-sonnet-4-5".into()),
+            mapped_model: Some("claude-sonnet-4-5-thinking".into()),
+            account_email: Some("a@example.com".into()),
+            client_ip: None,
+            error: None,
+            request_body: None,
+            response_body: None,
+            input_tokens: input,
+            output_tokens: output,
+            protocol: Some("anthropic".into()),
+            username: None,
+            cache_hit: false,
+            cached_tokens: None,
+            thinking_tokens: None,
+            cost_usd: None,
+        }
+    }
+
+    #[test]
+    fn test_request_counters_and_histogram() {
+        let metrics = ProxyMetrics::new();
+        metrics.observe_request(&log(200, 300, Some(100), Some(20)));
+        metrics.observe_request(&log(200, 4000, Some(50), Some(5)));
+        metrics.observe_request(&log(429, 50, None, None));
+
+        let mut out = String::new();
+        metrics.render_request_metrics(&mut out);
+
+        assert!(out.contains("# TYPE antigravity_requests_total counter"));
+        assert!(out.contains(
+            "antigravity_requests_total{protocol=\"anthropic\",model=\"claude-sonnet-4-5\",mapped_model=\"claude-sonnet-4-5-thinking\",account=\"a@example.com\",status=\"200\"} 2"
+        ));
+        // 0.5s 桶包含 0.3s 请求，5s 桶累计两次
+        assert!(out.contains("status=\"200\",le=\"0.5\"} 1"));
+        assert!(out.contains("status=\"200\",le=\"5\"} 2"));
+        assert!(out.contains("status=\"200\",le=\"+Inf\"} 2"));
+        assert!(out.contains("direction=\"input\"} 150"));
+        assert!(out.contains("direction=\"output\"} 25"));
+    }
+
+    #[test]
+    fn test_cache_hits_do_not_count_tokens() {
+        let metrics = ProxyMetrics::new();
+        let mut hit = log(200, 5, Some(100), Some(20));
+        hit.cache_hit = true;
+        hit.account_email = None;
+        metrics.observe_request(&hit);
+
+        let mut out = String::new();
+        metrics.render_request_metrics(&mut out);
+        assert!(out.contains(
+            "antigravity_response_cache_hits_total{protocol=\"anthropic\",model=\"claude-sonnet-4-5\"} 1"
+        ));
+        assert!(!out.contains("direction=\"input\""));
+        assert!(out.contains("account=\"\",status=\"200\"} 1"));
+    }
+
+    #[test]
+    fn test_label_escaping() {
+        assert_eq!(
+            format_labels(&[("model", "a\"b\\c\nd")]),
+            "{model=\"a\\\"b\\\\c\\nd\"}"
+        );
+    }
+
+    #[test]
+    fn test_rate_limited_accounts_render_all_reasons() {
+        let mut limited = HashMap::new();
+        limited.insert(RateLimitReason::QuotaExhausted, 2);
+        let mut out = String::new();
+        render_account_metrics(&mut out, 5, &limited);
+        assert!(out.contains("antigravity_accounts_available 5"));
+        assert!(out.contains("antigravity_rate_limited_accounts{reason=\"quota_exhausted\"} 2"));
+        assert!(out.contains("antigravity_rate_limited_accounts{reason=\"server_error\"} 0"));
+    }
+}
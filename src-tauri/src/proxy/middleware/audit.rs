@@ -0,0 +1,266 @@
+// Admin API 审计中间件
+//
+// 挂在 admin_auth_middleware 外层，未通过鉴权的变更请求 (401) 同样会被记录。
+// 只记录会改变状态的方法 (POST/PUT/PATCH/DELETE)，读接口不入库。
+use axum::{
+    body::{to_bytes, Body},
+    extract::{OriginalUri, Request},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+
+use crate::modules::audit::{self, ADMIN_REQUEST};
+use crate::modules::audit_db::AuditRecord;
+use crate::proxy::middleware::auth::AdminIdentity;
+
+/// 请求体超过该大小 (或无 Content-Length) 时不采集参数
+const MAX_CAPTURED_BODY: usize = 256 * 1024;
+/// 错误响应体超过该大小时不解析错误信息
+const MAX_ERROR_BODY: usize = 64 * 1024;
+
+/// 使用 POST 但只读、或调用极其频繁的接口，不记录
+const SKIPPED_PATHS: &[&str] = &[
+    "/api/accounts/device-preview",
+    "/api/proxy/routing/explain",
+    "/api/proxy/cli/status",
+    "/api/proxy/cli/config",
+    "/api/proxy/opencode/status",
+    "/api/proxy/opencode/config",
+    "/api/proxy/droid/status",
+    "/api/proxy/droid/config",
+    "/api/system/updates/check",
+    "/api/system/updates/touch",
+];
+
+/// 资源集合名，其后的路径段视为目标 ID
+const COLLECTION_SEGMENTS: &[&str] = &[
+    "accounts",
+    "user-tokens",
+    "rate-limits",
+    "device-versions",
+    "logs",
+    "users",
+    "sessions",
+];
+
+/// 集合下的固定子路由，不是 ID
+const STATIC_SEGMENTS: &[&str] = &[
+    "current",
+    "switch",
+    "refresh",
+    "device-preview",
+    "restore-original",
+    "import",
+    "sync",
+    "bulk-delete",
+    "export",
+    "encryption",
+    "reorder",
+    "warmup",
+    "oauth",
+    "summary",
+    "clear",
+    "count",
+    "groups",
+];
+
+/// 请求体中可作为目标的字段
+const TARGET_FIELDS: &[&str] = &[
+    "accountId",
+    "account_id",
+    "accountIds",
+    "account_ids",
+    "ipPattern",
+    "ip_pattern",
+    "id",
+    "username",
+];
+
+/// 由 handler 放入响应扩展的审计明细 (如配置 diff)，优先于请求体
+#[derive(Debug, Clone)]
+pub struct AuditDetails(pub Value);
+
+fn is_audited_method(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    )
+}
+
+/// 从路径或请求体推断操作对象
+fn extract_target(path: &str, body: Option<&Value>) -> Option<String> {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    for pair in segments.windows(2) {
+        if COLLECTION_SEGMENTS.contains(&pair[0]) && !STATIC_SEGMENTS.contains(&pair[1]) {
+            return Some(pair[1].to_string());
+        }
+    }
+
+    let body = body?.as_object()?;
+    TARGET_FIELDS.iter().find_map(|field| match body.get(*field)? {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Array(items) if !items.is_empty() => Some(
+            items
+                .iter()
+                .map(|v| v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string()))
+                .collect::<Vec<_>>()
+                .join(","),
+        ),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+/// 失败响应中的 `{"error": "..."}` / `{"error": {"message": "..."}}`
+fn extract_error(body: &[u8]) -> Option<String> {
+    let value: Value = serde_json::from_slice(body).ok()?;
+    match value.get("error")? {
+        Value::String(s) => Some(s.clone()),
+        Value::Object(obj) => obj.get("message").and_then(|m| m.as_str()).map(str::to_string),
+        _ => None,
+    }
+}
+
+pub async fn admin_audit_middleware(request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let path = request
+        .extensions()
+        .get::<OriginalUri>()
+        .map(|uri| uri.0.path().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+
+    if !is_audited_method(&method) || SKIPPED_PATHS.contains(&path.as_str()) {
+        return next.run(request).await;
+    }
+
+    let ip = super::ip_filter::extract_client_ip(&request);
+
+    // 采集请求体 (小于上限且声明了长度时)，读取后原样放回
+    let content_length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    let (request, body_json, body_omitted) = match content_length {
+        Some(len) if len > 0 && len <= MAX_CAPTURED_BODY => {
+            let (parts, body) = request.into_parts();
+            match to_bytes(body, MAX_CAPTURED_BODY).await {
+                Ok(bytes) => {
+                    let json = serde_json::from_slice::<Value>(&bytes).ok();
+                    (Request::from_parts(parts, Body::from(bytes)), json, false)
+                }
+                Err(e) => {
+                    tracing::warn!("[Audit] Failed to read request body for {}: {}", path, e);
+                    return Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(Body::from("Failed to read request body"))
+                        .unwrap_or_default();
+                }
+            }
+        }
+        Some(len) if len > MAX_CAPTURED_BODY => (request, None, true),
+        _ => (request, None, false),
+    };
+
+    let response = ADMIN_REQUEST.scope((), next.run(request)).await;
+    let status = response.status();
+
+    let target = extract_target(&path, body_json.as_ref());
+    let details = match response.extensions().get::<AuditDetails>() {
+        Some(AuditDetails(details)) => audit::redact(details),
+        None => match (&body_json, body_omitted) {
+            (Some(body), _) => serde_json::json!({ "body": audit::redact(body) }),
+            (None, true) => serde_json::json!({ "body": "(omitted: too large)" }),
+            (None, false) => Value::Null,
+        },
+    };
+
+    // 失败时尝试解析错误信息，需要把响应体读出来再放回
+    let (response, error) = if status.is_client_error() || status.is_server_error() {
+        let (parts, body) = response.into_parts();
+        match to_bytes(body, MAX_ERROR_BODY).await {
+            Ok(bytes) => {
+                let error = extract_error(&bytes).or_else(|| {
+                    status.canonical_reason().map(str::to_string)
+                });
+                (Response::from_parts(parts, Body::from(bytes)), error)
+            }
+            Err(_) => (
+                Response::from_parts(parts, Body::empty()),
+                status.canonical_reason().map(str::to_string),
+            ),
+        }
+    } else {
+        (response, None)
+    };
+
+    // 鉴权层 (或登录接口) 把身份写进响应扩展；admin_password / api_key 登录记为 admin
+    let actor = match response.extensions().get::<AdminIdentity>() {
+        Some(identity) => identity.username.clone(),
+        None if status == StatusCode::UNAUTHORIZED => "unauthenticated".to_string(),
+        None => "admin".to_string(),
+    };
+
+    let record = AuditRecord {
+        actor,
+        source: "admin_api".to_string(),
+        ip,
+        action: format!("{} {}", method, path),
+        target,
+        details,
+        success: status.is_success(),
+        status_code: Some(status.as_u16()),
+        error,
+    };
+    // 落库后再返回，保证响应发出时记录已存在
+    let _ = tokio::task::spawn_blocking(move || audit::record(record)).await;
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_extract_target_from_path_and_body() {
+        assert_eq!(
+            extract_target("/api/accounts/abc-123", None).as_deref(),
+            Some("abc-123")
+        );
+        assert_eq!(
+            extract_target("/api/accounts/abc/toggle-proxy", None).as_deref(),
+            Some("abc")
+        );
+        assert_eq!(
+            extract_target("/api/proxy/rate-limits/acc-9", None).as_deref(),
+            Some("acc-9")
+        );
+        assert_eq!(
+            extract_target("/api/accounts/switch", Some(&json!({ "accountId": "acc-1" }))).as_deref(),
+            Some("acc-1")
+        );
+        assert_eq!(
+            extract_target(
+                "/api/accounts/bulk-delete",
+                Some(&json!({ "accountIds": ["a", "b"] }))
+            )
+            .as_deref(),
+            Some("a,b")
+        );
+        assert_eq!(extract_target("/api/config", Some(&json!({ "config": {} }))), None);
+    }
+
+    #[test]
+    fn test_extract_error_shapes() {
+        assert_eq!(extract_error(br#"{"error":"nope"}"#).as_deref(), Some("nope"));
+        assert_eq!(
+            extract_error(br#"{"error":{"message":"bad","type":"x"}}"#).as_deref(),
+            Some("bad")
+        );
+        assert_eq!(extract_error(b"plain text"), None);
+    }
+}
@@ -2,13 +2,14 @@
 use axum::{
     extract::State,
     extract::Request,
-    http::{header, StatusCode},
+    http::{header, Method, StatusCode},
     middleware::Next,
     response::Response,
 };
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+use crate::modules::admin_user_db::{self, AdminRole, AdminSession};
 use crate::proxy::{ProxyAuthMode, ProxySecurityConfig};
 
 /// API Key 认证中间件 (代理接口使用，遵循 auth_mode)
@@ -77,10 +78,16 @@ async fn auth_middleware_internal(
             if let Some(token) = api_key {
                 // 尝试验证是否为 User Token（不阻止请求，只记录）
                 if let Ok(Some(user_token)) = crate::modules::user_token_db::get_token_by_value(token) {
+                    // 配额与模型白名单在任何鉴权模式下都生效
+                    let request = match enforce_user_token_quota(request, &user_token).await {
+                        Ok(r) => r,
+                        Err(resp) => return Ok(resp),
+                    };
                     let identity = UserTokenIdentity {
                         token_id: user_token.id,
                         token: user_token.token,
                         username: user_token.username,
+                        account_groups: user_token.allowed_groups,
                     };
                     // 注入 identity 到请求
                     let (mut parts, body) = request.into_parts();
@@ -90,6 +97,9 @@ async fn auth_middleware_internal(
                 }
             }
             
+            if let Err(resp) = enforce_global_budget(&request).await {
+                return Ok(resp);
+            }
             return Ok(next.run(request).await);
         }
 
@@ -113,6 +123,11 @@ async fn auth_middleware_internal(
         if is_health_check {
             return Ok(next.run(request).await);
         }
+
+        // 3. [NEW] 登录接口本身无需鉴权
+        if path == ADMIN_LOGIN_PATH {
+            return Ok(next.run(request).await);
+        }
     }
     
     // 从 header 中提取 API key
@@ -134,35 +149,26 @@ async fn auth_middleware_internal(
                 .and_then(|h| h.to_str().ok())
         });
 
-    if security.api_key.is_empty() && (security.admin_password.is_none() || security.admin_password.as_ref().unwrap().is_empty()) {
-        if force_strict {
-             tracing::error!("Admin auth is required but both api_key and admin_password are empty; denying request");
-             return Err(StatusCode::UNAUTHORIZED);
-        }
+    if force_strict {
+        // 管理接口：登录会话或 admin_password / api_key，并按角色校验路由权限
+        let api_key = api_key.map(str::to_string);
+        return admin_authorize(&security, api_key.as_deref(), &method, &path, request, next).await;
+    }
+
+    if security.api_key.is_empty() {
         tracing::error!("Proxy auth is enabled but api_key is empty; denying request");
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    // 认证逻辑
-    let authorized = if force_strict {
-        // 管理接口：优先使用独立的 admin_password，如果没有则回退使用 api_key
-        match &security.admin_password {
-            Some(pwd) if !pwd.is_empty() => {
-                api_key.map(|k| k == pwd).unwrap_or(false)
-            }
-            _ => {
-                // 回退使用 api_key
-                api_key.map(|k| k == security.api_key).unwrap_or(false)
-            }
-        }
-    } else {
-        // AI 代理接口：仅允许使用 api_key
-        api_key.map(|k| k == security.api_key).unwrap_or(false)
-    };
+    // AI 代理接口：仅允许使用 api_key
+    let authorized = api_key.map(|k| k == security.api_key).unwrap_or(false);
 
     if authorized {
+        if let Err(resp) = enforce_global_budget(&request).await {
+            return Ok(resp);
+        }
         Ok(next.run(request).await)
-    } else if !force_strict && api_key.is_some() {
+    } else if api_key.is_some() {
         // 尝试验证 UserToken
         let token = api_key.unwrap();
         
@@ -186,10 +192,16 @@ async fn auth_middleware_internal(
             Ok((true, _)) => {
                 // Token 有效，查询信息以便传递
                 if let Ok(Some(user_token)) = crate::modules::user_token_db::get_token_by_value(token) {
+                    // 校验配额 (RPM / 日月 Token / 模型白名单)
+                    let request = match enforce_user_token_quota(request, &user_token).await {
+                        Ok(r) => r,
+                        Err(resp) => return Ok(resp),
+                    };
                      let identity = UserTokenIdentity {
                         token_id: user_token.id,
                         token: user_token.token,
                         username: user_token.username,
+                        account_groups: user_token.allowed_groups,
                     };
                     
                     // [FIX] 将身份信息注入到请求 extensions 中，而不是响应
@@ -236,6 +248,475 @@ async fn auth_middleware_internal(
     }
 }
 
+/// 管理接口登录路径 (相对 `/api`)
+pub const ADMIN_LOGIN_PATH: &str = "/auth/login";
+
+/// 已通过鉴权的管理员身份
+///
+/// 同时写入请求与响应扩展：handler 据此读取当前用户，外层的审计中间件据此记录操作者。
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdminIdentity {
+    pub username: String,
+    pub role: AdminRole,
+    /// 通过 admin_password / api_key 登录时为空
+    pub session_id: Option<String>,
+}
+
+impl AdminIdentity {
+    /// admin_password / api_key 持有者，视为 Owner
+    pub fn legacy() -> Self {
+        Self {
+            username: "admin".to_string(),
+            role: AdminRole::Owner,
+            session_id: None,
+        }
+    }
+
+    fn from_session(session: &AdminSession) -> Self {
+        Self {
+            username: session.username.clone(),
+            role: session.role,
+            session_id: Some(session.id.clone()),
+        }
+    }
+}
+
+/// 仅 Owner 可访问的路径前缀 (任意方法)
+const OWNER_PREFIXES: &[&str] = &[
+    "/admin/",
+    "/accounts/export",
+    "/accounts/import/open",
+    "/accounts/encryption/",
+    "/accounts/bulk-delete",
+    "/system/backup",
+    "/system/restore",
+    "/proxy/api-key/",
+    "/proxy/cloudflared/install",
+    "/audit/export",
+];
+
+/// 仅 Owner 可修改的路径 (GET 仍按下方规则)
+const OWNER_WRITE_PATHS: &[&str] = &[
+    "/config",
+    "/security/config",
+    "/system/http-api/settings",
+    "/user-tokens",
+];
+
+/// 返回完整密钥 (api_key / admin_password / 令牌明文 / 代理凭据) 的读接口，仅 Owner 可见
+///
+/// 否则 Operator 可读出 api_key 后以 legacy 身份 (Owner) 登录，绕过角色限制。
+const OWNER_READ_PATHS: &[&str] = &["/config", "/user-tokens", "/proxy/pool/config"];
+
+/// 含敏感信息的只读接口，Viewer 不可见
+const SENSITIVE_READ_PREFIXES: &[&str] = &[
+    "/audit",
+    "/debug/",
+    "/auth/url",
+];
+
+/// 使用 POST 但不改变状态的接口
+const READ_ONLY_POSTS: &[&str] = &[
+    "/auth/logout",
+    "/accounts/device-preview",
+    "/proxy/routing/explain",
+    "/system/updates/check",
+    "/system/updates/touch",
+];
+
+fn matches_path(path: &str, rule: &str) -> bool {
+    if rule.ends_with('/') {
+        path.starts_with(rule)
+    } else {
+        path == rule || path.starts_with(&format!("{}/", rule))
+    }
+}
+
+/// 管理路由所需的最低角色 (路径不含 `/api` 前缀)
+pub fn required_admin_role(method: &Method, path: &str) -> AdminRole {
+    if OWNER_PREFIXES.iter().any(|rule| matches_path(path, rule)) {
+        return AdminRole::Owner;
+    }
+
+    let is_read = *method == Method::GET
+        || *method == Method::HEAD
+        || (*method == Method::POST && READ_ONLY_POSTS.contains(&path));
+    if is_read {
+        if OWNER_READ_PATHS.contains(&path) {
+            return AdminRole::Owner;
+        }
+        return if SENSITIVE_READ_PREFIXES.iter().any(|rule| matches_path(path, rule)) {
+            AdminRole::Operator
+        } else {
+            AdminRole::Viewer
+        };
+    }
+
+    if OWNER_WRITE_PATHS.iter().any(|rule| matches_path(path, rule)) {
+        return AdminRole::Owner;
+    }
+    // 删除账号本身 (DELETE /accounts/:id)；设备版本等子资源仍归 Operator
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    if *method == Method::DELETE && segments.len() == 2 && segments[0] == "accounts" {
+        return AdminRole::Owner;
+    }
+    AdminRole::Operator
+}
+
+fn forbidden_response(identity: &AdminIdentity, required: AdminRole) -> Response {
+    let body = serde_json::json!({
+        "error": format!(
+            "Permission denied: '{}' has role {} but this action requires {}",
+            identity.username,
+            identity.role.as_str(),
+            required.as_str()
+        )
+    });
+    let mut response = Response::builder()
+        .status(StatusCode::FORBIDDEN)
+        .header("Content-Type", "application/json")
+        .body(axum::body::Body::from(body.to_string()))
+        .unwrap();
+    response.extensions_mut().insert(identity.clone());
+    response
+}
+
+/// 管理接口鉴权：解析身份 -> 校验角色 -> 注入身份
+async fn admin_authorize(
+    security: &ProxySecurityConfig,
+    api_key: Option<&str>,
+    method: &Method,
+    path: &str,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let identity = match api_key {
+        Some(token) if token.starts_with(admin_user_db::SESSION_TOKEN_PREFIX) => {
+            let token = token.to_string();
+            let resolved = tokio::task::spawn_blocking(move || admin_user_db::resolve_session(&token))
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            match resolved {
+                Ok(Some(session)) => AdminIdentity::from_session(&session),
+                Ok(None) => return Err(StatusCode::UNAUTHORIZED),
+                Err(e) => {
+                    tracing::error!("Admin session lookup failed: {}", e);
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            }
+        }
+        _ => {
+            // 优先使用独立的 admin_password，如果没有则回退使用 api_key
+            let expected = match &security.admin_password {
+                Some(pwd) if !pwd.is_empty() => pwd.as_str(),
+                _ => security.api_key.as_str(),
+            };
+            if expected.is_empty() {
+                tracing::error!("Admin auth is required but both api_key and admin_password are empty; denying request");
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            if api_key != Some(expected) {
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            AdminIdentity::legacy()
+        }
+    };
+    authorize_identity(identity, method, path, request, next).await
+}
+
+/// 校验角色并把身份写入请求/响应扩展
+async fn authorize_identity(
+    identity: AdminIdentity,
+    method: &Method,
+    path: &str,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let required = required_admin_role(method, path);
+    if identity.role < required {
+        tracing::warn!(
+            "Admin '{}' ({}) denied {} {}: requires {}",
+            identity.username,
+            identity.role.as_str(),
+            method,
+            path,
+            required.as_str()
+        );
+        return Ok(forbidden_response(&identity, required));
+    }
+
+    let (mut parts, body) = request.into_parts();
+    parts.extensions.insert(identity.clone());
+    let mut response = next.run(Request::from_parts(parts, body)).await;
+    response.extensions_mut().insert(identity);
+    Ok(response)
+}
+
+/// 请求所属的 API 协议 (用于构造协议兼容的错误响应)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ApiProtocol {
+    Anthropic,
+    OpenAI,
+    Gemini,
+}
+
+fn detect_protocol(path: &str) -> ApiProtocol {
+    if path.starts_with("/v1/messages") {
+        ApiProtocol::Anthropic
+    } else if path.starts_with("/v1beta/") {
+        ApiProtocol::Gemini
+    } else {
+        ApiProtocol::OpenAI
+    }
+}
+
+/// 构造协议兼容的配额错误响应 (429 超限 / 403 模型不允许)
+fn quota_error_response(
+    protocol: ApiProtocol,
+    violation: &crate::modules::user_token_db::QuotaViolation,
+) -> Response {
+    use crate::modules::user_token_db::QuotaViolation;
+
+    let is_model_denied = matches!(violation, QuotaViolation::ModelNotAllowed { .. });
+    let status = if is_model_denied {
+        StatusCode::FORBIDDEN
+    } else {
+        StatusCode::TOO_MANY_REQUESTS
+    };
+    let message = violation.message();
+
+    let body = match protocol {
+        ApiProtocol::Anthropic => serde_json::json!({
+            "type": "error",
+            "error": {
+                "type": if is_model_denied { "permission_error" } else { "rate_limit_error" },
+                "message": message
+            }
+        }),
+        ApiProtocol::OpenAI => {
+            let (err_type, code) = match violation {
+                QuotaViolation::RateLimited { .. } => ("rate_limit_exceeded", "rate_limit_exceeded"),
+                QuotaViolation::ModelNotAllowed { .. } => ("invalid_request_error", "model_not_allowed"),
+                _ => ("insufficient_quota", "insufficient_quota"),
+            };
+            serde_json::json!({
+                "error": {
+                    "message": message,
+                    "type": err_type,
+                    "param": null,
+                    "code": code
+                }
+            })
+        }
+        ApiProtocol::Gemini => serde_json::json!({
+            "error": {
+                "code": status.as_u16(),
+                "message": message,
+                "status": if is_model_denied { "PERMISSION_DENIED" } else { "RESOURCE_EXHAUSTED" }
+            }
+        }),
+    };
+
+    let mut builder = Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json");
+    if let Some(secs) = violation.retry_after_secs() {
+        builder = builder.header(header::RETRY_AFTER, secs.to_string());
+    }
+    builder
+        .body(axum::body::Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// 构造协议兼容的请求体错误响应 (413 过大 / 400 读取失败)
+fn body_error_response(protocol: ApiProtocol, status: StatusCode, message: &str) -> Response {
+    let too_large = status == StatusCode::PAYLOAD_TOO_LARGE;
+    let body = match protocol {
+        ApiProtocol::Anthropic => serde_json::json!({
+            "type": "error",
+            "error": {
+                "type": if too_large { "request_too_large" } else { "invalid_request_error" },
+                "message": message
+            }
+        }),
+        ApiProtocol::OpenAI => serde_json::json!({
+            "error": {
+                "message": message,
+                "type": "invalid_request_error",
+                "param": null,
+                "code": if too_large { "request_too_large" } else { "invalid_request_body" }
+            }
+        }),
+        ApiProtocol::Gemini => serde_json::json!({
+            "error": {
+                "code": status.as_u16(),
+                "message": message,
+                "status": "INVALID_ARGUMENT"
+            }
+        }),
+    };
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(axum::body::Body::from(body.to_string()))
+        .unwrap()
+}
+
+/// body 读取失败是否由超出 `to_bytes` 的长度上限引起
+fn is_length_limit_error(err: &axum::Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(err);
+    while let Some(e) = source {
+        if e.to_string() == "length limit exceeded" {
+            return true;
+        }
+        source = e.source();
+    }
+    false
+}
+
+/// 提取请求的目标模型 (Gemini 从路径提取，其余协议从 JSON body 的 model 字段提取)
+///
+/// 仅在需要时读取 body，读取后重新组装请求以便下游继续使用。
+/// [FIX] body 读取失败 (超过上限或连接中断) 时直接返回协议兼容的 413/400，
+/// 不能以空 body 继续转发，否则模型白名单会被绕过。
+async fn extract_request_model(request: Request) -> Result<(Request, Option<String>), Response> {
+    let path = request.uri().path().to_string();
+    if let Some(rest) = path.strip_prefix("/v1beta/models/") {
+        let model = rest.split([':', '/']).next().unwrap_or(rest).to_string();
+        return Ok((request, Some(model)));
+    }
+
+    if request.method() != axum::http::Method::POST {
+        return Ok((request, None));
+    }
+
+    let is_json = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|ct| ct.contains("json"))
+        .unwrap_or(true);
+    if !is_json {
+        // multipart 等请求 (如音频转录) 不做 body 解析
+        return Ok((request, None));
+    }
+
+    let (parts, body) = request.into_parts();
+    match axum::body::to_bytes(body, MAX_MODEL_SNIFF_BODY_SIZE).await {
+        Ok(bytes) => {
+            let model = serde_json::from_slice::<serde_json::Value>(&bytes)
+                .ok()
+                .and_then(|v| v.get("model").and_then(|m| m.as_str()).map(|s| s.to_string()));
+            Ok((Request::from_parts(parts, axum::body::Body::from(bytes)), model))
+        }
+        Err(e) => {
+            let protocol = detect_protocol(&path);
+            if is_length_limit_error(&e) {
+                tracing::warn!("UserToken request body exceeds {} bytes: {}", MAX_MODEL_SNIFF_BODY_SIZE, path);
+                Err(body_error_response(
+                    protocol,
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    &format!("Request body exceeds the {} byte limit", MAX_MODEL_SNIFF_BODY_SIZE),
+                ))
+            } else {
+                tracing::warn!("Failed to read UserToken request body for {}: {}", path, e);
+                Err(body_error_response(
+                    protocol,
+                    StatusCode::BAD_REQUEST,
+                    &format!("Failed to read request body: {}", e),
+                ))
+            }
+        }
+    }
+}
+
+const MAX_MODEL_SNIFF_BODY_SIZE: usize = 100 * 1024 * 1024; // 与 monitor 中间件保持一致
+
+/// 校验 User Token 的配额与模型白名单
+///
+/// 通过时返回 (可能已重新组装 body 的) 请求，超限时返回协议兼容的错误响应。
+async fn enforce_user_token_quota(
+    request: Request,
+    user_token: &crate::modules::user_token_db::UserToken,
+) -> Result<Request, Response> {
+    let path = request.uri().path().to_string();
+    // 模型列表、健康检查、Token 计数等非推理请求不计入配额
+    // 批处理管理接口同样不计入：每条请求由 worker 执行时再按令牌计费
+    // 文件上传不是推理请求，容量由 proxy.files 限制
+    let is_count_tokens = path.ends_with("/count_tokens") || path.ends_with("countTokens");
+    if request.method() != axum::http::Method::POST
+        || is_count_tokens
+        || crate::proxy::batch_worker::is_batch_management_path(&path)
+        || crate::proxy::handlers::files::is_files_path(&path)
+    {
+        return Ok(request);
+    }
+
+    let (request, model) = if user_token.allowed_models.is_empty() {
+        (request, None)
+    } else {
+        extract_request_model(request).await?
+    };
+
+    // 先查全局预算 (只读)，被拒绝的请求不应消耗令牌自身的配额计数
+    enforce_global_budget(&request).await?;
+
+    match crate::modules::user_token_db::check_and_consume_quota(user_token, model.as_deref()) {
+        Ok(None) => Ok(request),
+        Ok(Some(violation)) => {
+            tracing::warn!(
+                "UserToken quota rejected for {}: {}",
+                user_token.username,
+                violation.message()
+            );
+            Err(quota_error_response(detect_protocol(&path), &violation))
+        }
+        Err(e) => {
+            // 配额存储异常时放行，避免数据库故障导致服务整体不可用
+            tracing::error!("UserToken quota check error: {}", e);
+            Ok(request)
+        }
+    }
+}
+
+/// 校验全局费用预算 (proxy.cost.budget)
+///
+/// 仅对推理请求生效；超限且配置为 block 时返回协议兼容的 429，配置为 warn 时只告警。
+async fn enforce_global_budget(request: &Request) -> Result<(), Response> {
+    let path = request.uri().path();
+    let is_count_tokens = path.ends_with("/count_tokens") || path.ends_with("countTokens");
+    if request.method() != axum::http::Method::POST
+        || is_count_tokens
+        || crate::proxy::batch_worker::is_batch_management_path(path)
+        || crate::proxy::handlers::files::is_files_path(path)
+    {
+        return Ok(());
+    }
+    if !crate::proxy::config::get_cost_config().budget.is_enabled() {
+        return Ok(());
+    }
+
+    let result = tokio::task::spawn_blocking(crate::modules::cost::check_global_budget)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    match result {
+        Ok(Some(violation)) => {
+            if crate::proxy::config::get_cost_config().budget.action == crate::proxy::config::BudgetAction::Block {
+                tracing::warn!("Global budget rejected request: {}", violation.message());
+                return Err(quota_error_response(detect_protocol(path), &violation));
+            }
+            crate::modules::cost::warn_budget_exceeded(&violation);
+            Ok(())
+        }
+        Ok(None) => Ok(()),
+        Err(e) => {
+            // 与用户令牌配额一致：统计库异常时放行
+            tracing::error!("Global budget check error: {}", e);
+            Ok(())
+        }
+    }
+}
+
 /// 用户令牌身份信息 (传递给 Monitor 使用)
 #[derive(Clone, Debug)]
 pub struct UserTokenIdentity {
@@ -243,6 +724,8 @@ pub struct UserTokenIdentity {
     #[allow(dead_code)] // 保留原始 token 便于审计/调试
     pub token: String,
     pub username: String,
+    /// 允许使用的账号分组，空 = 整个账号池
+    pub account_groups: Vec<String>,
 }
 
 #[cfg(test)]
@@ -276,4 +759,174 @@ mod tests {
     fn test_auth_placeholder() {
         assert!(true);
     }
+
+    #[test]
+    fn test_required_admin_role() {
+        use AdminRole::*;
+
+        // 统计与日志对 Viewer 开放
+        assert_eq!(required_admin_role(&Method::GET, "/accounts"), Viewer);
+        assert_eq!(required_admin_role(&Method::GET, "/stats/token/summary"), Viewer);
+        assert_eq!(required_admin_role(&Method::GET, "/logs"), Viewer);
+        assert_eq!(required_admin_role(&Method::POST, "/auth/logout"), Viewer);
+        // 含密钥的读接口
+        assert_eq!(required_admin_role(&Method::GET, "/audit"), Operator);
+        assert_eq!(required_admin_role(&Method::GET, "/user-tokens/summary"), Viewer);
+        assert_eq!(required_admin_role(&Method::GET, "/user-tokens/t1/quota"), Viewer);
+        // 返回完整密钥的读接口
+        assert_eq!(required_admin_role(&Method::GET, "/config"), Owner);
+        assert_eq!(required_admin_role(&Method::GET, "/user-tokens"), Owner);
+        assert_eq!(required_admin_role(&Method::GET, "/proxy/pool/config"), Owner);
+        // 日常操作
+        assert_eq!(required_admin_role(&Method::POST, "/accounts/switch"), Operator);
+        assert_eq!(required_admin_role(&Method::POST, "/logs/clear"), Operator);
+        assert_eq!(
+            required_admin_role(&Method::DELETE, "/accounts/a1/device-versions/v1"),
+            Operator
+        );
+        // 破坏性 / 导出令牌
+        assert_eq!(required_admin_role(&Method::DELETE, "/accounts/a1"), Owner);
+        assert_eq!(required_admin_role(&Method::POST, "/accounts/bulk-delete"), Owner);
+        assert_eq!(required_admin_role(&Method::POST, "/accounts/export"), Owner);
+        assert_eq!(required_admin_role(&Method::POST, "/accounts/export/seal"), Owner);
+        assert_eq!(required_admin_role(&Method::POST, "/config"), Owner);
+        assert_eq!(required_admin_role(&Method::PATCH, "/user-tokens/t1"), Owner);
+        assert_eq!(required_admin_role(&Method::GET, "/admin/users"), Owner);
+        assert_eq!(required_admin_role(&Method::GET, "/audit/export"), Owner);
+        // 前缀匹配按路径段，不误伤相似名称
+        assert_eq!(required_admin_role(&Method::GET, "/configuration"), Viewer);
+    }
+
+    #[tokio::test]
+    async fn test_forbidden_response_carries_identity() {
+        let identity = AdminIdentity {
+            username: "alice".to_string(),
+            role: AdminRole::Viewer,
+            session_id: Some("s1".to_string()),
+        };
+        let resp = forbidden_response(&identity, AdminRole::Owner);
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert_eq!(resp.extensions().get::<AdminIdentity>(), Some(&identity));
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert!(body["error"].as_str().unwrap().contains("requires owner"));
+    }
+
+    #[tokio::test]
+    async fn test_operator_cannot_read_config_secrets() {
+        use axum::{routing::get, Router};
+        use tower::ServiceExt;
+
+        let app = Router::new()
+            .route(
+                "/config",
+                get(|| async {
+                    let mut cfg = crate::models::AppConfig::new();
+                    cfg.proxy.api_key = "sk-secret-key".to_string();
+                    cfg.proxy.admin_password = Some("secret-password".to_string());
+                    axum::Json(cfg)
+                }),
+            )
+            .layer(axum::middleware::from_fn(|request: Request, next: Next| async move {
+                let operator = AdminIdentity {
+                    username: "bob".to_string(),
+                    role: AdminRole::Operator,
+                    session_id: Some("s1".to_string()),
+                };
+                authorize_identity(operator, &Method::GET, "/config", request, next).await
+            }));
+
+        let resp = app
+            .oneshot(Request::builder().uri("/config").body(axum::body::Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8_lossy(&bytes);
+        assert!(!body.contains("sk-secret-key"));
+        assert!(!body.contains("secret-password"));
+        assert!(!body.contains("api_key"));
+        assert!(!body.contains("admin_password"));
+    }
+
+    #[test]
+    fn test_detect_protocol() {
+        assert_eq!(detect_protocol("/v1/messages"), ApiProtocol::Anthropic);
+        assert_eq!(detect_protocol("/v1/messages/count_tokens"), ApiProtocol::Anthropic);
+        assert_eq!(detect_protocol("/v1beta/models/gemini-3-flash:generateContent"), ApiProtocol::Gemini);
+        assert_eq!(detect_protocol("/v1/chat/completions"), ApiProtocol::OpenAI);
+    }
+
+    #[tokio::test]
+    async fn test_quota_error_response_shapes() {
+        use crate::modules::user_token_db::QuotaViolation;
+
+        let violation = QuotaViolation::RateLimited { limit: 10, retry_after_secs: 30 };
+
+        let resp = quota_error_response(ApiProtocol::Anthropic, &violation);
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(resp.headers().get(header::RETRY_AFTER).unwrap(), "30");
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["type"], "error");
+        assert_eq!(body["error"]["type"], "rate_limit_error");
+
+        let resp = quota_error_response(ApiProtocol::Gemini, &violation);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["status"], "RESOURCE_EXHAUSTED");
+
+        let denied = QuotaViolation::ModelNotAllowed { model: "gpt-4o".to_string() };
+        let resp = quota_error_response(ApiProtocol::OpenAI, &denied);
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["code"], "model_not_allowed");
+
+        let budget = QuotaViolation::BudgetExceeded {
+            owner: None,
+            period: crate::modules::cost::BudgetPeriod::Day,
+            used_usd: 12.5,
+            limit_usd: 10.0,
+            retry_after_secs: 600,
+        };
+        let resp = quota_error_response(ApiProtocol::OpenAI, &budget);
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(resp.headers().get(header::RETRY_AFTER).unwrap(), "600");
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["code"], "insufficient_quota");
+        assert!(body["error"]["message"].as_str().unwrap().starts_with("The global daily budget is exceeded ($12.50/$10.00)"));
+    }
+
+    #[tokio::test]
+    async fn test_extract_request_model_rejects_unreadable_body() {
+        // 超出上限的读取错误识别为 413
+        let err = axum::body::to_bytes(axum::body::Body::from(vec![0u8; 16]), 8).await.unwrap_err();
+        assert!(is_length_limit_error(&err));
+
+        // body 中途出错时不能以空 body 放行 (否则绕过模型白名单)
+        let stream = futures::stream::iter(vec![
+            Ok(bytes::Bytes::from_static(b"{\"model\":")),
+            Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "client went away")),
+        ]);
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/v1/messages")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(axum::body::Body::from_stream(stream))
+            .unwrap();
+        let resp = extract_request_model(request).await.unwrap_err();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["type"], "error");
+        assert_eq!(body["error"]["type"], "invalid_request_error");
+
+        let resp = body_error_response(ApiProtocol::OpenAI, StatusCode::PAYLOAD_TOO_LARGE, "too large");
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["error"]["code"], "request_too_large");
+    }
 }
@@ -1,15 +1,19 @@
 // Middleware 模块 - Axum 中间件
 
+pub mod audit;
 pub mod auth;
 pub mod cors;
 pub mod logging;
 pub mod monitor;
 pub mod ip_filter;
+pub mod response_cache;
 
 pub mod service_status;
 
 pub use cors::cors_layer;
 pub use monitor::monitor_middleware;
 pub use service_status::service_status_middleware;
+pub use audit::admin_audit_middleware;
 pub use auth::{auth_middleware, admin_auth_middleware};
 pub use ip_filter::ip_filter_middleware;
+pub use response_cache::response_cache_middleware;
@@ -14,12 +14,42 @@ use futures::StreamExt;
 const MAX_REQUEST_LOG_SIZE: usize = 100 * 1024 * 1024; // 100MB
 const MAX_RESPONSE_LOG_SIZE: usize = 100 * 1024 * 1024; // 100MB for image responses
 
+/// 从 usage / usageMetadata 对象提取 Token 用量
+///
+/// 只覆盖对象中出现的字段：Anthropic 流式的 message_delta 只携带输出 Token，不能清空已解析的输入。
+fn apply_usage(log: &mut ProxyRequestLog, usage: &Value) {
+    let report = crate::modules::cost::UsageReport::parse(usage);
+    if report.input_tokens.is_some() {
+        log.input_tokens = report.input_tokens;
+    }
+    if report.output_tokens.is_some() {
+        log.output_tokens = report.output_tokens;
+    }
+    if report.cached_tokens.is_some() {
+        log.cached_tokens = report.cached_tokens;
+    }
+    if report.thinking_tokens.is_some() {
+        log.thinking_tokens = report.thinking_tokens;
+    }
+
+    if log.input_tokens.is_none() && log.output_tokens.is_none() {
+        log.output_tokens = usage.get("total_tokens")
+            .or(usage.get("totalTokenCount"))
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32);
+    }
+}
+
 /// Helper function to record User Token usage
+///
+/// 先按价格表计算本次请求的费用，用户令牌的预算与 token_stats 都使用这个结果。
 fn record_user_token_usage(
     user_token_identity: &Option<UserTokenIdentity>,
-    log: &ProxyRequestLog,
+    log: &mut ProxyRequestLog,
     user_agent: Option<String>,
 ) {
+    log.cost_usd = crate::modules::cost::cost_for_log(log);
+
     if let Some(identity) = user_token_identity {
         let _ = crate::modules::user_token_db::record_token_usage_and_ip(
             &identity.token_id,
@@ -27,6 +57,7 @@ fn record_user_token_usage(
             log.model.as_deref().unwrap_or("unknown"),
             log.input_tokens.unwrap_or(0) as i32,
             log.output_tokens.unwrap_or(0) as i32,
+            log.cost_usd,
             log.status as u16,
             user_agent,
         );
@@ -43,7 +74,13 @@ pub async fn monitor_middleware(
     let method = request.method().to_string();
     let uri = request.uri().to_string();
     
-    if uri.contains("event_logging") || uri.contains("/api/") || uri.starts_with("/internal/") {
+    if uri.contains("event_logging")
+        || uri.contains("/api/")
+        || uri.starts_with("/internal/")
+        || uri == "/metrics"
+        || crate::proxy::batch_worker::is_batch_management_path(&uri)
+        || crate::proxy::handlers::files::is_files_path(&uri)
+    {
         return next.run(request).await;
     }
     
@@ -112,10 +149,15 @@ pub async fn monitor_middleware(
         request
     };
     
+    // [NEW] 交互请求在途计数，批处理 worker 据此让路 (流式响应持有到流结束)
+    let interactive = crate::proxy::batch_worker::InteractiveGuard::enter(&request);
+
     let response = next.run(request).await;
     
     // user_token_identity 已在上面从请求 extensions 中提取
     
+    // 在读取响应 body 之前取耗时：流式响应为首包 (响应头) 耗时，不含流传输时间
+    // antigravity_request_duration_seconds 直接使用该值
     let duration = start.elapsed().as_millis() as u64;
     let status = response.status().as_u16();
     
@@ -138,6 +180,13 @@ pub async fn monitor_middleware(
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string());
 
+    // [NEW] 响应缓存命中标记 (由 response_cache 中间件写入)
+    let cache_hit = response
+        .headers()
+        .get(crate::proxy::middleware::response_cache::CACHE_STATUS_HEADER)
+        .and_then(|v| v.to_str().ok())
+        == Some("HIT");
+
     // Determine protocol from URL path
     let protocol = if uri.contains("/v1/messages") {
         Some("anthropic".to_string())
@@ -173,6 +222,10 @@ pub async fn monitor_middleware(
         output_tokens: None,
         protocol,
         username,
+        cache_hit,
+        cached_tokens: None,
+        thinking_tokens: None,
+        cost_usd: None,
     };
 
 
@@ -182,6 +235,7 @@ pub async fn monitor_middleware(
         let (tx, rx) = tokio::sync::mpsc::channel(64);
         
         tokio::spawn(async move {
+            let _interactive = interactive;
             let mut all_stream_data = Vec::new();
             let mut last_few_bytes = Vec::new();
             
@@ -305,6 +359,12 @@ pub async fn monitor_middleware(
                                     }
                                 }
                             }
+                            Some("message_start") => {
+                                // Anthropic 的输入 Token (含缓存读取) 只出现在 message_start
+                                if let Some(usage) = json.get("message").and_then(|m| m.get("usage")) {
+                                    apply_usage(&mut log, usage);
+                                }
+                            }
                             Some("message_delta") => {
                                 if let Some(delta) = json.get("delta") {
                                     if let Some(usage) = delta.get("usage") {
@@ -340,23 +400,7 @@ pub async fn monitor_middleware(
                             .or(json.get("usageMetadata"))
                             .or(json.get("response").and_then(|r| r.get("usage")))
                         {
-                            log.input_tokens = usage.get("prompt_tokens")
-                                .or(usage.get("input_tokens"))
-                                .or(usage.get("promptTokenCount"))
-                                .and_then(|v| v.as_u64())
-                                .map(|v| v as u32);
-                            log.output_tokens = usage.get("completion_tokens")
-                                .or(usage.get("output_tokens"))
-                                .or(usage.get("candidatesTokenCount"))
-                                .and_then(|v| v.as_u64())
-                                .map(|v| v as u32);
-                            
-                            if log.input_tokens.is_none() && log.output_tokens.is_none() {
-                                log.output_tokens = usage.get("total_tokens")
-                                    .or(usage.get("totalTokenCount"))
-                                    .and_then(|v| v.as_u64())
-                                    .map(|v| v as u32);
-                            }
+                            apply_usage(&mut log, usage);
                         }
                     }
                 }
@@ -408,16 +452,7 @@ pub async fn monitor_middleware(
                                     .or(json.get("usageMetadata"))
                                     .or(json.get("response").and_then(|r| r.get("usage")))
                                 {
-                                    log.input_tokens = usage.get("prompt_tokens")
-                                        .or(usage.get("input_tokens"))
-                                        .or(usage.get("promptTokenCount"))
-                                        .and_then(|v| v.as_u64())
-                                        .map(|v| v as u32);
-                                    log.output_tokens = usage.get("completion_tokens")
-                                        .or(usage.get("output_tokens"))
-                                        .or(usage.get("candidatesTokenCount"))
-                                        .and_then(|v| v.as_u64())
-                                        .map(|v| v as u32);
+                                    apply_usage(&mut log, usage);
                                     break;
                                 }
                             }
@@ -431,7 +466,7 @@ pub async fn monitor_middleware(
             }
 
             // Record User Token Usage
-            record_user_token_usage(&user_token_identity, &log, user_agent.clone());
+            record_user_token_usage(&user_token_identity, &mut log, user_agent.clone());
 
             monitor.log_request(log).await;
         });
@@ -445,23 +480,7 @@ pub async fn monitor_middleware(
                     if let Ok(json) = serde_json::from_str::<Value>(&s) {
                         // 支持 OpenAI "usage" 或 Gemini "usageMetadata"
                         if let Some(usage) = json.get("usage").or(json.get("usageMetadata")) {
-                            log.input_tokens = usage.get("prompt_tokens")
-                                .or(usage.get("input_tokens"))
-                                .or(usage.get("promptTokenCount"))
-                                .and_then(|v| v.as_u64())
-                                .map(|v| v as u32);
-                            log.output_tokens = usage.get("completion_tokens")
-                                .or(usage.get("output_tokens"))
-                                .or(usage.get("candidatesTokenCount"))
-                                .and_then(|v| v.as_u64())
-                                .map(|v| v as u32);
-                                
-                            if log.input_tokens.is_none() && log.output_tokens.is_none() {
-                                log.output_tokens = usage.get("total_tokens")
-                                    .or(usage.get("totalTokenCount"))
-                                    .and_then(|v| v.as_u64())
-                                    .map(|v| v as u32);
-                            }
+                            apply_usage(&mut log, usage);
                         }
                     }
                     log.response_body = Some(s.to_string());
@@ -474,7 +493,7 @@ pub async fn monitor_middleware(
                 }
 
                 // Record User Token Usage
-                record_user_token_usage(&user_token_identity, &log, user_agent.clone());
+                record_user_token_usage(&user_token_identity, &mut log, user_agent.clone());
 
                 monitor.log_request(log).await;
                 Response::from_parts(parts, Body::from(bytes))
@@ -483,7 +502,7 @@ pub async fn monitor_middleware(
                 log.response_body = Some("[Response too large (>100MB)]".to_string());
 
                 // Record User Token Usage (even if too large)
-                record_user_token_usage(&user_token_identity, &log, user_agent.clone());
+                record_user_token_usage(&user_token_identity, &mut log, user_agent.clone());
 
                 monitor.log_request(log).await;
                 Response::from_parts(parts, Body::empty())
@@ -493,7 +512,7 @@ pub async fn monitor_middleware(
         log.response_body = Some(format!("[{}]", content_type));
 
         // Record User Token Usage
-        record_user_token_usage(&user_token_identity, &log, user_agent);
+        record_user_token_usage(&user_token_identity, &mut log, user_agent);
 
         monitor.log_request(log).await;
         response
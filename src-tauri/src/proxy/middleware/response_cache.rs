@@ -0,0 +1,630 @@
+// 响应缓存中间件
+// 对确定性请求 (temperature = 0) 按 "路由后模型 + 规范化请求体" 指纹缓存完整响应，
+// 命中时直接回放 (流式响应按原协议逐事件回放 SSE)，不再消耗账号配额。
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use bytes::Bytes;
+use futures::StreamExt;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::modules::response_cache_db::{self, CachedResponse};
+use crate::proxy::common::routing_rules::RouteContext;
+use crate::proxy::middleware::auth::UserTokenIdentity;
+use crate::proxy::server::AppState;
+
+/// 响应头: HIT / MISS / BYPASS (monitor 中间件据此标记 cache_hit)
+pub const CACHE_STATUS_HEADER: &str = "X-Cache-Status";
+
+const MAX_REQUEST_SIZE: usize = 100 * 1024 * 1024; // 与 monitor 中间件一致
+/// 单条缓存响应的大小上限
+const MAX_ENTRY_SIZE: usize = 32 * 1024 * 1024;
+/// 指纹版本，规范化规则变化时递增以废弃旧条目
+const KEY_VERSION: &str = "v1";
+/// 不影响输出内容的顶层字段 (model 由路由后的模型名代替，stream 单独参与指纹)
+const IGNORED_FIELDS: [&str; 4] = ["model", "stream", "user", "metadata"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheProtocol {
+    OpenAI,
+    Anthropic,
+    Gemini,
+}
+
+impl CacheProtocol {
+    /// 与 ProxyRequestLog.protocol 取值一致
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheProtocol::OpenAI => "openai",
+            CacheProtocol::Anthropic => "anthropic",
+            CacheProtocol::Gemini => "gemini",
+        }
+    }
+}
+
+/// 可缓存请求的基本信息
+#[derive(Debug, Clone, PartialEq)]
+struct CacheableRequest {
+    protocol: CacheProtocol,
+    /// 协议内的端点 (chat.completions / generateContent ...)
+    endpoint: String,
+    /// 客户端请求的模型名
+    model: String,
+    stream: bool,
+}
+
+/// 识别可缓存的生成端点
+fn classify_request(path: &str, body: &Value) -> Option<CacheableRequest> {
+    let body_model = || body.get("model").and_then(|m| m.as_str()).map(|s| s.to_string());
+    let body_stream = body.get("stream").and_then(|s| s.as_bool()).unwrap_or(false);
+
+    match path {
+        "/v1/chat/completions" | "/v1/completions" => Some(CacheableRequest {
+            protocol: CacheProtocol::OpenAI,
+            endpoint: path.trim_start_matches("/v1/").to_string(),
+            model: body_model()?,
+            stream: body_stream,
+        }),
+        "/v1/messages" => Some(CacheableRequest {
+            protocol: CacheProtocol::Anthropic,
+            endpoint: "messages".to_string(),
+            model: body_model()?,
+            stream: body_stream,
+        }),
+        _ => {
+            let (model, method) = path.strip_prefix("/v1beta/models/")?.split_once(':')?;
+            if method != "generateContent" && method != "streamGenerateContent" {
+                return None;
+            }
+            Some(CacheableRequest {
+                protocol: CacheProtocol::Gemini,
+                endpoint: method.to_string(),
+                model: model.to_string(),
+                stream: method == "streamGenerateContent",
+            })
+        }
+    }
+}
+
+/// 是否显式要求确定性输出 (temperature = 0)
+fn is_deterministic(protocol: CacheProtocol, body: &Value) -> bool {
+    let temperature = match protocol {
+        CacheProtocol::Gemini => body.pointer("/generationConfig/temperature"),
+        CacheProtocol::OpenAI | CacheProtocol::Anthropic => body.get("temperature"),
+    };
+    temperature.and_then(|t| t.as_f64()) == Some(0.0)
+}
+
+/// 递归排序对象键并去掉 null 值，使等价请求得到相同的序列化结果
+fn canonicalize(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let mut out = serde_json::Map::with_capacity(keys.len());
+            for key in keys {
+                let v = &map[key];
+                if !v.is_null() {
+                    out.insert(key.clone(), canonicalize(v));
+                }
+            }
+            Value::Object(out)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonicalize).collect()),
+        other => other.clone(),
+    }
+}
+
+/// 计算缓存指纹: 协议、端点、路由后模型、是否流式、影响输出的请求头与规范化后的请求体
+fn cache_key(
+    request: &CacheableRequest,
+    mapped_model: &str,
+    upstream: &str,
+    anthropic_beta: Option<&str>,
+    body: &Value,
+) -> String {
+    let mut normalized = canonicalize(body);
+    if let Some(obj) = normalized.as_object_mut() {
+        for field in IGNORED_FIELDS {
+            obj.remove(field);
+        }
+    }
+
+    let mut hasher = Sha256::new();
+    for part in [
+        KEY_VERSION,
+        request.protocol.as_str(),
+        &request.endpoint,
+        mapped_model,
+        upstream,
+        if request.stream { "stream" } else { "json" },
+        anthropic_beta.unwrap_or(""),
+    ] {
+        hasher.update(part.as_bytes());
+        hasher.update([0u8]);
+    }
+    hasher.update(normalized.to_string().as_bytes());
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 客户端的 Cache-Control 指令
+#[derive(Debug, Default, Clone, Copy)]
+struct CacheDirectives {
+    /// 跳过查找 (仍写入新结果)
+    no_cache: bool,
+    /// 不写入
+    no_store: bool,
+}
+
+impl CacheDirectives {
+    fn from_headers(headers: &HeaderMap) -> Self {
+        let value = headers
+            .get(header::CACHE_CONTROL)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let has = |d: &str| value.split(',').any(|p| p.trim() == d);
+        Self {
+            no_cache: has("no-cache"),
+            no_store: has("no-store"),
+        }
+    }
+}
+
+/// 将 SSE 字节流切分为完整事件 (含结尾空行)，用于逐事件回放
+fn sse_event_chunks(body: &[u8]) -> Vec<Bytes> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i + 1 < body.len() {
+        if body[i] == b'\n' && body[i + 1] == b'\n' {
+            chunks.push(Bytes::copy_from_slice(&body[start..i + 2]));
+            start = i + 2;
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+    if start < body.len() {
+        chunks.push(Bytes::copy_from_slice(&body[start..]));
+    }
+    chunks
+}
+
+/// 流式响应是否完整结束且不含错误事件
+fn stream_completed(protocol: CacheProtocol, body: &[u8]) -> bool {
+    let Ok(text) = std::str::from_utf8(body) else {
+        return false;
+    };
+    let text = text.replace("\r\n", "\n");
+
+    let mut done = false;
+    for event in text.split("\n\n") {
+        let mut event_name = None;
+        let mut data = String::new();
+        for line in event.lines() {
+            if let Some(name) = line.strip_prefix("event:") {
+                event_name = Some(name.trim());
+            } else if let Some(d) = line.strip_prefix("data:") {
+                data.push_str(d.trim());
+            }
+        }
+        if event_name == Some("error") {
+            return false;
+        }
+        if data.is_empty() {
+            continue;
+        }
+        if data == "[DONE]" {
+            done |= protocol == CacheProtocol::OpenAI;
+            continue;
+        }
+        let Ok(json) = serde_json::from_str::<Value>(&data) else {
+            continue;
+        };
+        if json.get("error").is_some() || json.get("type").and_then(|t| t.as_str()) == Some("error") {
+            return false;
+        }
+        match protocol {
+            CacheProtocol::Anthropic => {
+                done |= json.get("type").and_then(|t| t.as_str()) == Some("message_stop");
+            }
+            CacheProtocol::Gemini => {
+                let candidates = json
+                    .get("candidates")
+                    .or_else(|| json.pointer("/response/candidates"))
+                    .and_then(|c| c.as_array());
+                done |= candidates.map_or(false, |c| {
+                    c.iter().any(|cand| cand.get("finishReason").is_some())
+                });
+            }
+            CacheProtocol::OpenAI => {}
+        }
+    }
+    done
+}
+
+/// 响应是否适合写入缓存
+fn response_is_cacheable(protocol: CacheProtocol, content_type: &str, body: &[u8]) -> bool {
+    if body.is_empty() {
+        return false;
+    }
+    if content_type.contains("text/event-stream") {
+        return stream_completed(protocol, body);
+    }
+    match serde_json::from_slice::<Value>(body) {
+        Ok(json) => json.get("error").is_none(),
+        Err(_) => false,
+    }
+}
+
+/// 构造命中后的回放响应
+fn replay_response(entry: CachedResponse) -> Response {
+    let is_stream = entry.content_type.contains("text/event-stream");
+    let age = (chrono::Utc::now().timestamp() - entry.created_at).max(0);
+
+    let mut builder = Response::builder()
+        .status(entry.status)
+        .header(header::CONTENT_TYPE, entry.content_type.as_str())
+        .header(header::AGE, age.to_string())
+        .header(CACHE_STATUS_HEADER, "HIT");
+    if let Ok(mapped) = HeaderValue::from_str(&entry.mapped_model) {
+        builder = builder.header("X-Mapped-Model", mapped);
+    }
+
+    let body = if is_stream {
+        builder = builder.header(header::CACHE_CONTROL, "no-cache");
+        let events = sse_event_chunks(&entry.body)
+            .into_iter()
+            .map(Ok::<_, std::io::Error>);
+        Body::from_stream(futures::stream::iter(events))
+    } else {
+        Body::from(entry.body)
+    };
+
+    builder
+        .body(body)
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// 未命中时待写入的条目信息
+struct PendingEntry {
+    key: String,
+    protocol: CacheProtocol,
+    mapped_model: String,
+    ttl_secs: i64,
+    max_entries: u64,
+    max_bytes: u64,
+}
+
+impl PendingEntry {
+    fn entry_limit(&self) -> usize {
+        (self.max_bytes as usize).min(MAX_ENTRY_SIZE)
+    }
+
+    async fn store(self, status: u16, content_type: String, body: Vec<u8>) {
+        if !response_is_cacheable(self.protocol, &content_type, &body) {
+            tracing::debug!("[Response-Cache] Skip storing incomplete or error response");
+            return;
+        }
+        let entry = CachedResponse {
+            key: self.key,
+            protocol: self.protocol.as_str().to_string(),
+            mapped_model: self.mapped_model,
+            status,
+            content_type,
+            body,
+            created_at: chrono::Utc::now().timestamp(),
+        };
+        let (ttl, max_entries, max_bytes) = (self.ttl_secs, self.max_entries, self.max_bytes);
+        let res = tokio::task::spawn_blocking(move || {
+            response_cache_db::store(&entry, ttl, max_entries, max_bytes)
+        })
+        .await;
+        match res {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::warn!("[Response-Cache] Failed to store response: {}", e),
+            Err(e) => tracing::warn!("[Response-Cache] Store task failed: {}", e),
+        }
+    }
+}
+
+fn with_cache_status(mut response: Response, status: &'static str) -> Response {
+    response
+        .headers_mut()
+        .insert(CACHE_STATUS_HEADER, HeaderValue::from_static(status));
+    response
+}
+
+/// 转发响应的同时收集完整响应体，结束后按需写入缓存
+async fn capture_response(response: Response, pending: PendingEntry) -> Response {
+    let status = response.status().as_u16();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    let response = with_cache_status(response, "MISS");
+    let limit = pending.entry_limit();
+
+    if content_type.contains("text/event-stream") {
+        let (parts, body) = response.into_parts();
+        let mut stream = body.into_data_stream();
+        let (tx, rx) = tokio::sync::mpsc::channel(64);
+
+        tokio::spawn(async move {
+            let mut captured = Vec::new();
+            let mut oversized = false;
+            let mut failed = false;
+            while let Some(chunk_res) = stream.next().await {
+                match chunk_res {
+                    Ok(chunk) => {
+                        if !oversized {
+                            if captured.len() + chunk.len() > limit {
+                                oversized = true;
+                                captured = Vec::new();
+                            } else {
+                                captured.extend_from_slice(&chunk);
+                            }
+                        }
+                        let _ = tx.send(Ok::<_, axum::Error>(chunk)).await;
+                    }
+                    Err(e) => {
+                        failed = true;
+                        let _ = tx.send(Err(e)).await;
+                    }
+                }
+            }
+            if !failed && !oversized {
+                pending.store(status, content_type, captured).await;
+            }
+        });
+
+        Response::from_parts(
+            parts,
+            Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(rx)),
+        )
+    } else if content_type.contains("application/json") {
+        let (parts, body) = response.into_parts();
+        match axum::body::to_bytes(body, MAX_REQUEST_SIZE).await {
+            Ok(bytes) => {
+                if bytes.len() <= limit {
+                    tokio::spawn(pending.store(status, content_type, bytes.to_vec()));
+                }
+                Response::from_parts(parts, Body::from(bytes))
+            }
+            Err(_) => Response::from_parts(parts, Body::empty()),
+        }
+    } else {
+        response
+    }
+}
+
+pub async fn response_cache_middleware(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let config = crate::proxy::config::get_response_cache_config();
+    if !config.enabled || request.method() != Method::POST {
+        return next.run(request).await;
+    }
+
+    let path = request.uri().path().to_string();
+    let is_generation_path = matches!(
+        path.as_str(),
+        "/v1/chat/completions" | "/v1/completions" | "/v1/messages"
+    ) || path.starts_with("/v1beta/models/");
+    if !is_generation_path {
+        return next.run(request).await;
+    }
+
+    let directives = CacheDirectives::from_headers(request.headers());
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, MAX_REQUEST_SIZE).await {
+        Ok(bytes) => bytes,
+        Err(_) => return StatusCode::PAYLOAD_TOO_LARGE.into_response(),
+    };
+
+    let parsed = serde_json::from_slice::<Value>(&bytes)
+        .ok()
+        .and_then(|body| classify_request(&path, &body).map(|req| (req, body)));
+    let Some((cacheable, body_json)) = parsed else {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    };
+    if config.require_zero_temperature && !is_deterministic(cacheable.protocol, &body_json) {
+        return next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    }
+
+    // 与 handler 使用相同的路由上下文，保证指纹中的模型即实际调用的模型
+    let route_ctx = RouteContext::from_request(
+        &cacheable.model,
+        cacheable.protocol.as_str(),
+        &body_json,
+        &parts.headers,
+        parts.extensions.get::<UserTokenIdentity>(),
+    )
+    .with_zai_enabled(state.zai.read().await.enabled);
+    let decision = state.model_router.read().await.explain(&route_ctx).decision;
+    let anthropic_beta = parts
+        .headers
+        .get("anthropic-beta")
+        .and_then(|v| v.to_str().ok());
+    let upstream = crate::proxy::providers::registry::upstream_label(
+        cacheable.protocol.as_str(),
+        &cacheable.model,
+        &decision,
+    );
+    let key = cache_key(
+        &cacheable,
+        &decision.mapped_model,
+        &upstream,
+        anthropic_beta,
+        &body_json,
+    );
+
+    if !directives.no_cache {
+        let lookup_key = key.clone();
+        match tokio::task::spawn_blocking(move || response_cache_db::lookup(&lookup_key)).await {
+            Ok(Ok(Some(entry))) => {
+                tracing::info!(
+                    "[Response-Cache] HIT {} {} ({})",
+                    cacheable.protocol.as_str(),
+                    decision.mapped_model,
+                    &key[..12]
+                );
+                return replay_response(entry);
+            }
+            Ok(Ok(None)) => {}
+            Ok(Err(e)) => tracing::warn!("[Response-Cache] Lookup failed: {}", e),
+            Err(e) => tracing::warn!("[Response-Cache] Lookup task failed: {}", e),
+        }
+    }
+
+    let response = next.run(Request::from_parts(parts, Body::from(bytes))).await;
+    if directives.no_store {
+        return with_cache_status(response, "BYPASS");
+    }
+    if response.status() != StatusCode::OK {
+        return with_cache_status(response, "MISS");
+    }
+
+    let mapped_model = response
+        .headers()
+        .get("X-Mapped-Model")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+        .unwrap_or(decision.mapped_model);
+    let pending = PendingEntry {
+        key,
+        protocol: cacheable.protocol,
+        mapped_model,
+        ttl_secs: config.ttl_secs as i64,
+        max_entries: config.max_entries,
+        max_bytes: config.max_size_mb.saturating_mul(1024 * 1024),
+    };
+    capture_response(response, pending).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn key_for(path: &str, body: &Value, mapped: &str) -> String {
+        let req = classify_request(path, body).unwrap();
+        cache_key(&req, mapped, "google", None, body)
+    }
+
+    #[test]
+    fn test_classify_request() {
+        let openai = classify_request(
+            "/v1/chat/completions",
+            &json!({"model": "gpt-4o", "stream": true, "messages": []}),
+        )
+        .unwrap();
+        assert_eq!(openai.protocol, CacheProtocol::OpenAI);
+        assert_eq!(openai.endpoint, "chat/completions");
+        assert!(openai.stream);
+
+        let gemini = classify_request(
+            "/v1beta/models/gemini-2.5-flash:streamGenerateContent",
+            &json!({"contents": []}),
+        )
+        .unwrap();
+        assert_eq!(gemini.protocol, CacheProtocol::Gemini);
+        assert_eq!(gemini.model, "gemini-2.5-flash");
+        assert!(gemini.stream);
+
+        assert!(classify_request("/v1beta/models/gemini-2.5-flash:countTokens", &json!({})).is_none());
+        assert!(classify_request("/v1/messages", &json!({"messages": []})).is_none());
+        assert!(classify_request("/v1/embeddings", &json!({"model": "x"})).is_none());
+    }
+
+    #[test]
+    fn test_is_deterministic() {
+        assert!(is_deterministic(CacheProtocol::OpenAI, &json!({"temperature": 0})));
+        assert!(is_deterministic(CacheProtocol::Anthropic, &json!({"temperature": 0.0})));
+        assert!(!is_deterministic(CacheProtocol::Anthropic, &json!({"temperature": 0.2})));
+        assert!(!is_deterministic(CacheProtocol::OpenAI, &json!({})));
+        assert!(is_deterministic(
+            CacheProtocol::Gemini,
+            &json!({"generationConfig": {"temperature": 0}})
+        ));
+        assert!(!is_deterministic(CacheProtocol::Gemini, &json!({"temperature": 0})));
+    }
+
+    #[test]
+    fn test_cache_key_normalization() {
+        let a = json!({
+            "model": "claude-sonnet-4-5",
+            "temperature": 0,
+            "max_tokens": 100,
+            "metadata": {"user_id": "session-a"},
+            "messages": [{"role": "user", "content": "hi"}],
+            "tools": null
+        });
+        let b = json!({
+            "messages": [{"content": "hi", "role": "user"}],
+            "max_tokens": 100,
+            "temperature": 0,
+            "metadata": {"user_id": "session-b"},
+            "model": "claude-sonnet-4-5-alias"
+        });
+        // 键顺序、null、metadata 与客户端模型名不影响指纹
+        assert_eq!(key_for("/v1/messages", &a, "m"), key_for("/v1/messages", &b, "m"));
+        // 路由后的模型不同则指纹不同
+        assert_ne!(key_for("/v1/messages", &a, "m"), key_for("/v1/messages", &a, "n"));
+
+        // 生成参数与流式标记参与指纹
+        let mut c = a.clone();
+        c["max_tokens"] = json!(200);
+        assert_ne!(key_for("/v1/messages", &a, "m"), key_for("/v1/messages", &c, "m"));
+        let mut d = a.clone();
+        d["stream"] = json!(true);
+        assert_ne!(key_for("/v1/messages", &a, "m"), key_for("/v1/messages", &d, "m"));
+    }
+
+    #[test]
+    fn test_stream_completion_per_protocol() {
+        let openai = b"data: {\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n\ndata: [DONE]\n\n";
+        assert!(stream_completed(CacheProtocol::OpenAI, openai));
+        assert!(!stream_completed(CacheProtocol::OpenAI, b"data: {\"choices\":[]}\n\n"));
+        assert!(!stream_completed(
+            CacheProtocol::OpenAI,
+            b"data: {\"error\":{\"message\":\"boom\"}}\n\ndata: [DONE]\n\n"
+        ));
+
+        let claude = b"event: message_start\ndata: {\"type\":\"message_start\"}\n\nevent: message_stop\ndata: {\"type\":\"message_stop\"}\n\n";
+        assert!(stream_completed(CacheProtocol::Anthropic, claude));
+        let claude_err = b"event: message_start\ndata: {\"type\":\"message_start\"}\n\nevent: error\ndata: {\"type\":\"error\"}\n\n";
+        assert!(!stream_completed(CacheProtocol::Anthropic, claude_err));
+
+        let gemini = b"data: {\"candidates\":[{\"content\":{}}]}\r\n\r\ndata: {\"candidates\":[{\"finishReason\":\"STOP\"}]}\n\n";
+        assert!(stream_completed(CacheProtocol::Gemini, gemini));
+        assert!(!stream_completed(CacheProtocol::Gemini, b"data: {\"candidates\":[{\"content\":{}}]}\n\n"));
+    }
+
+    #[test]
+    fn test_sse_event_chunks_split_on_event_boundaries() {
+        let body = b"event: a\ndata: 1\n\ndata: 2\n\ntrailing";
+        let chunks = sse_event_chunks(body);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(&chunks[0][..], b"event: a\ndata: 1\n\n");
+        assert_eq!(&chunks[1][..], b"data: 2\n\n");
+        assert_eq!(&chunks[2][..], b"trailing");
+    }
+
+    #[test]
+    fn test_response_is_cacheable() {
+        assert!(response_is_cacheable(CacheProtocol::OpenAI, "application/json", b"{\"choices\":[]}"));
+        assert!(!response_is_cacheable(CacheProtocol::OpenAI, "application/json", b"{\"error\":{}}"));
+        assert!(!response_is_cacheable(CacheProtocol::OpenAI, "application/json", b""));
+    }
+}
@@ -9,12 +9,14 @@ pub mod token_manager;
 
 // 新架构模块
 pub mod audio; // 音频处理模块
+pub mod batch_worker; // 批处理队列 worker
 pub mod cli_sync; // CLI 配置同步 (v3.3.35)
 pub mod droid_sync; // Droid (Factory CLI) 配置同步
 pub mod common; // 公共工具
 pub mod debug_logger;
 pub mod handlers; // API 端点处理器
 pub mod mappers; // 协议转换器
+pub mod metrics; // Prometheus 指标导出
 pub mod middleware; // Axum 中间件
 pub mod monitor; // 监控
 pub mod opencode_sync; // OpenCode 配置同步
@@ -31,6 +33,12 @@ pub mod zai_vision_tools; // Built-in Vision MCP tools (z.ai vision API) // 调
 pub use config::update_global_system_prompt_config;
 pub use config::update_thinking_budget_config;
 pub use config::update_image_thinking_mode;
+pub use config::update_response_cache_config;
+pub use config::update_cost_config;
+pub use config::update_providers_config;
+pub use config::update_structured_output_config;
+pub use config::update_batch_config;
+pub use config::update_files_config;
 pub use config::ProxyAuthMode;
 pub use config::ProxyConfig;
 pub use config::ProxyPoolConfig;
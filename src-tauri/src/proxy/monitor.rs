@@ -23,6 +23,14 @@ pub struct ProxyRequestLog {
     pub output_tokens: Option<u32>,
     pub protocol: Option<String>,     // 协议类型: "openai", "anthropic", "gemini"
     pub username: Option<String>,     // User token username
+    #[serde(default)]
+    pub cache_hit: bool,              // 是否由响应缓存直接返回
+    #[serde(default)]
+    pub cached_tokens: Option<u32>,   // 命中上下文缓存的输入 Token (包含在 input_tokens 中)
+    #[serde(default)]
+    pub thinking_tokens: Option<u32>, // 思考 / 推理 Token (包含在 output_tokens 中)
+    #[serde(default)]
+    pub cost_usd: Option<f64>,        // 按价格表计算的费用，未配置价格时为空
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -78,21 +86,47 @@ impl ProxyMonitor {
         self.enabled.load(Ordering::Relaxed)
     }
 
-    pub async fn log_request(&self, log: ProxyRequestLog) {
-        if let (Some(account), Some(input), Some(output)) = (
+    pub async fn log_request(&self, mut log: ProxyRequestLog) {
+        // 未经 monitor 中间件计价的日志 (如预热请求) 在这里补算费用
+        if log.cost_usd.is_none() {
+            log.cost_usd = crate::modules::cost::cost_for_log(&log);
+        }
+
+        // Prometheus 指标不受监控开关影响
+        crate::proxy::metrics::ProxyMetrics::global().observe_request(&log);
+
+        if let (Some(account), Some(usage)) = (
             &log.account_email,
-            log.input_tokens,
-            log.output_tokens,
+            crate::modules::cost::TokenUsage::from_log(&log),
         ) {
             let model = log.model.clone().unwrap_or_else(|| "unknown".to_string());
             let account = account.clone();
+            let username = log.username.clone();
+            let cost = log.cost_usd.unwrap_or(0.0);
             tokio::spawn(async move {
-                if let Err(e) = crate::modules::token_stats::record_usage(&account, &model, input, output) {
+                if let Err(e) = crate::modules::token_stats::record_usage(
+                    &account,
+                    &model,
+                    username.as_deref(),
+                    &usage,
+                    cost,
+                ) {
                     tracing::debug!("Failed to record token stats: {}", e);
                 }
             });
         }
 
+        // [NEW] 缓存命中不消耗账号配额，单独计入 token_stats 的缓存命中统计
+        if log.cache_hit {
+            let model = log.model.clone().unwrap_or_else(|| "unknown".to_string());
+            let (input, output) = (log.input_tokens.unwrap_or(0), log.output_tokens.unwrap_or(0));
+            tokio::spawn(async move {
+                if let Err(e) = crate::modules::token_stats::record_cache_hit(&model, input, output) {
+                    tracing::debug!("Failed to record cache hit stats: {}", e);
+                }
+            });
+        }
+
         if !self.is_enabled() {
             return;
         }
@@ -146,17 +180,8 @@ impl ProxyMonitor {
                 }
             }
 
-            // Record token stats if available
-            if let (Some(account), Some(input), Some(output)) = (
-                &log_to_save.account_email,
-                log_to_save.input_tokens,
-                log_to_save.output_tokens,
-            ) {
-                let model = log_to_save.model.clone().unwrap_or_else(|| "unknown".to_string());
-                if let Err(e) = crate::modules::token_stats::record_usage(account, &model, input, output) {
-                    tracing::debug!("Failed to record token stats: {}", e);
-                }
-            }
+            // [FIX] token_stats 已在 log_request 开头记录 (不受监控开关影响)，
+            // 这里不再重复写入，否则开启监控时 Token 与费用会被计算两次
         });
 
         // Emit event (send summary only, without body to reduce memory)
@@ -179,6 +204,10 @@ impl ProxyMonitor {
                 output_tokens: log.output_tokens,
                 protocol: log.protocol.clone(),
                 username: log.username.clone(),
+                cache_hit: log.cache_hit,
+                cached_tokens: log.cached_tokens,
+                thinking_tokens: log.thinking_tokens,
+                cost_usd: log.cost_usd,
             };
             let _ = app.emit("proxy://request", &log_summary);
         }
@@ -0,0 +1,248 @@
+//! OpenAI / Anthropic 兼容上游的透传转发
+//!
+//! 请求体保持客户端协议原样 (仅改写模型名)，响应 (JSON / SSE) 原样流式返回。
+//! 通过 `X-Account-Email: provider:<id>` 与 `X-Mapped-Model` 响应头接入监控日志、Token 统计与费用核算。
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::StreamExt;
+use serde_json::{json, Value};
+
+use super::registry::{self, KeyChoice, ProviderSelection};
+use super::zai_anthropic::{build_client, copy_passthrough_headers};
+use crate::proxy::config::{ProviderProtocol, UpstreamProxyConfig};
+
+/// Anthropic API 要求的版本头 (客户端未携带时补齐)
+const DEFAULT_ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// 拼接上游地址；base_url 已以 `/v1` 结尾时不重复拼接
+pub fn endpoint_url(base_url: &str, endpoint: &str) -> String {
+    let base = base_url.trim_end_matches('/');
+    match endpoint.strip_prefix("/v1/") {
+        Some(rest) if base.ends_with("/v1") => format!("{}/{}", base, rest),
+        _ => format!("{}{}", base, endpoint),
+    }
+}
+
+/// 按客户端协议构造错误响应
+pub fn error_response(
+    protocol: ProviderProtocol,
+    status: StatusCode,
+    error_type: &str,
+    message: &str,
+) -> Response {
+    let body = match protocol {
+        ProviderProtocol::Openai => json!({
+            "error": { "message": message, "type": error_type, "code": status.as_u16() }
+        }),
+        ProviderProtocol::Anthropic => json!({
+            "type": "error",
+            "error": { "type": error_type, "message": message }
+        }),
+    };
+    (status, Json(body)).into_response()
+}
+
+fn set_auth(headers: &mut HeaderMap, protocol: ProviderProtocol, key: &str) {
+    match protocol {
+        ProviderProtocol::Openai => {
+            if let Ok(v) = HeaderValue::from_str(&format!("Bearer {}", key)) {
+                headers.insert(header::AUTHORIZATION, v);
+            }
+        }
+        ProviderProtocol::Anthropic => {
+            if let Ok(v) = HeaderValue::from_str(key) {
+                headers.insert("x-api-key", v);
+            }
+        }
+    }
+}
+
+fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+    headers
+        .get("Retry-After")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.trim().parse::<u64>().ok())
+}
+
+/// 转发到自定义上游。429 时换下一个未冷却的 Key 重试，所有 Key 均不可用时返回 429
+pub async fn forward(
+    selection: &ProviderSelection,
+    endpoint: &str,
+    incoming_headers: &HeaderMap,
+    mut body: Value,
+    upstream_proxy: UpstreamProxyConfig,
+    timeout_secs: u64,
+    trace_id: &str,
+) -> Response {
+    let provider = &selection.provider;
+    let protocol = provider.protocol;
+
+    if body.get("model").is_some() {
+        body["model"] = Value::String(selection.upstream_model.clone());
+    }
+    // OpenAI 流式响应默认不带 usage，显式请求以便统计 Token 与费用
+    if protocol == ProviderProtocol::Openai
+        && body.get("stream").and_then(|v| v.as_bool()).unwrap_or(false)
+        && body.get("stream_options").is_none()
+    {
+        body["stream_options"] = json!({ "include_usage": true });
+    }
+    let body_bytes = serde_json::to_vec(&body).unwrap_or_default();
+
+    let url = endpoint_url(&provider.base_url, endpoint);
+    let client = match build_client(Some(upstream_proxy), timeout_secs.max(5)) {
+        Ok(c) => c,
+        Err(e) => return error_response(protocol, StatusCode::INTERNAL_SERVER_ERROR, "api_error", &e),
+    };
+
+    let mut base_headers = copy_passthrough_headers(incoming_headers);
+    base_headers
+        .entry(header::CONTENT_TYPE)
+        .or_insert(HeaderValue::from_static("application/json"));
+    if protocol == ProviderProtocol::Anthropic {
+        if let Some(beta) = incoming_headers.get("anthropic-beta") {
+            base_headers.insert("anthropic-beta", beta.clone());
+        }
+        base_headers
+            .entry("anthropic-version")
+            .or_insert(HeaderValue::from_static(DEFAULT_ANTHROPIC_VERSION));
+    }
+
+    let key_count = provider.api_keys.iter().filter(|k| !k.trim().is_empty()).count();
+    let mut tried: Vec<String> = Vec::new();
+    loop {
+        let key = match registry::acquire_key(provider, &tried) {
+            KeyChoice::NoAuth => None,
+            KeyChoice::Key(key) => Some(key),
+            KeyChoice::AllCoolingDown(wait) => {
+                tracing::warn!(
+                    "[{}] Provider '{}' has no available key (retry in {}s)",
+                    trace_id,
+                    provider.id,
+                    wait
+                );
+                let mut resp = error_response(
+                    protocol,
+                    StatusCode::TOO_MANY_REQUESTS,
+                    "rate_limit_error",
+                    &format!("All API keys of provider '{}' are rate limited", provider.id),
+                );
+                if let Ok(v) = HeaderValue::from_str(&wait.to_string()) {
+                    resp.headers_mut().insert(header::RETRY_AFTER, v);
+                }
+                return with_log_headers(resp, selection);
+            }
+        };
+
+        let mut headers = base_headers.clone();
+        if let Some(key) = &key {
+            set_auth(&mut headers, protocol, key);
+        }
+
+        tracing::info!(
+            "[{}] Forwarding to provider '{}' ({}, {}): {} -> {}",
+            trace_id,
+            provider.id,
+            protocol.as_str(),
+            selection.reason,
+            selection.upstream_model,
+            url
+        );
+
+        let resp = match client
+            .request(Method::POST, &url)
+            .headers(headers)
+            .body(body_bytes.clone())
+            .send()
+            .await
+        {
+            Ok(r) => r,
+            Err(e) => {
+                let message = format!("Provider '{}' request failed: {}", provider.id, e);
+                registry::report_error(provider, &message);
+                tracing::error!("[{}] {}", trace_id, message);
+                return with_log_headers(
+                    error_response(protocol, StatusCode::BAD_GATEWAY, "api_error", &message),
+                    selection,
+                );
+            }
+        };
+
+        let status = resp.status().as_u16();
+        let retry_after = parse_retry_after(resp.headers());
+        registry::report_response(provider, key.as_deref(), status, retry_after);
+
+        // 限流时换 Key 重试 (仅配置了多个 Key 时)
+        if status == 429 {
+            if let Some(key) = key {
+                tried.push(key);
+                if tried.len() < key_count {
+                    continue;
+                }
+            }
+        }
+
+        let status = StatusCode::from_u16(status).unwrap_or(StatusCode::BAD_GATEWAY);
+        let mut out = Response::builder().status(status);
+        for name in [header::CONTENT_TYPE, header::RETRY_AFTER] {
+            if let Some(v) = resp.headers().get(&name) {
+                out = out.header(name, v.clone());
+            }
+        }
+
+        // 中途出错时中止响应体 (客户端看到连接中断)，而不是把错误文本混入 SSE / JSON 数据
+        let stream_provider = provider.clone();
+        let stream_trace_id = trace_id.to_string();
+        let stream = resp.bytes_stream().map(move |chunk| {
+            chunk.map_err(|e| {
+                let message = format!("Provider '{}' stream error: {}", stream_provider.id, e);
+                registry::report_stream_error(&stream_provider, &message);
+                tracing::error!("[{}] {}", stream_trace_id, message);
+                std::io::Error::other(message)
+            })
+        });
+
+        let response = out.body(Body::from_stream(stream)).unwrap_or_else(|_| {
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build response").into_response()
+        });
+        return with_log_headers(response, selection);
+    }
+}
+
+/// 监控中间件据此记录账号 (`provider:<id>`) 与实际模型
+fn with_log_headers(mut response: Response, selection: &ProviderSelection) -> Response {
+    let headers = response.headers_mut();
+    if let Ok(v) = HeaderValue::from_str(&selection.account_label()) {
+        headers.insert("X-Account-Email", v);
+    }
+    if let Ok(v) = HeaderValue::from_str(&selection.upstream_model) {
+        headers.insert("X-Mapped-Model", v);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_url_handles_v1_suffix() {
+        assert_eq!(
+            endpoint_url("http://127.0.0.1:8000", "/v1/chat/completions"),
+            "http://127.0.0.1:8000/v1/chat/completions"
+        );
+        assert_eq!(
+            endpoint_url("https://llm.example.com/openai/v1/", "/v1/chat/completions"),
+            "https://llm.example.com/openai/v1/chat/completions"
+        );
+        assert_eq!(
+            endpoint_url("https://api.anthropic.com", "/v1/messages/count_tokens"),
+            "https://api.anthropic.com/v1/messages/count_tokens"
+        );
+    }
+}
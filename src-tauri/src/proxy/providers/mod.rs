@@ -1,2 +1,3 @@
+pub mod compatible;
+pub mod registry;
 pub mod zai_anthropic;
-
@@ -0,0 +1,462 @@
+//! 自定义上游注册表
+//!
+//! 按协议与模型为请求选择 OpenAI / Anthropic 兼容上游 (公司网关、本地 vLLM、Anthropic API Key 等)，
+//! 并在进程内跟踪每个上游、每个 Key 的请求数与限流冷却。配置来自 `proxy.providers`，运行状态不落盘。
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::proxy::common::model_mapping::wildcard_match;
+use crate::proxy::common::routing_rules::RouteDecision;
+use crate::proxy::config::{
+    get_providers_config, ProviderDispatchMode, ProviderProtocol, UpstreamProviderConfig,
+};
+
+/// 统计 / 日志中上游的账号标识前缀 (`provider:<id>`)
+pub const ACCOUNT_PREFIX: &str = "provider:";
+
+/// 一次调度结果
+#[derive(Debug, Clone)]
+pub struct ProviderSelection {
+    pub provider: UpstreamProviderConfig,
+    /// 发送给上游的模型名 (已应用 model_mapping)
+    pub upstream_model: String,
+    /// 选中原因: rule / model / fallback
+    pub reason: &'static str,
+}
+
+impl ProviderSelection {
+    pub fn account_label(&self) -> String {
+        format!("{}{}", ACCOUNT_PREFIX, self.provider.id)
+    }
+}
+
+/// 上游是否声明服务该模型
+pub fn serves_model(provider: &UpstreamProviderConfig, model: &str) -> bool {
+    provider.models.iter().any(|pattern| wildcard_match(pattern, model))
+}
+
+/// 转发前改写模型名: 精确映射优先，其次按通配符特异度 (非通配字符数) 选择，均未命中时原样使用
+pub fn map_model(provider: &UpstreamProviderConfig, model: &str) -> String {
+    if let Some(mapped) = provider.model_mapping.get(model) {
+        return mapped.clone();
+    }
+    provider
+        .model_mapping
+        .iter()
+        .filter(|(pattern, _)| pattern.contains('*') && wildcard_match(pattern, model))
+        .max_by(|(a, _), (b, _)| {
+            let specificity = |p: &str| p.chars().count() - p.matches('*').count();
+            specificity(a).cmp(&specificity(b)).then_with(|| b.cmp(a))
+        })
+        .map(|(_, target)| target.clone())
+        .unwrap_or_else(|| model.to_string())
+}
+
+/// 按配置顺序查找首个启用、协议一致且 `models` 命中的上游
+pub fn match_by_model<'a>(
+    providers: &'a [UpstreamProviderConfig],
+    protocol: ProviderProtocol,
+    model: &str,
+) -> Option<&'a UpstreamProviderConfig> {
+    providers
+        .iter()
+        .find(|p| p.enabled && p.protocol == protocol && serves_model(p, model))
+}
+
+/// 为请求选择自定义上游，返回 None 时继续走 Google 账号池
+///
+/// 1. 路由规则显式指定的上游 (规则匹配阶段已校验存在性与协议)
+/// 2. `models` 列表命中的上游：exclusive 总是转发；fallback 仅在账号池对目标模型无可用账号时转发
+pub async fn select_provider(
+    token_manager: &crate::proxy::token_manager::TokenManager,
+    protocol: ProviderProtocol,
+    client_model: &str,
+    route: &RouteDecision,
+    account_groups: &[String],
+    trace_id: &str,
+) -> Option<ProviderSelection> {
+    let providers = get_providers_config();
+    if providers.is_empty() {
+        return None;
+    }
+
+    if let Some(id) = &route.provider {
+        match providers
+            .iter()
+            .find(|p| &p.id == id && p.enabled && p.protocol == protocol)
+        {
+            Some(provider) => {
+                tracing::info!(
+                    "[{}] Routing rule {:?} dispatches to provider '{}'",
+                    trace_id,
+                    route.rule_id,
+                    provider.id
+                );
+                return Some(ProviderSelection {
+                    upstream_model: map_model(provider, &route.mapped_model),
+                    provider: provider.clone(),
+                    reason: "rule",
+                });
+            }
+            None => tracing::warn!(
+                "[{}] Routing rule {:?} targets provider '{}' which is unavailable for {}, using Google flow",
+                trace_id,
+                route.rule_id,
+                id,
+                protocol.as_str()
+            ),
+        }
+    }
+
+    let provider = match_by_model(&providers, protocol, client_model)?;
+    let reason = match provider.dispatch_mode {
+        ProviderDispatchMode::Exclusive => "model",
+        ProviderDispatchMode::Fallback => {
+            let pool_available = token_manager.len() > 0
+                && token_manager
+                    .has_available_account(protocol.as_str(), &route.mapped_model, account_groups)
+                    .await;
+            if pool_available {
+                return None;
+            }
+            tracing::info!(
+                "[{}] No Google account available for {}, falling back to provider '{}'",
+                trace_id,
+                route.mapped_model,
+                provider.id
+            );
+            "fallback"
+        }
+    };
+
+    Some(ProviderSelection {
+        upstream_model: map_model(provider, client_model),
+        provider: provider.clone(),
+        reason,
+    })
+}
+
+/// 请求的上游标识 (响应缓存指纹使用)：自定义上游 `provider:<id>` / `zai` / `google`
+///
+/// fallback 模式取决于实时账号可用性，这里只识别确定性的调度 (路由规则与 exclusive 上游)
+pub fn upstream_label(protocol: &str, client_model: &str, route: &RouteDecision) -> String {
+    if let Some(id) = &route.provider {
+        return format!("{}{}", ACCOUNT_PREFIX, id);
+    }
+    if route.use_zai {
+        return "zai".to_string();
+    }
+    let protocol = match protocol {
+        "openai" => ProviderProtocol::Openai,
+        "anthropic" => ProviderProtocol::Anthropic,
+        _ => return "google".to_string(),
+    };
+    let providers = get_providers_config();
+    match match_by_model(&providers, protocol, client_model) {
+        Some(p) if p.dispatch_mode == ProviderDispatchMode::Exclusive => {
+            format!("{}{}", ACCOUNT_PREFIX, p.id)
+        }
+        _ => "google".to_string(),
+    }
+}
+
+// ============================================================================
+// 运行状态: 请求计数与 Key 限流冷却
+// ============================================================================
+
+#[derive(Debug, Default)]
+struct KeyRuntime {
+    requests: u64,
+    rate_limited: u64,
+    cooldown_until: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+struct ProviderRuntime {
+    /// 以 Key 原文为索引 (仅驻留内存)，Key 顺序调整后统计不会错位
+    keys: HashMap<String, KeyRuntime>,
+    next_key: usize,
+    requests: u64,
+    errors: u64,
+    rate_limited: u64,
+    last_status: Option<u16>,
+    last_error: Option<String>,
+    last_used_at: Option<i64>,
+}
+
+static RUNTIME: OnceLock<Mutex<HashMap<String, ProviderRuntime>>> = OnceLock::new();
+
+fn runtime() -> &'static Mutex<HashMap<String, ProviderRuntime>> {
+    RUNTIME.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Key 选择结果
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyChoice {
+    /// 上游未配置 Key (如本地 vLLM)，不发送认证头
+    NoAuth,
+    Key(String),
+    /// 所有 Key 都在冷却中，附带最短剩余秒数
+    AllCoolingDown(u64),
+}
+
+/// 轮询选择下一个未冷却、且本次请求尚未尝试过的 Key
+pub fn acquire_key(provider: &UpstreamProviderConfig, tried: &[String]) -> KeyChoice {
+    let keys: Vec<&String> = provider
+        .api_keys
+        .iter()
+        .filter(|k| !k.trim().is_empty())
+        .collect();
+    if keys.is_empty() {
+        return KeyChoice::NoAuth;
+    }
+
+    let now = Instant::now();
+    let mut guard = runtime().lock().unwrap_or_else(|e| e.into_inner());
+    let state = guard.entry(provider.id.clone()).or_default();
+
+    let mut min_wait: Option<Duration> = None;
+    for offset in 0..keys.len() {
+        let idx = (state.next_key + offset) % keys.len();
+        let key = keys[idx];
+        if tried.contains(key) {
+            continue;
+        }
+        match state.keys.get(key).and_then(|k| k.cooldown_until) {
+            Some(until) if until > now => {
+                let wait = until - now;
+                min_wait = Some(min_wait.map_or(wait, |w| w.min(wait)));
+            }
+            _ => {
+                state.next_key = (idx + 1) % keys.len();
+                return KeyChoice::Key(key.clone());
+            }
+        }
+    }
+
+    KeyChoice::AllCoolingDown(min_wait.map(|d| d.as_secs().max(1)).unwrap_or(1))
+}
+
+/// 记录一次上游响应；429 时按 Retry-After (或配置的默认冷却) 冷却该 Key
+pub fn report_response(
+    provider: &UpstreamProviderConfig,
+    key: Option<&str>,
+    status: u16,
+    retry_after_secs: Option<u64>,
+) {
+    let mut guard = runtime().lock().unwrap_or_else(|e| e.into_inner());
+    let state = guard.entry(provider.id.clone()).or_default();
+    state.requests += 1;
+    state.last_status = Some(status);
+    state.last_used_at = Some(chrono::Utc::now().timestamp());
+    if status >= 400 {
+        state.errors += 1;
+    }
+    if status == 429 {
+        state.rate_limited += 1;
+    }
+
+    if let Some(key) = key {
+        let key_state = state.keys.entry(key.to_string()).or_default();
+        key_state.requests += 1;
+        if status == 429 {
+            key_state.rate_limited += 1;
+            let secs = retry_after_secs.unwrap_or(provider.rate_limit_cooldown_secs).max(1);
+            key_state.cooldown_until = Some(Instant::now() + Duration::from_secs(secs));
+            tracing::warn!(
+                "[Providers] {} key {} rate limited, cooling down for {}s",
+                provider.id,
+                mask_key(key),
+                secs
+            );
+        }
+    }
+}
+
+/// 记录一次网络层失败 (未拿到上游响应)
+pub fn report_error(provider: &UpstreamProviderConfig, error: &str) {
+    let mut guard = runtime().lock().unwrap_or_else(|e| e.into_inner());
+    let state = guard.entry(provider.id.clone()).or_default();
+    state.requests += 1;
+    state.errors += 1;
+    state.last_error = Some(error.to_string());
+    state.last_used_at = Some(chrono::Utc::now().timestamp());
+}
+
+/// 记录响应流中途断开 (请求已由 `report_response` 计数，这里只记错误)
+pub fn report_stream_error(provider: &UpstreamProviderConfig, error: &str) {
+    let mut guard = runtime().lock().unwrap_or_else(|e| e.into_inner());
+    let state = guard.entry(provider.id.clone()).or_default();
+    state.errors += 1;
+    state.last_error = Some(error.to_string());
+}
+
+fn mask_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= 8 {
+        return "***".to_string();
+    }
+    format!(
+        "{}…{}",
+        chars[..4].iter().collect::<String>(),
+        chars[chars.len() - 4..].iter().collect::<String>()
+    )
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderKeyStatus {
+    /// 打码后的 Key (前 4 位 + 后 4 位)
+    pub key: String,
+    pub requests: u64,
+    pub rate_limited: u64,
+    /// 剩余冷却秒数 (0 = 可用)
+    pub cooldown_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderStatus {
+    pub id: String,
+    pub name: String,
+    pub protocol: ProviderProtocol,
+    pub enabled: bool,
+    pub dispatch_mode: ProviderDispatchMode,
+    pub models: Vec<String>,
+    pub keys: Vec<ProviderKeyStatus>,
+    pub requests: u64,
+    pub errors: u64,
+    pub rate_limited: u64,
+    pub last_status: Option<u16>,
+    pub last_error: Option<String>,
+    pub last_used_at: Option<i64>,
+}
+
+/// 当前配置中所有上游的运行状态 (Admin API / 前端展示)
+pub fn get_provider_statuses() -> Vec<ProviderStatus> {
+    let now = Instant::now();
+    let guard = runtime().lock().unwrap_or_else(|e| e.into_inner());
+    get_providers_config()
+        .into_iter()
+        .map(|provider| {
+            let state = guard.get(&provider.id);
+            let keys = provider
+                .api_keys
+                .iter()
+                .filter(|k| !k.trim().is_empty())
+                .map(|key| {
+                    let key_state = state.and_then(|s| s.keys.get(key));
+                    ProviderKeyStatus {
+                        key: mask_key(key),
+                        requests: key_state.map(|k| k.requests).unwrap_or(0),
+                        rate_limited: key_state.map(|k| k.rate_limited).unwrap_or(0),
+                        cooldown_secs: key_state
+                            .and_then(|k| k.cooldown_until)
+                            .filter(|until| *until > now)
+                            .map(|until| (until - now).as_secs().max(1))
+                            .unwrap_or(0),
+                    }
+                })
+                .collect();
+            ProviderStatus {
+                id: provider.id.clone(),
+                name: provider.display_name().to_string(),
+                protocol: provider.protocol,
+                enabled: provider.enabled,
+                dispatch_mode: provider.dispatch_mode,
+                models: provider.models.clone(),
+                keys,
+                requests: state.map(|s| s.requests).unwrap_or(0),
+                errors: state.map(|s| s.errors).unwrap_or(0),
+                rate_limited: state.map(|s| s.rate_limited).unwrap_or(0),
+                last_status: state.and_then(|s| s.last_status),
+                last_error: state.and_then(|s| s.last_error.clone()),
+                last_used_at: state.and_then(|s| s.last_used_at),
+            }
+        })
+        .collect()
+}
+
+/// 上游服务的具体模型名 (非通配符)，用于 /v1/models 列表
+pub fn listed_model_ids() -> Vec<String> {
+    get_providers_config()
+        .into_iter()
+        .filter(|p| p.enabled)
+        .flat_map(|p| p.models.into_iter())
+        .filter(|m| !m.contains('*'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(id: &str, protocol: ProviderProtocol, models: &[&str]) -> UpstreamProviderConfig {
+        UpstreamProviderConfig {
+            id: id.to_string(),
+            name: None,
+            enabled: true,
+            protocol,
+            base_url: "http://127.0.0.1:8000".to_string(),
+            api_keys: Vec::new(),
+            models: models.iter().map(|m| m.to_string()).collect(),
+            model_mapping: HashMap::new(),
+            dispatch_mode: ProviderDispatchMode::Exclusive,
+            rate_limit_cooldown_secs: 60,
+        }
+    }
+
+    #[test]
+    fn test_match_by_model_respects_protocol_and_order() {
+        let mut disabled = provider("off", ProviderProtocol::Openai, &["*"]);
+        disabled.enabled = false;
+        let providers = vec![
+            disabled,
+            provider("anthropic", ProviderProtocol::Anthropic, &["claude-*"]),
+            provider("vllm", ProviderProtocol::Openai, &["qwen-*", "llama-3-70b"]),
+            provider("gateway", ProviderProtocol::Openai, &["*"]),
+        ];
+
+        let hit = |protocol, model| match_by_model(&providers, protocol, model).map(|p| p.id.as_str());
+        assert_eq!(hit(ProviderProtocol::Openai, "qwen-2.5-72b"), Some("vllm"));
+        assert_eq!(hit(ProviderProtocol::Openai, "gpt-4o"), Some("gateway"));
+        assert_eq!(hit(ProviderProtocol::Anthropic, "claude-sonnet-4-5"), Some("anthropic"));
+        assert_eq!(hit(ProviderProtocol::Anthropic, "glm-4.6"), None);
+    }
+
+    #[test]
+    fn test_map_model_prefers_exact_then_specific_wildcard() {
+        let mut p = provider("vllm", ProviderProtocol::Openai, &["*"]);
+        p.model_mapping.insert("gpt-4o".to_string(), "qwen-72b".to_string());
+        p.model_mapping.insert("gpt-*".to_string(), "qwen-7b".to_string());
+        p.model_mapping.insert("gpt-4*".to_string(), "qwen-32b".to_string());
+
+        assert_eq!(map_model(&p, "gpt-4o"), "qwen-72b");
+        assert_eq!(map_model(&p, "gpt-4.1"), "qwen-32b");
+        assert_eq!(map_model(&p, "gpt-3.5-turbo"), "qwen-7b");
+        assert_eq!(map_model(&p, "llama"), "llama");
+    }
+
+    #[test]
+    fn test_acquire_key_rotates_and_skips_cooling_keys() {
+        let mut p = provider("test-keys", ProviderProtocol::Anthropic, &[]);
+        assert_eq!(acquire_key(&p, &[]), KeyChoice::NoAuth);
+
+        p.api_keys = vec!["sk-ant-key-one-0001".to_string(), "sk-ant-key-two-0002".to_string()];
+        let first = acquire_key(&p, &[]);
+        let second = acquire_key(&p, &[]);
+        assert_ne!(first, second);
+
+        // 第一个 Key 被限流后只会选到第二个
+        report_response(&p, Some("sk-ant-key-one-0001"), 429, Some(30));
+        for _ in 0..3 {
+            assert_eq!(acquire_key(&p, &[]), KeyChoice::Key("sk-ant-key-two-0002".to_string()));
+        }
+        // 已尝试过第二个 Key 时，所有 Key 都不可用
+        match acquire_key(&p, &["sk-ant-key-two-0002".to_string()]) {
+            KeyChoice::AllCoolingDown(secs) => assert!(secs > 0 && secs <= 30),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -46,7 +46,7 @@ fn join_base_url(base: &str, path: &str) -> Result<String, String> {
     Ok(format!("{}{}", base, path))
 }
 
-fn build_client(
+pub(crate) fn build_client(
     upstream_proxy: Option<crate::proxy::config::UpstreamProxyConfig>,
     timeout_secs: u64,
 ) -> Result<reqwest::Client, String> {
@@ -68,7 +68,7 @@ fn build_client(
         .map_err(|e| format!("Failed to build HTTP client: {}", e))
 }
 
-fn copy_passthrough_headers(incoming: &HeaderMap) -> HeaderMap {
+pub(crate) fn copy_passthrough_headers(incoming: &HeaderMap) -> HeaderMap {
     // Only forward a conservative set of headers to avoid leaking the local proxy key or cookies.
     let mut out = HeaderMap::new();
 
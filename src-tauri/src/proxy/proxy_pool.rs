@@ -391,6 +391,19 @@ impl ProxyPoolManager {
         let mut config = self.config.write().await;
         for (id, is_healthy, latency) in results {
             if let Some(proxy) = config.proxies.iter_mut().find(|p| p.id == id) {
+                // [NEW] 由健康变为失败时发送 Webhook 告警
+                if proxy.is_healthy && !is_healthy {
+                    crate::modules::webhook::notify(
+                        crate::modules::webhook::WebhookEvent::ProxyUnhealthy,
+                        proxy.name.clone(),
+                        format!("Proxy {} failed its health check", proxy.name),
+                        serde_json::json!({
+                            "proxy_id": proxy.id,
+                            "name": proxy.name,
+                            "url": redact_proxy_url(&proxy.url),
+                        }),
+                    );
+                }
                 proxy.is_healthy = is_healthy;
                 proxy.latency = latency;
                 proxy.last_check_time = Some(chrono::Utc::now().timestamp());
@@ -481,3 +494,17 @@ impl ProxyPoolManager {
         });
     }
 }
+
+/// 去掉代理 URL 中的账号密码，避免随告警外发
+fn redact_proxy_url(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(mut parsed) => {
+            if !parsed.username().is_empty() || parsed.password().is_some() {
+                let _ = parsed.set_username("***");
+                let _ = parsed.set_password(None);
+            }
+            parsed.to_string()
+        }
+        Err(_) => "<invalid url>".to_string(),
+    }
+}
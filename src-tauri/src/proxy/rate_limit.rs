@@ -1,9 +1,13 @@
 use dashmap::DashMap;
-use std::time::{SystemTime, Duration};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, Duration, UNIX_EPOCH};
 use regex::Regex;
+use tokio::sync::mpsc;
+use crate::modules::rate_limit_db::{self, PersistedRateLimit};
 
 /// 限流原因类型
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RateLimitReason {
     /// 配额耗尽 (QUOTA_EXHAUSTED)
     QuotaExhausted,
@@ -17,6 +21,29 @@ pub enum RateLimitReason {
     Unknown,
 }
 
+impl RateLimitReason {
+    /// 持久化用的字符串形式
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateLimitReason::QuotaExhausted => "quota_exhausted",
+            RateLimitReason::RateLimitExceeded => "rate_limit_exceeded",
+            RateLimitReason::ModelCapacityExhausted => "model_capacity_exhausted",
+            RateLimitReason::ServerError => "server_error",
+            RateLimitReason::Unknown => "unknown",
+        }
+    }
+
+    pub fn from_str_lossy(s: &str) -> Self {
+        match s {
+            "quota_exhausted" => RateLimitReason::QuotaExhausted,
+            "rate_limit_exceeded" => RateLimitReason::RateLimitExceeded,
+            "model_capacity_exhausted" => RateLimitReason::ModelCapacityExhausted,
+            "server_error" => RateLimitReason::ServerError,
+            _ => RateLimitReason::Unknown,
+        }
+    }
+}
+
 /// 限流信息
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
@@ -46,6 +73,50 @@ pub struct RateLimitTracker {
     limits: DashMap<String, RateLimitInfo>,
     /// 连续失败计数（用于智能指数退避），带时间戳用于自动过期
     failure_counts: DashMap<String, (u32, SystemTime)>,
+    /// [NEW] 持久化目录与写入队列 (由 TokenManager 在加载账号时开启)
+    persistence: Mutex<Option<Persistence>>,
+}
+
+struct Persistence {
+    data_dir: PathBuf,
+    tx: mpsc::UnboundedSender<PersistOp>,
+}
+
+/// 待写入 rate_limits.db 的变更，由后台线程按顺序执行，不阻塞请求路径
+enum PersistOp {
+    SaveLimit(PersistedRateLimit),
+    DeleteLimit(String),
+    DeleteExpiredLimits(i64),
+    ClearLimits,
+    SaveFailureCount(String, u32, i64),
+    DeleteFailureCount(String),
+}
+
+impl PersistOp {
+    fn apply(self, data_dir: &Path) -> Result<(), String> {
+        match self {
+            PersistOp::SaveLimit(record) => rate_limit_db::save_limit(data_dir, &record),
+            PersistOp::DeleteLimit(key) => rate_limit_db::delete_limit(data_dir, &key),
+            PersistOp::DeleteExpiredLimits(now) => {
+                rate_limit_db::delete_expired_limits(data_dir, now).map(|_| ())
+            }
+            PersistOp::ClearLimits => rate_limit_db::clear_limits(data_dir),
+            PersistOp::SaveFailureCount(account_id, count, updated_at) => {
+                rate_limit_db::save_failure_count(data_dir, &account_id, count, updated_at)
+            }
+            PersistOp::DeleteFailureCount(account_id) => {
+                rate_limit_db::delete_failure_count(data_dir, &account_id)
+            }
+        }
+    }
+}
+
+fn to_unix_secs(t: SystemTime) -> i64 {
+    t.duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+fn from_unix_secs(secs: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs.max(0) as u64)
 }
 
 impl RateLimitTracker {
@@ -53,8 +124,120 @@ impl RateLimitTracker {
         Self {
             limits: DashMap::new(),
             failure_counts: DashMap::new(),
+            persistence: Mutex::new(None),
+        }
+    }
+
+    /// 开启持久化，之后的限流变更会写入 `data_dir/rate_limits.db`
+    ///
+    /// SQLite 写入在阻塞线程上按提交顺序执行 (有 Tokio 运行时则用 `spawn_blocking`)。
+    /// 重复调用会替换目录，旧的写入线程在队列清空后退出。
+    pub fn enable_persistence(&self, data_dir: PathBuf) {
+        let (tx, mut rx) = mpsc::unbounded_channel::<PersistOp>();
+        let worker_dir = data_dir.clone();
+        let worker = move || {
+            while let Some(op) = rx.blocking_recv() {
+                if let Err(e) = op.apply(&worker_dir) {
+                    tracing::warn!("[RateLimit] 持久化限流状态失败: {}", e);
+                }
+            }
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(worker);
+            }
+            Err(_) => {
+                std::thread::spawn(worker);
+            }
+        }
+        if let Ok(mut persistence) = self.persistence.lock() {
+            *persistence = Some(Persistence { data_dir, tx });
+        }
+    }
+
+    fn persist_dir(&self) -> Option<PathBuf> {
+        self.persistence
+            .lock()
+            .ok()
+            .and_then(|p| p.as_ref().map(|p| p.data_dir.clone()))
+    }
+
+    fn is_persistent(&self) -> bool {
+        self.persistence.lock().map(|p| p.is_some()).unwrap_or(false)
+    }
+
+    fn persist(&self, op: PersistOp) {
+        if let Ok(persistence) = self.persistence.lock() {
+            if let Some(p) = persistence.as_ref() {
+                let _ = p.tx.send(op);
+            }
         }
     }
+
+    fn persist_limit(&self, key: &str, account_id: &str, info: &RateLimitInfo) {
+        if !self.is_persistent() {
+            return;
+        }
+        let record = PersistedRateLimit {
+            key: key.to_string(),
+            account_id: account_id.to_string(),
+            model: if key == account_id { None } else { info.model.clone() },
+            reason: info.reason.as_str().to_string(),
+            reset_at: to_unix_secs(info.reset_time),
+            retry_after_sec: info.retry_after_sec as i64,
+            detected_at: to_unix_secs(info.detected_at),
+        };
+        self.persist(PersistOp::SaveLimit(record));
+    }
+
+    fn persist_failure_count(&self, account_id: &str, count: u32, updated_at: SystemTime) {
+        self.persist(PersistOp::SaveFailureCount(
+            account_id.to_string(),
+            count,
+            to_unix_secs(updated_at),
+        ));
+    }
+
+    /// 从 SQLite 恢复未过期的限流记录与失败计数 (过期记录在加载时删除)
+    ///
+    /// 需先调用 `enable_persistence`。内存中已存在且更晚解除的记录保持不变。返回恢复的限流记录数。
+    pub fn restore_persisted(&self) -> Result<usize, String> {
+        let Some(data_dir) = self.persist_dir() else {
+            return Ok(0);
+        };
+        let (limits, failures) =
+            rate_limit_db::load_active_state(&data_dir, FAILURE_COUNT_EXPIRY_SECONDS as i64)?;
+
+        let mut restored = 0;
+        for record in limits {
+            let reset_time = from_unix_secs(record.reset_at);
+            if matches!(self.limits.get(&record.key), Some(existing) if existing.reset_time >= reset_time) {
+                continue;
+            }
+            self.limits.insert(
+                record.key,
+                RateLimitInfo {
+                    reset_time,
+                    retry_after_sec: record.retry_after_sec.max(0) as u64,
+                    detected_at: from_unix_secs(record.detected_at),
+                    reason: RateLimitReason::from_str_lossy(&record.reason),
+                    model: record.model,
+                },
+            );
+            restored += 1;
+        }
+
+        for record in failures {
+            self.failure_counts
+                .entry(record.account_id)
+                .or_insert((record.count, from_unix_secs(record.updated_at)));
+        }
+
+        if restored > 0 {
+            tracing::info!("[RateLimit] 已从数据库恢复 {} 条限流记录", restored);
+        }
+        Ok(restored)
+    }
     
     /// 生成限流 Key
     /// - 账号级: "account_id"
@@ -98,9 +281,12 @@ impl RateLimitTracker {
     pub fn mark_success(&self, account_id: &str) {
         if self.failure_counts.remove(account_id).is_some() {
             tracing::debug!("账号 {} 请求成功，已重置失败计数", account_id);
+            self.persist(PersistOp::DeleteFailureCount(account_id.to_string()));
         }
         // 清除账号级限流
-        self.limits.remove(account_id);
+        if self.limits.remove(account_id).is_some() {
+            self.persist(PersistOp::DeleteLimit(account_id.to_string()));
+        }
         // 注意：我们暂时无法清除该账号下的所有模型级锁，因为我们不知道哪些模型被锁了
         // 除非遍历 limits。考虑到模型级锁通常是 QuotaExhausted，让其自然过期也是可以接受的。
         // 或者我们可以引入索引，但为了简单，暂时只清除 Account 级锁。
@@ -129,6 +315,7 @@ impl RateLimitTracker {
         };
         
         let key = self.get_limit_key(account_id, model.as_deref());
+        self.persist_limit(&key, account_id, &info);
         self.limits.insert(key, info);
         
         if let Some(m) = &model {
@@ -243,7 +430,10 @@ impl RateLimitTracker {
                     }
                     entry.0 += 1;
                     entry.1 = now;
-                    entry.0
+                    let count = entry.0;
+                    drop(entry);
+                    self.persist_failure_count(account_id, count, now);
+                    count
                 } else {
                     // ServerError (5xx) 使用固定值 1，不累加，避免污染 429 的退避阶梯
                     1
@@ -314,6 +504,7 @@ impl RateLimitTracker {
             account_id.to_string()
         };
 
+        self.persist_limit(&key, account_id, &info);
         self.limits.insert(key, info.clone());
         
         tracing::warn!(
@@ -523,6 +714,26 @@ impl RateLimitTracker {
         }
     }
     
+    /// 按限流原因统计仍处于锁定期的账号数 (同一账号同一原因只计一次)
+    pub fn active_accounts_by_reason(&self) -> std::collections::HashMap<RateLimitReason, usize> {
+        let now = SystemTime::now();
+        let mut seen = std::collections::HashSet::new();
+        let mut counts = std::collections::HashMap::new();
+
+        for entry in self.limits.iter() {
+            if entry.value().reset_time <= now {
+                continue;
+            }
+            // Key 形如 "account_id" 或 "account_id:model"
+            let account_id = entry.key().split(':').next().unwrap_or_default().to_string();
+            let reason = entry.value().reason;
+            if seen.insert((account_id, reason)) {
+                *counts.entry(reason).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
     /// 清除过期的限流记录
     #[allow(dead_code)]
     pub fn cleanup_expired(&self) -> usize {
@@ -540,6 +751,7 @@ impl RateLimitTracker {
         
         if count > 0 {
             tracing::debug!("清除了 {} 个过期的限流记录", count);
+            self.persist(PersistOp::DeleteExpiredLimits(to_unix_secs(now)));
         }
         
         count
@@ -547,6 +759,7 @@ impl RateLimitTracker {
     
     /// 清除指定账号的限流记录
     pub fn clear(&self, account_id: &str) -> bool {
+        self.persist(PersistOp::DeleteLimit(account_id.to_string()));
         self.limits.remove(account_id).is_some()
     }
     
@@ -557,6 +770,7 @@ impl RateLimitTracker {
     pub fn clear_all(&self) {
         let count = self.limits.len();
         self.limits.clear();
+        self.persist(PersistOp::ClearLimits);
         tracing::warn!("🔄 Optimistic reset: Cleared all {} rate limit record(s)", count);
     }
 }
@@ -571,6 +785,19 @@ impl Default for RateLimitTracker {
 mod tests {
     use super::*;
     
+    #[test]
+    fn test_reason_round_trip() {
+        for reason in [
+            RateLimitReason::QuotaExhausted,
+            RateLimitReason::RateLimitExceeded,
+            RateLimitReason::ModelCapacityExhausted,
+            RateLimitReason::ServerError,
+            RateLimitReason::Unknown,
+        ] {
+            assert_eq!(RateLimitReason::from_str_lossy(reason.as_str()), reason);
+        }
+    }
+
     #[test]
     fn test_parse_retry_time_minutes_seconds() {
         let tracker = RateLimitTracker::new();
@@ -679,4 +906,37 @@ mod tests {
         let info = tracker.parse_from_error("acc2", 429, None, quota_body, None, &backoff_steps);
         assert_eq!(info.unwrap().retry_after_sec, 7200);
     }
+
+    #[test]
+    fn test_persistence_is_scoped_to_data_dir() {
+        let dir = std::env::temp_dir().join(format!("rate_limit_scope_{}", uuid::Uuid::new_v4()));
+        let other = std::env::temp_dir().join(format!("rate_limit_scope_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::create_dir_all(&other).unwrap();
+
+        let tracker = RateLimitTracker::new();
+        tracker.enable_persistence(dir.clone());
+        let reset = SystemTime::now() + Duration::from_secs(600);
+        tracker.set_lockout_until("acc1", reset, RateLimitReason::QuotaExhausted, None);
+
+        // 写入在后台线程执行，轮询等待落盘
+        let restored_from = |path: &PathBuf| {
+            let fresh = RateLimitTracker::new();
+            fresh.enable_persistence(path.clone());
+            fresh.restore_persisted().unwrap()
+        };
+        let mut restored = 0;
+        for _ in 0..50 {
+            restored = restored_from(&dir);
+            if restored > 0 {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(restored, 1);
+        assert_eq!(restored_from(&other), 0);
+
+        let _ = std::fs::remove_dir_all(&dir);
+        let _ = std::fs::remove_dir_all(&other);
+    }
 }
@@ -92,7 +92,7 @@ pub fn take_pending_delete_accounts() -> Vec<String> {
 #[derive(Clone)]
 pub struct AppState {
     pub token_manager: Arc<TokenManager>,
-    pub custom_mapping: Arc<tokio::sync::RwLock<std::collections::HashMap<String, String>>>,
+    pub model_router: Arc<tokio::sync::RwLock<crate::proxy::common::routing_rules::ModelRouter>>,
     #[allow(dead_code)]
     pub request_timeout: u64, // API 请求超时(秒)
     #[allow(dead_code)]
@@ -214,7 +214,7 @@ fn to_account_response(
 #[derive(Clone)]
 pub struct AxumServer {
     shutdown_tx: Arc<tokio::sync::Mutex<Option<oneshot::Sender<()>>>>,
-    custom_mapping: Arc<tokio::sync::RwLock<std::collections::HashMap<String, String>>>,
+    model_router: Arc<tokio::sync::RwLock<crate::proxy::common::routing_rules::ModelRouter>>,
     proxy_state: Arc<tokio::sync::RwLock<crate::proxy::config::UpstreamProxyConfig>>,
     upstream: Arc<crate::proxy::upstream::client::UpstreamClient>,
     security_state: Arc<RwLock<crate::proxy::ProxySecurityConfig>>,
@@ -232,10 +232,10 @@ pub struct AxumServer {
 impl AxumServer {
     pub async fn update_mapping(&self, config: &crate::proxy::config::ProxyConfig) {
         {
-            let mut m = self.custom_mapping.write().await;
-            *m = config.custom_mapping.clone();
+            let mut router = self.model_router.write().await;
+            *router = crate::proxy::common::routing_rules::ModelRouter::from_config(config);
         }
-        tracing::debug!("模型映射 (Custom) 已全量热更新");
+        tracing::debug!("模型路由规则 (Custom) 已全量热更新");
     }
 
     /// 更新代理配置
@@ -258,6 +258,16 @@ impl AxumServer {
         tracing::info!("反代服务安全配置已热更新");
     }
 
+    /// 仅更新 IP 黑白名单开关，不触碰鉴权相关字段 (端口/API Key 变更需要重启)
+    pub async fn update_security_monitor(
+        &self,
+        monitor: crate::proxy::config::SecurityMonitorConfig,
+    ) {
+        let mut sec = self.security_state.write().await;
+        sec.security_monitor = monitor;
+        tracing::info!("IP 黑白名单配置已热更新");
+    }
+
     pub async fn update_zai(&self, config: &crate::proxy::config::ProxyConfig) {
         let mut zai = self.zai_state.write().await;
         *zai = config.zai.clone();
@@ -294,7 +304,7 @@ impl AxumServer {
         host: String,
         port: u16,
         token_manager: Arc<TokenManager>,
-        custom_mapping: std::collections::HashMap<String, String>,
+        model_router: crate::proxy::common::routing_rules::ModelRouter,
         _request_timeout: u64,
         upstream_proxy: crate::proxy::config::UpstreamProxyConfig,
         user_agent_override: Option<String>,
@@ -308,7 +318,7 @@ impl AxumServer {
         cloudflared_state: Arc<crate::commands::cloudflared::CloudflaredState>,
         proxy_pool_config: crate::proxy::config::ProxyPoolConfig, // [NEW]
     ) -> Result<(Self, tokio::task::JoinHandle<()>), String> {
-        let custom_mapping_state = Arc::new(tokio::sync::RwLock::new(custom_mapping));
+        let model_router_state = Arc::new(tokio::sync::RwLock::new(model_router));
         let proxy_state = Arc::new(tokio::sync::RwLock::new(upstream_proxy.clone()));
         let proxy_pool_state = Arc::new(tokio::sync::RwLock::new(proxy_pool_config));
         let proxy_pool_manager = crate::proxy::proxy_pool::init_global_proxy_pool(proxy_pool_state.clone());
@@ -325,7 +335,7 @@ impl AxumServer {
 
         let state = AppState {
             token_manager: token_manager.clone(),
-            custom_mapping: custom_mapping_state.clone(),
+            model_router: model_router_state.clone(),
             request_timeout: 300, // 5分钟超时
             thought_signature_map: Arc::new(tokio::sync::Mutex::new(
                 std::collections::HashMap::new(),
@@ -364,14 +374,16 @@ impl AxumServer {
         // 构建路由 - 使用新架构的 handlers！
         use crate::proxy::handlers;
         use crate::proxy::middleware::{
-            admin_auth_middleware, auth_middleware, cors_layer, ip_filter_middleware,
-            monitor_middleware, service_status_middleware,
+            admin_audit_middleware, admin_auth_middleware, auth_middleware, cors_layer,
+            ip_filter_middleware,
+            monitor_middleware, response_cache_middleware, service_status_middleware,
         };
 
         // 1. 构建主 AI 代理路由 (遵循 auth_mode 配置)
         let proxy_routes = Router::new()
             .route("/health", get(health_check_handler))
             .route("/healthz", get(health_check_handler))
+            .route("/metrics", get(crate::proxy::metrics::handle_metrics)) // Prometheus 抓取端点 (遵循 auth_mode)
             // OpenAI Protocol
             .route("/v1/models", get(handlers::openai::handle_list_models))
             .route(
@@ -382,7 +394,16 @@ impl AxumServer {
                 "/v1/completions",
                 post(handlers::openai::handle_completions),
             )
-            .route("/v1/responses", post(handlers::openai::handle_completions)) // 兼容 Codex CLI
+            .route(
+                "/v1/embeddings",
+                post(handlers::embeddings::handle_openai_embeddings),
+            ) // Embeddings API
+            .route("/v1/responses", post(handlers::openai::handle_responses)) // 原生 Responses API (Codex CLI)
+            .route(
+                "/v1/responses/:response_id",
+                get(handlers::openai::handle_get_response)
+                    .delete(handlers::openai::handle_delete_response),
+            )
             .route(
                 "/v1/images/generations",
                 post(handlers::openai::handle_images_generations),
@@ -395,12 +416,63 @@ impl AxumServer {
                 "/v1/audio/transcriptions",
                 post(handlers::audio::handle_audio_transcription),
             ) // 音频转录 API
+            // Batch API (本地队列，空闲时执行)
+            .route(
+                "/v1/batches",
+                post(handlers::batches::handle_create_batch).get(handlers::batches::handle_list_batches),
+            )
+            .route("/v1/batches/:batch_id", get(handlers::batches::handle_get_batch))
+            .route(
+                "/v1/batches/:batch_id/cancel",
+                post(handlers::batches::handle_cancel_batch),
+            )
+            .route(
+                "/v1/batches/:batch_id/output",
+                get(handlers::batches::handle_batch_output),
+            )
+            .route(
+                "/v1/batches/:batch_id/errors",
+                get(handlers::batches::handle_batch_errors),
+            )
+            // Files API (Anthropic / OpenAI 共用，按请求头返回对应格式)
+            .route(
+                "/v1/files",
+                post(handlers::files::handle_upload_file)
+                    .get(handlers::files::handle_list_files)
+                    .layer(DefaultBodyLimit::disable()), // 大小由 proxy.files.max_file_size_mb 在读取时限制
+            )
+            .route(
+                "/v1/files/:file_id",
+                get(handlers::files::handle_get_file).delete(handlers::files::handle_delete_file),
+            )
+            .route(
+                "/v1/files/:file_id/content",
+                get(handlers::files::handle_file_content),
+            )
             // Claude Protocol
             .route("/v1/messages", post(handlers::claude::handle_messages))
             .route(
                 "/v1/messages/count_tokens",
                 post(handlers::claude::handle_count_tokens),
             )
+            .route(
+                "/v1/messages/batches",
+                post(handlers::batches::handle_create_message_batch)
+                    .get(handlers::batches::handle_list_message_batches),
+            )
+            .route(
+                "/v1/messages/batches/:batch_id",
+                get(handlers::batches::handle_get_message_batch)
+                    .delete(handlers::batches::handle_delete_message_batch),
+            )
+            .route(
+                "/v1/messages/batches/:batch_id/cancel",
+                post(handlers::batches::handle_cancel_message_batch),
+            )
+            .route(
+                "/v1/messages/batches/:batch_id/results",
+                get(handlers::batches::handle_message_batch_results),
+            )
             .route(
                 "/v1/models/claude",
                 get(handlers::claude::handle_list_models),
@@ -435,9 +507,14 @@ impl AxumServer {
             .route("/v1/api/event_logging", post(silent_ok_handler))
             // 应用 AI 服务特定的层
             // 注意：Axum layer 执行顺序是从下往上（洋葱模型）
-            // 请求: ip_filter -> auth -> monitor -> handler
-            // 响应: handler -> monitor -> auth -> ip_filter
+            // 请求: ip_filter -> auth -> monitor -> response_cache -> handler
+            // 响应: handler -> response_cache -> monitor -> auth -> ip_filter
             // monitor 需要在 auth 之后执行才能获取 UserTokenIdentity
+            // response_cache 位于 monitor 之内，命中的回放响应同样会被记录
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                response_cache_middleware,
+            ))
             .layer(axum::middleware::from_fn_with_state(
                 state.clone(),
                 monitor_middleware,
@@ -525,16 +602,47 @@ impl AxumServer {
             .route("/proxy/start", post(admin_start_proxy_service))
             .route("/proxy/stop", post(admin_stop_proxy_service))
             .route("/proxy/mapping", post(admin_update_model_mapping))
+            .route("/proxy/routing/explain", post(admin_explain_model_route))
             .route("/proxy/api-key/generate", post(admin_generate_api_key))
             .route(
                 "/proxy/session-bindings/clear",
                 post(admin_clear_proxy_session_bindings),
             )
             .route("/proxy/rate-limits", delete(admin_clear_all_rate_limits))
+            .route(
+                "/proxy/response-cache",
+                get(admin_get_response_cache_stats).delete(admin_clear_response_cache),
+            )
+            .route("/proxy/providers/status", get(admin_get_provider_statuses))
+            .route("/proxy/batches", get(admin_get_batch_overview))
+            .route("/proxy/batches/:batchId/cancel", post(admin_cancel_batch))
+            .route("/proxy/files/stats", get(admin_get_file_store_stats))
             .route(
                 "/proxy/rate-limits/:accountId",
                 delete(admin_clear_rate_limit),
             )
+            .route(
+                "/webhooks/deliveries",
+                get(admin_get_webhook_deliveries).delete(admin_clear_webhook_deliveries),
+            )
+            .route("/webhooks/test", post(admin_test_webhook))
+            .route("/audit", get(admin_get_audit_logs))
+            .route("/audit/export", get(admin_export_audit_logs))
+            .route("/audit/verify", get(admin_verify_audit_log))
+            // [NEW] 管理员登录会话与账号管理
+            .route("/auth/login", post(admin_login))
+            .route("/auth/logout", post(admin_logout))
+            .route("/auth/me", get(admin_auth_me))
+            .route(
+                "/admin/users",
+                get(admin_list_admin_users).post(admin_create_admin_user),
+            )
+            .route(
+                "/admin/users/:id",
+                axum::routing::patch(admin_update_admin_user).delete(admin_delete_admin_user),
+            )
+            .route("/admin/sessions", get(admin_list_admin_sessions))
+            .route("/admin/sessions/:id", delete(admin_revoke_admin_session))
             .route(
                 "/proxy/preferred-account",
                 get(admin_get_preferred_account).post(admin_set_preferred_account),
@@ -563,6 +671,7 @@ impl AxumServer {
             .route("/proxy/stats", get(admin_get_proxy_stats))
             .route("/logs", get(admin_get_proxy_logs_filtered))
             .route("/logs/count", get(admin_get_proxy_logs_count_filtered))
+            .route("/logs/groups", get(admin_get_proxy_log_stats_by_group))
             .route("/logs/clear", post(admin_clear_proxy_logs))
             .route("/logs/:logId", get(admin_get_proxy_log_detail))
             // Debug Console (Log Bridge)
@@ -581,6 +690,9 @@ impl AxumServer {
             )
             .route("/stats/token/summary", get(admin_get_token_stats_summary))
             .route("/stats/token/by-model", get(admin_get_token_stats_by_model))
+            .route("/stats/token/by-group", get(admin_get_token_stats_by_group))
+            .route("/stats/token/by-user-token", get(admin_get_token_stats_by_user_token))
+            .route("/stats/token/cache-hits", get(admin_get_token_stats_cache_hits))
             .route(
                 "/stats/token/model-trend/hourly",
                 get(admin_get_token_stats_model_trend_hourly),
@@ -599,15 +711,23 @@ impl AxumServer {
             )
             .route("/accounts/bulk-delete", post(admin_delete_accounts))
             .route("/accounts/export", post(admin_export_accounts))
+            .route("/accounts/export/seal", post(admin_seal_account_export))
+            .route("/accounts/import/open", post(admin_open_account_export))
+            .route("/accounts/encryption", get(admin_get_account_encryption_status))
+            .route("/accounts/encryption/unlock", post(admin_unlock_account_encryption))
+            .route("/accounts/encryption/rotate", post(admin_rotate_account_encryption_key))
             .route("/accounts/reorder", post(admin_reorder_accounts))
             .route("/accounts/:accountId/quota", get(admin_fetch_account_quota))
             .route(
                 "/accounts/:accountId/toggle-proxy",
                 post(admin_toggle_proxy_status),
             )
+            .route("/accounts/:accountId/groups", post(admin_update_account_groups))
             .route("/accounts/warmup", post(admin_warm_up_all_accounts))
             .route("/accounts/:accountId/warmup", post(admin_warm_up_account))
             .route("/system/data-dir", get(admin_get_data_dir_path))
+            .route("/system/backup", post(admin_create_backup))
+            .route("/system/restore", post(admin_restore_backup))
             .route("/system/updates/settings", get(admin_get_update_settings))
             .route(
                 "/system/updates/check-status",
@@ -649,6 +769,7 @@ impl AxumServer {
             .route("/user-tokens", get(admin_list_user_tokens).post(admin_create_user_token))
             .route("/user-tokens/summary", get(admin_get_user_token_summary))
             .route("/user-tokens/:id/renew", post(admin_renew_user_token))
+            .route("/user-tokens/:id/quota", get(admin_get_user_token_quota_usage).delete(admin_reset_user_token_quota_usage))
             .route("/user-tokens/:id", delete(admin_delete_user_token).patch(admin_update_user_token))
             // OAuth (Web) - Admin 接口
             .route("/auth/url", get(admin_prepare_oauth_url_web))
@@ -656,7 +777,9 @@ impl AxumServer {
             .layer(axum::middleware::from_fn_with_state(
                 state.clone(),
                 admin_auth_middleware,
-            ));
+            ))
+            // [NEW] 审计层在鉴权外层，未授权的变更请求也会留痕
+            .layer(axum::middleware::from_fn(admin_audit_middleware));
 
         // 3. 整合并应用全局层
         // 从环境变量读取 body 大小限制，默认 50MB
@@ -680,6 +803,12 @@ impl AxumServer {
             .layer(DefaultBodyLimit::max(max_body_size)) // 放宽 body 大小限制
             .with_state(state.clone());
 
+        // [NEW] 批处理 worker: 通过内部 Router 转发队列中的请求
+        let batch_worker = crate::proxy::batch_worker::spawn(
+            state.clone(),
+            batch_dispatch_router(&state, max_body_size),
+        );
+
         // 静态文件托管 (用于 Headless/Docker 模式)
         let dist_path = std::env::var("ABV_DIST_PATH").unwrap_or_else(|_| "dist".to_string());
         let app = if std::path::Path::new(&dist_path).exists() {
@@ -704,7 +833,7 @@ impl AxumServer {
 
         let server_instance = Self {
             shutdown_tx: Arc::new(tokio::sync::Mutex::new(Some(shutdown_tx))),
-            custom_mapping: custom_mapping_state.clone(),
+            model_router: model_router_state.clone(),
             proxy_state,
             upstream: state.upstream.clone(),
             security_state,
@@ -758,6 +887,7 @@ impl AxumServer {
                     }
                     _ = &mut shutdown_rx => {
                         tracing::info!("反代服务器停止监听");
+                        batch_worker.abort();
                         break;
                     }
                 }
@@ -780,6 +910,42 @@ impl AxumServer {
     }
 }
 
+/// 批处理 worker 使用的内部 Router
+///
+/// 与对外的 proxy_routes 共用 handler 和 auth -> monitor -> response_cache 中间件，
+/// 不经过 ip_filter 与服务状态层 (worker 自行检查运行状态)。
+fn batch_dispatch_router(state: &AppState, max_body_size: usize) -> Router {
+    use crate::proxy::handlers;
+    use crate::proxy::middleware::{auth_middleware, monitor_middleware, response_cache_middleware};
+
+    Router::new()
+        .route("/v1/messages", post(handlers::claude::handle_messages))
+        .route(
+            "/v1/chat/completions",
+            post(handlers::openai::handle_chat_completions),
+        )
+        .route("/v1/completions", post(handlers::openai::handle_completions))
+        .route("/v1/responses", post(handlers::openai::handle_responses))
+        .route(
+            "/v1/embeddings",
+            post(handlers::embeddings::handle_openai_embeddings),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            response_cache_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            monitor_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            auth_middleware,
+        ))
+        .layer(DefaultBodyLimit::max(max_body_size))
+        .with_state(state.clone())
+}
+
 // ===== API 处理器 (旧代码已移除，由 src/proxy/handlers/* 接管) =====
 
 /// 健康检查处理器
@@ -1252,11 +1418,81 @@ struct SaveConfigWrapper {
     config: AppConfig,
 }
 
+#[derive(Deserialize)]
+struct CreateBackupRequest {
+    #[serde(default)]
+    sections: Option<Vec<crate::modules::backup::BackupSection>>,
+    #[serde(default)]
+    passphrase: Option<String>,
+}
+
+async fn admin_create_backup(
+    Json(payload): Json<CreateBackupRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let archive = crate::modules::backup::create_backup(
+        &payload.sections.unwrap_or_default(),
+        payload.passphrase.as_deref(),
+    )
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+    Ok(Json(archive))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestoreBackupRequest {
+    content: String,
+    #[serde(default)]
+    sections: Option<Vec<crate::modules::backup::BackupSection>>,
+    #[serde(default)]
+    accounts: Option<Vec<String>>,
+    #[serde(default)]
+    dry_run: bool,
+    #[serde(default)]
+    passphrase: Option<String>,
+}
+
+async fn admin_restore_backup(
+    State(state): State<AppState>,
+    Json(payload): Json<RestoreBackupRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let options = crate::modules::backup::RestoreOptions {
+        sections: payload.sections,
+        accounts: payload.accounts,
+        dry_run: payload.dry_run,
+        passphrase: payload.passphrase,
+    };
+    let report = crate::modules::backup::restore_backup(&payload.content, &options)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    if report.dry_run {
+        return Ok(Json(report));
+    }
+
+    // 配置被恢复时复用 admin_save_config 的热更新流程
+    if report.changed(crate::modules::backup::BackupSection::Config) {
+        let config = config::load_app_config().map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: e }),
+            )
+        })?;
+        admin_save_config(State(state.clone()), Json(SaveConfigWrapper { config })).await?;
+    }
+    let _ = state.token_manager.reload_all_accounts().await;
+    Ok(Json(report))
+}
+
 async fn admin_save_config(
     State(state): State<AppState>,
     Json(payload): Json<SaveConfigWrapper>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let new_config = payload.config;
+    // [NEW] 保存前的配置，用于审计日志中的字段 diff
+    let previous = config::load_app_config().ok();
     // 1. 持久化
     config::save_app_config(&new_config).map_err(|e| {
         (
@@ -1273,10 +1509,10 @@ async fn admin_save_config(
     // 或者直接操作 AppState 里的各状态。
     // 在本重构中，各个状态已经在 AppState 中了。
 
-    // 更新模型映射
+    // 更新模型路由规则
     {
-        let mut mapping = state.custom_mapping.write().await;
-        *mapping = new_config.clone().proxy.custom_mapping;
+        let mut router = state.model_router.write().await;
+        *router = crate::proxy::common::routing_rules::ModelRouter::from_config(&new_config.proxy);
     }
 
     // 更新上游代理
@@ -1309,7 +1545,21 @@ async fn admin_save_config(
         *pool = new_config.clone().proxy.proxy_pool;
     }
 
-    Ok(StatusCode::OK)
+    // 更新响应缓存与费用核算配置
+    crate::proxy::update_response_cache_config(new_config.proxy.response_cache.clone());
+    crate::proxy::update_cost_config(new_config.proxy.cost.clone());
+    crate::proxy::update_providers_config(new_config.proxy.providers.clone());
+    crate::proxy::update_structured_output_config(new_config.proxy.structured_output.clone());
+    crate::proxy::update_batch_config(new_config.proxy.batch.clone());
+    crate::proxy::update_files_config(new_config.proxy.files.clone());
+
+    let diff = previous
+        .map(|prev| crate::modules::audit::config_diff_details(&prev, &new_config))
+        .unwrap_or(serde_json::Value::Null);
+    Ok((
+        StatusCode::OK,
+        axum::Extension(crate::proxy::middleware::audit::AuditDetails(diff)),
+    ))
 }
 
 // [FIX Web Mode] Get proxy pool config
@@ -1451,8 +1701,8 @@ async fn admin_update_model_mapping(
 
     // 1. 更新内存状态 (热更新)
     {
-        let mut mapping = state.custom_mapping.write().await;
-        *mapping = config.custom_mapping.clone();
+        let mut router = state.model_router.write().await;
+        *router = crate::proxy::common::routing_rules::ModelRouter::from_config(&config);
     }
 
     // 2. 持久化到硬盘 (修复 #1149)
@@ -1465,6 +1715,8 @@ async fn admin_update_model_mapping(
     })?;
 
     app_config.proxy.custom_mapping = config.custom_mapping;
+    app_config.proxy.model_routing_rules = config.model_routing_rules;
+    app_config.proxy.model_fallback_chains = config.model_fallback_chains;
 
     crate::modules::config::save_app_config(&app_config).map_err(|e| {
         (
@@ -1477,6 +1729,76 @@ async fn admin_update_model_mapping(
     Ok(StatusCode::OK)
 }
 
+/// 路由规则调试请求：可直接给出上下文字段，也可附带一个样例请求体自动检测特征
+#[derive(Deserialize)]
+struct ExplainRouteRequest {
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    protocol: Option<String>,
+    #[serde(default)]
+    user_token_id: Option<String>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    user_agent: Option<String>,
+    #[serde(default)]
+    has_tools: Option<bool>,
+    #[serde(default)]
+    has_images: Option<bool>,
+    #[serde(default)]
+    has_thinking: Option<bool>,
+    /// 样例请求体 (Claude / OpenAI / Gemini 格式)
+    #[serde(default)]
+    request: Option<serde_json::Value>,
+}
+
+async fn admin_explain_model_route(
+    State(state): State<AppState>,
+    Json(payload): Json<ExplainRouteRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    use crate::proxy::common::routing_rules::RouteContext;
+
+    let model = payload
+        .model
+        .clone()
+        .or_else(|| {
+            payload
+                .request
+                .as_ref()
+                .and_then(|r| r.get("model"))
+                .and_then(|m| m.as_str())
+                .map(|m| m.to_string())
+        })
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: "`model` is required (directly or in `request`)".to_string(),
+                }),
+            )
+        })?;
+
+    let mut ctx = RouteContext {
+        model,
+        protocol: payload.protocol,
+        user_token_id: payload.user_token_id,
+        username: payload.username,
+        user_agent: payload.user_agent,
+        ..Default::default()
+    };
+    if let Some(body) = payload.request.as_ref() {
+        ctx = ctx.with_body_features(body);
+    }
+    ctx.has_tools = payload.has_tools.unwrap_or(ctx.has_tools);
+    ctx.has_images = payload.has_images.unwrap_or(ctx.has_images);
+    ctx.has_thinking = payload.has_thinking.unwrap_or(ctx.has_thinking);
+    ctx.zai_enabled = state.zai.read().await.enabled;
+
+    let explanation = state.model_router.read().await.explain(&ctx);
+    Ok(Json(explanation))
+}
+
 async fn admin_generate_api_key() -> impl IntoResponse {
     let new_key = format!("sk-{}", uuid::Uuid::new_v4().to_string().replace("-", ""));
     Json(new_key)
@@ -1494,136 +1816,44 @@ async fn admin_clear_all_rate_limits(State(state): State<AppState>) -> impl Into
     StatusCode::OK
 }
 
-async fn admin_clear_rate_limit(
-    State(state): State<AppState>,
-    Path(account_id): Path<String>,
-) -> impl IntoResponse {
-    let cleared = state.token_manager.clear_rate_limit(&account_id);
-    if cleared {
-        logger::log_info(&format!("[API] 已清除账号 {} 的限流记录", account_id));
-        StatusCode::OK
-    } else {
-        StatusCode::NOT_FOUND
-    }
-}
-
-async fn admin_get_preferred_account(State(state): State<AppState>) -> impl IntoResponse {
-    let pref = state.token_manager.get_preferred_account().await;
-    Json(pref)
-}
-
-#[derive(Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct SetPreferredAccountRequest {
-    account_id: Option<String>,
-}
-
-async fn admin_set_preferred_account(
-    State(state): State<AppState>,
-    Json(payload): Json<SetPreferredAccountRequest>,
-) -> impl IntoResponse {
-    state
-        .token_manager
-        .set_preferred_account(payload.account_id)
-        .await;
-    StatusCode::OK
+async fn admin_get_provider_statuses() -> impl IntoResponse {
+    Json(crate::proxy::providers::registry::get_provider_statuses())
 }
 
-async fn admin_fetch_zai_models(
-    Path(_id): Path<String>,
-    Json(payload): Json<serde_json::Value>, // 复用前端传来的参数
+async fn admin_get_response_cache_stats(
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    // 这里简单实现，如果需要更复杂的抓取逻辑，可以调用 zai 模块
-    // 目前前端 fetch_zai_models 本质上也是一个工具函数，
-    // 我们可以在后端通过 reqwest 代理抓取。
-    let zai_config = payload.get("zai").ok_or_else(|| {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Missing zai config".to_string(),
-            }),
-        )
-    })?;
-
-    let api_key = zai_config
-        .get("api_key")
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-    let base_url = zai_config
-        .get("base_url")
-        .and_then(|v| v.as_str())
-        .unwrap_or("https://api.z.ai");
-
-    // 尝试从 z.ai 获取模型
-    let client = reqwest::Client::new();
-    let resp = client
-        .get(format!("{}/v1/models", base_url))
-        .header("Authorization", format!("Bearer {}", api_key))
-        .send()
-        .await
-        .map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: e.to_string(),
-                }),
-            )
-        })?;
-
-    let data: serde_json::Value = resp.json().await.map_err(|e| {
-        (
+    match tokio::task::spawn_blocking(crate::modules::response_cache_db::get_stats).await {
+        Ok(Ok(stats)) => Ok(Json(stats)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse {
                 error: e.to_string(),
             }),
-        )
-    })?;
-
-    // 提取模型 ID 列表
-    let models = data
-        .get("data")
-        .and_then(|v| v.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|m| {
-                    m.get("id")
-                        .and_then(|id| id.as_str().map(|s| s.to_string()))
-                })
-                .collect::<Vec<String>>()
-        })
-        .unwrap_or_default();
-
-    Ok(Json(models))
-}
-
-async fn admin_set_proxy_monitor_enabled(
-    State(state): State<AppState>,
-    Json(payload): Json<serde_json::Value>,
-) -> impl IntoResponse {
-    let enabled = payload
-        .get("enabled")
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false);
-
-    // [FIX #1269] 只有在状态真正改变时才记录日志并设置，避免重复触发导致的"重启"错觉
-    if state.monitor.is_enabled() != enabled {
-        state.monitor.set_enabled(enabled);
-        logger::log_info(&format!("[API] 监控状态已设置为: {}", enabled));
+        )),
     }
+}
 
-    StatusCode::OK
+#[derive(Deserialize)]
+struct WebhookDeliveriesQuery {
+    limit: Option<usize>,
+    offset: Option<usize>,
 }
 
-async fn admin_get_proxy_logs_count_filtered(
-    Query(params): Query<LogsRequest>,
+async fn admin_get_webhook_deliveries(
+    Query(q): Query<WebhookDeliveriesQuery>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let (limit, offset) = (q.limit.unwrap_or(100), q.offset.unwrap_or(0));
     let res = tokio::task::spawn_blocking(move || {
-        proxy_db::get_logs_count_filtered(&params.filter, params.errors_only)
+        crate::modules::webhook_db::list_deliveries(limit, offset)
     })
     .await;
 
     match res {
-        Ok(Ok(count)) => Ok(Json(count)),
+        Ok(Ok(deliveries)) => Ok(Json(deliveries)),
         Ok(Err(e)) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse { error: e }),
@@ -1637,25 +1867,596 @@ async fn admin_get_proxy_logs_count_filtered(
     }
 }
 
-async fn admin_clear_proxy_logs() -> impl IntoResponse {
-    let _ = tokio::task::spawn_blocking(|| {
-        if let Err(e) = proxy_db::clear_logs() {
-            logger::log_error(&format!("[API] 清除反代日志失败: {}", e));
-        }
-    })
-    .await;
-    logger::log_info("[API] 已清除所有反代日志");
-    StatusCode::OK
-}
-
-async fn admin_get_proxy_log_detail(
-    Path(log_id): Path<String>,
+async fn admin_get_audit_logs(
+    Query(q): Query<crate::modules::audit_db::AuditQuery>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
-    let res =
-        tokio::task::spawn_blocking(move || crate::modules::proxy_db::get_log_detail(&log_id))
-            .await;
-
-    match res {
+    match tokio::task::spawn_blocking(move || crate::modules::audit_db::query_page(&q)).await {
+        Ok(Ok(page)) => Ok(Json(page)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+#[derive(Deserialize)]
+struct AuditExportQuery {
+    format: Option<String>,
+    since: Option<i64>,
+    until: Option<i64>,
+    actor: Option<String>,
+    action: Option<String>,
+    target: Option<String>,
+    ip: Option<String>,
+    success: Option<bool>,
+}
+
+async fn admin_export_audit_logs(
+    Query(q): Query<AuditExportQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let format = q.format.unwrap_or_else(|| "json".to_string());
+    if format != "csv" && format != "json" {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!("Unsupported export format: {}", format),
+            }),
+        ));
+    }
+    let query = crate::modules::audit_db::AuditQuery {
+        since: q.since,
+        until: q.until,
+        actor: q.actor,
+        action: q.action,
+        target: q.target,
+        ip: q.ip,
+        success: q.success,
+        limit: None,
+        offset: None,
+    };
+    let fmt = format.clone();
+    let res =
+        tokio::task::spawn_blocking(move || crate::modules::audit_db::export(&query, &fmt)).await;
+
+    match res {
+        Ok(Ok(content)) => {
+            let content_type = if format == "csv" {
+                "text/csv; charset=utf-8"
+            } else {
+                "application/json"
+            };
+            let disposition = format!(
+                "attachment; filename=\"audit-log-{}.{}\"",
+                chrono::Utc::now().format("%Y%m%d-%H%M%S"),
+                format
+            );
+            Ok((
+                [
+                    (axum::http::header::CONTENT_TYPE, content_type.to_string()),
+                    (axum::http::header::CONTENT_DISPOSITION, disposition),
+                ],
+                content,
+            ))
+        }
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+async fn admin_verify_audit_log() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    match tokio::task::spawn_blocking(crate::modules::audit_db::verify_chain).await {
+        Ok(Ok(status)) => Ok(Json(status)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+// ===== [NEW] 管理员登录与账号管理 =====
+
+/// 登录失败后的固定延迟，拖慢在线暴力破解
+const ADMIN_LOGIN_FAILURE_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+#[derive(Deserialize)]
+struct AdminLoginRequest {
+    username: String,
+    password: String,
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer ").or(Some(s)))
+        .or_else(|| headers.get("x-api-key").and_then(|h| h.to_str().ok()))
+        .map(str::to_string)
+}
+
+async fn admin_login(
+    request: axum::extract::Request,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    let ip = crate::proxy::middleware::ip_filter::extract_client_ip(&request);
+    let user_agent = request
+        .headers()
+        .get(axum::http::header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let bytes = axum::body::to_bytes(request.into_body(), 64 * 1024)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+        })?;
+    let payload: AdminLoginRequest = serde_json::from_slice(&bytes).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: format!("Invalid login request: {}", e),
+            }),
+        )
+    })?;
+
+    let ttl_secs = config::load_app_config()
+        .map(|c| c.proxy.admin_session_ttl_hours)
+        .unwrap_or(12)
+        .saturating_mul(3600) as i64;
+    let result = tokio::task::spawn_blocking(move || {
+        crate::modules::admin_user_db::login(
+            &payload.username,
+            &payload.password,
+            ttl_secs,
+            ip.as_deref(),
+            user_agent.as_deref(),
+        )
+    })
+    .await
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+
+    match result {
+        Some(login) => {
+            // 让审计记录显示登录的用户名
+            let identity = crate::proxy::middleware::auth::AdminIdentity {
+                username: login.user.username.clone(),
+                role: login.user.role,
+                session_id: None,
+            };
+            let mut response = Json(login).into_response();
+            response.extensions_mut().insert(identity);
+            Ok(response)
+        }
+        None => {
+            tokio::time::sleep(ADMIN_LOGIN_FAILURE_DELAY).await;
+            Err((
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse {
+                    error: "Invalid username or password".to_string(),
+                }),
+            ))
+        }
+    }
+}
+
+async fn admin_logout(headers: HeaderMap) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    // admin_password / api_key 没有会话可注销，直接返回成功
+    let Some(token) = bearer_token(&headers)
+        .filter(|t| t.starts_with(crate::modules::admin_user_db::SESSION_TOKEN_PREFIX))
+    else {
+        return Ok(StatusCode::NO_CONTENT);
+    };
+    match tokio::task::spawn_blocking(move || crate::modules::admin_user_db::revoke_token(&token)).await {
+        Ok(Ok(_)) => Ok(StatusCode::NO_CONTENT),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+/// 当前登录身份；auth_mode=off 时没有鉴权层注入身份，按 Owner 返回
+async fn admin_auth_me(
+    identity: Option<axum::Extension<crate::proxy::middleware::auth::AdminIdentity>>,
+) -> impl IntoResponse {
+    let identity = identity
+        .map(|axum::Extension(identity)| identity)
+        .unwrap_or_else(crate::proxy::middleware::auth::AdminIdentity::legacy);
+    Json(serde_json::json!({
+        "username": identity.username,
+        "role": identity.role,
+        "session": identity.session_id.is_some(),
+    }))
+}
+
+fn admin_user_error(e: String) -> (StatusCode, Json<ErrorResponse>) {
+    let status = if e.contains("not found") {
+        StatusCode::NOT_FOUND
+    } else {
+        StatusCode::BAD_REQUEST
+    };
+    (status, Json(ErrorResponse { error: e }))
+}
+
+async fn admin_list_admin_users() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let users = crate::commands::admin_user::list_admin_users().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+    Ok(Json(users))
+}
+
+async fn admin_create_admin_user(
+    Json(payload): Json<crate::modules::admin_user_db::NewAdminUser>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let user = crate::commands::admin_user::create_admin_user(payload)
+        .await
+        .map_err(admin_user_error)?;
+    Ok(Json(user))
+}
+
+async fn admin_update_admin_user(
+    Path(id): Path<String>,
+    Json(payload): Json<crate::modules::admin_user_db::AdminUserUpdate>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let user = crate::commands::admin_user::update_admin_user(id, payload)
+        .await
+        .map_err(admin_user_error)?;
+    Ok(Json(user))
+}
+
+async fn admin_delete_admin_user(
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    crate::commands::admin_user::delete_admin_user(id)
+        .await
+        .map_err(admin_user_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn admin_list_admin_sessions() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let sessions = crate::commands::admin_user::list_admin_sessions().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+    Ok(Json(sessions))
+}
+
+async fn admin_revoke_admin_session(
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let revoked = crate::commands::admin_user::revoke_admin_session(id).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+    if !revoked {
+        return Err(admin_user_error("Session not found".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn admin_clear_webhook_deliveries(
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    match tokio::task::spawn_blocking(crate::modules::webhook_db::clear_deliveries).await {
+        Ok(Ok(removed)) => Ok(Json(removed)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+#[derive(Deserialize)]
+struct TestWebhookRequest {
+    endpoint: crate::modules::webhook::WebhookEndpoint,
+}
+
+async fn admin_test_webhook(
+    Json(payload): Json<TestWebhookRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    crate::modules::webhook::send_test(payload.endpoint)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))
+}
+
+async fn admin_clear_response_cache(
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    match tokio::task::spawn_blocking(crate::modules::response_cache_db::clear).await {
+        Ok(Ok(removed)) => {
+            logger::log_info(&format!("[API] 已清空响应缓存 ({} 条)", removed));
+            Ok(Json(removed))
+        }
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+async fn admin_get_batch_overview(
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    match tokio::task::spawn_blocking(|| crate::modules::batch_db::get_overview(50)).await {
+        Ok(Ok(overview)) => Ok(Json(overview)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+async fn admin_get_file_store_stats(
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    match tokio::task::spawn_blocking(crate::modules::file_db::get_stats).await {
+        Ok(Ok(stats)) => Ok(Json(stats)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+async fn admin_cancel_batch(
+    Path(batch_id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let id = batch_id.clone();
+    match tokio::task::spawn_blocking(move || crate::modules::batch_db::cancel_batch(&id)).await {
+        Ok(Ok(Some(batch))) => {
+            logger::log_info(&format!("[API] 已取消批次 {}", batch_id));
+            Ok(Json(batch))
+        }
+        Ok(Ok(None)) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: format!("Batch '{}' not found", batch_id),
+            }),
+        )),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+async fn admin_clear_rate_limit(
+    State(state): State<AppState>,
+    Path(account_id): Path<String>,
+) -> impl IntoResponse {
+    let cleared = state.token_manager.clear_rate_limit(&account_id);
+    if cleared {
+        logger::log_info(&format!("[API] 已清除账号 {} 的限流记录", account_id));
+        StatusCode::OK
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+async fn admin_get_preferred_account(State(state): State<AppState>) -> impl IntoResponse {
+    let pref = state.token_manager.get_preferred_account().await;
+    Json(pref)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetPreferredAccountRequest {
+    account_id: Option<String>,
+}
+
+async fn admin_set_preferred_account(
+    State(state): State<AppState>,
+    Json(payload): Json<SetPreferredAccountRequest>,
+) -> impl IntoResponse {
+    state
+        .token_manager
+        .set_preferred_account(payload.account_id)
+        .await;
+    StatusCode::OK
+}
+
+async fn admin_fetch_zai_models(
+    Path(_id): Path<String>,
+    Json(payload): Json<serde_json::Value>, // 复用前端传来的参数
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    // 这里简单实现，如果需要更复杂的抓取逻辑，可以调用 zai 模块
+    // 目前前端 fetch_zai_models 本质上也是一个工具函数，
+    // 我们可以在后端通过 reqwest 代理抓取。
+    let zai_config = payload.get("zai").ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse {
+                error: "Missing zai config".to_string(),
+            }),
+        )
+    })?;
+
+    let api_key = zai_config
+        .get("api_key")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let base_url = zai_config
+        .get("base_url")
+        .and_then(|v| v.as_str())
+        .unwrap_or("https://api.z.ai");
+
+    // 尝试从 z.ai 获取模型
+    let client = reqwest::Client::new();
+    let resp = client
+        .get(format!("{}/v1/models", base_url))
+        .header("Authorization", format!("Bearer {}", api_key))
+        .send()
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse {
+                    error: e.to_string(),
+                }),
+            )
+        })?;
+
+    let data: serde_json::Value = resp.json().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )
+    })?;
+
+    // 提取模型 ID 列表
+    let models = data
+        .get("data")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|m| {
+                    m.get("id")
+                        .and_then(|id| id.as_str().map(|s| s.to_string()))
+                })
+                .collect::<Vec<String>>()
+        })
+        .unwrap_or_default();
+
+    Ok(Json(models))
+}
+
+async fn admin_set_proxy_monitor_enabled(
+    State(state): State<AppState>,
+    Json(payload): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    let enabled = payload
+        .get("enabled")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    // [FIX #1269] 只有在状态真正改变时才记录日志并设置，避免重复触发导致的"重启"错觉
+    if state.monitor.is_enabled() != enabled {
+        state.monitor.set_enabled(enabled);
+        logger::log_info(&format!("[API] 监控状态已设置为: {}", enabled));
+    }
+
+    StatusCode::OK
+}
+
+async fn admin_get_proxy_logs_count_filtered(
+    Query(params): Query<LogsRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let res = tokio::task::spawn_blocking(move || {
+        proxy_db::get_logs_count_filtered(&params.filter, params.errors_only)
+    })
+    .await;
+
+    match res {
+        Ok(Ok(count)) => Ok(Json(count)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+async fn admin_clear_proxy_logs() -> impl IntoResponse {
+    let _ = tokio::task::spawn_blocking(|| {
+        if let Err(e) = proxy_db::clear_logs() {
+            logger::log_error(&format!("[API] 清除反代日志失败: {}", e));
+        }
+    })
+    .await;
+    logger::log_info("[API] 已清除所有反代日志");
+    StatusCode::OK
+}
+
+async fn admin_get_proxy_log_detail(
+    Path(log_id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let res =
+        tokio::task::spawn_blocking(move || crate::modules::proxy_db::get_log_detail(&log_id))
+            .await;
+
+    match res {
         Ok(Ok(log)) => Ok(Json(log)),
         Ok(Err(e)) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -1803,6 +2604,47 @@ async fn admin_update_user_token(
     Ok(StatusCode::OK)
 }
 
+async fn admin_get_user_token_quota_usage(
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let token_id = id.clone();
+    match tokio::task::spawn_blocking(move || {
+        crate::modules::user_token_db::get_token_quota_usage(&token_id)
+    })
+    .await
+    {
+        Ok(Ok(Some(usage))) => Ok(Json(usage)),
+        Ok(Ok(None)) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse {
+                error: format!("Token '{}' not found", id),
+            }),
+        )),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+async fn admin_reset_user_token_quota_usage(
+    Path(id): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    crate::commands::user_token::reset_user_token_quota_usage(id).await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
 async fn admin_should_check_updates() -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)>
 {
     let settings = crate::modules::update_checker::load_update_settings().map_err(|e| {
@@ -1987,6 +2829,90 @@ async fn admin_get_token_stats_summary(
     }
 }
 
+async fn admin_get_token_stats_by_group(
+    Query(p): Query<StatsPeriodQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let hours = p.hours.unwrap_or(168);
+    let res = tokio::task::spawn_blocking(move || token_stats::get_group_stats(hours)).await;
+
+    match res {
+        Ok(Ok(stats)) => Ok(Json(stats)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+async fn admin_get_token_stats_by_user_token(
+    Query(p): Query<StatsPeriodQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let hours = p.hours.unwrap_or(168);
+    let res = tokio::task::spawn_blocking(move || token_stats::get_user_token_stats(hours)).await;
+
+    match res {
+        Ok(Ok(stats)) => Ok(Json(stats)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+async fn admin_get_token_stats_cache_hits(
+    Query(p): Query<StatsPeriodQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let hours = p.hours.unwrap_or(168);
+    let res = tokio::task::spawn_blocking(move || token_stats::get_cache_hit_stats(hours)).await;
+
+    match res {
+        Ok(Ok(stats)) => Ok(Json(stats)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
+async fn admin_get_proxy_log_stats_by_group(
+    Query(p): Query<StatsPeriodQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let hours = p.hours.unwrap_or(168);
+    let res = tokio::task::spawn_blocking(move || proxy_db::get_group_log_stats(hours)).await;
+
+    match res {
+        Ok(Ok(stats)) => Ok(Json(stats)),
+        Ok(Err(e)) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
+        )),
+    }
+}
+
 async fn admin_get_token_stats_by_model(
     Query(p): Query<StatsPeriodQuery>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
@@ -2258,6 +3184,92 @@ async fn admin_fetch_account_quota(
     Ok(Json(quota))
 }
 
+#[derive(Deserialize)]
+struct UpdateAccountGroupsRequest {
+    #[serde(default)]
+    groups: Vec<String>,
+}
+
+async fn admin_update_account_groups(
+    Path(account_id): Path<String>,
+    Json(payload): Json<UpdateAccountGroupsRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let account = crate::commands::update_account_groups(account_id, payload.groups)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse { error: e }),
+            )
+        })?;
+    Ok(Json(account))
+}
+
+#[derive(Deserialize)]
+struct AccountExportCryptoRequest {
+    content: String,
+    passphrase: String,
+}
+
+async fn admin_seal_account_export(
+    Json(payload): Json<AccountExportCryptoRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let sealed = crate::modules::account_crypto::seal_export(&payload.content, &payload.passphrase)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    Ok(Json(sealed))
+}
+
+async fn admin_open_account_export(
+    Json(payload): Json<AccountExportCryptoRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let plain = crate::modules::account_crypto::open_export(&payload.content, &payload.passphrase)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    Ok(Json(plain))
+}
+
+async fn admin_get_account_encryption_status(
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let status = crate::modules::account_crypto::status().map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    Ok(Json(status))
+}
+
+#[derive(Deserialize)]
+struct UnlockAccountEncryptionRequest {
+    passphrase: String,
+}
+
+async fn admin_unlock_account_encryption(
+    State(state): State<AppState>,
+    Json(payload): Json<UnlockAccountEncryptionRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let status = crate::modules::account_crypto::unlock(&payload.passphrase)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(ErrorResponse { error: e })))?;
+    let _ = state.token_manager.reload_all_accounts().await;
+    Ok(Json(status))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RotateAccountEncryptionRequest {
+    new_passphrase: Option<String>,
+    new_key_file: Option<String>,
+    #[serde(default)]
+    disable: bool,
+}
+
+async fn admin_rotate_account_encryption_key(
+    Json(payload): Json<RotateAccountEncryptionRequest>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let status = crate::commands::rotate_account_encryption_key(
+        payload.new_passphrase,
+        payload.new_key_file,
+        Some(payload.disable),
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    Ok(Json(status))
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct ToggleProxyRequest {
@@ -1,6 +1,8 @@
+use crate::modules::session_state_db::{self, PersistOp, PersistedSignature, SessionStateWriter, SignatureLayer};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::{Mutex, OnceLock};
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 // Node.js proxy uses 2 hours TTL
 const SIGNATURE_TTL: Duration = Duration::from_secs(2 * 60 * 60);
@@ -33,6 +35,14 @@ impl<T> CacheEntry<T> {
         }
     }
 
+    /// 从持久化记录恢复，保留原始写入时间以延续 TTL
+    fn restored(data: T, created_at: i64) -> Self {
+        Self {
+            data,
+            timestamp: UNIX_EPOCH + Duration::from_secs(created_at.max(0) as u64),
+        }
+    }
+
     fn is_expired(&self) -> bool {
         self.timestamp.elapsed().unwrap_or(Duration::ZERO) > SIGNATURE_TTL
     }
@@ -58,6 +68,15 @@ pub struct SignatureCache {
     /// Value: The most recent valid thought signature for this session
     /// This prevents signature pollution between different conversations
     session_signatures: Mutex<HashMap<String, CacheEntry<SessionSignatureEntry>>>,
+
+    /// [NEW] 持久化队列：设置后变更会提交给后台线程写入 session_state.db (重启后恢复)
+    writer: Mutex<Option<SessionStateWriter>>,
+}
+
+fn to_unix_secs(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
 }
 
 impl SignatureCache {
@@ -66,6 +85,7 @@ impl SignatureCache {
             tool_signatures: Mutex::new(HashMap::new()),
             thinking_families: Mutex::new(HashMap::new()),
             session_signatures: Mutex::new(HashMap::new()),
+            writer: Mutex::new(None),
         }
     }
 
@@ -75,6 +95,101 @@ impl SignatureCache {
         INSTANCE.get_or_init(SignatureCache::new)
     }
 
+    // ===== Persistence (session_state.db) =====
+
+    /// 开启持久化，之后的缓存变更会异步写入 `data_dir/session_state.db`
+    pub fn enable_persistence(&self, data_dir: PathBuf) {
+        if let Ok(mut writer) = self.writer.lock() {
+            *writer = Some(SessionStateWriter::for_dir(&data_dir));
+        }
+    }
+
+    fn writer(&self) -> Option<SessionStateWriter> {
+        self.writer.lock().ok().and_then(|writer| writer.clone())
+    }
+
+    /// 只向后台写入线程提交变更，不在请求路径 (以及缓存锁内) 做磁盘 I/O
+    fn persist(&self, op: PersistOp) {
+        if let Some(writer) = self.writer() {
+            writer.send(op);
+        }
+    }
+
+    fn persist_entry(&self, layer: SignatureLayer, key: &str, value: &str, message_count: usize, timestamp: SystemTime) {
+        if self.writer().is_none() {
+            return;
+        }
+        let record = PersistedSignature {
+            layer,
+            key: key.to_string(),
+            value: value.to_string(),
+            message_count: message_count as i64,
+            created_at: to_unix_secs(timestamp),
+        };
+        self.persist(PersistOp::SaveSignature(record));
+    }
+
+    /// 内存缓存清理时一并删除磁盘上的过期记录
+    fn prune_persisted(&self) {
+        self.persist(PersistOp::DeleteExpiredSignatures {
+            now: to_unix_secs(SystemTime::now()),
+            ttl_secs: SIGNATURE_TTL.as_secs() as i64,
+        });
+    }
+
+    /// 从 session_state.db 恢复三层签名缓存 (保留原始写入时间，过期记录在加载时删除)
+    ///
+    /// 需先调用 `enable_persistence`。内存中已存在且更新的条目保持不变。返回恢复的条目数。
+    pub fn restore_persisted(&self) -> Result<usize, String> {
+        let Some(writer) = self.writer() else {
+            return Ok(0);
+        };
+        // 先等待队列中的写入落盘，避免读到旧数据
+        writer.flush();
+        let records = session_state_db::load_signatures(writer.data_dir(), SIGNATURE_TTL.as_secs() as i64)?;
+        let mut restored = 0;
+
+        for record in records {
+            let inserted = match record.layer {
+                SignatureLayer::Tool => {
+                    let mut cache = self.tool_signatures.lock().map_err(|e| e.to_string())?;
+                    Self::restore_into(&mut cache, record.key, CacheEntry::restored(record.value, record.created_at))
+                }
+                SignatureLayer::Family => {
+                    let mut cache = self.thinking_families.lock().map_err(|e| e.to_string())?;
+                    Self::restore_into(&mut cache, record.key, CacheEntry::restored(record.value, record.created_at))
+                }
+                SignatureLayer::Session => {
+                    let mut cache = self.session_signatures.lock().map_err(|e| e.to_string())?;
+                    let entry = SessionSignatureEntry {
+                        signature: record.value,
+                        message_count: record.message_count.max(0) as usize,
+                    };
+                    Self::restore_into(&mut cache, record.key, CacheEntry::restored(entry, record.created_at))
+                }
+            };
+            if inserted {
+                restored += 1;
+            }
+        }
+
+        if restored > 0 {
+            tracing::info!("[SignatureCache] Restored {} persisted signature(s)", restored);
+        }
+        Ok(restored)
+    }
+
+    fn restore_into<T>(cache: &mut HashMap<String, CacheEntry<T>>, key: String, entry: CacheEntry<T>) -> bool {
+        if entry.is_expired() {
+            return false;
+        }
+        if matches!(cache.get(&key), Some(existing) if existing.timestamp >= entry.timestamp) {
+            return false;
+        }
+        cache.insert(key, entry);
+        true
+    }
+
     /// Store a tool call signature
     pub fn cache_tool_signature(&self, tool_use_id: &str, signature: String) {
         if signature.len() < MIN_SIGNATURE_LENGTH {
@@ -83,7 +198,9 @@ impl SignatureCache {
         
         if let Ok(mut cache) = self.tool_signatures.lock() {
             tracing::debug!("[SignatureCache] Caching tool signature for id: {}", tool_use_id);
-            cache.insert(tool_use_id.to_string(), CacheEntry::new(signature));
+            let entry = CacheEntry::new(signature);
+            self.persist_entry(SignatureLayer::Tool, tool_use_id, &entry.data, 0, entry.timestamp);
+            cache.insert(tool_use_id.to_string(), entry);
             
             // Clean up expired entries when limit is reached
             if cache.len() > TOOL_CACHE_LIMIT {
@@ -92,6 +209,7 @@ impl SignatureCache {
                 let after = cache.len();
                 if before != after {
                     tracing::debug!("[SignatureCache] Tool cache cleanup: {} -> {} entries", before, after);
+                    self.prune_persisted();
                 }
             }
         }
@@ -118,7 +236,9 @@ impl SignatureCache {
 
         if let Ok(mut cache) = self.thinking_families.lock() {
             tracing::debug!("[SignatureCache] Caching thinking family for sig (len={}): {}", signature.len(), family);
-            cache.insert(signature, CacheEntry::new(family));
+            let entry = CacheEntry::new(family);
+            self.persist_entry(SignatureLayer::Family, &signature, &entry.data, 0, entry.timestamp);
+            cache.insert(signature, entry);
             
             if cache.len() > FAMILY_CACHE_LIMIT {
                 let before = cache.len();
@@ -126,6 +246,7 @@ impl SignatureCache {
                 let after = cache.len();
                 if before != after {
                     tracing::debug!("[SignatureCache] Family cache cleanup: {} -> {} entries", before, after);
+                    self.prune_persisted();
                 }
             }
         }
@@ -193,13 +314,18 @@ impl SignatureCache {
                     message_count,
                     signature.len()
                 );
-                cache.insert(
-                    session_id.to_string(), 
-                    CacheEntry::new(SessionSignatureEntry { 
-                        signature, 
-                        message_count 
-                    })
+                let entry = CacheEntry::new(SessionSignatureEntry { 
+                    signature, 
+                    message_count 
+                });
+                self.persist_entry(
+                    SignatureLayer::Session,
+                    session_id,
+                    &entry.data.signature,
+                    message_count,
+                    entry.timestamp,
                 );
+                cache.insert(session_id.to_string(), entry);
             }
 
             // Cleanup when limit is reached (Session cache has largest limit)
@@ -214,6 +340,7 @@ impl SignatureCache {
                         after,
                         SESSION_CACHE_LIMIT
                     );
+                    self.prune_persisted();
                 }
             }
         }
@@ -247,6 +374,7 @@ impl SignatureCache {
                 tracing::debug!("[SignatureCache] Deleted session signature for: {}", session_id);
             }
         }
+        self.persist(PersistOp::DeleteSignature(SignatureLayer::Session, session_id.to_string()));
     }
 
     /// Clear all caches (for testing or manual reset)
@@ -261,6 +389,7 @@ impl SignatureCache {
         if let Ok(mut cache) = self.session_signatures.lock() {
             cache.clear();
         }
+        self.persist(PersistOp::ClearSignatures);
     }
 }
 
@@ -329,6 +458,27 @@ mod tests {
         assert!(cache.get_session_signature("sid-other").is_none());
     }
 
+    #[test]
+    fn test_restore_keeps_original_timestamp() {
+        let now = to_unix_secs(SystemTime::now());
+        let mut map: HashMap<String, CacheEntry<String>> = HashMap::new();
+
+        // 仍在 TTL 内的记录恢复后保留原始写入时间
+        let fresh = CacheEntry::restored("sig".to_string(), now - 60);
+        assert!(SignatureCache::restore_into(&mut map, "tool_1".to_string(), fresh));
+        assert_eq!(to_unix_secs(map["tool_1"].timestamp), now - 60);
+
+        // 已过期的记录不会恢复
+        let stale = CacheEntry::restored("old".to_string(), now - SIGNATURE_TTL.as_secs() as i64 - 1);
+        assert!(!SignatureCache::restore_into(&mut map, "tool_2".to_string(), stale));
+
+        // 内存中更新的条目不会被旧记录覆盖
+        map.insert("tool_3".to_string(), CacheEntry::new("live".to_string()));
+        let older = CacheEntry::restored("older".to_string(), now - 120);
+        assert!(!SignatureCache::restore_into(&mut map, "tool_3".to_string(), older));
+        assert_eq!(map["tool_3"].data, "live");
+    }
+
     #[test]
     fn test_clear_all_caches() {
         let cache = SignatureCache::new();
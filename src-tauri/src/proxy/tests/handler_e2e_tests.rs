@@ -0,0 +1,492 @@
+//! Handler End-to-End Tests
+//! 通过本地 mock v1internal 服务验证完整请求链路：
+//! handler → 协议转换 → TokenManager → UpstreamClient → 响应转换
+//!
+//! 覆盖：文本/思维链签名/工具调用转换、429 retryDelay 重试、403 VALIDATION_REQUIRED 账号轮换
+
+use super::mock_upstream::{
+    function_call_part, text_part, thinking_part, MockProvider, MockReply, MockUpstream,
+    TEST_SIGNATURE,
+};
+use crate::proxy::handlers;
+use crate::proxy::server::AppState;
+use crate::proxy::TokenManager;
+use axum::{
+    body::Bytes,
+    http::{HeaderMap, Request, StatusCode},
+    routing::post,
+    Router,
+};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+
+struct Harness {
+    mock: MockUpstream,
+    state: AppState,
+    data_dir: PathBuf,
+}
+
+impl Harness {
+    /// 创建临时账号目录 (每个账号: atk-{id} / pid-{id} / {id}@test.com) 并启动 mock
+    async fn new(account_ids: &[&str]) -> Self {
+        let data_dir = std::env::temp_dir().join(format!(
+            "antigravity-handler-e2e-{}",
+            uuid::Uuid::new_v4()
+        ));
+        let accounts_dir = data_dir.join("accounts");
+        std::fs::create_dir_all(&accounts_dir).unwrap();
+
+        let now = chrono::Utc::now().timestamp();
+        for id in account_ids {
+            let account = json!({
+                "id": id,
+                "email": format!("{}@test.com", id),
+                "token": {
+                    "access_token": format!("atk-{}", id),
+                    "refresh_token": format!("rtk-{}", id),
+                    "expires_in": 3600,
+                    "expiry_timestamp": now + 3600,
+                    "project_id": format!("pid-{}", id)
+                },
+                "disabled": false,
+                "proxy_disabled": false,
+                "created_at": now,
+                "last_used": now
+            });
+            std::fs::write(
+                accounts_dir.join(format!("{}.json", id)),
+                serde_json::to_string_pretty(&account).unwrap(),
+            )
+            .unwrap();
+        }
+
+        let token_manager = Arc::new(TokenManager::new(data_dir.clone()));
+        token_manager.load_accounts().await.unwrap();
+
+        let mock = MockUpstream::start().await;
+        let proxy_config = crate::proxy::config::ProxyConfig::default();
+        let proxy_pool_state = Arc::new(RwLock::new(proxy_config.proxy_pool.clone()));
+        let integration = crate::modules::integration::SystemManager::Headless;
+
+        let state = AppState {
+            token_manager,
+            model_router: Arc::new(RwLock::new(
+                crate::proxy::common::routing_rules::ModelRouter::from_config(&proxy_config),
+            )),
+            request_timeout: 30,
+            thought_signature_map: Arc::new(tokio::sync::Mutex::new(
+                std::collections::HashMap::new(),
+            )),
+            upstream_proxy: Arc::new(RwLock::new(proxy_config.upstream_proxy.clone())),
+            upstream: Arc::new(
+                crate::proxy::upstream::client::UpstreamClient::new(None, None)
+                    .with_base_urls(vec![mock.base_url()]),
+            ),
+            zai: Arc::new(RwLock::new(proxy_config.zai.clone())),
+            provider_rr: Arc::new(AtomicUsize::new(0)),
+            zai_vision_mcp: Arc::new(crate::proxy::zai_vision_mcp::ZaiVisionMcpState::new()),
+            monitor: Arc::new(crate::proxy::monitor::ProxyMonitor::new(100, None)),
+            experimental: Arc::new(RwLock::new(proxy_config.experimental.clone())),
+            debug_logging: Arc::new(RwLock::new(proxy_config.debug_logging.clone())),
+            switching: Arc::new(RwLock::new(false)),
+            integration: integration.clone(),
+            account_service: Arc::new(crate::modules::account_service::AccountService::new(
+                integration,
+            )),
+            security: Arc::new(RwLock::new(
+                crate::proxy::ProxySecurityConfig::from_proxy_config(&proxy_config),
+            )),
+            cloudflared_state: Arc::new(crate::commands::cloudflared::CloudflaredState::new()),
+            is_running: Arc::new(RwLock::new(true)),
+            port: proxy_config.port,
+            proxy_pool_state: proxy_pool_state.clone(),
+            proxy_pool_manager: Arc::new(crate::proxy::proxy_pool::ProxyPoolManager::new(
+                proxy_pool_state,
+            )),
+        };
+
+        Self {
+            mock,
+            state,
+            data_dir,
+        }
+    }
+
+    /// 与 server.rs 相同的 handler，但不挂载鉴权/监控中间件
+    fn router(&self) -> Router {
+        Router::new()
+            .route("/v1/messages", post(handlers::claude::handle_messages))
+            .route(
+                "/v1/chat/completions",
+                post(handlers::openai::handle_chat_completions),
+            )
+            .route("/v1/completions", post(handlers::openai::handle_completions))
+            .route("/v1/responses", post(handlers::openai::handle_responses))
+            .route("/v1beta/models/:model", post(handlers::gemini::handle_generate))
+            .with_state(self.state.clone())
+    }
+
+    async fn post(&self, path: &str, body: Value) -> (StatusCode, HeaderMap, Bytes) {
+        let request = Request::builder()
+            .method("POST")
+            .uri(path)
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(body.to_string()))
+            .unwrap();
+        let response = self.router().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, headers, bytes)
+    }
+
+    async fn post_json(&self, path: &str, body: Value) -> (StatusCode, HeaderMap, Value) {
+        let (status, headers, bytes) = self.post(path, body).await;
+        let json = serde_json::from_slice(&bytes).unwrap_or_else(|_| {
+            panic!(
+                "non-JSON response ({}): {}",
+                status,
+                String::from_utf8_lossy(&bytes)
+            )
+        });
+        (status, headers, json)
+    }
+
+    fn account_file(&self, id: &str) -> Value {
+        let path = self.data_dir.join("accounts").join(format!("{}.json", id));
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.data_dir);
+    }
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> &'a str {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+}
+
+/// atk-{id} → {id}@test.com
+fn email_for_token(access_token: &str) -> String {
+    format!("{}@test.com", access_token.trim_start_matches("atk-"))
+}
+
+fn claude_request(model: &str, text: &str) -> Value {
+    json!({
+        "model": model,
+        "max_tokens": 1024,
+        "messages": [{ "role": "user", "content": text }]
+    })
+}
+
+// ============================================================================
+// Claude (/v1/messages)
+// ============================================================================
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_claude_non_stream_text_conversion() {
+    let h = Harness::new(&["acc1"]).await;
+    h.mock.push(MockReply::stream(vec![
+        vec![text_part("Hello, ")],
+        vec![text_part("world!")],
+    ]));
+
+    let (status, headers, body) = h
+        .post_json("/v1/messages", claude_request("claude-sonnet-4-5", "Hello from e2e"))
+        .await;
+
+    assert_eq!(status, StatusCode::OK, "body: {}", body);
+    assert_eq!(header(&headers, "X-Account-Email"), "acc1@test.com");
+    assert_eq!(body["type"], "message");
+    assert_eq!(body["role"], "assistant");
+    assert_eq!(body["content"][0]["type"], "text");
+    assert_eq!(body["content"][0]["text"], "Hello, world!");
+    assert_eq!(body["stop_reason"], "end_turn");
+
+    // 非流式请求内部转为 SSE，且请求体为 v1internal 包装格式
+    let requests = h.mock.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "streamGenerateContent");
+    assert_eq!(requests[0].query.as_deref(), Some("alt=sse"));
+    assert_eq!(requests[0].access_token, "atk-acc1");
+    assert_eq!(requests[0].body["project"], "pid-acc1");
+    assert!(requests[0].body["model"].is_string());
+    assert!(requests[0].body["request"]["contents"]
+        .to_string()
+        .contains("Hello from e2e"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_claude_stream_thinking_signature() {
+    let h = Harness::new(&["acc1"]).await;
+    h.mock.push(MockReply::stream(vec![
+        vec![thinking_part("Let me think.", TEST_SIGNATURE)],
+        vec![text_part("The answer is 4.")],
+    ]));
+
+    let mut request = claude_request("claude-sonnet-4-5-thinking", "What is 2+2?");
+    request["stream"] = json!(true);
+    request["thinking"] = json!({ "type": "enabled", "budget_tokens": 512 });
+
+    let (status, headers, bytes) = h.post("/v1/messages", request).await;
+    let sse = String::from_utf8_lossy(&bytes);
+
+    assert_eq!(status, StatusCode::OK, "body: {}", sse);
+    assert_eq!(header(&headers, "content-type"), "text/event-stream");
+    assert!(sse.contains("event: message_start"));
+    assert!(sse.contains("thinking_delta"));
+    assert!(sse.contains("Let me think."));
+    assert!(sse.contains("signature_delta"));
+    assert!(sse.contains(TEST_SIGNATURE));
+    assert!(sse.contains("The answer is 4."));
+    assert!(sse.contains("event: message_stop"));
+
+    // 思维链在文本之前输出
+    let thinking_pos = sse.find("thinking_delta").unwrap();
+    let text_pos = sse.find("The answer is 4.").unwrap();
+    assert!(thinking_pos < text_pos);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_claude_function_call_to_tool_use() {
+    let h = Harness::new(&["acc1"]).await;
+    h.mock.push(MockReply::stream(vec![vec![function_call_part(
+        "get_weather",
+        json!({ "city": "Paris" }),
+        Some(TEST_SIGNATURE),
+    )]]));
+
+    let mut request = claude_request("claude-sonnet-4-5", "Weather in Paris?");
+    request["tools"] = json!([{
+        "name": "get_weather",
+        "description": "Get the current weather for a city",
+        "input_schema": {
+            "type": "object",
+            "properties": { "city": { "type": "string" } },
+            "required": ["city"]
+        }
+    }]);
+
+    let (status, _, body) = h.post_json("/v1/messages", request).await;
+
+    assert_eq!(status, StatusCode::OK, "body: {}", body);
+    let tool_use = body["content"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|b| b["type"] == "tool_use")
+        .unwrap_or_else(|| panic!("no tool_use block: {}", body));
+    assert_eq!(tool_use["name"], "get_weather");
+    assert_eq!(tool_use["input"]["city"], "Paris");
+    assert!(tool_use["id"].as_str().map_or(false, |id| !id.is_empty()));
+    assert_eq!(body["stop_reason"], "tool_use");
+
+    // 工具声明已转换为 Gemini functionDeclarations
+    let requests = h.mock.requests();
+    assert!(requests[0].body["request"]["tools"]
+        .to_string()
+        .contains("get_weather"));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_claude_429_retry_delay_rotates_account() {
+    let h = Harness::new(&["acc1", "acc2"]).await;
+    h.mock.push(MockReply::rate_limited("0.1s"));
+    h.mock.push(MockReply::text("recovered"));
+
+    let started = std::time::Instant::now();
+    let (status, headers, body) = h
+        .post_json("/v1/messages", claude_request("claude-sonnet-4-5", "Hello"))
+        .await;
+
+    assert_eq!(status, StatusCode::OK, "body: {}", body);
+    assert_eq!(body["content"][0]["text"], "recovered");
+
+    let requests = h.mock.requests();
+    assert_eq!(requests.len(), 2);
+    assert_ne!(
+        requests[0].access_token, requests[1].access_token,
+        "retry after 429 should rotate to another account"
+    );
+    assert_eq!(
+        header(&headers, "X-Account-Email"),
+        email_for_token(&requests[1].access_token)
+    );
+
+    // retryDelay (100ms) + 200ms 缓冲；不应退化为 5s 的默认退避
+    let elapsed = started.elapsed();
+    assert!(elapsed >= std::time::Duration::from_millis(300), "{:?}", elapsed);
+    assert!(elapsed < std::time::Duration::from_secs(5), "{:?}", elapsed);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_claude_403_validation_required_blocks_account() {
+    let h = Harness::new(&["acc1", "acc2"]).await;
+    h.mock.push(MockReply::validation_required());
+    h.mock.push(MockReply::text("ok"));
+
+    let (status, headers, body) = h
+        .post_json("/v1/messages", claude_request("claude-sonnet-4-5", "Hello"))
+        .await;
+
+    assert_eq!(status, StatusCode::OK, "body: {}", body);
+
+    let requests = h.mock.requests();
+    assert_eq!(requests.len(), 2);
+    let blocked_id = requests[0].access_token.trim_start_matches("atk-").to_string();
+    assert_ne!(requests[0].access_token, requests[1].access_token);
+    assert_eq!(
+        header(&headers, "X-Account-Email"),
+        email_for_token(&requests[1].access_token)
+    );
+
+    // 被验证拦截的账号写入磁盘，并从内存池移除
+    let account = h.account_file(&blocked_id);
+    assert_eq!(account["validation_blocked"], true);
+    assert!(account["validation_blocked_until"].as_i64().unwrap() > chrono::Utc::now().timestamp());
+    assert_eq!(account["quota"]["is_forbidden"], true);
+    assert_eq!(h.state.token_manager.len(), 1);
+}
+
+// ============================================================================
+// OpenAI (/v1/chat/completions)
+// ============================================================================
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_openai_chat_completion_conversion() {
+    let h = Harness::new(&["acc1"]).await;
+    h.mock.push(MockReply::stream(vec![
+        vec![text_part("Bonjour")],
+        vec![text_part(" le monde")],
+    ]));
+
+    let (status, _, body) = h
+        .post_json(
+            "/v1/chat/completions",
+            json!({
+                "model": "gemini-3-flash",
+                "messages": [
+                    { "role": "system", "content": "Be brief." },
+                    { "role": "user", "content": "Say hello in French" }
+                ]
+            }),
+        )
+        .await;
+
+    assert_eq!(status, StatusCode::OK, "body: {}", body);
+    assert_eq!(body["object"], "chat.completion");
+    assert_eq!(body["choices"][0]["message"]["role"], "assistant");
+    assert_eq!(body["choices"][0]["message"]["content"], "Bonjour le monde");
+    assert_eq!(body["choices"][0]["finish_reason"], "stop");
+
+    let requests = h.mock.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].body["project"], "pid-acc1");
+    let upstream = requests[0].body["request"].to_string();
+    assert!(upstream.contains("Say hello in French"));
+    assert!(upstream.contains("Be brief."));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_openai_responses_and_completions_dispatch_to_provider() {
+    let h = Harness::new(&["acc1"]).await;
+    let provider = MockProvider::start().await;
+
+    // 追加而非覆盖全局上游列表，避免影响并行运行的其他测试
+    let mut providers: Vec<_> = crate::proxy::config::get_providers_config()
+        .into_iter()
+        .filter(|p| p.id != "e2e-provider")
+        .collect();
+    providers.push(
+        serde_json::from_value(json!({
+            "id": "e2e-provider",
+            "protocol": "openai",
+            "base_url": provider.base_url(),
+            "models": ["e2e-provider-*"],
+            "model_mapping": { "e2e-provider-*": "upstream-model" }
+        }))
+        .unwrap(),
+    );
+    crate::proxy::config::update_providers_config(providers);
+
+    let (status, _, body) = h
+        .post_json(
+            "/v1/responses",
+            json!({
+                "model": "e2e-provider-chat",
+                "input": "Hello provider",
+                "previous_response_id": "resp_upstream_1"
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "body: {}", body);
+    assert_eq!(body["object"], "mock.provider");
+
+    let (status, _, body) = h
+        .post_json(
+            "/v1/completions",
+            json!({ "model": "e2e-provider-legacy", "prompt": "Once upon a time" }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "body: {}", body);
+    assert_eq!(body["object"], "mock.provider");
+
+    // 原样转发到上游的同名接口，只改写模型名；Google 账号池未被调用
+    let requests = provider.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].path, "/v1/responses");
+    assert_eq!(requests[0].body["model"], "upstream-model");
+    assert_eq!(requests[0].body["input"], "Hello provider");
+    assert_eq!(requests[0].body["previous_response_id"], "resp_upstream_1");
+    assert_eq!(requests[1].path, "/v1/completions");
+    assert_eq!(requests[1].body["model"], "upstream-model");
+    assert_eq!(requests[1].body["prompt"], "Once upon a time");
+    assert!(requests[1].body.get("messages").is_none());
+    assert!(h.mock.requests().is_empty());
+}
+
+// ============================================================================
+// Gemini (/v1beta/models/:model)
+// ============================================================================
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_gemini_generate_content_unwraps_response() {
+    let h = Harness::new(&["acc1"]).await;
+    h.mock.push(MockReply::text("native gemini"));
+
+    let (status, _, body) = h
+        .post_json(
+            "/v1beta/models/gemini-3-flash:generateContent",
+            json!({
+                "contents": [{ "role": "user", "parts": [{ "text": "Hi Gemini" }] }]
+            }),
+        )
+        .await;
+
+    assert_eq!(status, StatusCode::OK, "body: {}", body);
+    // 客户端收到的是标准 Gemini 响应，而不是 v1internal 的 {"response": ...} 包装
+    assert!(body.get("response").is_none(), "body: {}", body);
+    assert_eq!(
+        body["candidates"][0]["content"]["parts"][0]["text"],
+        "native gemini"
+    );
+
+    let requests = h.mock.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].access_token, "atk-acc1");
+    assert_eq!(requests[0].body["project"], "pid-acc1");
+    assert!(requests[0].body["request"]["contents"]
+        .to_string()
+        .contains("Hi Gemini"));
+}
@@ -0,0 +1,320 @@
+//! In-process mock of the cloudcode-pa `v1internal` API
+//! 本地 v1internal 模拟服务：按脚本依次返回 SSE 片段或错误，并记录收到的请求
+//!
+//! 配合 `UpstreamClient::with_base_urls` 使用，handler 测试无需访问网络。
+
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::{header, HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Router,
+};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// 满足 Claude 签名最小长度 (50) 的测试签名，包含 `_` 以避免被当作 Base64 解码
+pub const TEST_SIGNATURE: &str =
+    "sig_mock_upstream_0123456789_abcdefghijklmnopqrstuvwxyz_ABCDEFGHIJKLMNOP";
+
+/// 单次上游响应脚本
+#[derive(Debug, Clone)]
+pub enum MockReply {
+    /// 成功响应：每个元素是一个 v1internal 片段 (`{"response": {...}}`)
+    /// streamGenerateContent 逐条输出 SSE，generateContent 返回最后一条
+    Generate(Vec<Value>),
+    /// 错误响应 (原样返回 body)
+    Error { status: u16, body: Value },
+}
+
+impl MockReply {
+    /// 单段文本回复
+    pub fn text(text: &str) -> Self {
+        Self::stream(vec![vec![text_part(text)]])
+    }
+
+    /// 多个片段，每个片段包含若干 part；最后一个片段带 finishReason 与 usageMetadata
+    pub fn stream(chunks: Vec<Vec<Value>>) -> Self {
+        let total = chunks.len();
+        Self::Generate(
+            chunks
+                .into_iter()
+                .enumerate()
+                .map(|(idx, parts)| chunk(parts, idx + 1 == total))
+                .collect(),
+        )
+    }
+
+    /// 429 RESOURCE_EXHAUSTED，带 RetryInfo.retryDelay 与 quotaResetDelay
+    pub fn rate_limited(retry_delay: &str) -> Self {
+        Self::Error {
+            status: 429,
+            body: json!({
+                "error": {
+                    "code": 429,
+                    "message": "You have exhausted your capacity on this model.",
+                    "status": "RESOURCE_EXHAUSTED",
+                    "details": [
+                        {
+                            "@type": "type.googleapis.com/google.rpc.ErrorInfo",
+                            "reason": "RATE_LIMIT_EXCEEDED",
+                            "domain": "cloudcode-pa.googleapis.com",
+                            "metadata": { "quotaResetDelay": retry_delay }
+                        },
+                        {
+                            "@type": "type.googleapis.com/google.rpc.RetryInfo",
+                            "retryDelay": retry_delay
+                        }
+                    ]
+                }
+            }),
+        }
+    }
+
+    /// 403 VALIDATION_REQUIRED (账号需要人工验证)
+    pub fn validation_required() -> Self {
+        Self::Error {
+            status: 403,
+            body: json!({
+                "error": {
+                    "code": 403,
+                    "message": "VALIDATION_REQUIRED: please verify your account to continue.",
+                    "status": "PERMISSION_DENIED",
+                    "details": [
+                        {
+                            "@type": "type.googleapis.com/google.rpc.ErrorInfo",
+                            "reason": "VALIDATION_REQUIRED",
+                            "metadata": { "validation_url": "https://accounts.google.com/signin/continue" }
+                        }
+                    ]
+                }
+            }),
+        }
+    }
+}
+
+pub fn text_part(text: &str) -> Value {
+    json!({ "text": text })
+}
+
+pub fn thinking_part(thought: &str, signature: &str) -> Value {
+    json!({ "text": thought, "thought": true, "thoughtSignature": signature })
+}
+
+pub fn function_call_part(name: &str, args: Value, signature: Option<&str>) -> Value {
+    let mut part = json!({ "functionCall": { "name": name, "args": args } });
+    if let Some(sig) = signature {
+        part["thoughtSignature"] = json!(sig);
+    }
+    part
+}
+
+/// 构造一个 v1internal 响应片段
+fn chunk(parts: Vec<Value>, last: bool) -> Value {
+    let mut candidate = json!({
+        "content": { "role": "model", "parts": parts },
+        "index": 0
+    });
+    let mut response = json!({ "modelVersion": "mock-model" });
+    if last {
+        candidate["finishReason"] = json!("STOP");
+        response["usageMetadata"] = json!({
+            "promptTokenCount": 12,
+            "candidatesTokenCount": 8,
+            "totalTokenCount": 20
+        });
+    }
+    response["candidates"] = json!([candidate]);
+    json!({ "response": response, "responseId": "mock-response" })
+}
+
+/// mock 收到的一次上游调用
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    /// v1internal 方法名，如 `streamGenerateContent`
+    pub method: String,
+    pub query: Option<String>,
+    /// Authorization 中的 Bearer token (用于判断使用了哪个账号)
+    pub access_token: String,
+    pub body: Value,
+}
+
+#[derive(Default)]
+struct MockState {
+    replies: Mutex<VecDeque<MockReply>>,
+    requests: Mutex<Vec<RecordedRequest>>,
+}
+
+pub struct MockUpstream {
+    base_url: String,
+    state: Arc<MockState>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl MockUpstream {
+    /// 在 127.0.0.1 随机端口启动
+    pub async fn start() -> Self {
+        let state = Arc::new(MockState::default());
+        let app = Router::new().fallback(handle_v1internal).with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock upstream");
+        let addr = listener.local_addr().expect("mock upstream addr");
+        let handle = tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+
+        Self {
+            base_url: format!("http://{}/v1internal", addr),
+            state,
+            handle,
+        }
+    }
+
+    /// 传给 `UpstreamClient::with_base_urls` 的端点
+    pub fn base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    /// 追加一条脚本响应 (FIFO)
+    pub fn push(&self, reply: MockReply) {
+        self.state.replies.lock().unwrap().push_back(reply);
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockUpstream {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn handle_v1internal(
+    State(state): State<Arc<MockState>>,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    // 路径形如 /v1internal:streamGenerateContent
+    let method = uri
+        .path()
+        .rsplit_once(':')
+        .map(|(_, m)| m.to_string())
+        .unwrap_or_default();
+    let access_token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .unwrap_or_default()
+        .to_string();
+
+    state.requests.lock().unwrap().push(RecordedRequest {
+        method: method.clone(),
+        query: uri.query().map(|q| q.to_string()),
+        access_token,
+        body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+    });
+
+    let reply = state.replies.lock().unwrap().pop_front();
+    match reply {
+        Some(MockReply::Generate(chunks)) if method == "streamGenerateContent" => {
+            let events: Vec<Result<Bytes, std::convert::Infallible>> = chunks
+                .iter()
+                .map(|c| Ok(Bytes::from(format!("data: {}\n\n", c))))
+                .collect();
+            Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "text/event-stream")
+                .body(Body::from_stream(futures::stream::iter(events)))
+                .unwrap()
+        }
+        Some(MockReply::Generate(chunks)) => {
+            let last = chunks.last().cloned().unwrap_or(Value::Null);
+            (StatusCode::OK, axum::Json(last)).into_response()
+        }
+        Some(MockReply::Error { status, body }) => (
+            StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            axum::Json(body),
+        )
+            .into_response(),
+        None => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            axum::Json(json!({
+                "error": { "code": 500, "message": "mock upstream: no scripted reply", "status": "INTERNAL" }
+            })),
+        )
+            .into_response(),
+    }
+}
+
+/// mock 自定义上游收到的一次请求
+#[derive(Debug, Clone)]
+pub struct ProviderRequest {
+    pub path: String,
+    pub body: Value,
+}
+
+/// OpenAI 兼容的自定义上游：记录请求路径与请求体，固定返回 `{"object": "mock.provider", "path": ...}`
+pub struct MockProvider {
+    base_url: String,
+    requests: Arc<Mutex<Vec<ProviderRequest>>>,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+impl MockProvider {
+    pub async fn start() -> Self {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let app = Router::new()
+            .fallback(handle_provider)
+            .with_state(requests.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock provider");
+        let addr = listener.local_addr().expect("mock provider addr");
+        let handle = tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+
+        Self {
+            base_url: format!("http://{}", addr),
+            requests,
+            handle,
+        }
+    }
+
+    /// 写入 `UpstreamProviderConfig.base_url` 的地址
+    pub fn base_url(&self) -> String {
+        self.base_url.clone()
+    }
+
+    pub fn requests(&self) -> Vec<ProviderRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockProvider {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn handle_provider(
+    State(requests): State<Arc<Mutex<Vec<ProviderRequest>>>>,
+    uri: Uri,
+    body: Bytes,
+) -> Response {
+    let path = uri.path().to_string();
+    requests.lock().unwrap().push(ProviderRequest {
+        path: path.clone(),
+        body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+    });
+    (
+        StatusCode::OK,
+        axum::Json(json!({ "object": "mock.provider", "path": path })),
+    )
+        .into_response()
+}
@@ -38,6 +38,7 @@ mod tests {
             validation_blocked: false,
             validation_blocked_until: 0,
             model_quotas: std::collections::HashMap::new(),
+            groups: Vec::new(),
         }
     }
 
@@ -1141,6 +1142,7 @@ mod tests {
             validation_blocked: false,
             validation_blocked_until: 0,
             model_quotas: std::collections::HashMap::new(),
+            groups: Vec::new(),
         }
     }
 }
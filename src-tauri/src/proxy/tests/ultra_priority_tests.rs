@@ -53,6 +53,7 @@ fn create_test_token(
         validation_blocked: false,
         validation_blocked_until: 0,
         model_quotas,
+        groups: Vec::new(),
     }
 }
 
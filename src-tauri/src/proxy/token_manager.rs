@@ -3,12 +3,17 @@ use dashmap::DashMap;
 use std::collections::{HashSet, HashMap};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use tokio_util::sync::CancellationToken;
 
 use crate::proxy::rate_limit::RateLimitTracker;
 use crate::proxy::sticky_config::StickySessionConfig;
 
+/// 持久化会话绑定的有效期 (与思维签名缓存 TTL 一致，超过后签名也已失效)
+const SESSION_BINDING_TTL_SECS: i64 = 2 * 60 * 60;
+/// 复用绑定时刷新磁盘记录的最小间隔，避免每个请求都写库
+const SESSION_BINDING_TOUCH_INTERVAL_SECS: i64 = 5 * 60;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum OnDiskAccountState {
     Enabled,
@@ -34,6 +39,14 @@ pub struct ProxyToken {
     pub validation_blocked: bool,          // [NEW] Check for validation block (VALIDATION_REQUIRED temporary block)
     pub validation_blocked_until: i64,     // [NEW] Timestamp until which the account is blocked
     pub model_quotas: HashMap<String, i32>, // [OPTIMIZATION] In-memory cache for model-specific quotas
+    pub groups: Vec<String>,               // [NEW] 账号分组 (User Token 按分组隔离号池)
+}
+
+impl ProxyToken {
+    /// 账号是否属于允许的分组之一 (allowed 为空表示不限制)
+    pub fn in_groups(&self, allowed: &[String]) -> bool {
+        allowed.is_empty() || self.groups.iter().any(|g| allowed.contains(g))
+    }
 }
 
 pub struct TokenManager {
@@ -44,6 +57,8 @@ pub struct TokenManager {
     rate_limit_tracker: Arc<RateLimitTracker>, // 新增: 限流跟踪器
     sticky_config: Arc<tokio::sync::RwLock<StickySessionConfig>>, // 新增：调度配置
     session_accounts: Arc<DashMap<String, String>>, // 新增：会话与账号映射 (SessionID -> AccountID)
+    session_writer: Arc<OnceLock<crate::modules::session_state_db::SessionStateWriter>>, // [NEW] 会话绑定的 session_state.db 后台写入队列
+    session_touched_at: Arc<DashMap<String, i64>>, // [NEW] SessionID -> 最近一次写库时间 (Unix 秒)
     preferred_account_id: Arc<tokio::sync::RwLock<Option<String>>>, // [FIX #820] 优先使用的账号ID（固定账号模式）
     health_scores: Arc<DashMap<String, f32>>,                       // account_id -> health_score
     circuit_breaker_config: Arc<tokio::sync::RwLock<crate::models::CircuitBreakerConfig>>, // [NEW] 熔断配置缓存
@@ -63,6 +78,8 @@ impl TokenManager {
             rate_limit_tracker: Arc::new(RateLimitTracker::new()),
             sticky_config: Arc::new(tokio::sync::RwLock::new(StickySessionConfig::default())),
             session_accounts: Arc::new(DashMap::new()),
+            session_writer: Arc::new(OnceLock::new()),
+            session_touched_at: Arc::new(DashMap::new()),
             preferred_account_id: Arc::new(tokio::sync::RwLock::new(None)), // [FIX #820]
             health_scores: Arc::new(DashMap::new()),
             circuit_breaker_config: Arc::new(tokio::sync::RwLock::new(
@@ -155,6 +172,24 @@ impl TokenManager {
             }
         }
 
+        // [NEW] 恢复持久化的限流 / 熔断状态，避免重启后立即冲击仍处于锁定期的账号
+        self.rate_limit_tracker.enable_persistence(self.data_dir.clone());
+        if let Err(e) = self.rate_limit_tracker.restore_persisted() {
+            tracing::warn!("恢复限流状态失败: {}", e);
+        }
+
+        // [NEW] 恢复会话绑定与思维签名缓存，重启后进行中的会话保持粘性账号与签名
+        self.session_writer
+            .get_or_init(|| crate::modules::session_state_db::SessionStateWriter::for_dir(&self.data_dir));
+        if let Err(e) = self.restore_session_bindings() {
+            tracing::warn!("恢复会话绑定失败: {}", e);
+        }
+        let signature_cache = crate::proxy::SignatureCache::global();
+        signature_cache.enable_persistence(self.data_dir.clone());
+        if let Err(e) = signature_cache.restore_persisted() {
+            tracing::warn!("恢复签名缓存失败: {}", e);
+        }
+
         Ok(count)
     }
 
@@ -208,7 +243,7 @@ impl TokenManager {
         self.clear_rate_limit(account_id);
 
         // 4. 清理涉及该账号的所有会话绑定
-        self.session_accounts.retain(|_, v| v != account_id);
+        self.unbind_account_sessions(account_id);
 
         // 5. 如果是当前优先账号，也需要清理
         if let Ok(mut preferred) = self.preferred_account_id.try_write() {
@@ -252,6 +287,15 @@ impl TokenManager {
                 }
             };
 
+            // [NEW] 账号文件可能已加密 (account_crypto)；密钥不可用时视为未知
+            let content = match crate::modules::account_crypto::decode_account_content(&content) {
+                Ok(c) => c,
+                Err(e) => {
+                    tracing::debug!("Failed to decrypt account file {:?}: {}", account_path, e);
+                    return OnDiskAccountState::Unknown;
+                }
+            };
+
             let account = match serde_json::from_str::<serde_json::Value>(&content) {
                 Ok(v) => v,
                 Err(e) => {
@@ -294,7 +338,8 @@ impl TokenManager {
 
     /// 加载单个账号
     async fn load_single_account(&self, path: &PathBuf) -> Result<Option<ProxyToken>, String> {
-        let content = std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?;
+        let content = crate::modules::account_crypto::read_account_file(path)
+            .map_err(|e| format!("读取文件失败: {}", e))?;
 
         let mut account: serde_json::Value =
             serde_json::from_str(&content).map_err(|e| format!("解析 JSON 失败: {}", e))?;
@@ -357,9 +402,7 @@ impl TokenManager {
                 account["validation_blocked_until"] = serde_json::json!(0);
                 account["validation_blocked_reason"] = serde_json::Value::Null;
 
-                let updated_json =
-                    serde_json::to_string_pretty(&account).map_err(|e| e.to_string())?;
-                std::fs::write(path, updated_json).map_err(|e| e.to_string())?;
+                crate::modules::account_crypto::write_account_value(path, &account)?;
                 tracing::info!(
                     "Validation block expired and cleared for account: {}",
                     account
@@ -481,6 +524,17 @@ impl TokenManager {
             })
             .unwrap_or_default();
 
+        let groups: Vec<String> = account
+            .get("groups")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
         let health_score = self.health_scores.get(&account_id).map(|v| *v).unwrap_or(1.0);
 
         // [NEW] 提取最近的配额刷新时间（用于排序优化：刷新时间越近优先级越高）
@@ -516,6 +570,7 @@ impl TokenManager {
             validation_blocked: account.get("validation_blocked").and_then(|v| v.as_bool()).unwrap_or(false),
             validation_blocked_until: account.get("validation_blocked_until").and_then(|v| v.as_i64()).unwrap_or(0),
             model_quotas,
+            groups,
         }))
     }
 
@@ -682,7 +737,7 @@ impl TokenManager {
     /// * `model_name` - 目标模型名称（已标准化）
     #[allow(dead_code)] // 预留给精确配额读取逻辑
     fn get_model_quota_from_json(account_path: &PathBuf, model_name: &str) -> Option<i32> {
-        let content = std::fs::read_to_string(account_path).ok()?;
+        let content = crate::modules::account_crypto::read_account_file(account_path).ok()?;
         let account: serde_json::Value = serde_json::from_str(&content).ok()?;
         let models = account.get("quota")?.get("models")?.as_array()?;
 
@@ -742,12 +797,34 @@ impl TokenManager {
             );
 
             // 3. 写入磁盘
-            std::fs::write(account_path, serde_json::to_string_pretty(account_json).unwrap())
+            crate::modules::account_crypto::write_account_value(account_path, account_json)
                 .map_err(|e| format!("写入文件失败: {}", e))?;
 
             // [FIX] 触发 TokenManager 的账号重新加载信号，确保内存中的 protected_models 同步
             crate::proxy::server::trigger_account_reload(account_id);
 
+            // [NEW] Webhook 告警
+            let email = account_json
+                .get("email")
+                .and_then(|v| v.as_str())
+                .unwrap_or(account_id)
+                .to_string();
+            crate::modules::webhook::notify(
+                crate::modules::webhook::WebhookEvent::QuotaProtectionTriggered,
+                format!("{}/{}", email, model_name),
+                format!(
+                    "Account {} is protected for {} (quota {}% <= {}%)",
+                    email, model_name, current_val, threshold
+                ),
+                serde_json::json!({
+                    "account_id": account_id,
+                    "email": email,
+                    "model": model_name,
+                    "percentage": current_val,
+                    "threshold": threshold,
+                }),
+            );
+
             return Ok(true);
         }
 
@@ -793,7 +870,7 @@ impl TokenManager {
 
         account_json["protected_models"] = serde_json::Value::Array(protected_list);
 
-        let _ = std::fs::write(account_path, serde_json::to_string_pretty(account_json).unwrap());
+        let _ = crate::modules::account_crypto::write_account_value(account_path, account_json);
 
         false // 返回 false 表示现在已可以尝试加载该账号（模型级过滤会在 get_token 时发生）
     }
@@ -820,10 +897,7 @@ impl TokenManager {
                     account_id,
                     model_name
                 );
-                std::fs::write(
-                    account_path,
-                    serde_json::to_string_pretty(account_json).unwrap(),
-                )
+                crate::modules::account_crypto::write_account_value(account_path, account_json)
                 .map_err(|e| format!("写入文件失败: {}", e))?;
                 return Ok(true);
             }
@@ -944,12 +1018,14 @@ impl TokenManager {
     /// 参数 `force_rotate` 为 true 时将忽略锁定，强制切换账号
     /// 参数 `session_id` 用于跨请求维持会话粘性
     /// 参数 `target_model` 用于检查配额保护 (Issue #621)
+    /// 参数 `account_groups` 限定可选账号分组 (来自 User Token)，空 = 整个账号池
     pub async fn get_token(
         &self,
         quota_group: &str,
         force_rotate: bool,
         session_id: Option<&str>,
         target_model: &str,
+        account_groups: &[String],
     ) -> Result<(String, String, String, String, u64), String> {
         // [FIX] 检查并处理待重新加载的账号（配额保护同步）
         let pending_reload = crate::proxy::server::take_pending_reload_accounts();
@@ -978,7 +1054,13 @@ impl TokenManager {
         let timeout_duration = std::time::Duration::from_secs(5);
         match tokio::time::timeout(
             timeout_duration,
-            self.get_token_internal(quota_group, force_rotate, session_id, target_model),
+            self.get_token_internal(
+                quota_group,
+                force_rotate,
+                session_id,
+                target_model,
+                account_groups,
+            ),
         )
         .await
         {
@@ -996,6 +1078,7 @@ impl TokenManager {
         force_rotate: bool,
         session_id: Option<&str>,
         target_model: &str,
+        account_groups: &[String],
     ) -> Result<(String, String, String, String, u64), String> {
         let mut tokens_snapshot: Vec<ProxyToken> =
             self.tokens.iter().map(|e| e.value().clone()).collect();
@@ -1004,6 +1087,18 @@ impl TokenManager {
             return Err("Token pool is empty".to_string());
         }
 
+        // [NEW] 0. 分组隔离：后续的固定账号 / 粘性会话 / 轮询都只在允许的分组内进行
+        if !account_groups.is_empty() {
+            tokens_snapshot.retain(|t| t.in_groups(account_groups));
+            total = tokens_snapshot.len();
+            if total == 0 {
+                return Err(format!(
+                    "No accounts available in groups: {}",
+                    account_groups.join(", ")
+                ));
+            }
+        }
+
         // [NEW] 1. 动态能力过滤 (Capability Filter)
         
         // 定义常量
@@ -1242,7 +1337,7 @@ impl TokenManager {
 
         // 【优化 Issue #284】将锁操作移到循环外，避免重复获取锁
         // 预先获取 last_used_account 的快照，避免在循环中多次加锁
-        let last_used_account_id = if quota_group != "image_gen" {
+        let last_used_account_id = if !is_isolated_quota_group(quota_group) {
             let last_used = self.last_used_account.lock().await;
             last_used.clone()
         } else {
@@ -1289,19 +1384,20 @@ impl TokenManager {
                                 "Sticky Session: Bound account {} is rate-limited ({}s), unbinding and switching.",
                                 bound_token.email, reset_sec
                             );
-                            self.session_accounts.remove(sid);
+                            self.unbind_session(sid);
                         } else if !attempted.contains(&bound_id)
                             && !(quota_protection_enabled
                                 && bound_token.protected_models.contains(&normalized_target))
                         {
                             // 3. 账号可用且未被标记为尝试失败，优先复用
                             tracing::debug!("Sticky Session: Successfully reusing bound account {} for session {}", bound_token.email, sid);
+                            self.touch_session(sid, &bound_id);
                             target_token = Some(bound_token.clone());
                         } else if quota_protection_enabled
                             && bound_token.protected_models.contains(&normalized_target)
                         {
                             tracing::debug!("Sticky Session: Bound account {} is quota-protected for model {} [{}], unbinding and switching.", bound_token.email, normalized_target, target_model);
-                            self.session_accounts.remove(sid);
+                            self.unbind_session(sid);
                         }
                     } else {
                         // 绑定的账号已不存在（可能被删除），解绑
@@ -1309,7 +1405,7 @@ impl TokenManager {
                             "Sticky Session: Bound account not found for session {}, unbinding",
                             sid
                         );
-                        self.session_accounts.remove(sid);
+                        self.unbind_session(sid);
                     }
                 }
             }
@@ -1318,7 +1414,7 @@ impl TokenManager {
             // 【修复】性能优先模式应跳过 60s 锁定；
             if target_token.is_none()
                 && !rotate
-                && quota_group != "image_gen"
+                && !is_isolated_quota_group(quota_group)
                 && scheduling.mode != SchedulingMode::PerformanceFirst
             {
                 // 【优化】使用预先获取的快照，不再在循环内加锁
@@ -1376,8 +1472,7 @@ impl TokenManager {
                         // 如果是会话首次分配且需要粘性，在此建立绑定
                         if let Some(sid) = session_id {
                             if scheduling.mode != SchedulingMode::PerformanceFirst {
-                                self.session_accounts
-                                    .insert(sid.to_string(), selected.account_id.clone());
+                                self.bind_session(sid, &selected.account_id);
                                 tracing::debug!(
                                     "Sticky Session: Bound new account {} to session {}",
                                     selected.email,
@@ -1477,6 +1572,22 @@ impl TokenManager {
                                 }
                             }
                         } else {
+                            // [NEW] Webhook 告警 (按模型冷却去重)
+                            crate::modules::webhook::notify(
+                                crate::modules::webhook::WebhookEvent::ModelRateLimited,
+                                normalized_target.clone(),
+                                format!(
+                                    "All {} accounts are rate limited for {}. Shortest wait {}s",
+                                    tokens_snapshot.len(),
+                                    normalized_target,
+                                    wait_sec
+                                ),
+                                serde_json::json!({
+                                    "model": normalized_target,
+                                    "accounts": tokens_snapshot.len(),
+                                    "wait_secs": wait_sec,
+                                }),
+                            );
                             return Err(format!("All accounts limited. Wait {}s.", wait_sec));
                         }
                     } else {
@@ -1558,7 +1669,7 @@ impl TokenManager {
                         attempted.insert(token.account_id.clone());
 
                         // 【优化】标记需要清除锁定，避免在循环内加锁
-                        if quota_group != "image_gen" {
+                        if !is_isolated_quota_group(quota_group) {
                             if matches!(&last_used_account_id, Some((id, _)) if id == &token.account_id)
                             {
                                 need_update_last_used =
@@ -1602,7 +1713,7 @@ impl TokenManager {
 
             // 【优化】在成功返回前，统一更新 last_used_account（如果需要）
             if let Some((new_account_id, new_time)) = need_update_last_used {
-                if quota_group != "image_gen" {
+                if !is_isolated_quota_group(quota_group) {
                     let mut last_used = self.last_used_account.lock().await;
                     if new_account_id.is_empty() {
                         // 空字符串表示需要清除锁定
@@ -1629,7 +1740,7 @@ impl TokenManager {
         };
 
         let mut content: serde_json::Value = serde_json::from_str(
-            &std::fs::read_to_string(&path).map_err(|e| format!("读取文件失败: {}", e))?,
+            &crate::modules::account_crypto::read_account_file(&path).map_err(|e| format!("读取文件失败: {}", e))?,
         )
         .map_err(|e| format!("解析 JSON 失败: {}", e))?;
 
@@ -1638,7 +1749,7 @@ impl TokenManager {
         content["disabled_at"] = serde_json::Value::Number(now.into());
         content["disabled_reason"] = serde_json::Value::String(truncate_reason(reason, 800));
 
-        std::fs::write(&path, serde_json::to_string_pretty(&content).unwrap())
+        crate::modules::account_crypto::write_account_value(&path, &content)
             .map_err(|e| format!("写入文件失败: {}", e))?;
 
         // 【修复 Issue #3】从内存中移除禁用的账号，防止被60s锁定逻辑继续使用
@@ -1656,12 +1767,12 @@ impl TokenManager {
         let path = &entry.account_path;
 
         let mut content: serde_json::Value = serde_json::from_str(
-            &std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?
+            &crate::modules::account_crypto::read_account_file(path).map_err(|e| format!("读取文件失败: {}", e))?
         ).map_err(|e| format!("解析 JSON 失败: {}", e))?;
 
         content["token"]["project_id"] = serde_json::Value::String(project_id.to_string());
 
-        std::fs::write(path, serde_json::to_string_pretty(&content).unwrap())
+        crate::modules::account_crypto::write_account_value(path, &content)
             .map_err(|e| format!("写入文件失败: {}", e))?;
 
         tracing::debug!("已保存 project_id 到账号 {}", account_id);
@@ -1676,7 +1787,7 @@ impl TokenManager {
         let path = &entry.account_path;
 
         let mut content: serde_json::Value = serde_json::from_str(
-            &std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {}", e))?
+            &crate::modules::account_crypto::read_account_file(path).map_err(|e| format!("读取文件失败: {}", e))?
         ).map_err(|e| format!("解析 JSON 失败: {}", e))?;
 
         let now = chrono::Utc::now().timestamp();
@@ -1685,7 +1796,7 @@ impl TokenManager {
         content["token"]["expires_in"] = serde_json::Value::Number(token_response.expires_in.into());
         content["token"]["expiry_timestamp"] = serde_json::Value::Number((now + token_response.expires_in).into());
 
-        std::fs::write(path, serde_json::to_string_pretty(&content).unwrap())
+        crate::modules::account_crypto::write_account_value(path, &content)
             .map_err(|e| format!("写入文件失败: {}", e))?;
 
         tracing::debug!("已保存刷新后的 token 到账号 {}", account_id);
@@ -1810,6 +1921,13 @@ impl TokenManager {
         );
     }
 
+    /// 按限流原因统计当前被锁定的账号数 (用于 /metrics)
+    pub fn rate_limited_accounts_by_reason(
+        &self,
+    ) -> HashMap<crate::proxy::rate_limit::RateLimitReason, usize> {
+        self.rate_limit_tracker.active_accounts_by_reason()
+    }
+
     /// 检查账号是否在限流中 (支持模型级)
     pub async fn is_rate_limited(&self, account_id: &str, model: Option<&str>) -> bool {
         // [NEW] 检查熔断是否启用
@@ -1884,23 +2002,37 @@ impl TokenManager {
     /// # 示例
     /// ```ignore
     /// // 检查是否有可用账号处理 claude-sonnet 请求
-    /// let has_available = token_manager.has_available_account("claude", "claude-sonnet-4-20250514").await;
+    /// let has_available = token_manager.has_available_account("claude", "claude-sonnet-4-20250514", &[]).await;
     /// if !has_available {
     ///     // 切换到外部提供商
     /// }
     /// ```
-    pub async fn has_available_account(&self, _quota_group: &str, target_model: &str) -> bool {
+    pub async fn has_available_account(
+        &self,
+        _quota_group: &str,
+        target_model: &str,
+        account_groups: &[String],
+    ) -> bool {
         // 检查配额保护是否启用
         let quota_protection_enabled = crate::modules::config::load_app_config()
             .map(|cfg| cfg.quota_protection.enabled)
             .unwrap_or(false);
 
+        // 配额保护记录的是标准化模型 ID (如 gemini-3-flash / claude)
+        let protected_id = crate::proxy::common::model_mapping::normalize_to_standard_id(target_model)
+            .unwrap_or_else(|| target_model.to_string());
+
         // 遍历所有账号,检查是否有可用的
         for entry in self.tokens.iter() {
             let token = entry.value();
 
-            // 1. 检查是否被限流
-            if self.is_rate_limited(&token.account_id, None).await {
+            // 0. 仅考虑调用方可用的分组
+            if !token.in_groups(account_groups) {
+                continue;
+            }
+
+            // 1. 检查是否被限流 (账号级 + 模型级)
+            if self.is_rate_limited(&token.account_id, Some(target_model)).await {
                 tracing::debug!(
                     "[Fallback Check] Account {} is rate-limited, skipping",
                     token.email
@@ -1909,7 +2041,7 @@ impl TokenManager {
             }
 
             // 2. 检查是否被配额保护(如果启用)
-            if quota_protection_enabled && token.protected_models.contains(target_model) {
+            if quota_protection_enabled && token.protected_models.contains(&protected_id) {
                 tracing::debug!(
                     "[Fallback Check] Account {} is quota-protected for model {}, skipping",
                     token.email,
@@ -1945,7 +2077,7 @@ impl TokenManager {
         // 直接用 account_id 查找账号文件（文件名是 {account_id}.json）
         let account_path = self.data_dir.join("accounts").join(format!("{}.json", account_id));
 
-        let content = std::fs::read_to_string(&account_path).ok()?;
+        let content = crate::modules::account_crypto::read_account_file(&account_path).ok()?;
         let account: serde_json::Value = serde_json::from_str(&content).ok()?;
 
         // 获取 quota.models 中最早的 reset_time（最保守的锁定策略）
@@ -2194,12 +2326,103 @@ impl TokenManager {
     /// 清除特定会话的粘性映射
     #[allow(dead_code)]
     pub fn clear_session_binding(&self, session_id: &str) {
-        self.session_accounts.remove(session_id);
+        self.unbind_session(session_id);
     }
 
     /// 清除所有会话的粘性映射
     pub fn clear_all_sessions(&self) {
         self.session_accounts.clear();
+        self.session_touched_at.clear();
+        self.persist_session_op(crate::modules::session_state_db::PersistOp::ClearSessionBindings);
+    }
+
+    // ===== [NEW] 会话绑定持久化 (session_state.db) =====
+
+    /// 提交给后台写入线程 (不阻塞 get_token 的请求路径)；未开启持久化时忽略
+    fn persist_session_op(&self, op: crate::modules::session_state_db::PersistOp) {
+        if let Some(writer) = self.session_writer.get() {
+            writer.send(op);
+        }
+    }
+
+    fn persist_session_binding(&self, session_id: &str, account_id: &str, now: i64) {
+        self.session_touched_at.insert(session_id.to_string(), now);
+        self.persist_session_op(crate::modules::session_state_db::PersistOp::SaveSessionBinding(
+            crate::modules::session_state_db::PersistedSessionBinding {
+                session_id: session_id.to_string(),
+                account_id: account_id.to_string(),
+                updated_at: now,
+            },
+        ));
+    }
+
+    /// 建立会话与账号的绑定
+    fn bind_session(&self, session_id: &str, account_id: &str) {
+        self.session_accounts
+            .insert(session_id.to_string(), account_id.to_string());
+        self.persist_session_binding(session_id, account_id, chrono::Utc::now().timestamp());
+    }
+
+    /// 复用绑定时按间隔刷新磁盘记录，使活跃会话不会在重启时因 TTL 过期
+    fn touch_session(&self, session_id: &str, account_id: &str) {
+        let now = chrono::Utc::now().timestamp();
+        let last = self.session_touched_at.get(session_id).map(|v| *v).unwrap_or(0);
+        if now - last >= SESSION_BINDING_TOUCH_INTERVAL_SECS {
+            self.persist_session_binding(session_id, account_id, now);
+        }
+    }
+
+    /// 解除单个会话的绑定
+    fn unbind_session(&self, session_id: &str) {
+        self.session_accounts.remove(session_id);
+        self.session_touched_at.remove(session_id);
+        self.persist_session_op(crate::modules::session_state_db::PersistOp::DeleteSessionBinding(
+            session_id.to_string(),
+        ));
+    }
+
+    /// 解除绑定到指定账号的所有会话
+    fn unbind_account_sessions(&self, account_id: &str) {
+        let touched = &self.session_touched_at;
+        self.session_accounts.retain(|sid, v| {
+            if *v == account_id {
+                touched.remove(sid);
+                false
+            } else {
+                true
+            }
+        });
+        self.persist_session_op(crate::modules::session_state_db::PersistOp::DeleteAccountSessionBindings(
+            account_id.to_string(),
+        ));
+    }
+
+    /// 从 session_state.db 恢复未过期的会话绑定 (跳过已不在账号池中的账号)
+    ///
+    /// 内存中已存在的绑定保持不变。返回恢复的绑定数。
+    fn restore_session_bindings(&self) -> Result<usize, String> {
+        // 先等待队列中的写入落盘，避免读到旧数据
+        if let Some(writer) = self.session_writer.get() {
+            writer.flush();
+        }
+        let bindings = crate::modules::session_state_db::load_session_bindings(&self.data_dir, SESSION_BINDING_TTL_SECS)?;
+        let mut restored = 0;
+        for binding in bindings {
+            if !self.tokens.contains_key(&binding.account_id)
+                || self.session_accounts.contains_key(&binding.session_id)
+            {
+                continue;
+            }
+            self.session_touched_at
+                .insert(binding.session_id.clone(), binding.updated_at);
+            self.session_accounts
+                .insert(binding.session_id, binding.account_id);
+            restored += 1;
+        }
+        if restored > 0 {
+            tracing::info!("恢复了 {} 个会话绑定", restored);
+        }
+        Ok(restored)
     }
 
     // ===== [FIX #820] 固定账号模式相关方法 =====
@@ -2380,7 +2603,7 @@ impl TokenManager {
              return Err(format!("Account file not found: {:?}", path));
         }
 
-        let content = std::fs::read_to_string(&path)
+        let content = crate::modules::account_crypto::read_account_file(&path)
              .map_err(|e| format!("Failed to read account file: {}", e))?;
 
         let mut account: serde_json::Value = serde_json::from_str(&content)
@@ -2391,12 +2614,12 @@ impl TokenManager {
         account["validation_blocked_reason"] = serde_json::Value::String(reason.to_string());
 
         // Clear sticky session if blocked
-        self.session_accounts.retain(|_, v| *v != account_id);
+        self.unbind_account_sessions(account_id);
 
         let json_str = serde_json::to_string_pretty(&account)
              .map_err(|e| format!("Failed to serialize account JSON: {}", e))?;
 
-        std::fs::write(&path, json_str)
+        crate::modules::account_crypto::write_account_file(&path, &json_str)
              .map_err(|e| format!("Failed to write account file: {}", e))?;
 
         tracing::info!(
@@ -2422,7 +2645,7 @@ impl TokenManager {
             return Err(format!("Account file not found: {:?}", path));
         }
 
-        let content = std::fs::read_to_string(&path)
+        let content = crate::modules::account_crypto::read_account_file(&path)
             .map_err(|e| format!("Failed to read account file: {}", e))?;
 
         let mut account: serde_json::Value = serde_json::from_str(&content)
@@ -2443,12 +2666,12 @@ impl TokenManager {
         }
 
         // Clear sticky session if forbidden
-        self.session_accounts.retain(|_, v| *v != account_id);
+        self.unbind_account_sessions(account_id);
 
         let json_str = serde_json::to_string_pretty(&account)
             .map_err(|e| format!("Failed to serialize account JSON: {}", e))?;
 
-        std::fs::write(&path, json_str)
+        crate::modules::account_crypto::write_account_file(&path, &json_str)
             .map_err(|e| format!("Failed to write account file: {}", e))?;
 
         // [FIX] 从内存池中移除账号，避免重试时再次选中
@@ -2460,10 +2683,32 @@ impl TokenManager {
             truncate_reason(reason, 100)
         );
 
+        // [NEW] Webhook 告警
+        let email = account
+            .get("email")
+            .and_then(|v| v.as_str())
+            .unwrap_or(account_id)
+            .to_string();
+        crate::modules::webhook::notify(
+            crate::modules::webhook::WebhookEvent::AccountForbidden,
+            email.clone(),
+            format!("Account {} was marked forbidden (403)", email),
+            serde_json::json!({
+                "account_id": account_id,
+                "email": email,
+                "reason": truncate_reason(reason, 500),
+            }),
+        );
+
         Ok(())
     }
 }
 
+/// 独立配额组 (图像生成 / Embedding) 不读写 60s 锁定，避免与对话请求争抢同一账号
+fn is_isolated_quota_group(quota_group: &str) -> bool {
+    matches!(quota_group, "image_gen" | "embedding")
+}
+
 /// 截断过长的原因字符串
 fn truncate_reason(reason: &str, max_len: usize) -> String {
     if reason.len() <= max_len {
@@ -2583,7 +2828,7 @@ mod tests {
         write_account("acc1", "a@test.com", true);
 
         let (_token, _project_id, email, account_id, _wait_ms) = manager
-            .get_token("gemini", false, Some("sid1"), "gemini-1.5-flash")
+            .get_token("gemini", false, Some("sid1"), "gemini-1.5-flash", &[])
             .await
             .unwrap();
 
@@ -2642,7 +2887,7 @@ mod tests {
 
         // Prime: first request should bind the session to acc1.
         let (_token, _project_id, _email, account_id, _wait_ms) = manager
-            .get_token("gemini", false, Some("sid1"), "gemini-1.5-flash")
+            .get_token("gemini", false, Some("sid1"), "gemini-1.5-flash", &[])
             .await
             .unwrap();
         assert_eq!(account_id, "acc1");
@@ -2655,7 +2900,7 @@ mod tests {
         write_account("acc1", "a@test.com", 90, true);
 
         let (_token, _project_id, email, account_id, _wait_ms) = manager
-            .get_token("gemini", false, Some("sid1"), "gemini-1.5-flash")
+            .get_token("gemini", false, Some("sid1"), "gemini-1.5-flash", &[])
             .await
             .unwrap();
 
@@ -2671,6 +2916,209 @@ mod tests {
         let _ = std::fs::remove_dir_all(&tmp_root);
     }
 
+    #[tokio::test]
+    async fn test_session_bindings_survive_restart() {
+        let tmp_root = std::env::temp_dir().join(format!(
+            "antigravity-token-manager-test-session-restore-{}",
+            uuid::Uuid::new_v4()
+        ));
+        let accounts_dir = tmp_root.join("accounts");
+        std::fs::create_dir_all(&accounts_dir).unwrap();
+
+        let now = chrono::Utc::now().timestamp();
+        for id in ["acc1", "acc2"] {
+            let json = serde_json::json!({
+                "id": id,
+                "email": format!("{}@test.com", id),
+                "token": {
+                    "access_token": format!("atk-{}", id),
+                    "refresh_token": format!("rtk-{}", id),
+                    "expires_in": 3600,
+                    "expiry_timestamp": now + 3600,
+                    "project_id": format!("pid-{}", id)
+                },
+                "disabled": false,
+                "proxy_disabled": false,
+                "created_at": now,
+                "last_used": now
+            });
+            std::fs::write(
+                accounts_dir.join(format!("{}.json", id)),
+                serde_json::to_string_pretty(&json).unwrap(),
+            )
+            .unwrap();
+        }
+
+        // 第一次启动：建立绑定
+        let bound = {
+            let manager = TokenManager::new(tmp_root.clone());
+            manager.load_accounts().await.unwrap();
+            let (_, _, _, account_id, _) = manager
+                .get_token("gemini", false, Some("sid-restore"), "gemini-1.5-flash", &[])
+                .await
+                .unwrap();
+            manager.bind_session("sid-gone", "acc1");
+            manager.clear_session_binding("sid-gone");
+            account_id
+        };
+
+        // 重启：绑定从 session_state.db 恢复，已解绑的会话不会复活
+        let manager = TokenManager::new(tmp_root.clone());
+        manager.load_accounts().await.unwrap();
+        assert_eq!(
+            manager.session_accounts.get("sid-restore").map(|v| v.clone()),
+            Some(bound.clone())
+        );
+        assert!(manager.session_accounts.get("sid-gone").is_none());
+
+        // 删除账号时同步清理持久化绑定
+        manager.remove_account(&bound);
+        let manager = TokenManager::new(tmp_root.clone());
+        manager.load_accounts().await.unwrap();
+        assert!(manager.session_accounts.get("sid-restore").is_none());
+
+        let _ = std::fs::remove_dir_all(&tmp_root);
+    }
+
+    #[tokio::test]
+    async fn test_account_groups_isolate_pool() {
+        let tmp_root = std::env::temp_dir().join(format!(
+            "antigravity-token-manager-test-groups-{}",
+            uuid::Uuid::new_v4()
+        ));
+        let accounts_dir = tmp_root.join("accounts");
+        std::fs::create_dir_all(&accounts_dir).unwrap();
+
+        let now = chrono::Utc::now().timestamp();
+        // acc1 配额更高，不限分组时总会被优先选中
+        for (id, group, percentage) in [("acc1", "team-a", 90), ("acc2", "team-b", 10)] {
+            let json = serde_json::json!({
+                "id": id,
+                "email": format!("{}@test.com", id),
+                "token": {
+                    "access_token": format!("atk-{}", id),
+                    "refresh_token": format!("rtk-{}", id),
+                    "expires_in": 3600,
+                    "expiry_timestamp": now + 3600,
+                    "project_id": format!("pid-{}", id)
+                },
+                "quota": {
+                    "models": [
+                        { "name": "gemini-1.5-flash", "percentage": percentage }
+                    ]
+                },
+                "groups": [group],
+                "disabled": false,
+                "proxy_disabled": false,
+                "created_at": now,
+                "last_used": now
+            });
+            std::fs::write(
+                accounts_dir.join(format!("{}.json", id)),
+                serde_json::to_string_pretty(&json).unwrap(),
+            )
+            .unwrap();
+        }
+
+        let manager = TokenManager::new(tmp_root.clone());
+        manager.load_accounts().await.unwrap();
+        let team_b = vec!["team-b".to_string()];
+
+        // 粘性会话：sid1 先绑定到 acc1，team-b 的请求不能复用该绑定
+        let (_, _, _, account_id, _) = manager
+            .get_token("gemini", false, Some("sid1"), "gemini-1.5-flash", &[])
+            .await
+            .unwrap();
+        assert_eq!(account_id, "acc1");
+        let (_, _, _, account_id, _) = manager
+            .get_token("gemini", false, Some("sid1"), "gemini-1.5-flash", &team_b)
+            .await
+            .unwrap();
+        assert_eq!(account_id, "acc2");
+
+        // 固定账号模式：优先账号不在分组内时回退到分组内轮询
+        manager.set_preferred_account(Some("acc1".to_string())).await;
+        let (_, _, _, account_id, _) = manager
+            .get_token("gemini", false, None, "gemini-1.5-flash", &team_b)
+            .await
+            .unwrap();
+        assert_eq!(account_id, "acc2");
+
+        let unknown = vec!["team-c".to_string()];
+        assert!(manager
+            .get_token("gemini", false, None, "gemini-1.5-flash", &unknown)
+            .await
+            .is_err());
+        assert!(!manager.has_available_account("gemini", "gemini-1.5-flash", &unknown).await);
+        assert!(manager.has_available_account("gemini", "gemini-1.5-flash", &team_b).await);
+
+        let _ = std::fs::remove_dir_all(&tmp_root);
+    }
+
+    #[tokio::test]
+    async fn test_embedding_calls_leave_chat_lock_alone() {
+        let tmp_root = std::env::temp_dir().join(format!(
+            "antigravity-token-manager-test-embedding-{}",
+            uuid::Uuid::new_v4()
+        ));
+        let accounts_dir = tmp_root.join("accounts");
+        std::fs::create_dir_all(&accounts_dir).unwrap();
+
+        let now = chrono::Utc::now().timestamp();
+        for (id, percentage) in [("acc1", 90), ("acc2", 80)] {
+            let json = serde_json::json!({
+                "id": id,
+                "email": format!("{}@test.com", id),
+                "token": {
+                    "access_token": format!("atk-{}", id),
+                    "refresh_token": format!("rtk-{}", id),
+                    "expires_in": 3600,
+                    "expiry_timestamp": now + 3600,
+                    "project_id": format!("pid-{}", id)
+                },
+                "quota": {
+                    "models": [
+                        { "name": "gemini-1.5-flash", "percentage": percentage }
+                    ]
+                },
+                "disabled": false,
+                "proxy_disabled": false,
+                "created_at": now,
+                "last_used": now
+            });
+            std::fs::write(
+                accounts_dir.join(format!("{}.json", id)),
+                serde_json::to_string_pretty(&json).unwrap(),
+            )
+            .unwrap();
+        }
+
+        let manager = TokenManager::new(tmp_root.clone());
+        manager.load_accounts().await.unwrap();
+
+        // 尚无对话请求时，Embedding 调用不会建立锁定
+        manager
+            .get_token("embedding", false, None, "gemini-1.5-flash", &[])
+            .await
+            .unwrap();
+        assert!(manager.last_used_account.lock().await.is_none());
+
+        // 对话请求建立锁定后，Embedding 调用既不复用也不覆盖它
+        manager
+            .get_token("gemini", false, None, "gemini-1.5-flash", &[])
+            .await
+            .unwrap();
+        let chat_lock = manager.last_used_account.lock().await.clone();
+        assert!(chat_lock.is_some());
+        manager
+            .get_token("embedding", true, None, "gemini-1.5-flash", &[])
+            .await
+            .unwrap();
+        assert_eq!(*manager.last_used_account.lock().await, chat_lock);
+
+        let _ = std::fs::remove_dir_all(&tmp_root);
+    }
+
     /// 创建测试用的 ProxyToken
     fn create_test_token(
         email: &str,
@@ -2696,6 +3144,7 @@ mod tests {
             validation_blocked: false,
             validation_blocked_until: 0,
             model_quotas: HashMap::new(),
+            groups: Vec::new(),
         }
     }
 
@@ -2952,6 +3401,7 @@ mod tests {
             validation_blocked: false,
             validation_blocked_until: 0,
             model_quotas: HashMap::new(),
+            groups: Vec::new(),
         }
     }
 
@@ -56,11 +56,38 @@ const V1_INTERNAL_BASE_URL_FALLBACKS: [&str; 3] = [
     V1_INTERNAL_BASE_URL_PROD,    // 优先级 3: Prod (仅作为兜底)
 ];
 
+/// [NEW] 覆盖上游端点 (逗号分隔，按顺序降级)，用于自建网关或本地 mock 测试
+const UPSTREAM_BASE_URL_ENV: &str = "ABV_UPSTREAM_BASE_URL";
+
+/// 解析端点列表：去除空白与末尾 `/`，忽略空项
+fn parse_base_urls(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|s| s.trim().trim_end_matches('/').to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// 默认端点列表：优先读取环境变量，否则使用内置的 Sandbox → Daily → Prod
+fn default_base_urls() -> Vec<String> {
+    if let Ok(raw) = std::env::var(UPSTREAM_BASE_URL_ENV) {
+        let urls = parse_base_urls(&raw);
+        if !urls.is_empty() {
+            tracing::info!("UpstreamClient using {} override: {:?}", UPSTREAM_BASE_URL_ENV, urls);
+            return urls;
+        }
+    }
+    V1_INTERNAL_BASE_URL_FALLBACKS
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
 pub struct UpstreamClient {
     default_client: Client,
     proxy_pool: Option<Arc<crate::proxy::proxy_pool::ProxyPoolManager>>,
     client_cache: DashMap<String, Client>, // proxy_id -> Client
     user_agent_override: RwLock<Option<String>>,
+    base_urls: Vec<String>, // [NEW] v1internal 端点 (按降级顺序)
 }
 
 impl UpstreamClient {
@@ -93,7 +120,26 @@ impl UpstreamClient {
             proxy_pool,
             client_cache: DashMap::new(),
             user_agent_override: RwLock::new(None),
+            base_urls: default_base_urls(),
+        }
+    }
+
+    /// [NEW] 替换 v1internal 端点列表 (按顺序降级)，空列表将被忽略
+    pub fn with_base_urls(mut self, base_urls: Vec<String>) -> Self {
+        let urls: Vec<String> = base_urls
+            .into_iter()
+            .map(|s| s.trim().trim_end_matches('/').to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        if !urls.is_empty() {
+            self.base_urls = urls;
         }
+        self
+    }
+
+    /// 当前使用的 v1internal 端点列表
+    pub fn base_urls(&self) -> &[String] {
+        &self.base_urls
     }
 
     /// Internal helper to build a client with optional upstream proxy config
@@ -336,9 +382,10 @@ impl UpstreamClient {
         let mut fallback_attempts: Vec<FallbackAttemptLog> = Vec::new();
 
         // 遍历所有端点，失败时自动切换
-        for (idx, base_url) in V1_INTERNAL_BASE_URL_FALLBACKS.iter().enumerate() {
+        let endpoint_count = self.base_urls.len();
+        for (idx, base_url) in self.base_urls.iter().enumerate() {
             let url = Self::build_url(base_url, method, query_string);
-            let has_next = idx + 1 < V1_INTERNAL_BASE_URL_FALLBACKS.len();
+            let has_next = idx + 1 < endpoint_count;
 
             let response = client
                 .post(&url)
@@ -356,7 +403,7 @@ impl UpstreamClient {
                                 "✓ Upstream fallback succeeded | Endpoint: {} | Status: {} | Next endpoints available: {}",
                                 base_url,
                                 status,
-                                V1_INTERNAL_BASE_URL_FALLBACKS.len() - idx - 1
+                                endpoint_count - idx - 1
                             );
                         } else {
                             tracing::debug!(
@@ -486,4 +533,23 @@ mod tests {
             "https://cloudcode-pa.googleapis.com/v1internal:streamGenerateContent?alt=sse"
         );
     }
+
+    #[test]
+    fn test_base_url_override() {
+        assert_eq!(
+            parse_base_urls(" http://127.0.0.1:9000/v1internal/ , ,https://gw.example/v1internal"),
+            vec![
+                "http://127.0.0.1:9000/v1internal".to_string(),
+                "https://gw.example/v1internal".to_string()
+            ]
+        );
+
+        let client = UpstreamClient::new(None, None)
+            .with_base_urls(vec!["http://127.0.0.1:9000/v1internal/".to_string()]);
+        assert_eq!(client.base_urls(), &["http://127.0.0.1:9000/v1internal".to_string()]);
+
+        // 空列表不会清空端点
+        let client = client.with_base_urls(vec![]);
+        assert_eq!(client.base_urls().len(), 1);
+    }
 }
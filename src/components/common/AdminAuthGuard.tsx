@@ -1,5 +1,5 @@
 import React, { useState, useEffect } from 'react';
-import { Lock, Key, Globe, AlertCircle, Loader2 } from 'lucide-react';
+import { Lock, Key, Globe, AlertCircle, Loader2, User } from 'lucide-react';
 import { useTranslation } from 'react-i18next';
 import { isTauri } from '../../utils/env';
 
@@ -7,11 +7,15 @@ import { isTauri } from '../../utils/env';
  * AdminAuthGuard
  * 针对 Docker/Web 模式的强制鉴权保护层。
  * 如果检测到没有存储的 API Key 或后端返回 401，将拦截 UI 并要求输入 Key。
+ * [NEW] 也可用管理员账号登录，换取的会话令牌同样存放在 abv_admin_api_key。
  */
 export const AdminAuthGuard: React.FC<{ children: React.ReactNode }> = ({ children }) => {
     const { t, i18n } = useTranslation();
     const [isAuthenticated, setIsAuthenticated] = useState(isTauri());
     const [apiKey, setApiKey] = useState('');
+    const [mode, setMode] = useState<'key' | 'account'>('key');
+    const [username, setUsername] = useState('');
+    const [password, setPassword] = useState('');
     const [showLangMenu, setShowLangMenu] = useState(false);
     const [isLoading, setIsLoading] = useState(false);
     const [error, setError] = useState('');
@@ -48,8 +52,43 @@ export const AdminAuthGuard: React.FC<{ children: React.ReactNode }> = ({ childr
         return () => window.removeEventListener('abv-unauthorized', handleUnauthorized);
     }, []);
 
+    const handleAccountLogin = async () => {
+        if (!username.trim() || !password) return;
+
+        setIsLoading(true);
+        setError('');
+
+        try {
+            const response = await fetch('/api/auth/login', {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({ username: username.trim(), password })
+            });
+
+            if (response.ok) {
+                const data = await response.json();
+                sessionStorage.setItem('abv_admin_api_key', data.token);
+                localStorage.removeItem('abv_admin_api_key');
+                setIsAuthenticated(true);
+                window.location.reload();
+            } else if (response.status === 401) {
+                setError(t('login.error_invalid_credentials'));
+            } else {
+                setError(t('login.error_network'));
+            }
+        } catch (err) {
+            setError(t('login.error_network'));
+        } finally {
+            setIsLoading(false);
+        }
+    };
+
     const handleLogin = async (e: React.FormEvent) => {
         e.preventDefault();
+        if (mode === 'account') {
+            await handleAccountLogin();
+            return;
+        }
         const trimmedKey = apiKey.trim();
         if (!trimmedKey) return;
 
@@ -155,19 +194,62 @@ export const AdminAuthGuard: React.FC<{ children: React.ReactNode }> = ({ childr
                     <h2 className="text-2xl font-bold text-center text-slate-900 dark:text-slate-100 mb-2 font-display">{t('login.title')}</h2>
                     <p className="text-center text-slate-500 dark:text-slate-400 mb-8 text-sm">{t('login.desc')}</p>
 
+                    <div className="flex mb-6 p-1 bg-slate-100 dark:bg-base-200 rounded-2xl text-sm font-medium">
+                        {(['key', 'account'] as const).map((m) => (
+                            <button
+                                key={m}
+                                type="button"
+                                onClick={() => { setMode(m); setError(''); }}
+                                className={`flex-1 py-2 rounded-xl transition-all ${mode === m ? 'bg-white dark:bg-base-100 shadow-sm text-blue-500' : 'text-slate-500 dark:text-slate-400'}`}
+                            >
+                                {m === 'key' ? t('login.mode_key') : t('login.mode_account')}
+                            </button>
+                        ))}
+                    </div>
+
                     <form onSubmit={handleLogin} className="space-y-6">
-                        <div className="relative">
-                            <Key className="absolute left-4 top-1/2 -translate-y-1/2 w-5 h-5 text-slate-400" />
-                            <input
-                                type="password"
-                                placeholder={t('login.placeholder')}
-                                className={`w-full pl-12 pr-4 py-4 bg-slate-50 dark:bg-base-200 border-2 rounded-2xl focus:ring-2 focus:ring-blue-500 transition-all outline-none text-slate-900 dark:text-white ${error ? 'border-red-400' : 'border-transparent'}`}
-                                value={apiKey}
-                                onChange={(e) => { setApiKey(e.target.value); setError(''); }}
-                                autoFocus
-                                disabled={isLoading}
-                            />
-                        </div>
+                        {mode === 'key' ? (
+                            <div className="relative">
+                                <Key className="absolute left-4 top-1/2 -translate-y-1/2 w-5 h-5 text-slate-400" />
+                                <input
+                                    type="password"
+                                    placeholder={t('login.placeholder')}
+                                    className={`w-full pl-12 pr-4 py-4 bg-slate-50 dark:bg-base-200 border-2 rounded-2xl focus:ring-2 focus:ring-blue-500 transition-all outline-none text-slate-900 dark:text-white ${error ? 'border-red-400' : 'border-transparent'}`}
+                                    value={apiKey}
+                                    onChange={(e) => { setApiKey(e.target.value); setError(''); }}
+                                    autoFocus
+                                    disabled={isLoading}
+                                />
+                            </div>
+                        ) : (
+                            <div className="space-y-3">
+                                <div className="relative">
+                                    <User className="absolute left-4 top-1/2 -translate-y-1/2 w-5 h-5 text-slate-400" />
+                                    <input
+                                        type="text"
+                                        autoComplete="username"
+                                        placeholder={t('login.username_placeholder')}
+                                        className={`w-full pl-12 pr-4 py-4 bg-slate-50 dark:bg-base-200 border-2 rounded-2xl focus:ring-2 focus:ring-blue-500 transition-all outline-none text-slate-900 dark:text-white ${error ? 'border-red-400' : 'border-transparent'}`}
+                                        value={username}
+                                        onChange={(e) => { setUsername(e.target.value); setError(''); }}
+                                        autoFocus
+                                        disabled={isLoading}
+                                    />
+                                </div>
+                                <div className="relative">
+                                    <Key className="absolute left-4 top-1/2 -translate-y-1/2 w-5 h-5 text-slate-400" />
+                                    <input
+                                        type="password"
+                                        autoComplete="current-password"
+                                        placeholder={t('login.password_placeholder')}
+                                        className={`w-full pl-12 pr-4 py-4 bg-slate-50 dark:bg-base-200 border-2 rounded-2xl focus:ring-2 focus:ring-blue-500 transition-all outline-none text-slate-900 dark:text-white ${error ? 'border-red-400' : 'border-transparent'}`}
+                                        value={password}
+                                        onChange={(e) => { setPassword(e.target.value); setError(''); }}
+                                        disabled={isLoading}
+                                    />
+                                </div>
+                            </div>
+                        )}
                         {error && (
                             <div className="flex items-center gap-2 text-red-500 text-sm">
                                 <AlertCircle className="w-4 h-4" />
@@ -176,7 +258,7 @@ export const AdminAuthGuard: React.FC<{ children: React.ReactNode }> = ({ childr
                         )}
                         <button
                             type="submit"
-                            disabled={isLoading || !apiKey.trim()}
+                            disabled={isLoading || (mode === 'key' ? !apiKey.trim() : !username.trim() || !password)}
                             className="w-full py-4 bg-blue-500 hover:bg-blue-600 disabled:bg-blue-300 disabled:cursor-not-allowed text-white font-bold rounded-2xl shadow-lg shadow-blue-500/30 transition-all active:scale-[0.98] flex items-center justify-center gap-2"
                         >
                             {isLoading ? (
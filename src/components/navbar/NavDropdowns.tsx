@@ -5,6 +5,7 @@ import { useTranslation } from 'react-i18next';
 import type { NavItem, Language } from './constants';
 import { isTauri } from '../../utils/env';
 import { useViewStore } from '../../stores/useViewStore';
+import { adminLogout } from '../../utils/request';
 
 // useClickOutside Hook
 export function useClickOutside(
@@ -193,9 +194,8 @@ export function MoreDropdown({
         setIsOpen(false);
     };
 
-    const handleLogout = () => {
-        sessionStorage.removeItem('abv_admin_api_key');
-        localStorage.removeItem('abv_admin_api_key');
+    const handleLogout = async () => {
+        await adminLogout();
         window.location.reload();
     };
 
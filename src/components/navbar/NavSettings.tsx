@@ -4,6 +4,7 @@ import { LanguageDropdown, MoreDropdown } from './NavDropdowns';
 import { LANGUAGES } from './constants';
 import { isTauri } from '../../utils/env';
 import { useViewStore } from '../../stores/useViewStore';
+import { adminLogout } from '../../utils/request';
 
 interface NavSettingsProps {
     theme: 'light' | 'dark';
@@ -28,9 +29,8 @@ export function NavSettings({
     const { t } = useTranslation();
     const { setMiniView } = useViewStore();
 
-    const handleLogout = () => {
-        sessionStorage.removeItem('abv_admin_api_key');
-        localStorage.removeItem('abv_admin_api_key');
+    const handleLogout = async () => {
+        await adminLogout();
         window.location.reload();
     };
 
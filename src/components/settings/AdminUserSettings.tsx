@@ -0,0 +1,197 @@
+import { useEffect, useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { Users, Plus, Trash2, KeyRound, LogOut, RefreshCw } from 'lucide-react';
+import { request } from '../../utils/request';
+import { AdminRole, AdminSession, AdminUser } from '../../types/config';
+import { showToast } from '../common/ToastContainer';
+
+interface AdminUserSettingsProps {
+    sessionTtlHours: number;
+    onSessionTtlChange: (hours: number) => void;
+}
+
+const ROLES: AdminRole[] = ['viewer', 'operator', 'owner'];
+
+export default function AdminUserSettings({ sessionTtlHours, onSessionTtlChange }: AdminUserSettingsProps) {
+    const { t } = useTranslation();
+    const [users, setUsers] = useState<AdminUser[]>([]);
+    const [sessions, setSessions] = useState<AdminSession[]>([]);
+    const [username, setUsername] = useState('');
+    const [password, setPassword] = useState('');
+    const [role, setRole] = useState<AdminRole>('viewer');
+
+    const load = async () => {
+        try {
+            const [userList, sessionList] = await Promise.all([
+                request<AdminUser[]>('list_admin_users'),
+                request<AdminSession[]>('list_admin_sessions'),
+            ]);
+            setUsers(userList);
+            setSessions(sessionList);
+        } catch (error) {
+            console.error('Failed to load admin users:', error);
+        }
+    };
+
+    useEffect(() => {
+        load();
+    }, []);
+
+    const run = async (action: () => Promise<unknown>) => {
+        try {
+            await action();
+            await load();
+        } catch (error) {
+            showToast(`${t('common.error')}: ${error}`, 'error');
+        }
+    };
+
+    const handleCreate = () => run(async () => {
+        await request('create_admin_user', { request: { username: username.trim(), password, role } });
+        setUsername('');
+        setPassword('');
+        showToast(t('common.success'), 'success');
+    });
+
+    const handleResetPassword = (user: AdminUser) => {
+        const next = prompt(t('settings.admin_users.new_password_prompt', { name: user.username }));
+        if (!next) return;
+        run(() => request('update_admin_user', { id: user.id, request: { password: next } }));
+    };
+
+    const handleDelete = (user: AdminUser) => {
+        if (!confirm(t('settings.admin_users.confirm_delete', { name: user.username }))) return;
+        run(() => request('delete_admin_user', { id: user.id }));
+    };
+
+    const inputCls = "px-3 py-1.5 bg-gray-50 dark:bg-base-200 border border-gray-200 dark:border-base-300 rounded-lg focus:ring-2 focus:ring-violet-500 outline-none text-xs";
+
+    return (
+        <div className="animate-in fade-in duration-500">
+            <div className="flex items-center justify-between">
+                <div className="flex items-center gap-4">
+                    <div className="w-10 h-10 rounded-xl bg-violet-50 dark:bg-violet-900/20 flex items-center justify-center text-violet-500 group-hover:bg-violet-500 group-hover:text-white transition-all duration-300">
+                        <Users size={20} />
+                    </div>
+                    <div>
+                        <div className="font-bold text-gray-900 dark:text-gray-100">
+                            {t('settings.admin_users.title')}
+                        </div>
+                        <p className="text-xs text-gray-500 dark:text-gray-400 mt-0.5">
+                            {t('settings.admin_users.desc')}
+                        </p>
+                    </div>
+                </div>
+                <button onClick={load} className="btn btn-xs btn-ghost h-7 min-h-0 px-2">
+                    <RefreshCw size={12} />
+                </button>
+            </div>
+
+            <div className="mt-4 space-y-4">
+                <div className="flex items-center justify-between text-xs">
+                    <span className="text-gray-600 dark:text-gray-300">{t('settings.admin_users.session_ttl')}</span>
+                    <input
+                        type="number"
+                        min={1}
+                        className={`${inputCls} w-24 text-right`}
+                        value={sessionTtlHours}
+                        onChange={(e) => onSessionTtlChange(Math.max(1, parseInt(e.target.value) || 1))}
+                    />
+                </div>
+
+                <div className="space-y-1">
+                    {users.length === 0 && (
+                        <p className="text-xs text-gray-400 dark:text-gray-500">{t('settings.admin_users.empty')}</p>
+                    )}
+                    {users.map(user => (
+                        <div key={user.id} className="flex items-center gap-2 text-xs py-1">
+                            <span className={`flex-1 font-medium ${user.enabled ? 'text-gray-800 dark:text-gray-200' : 'text-gray-400 line-through'}`}>
+                                {user.username}
+                            </span>
+                            <span className="text-gray-400 hidden sm:inline">
+                                {user.last_login_at
+                                    ? new Date(user.last_login_at * 1000).toLocaleString()
+                                    : t('settings.admin_users.never_logged_in')}
+                            </span>
+                            <select
+                                className={inputCls}
+                                value={user.role}
+                                onChange={(e) => run(() => request('update_admin_user', { id: user.id, request: { role: e.target.value } }))}
+                            >
+                                {ROLES.map(r => <option key={r} value={r}>{t(`settings.admin_users.role_${r}`)}</option>)}
+                            </select>
+                            <input
+                                type="checkbox"
+                                className="toggle toggle-xs toggle-success"
+                                checked={user.enabled}
+                                title={t('settings.admin_users.enabled')}
+                                onChange={(e) => run(() => request('update_admin_user', { id: user.id, request: { enabled: e.target.checked } }))}
+                            />
+                            <button onClick={() => handleResetPassword(user)} className="btn btn-xs btn-ghost h-7 min-h-0 px-2" title={t('settings.admin_users.reset_password')}>
+                                <KeyRound size={12} />
+                            </button>
+                            <button onClick={() => handleDelete(user)} className="btn btn-xs btn-ghost h-7 min-h-0 px-2 text-red-500">
+                                <Trash2 size={12} />
+                            </button>
+                        </div>
+                    ))}
+                </div>
+
+                <div className="flex flex-wrap items-center gap-2">
+                    <input
+                        className={`${inputCls} flex-1 min-w-[120px]`}
+                        placeholder={t('settings.admin_users.username')}
+                        value={username}
+                        onChange={(e) => setUsername(e.target.value)}
+                    />
+                    <input
+                        type="password"
+                        className={`${inputCls} flex-1 min-w-[120px]`}
+                        placeholder={t('settings.admin_users.password')}
+                        value={password}
+                        onChange={(e) => setPassword(e.target.value)}
+                    />
+                    <select className={inputCls} value={role} onChange={(e) => setRole(e.target.value as AdminRole)}>
+                        {ROLES.map(r => <option key={r} value={r}>{t(`settings.admin_users.role_${r}`)}</option>)}
+                    </select>
+                    <button
+                        onClick={handleCreate}
+                        disabled={!username.trim() || password.length < 8}
+                        className="btn btn-xs btn-primary h-7 min-h-0 px-2 gap-1"
+                    >
+                        <Plus size={12} /> {t('settings.admin_users.add')}
+                    </button>
+                </div>
+                <p className="text-[11px] text-gray-400 dark:text-gray-500">{t('settings.admin_users.roles_hint')}</p>
+
+                <div>
+                    <div className="text-xs font-medium text-gray-700 dark:text-gray-300 mb-1">
+                        {t('settings.admin_users.sessions')}
+                    </div>
+                    {sessions.length === 0 ? (
+                        <p className="text-xs text-gray-400 dark:text-gray-500">{t('settings.admin_users.no_sessions')}</p>
+                    ) : (
+                        <div className="space-y-1">
+                            {sessions.map(session => (
+                                <div key={session.id} className="flex items-center gap-2 text-[11px] font-mono">
+                                    <span className="flex-1 truncate">
+                                        {session.username}{session.ip ? ` @ ${session.ip}` : ''}
+                                    </span>
+                                    <span className="text-gray-400">
+                                        {t('settings.admin_users.expires', { time: new Date(session.expires_at * 1000).toLocaleString() })}
+                                    </span>
+                                    <button
+                                        onClick={() => run(() => request('revoke_admin_session', { id: session.id }))}
+                                        className="btn btn-xs btn-ghost h-6 min-h-0 px-2 gap-1"
+                                    >
+                                        <LogOut size={12} /> {t('settings.admin_users.revoke')}
+                                    </button>
+                                </div>
+                            ))}
+                        </div>
+                    )}
+                </div>
+            </div>
+        </div>
+    );
+}
@@ -0,0 +1,228 @@
+import { Fragment, useEffect, useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { ShieldCheck, RefreshCw, Download, CheckCircle, XCircle, ChevronLeft, ChevronRight } from 'lucide-react';
+import { request } from '../../utils/request';
+import { isTauri } from '../../utils/env';
+import { AuditChainStatus, AuditEntry, AuditLogPage } from '../../types/config';
+import { showToast } from '../common/ToastContainer';
+
+const PAGE_SIZE = 20;
+
+type OutcomeFilter = 'all' | 'success' | 'failure';
+
+export default function AuditLogViewer() {
+    const { t } = useTranslation();
+    const [page, setPage] = useState<AuditLogPage>({ entries: [], total: 0 });
+    const [offset, setOffset] = useState(0);
+    const [action, setAction] = useState('');
+    const [actor, setActor] = useState('');
+    const [outcome, setOutcome] = useState<OutcomeFilter>('all');
+    const [expandedId, setExpandedId] = useState<number | null>(null);
+    const [chain, setChain] = useState<AuditChainStatus | null>(null);
+
+    const filters = () => ({
+        action: action.trim() || undefined,
+        actor: actor.trim() || undefined,
+        success: outcome === 'all' ? undefined : outcome === 'success',
+    });
+
+    const loadPage = async (nextOffset = offset) => {
+        try {
+            setPage(await request<AuditLogPage>('get_audit_logs', {
+                ...filters(),
+                limit: PAGE_SIZE,
+                offset: nextOffset,
+            }));
+            setOffset(nextOffset);
+        } catch (error) {
+            console.error('Failed to load audit log:', error);
+        }
+    };
+
+    useEffect(() => {
+        loadPage(0);
+    }, [actor, outcome]);
+
+    const handleExport = async (format: 'csv' | 'json') => {
+        try {
+            const data = await request<any>('export_audit_logs', { format, ...filters() });
+            const content = typeof data === 'string' ? data : JSON.stringify(data, null, 2);
+            const fileName = `audit-log-${new Date().toISOString().split('T')[0]}.${format}`;
+
+            if (isTauri()) {
+                const { save } = await import('@tauri-apps/plugin-dialog');
+                const path = await save({
+                    filters: [{ name: format.toUpperCase(), extensions: [format] }],
+                    defaultPath: fileName,
+                });
+                if (!path) return;
+                await request('save_text_file', { path, content });
+                showToast(`${t('common.success')} ${path}`, 'success');
+            } else {
+                const blob = new Blob([content], { type: format === 'csv' ? 'text/csv' : 'application/json' });
+                const url = URL.createObjectURL(blob);
+                const a = document.createElement('a');
+                a.href = url;
+                a.download = fileName;
+                document.body.appendChild(a);
+                a.click();
+                document.body.removeChild(a);
+                URL.revokeObjectURL(url);
+            }
+        } catch (error) {
+            showToast(`${t('common.error')}: ${error}`, 'error');
+        }
+    };
+
+    const handleVerify = async () => {
+        try {
+            setChain(await request<AuditChainStatus>('verify_audit_log'));
+        } catch (error) {
+            showToast(`${t('common.error')}: ${error}`, 'error');
+        }
+    };
+
+    const inputCls = "px-3 py-1.5 bg-gray-50 dark:bg-base-200 border border-gray-200 dark:border-base-300 rounded-lg focus:ring-2 focus:ring-indigo-500 outline-none text-xs";
+
+    const renderDetails = (entry: AuditEntry) => {
+        const changes: string[] | undefined = entry.details?.changes;
+        if (Array.isArray(changes)) {
+            return changes.length === 0
+                ? <span className="text-gray-400">{t('settings.audit.no_changes')}</span>
+                : <ul className="space-y-0.5">{changes.map((c, i) => <li key={i}>{c}</li>)}</ul>;
+        }
+        return <pre className="whitespace-pre-wrap break-all">{JSON.stringify(entry.details, null, 2)}</pre>;
+    };
+
+    return (
+        <div className="animate-in fade-in duration-500">
+            <div className="flex items-center justify-between">
+                <div className="flex items-center gap-4">
+                    <div className="w-10 h-10 rounded-xl bg-indigo-50 dark:bg-indigo-900/20 flex items-center justify-center text-indigo-500 group-hover:bg-indigo-500 group-hover:text-white transition-all duration-300">
+                        <ShieldCheck size={20} />
+                    </div>
+                    <div>
+                        <div className="font-bold text-gray-900 dark:text-gray-100">
+                            {t('settings.audit.title')}
+                        </div>
+                        <p className="text-xs text-gray-500 dark:text-gray-400 mt-0.5">
+                            {t('settings.audit.desc')}
+                        </p>
+                    </div>
+                </div>
+                <div className="flex items-center gap-1">
+                    <button onClick={handleVerify} className="btn btn-xs btn-ghost h-7 min-h-0 px-2">
+                        {t('settings.audit.verify')}
+                    </button>
+                    <button onClick={() => handleExport('csv')} className="btn btn-xs btn-ghost h-7 min-h-0 px-2 gap-1">
+                        <Download size={12} /> CSV
+                    </button>
+                    <button onClick={() => handleExport('json')} className="btn btn-xs btn-ghost h-7 min-h-0 px-2 gap-1">
+                        <Download size={12} /> JSON
+                    </button>
+                </div>
+            </div>
+
+            {chain && (
+                <p className={`mt-3 text-xs ${chain.valid ? 'text-green-600' : 'text-red-500'}`}>
+                    {chain.valid
+                        ? t('settings.audit.chain_valid', { count: chain.total })
+                        : t('settings.audit.chain_broken', { id: chain.first_broken_id })}
+                </p>
+            )}
+
+            <div className="mt-4 space-y-2">
+                <div className="flex flex-wrap items-center gap-2">
+                    <input
+                        className={`${inputCls} flex-1 min-w-[160px]`}
+                        placeholder={t('settings.audit.action_placeholder')}
+                        value={action}
+                        onChange={(e) => setAction(e.target.value)}
+                        onKeyDown={(e) => e.key === 'Enter' && loadPage(0)}
+                    />
+                    {/* 操作者可能是管理员用户名，这里用输入框 + 常用值提示 */}
+                    <input
+                        className={`${inputCls} w-36`}
+                        list="audit-actor-options"
+                        placeholder={t('settings.audit.actor_all')}
+                        value={actor}
+                        onChange={(e) => setActor(e.target.value)}
+                    />
+                    <datalist id="audit-actor-options">
+                        <option value="admin" />
+                        <option value="desktop" />
+                        <option value="unauthenticated" />
+                    </datalist>
+                    <select className={inputCls} value={outcome} onChange={(e) => setOutcome(e.target.value as OutcomeFilter)}>
+                        <option value="all">{t('settings.audit.outcome_all')}</option>
+                        <option value="success">{t('settings.audit.outcome_success')}</option>
+                        <option value="failure">{t('settings.audit.outcome_failure')}</option>
+                    </select>
+                    <button onClick={() => loadPage(0)} className="btn btn-xs btn-ghost h-7 min-h-0 px-2">
+                        <RefreshCw size={12} />
+                    </button>
+                </div>
+
+                {page.entries.length === 0 ? (
+                    <p className="text-xs text-gray-400 dark:text-gray-500">{t('settings.audit.empty')}</p>
+                ) : (
+                    <div className="max-h-80 overflow-y-auto rounded-lg border border-gray-100 dark:border-base-300">
+                        <table className="table table-xs w-full">
+                            <tbody className="font-mono text-[11px]">
+                                {page.entries.map(entry => (
+                                    <Fragment key={entry.id}>
+                                        <tr
+                                            className="cursor-pointer hover:bg-gray-50 dark:hover:bg-base-200"
+                                            title={entry.error || undefined}
+                                            onClick={() => setExpandedId(expandedId === entry.id ? null : entry.id)}
+                                        >
+                                            <td className="w-5">
+                                                {entry.success
+                                                    ? <CheckCircle size={12} className="text-green-500" />
+                                                    : <XCircle size={12} className="text-red-500" />}
+                                            </td>
+                                            <td className="text-gray-500 whitespace-nowrap">{new Date(entry.timestamp * 1000).toLocaleString()}</td>
+                                            <td className="whitespace-nowrap">{entry.actor}{entry.ip ? ` @ ${entry.ip}` : ''}</td>
+                                            <td className="truncate max-w-[220px]">{entry.action}</td>
+                                            <td className="truncate max-w-[140px]">{entry.target || '-'}</td>
+                                            <td className="text-right whitespace-nowrap">{entry.status_code ?? '-'}</td>
+                                        </tr>
+                                        {expandedId === entry.id && (
+                                            <tr>
+                                                <td colSpan={6} className="bg-gray-50 dark:bg-base-200 text-gray-600 dark:text-gray-300">
+                                                    {entry.error && <div className="text-red-500 mb-1">{entry.error}</div>}
+                                                    {renderDetails(entry)}
+                                                </td>
+                                            </tr>
+                                        )}
+                                    </Fragment>
+                                ))}
+                            </tbody>
+                        </table>
+                    </div>
+                )}
+
+                <div className="flex items-center justify-between text-xs text-gray-500 dark:text-gray-400">
+                    <span>{t('settings.audit.total', { count: page.total })}</span>
+                    <div className="flex items-center gap-1">
+                        <button
+                            disabled={offset === 0}
+                            onClick={() => loadPage(Math.max(0, offset - PAGE_SIZE))}
+                            className="btn btn-xs btn-ghost h-7 min-h-0 px-2"
+                        >
+                            <ChevronLeft size={12} />
+                        </button>
+                        <span>{Math.floor(offset / PAGE_SIZE) + 1}</span>
+                        <button
+                            disabled={offset + PAGE_SIZE >= page.total}
+                            onClick={() => loadPage(offset + PAGE_SIZE)}
+                            className="btn btn-xs btn-ghost h-7 min-h-0 px-2"
+                        >
+                            <ChevronRight size={12} />
+                        </button>
+                    </div>
+                </div>
+            </div>
+        </div>
+    );
+}
@@ -0,0 +1,170 @@
+import { useEffect, useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { Layers, RefreshCw, XCircle } from 'lucide-react';
+import { request } from '../../utils/request';
+import { BatchConfig, BatchOverview, BatchRecord } from '../../types/config';
+import { showToast } from '../common/ToastContainer';
+
+interface BatchSettingsProps {
+    config: BatchConfig;
+    onChange: (config: BatchConfig) => void;
+}
+
+type NumberField = 'concurrency' | 'max_interactive_in_flight' | 'max_attempts' | 'max_requests_per_batch' | 'retention_days';
+
+const STATUS_STYLES: Record<BatchRecord['status'], string> = {
+    in_progress: 'text-blue-600 bg-blue-50 dark:bg-blue-900/20 dark:text-blue-400',
+    canceling: 'text-amber-600 bg-amber-50 dark:bg-amber-900/20 dark:text-amber-400',
+    ended: 'text-gray-500 bg-gray-100 dark:bg-base-300 dark:text-gray-400',
+};
+
+export default function BatchSettings({ config, onChange }: BatchSettingsProps) {
+    const { t } = useTranslation();
+    const [overview, setOverview] = useState<BatchOverview | null>(null);
+    const [cancelling, setCancelling] = useState<string | null>(null);
+
+    const loadOverview = async () => {
+        try {
+            setOverview(await request<BatchOverview>('get_batch_overview'));
+        } catch (error) {
+            console.error('Failed to load batch overview:', error);
+        }
+    };
+
+    useEffect(() => {
+        loadOverview();
+    }, []);
+
+    const handleCancel = async (batchId: string) => {
+        setCancelling(batchId);
+        try {
+            await request('cancel_batch', { batchId });
+            showToast(t('proxy.config.batch.cancelled', { defaultValue: 'Batch cancelled' }), 'success');
+            await loadOverview();
+        } catch (error) {
+            showToast(String(error), 'error');
+        } finally {
+            setCancelling(null);
+        }
+    };
+
+    // 数值输入: 非法值回退到最小值
+    const handleNumberChange = (field: NumberField, value: string, min: number) => {
+        const num = parseInt(value, 10);
+        onChange({ ...config, [field]: Math.max(min, isNaN(num) ? min : num) });
+    };
+
+    const inputCls = "w-full px-3 py-2 bg-gray-50 dark:bg-base-200 border border-gray-200 dark:border-base-300 rounded-lg focus:ring-2 focus:ring-sky-500 outline-none text-sm font-bold text-sky-600 dark:text-sky-400";
+
+    const fields: { field: NumberField; label: string; min: number }[] = [
+        { field: 'concurrency', label: t('proxy.config.batch.concurrency', { defaultValue: 'Concurrency' }), min: 1 },
+        { field: 'max_interactive_in_flight', label: t('proxy.config.batch.max_interactive', { defaultValue: 'Yield above (interactive)' }), min: 0 },
+        { field: 'max_attempts', label: t('proxy.config.batch.max_attempts', { defaultValue: 'Max attempts' }), min: 1 },
+        { field: 'max_requests_per_batch', label: t('proxy.config.batch.max_requests', { defaultValue: 'Max requests / batch' }), min: 1 },
+        { field: 'retention_days', label: t('proxy.config.batch.retention_days', { defaultValue: 'Keep results (days)' }), min: 0 },
+    ];
+
+    return (
+        <div className="space-y-6">
+            <div className="bg-sky-50/50 dark:bg-sky-900/10 border border-sky-100 dark:border-sky-800/30 rounded-lg p-4">
+                <div className="flex gap-3">
+                    <Layers className="w-5 h-5 text-sky-500 shrink-0 mt-0.5" />
+                    <div className="space-y-1">
+                        <h4 className="font-medium text-sm text-gray-900 dark:text-gray-100">
+                            {t('proxy.config.batch.title', { defaultValue: 'Batch Queue' })}
+                        </h4>
+                        <p className="text-xs text-gray-500 dark:text-gray-400 leading-relaxed">
+                            {t('proxy.config.batch.tooltip', {
+                                defaultValue: 'Serves /v1/messages/batches and /v1/batches from a local queue. Requests run one by one through the normal account pool, only while interactive traffic is below the limit. When disabled, batches are still accepted and resume later.',
+                            })}
+                        </p>
+                    </div>
+                </div>
+            </div>
+
+            <div className="grid grid-cols-3 gap-4">
+                {fields.map(({ field, label, min }) => (
+                    <div key={field} className="space-y-1.5">
+                        <label className="text-xs font-bold text-gray-500 dark:text-gray-400 uppercase tracking-wider">
+                            {label}
+                        </label>
+                        <input
+                            type="number"
+                            min={min}
+                            className={inputCls}
+                            value={config[field]}
+                            onChange={(e) => handleNumberChange(field, e.target.value, min)}
+                        />
+                    </div>
+                ))}
+            </div>
+
+            <div className="space-y-2">
+                <div className="flex items-center justify-between">
+                    <div className="text-xs text-gray-500 dark:text-gray-400">
+                        {overview
+                            ? t('proxy.config.batch.stats', {
+                                active: overview.stats.active_batches,
+                                queued: overview.stats.queued,
+                                running: overview.stats.running,
+                                defaultValue: '{{active}} active batches · {{queued}} queued · {{running}} running',
+                            })
+                            : '-'}
+                    </div>
+                    <button
+                        onClick={loadOverview}
+                        className="btn btn-xs btn-ghost gap-1 h-7 min-h-0 px-2 rounded-md"
+                    >
+                        <RefreshCw size={12} />
+                    </button>
+                </div>
+
+                {overview && overview.batches.length > 0 ? (
+                    <div className="max-h-72 overflow-y-auto rounded-lg border border-gray-100 dark:border-base-300 divide-y divide-gray-100 dark:divide-base-300">
+                        {overview.batches.map((batch) => {
+                            const done = batch.counts.succeeded + batch.counts.errored + batch.counts.canceled + batch.counts.expired;
+                            const total = done + batch.counts.queued + batch.counts.running;
+                            return (
+                                <div key={batch.id} className="flex items-center justify-between gap-3 px-3 py-2 text-xs">
+                                    <div className="min-w-0 space-y-0.5">
+                                        <div className="font-mono text-gray-900 dark:text-base-content truncate">{batch.id}</div>
+                                        <div className="text-[10px] text-gray-500 dark:text-gray-400">
+                                            {batch.endpoint} · {new Date(batch.created_at * 1000).toLocaleString()}
+                                        </div>
+                                    </div>
+                                    <div className="flex items-center gap-2 shrink-0">
+                                        <span className="text-gray-600 dark:text-gray-300">
+                                            {t('proxy.config.batch.progress', {
+                                                done,
+                                                total,
+                                                errored: batch.counts.errored + batch.counts.expired,
+                                                defaultValue: '{{done}}/{{total}} · {{errored}} failed',
+                                            })}
+                                        </span>
+                                        <span className={`px-1.5 py-0.5 rounded font-bold ${STATUS_STYLES[batch.status]}`}>
+                                            {t(`proxy.config.batch.status_${batch.status}`, { defaultValue: batch.status })}
+                                        </span>
+                                        {batch.status === 'in_progress' && (
+                                            <button
+                                                onClick={() => handleCancel(batch.id)}
+                                                disabled={cancelling === batch.id}
+                                                className="btn btn-xs btn-ghost text-red-500 hover:bg-red-50 dark:hover:bg-red-900/20 h-6 min-h-0 px-1.5 rounded-md"
+                                                title={t('proxy.config.batch.cancel', { defaultValue: 'Cancel' })}
+                                            >
+                                                <XCircle size={12} />
+                                            </button>
+                                        )}
+                                    </div>
+                                </div>
+                            );
+                        })}
+                    </div>
+                ) : (
+                    <div className="text-xs text-gray-400 text-center py-4">
+                        {t('proxy.config.batch.empty', { defaultValue: 'No batches yet' })}
+                    </div>
+                )}
+            </div>
+        </div>
+    );
+}
@@ -0,0 +1,205 @@
+import { useTranslation } from 'react-i18next';
+import { DollarSign, Plus, Trash2 } from 'lucide-react';
+import { CostConfig, ModelPrice, BudgetAction } from '../../types/config';
+
+interface CostSettingsProps {
+    config: CostConfig;
+    onChange: (config: CostConfig) => void;
+}
+
+// 数值输入: 空值 / 非法值视为 0，可选字段为空时返回 null (沿用 input / output 价格)
+const parsePrice = (value: string): number => {
+    const num = parseFloat(value);
+    return isNaN(num) || num < 0 ? 0 : num;
+};
+
+const parseOptionalPrice = (value: string): number | null => (value.trim() === '' ? null : parsePrice(value));
+
+export default function CostSettings({ config, onChange }: CostSettingsProps) {
+    const { t } = useTranslation();
+
+    const updatePrice = (index: number, patch: Partial<ModelPrice>) => {
+        const prices = config.prices.map((p, i) => (i === index ? { ...p, ...patch } : p));
+        onChange({ ...config, prices });
+    };
+
+    const addPrice = () => {
+        onChange({ ...config, prices: [...config.prices, { model: '', input: 0, output: 0 }] });
+    };
+
+    const removePrice = (index: number) => {
+        onChange({ ...config, prices: config.prices.filter((_, i) => i !== index) });
+    };
+
+    const inputCls = "w-full px-2 py-1.5 bg-gray-50 dark:bg-base-200 border border-gray-200 dark:border-base-300 rounded-lg focus:ring-2 focus:ring-emerald-500 outline-none text-xs font-mono";
+
+    return (
+        <div className="space-y-6">
+            <div className="bg-emerald-50/50 dark:bg-emerald-900/10 border border-emerald-100 dark:border-emerald-800/30 rounded-lg p-4">
+                <div className="flex gap-3">
+                    <DollarSign className="w-5 h-5 text-emerald-500 shrink-0 mt-0.5" />
+                    <div className="space-y-1">
+                        <h4 className="font-medium text-sm text-gray-900 dark:text-gray-100">
+                            {t('proxy.config.cost.title', { defaultValue: 'Cost & Budgets' })}
+                        </h4>
+                        <p className="text-xs text-gray-500 dark:text-gray-400 leading-relaxed">
+                            {t('proxy.config.cost.tooltip', {
+                                defaultValue: 'Prices are USD per 1M tokens. Exact model names win over wildcards; models without a price are recorded at $0. Cached input and thinking fall back to the input / output price when left empty.',
+                            })}
+                        </p>
+                    </div>
+                </div>
+            </div>
+
+            {/* 价格表 */}
+            <div className="space-y-2">
+                <div className="flex items-center justify-between">
+                    <label className="text-xs font-bold text-gray-500 dark:text-gray-400 uppercase tracking-wider">
+                        {t('proxy.config.cost.prices', { defaultValue: 'Model Prices' })}
+                    </label>
+                    <button
+                        onClick={addPrice}
+                        className="btn btn-xs btn-ghost gap-1 h-7 min-h-0 px-2 rounded-md border border-gray-200 dark:border-base-300"
+                    >
+                        <Plus size={12} />
+                        {t('proxy.config.cost.add_price', { defaultValue: 'Add' })}
+                    </button>
+                </div>
+                {config.prices.length === 0 ? (
+                    <div className="text-xs text-gray-400 italic py-2">
+                        {t('proxy.config.cost.no_prices', { defaultValue: 'No prices configured. All requests are recorded at $0.' })}
+                    </div>
+                ) : (
+                    <div className="overflow-x-auto">
+                        <table className="w-full text-xs">
+                            <thead>
+                                <tr className="text-gray-500 dark:text-gray-400">
+                                    <th className="text-left py-1 pr-2 font-medium">{t('proxy.config.cost.model', { defaultValue: 'Model (* wildcard)' })}</th>
+                                    <th className="text-left py-1 px-1 font-medium">{t('proxy.config.cost.input', { defaultValue: 'Input' })}</th>
+                                    <th className="text-left py-1 px-1 font-medium">{t('proxy.config.cost.output', { defaultValue: 'Output' })}</th>
+                                    <th className="text-left py-1 px-1 font-medium">{t('proxy.config.cost.cached_input', { defaultValue: 'Cached Input' })}</th>
+                                    <th className="text-left py-1 px-1 font-medium">{t('proxy.config.cost.thinking', { defaultValue: 'Thinking' })}</th>
+                                    <th />
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {config.prices.map((price, index) => (
+                                    <tr key={index}>
+                                        <td className="py-1 pr-2 min-w-[160px]">
+                                            <input
+                                                type="text"
+                                                className={inputCls}
+                                                value={price.model}
+                                                placeholder="claude-sonnet-*"
+                                                onChange={(e) => updatePrice(index, { model: e.target.value })}
+                                            />
+                                        </td>
+                                        <td className="py-1 px-1">
+                                            <input
+                                                type="number"
+                                                min="0"
+                                                step="0.01"
+                                                className={inputCls}
+                                                value={price.input}
+                                                onChange={(e) => updatePrice(index, { input: parsePrice(e.target.value) })}
+                                            />
+                                        </td>
+                                        <td className="py-1 px-1">
+                                            <input
+                                                type="number"
+                                                min="0"
+                                                step="0.01"
+                                                className={inputCls}
+                                                value={price.output}
+                                                onChange={(e) => updatePrice(index, { output: parsePrice(e.target.value) })}
+                                            />
+                                        </td>
+                                        <td className="py-1 px-1">
+                                            <input
+                                                type="number"
+                                                min="0"
+                                                step="0.01"
+                                                className={inputCls}
+                                                value={price.cached_input ?? ''}
+                                                placeholder={String(price.input)}
+                                                onChange={(e) => updatePrice(index, { cached_input: parseOptionalPrice(e.target.value) })}
+                                            />
+                                        </td>
+                                        <td className="py-1 px-1">
+                                            <input
+                                                type="number"
+                                                min="0"
+                                                step="0.01"
+                                                className={inputCls}
+                                                value={price.thinking ?? ''}
+                                                placeholder={String(price.output)}
+                                                onChange={(e) => updatePrice(index, { thinking: parseOptionalPrice(e.target.value) })}
+                                            />
+                                        </td>
+                                        <td className="py-1 pl-1">
+                                            <button
+                                                onClick={() => removePrice(index)}
+                                                className="btn btn-xs btn-ghost text-red-500 h-7 min-h-0 px-2"
+                                                title={t('common.delete', { defaultValue: 'Delete' })}
+                                            >
+                                                <Trash2 size={12} />
+                                            </button>
+                                        </td>
+                                    </tr>
+                                ))}
+                            </tbody>
+                        </table>
+                    </div>
+                )}
+            </div>
+
+            {/* 全局预算 */}
+            <div className="grid grid-cols-3 gap-4">
+                <div className="space-y-1.5">
+                    <label className="text-xs font-bold text-gray-500 dark:text-gray-400 uppercase tracking-wider">
+                        {t('proxy.config.cost.daily_budget', { defaultValue: 'Daily Budget (USD)' })}
+                    </label>
+                    <input
+                        type="number"
+                        min="0"
+                        step="0.01"
+                        className={inputCls}
+                        value={config.budget.daily_usd}
+                        onChange={(e) => onChange({ ...config, budget: { ...config.budget, daily_usd: parsePrice(e.target.value) } })}
+                    />
+                </div>
+                <div className="space-y-1.5">
+                    <label className="text-xs font-bold text-gray-500 dark:text-gray-400 uppercase tracking-wider">
+                        {t('proxy.config.cost.monthly_budget', { defaultValue: 'Monthly Budget (USD)' })}
+                    </label>
+                    <input
+                        type="number"
+                        min="0"
+                        step="0.01"
+                        className={inputCls}
+                        value={config.budget.monthly_usd}
+                        onChange={(e) => onChange({ ...config, budget: { ...config.budget, monthly_usd: parsePrice(e.target.value) } })}
+                    />
+                </div>
+                <div className="space-y-1.5">
+                    <label className="text-xs font-bold text-gray-500 dark:text-gray-400 uppercase tracking-wider">
+                        {t('proxy.config.cost.budget_action', { defaultValue: 'When Exceeded' })}
+                    </label>
+                    <select
+                        className="select select-sm select-bordered w-full"
+                        value={config.budget.action}
+                        onChange={(e) => onChange({ ...config, budget: { ...config.budget, action: e.target.value as BudgetAction } })}
+                    >
+                        <option value="warn">{t('proxy.config.cost.action_warn', { defaultValue: 'Warn only' })}</option>
+                        <option value="block">{t('proxy.config.cost.action_block', { defaultValue: 'Block requests' })}</option>
+                    </select>
+                </div>
+            </div>
+            <p className="text-[10px] text-gray-500 dark:text-gray-400">
+                {t('proxy.config.cost.budget_hint', {
+                    defaultValue: 'Global budgets cover all traffic and reset at UTC midnight / the 1st of the month. 0 = unlimited. Per-user budgets are set on each user token.',
+                })}
+            </p>
+        </div>
+    );
+}
@@ -0,0 +1,118 @@
+import { useEffect, useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { FileText, RefreshCw } from 'lucide-react';
+import { request } from '../../utils/request';
+import { FilesConfig, FileStoreStats } from '../../types/config';
+
+interface FilesSettingsProps {
+    config: FilesConfig;
+    onChange: (config: FilesConfig) => void;
+}
+
+type NumberField = 'max_file_size_mb' | 'max_total_size_mb' | 'ttl_days';
+
+const formatBytes = (bytes: number) => {
+    if (bytes < 1024) return `${bytes} B`;
+    if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+    if (bytes < 1024 * 1024 * 1024) return `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+    return `${(bytes / 1024 / 1024 / 1024).toFixed(2)} GB`;
+};
+
+export default function FilesSettings({ config, onChange }: FilesSettingsProps) {
+    const { t } = useTranslation();
+    const [stats, setStats] = useState<FileStoreStats | null>(null);
+
+    const loadStats = async () => {
+        try {
+            setStats(await request<FileStoreStats>('get_file_store_stats'));
+        } catch (error) {
+            console.error('Failed to load file store stats:', error);
+        }
+    };
+
+    useEffect(() => {
+        loadStats();
+    }, []);
+
+    // 数值输入: 非法值回退到最小值
+    const handleNumberChange = (field: NumberField, value: string, min: number) => {
+        const num = parseInt(value, 10);
+        onChange({ ...config, [field]: Math.max(min, isNaN(num) ? min : num) });
+    };
+
+    const inputCls = "w-full px-3 py-2 bg-gray-50 dark:bg-base-200 border border-gray-200 dark:border-base-300 rounded-lg focus:ring-2 focus:ring-teal-500 outline-none text-sm font-bold text-teal-600 dark:text-teal-400";
+
+    const fields: { field: NumberField; label: string; min: number }[] = [
+        { field: 'max_file_size_mb', label: t('proxy.config.files.max_file_size', { defaultValue: 'Max file size (MB)' }), min: 1 },
+        { field: 'max_total_size_mb', label: t('proxy.config.files.max_total_size', { defaultValue: 'Total storage (MB)' }), min: 1 },
+        { field: 'ttl_days', label: t('proxy.config.files.ttl_days', { defaultValue: 'Keep files (days)' }), min: 0 },
+    ];
+
+    const usage = stats && config.max_total_size_mb > 0
+        ? Math.min(100, (stats.stored_bytes / (config.max_total_size_mb * 1024 * 1024)) * 100)
+        : 0;
+
+    return (
+        <div className="space-y-6">
+            <div className="bg-teal-50/50 dark:bg-teal-900/10 border border-teal-100 dark:border-teal-800/30 rounded-lg p-4">
+                <div className="flex gap-3">
+                    <FileText className="w-5 h-5 text-teal-500 shrink-0 mt-0.5" />
+                    <div className="space-y-1">
+                        <h4 className="font-medium text-sm text-gray-900 dark:text-gray-100">
+                            {t('proxy.config.files.title', { defaultValue: 'Files API' })}
+                        </h4>
+                        <p className="text-xs text-gray-500 dark:text-gray-400 leading-relaxed">
+                            {t('proxy.config.files.tooltip', {
+                                defaultValue: 'Serves /v1/files for Anthropic and OpenAI clients. Uploads are kept on this machine, and any file_id used in a request is inlined before it goes upstream. Identical uploads are stored once.',
+                            })}
+                        </p>
+                    </div>
+                </div>
+            </div>
+
+            <div className="grid grid-cols-3 gap-4">
+                {fields.map(({ field, label, min }) => (
+                    <div key={field} className="space-y-1.5">
+                        <label className="text-xs font-bold text-gray-500 dark:text-gray-400 uppercase tracking-wider">
+                            {label}
+                        </label>
+                        <input
+                            type="number"
+                            min={min}
+                            className={inputCls}
+                            value={config[field]}
+                            onChange={(e) => handleNumberChange(field, e.target.value, min)}
+                        />
+                    </div>
+                ))}
+            </div>
+
+            <div className="space-y-2">
+                <div className="flex items-center justify-between">
+                    <div className="text-xs text-gray-500 dark:text-gray-400">
+                        {stats
+                            ? t('proxy.config.files.stats', {
+                                files: stats.files,
+                                stored: formatBytes(stats.stored_bytes),
+                                total: formatBytes(stats.total_bytes),
+                                defaultValue: '{{files}} files · {{stored}} on disk ({{total}} before dedup)',
+                            })
+                            : '-'}
+                    </div>
+                    <button
+                        onClick={loadStats}
+                        className="btn btn-xs btn-ghost gap-1 h-7 min-h-0 px-2 rounded-md"
+                    >
+                        <RefreshCw size={12} />
+                    </button>
+                </div>
+                <div className="h-1.5 rounded-full bg-gray-100 dark:bg-base-300 overflow-hidden">
+                    <div
+                        className={`h-full rounded-full ${usage > 90 ? 'bg-red-500' : 'bg-teal-500'}`}
+                        style={{ width: `${usage}%` }}
+                    />
+                </div>
+            </div>
+        </div>
+    );
+}
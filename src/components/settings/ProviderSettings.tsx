@@ -0,0 +1,268 @@
+import { useEffect, useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { Server, Plus, Trash2, RefreshCw } from 'lucide-react';
+import { request } from '../../utils/request';
+import { UpstreamProviderConfig, ProviderStatus, ProviderProtocol, ProviderDispatchMode } from '../../types/config';
+
+interface ProviderSettingsProps {
+    providers: UpstreamProviderConfig[];
+    onChange: (providers: UpstreamProviderConfig[]) => void;
+}
+
+const newProvider = (index: number): UpstreamProviderConfig => ({
+    id: `provider-${index + 1}`,
+    name: null,
+    enabled: true,
+    protocol: 'openai',
+    base_url: '',
+    api_keys: [],
+    models: [],
+    model_mapping: {},
+    dispatch_mode: 'exclusive',
+    rate_limit_cooldown_secs: 60,
+});
+
+// 列表输入: 每行 / 逗号分隔一项
+const parseList = (value: string): string[] =>
+    value.split(/[\n,]/).map((s) => s.trim()).filter(Boolean);
+
+// 模型映射: 每行 `客户端模型=上游模型`
+const formatMapping = (mapping: Record<string, string>) =>
+    Object.entries(mapping).map(([from, to]) => `${from}=${to}`).join('\n');
+
+const parseMapping = (value: string): Record<string, string> => {
+    const mapping: Record<string, string> = {};
+    for (const line of value.split('\n')) {
+        const [from, ...rest] = line.split('=');
+        const to = rest.join('=').trim();
+        if (from.trim() && to) mapping[from.trim()] = to;
+    }
+    return mapping;
+};
+
+export default function ProviderSettings({ providers, onChange }: ProviderSettingsProps) {
+    const { t } = useTranslation();
+    const [statuses, setStatuses] = useState<ProviderStatus[]>([]);
+
+    const loadStatuses = async () => {
+        try {
+            setStatuses(await request<ProviderStatus[]>('get_provider_statuses'));
+        } catch (error) {
+            console.error('Failed to load provider statuses:', error);
+        }
+    };
+
+    useEffect(() => {
+        loadStatuses();
+    }, []);
+
+    const updateProvider = (index: number, patch: Partial<UpstreamProviderConfig>) => {
+        onChange(providers.map((p, i) => (i === index ? { ...p, ...patch } : p)));
+    };
+
+    const inputCls = "w-full px-2 py-1.5 bg-gray-50 dark:bg-base-200 border border-gray-200 dark:border-base-300 rounded-lg focus:ring-2 focus:ring-blue-500 outline-none text-xs font-mono";
+    const labelCls = "text-[10px] font-bold text-gray-500 dark:text-gray-400 uppercase tracking-wider";
+
+    return (
+        <div className="space-y-6">
+            <div className="bg-blue-50/50 dark:bg-blue-900/10 border border-blue-100 dark:border-blue-800/30 rounded-lg p-4">
+                <div className="flex gap-3">
+                    <Server className="w-5 h-5 text-blue-500 shrink-0 mt-0.5" />
+                    <div className="space-y-1">
+                        <h4 className="font-medium text-sm text-gray-900 dark:text-gray-100">
+                            {t('proxy.config.providers.title', { defaultValue: 'Custom Upstreams' })}
+                        </h4>
+                        <p className="text-xs text-gray-500 dark:text-gray-400 leading-relaxed">
+                            {t('proxy.config.providers.tooltip', {
+                                defaultValue: 'Forward matching models to any OpenAI- or Anthropic-compatible endpoint (company gateway, vLLM, Ollama, other vendors). Requests are passed through in their own protocol: an OpenAI provider only serves /v1/chat/completions, an Anthropic provider only serves /v1/messages.',
+                            })}
+                        </p>
+                    </div>
+                </div>
+            </div>
+
+            <div className="flex items-center justify-between">
+                <label className="text-xs font-bold text-gray-500 dark:text-gray-400 uppercase tracking-wider">
+                    {t('proxy.config.providers.list', { defaultValue: 'Providers' })}
+                </label>
+                <div className="flex gap-2">
+                    <button
+                        onClick={loadStatuses}
+                        className="btn btn-xs btn-ghost gap-1 h-7 min-h-0 px-2 rounded-md border border-gray-200 dark:border-base-300"
+                    >
+                        <RefreshCw size={12} />
+                        {t('proxy.config.providers.refresh_status', { defaultValue: 'Refresh Status' })}
+                    </button>
+                    <button
+                        onClick={() => onChange([...providers, newProvider(providers.length)])}
+                        className="btn btn-xs btn-ghost gap-1 h-7 min-h-0 px-2 rounded-md border border-gray-200 dark:border-base-300"
+                    >
+                        <Plus size={12} />
+                        {t('proxy.config.providers.add', { defaultValue: 'Add' })}
+                    </button>
+                </div>
+            </div>
+
+            {providers.length === 0 && (
+                <div className="text-xs text-gray-400 italic">
+                    {t('proxy.config.providers.empty', { defaultValue: 'No custom upstreams configured.' })}
+                </div>
+            )}
+
+            {providers.map((provider, index) => {
+                const status = statuses.find((s) => s.id === provider.id);
+                return (
+                    <div key={index} className="p-4 rounded-xl border border-gray-100 dark:border-base-300 bg-gray-50/50 dark:bg-base-200/50 space-y-3">
+                        <div className="flex items-center justify-between gap-2">
+                            <div className="flex items-center gap-2">
+                                <input
+                                    type="checkbox"
+                                    className="toggle toggle-sm toggle-primary"
+                                    checked={provider.enabled}
+                                    onChange={(e) => updateProvider(index, { enabled: e.target.checked })}
+                                />
+                                <span className="text-sm font-bold text-gray-900 dark:text-base-content">
+                                    {provider.name || provider.id}
+                                </span>
+                                {status && (
+                                    <span className="text-[10px] text-gray-500 dark:text-gray-400">
+                                        {t('proxy.config.providers.status_summary', {
+                                            requests: status.requests,
+                                            errors: status.errors,
+                                            rate_limited: status.rate_limited,
+                                            defaultValue: '{{requests}} requests · {{errors}} errors · {{rate_limited}} rate limited',
+                                        })}
+                                        {status.last_status ? ` · HTTP ${status.last_status}` : ''}
+                                    </span>
+                                )}
+                            </div>
+                            <button
+                                onClick={() => onChange(providers.filter((_, i) => i !== index))}
+                                className="btn btn-xs btn-ghost text-red-500 h-7 min-h-0 px-2"
+                                title={t('common.delete', { defaultValue: 'Delete' })}
+                            >
+                                <Trash2 size={12} />
+                            </button>
+                        </div>
+
+                        <div className="grid grid-cols-2 md:grid-cols-4 gap-3">
+                            <div className="space-y-1">
+                                <label className={labelCls}>{t('proxy.config.providers.id', { defaultValue: 'ID' })}</label>
+                                <input
+                                    type="text"
+                                    className={inputCls}
+                                    value={provider.id}
+                                    onChange={(e) => updateProvider(index, { id: e.target.value.trim() })}
+                                />
+                            </div>
+                            <div className="space-y-1">
+                                <label className={labelCls}>{t('proxy.config.providers.name', { defaultValue: 'Name' })}</label>
+                                <input
+                                    type="text"
+                                    className={inputCls}
+                                    value={provider.name || ''}
+                                    onChange={(e) => updateProvider(index, { name: e.target.value || null })}
+                                />
+                            </div>
+                            <div className="space-y-1">
+                                <label className={labelCls}>{t('proxy.config.providers.protocol', { defaultValue: 'Protocol' })}</label>
+                                <select
+                                    className="select select-sm select-bordered w-full"
+                                    value={provider.protocol}
+                                    onChange={(e) => updateProvider(index, { protocol: e.target.value as ProviderProtocol })}
+                                >
+                                    <option value="openai">OpenAI</option>
+                                    <option value="anthropic">Anthropic</option>
+                                </select>
+                            </div>
+                            <div className="space-y-1">
+                                <label className={labelCls}>{t('proxy.config.providers.dispatch_mode', { defaultValue: 'Dispatch' })}</label>
+                                <select
+                                    className="select select-sm select-bordered w-full"
+                                    value={provider.dispatch_mode}
+                                    onChange={(e) => updateProvider(index, { dispatch_mode: e.target.value as ProviderDispatchMode })}
+                                >
+                                    <option value="exclusive">{t('proxy.config.providers.mode_exclusive', { defaultValue: 'Always' })}</option>
+                                    <option value="fallback">{t('proxy.config.providers.mode_fallback', { defaultValue: 'When pool exhausted' })}</option>
+                                </select>
+                            </div>
+                        </div>
+
+                        <div className="grid grid-cols-1 md:grid-cols-[1fr_140px] gap-3">
+                            <div className="space-y-1">
+                                <label className={labelCls}>{t('proxy.config.providers.base_url', { defaultValue: 'Base URL' })}</label>
+                                <input
+                                    type="text"
+                                    className={inputCls}
+                                    value={provider.base_url}
+                                    placeholder="https://llm.example.com"
+                                    onChange={(e) => updateProvider(index, { base_url: e.target.value.trim() })}
+                                />
+                            </div>
+                            <div className="space-y-1">
+                                <label className={labelCls}>{t('proxy.config.providers.cooldown', { defaultValue: '429 Cooldown (s)' })}</label>
+                                <input
+                                    type="number"
+                                    min="1"
+                                    className={inputCls}
+                                    value={provider.rate_limit_cooldown_secs}
+                                    onChange={(e) => updateProvider(index, { rate_limit_cooldown_secs: Math.max(1, parseInt(e.target.value, 10) || 60) })}
+                                />
+                            </div>
+                        </div>
+
+                        <div className="grid grid-cols-1 md:grid-cols-3 gap-3">
+                            <div className="space-y-1">
+                                <label className={labelCls}>{t('proxy.config.providers.api_keys', { defaultValue: 'API Keys (one per line)' })}</label>
+                                <textarea
+                                    rows={3}
+                                    className={inputCls}
+                                    defaultValue={provider.api_keys.join('\n')}
+                                    onBlur={(e) => updateProvider(index, { api_keys: parseList(e.target.value) })}
+                                />
+                                {status && status.keys.length > 0 && (
+                                    <div className="space-y-0.5">
+                                        {status.keys.map((key) => (
+                                            <div key={key.key} className="text-[10px] font-mono text-gray-500 dark:text-gray-400">
+                                                {key.key} · {key.requests}
+                                                {key.cooldown_secs > 0 && (
+                                                    <span className="text-amber-500">
+                                                        {' '}· {t('proxy.config.providers.cooling_down', { secs: key.cooldown_secs, defaultValue: 'cooling down {{secs}}s' })}
+                                                    </span>
+                                                )}
+                                            </div>
+                                        ))}
+                                    </div>
+                                )}
+                            </div>
+                            <div className="space-y-1">
+                                <label className={labelCls}>{t('proxy.config.providers.models', { defaultValue: 'Models (* wildcard)' })}</label>
+                                <textarea
+                                    rows={3}
+                                    className={inputCls}
+                                    defaultValue={provider.models.join('\n')}
+                                    placeholder="qwen-*"
+                                    onBlur={(e) => updateProvider(index, { models: parseList(e.target.value) })}
+                                />
+                            </div>
+                            <div className="space-y-1">
+                                <label className={labelCls}>{t('proxy.config.providers.model_mapping', { defaultValue: 'Model Mapping (client=upstream)' })}</label>
+                                <textarea
+                                    rows={3}
+                                    className={inputCls}
+                                    defaultValue={formatMapping(provider.model_mapping)}
+                                    placeholder="qwen-*=Qwen/Qwen2.5-72B-Instruct"
+                                    onBlur={(e) => updateProvider(index, { model_mapping: parseMapping(e.target.value) })}
+                                />
+                            </div>
+                        </div>
+
+                        {status?.last_error && (
+                            <div className="text-[10px] text-red-500 font-mono break-all">{status.last_error}</div>
+                        )}
+                    </div>
+                );
+            })}
+        </div>
+    );
+}
@@ -0,0 +1,161 @@
+import { useEffect, useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { Database, Trash2, RefreshCw } from 'lucide-react';
+import { request } from '../../utils/request';
+import { ResponseCacheConfig, ResponseCacheStats } from '../../types/config';
+import { showToast } from '../common/ToastContainer';
+
+interface ResponseCacheProps {
+    config: ResponseCacheConfig;
+    onChange: (config: ResponseCacheConfig) => void;
+}
+
+const formatBytes = (bytes: number) => {
+    if (bytes < 1024) return `${bytes} B`;
+    if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+    return `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+};
+
+export default function ResponseCache({ config, onChange }: ResponseCacheProps) {
+    const { t } = useTranslation();
+    const [stats, setStats] = useState<ResponseCacheStats | null>(null);
+    const [loading, setLoading] = useState(false);
+
+    const loadStats = async () => {
+        try {
+            setStats(await request<ResponseCacheStats>('get_response_cache_stats'));
+        } catch (error) {
+            console.error('Failed to load response cache stats:', error);
+        }
+    };
+
+    useEffect(() => {
+        loadStats();
+    }, []);
+
+    const handleClear = async () => {
+        setLoading(true);
+        try {
+            const removed = await request<number>('clear_response_cache');
+            showToast(t('proxy.config.response_cache.cleared', { count: removed, defaultValue: 'Cleared {{count}} cached responses' }), 'success');
+            await loadStats();
+        } catch (error) {
+            showToast(String(error), 'error');
+        } finally {
+            setLoading(false);
+        }
+    };
+
+    // 数值输入: 非法值回退到最小值
+    const handleNumberChange = (field: 'ttl_secs' | 'max_entries' | 'max_size_mb', value: string, min: number) => {
+        const num = parseInt(value, 10);
+        onChange({ ...config, [field]: Math.max(min, isNaN(num) ? min : num) });
+    };
+
+    const inputCls = "w-full px-3 py-2 bg-gray-50 dark:bg-base-200 border border-gray-200 dark:border-base-300 rounded-lg focus:ring-2 focus:ring-emerald-500 outline-none text-sm font-bold text-emerald-600 dark:text-emerald-400";
+
+    return (
+        <div className="space-y-6">
+            <div className="bg-emerald-50/50 dark:bg-emerald-900/10 border border-emerald-100 dark:border-emerald-800/30 rounded-lg p-4">
+                <div className="flex gap-3">
+                    <Database className="w-5 h-5 text-emerald-500 shrink-0 mt-0.5" />
+                    <div className="space-y-1">
+                        <h4 className="font-medium text-sm text-gray-900 dark:text-gray-100">
+                            {t('proxy.config.response_cache.title', { defaultValue: 'Response Cache' })}
+                        </h4>
+                        <p className="text-xs text-gray-500 dark:text-gray-400 leading-relaxed">
+                            {t('proxy.config.response_cache.tooltip', {
+                                defaultValue: 'Replays stored responses for identical deterministic requests (temperature = 0) without calling upstream. Send "Cache-Control: no-cache" to bypass a lookup or "no-store" to skip writing.',
+                            })}
+                        </p>
+                    </div>
+                </div>
+            </div>
+
+            <div className="grid grid-cols-3 gap-4">
+                <div className="space-y-1.5">
+                    <label className="text-xs font-bold text-gray-500 dark:text-gray-400 uppercase tracking-wider">
+                        {t('proxy.config.response_cache.ttl', { defaultValue: 'TTL (seconds)' })}
+                    </label>
+                    <input
+                        type="number"
+                        min="60"
+                        className={inputCls}
+                        value={config.ttl_secs}
+                        onChange={(e) => handleNumberChange('ttl_secs', e.target.value, 60)}
+                    />
+                </div>
+                <div className="space-y-1.5">
+                    <label className="text-xs font-bold text-gray-500 dark:text-gray-400 uppercase tracking-wider">
+                        {t('proxy.config.response_cache.max_entries', { defaultValue: 'Max Entries' })}
+                    </label>
+                    <input
+                        type="number"
+                        min="1"
+                        className={inputCls}
+                        value={config.max_entries}
+                        onChange={(e) => handleNumberChange('max_entries', e.target.value, 1)}
+                    />
+                </div>
+                <div className="space-y-1.5">
+                    <label className="text-xs font-bold text-gray-500 dark:text-gray-400 uppercase tracking-wider">
+                        {t('proxy.config.response_cache.max_size_mb', { defaultValue: 'Max Size (MB)' })}
+                    </label>
+                    <input
+                        type="number"
+                        min="1"
+                        className={inputCls}
+                        value={config.max_size_mb}
+                        onChange={(e) => handleNumberChange('max_size_mb', e.target.value, 1)}
+                    />
+                </div>
+            </div>
+
+            <div className="flex items-center justify-between p-3 bg-gray-50 dark:bg-base-200 rounded-xl border border-gray-100 dark:border-base-300">
+                <div className="space-y-0.5">
+                    <div className="text-sm font-bold text-gray-900 dark:text-base-content">
+                        {t('proxy.config.response_cache.require_zero_temperature', { defaultValue: 'Only cache temperature = 0' })}
+                    </div>
+                    <p className="text-[10px] text-gray-500 dark:text-gray-400">
+                        {t('proxy.config.response_cache.require_zero_temperature_desc', { defaultValue: 'When disabled, any identical request may be answered from cache regardless of sampling settings.' })}
+                    </p>
+                </div>
+                <input
+                    type="checkbox"
+                    className="toggle toggle-sm toggle-success"
+                    checked={config.require_zero_temperature}
+                    onChange={(e) => onChange({ ...config, require_zero_temperature: e.target.checked })}
+                />
+            </div>
+
+            <div className="flex items-center justify-between">
+                <div className="text-xs text-gray-500 dark:text-gray-400">
+                    {stats
+                        ? t('proxy.config.response_cache.stats', {
+                            entries: stats.entries,
+                            size: formatBytes(stats.total_bytes),
+                            hits: stats.total_hits,
+                            defaultValue: '{{entries}} entries · {{size}} · {{hits}} hits',
+                        })
+                        : '-'}
+                </div>
+                <div className="flex items-center gap-2">
+                    <button
+                        onClick={loadStats}
+                        className="btn btn-xs btn-ghost gap-1 h-7 min-h-0 px-2 rounded-md"
+                    >
+                        <RefreshCw size={12} />
+                    </button>
+                    <button
+                        onClick={handleClear}
+                        disabled={loading}
+                        className="btn btn-xs btn-ghost text-red-500 hover:bg-red-50 dark:hover:bg-red-900/20 gap-1 h-7 min-h-0 px-2 rounded-md border border-red-200 dark:border-red-800/50 shadow-sm"
+                    >
+                        <Trash2 size={12} />
+                        {t('proxy.config.response_cache.clear', { defaultValue: 'Clear Cache' })}
+                    </button>
+                </div>
+            </div>
+        </div>
+    );
+}
@@ -0,0 +1,65 @@
+import { useTranslation } from 'react-i18next';
+import { Braces } from 'lucide-react';
+import { StructuredOutputConfig, StrictSchemaMode, SchemaMismatchAction } from '../../types/config';
+
+interface StructuredOutputSettingsProps {
+    config: StructuredOutputConfig;
+    onChange: (config: StructuredOutputConfig) => void;
+}
+
+export default function StructuredOutputSettings({ config, onChange }: StructuredOutputSettingsProps) {
+    const { t } = useTranslation();
+
+    const labelCls = "text-xs font-bold text-gray-500 dark:text-gray-400 uppercase tracking-wider";
+
+    return (
+        <div className="space-y-6">
+            <div className="bg-indigo-50/50 dark:bg-indigo-900/10 border border-indigo-100 dark:border-indigo-800/30 rounded-lg p-4">
+                <div className="flex gap-3">
+                    <Braces className="w-5 h-5 text-indigo-500 shrink-0 mt-0.5" />
+                    <div className="space-y-1">
+                        <h4 className="font-medium text-sm text-gray-900 dark:text-gray-100">
+                            {t('proxy.config.structured_output.title', { defaultValue: 'Structured Outputs' })}
+                        </h4>
+                        <p className="text-xs text-gray-500 dark:text-gray-400 leading-relaxed">
+                            {t('proxy.config.structured_output.tooltip', {
+                                defaultValue: 'JSON schemas from Claude output_format and OpenAI response_format are sent to Gemini as responseSchema. In strict mode the proxy buffers the answer, validates it against the original schema and retries or returns an error when it does not match. Strict requests are not streamed until validation passes.',
+                            })}
+                        </p>
+                    </div>
+                </div>
+            </div>
+
+            <div className="grid grid-cols-2 gap-4">
+                <div className="space-y-1.5">
+                    <label className={labelCls}>
+                        {t('proxy.config.structured_output.strict', { defaultValue: 'Validation' })}
+                    </label>
+                    <select
+                        className="select select-sm select-bordered w-full"
+                        value={config.strict}
+                        onChange={(e) => onChange({ ...config, strict: e.target.value as StrictSchemaMode })}
+                    >
+                        <option value="off">{t('proxy.config.structured_output.strict_off', { defaultValue: 'Off' })}</option>
+                        <option value="request">{t('proxy.config.structured_output.strict_request', { defaultValue: 'When the request is strict' })}</option>
+                        <option value="always">{t('proxy.config.structured_output.strict_always', { defaultValue: 'Always' })}</option>
+                    </select>
+                </div>
+                <div className="space-y-1.5">
+                    <label className={labelCls}>
+                        {t('proxy.config.structured_output.on_mismatch', { defaultValue: 'On Mismatch' })}
+                    </label>
+                    <select
+                        className="select select-sm select-bordered w-full"
+                        value={config.on_mismatch}
+                        disabled={config.strict === 'off'}
+                        onChange={(e) => onChange({ ...config, on_mismatch: e.target.value as SchemaMismatchAction })}
+                    >
+                        <option value="retry">{t('proxy.config.structured_output.action_retry', { defaultValue: 'Retry once, then error' })}</option>
+                        <option value="error">{t('proxy.config.structured_output.action_error', { defaultValue: 'Return error' })}</option>
+                    </select>
+                </div>
+            </div>
+        </div>
+    );
+}
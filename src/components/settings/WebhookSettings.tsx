@@ -0,0 +1,325 @@
+import { useEffect, useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { Bell, Plus, Trash2, Send, RefreshCw, CheckCircle, XCircle } from 'lucide-react';
+import { request } from '../../utils/request';
+import { WebhookConfig, WebhookDelivery, WebhookEndpoint, WebhookEvent, WebhookPayloadFormat } from '../../types/config';
+import { showToast } from '../common/ToastContainer';
+
+interface WebhookSettingsProps {
+    config: WebhookConfig;
+    onChange: (config: WebhookConfig) => void;
+}
+
+export const DEFAULT_WEBHOOK_CONFIG: WebhookConfig = {
+    enabled: false,
+    endpoints: [],
+    max_retries: 3,
+    retry_base_secs: 5,
+    cooldown_secs: 300,
+};
+
+const EVENTS: WebhookEvent[] = [
+    'account_forbidden',
+    'quota_protection_triggered',
+    'model_rate_limited',
+    'proxy_unhealthy',
+    'user_token_expired',
+    'tunnel_down',
+    'budget_exceeded',
+];
+
+const FORMATS: WebhookPayloadFormat[] = ['generic', 'slack', 'discord', 'custom'];
+
+const TEMPLATE_PLACEHOLDERS = ['event', 'severity', 'title', 'message', 'subject', 'timestamp', 'time', 'color', 'details'];
+
+const CUSTOM_TEMPLATE_EXAMPLE = '{\n  "alert": "{{title}}",\n  "text": "{{message}}",\n  "at": "{{time}}",\n  "data": "{{details}}"\n}';
+
+export default function WebhookSettings({ config, onChange }: WebhookSettingsProps) {
+    const { t } = useTranslation();
+    const [deliveries, setDeliveries] = useState<WebhookDelivery[]>([]);
+    const [testingId, setTestingId] = useState<string | null>(null);
+
+    const loadDeliveries = async () => {
+        try {
+            setDeliveries(await request<WebhookDelivery[]>('get_webhook_deliveries', { limit: 20, offset: 0 }));
+        } catch (error) {
+            console.error('Failed to load webhook deliveries:', error);
+        }
+    };
+
+    useEffect(() => {
+        loadDeliveries();
+    }, []);
+
+    const updateEndpoint = (id: string, updates: Partial<WebhookEndpoint>) => {
+        onChange({
+            ...config,
+            endpoints: config.endpoints.map(ep => ep.id === id ? { ...ep, ...updates } : ep),
+        });
+    };
+
+    const addEndpoint = () => {
+        onChange({
+            ...config,
+            endpoints: [
+                ...config.endpoints,
+                { id: `webhook_${Date.now()}`, name: '', url: '', enabled: true, events: [], format: 'generic' },
+            ],
+        });
+    };
+
+    const removeEndpoint = (id: string) => {
+        onChange({ ...config, endpoints: config.endpoints.filter(ep => ep.id !== id) });
+    };
+
+    const toggleEvent = (endpoint: WebhookEndpoint, event: WebhookEvent) => {
+        const events = endpoint.events.includes(event)
+            ? endpoint.events.filter(e => e !== event)
+            : [...endpoint.events, event];
+        updateEndpoint(endpoint.id, { events });
+    };
+
+    const handleTest = async (endpoint: WebhookEndpoint) => {
+        setTestingId(endpoint.id);
+        try {
+            const result = await request<WebhookDelivery>('test_webhook', { endpoint });
+            if (result.success) {
+                showToast(t('settings.webhooks.test_success', { defaultValue: 'Test notification delivered' }), 'success');
+            } else {
+                showToast(`${t('settings.webhooks.test_failed', { defaultValue: 'Test notification failed' })}: ${result.error || ''}`, 'error');
+            }
+            await loadDeliveries();
+        } catch (error) {
+            showToast(String(error), 'error');
+        } finally {
+            setTestingId(null);
+        }
+    };
+
+    const handleClearLog = async () => {
+        try {
+            await request<number>('clear_webhook_deliveries');
+            setDeliveries([]);
+        } catch (error) {
+            showToast(String(error), 'error');
+        }
+    };
+
+    const handleNumberChange = (field: 'max_retries' | 'retry_base_secs' | 'cooldown_secs', value: string) => {
+        const num = parseInt(value, 10);
+        onChange({ ...config, [field]: Math.max(0, isNaN(num) ? 0 : num) });
+    };
+
+    const inputCls = "w-full px-3 py-2 bg-gray-50 dark:bg-base-200 border border-gray-200 dark:border-base-300 rounded-lg focus:ring-2 focus:ring-sky-500 outline-none text-sm";
+
+    return (
+        <div className="animate-in fade-in duration-500">
+            <div className="flex items-center justify-between">
+                <div className="flex items-center gap-4">
+                    <div className="w-10 h-10 rounded-xl bg-sky-50 dark:bg-sky-900/20 flex items-center justify-center text-sky-500 group-hover:bg-sky-500 group-hover:text-white transition-all duration-300">
+                        <Bell size={20} />
+                    </div>
+                    <div>
+                        <div className="font-bold text-gray-900 dark:text-gray-100">
+                            {t('settings.webhooks.title')}
+                        </div>
+                        <p className="text-xs text-gray-500 dark:text-gray-400 mt-0.5">
+                            {t('settings.webhooks.desc')}
+                        </p>
+                    </div>
+                </div>
+                <label className="relative inline-flex items-center cursor-pointer">
+                    <input
+                        type="checkbox"
+                        className="sr-only peer"
+                        checked={config.enabled}
+                        onChange={(e) => onChange({ ...config, enabled: e.target.checked })}
+                    />
+                    <div className="w-11 h-6 bg-gray-200 dark:bg-base-300 peer-focus:outline-none rounded-full peer peer-checked:after:translate-x-full peer-checked:after:border-white after:content-[''] after:absolute after:top-[2px] after:left-[2px] after:bg-white after:border-gray-300 after:border after:rounded-full after:h-5 after:w-5 after:transition-all peer-checked:bg-sky-500 shadow-inner"></div>
+                </label>
+            </div>
+
+            {config.enabled && (
+                <div className="mt-5 pt-5 border-t border-gray-100 dark:border-base-200 space-y-6 animate-in slide-in-from-top-1 duration-200">
+                    {/* 全局投递参数 */}
+                    <div className="grid grid-cols-3 gap-4">
+                        <div className="space-y-1.5">
+                            <label className="text-xs font-bold text-gray-500 dark:text-gray-400 uppercase tracking-wider">
+                                {t('settings.webhooks.max_retries')}
+                            </label>
+                            <input type="number" min="0" className={inputCls} value={config.max_retries}
+                                onChange={(e) => handleNumberChange('max_retries', e.target.value)} />
+                        </div>
+                        <div className="space-y-1.5">
+                            <label className="text-xs font-bold text-gray-500 dark:text-gray-400 uppercase tracking-wider">
+                                {t('settings.webhooks.retry_base_secs')}
+                            </label>
+                            <input type="number" min="0" className={inputCls} value={config.retry_base_secs}
+                                onChange={(e) => handleNumberChange('retry_base_secs', e.target.value)} />
+                        </div>
+                        <div className="space-y-1.5">
+                            <label className="text-xs font-bold text-gray-500 dark:text-gray-400 uppercase tracking-wider">
+                                {t('settings.webhooks.cooldown_secs')}
+                            </label>
+                            <input type="number" min="0" className={inputCls} value={config.cooldown_secs}
+                                onChange={(e) => handleNumberChange('cooldown_secs', e.target.value)} />
+                        </div>
+                    </div>
+
+                    {/* 端点列表 */}
+                    <div className="space-y-3">
+                        {config.endpoints.map((endpoint) => (
+                            <div key={endpoint.id} className="p-4 rounded-xl border border-gray-100 dark:border-base-300 bg-gray-50/50 dark:bg-base-200/50 space-y-3">
+                                <div className="flex items-center gap-2">
+                                    <input
+                                        type="checkbox"
+                                        className="toggle toggle-xs toggle-info"
+                                        checked={endpoint.enabled}
+                                        onChange={(e) => updateEndpoint(endpoint.id, { enabled: e.target.checked })}
+                                    />
+                                    <input
+                                        type="text"
+                                        className={`${inputCls} max-w-[160px]`}
+                                        placeholder={t('settings.webhooks.name_placeholder')}
+                                        value={endpoint.name}
+                                        onChange={(e) => updateEndpoint(endpoint.id, { name: e.target.value })}
+                                    />
+                                    <input
+                                        type="text"
+                                        className={`${inputCls} flex-1 font-mono`}
+                                        placeholder="https://hooks.slack.com/services/..."
+                                        value={endpoint.url}
+                                        onChange={(e) => updateEndpoint(endpoint.id, { url: e.target.value })}
+                                    />
+                                    <select
+                                        className={`${inputCls} max-w-[110px]`}
+                                        value={endpoint.format}
+                                        onChange={(e) => updateEndpoint(endpoint.id, { format: e.target.value as WebhookPayloadFormat })}
+                                    >
+                                        {FORMATS.map(f => (
+                                            <option key={f} value={f}>{t(`settings.webhooks.formats.${f}`)}</option>
+                                        ))}
+                                    </select>
+                                    <button
+                                        onClick={() => handleTest(endpoint)}
+                                        disabled={!endpoint.url || testingId === endpoint.id}
+                                        className="btn btn-xs btn-ghost text-sky-500 h-8 min-h-0 px-2"
+                                        title={t('settings.webhooks.test')}
+                                    >
+                                        {testingId === endpoint.id ? <span className="loading loading-spinner loading-xs"></span> : <Send size={14} />}
+                                    </button>
+                                    <button
+                                        onClick={() => removeEndpoint(endpoint.id)}
+                                        className="btn btn-xs btn-ghost text-red-500 h-8 min-h-0 px-2"
+                                        title={t('common.delete')}
+                                    >
+                                        <Trash2 size={14} />
+                                    </button>
+                                </div>
+
+                                <div className="space-y-1.5">
+                                    <label className="text-[10px] font-bold text-gray-400 dark:text-gray-500 uppercase tracking-wider">
+                                        {t('settings.webhooks.events')}
+                                        <span className="ml-2 normal-case font-normal">{t('settings.webhooks.events_hint')}</span>
+                                    </label>
+                                    <div className="flex flex-wrap gap-1.5">
+                                        {EVENTS.map(event => {
+                                            const selected = endpoint.events.includes(event);
+                                            return (
+                                                <button
+                                                    key={event}
+                                                    onClick={() => toggleEvent(endpoint, event)}
+                                                    className={`px-2 py-1 rounded-md text-[11px] border transition-colors ${selected
+                                                        ? 'bg-sky-50 dark:bg-sky-900/20 border-sky-300 dark:border-sky-700 text-sky-600 dark:text-sky-400'
+                                                        : 'border-gray-200 dark:border-base-300 text-gray-500 dark:text-gray-400'}`}
+                                                >
+                                                    {t(`settings.webhooks.event_names.${event}`)}
+                                                </button>
+                                            );
+                                        })}
+                                    </div>
+                                </div>
+
+                                {endpoint.format === 'custom' && (
+                                    <div className="space-y-1.5">
+                                        <label className="text-[10px] font-bold text-gray-400 dark:text-gray-500 uppercase tracking-wider">
+                                            {t('settings.webhooks.template')}
+                                        </label>
+                                        <textarea
+                                            className={`${inputCls} font-mono text-xs h-28`}
+                                            placeholder={CUSTOM_TEMPLATE_EXAMPLE}
+                                            value={endpoint.template || ''}
+                                            onChange={(e) => updateEndpoint(endpoint.id, { template: e.target.value })}
+                                        />
+                                        <p className="text-[10px] text-gray-400 dark:text-gray-500">
+                                            {t('settings.webhooks.template_hint')} <span className="font-mono">{TEMPLATE_PLACEHOLDERS.map(p => `{{${p}}}`).join(' ')}</span>
+                                        </p>
+                                    </div>
+                                )}
+
+                                <input
+                                    type="password"
+                                    className={`${inputCls} font-mono`}
+                                    placeholder={t('settings.webhooks.secret_placeholder')}
+                                    value={endpoint.secret || ''}
+                                    onChange={(e) => updateEndpoint(endpoint.id, { secret: e.target.value || null })}
+                                />
+                            </div>
+                        ))}
+
+                        <button
+                            onClick={addEndpoint}
+                            className="btn btn-xs btn-ghost text-sky-500 hover:bg-sky-50 dark:hover:bg-sky-900/20 gap-1 h-7 min-h-0 px-2 rounded-md border border-sky-200 dark:border-sky-800/50 shadow-sm"
+                        >
+                            <Plus size={14} />
+                            {t('settings.webhooks.add_endpoint')}
+                        </button>
+                    </div>
+
+                    {/* 投递日志 */}
+                    <div className="space-y-2">
+                        <div className="flex items-center justify-between">
+                            <label className="text-xs font-bold text-gray-500 dark:text-gray-400 uppercase tracking-wider">
+                                {t('settings.webhooks.delivery_log')}
+                            </label>
+                            <div className="flex items-center gap-1">
+                                <button onClick={loadDeliveries} className="btn btn-xs btn-ghost h-7 min-h-0 px-2">
+                                    <RefreshCw size={12} />
+                                </button>
+                                <button onClick={handleClearLog} className="btn btn-xs btn-ghost text-red-500 h-7 min-h-0 px-2">
+                                    <Trash2 size={12} />
+                                </button>
+                            </div>
+                        </div>
+                        {deliveries.length === 0 ? (
+                            <p className="text-xs text-gray-400 dark:text-gray-500">{t('settings.webhooks.no_deliveries')}</p>
+                        ) : (
+                            <div className="max-h-60 overflow-y-auto rounded-lg border border-gray-100 dark:border-base-300">
+                                <table className="table table-xs w-full">
+                                    <tbody className="font-mono text-[11px]">
+                                        {deliveries.map(d => (
+                                            <tr key={d.id} title={d.error || d.payload}>
+                                                <td className="w-5">
+                                                    {d.success
+                                                        ? <CheckCircle size={12} className="text-green-500" />
+                                                        : <XCircle size={12} className="text-red-500" />}
+                                                </td>
+                                                <td className="text-gray-500 whitespace-nowrap">{new Date(d.created_at * 1000).toLocaleString()}</td>
+                                                <td>{d.event}</td>
+                                                <td className="truncate max-w-[160px]">{d.subject}</td>
+                                                <td className="truncate max-w-[120px]">{d.endpoint_name}</td>
+                                                <td className="text-right whitespace-nowrap">
+                                                    {d.status_code ?? '-'} · {t('settings.webhooks.attempts', { count: d.attempts })}
+                                                </td>
+                                            </tr>
+                                        ))}
+                                    </tbody>
+                                </table>
+                            </div>
+                        )}
+                    </div>
+                </div>
+            )}
+        </div>
+    );
+}
@@ -545,7 +545,14 @@ function Accounts() {
       }
 
       const exportData = response.accounts;
-      const content = JSON.stringify(exportData, null, 2);
+      let content = JSON.stringify(exportData, null, 2);
+
+      // 可选口令加密 (取消 = 放弃导出，留空 = 明文)
+      const passphrase = window.prompt(t("accounts.export_passphrase_prompt"));
+      if (passphrase === null) return;
+      if (passphrase) {
+        content = await invoke("seal_account_export", { content, passphrase });
+      }
       const fileName = `antigravity_accounts_${new Date().toISOString().split("T")[0]}.json`;
 
       // 2. Determine Path & Export
@@ -623,6 +630,19 @@ function Accounts() {
       return;
     }
 
+    // 加密导出文件: 先用口令解开
+    if (importData && !Array.isArray(importData) && (importData as any).ag_encrypted_export) {
+      const passphrase = window.prompt(t("accounts.import_passphrase_prompt"));
+      if (!passphrase) return;
+      try {
+        const plain: string = await invoke("open_account_export", { content, passphrase });
+        importData = JSON.parse(plain);
+      } catch (error) {
+        showToast(t("accounts.import_fail", { error: String(error) }), "error");
+        return;
+      }
+    }
+
     if (!Array.isArray(importData) || importData.length === 0) {
       showToast(t("accounts.import_invalid_format"), "error");
       return;
@@ -22,9 +22,15 @@ import {
     Check,
     X,
     Edit2,
-    Save
+    Save,
+    Database,
+    DollarSign,
+    Server,
+    Braces,
+    Layers,
+    FileText
 } from 'lucide-react';
-import { AppConfig, ProxyConfig, StickySessionConfig, ExperimentalConfig } from '../types/config';
+import { AppConfig, ProxyConfig, StickySessionConfig, ExperimentalConfig, ResponseCacheConfig, CostConfig, StructuredOutputConfig, BatchConfig, FilesConfig } from '../types/config';
 import HelpTooltip from '../components/common/HelpTooltip';
 import ModalDialog from '../components/common/ModalDialog';
 import { showToast } from '../components/common/ToastContainer';
@@ -36,6 +42,12 @@ import DebouncedSlider from '../components/common/DebouncedSlider';
 import { listAccounts } from '../services/accountService';
 import CircuitBreaker from '../components/settings/CircuitBreaker';
 import AdvancedThinking from '../components/settings/AdvancedThinking';
+import ResponseCache from '../components/settings/ResponseCache';
+import CostSettings from '../components/settings/CostSettings';
+import ProviderSettings from '../components/settings/ProviderSettings';
+import StructuredOutputSettings from '../components/settings/StructuredOutputSettings';
+import BatchSettings from '../components/settings/BatchSettings';
+import FilesSettings from '../components/settings/FilesSettings';
 import { CircuitBreakerConfig } from '../types/config';
 
 interface ProxyStatus {
@@ -45,6 +57,39 @@ interface ProxyStatus {
     active_accounts: number;
 }
 
+const DEFAULT_RESPONSE_CACHE: ResponseCacheConfig = {
+    enabled: false,
+    ttl_secs: 86400,
+    max_entries: 10000,
+    max_size_mb: 512,
+    require_zero_temperature: true,
+};
+
+const DEFAULT_COST_CONFIG: CostConfig = {
+    prices: [],
+    budget: { daily_usd: 0, monthly_usd: 0, action: 'warn' },
+};
+
+const DEFAULT_STRUCTURED_OUTPUT: StructuredOutputConfig = {
+    strict: 'request',
+    on_mismatch: 'retry',
+};
+
+const DEFAULT_BATCH_CONFIG: BatchConfig = {
+    enabled: true,
+    concurrency: 2,
+    max_interactive_in_flight: 0,
+    max_attempts: 5,
+    max_requests_per_batch: 100000,
+    retention_days: 29,
+};
+
+const DEFAULT_FILES_CONFIG: FilesConfig = {
+    max_file_size_mb: 100,
+    max_total_size_mb: 10240,
+    ttl_days: 30,
+};
+
 interface CustomPreset {
     id: string;
     name: string;
@@ -1926,6 +1971,76 @@ print(response.choices[0].message.content)`;
                                 />
                             </CollapsibleCard>
 
+                            {/* [NEW] 响应缓存 */}
+                            <CollapsibleCard
+                                title={t('proxy.config.response_cache.title', { defaultValue: 'Response Cache' })}
+                                icon={<Database size={18} className="text-emerald-500" />}
+                                enabled={!!appConfig.proxy.response_cache?.enabled}
+                                onToggle={(enabled) => updateProxyConfig({ response_cache: { ...(appConfig.proxy.response_cache || DEFAULT_RESPONSE_CACHE), enabled } })}
+                            >
+                                <ResponseCache
+                                    config={appConfig.proxy.response_cache || DEFAULT_RESPONSE_CACHE}
+                                    onChange={(response_cache) => updateProxyConfig({ response_cache })}
+                                />
+                            </CollapsibleCard>
+
+                            {/* [NEW] 费用核算与预算 */}
+                            <CollapsibleCard
+                                title={t('proxy.config.cost.title', { defaultValue: 'Cost & Budgets' })}
+                                icon={<DollarSign size={18} className="text-emerald-500" />}
+                            >
+                                <CostSettings
+                                    config={appConfig.proxy.cost || DEFAULT_COST_CONFIG}
+                                    onChange={(cost) => updateProxyConfig({ cost })}
+                                />
+                            </CollapsibleCard>
+
+                            {/* [NEW] 自定义上游 (OpenAI / Anthropic 兼容) */}
+                            <CollapsibleCard
+                                title={t('proxy.config.providers.title', { defaultValue: 'Custom Upstreams' })}
+                                icon={<Server size={18} className="text-blue-500" />}
+                            >
+                                <ProviderSettings
+                                    providers={appConfig.proxy.providers || []}
+                                    onChange={(providers) => updateProxyConfig({ providers })}
+                                />
+                            </CollapsibleCard>
+
+                            {/* [NEW] 结构化输出 (JSON Schema) */}
+                            <CollapsibleCard
+                                title={t('proxy.config.structured_output.title', { defaultValue: 'Structured Outputs' })}
+                                icon={<Braces size={18} className="text-indigo-500" />}
+                            >
+                                <StructuredOutputSettings
+                                    config={appConfig.proxy.structured_output || DEFAULT_STRUCTURED_OUTPUT}
+                                    onChange={(structured_output) => updateProxyConfig({ structured_output })}
+                                />
+                            </CollapsibleCard>
+
+                            {/* [NEW] 批处理队列 */}
+                            <CollapsibleCard
+                                title={t('proxy.config.batch.title', { defaultValue: 'Batch Queue' })}
+                                icon={<Layers size={18} className="text-sky-500" />}
+                                enabled={appConfig.proxy.batch?.enabled ?? true}
+                                onToggle={(enabled) => updateProxyConfig({ batch: { ...(appConfig.proxy.batch || DEFAULT_BATCH_CONFIG), enabled } })}
+                            >
+                                <BatchSettings
+                                    config={appConfig.proxy.batch || DEFAULT_BATCH_CONFIG}
+                                    onChange={(batch) => updateProxyConfig({ batch })}
+                                />
+                            </CollapsibleCard>
+
+                            {/* [NEW] Files API */}
+                            <CollapsibleCard
+                                title={t('proxy.config.files.title', { defaultValue: 'Files API' })}
+                                icon={<FileText size={18} className="text-teal-500" />}
+                            >
+                                <FilesSettings
+                                    config={appConfig.proxy.files || DEFAULT_FILES_CONFIG}
+                                    onChange={(files) => updateProxyConfig({ files })}
+                                />
+                            </CollapsibleCard>
+
                             {/* 实验性设置 */}
                             <CollapsibleCard
                                 title={t('proxy.config.experimental.title')}
@@ -17,6 +17,9 @@ import { relaunch } from '@tauri-apps/plugin-process';
 
 import DebugConsole from '../components/debug/DebugConsole';
 import ProxyPoolSettings from '../components/settings/ProxyPoolSettings';
+import WebhookSettings, { DEFAULT_WEBHOOK_CONFIG } from '../components/settings/WebhookSettings';
+import AuditLogViewer from '../components/settings/AuditLogViewer';
+import AdminUserSettings from '../components/settings/AdminUserSettings';
 
 
 function Settings() {
@@ -1073,6 +1076,33 @@ function Settings() {
                                     </div>
                                 </div>
 
+
+                                {/* [NEW] Webhook 通知 */}
+                                <div className="group bg-white dark:bg-base-100 rounded-xl p-5 border border-gray-100 dark:border-base-200 hover:border-sky-200 transition-all duration-300 shadow-sm">
+                                    <WebhookSettings
+                                        config={formData.webhooks || DEFAULT_WEBHOOK_CONFIG}
+                                        onChange={(newConfig) => setFormData({
+                                            ...formData,
+                                            webhooks: newConfig
+                                        })}
+                                    />
+                                </div>
+
+                                {/* [NEW] 审计日志 */}
+                                <div className="group bg-white dark:bg-base-100 rounded-xl p-5 border border-gray-100 dark:border-base-200 hover:border-indigo-200 transition-all duration-300 shadow-sm">
+                                    <AuditLogViewer />
+                                </div>
+
+                                {/* [NEW] 管理员账号与登录会话 */}
+                                <div className="group bg-white dark:bg-base-100 rounded-xl p-5 border border-gray-100 dark:border-base-200 hover:border-violet-200 transition-all duration-300 shadow-sm">
+                                    <AdminUserSettings
+                                        sessionTtlHours={formData.proxy?.admin_session_ttl_hours ?? 12}
+                                        onSessionTtlChange={(hours) => setFormData({
+                                            ...formData,
+                                            proxy: { ...formData.proxy, admin_session_ttl_hours: hours }
+                                        })}
+                                    />
+                                </div>
                             </div>
                         </>
                     )}
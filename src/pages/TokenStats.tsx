@@ -2,7 +2,7 @@ import React, { useEffect, useState, useRef, useCallback } from 'react';
 import { request as invoke } from '../utils/request';
 import { useTranslation } from 'react-i18next';
 import { AreaChart, Area, BarChart, Bar, XAxis, YAxis, CartesianGrid, Tooltip, ResponsiveContainer, PieChart, Pie, Cell, Legend } from 'recharts';
-import { Clock, Calendar, CalendarDays, Users, Zap, TrendingUp, RefreshCw, Cpu } from 'lucide-react';
+import { Clock, Calendar, CalendarDays, Users, Zap, TrendingUp, RefreshCw, Cpu, DollarSign, KeyRound } from 'lucide-react';
 
 interface TokenStatsAggregated {
     period: string;
@@ -10,6 +10,7 @@ interface TokenStatsAggregated {
     total_output_tokens: number;
     total_tokens: number;
     request_count: number;
+    total_cost?: number;
 }
 
 interface AccountTokenStats {
@@ -18,6 +19,7 @@ interface AccountTokenStats {
     total_output_tokens: number;
     total_tokens: number;
     request_count: number;
+    total_cost?: number;
 }
 
 interface ModelTokenStats {
@@ -26,6 +28,7 @@ interface ModelTokenStats {
     total_output_tokens: number;
     total_tokens: number;
     request_count: number;
+    total_cost?: number;
 }
 
 interface ModelTrendPoint {
@@ -44,6 +47,16 @@ interface TokenStatsSummary {
     total_tokens: number;
     total_requests: number;
     unique_accounts: number;
+    total_cost?: number;
+}
+
+interface UserTokenUsageStats {
+    username: string;
+    total_input_tokens: number;
+    total_output_tokens: number;
+    total_tokens: number;
+    request_count: number;
+    total_cost: number;
 }
 
 type TimeRange = 'hourly' | 'daily' | 'weekly';
@@ -63,6 +76,13 @@ const formatNumber = (num: number): string => {
     return num.toString();
 };
 
+const formatCost = (usd: number | undefined): string => {
+    const v = usd || 0;
+    if (v === 0) return '$0';
+    if (v < 0.01) return `$${v.toFixed(4)}`;
+    return `$${v.toFixed(2)}`;
+};
+
 const shortenModelName = (model: string): string => {
     return model
         .replace('gemini-', 'g-')
@@ -83,6 +103,7 @@ const TokenStats: React.FC = () => {
     const [allModels, setAllModels] = useState<string[]>([]);
     const [allAccounts, setAllAccounts] = useState<string[]>([]);
     const [summary, setSummary] = useState<TokenStatsSummary | null>(null);
+    const [userTokenData, setUserTokenData] = useState<UserTokenUsageStats[]>([]);
     const [loading, setLoading] = useState(true);
 
     const fetchData = async () => {
@@ -149,15 +170,17 @@ const TokenStats: React.FC = () => {
             });
             setAccountTrendData(transformedAccountTrend);
 
-            const [accounts, models_stats, summaryData] = await Promise.all([
+            const [accounts, models_stats, summaryData, userTokens] = await Promise.all([
                 invoke<AccountTokenStats[]>('get_token_stats_by_account', { hours }),
                 invoke<ModelTokenStats[]>('get_token_stats_by_model', { hours }),
-                invoke<TokenStatsSummary>('get_token_stats_summary', { hours })
+                invoke<TokenStatsSummary>('get_token_stats_summary', { hours }),
+                invoke<UserTokenUsageStats[]>('get_token_stats_by_user_token', { hours }).catch(() => [])
             ]);
 
             setAccountData(accounts);
             setModelData(models_stats);
             setSummary(summaryData);
+            setUserTokenData(userTokens);
         } catch (error) {
             console.error('Failed to fetch token stats:', error);
         } finally {
@@ -360,7 +383,7 @@ const TokenStats: React.FC = () => {
                 </div>
 
                 {summary && (
-                    <div className="grid grid-cols-2 md:grid-cols-5 gap-4">
+                    <div className="grid grid-cols-2 md:grid-cols-6 gap-4">
                         <div className="bg-gradient-to-br from-white to-gray-50 dark:from-gray-800 dark:to-gray-800/50 rounded-xl p-4 shadow-sm border border-gray-200 dark:border-gray-700 hover:shadow-md transition-shadow">
                             <div className="flex items-center gap-2 text-gray-500 dark:text-gray-400 text-sm mb-2">
                                 <div className="p-1.5 rounded-lg bg-gray-100 dark:bg-gray-700">
@@ -416,6 +439,17 @@ const TokenStats: React.FC = () => {
                                 {modelData.length}
                             </div>
                         </div>
+                        <div className="bg-gradient-to-br from-emerald-50/50 to-white dark:from-emerald-900/10 dark:to-gray-800 rounded-xl p-4 shadow-sm border border-emerald-100 dark:border-emerald-900/30 hover:shadow-md transition-shadow">
+                            <div className="flex items-center gap-2 text-emerald-600/80 dark:text-emerald-400/80 text-sm mb-2">
+                                <div className="p-1.5 rounded-lg bg-emerald-100/50 dark:bg-emerald-900/30">
+                                    <DollarSign className="w-4 h-4 text-emerald-600 dark:text-emerald-400" />
+                                </div>
+                                {t('token_stats.total_cost', '估算费用')}
+                            </div>
+                            <div className="text-2xl font-bold text-emerald-600 dark:text-emerald-400">
+                                {formatCost(summary.total_cost)}
+                            </div>
+                        </div>
                     </div>
                 )}
 
@@ -650,6 +684,9 @@ const TokenStats: React.FC = () => {
                                             <th className="text-right py-3 px-4 font-medium text-gray-500 dark:text-gray-400">
                                                 {t('token_stats.total', '合计')}
                                             </th>
+                                            <th className="text-right py-3 px-4 font-medium text-gray-500 dark:text-gray-400">
+                                                {t('token_stats.cost', '费用')}
+                                            </th>
                                             <th className="text-right py-3 px-4 font-medium text-gray-500 dark:text-gray-400">
                                                 {t('token_stats.percentage', '占比')}
                                             </th>
@@ -686,6 +723,9 @@ const TokenStats: React.FC = () => {
                                                     <td className="py-3 px-4 text-right font-semibold text-gray-800 dark:text-white">
                                                         {formatNumber(model.total_tokens)}
                                                     </td>
+                                                    <td className="py-3 px-4 text-right text-emerald-600">
+                                                        {formatCost(model.total_cost)}
+                                                    </td>
                                                     <td className="py-3 px-4 text-right">
                                                         <div className="flex items-center justify-end gap-2">
                                                             <div className="w-16 bg-gray-200 dark:bg-gray-700 rounded-full h-2">
@@ -739,6 +779,9 @@ const TokenStats: React.FC = () => {
                                             <th className="text-right py-3 px-4 font-medium text-gray-500 dark:text-gray-400">
                                                 {t('token_stats.total', '合计')}
                                             </th>
+                                            <th className="text-right py-3 px-4 font-medium text-gray-500 dark:text-gray-400">
+                                                {t('token_stats.cost', '费用')}
+                                            </th>
                                         </tr>
                                     </thead>
                                     <tbody>
@@ -762,6 +805,68 @@ const TokenStats: React.FC = () => {
                                                 <td className="py-3 px-4 text-right font-semibold text-gray-800 dark:text-white">
                                                     {formatNumber(account.total_tokens)}
                                                 </td>
+                                                <td className="py-3 px-4 text-right text-emerald-600">
+                                                    {formatCost(account.total_cost)}
+                                                </td>
+                                            </tr>
+                                        ))}
+                                    </tbody>
+                                </table>
+                            </div>
+                        </div>
+                    )
+                }
+
+                {/* [NEW] 按 User Token 统计费用 */}
+                {
+                    userTokenData.length > 0 && (
+                        <div className="bg-white dark:bg-gray-800 rounded-xl p-6 shadow-sm border border-gray-200 dark:border-gray-700">
+                            <h2 className="text-lg font-semibold text-gray-800 dark:text-white mb-4 flex items-center gap-2">
+                                <KeyRound className="w-5 h-5 text-emerald-500" />
+                                {t('token_stats.user_token_details', '按 User Token 统计')}
+                            </h2>
+                            <div className="overflow-x-auto">
+                                <table className="w-full text-sm">
+                                    <thead>
+                                        <tr className="border-b border-gray-200 dark:border-gray-700">
+                                            <th className="text-left py-3 px-4 font-medium text-gray-500 dark:text-gray-400">
+                                                {t('token_stats.user_token', '用户')}
+                                            </th>
+                                            <th className="text-right py-3 px-4 font-medium text-gray-500 dark:text-gray-400">
+                                                {t('token_stats.requests', '请求数')}
+                                            </th>
+                                            <th className="text-right py-3 px-4 font-medium text-gray-500 dark:text-gray-400">
+                                                {t('token_stats.input', '输入')}
+                                            </th>
+                                            <th className="text-right py-3 px-4 font-medium text-gray-500 dark:text-gray-400">
+                                                {t('token_stats.output', '输出')}
+                                            </th>
+                                            <th className="text-right py-3 px-4 font-medium text-gray-500 dark:text-gray-400">
+                                                {t('token_stats.cost', '费用')}
+                                            </th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {userTokenData.map((row) => (
+                                            <tr
+                                                key={row.username}
+                                                className="border-b border-gray-100 dark:border-gray-700/50 hover:bg-gray-50 dark:hover:bg-gray-700/30"
+                                            >
+                                                <td className="py-3 px-4 text-gray-800 dark:text-white">
+                                                    {row.username}
+                                                </td>
+                                                <td className="py-3 px-4 text-right text-gray-600 dark:text-gray-300">
+                                                    {row.request_count.toLocaleString()}
+                                                </td>
+                                                <td className="py-3 px-4 text-right text-blue-600">
+                                                    {formatNumber(row.total_input_tokens)}
+                                                </td>
+                                                <td className="py-3 px-4 text-right text-purple-600">
+                                                    {formatNumber(row.total_output_tokens)}
+                                                </td>
+                                                <td className="py-3 px-4 text-right font-semibold text-emerald-600">
+                                                    {formatCost(row.total_cost)}
+                                                </td>
                                             </tr>
                                         ))}
                                     </tbody>
@@ -22,6 +22,14 @@ interface UserToken {
     last_used_at?: number;
     total_requests: number;
     total_tokens_used: number;
+    rpm_limit?: number;
+    daily_token_limit?: number;
+    monthly_token_limit?: number;
+    allowed_models?: string[];
+    allowed_groups?: string[];
+    daily_budget_usd?: number;
+    monthly_budget_usd?: number;
+    budget_action?: 'warn' | 'block';
 }
 
 interface UserTokenStats {
@@ -49,6 +57,9 @@ const UserToken: React.FC = () => {
     const [editMaxIps, setEditMaxIps] = useState(0);
     const [editCurfewStart, setEditCurfewStart] = useState('');
     const [editCurfewEnd, setEditCurfewEnd] = useState('');
+    const [editDailyBudget, setEditDailyBudget] = useState(0);
+    const [editMonthlyBudget, setEditMonthlyBudget] = useState(0);
+    const [editBudgetAction, setEditBudgetAction] = useState<'warn' | 'block'>('warn');
     const [updating, setUpdating] = useState(false);
 
     // Create Form State
@@ -59,6 +70,9 @@ const UserToken: React.FC = () => {
     const [newCurfewStart, setNewCurfewStart] = useState('');
     const [newCurfewEnd, setNewCurfewEnd] = useState('');
     const [newCustomExpires, setNewCustomExpires] = useState(''); // datetime-local value
+    const [newDailyBudget, setNewDailyBudget] = useState(0);
+    const [newMonthlyBudget, setNewMonthlyBudget] = useState(0);
+    const [newBudgetAction, setNewBudgetAction] = useState<'warn' | 'block'>('warn');
 
     const loadData = async () => {
         setLoading(true);
@@ -108,7 +122,10 @@ const UserToken: React.FC = () => {
                     max_ips: newMaxIps,
                     curfew_start: newCurfewStart || null,
                     curfew_end: newCurfewEnd || null,
-                    custom_expires_at: customExpiresAt || null
+                    custom_expires_at: customExpiresAt || null,
+                    daily_budget_usd: newDailyBudget,
+                    monthly_budget_usd: newMonthlyBudget,
+                    budget_action: newBudgetAction
                 }
             });
             showToast(t('common.create_success') || 'Created successfully', 'success');
@@ -120,6 +137,9 @@ const UserToken: React.FC = () => {
             setNewCurfewStart('');
             setNewCurfewEnd('');
             setNewCustomExpires('');
+            setNewDailyBudget(0);
+            setNewMonthlyBudget(0);
+            setNewBudgetAction('warn');
             loadData();
         } catch (e) {
             console.error('Failed to create token', e);
@@ -147,6 +167,9 @@ const UserToken: React.FC = () => {
         setEditMaxIps(token.max_ips ?? 0);  // 使用 ?? 确保 null/undefined 变为 0
         setEditCurfewStart(token.curfew_start ?? '');
         setEditCurfewEnd(token.curfew_end ?? '');
+        setEditDailyBudget(token.daily_budget_usd ?? 0);
+        setEditMonthlyBudget(token.monthly_budget_usd ?? 0);
+        setEditBudgetAction(token.budget_action ?? 'warn');
         setShowEditModal(true);
     };
 
@@ -167,7 +190,10 @@ const UserToken: React.FC = () => {
                     max_ips: editMaxIps,
                     // 使用双层包装: undefined = 不更新, null = 清空, string = 设置值
                     curfew_start: editCurfewStart === '' ? null : editCurfewStart,
-                    curfew_end: editCurfewEnd === '' ? null : editCurfewEnd
+                    curfew_end: editCurfewEnd === '' ? null : editCurfewEnd,
+                    daily_budget_usd: editDailyBudget,
+                    monthly_budget_usd: editMonthlyBudget,
+                    budget_action: editBudgetAction
                 }
             });
             showToast(t('common.update_success') || 'Updated successfully', 'success');
@@ -398,6 +424,15 @@ const UserToken: React.FC = () => {
                                                 <span>{token.curfew_start} - {token.curfew_end}</span>
                                             </div>
                                         )}
+                                        {((token.daily_budget_usd ?? 0) > 0 || (token.monthly_budget_usd ?? 0) > 0) && (
+                                            <div
+                                                className={`text-[10px] mt-1.5 w-fit px-1.5 py-0.5 rounded ${token.budget_action === 'block' ? 'bg-red-50 dark:bg-red-900/20 text-red-500' : 'bg-gray-50 dark:bg-base-200 text-gray-400'}`}
+                                                title={t('user_token.budget', { defaultValue: 'Budget (USD)' })}
+                                            >
+                                                {(token.daily_budget_usd ?? 0) > 0 && <span>${token.daily_budget_usd}/{t('user_token.per_day', { defaultValue: 'day' })} </span>}
+                                                {(token.monthly_budget_usd ?? 0) > 0 && <span>${token.monthly_budget_usd}/{t('user_token.per_month', { defaultValue: 'month' })}</span>}
+                                            </div>
+                                        )}
                                     </td>
                                     <td className="text-[10px] text-gray-400 italic">
                                         {formatTime(token.created_at)}
@@ -542,6 +577,45 @@ const UserToken: React.FC = () => {
                             </div>
                         )}
 
+                        <div className="form-control w-full mb-3">
+                            <label className="label">
+                                <span className="label-text">{t('user_token.budget', { defaultValue: 'Budget (USD)' })}</span>
+                            </label>
+                            <div className="grid grid-cols-3 gap-2">
+                                <input
+                                    type="number"
+                                    className="input input-bordered w-full"
+                                    value={newDailyBudget}
+                                    onChange={e => setNewDailyBudget(parseFloat(e.target.value) || 0)}
+                                    min="0"
+                                    step="0.01"
+                                    placeholder={t('user_token.daily_budget', { defaultValue: 'Daily' })}
+                                    title={t('user_token.daily_budget', { defaultValue: 'Daily' })}
+                                />
+                                <input
+                                    type="number"
+                                    className="input input-bordered w-full"
+                                    value={newMonthlyBudget}
+                                    onChange={e => setNewMonthlyBudget(parseFloat(e.target.value) || 0)}
+                                    min="0"
+                                    step="0.01"
+                                    placeholder={t('user_token.monthly_budget', { defaultValue: 'Monthly' })}
+                                    title={t('user_token.monthly_budget', { defaultValue: 'Monthly' })}
+                                />
+                                <select
+                                    className="select select-bordered w-full"
+                                    value={newBudgetAction}
+                                    onChange={e => setNewBudgetAction(e.target.value as 'warn' | 'block')}
+                                >
+                                    <option value="warn">{t('user_token.budget_action_warn', { defaultValue: 'Warn only' })}</option>
+                                    <option value="block">{t('user_token.budget_action_block', { defaultValue: 'Block requests' })}</option>
+                                </select>
+                            </div>
+                            <label className="label">
+                                <span className="label-text-alt text-gray-500">{t('user_token.hint_budget', { defaultValue: 'Daily / monthly spend limit in USD (UTC). 0 = Unlimited.' })}</span>
+                            </label>
+                        </div>
+
                         <div className="form-control w-full mb-3">
                             <label className="label">
                                 <span className="label-text">{t('user_token.curfew', { defaultValue: 'Curfew (Service Unavailable Time)' })}</span>
@@ -632,6 +706,45 @@ const UserToken: React.FC = () => {
                             </label>
                         </div>
 
+                        <div className="form-control w-full mb-3">
+                            <label className="label">
+                                <span className="label-text">{t('user_token.budget', { defaultValue: 'Budget (USD)' })}</span>
+                            </label>
+                            <div className="grid grid-cols-3 gap-2">
+                                <input
+                                    type="number"
+                                    className="input input-bordered w-full"
+                                    value={editDailyBudget}
+                                    onChange={e => setEditDailyBudget(parseFloat(e.target.value) || 0)}
+                                    min="0"
+                                    step="0.01"
+                                    placeholder={t('user_token.daily_budget', { defaultValue: 'Daily' })}
+                                    title={t('user_token.daily_budget', { defaultValue: 'Daily' })}
+                                />
+                                <input
+                                    type="number"
+                                    className="input input-bordered w-full"
+                                    value={editMonthlyBudget}
+                                    onChange={e => setEditMonthlyBudget(parseFloat(e.target.value) || 0)}
+                                    min="0"
+                                    step="0.01"
+                                    placeholder={t('user_token.monthly_budget', { defaultValue: 'Monthly' })}
+                                    title={t('user_token.monthly_budget', { defaultValue: 'Monthly' })}
+                                />
+                                <select
+                                    className="select select-bordered w-full"
+                                    value={editBudgetAction}
+                                    onChange={e => setEditBudgetAction(e.target.value as 'warn' | 'block')}
+                                >
+                                    <option value="warn">{t('user_token.budget_action_warn', { defaultValue: 'Warn only' })}</option>
+                                    <option value="block">{t('user_token.budget_action_block', { defaultValue: 'Block requests' })}</option>
+                                </select>
+                            </div>
+                            <label className="label">
+                                <span className="label-text-alt text-gray-500">{t('user_token.hint_budget', { defaultValue: 'Daily / monthly spend limit in USD (UTC). 0 = Unlimited.' })}</span>
+                            </label>
+                        </div>
+
                         <div className="form-control w-full mb-3">
                             <label className="label">
                                 <span className="label-text">{t('user_token.curfew', { defaultValue: 'Curfew (Service Unavailable Time)' })}</span>
@@ -14,6 +14,7 @@ export interface Account {
     proxy_disabled_at?: number;
     protected_models?: string[];
     custom_label?: string;  // 用户自定义标签
+    groups?: string[];      // 账号分组 (User Token 按分组隔离号池)
     created_at: number;
     last_used: number;
 }
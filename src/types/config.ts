@@ -10,8 +10,11 @@ export interface ProxyConfig {
     port: number;
     api_key: string;
     admin_password?: string;
+    admin_session_ttl_hours?: number;
     auto_start: boolean;
     custom_mapping?: Record<string, string>;
+    model_routing_rules?: ModelRoutingRule[]; // [NEW] 有序路由规则 (优先于 custom_mapping)
+    model_fallback_chains?: Record<string, string[]>; // [NEW] 模型降级链 (目标模型全池不可用时生效)
     request_timeout: number;
     enable_logging: boolean;
     debug_logging?: DebugLoggingConfig;
@@ -25,6 +28,205 @@ export interface ProxyConfig {
     global_system_prompt?: GlobalSystemPromptConfig;
     image_thinking_mode?: 'enabled' | 'disabled'; // [NEW] 图像思维模式开关
     proxy_pool?: ProxyPoolConfig;
+    response_cache?: ResponseCacheConfig; // [NEW] 确定性请求响应缓存
+    cost?: CostConfig; // [NEW] 模型价格表与预算
+    providers?: UpstreamProviderConfig[]; // [NEW] OpenAI / Anthropic 兼容的自定义上游
+    structured_output?: StructuredOutputConfig; // [NEW] JSON Schema 结构化输出校验
+    batch?: BatchConfig; // [NEW] 批处理队列 (Message Batches / OpenAI Batch)
+    files?: FilesConfig; // [NEW] Files API 本地存储
+}
+
+// ============================================================================
+// Files API 本地存储
+// ============================================================================
+
+export interface FilesConfig {
+    max_file_size_mb: number;
+    max_total_size_mb: number;
+    ttl_days: number; // 0 = 不过期
+}
+
+export interface FileStoreStats {
+    files: number;
+    total_bytes: number;
+    stored_bytes: number; // 去重后实际占用
+}
+
+// ============================================================================
+// 批处理队列 (Anthropic Message Batches / OpenAI Batch)
+// ============================================================================
+
+export interface BatchConfig {
+    enabled: boolean;
+    concurrency: number;
+    max_interactive_in_flight: number; // 交互请求超过该值时暂停，0 = 有交互请求就让路
+    max_attempts: number;
+    max_requests_per_batch: number;
+    retention_days: number;
+}
+
+export type BatchStatus = 'in_progress' | 'canceling' | 'ended';
+
+export interface BatchCounts {
+    queued: number;
+    running: number;
+    succeeded: number;
+    errored: number;
+    canceled: number;
+    expired: number;
+}
+
+export interface BatchRecord {
+    id: string;
+    protocol: 'anthropic' | 'openai';
+    endpoint: string;
+    status: BatchStatus;
+    owner_token_id?: string | null;
+    client_ip?: string | null;
+    created_at: number;
+    expires_at: number;
+    started_at?: number | null;
+    ended_at?: number | null;
+    cancel_requested_at?: number | null;
+    counts: BatchCounts;
+}
+
+export interface BatchOverview {
+    stats: { active_batches: number; queued: number; running: number };
+    batches: BatchRecord[];
+}
+
+// ============================================================================
+// 结构化输出 (JSON Schema -> Gemini responseSchema)
+// ============================================================================
+
+/** off: 不校验; request: 请求声明严格时校验; always: 所有带 Schema 的请求都校验 */
+export type StrictSchemaMode = 'off' | 'request' | 'always';
+
+/** error: 直接返回错误; retry: 重试一次后再返回错误 */
+export type SchemaMismatchAction = 'error' | 'retry';
+
+export interface StructuredOutputConfig {
+    strict: StrictSchemaMode;
+    on_mismatch: SchemaMismatchAction;
+}
+
+// ============================================================================
+// 自定义上游 (OpenAI / Anthropic 兼容接口，请求按原协议透传)
+// ============================================================================
+
+export type ProviderProtocol = 'openai' | 'anthropic';
+
+/** exclusive: 命中的模型总是转发; fallback: 仅账号池无可用账号时转发 */
+export type ProviderDispatchMode = 'exclusive' | 'fallback';
+
+export interface UpstreamProviderConfig {
+    id: string;
+    name?: string | null;
+    enabled: boolean;
+    protocol: ProviderProtocol;
+    base_url: string;
+    api_keys: string[];
+    models: string[]; // 支持 * 通配符
+    model_mapping: Record<string, string>;
+    dispatch_mode: ProviderDispatchMode;
+    rate_limit_cooldown_secs: number;
+}
+
+export interface ProviderKeyStatus {
+    key: string; // 已脱敏
+    requests: number;
+    rate_limited: number;
+    cooldown_secs: number; // 0 = 可用
+}
+
+export interface ProviderStatus {
+    id: string;
+    name: string;
+    protocol: ProviderProtocol;
+    enabled: boolean;
+    dispatch_mode: ProviderDispatchMode;
+    models: string[];
+    keys: ProviderKeyStatus[];
+    requests: number;
+    errors: number;
+    rate_limited: number;
+    last_status?: number | null;
+    last_error?: string | null;
+    last_used_at?: number | null;
+}
+
+// ============================================================================
+// 费用核算 (价格单位: 美元 / 百万 Token)
+// ============================================================================
+
+export interface ModelPrice {
+    model: string; // 支持 * 通配符
+    input: number;
+    output: number;
+    cached_input?: number | null; // 未设置时按 input 计价
+    thinking?: number | null; // 未设置时按 output 计价
+}
+
+export type BudgetAction = 'warn' | 'block';
+
+export interface BudgetConfig {
+    daily_usd: number; // 0 = 不限制
+    monthly_usd: number; // 0 = 不限制
+    action: BudgetAction;
+}
+
+export interface CostConfig {
+    prices: ModelPrice[];
+    budget: BudgetConfig;
+}
+
+// ============================================================================
+// 响应缓存 (仅缓存 temperature = 0 的确定性请求)
+// ============================================================================
+
+export interface ResponseCacheConfig {
+    enabled: boolean;
+    ttl_secs: number;
+    max_entries: number;
+    max_size_mb: number;
+    require_zero_temperature: boolean;
+}
+
+export interface ResponseCacheStats {
+    entries: number;
+    total_bytes: number;
+    total_hits: number;
+    oldest_created_at?: number | null;
+}
+
+// ============================================================================
+// 模型路由规则 (按顺序匹配，首个命中的规则生效)
+// ============================================================================
+
+export interface RouteConditions {
+    model?: string;
+    protocols?: string[];
+    user_tokens?: string[];
+    usernames?: string[];
+    user_agent?: string;
+    has_tools?: boolean;
+    has_images?: boolean;
+    has_thinking?: boolean;
+}
+
+export type RouteTarget =
+    | { type: 'model'; model: string }
+    | { type: 'fallback'; models: string[] }
+    | { type: 'zai'; model?: string }
+    | { type: 'provider'; provider: string; model?: string };
+
+export interface ModelRoutingRule {
+    id: string;
+    name?: string;
+    enabled?: boolean;
+    match: RouteConditions;
+    target: RouteTarget;
 }
 
 // ============================================================================
@@ -144,6 +346,123 @@ export interface AppConfig {
     circuit_breaker: CircuitBreakerConfig; // [NEW] 熔断器配置
     proxy: ProxyConfig;
     cloudflared: CloudflaredConfig; // [NEW] Cloudflared 配置
+    webhooks?: WebhookConfig; // [NEW] 运维事件 Webhook 通知
+}
+
+// ============================================================================
+// Webhook 通知
+// ============================================================================
+
+export type WebhookEvent =
+    | 'account_forbidden'
+    | 'quota_protection_triggered'
+    | 'model_rate_limited'
+    | 'proxy_unhealthy'
+    | 'user_token_expired'
+    | 'tunnel_down'
+    | 'budget_exceeded'
+    | 'test';
+
+export type WebhookPayloadFormat = 'generic' | 'slack' | 'discord' | 'custom';
+
+export interface WebhookEndpoint {
+    id: string;
+    name: string;
+    url: string;
+    enabled: boolean;
+    events: WebhookEvent[]; // 为空表示订阅全部
+    format: WebhookPayloadFormat;
+    template?: string | null; // format = custom 时的 JSON 模板
+    headers?: Record<string, string>;
+    secret?: string | null; // HMAC-SHA256 签名密钥
+}
+
+export interface WebhookConfig {
+    enabled: boolean;
+    endpoints: WebhookEndpoint[];
+    max_retries: number;
+    retry_base_secs: number;
+    cooldown_secs: number;
+}
+
+export interface WebhookDelivery {
+    id: string;
+    endpoint_id: string;
+    endpoint_name: string;
+    event: string;
+    subject: string;
+    url: string;
+    success: boolean;
+    attempts: number;
+    status_code?: number | null;
+    error?: string | null;
+    payload: string;
+    created_at: number;
+    duration_ms: number;
+}
+
+// ============================================================================
+// 审计日志
+// ============================================================================
+
+export interface AuditEntry {
+    id: number;
+    timestamp: number;
+    /** admin / unauthenticated / desktop */
+    actor: string;
+    /** admin_api / tauri */
+    source: string;
+    ip?: string | null;
+    action: string;
+    target?: string | null;
+    details: any;
+    success: boolean;
+    status_code?: number | null;
+    error?: string | null;
+    prev_hash: string;
+    hash: string;
+}
+
+export interface AuditLogPage {
+    entries: AuditEntry[];
+    total: number;
+}
+
+export interface AuditChainStatus {
+    total: number;
+    valid: boolean;
+    first_broken_id?: number | null;
+}
+
+// [NEW] 管理员账号与登录会话
+export type AdminRole = 'viewer' | 'operator' | 'owner';
+
+export interface AdminUser {
+    id: string;
+    username: string;
+    role: AdminRole;
+    enabled: boolean;
+    created_at: number;
+    updated_at: number;
+    last_login_at?: number | null;
+}
+
+export interface AdminSession {
+    id: string;
+    user_id: string;
+    username: string;
+    role: AdminRole;
+    created_at: number;
+    expires_at: number;
+    last_seen_at: number;
+    ip?: string | null;
+    user_agent?: string | null;
+}
+
+export interface AdminLoginResult {
+    token: string;
+    expires_at: number;
+    user: AdminUser;
 }
 
 // ============================================================================
@@ -19,7 +19,13 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   'warm_up_all_accounts': { url: '/api/accounts/warmup', method: 'POST' },
   'warm_up_account': { url: '/api/accounts/:accountId/warmup', method: 'POST' },
   'update_account_label': { url: '/api/accounts/:accountId/label', method: 'POST' },
+  'update_account_groups': { url: '/api/accounts/:accountId/groups', method: 'POST' },
   'export_accounts': { url: '/api/accounts/export', method: 'POST' },
+  'seal_account_export': { url: '/api/accounts/export/seal', method: 'POST' },
+  'open_account_export': { url: '/api/accounts/import/open', method: 'POST' },
+  'get_account_encryption_status': { url: '/api/accounts/encryption', method: 'GET' },
+  'unlock_account_encryption': { url: '/api/accounts/encryption/unlock', method: 'POST' },
+  'rotate_account_encryption_key': { url: '/api/accounts/encryption/rotate', method: 'POST' },
   'bind_device_profile': { url: '/api/accounts/:accountId/bind-device', method: 'POST' },
   'get_device_profiles': { url: '/api/accounts/:accountId/device-profiles', method: 'GET' },
   'list_device_versions': { url: '/api/accounts/:accountId/device-versions', method: 'GET' },
@@ -51,6 +57,7 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   // Logs & Monitoring
   'get_proxy_logs_filtered': { url: '/api/logs', method: 'GET' },
   'get_proxy_logs_count_filtered': { url: '/api/logs/count', method: 'GET' },
+  'get_proxy_log_stats_by_group': { url: '/api/logs/groups', method: 'GET' },
   'clear_proxy_logs': { url: '/api/logs/clear', method: 'POST' },
   'get_proxy_log_detail': { url: '/api/logs/:logId', method: 'GET' },
 
@@ -81,6 +88,9 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   'get_token_stats_by_account': { url: '/api/stats/token/by-account', method: 'GET' },
   'get_token_stats_summary': { url: '/api/stats/token/summary', method: 'GET' },
   'get_token_stats_by_model': { url: '/api/stats/token/by-model', method: 'GET' },
+  'get_token_stats_by_group': { url: '/api/stats/token/by-group', method: 'GET' },
+  'get_token_stats_by_user_token': { url: '/api/stats/token/by-user-token', method: 'GET' },
+  'get_token_stats_cache_hits': { url: '/api/stats/token/cache-hits', method: 'GET' },
   'get_token_stats_model_trend_hourly': { url: '/api/stats/token/model-trend/hourly', method: 'GET' },
   'get_token_stats_model_trend_daily': { url: '/api/stats/token/model-trend/daily', method: 'GET' },
   'get_token_stats_account_trend_hourly': { url: '/api/stats/token/account-trend/hourly', method: 'GET' },
@@ -89,6 +99,8 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
 
   // System
   'get_data_dir_path': { url: '/api/system/data-dir', method: 'GET' },
+  'create_backup': { url: '/api/system/backup', method: 'POST' },
+  'restore_backup': { url: '/api/system/restore', method: 'POST' },
   'get_update_settings': { url: '/api/system/updates/settings', method: 'GET' },
   'save_update_settings': { url: '/api/system/updates/save', method: 'POST' },
   'is_auto_launch_enabled': { url: '/api/system/autostart/status', method: 'GET' },
@@ -152,6 +164,8 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   'renew_user_token': { url: '/api/user-tokens/:id/renew', method: 'POST' },
   'delete_user_token': { url: '/api/user-tokens/:id', method: 'DELETE' },
   'update_user_token': { url: '/api/user-tokens/:id', method: 'PATCH' },
+  'get_user_token_quota_usage': { url: '/api/user-tokens/:tokenId/quota', method: 'GET' },
+  'reset_user_token_quota_usage': { url: '/api/user-tokens/:tokenId/quota', method: 'DELETE' },
 
   // Proxy Pool (Web Mode Fix)
   'get_proxy_pool_config': { url: '/api/proxy/pool/config', method: 'GET' },
@@ -159,6 +173,34 @@ const COMMAND_MAPPING: Record<string, { url: string; method: 'GET' | 'POST' | 'D
   'bind_account_proxy': { url: '/api/proxy/pool/bind', method: 'POST' },
   'unbind_account_proxy': { url: '/api/proxy/pool/unbind', method: 'POST' },
   'get_account_proxy_binding': { url: '/api/proxy/pool/binding/:accountId', method: 'GET' },
+
+  // Response Cache
+  'get_response_cache_stats': { url: '/api/proxy/response-cache', method: 'GET' },
+  'get_provider_statuses': { url: '/api/proxy/providers/status', method: 'GET' },
+  'clear_response_cache': { url: '/api/proxy/response-cache', method: 'DELETE' },
+
+  // Batches
+  'get_batch_overview': { url: '/api/proxy/batches', method: 'GET' },
+  'cancel_batch': { url: '/api/proxy/batches/:batchId/cancel', method: 'POST' },
+  'get_file_store_stats': { url: '/api/proxy/files/stats', method: 'GET' },
+
+  // Webhooks
+  'get_webhook_deliveries': { url: '/api/webhooks/deliveries', method: 'GET' },
+  'clear_webhook_deliveries': { url: '/api/webhooks/deliveries', method: 'DELETE' },
+  'test_webhook': { url: '/api/webhooks/test', method: 'POST' },
+
+  // Audit Log
+  'get_audit_logs': { url: '/api/audit', method: 'GET' },
+  'export_audit_logs': { url: '/api/audit/export', method: 'GET' },
+  'verify_audit_log': { url: '/api/audit/verify', method: 'GET' },
+
+  // Admin Users & Sessions
+  'list_admin_users': { url: '/api/admin/users', method: 'GET' },
+  'create_admin_user': { url: '/api/admin/users', method: 'POST' },
+  'update_admin_user': { url: '/api/admin/users/:id', method: 'PATCH' },
+  'delete_admin_user': { url: '/api/admin/users/:id', method: 'DELETE' },
+  'list_admin_sessions': { url: '/api/admin/sessions', method: 'GET' },
+  'revoke_admin_session': { url: '/api/admin/sessions/:id', method: 'DELETE' },
 };
 
 export async function request<T>(cmd: string, args?: any): Promise<T> {
@@ -265,3 +307,18 @@ export async function request<T>(cmd: string, args?: any): Promise<T> {
     throw error;
   }
 }
+
+/**
+ * [NEW] Web 模式退出登录：若当前凭据是登录会话令牌，先在服务端注销，再清理本地存储
+ */
+export async function adminLogout(): Promise<void> {
+  const token = sessionStorage.getItem('abv_admin_api_key');
+  if (token && token.startsWith('abv_sess_')) {
+    await fetch('/api/auth/logout', {
+      method: 'POST',
+      headers: { 'Authorization': `Bearer ${token}` },
+    }).catch(() => undefined);
+  }
+  sessionStorage.removeItem('abv_admin_api_key');
+  localStorage.removeItem('abv_admin_api_key');
+}